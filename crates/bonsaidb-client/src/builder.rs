@@ -5,17 +5,21 @@ use std::time::Duration;
 
 use bonsaidb_core::api;
 use bonsaidb_core::api::ApiName;
-use bonsaidb_core::networking::CURRENT_PROTOCOL_VERSION;
+use bonsaidb_core::connection::Authentication;
+#[cfg(feature = "compression")]
+use bonsaidb_core::networking::Compression;
+use bonsaidb_core::networking::{TransportCompression, SUPPORTED_PROTOCOL_VERSIONS};
 #[cfg(not(target_arch = "wasm32"))]
 use fabruic::Certificate;
+use futures::Future;
 #[cfg(not(target_arch = "wasm32"))]
 use tokio::runtime::Handle;
 use url::Url;
 
-use crate::client::{AnyApiCallback, ApiCallback};
+use crate::client::{AnyApiCallback, ApiCallback, CredentialProvider, Replicas};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::BlockingClient;
-use crate::{AsyncClient, Error};
+use crate::{AsyncClient, Error, ReplicaRouting, RequestInterceptor};
 
 pub struct Async;
 #[cfg(not(target_arch = "wasm32"))]
@@ -25,14 +29,28 @@ pub struct Blocking;
 #[must_use]
 pub struct Builder<AsyncMode> {
     url: Url,
-    protocol_version: &'static str,
+    protocol_versions: &'static [&'static str],
     custom_apis: HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
     connect_timeout: Option<Duration>,
     request_timeout: Option<Duration>,
+    compression: Option<TransportCompression>,
+    interceptor: Option<Arc<dyn RequestInterceptor>>,
+    credential_provider: Option<CredentialProvider>,
+    replicas: Vec<Url>,
+    replica_routing: ReplicaRouting,
+    default_database: Option<String>,
     #[cfg(not(target_arch = "wasm32"))]
     certificate: Option<fabruic::Certificate>,
     #[cfg(not(target_arch = "wasm32"))]
+    certificate_fingerprint: Option<[u8; 32]>,
+    #[cfg(not(target_arch = "wasm32"))]
+    certificate_authorities: Vec<fabruic::Certificate>,
+    #[cfg(not(target_arch = "wasm32"))]
+    fallbacks: Vec<Url>,
+    #[cfg(not(target_arch = "wasm32"))]
     tokio: Option<Handle>,
+    #[cfg(not(target_arch = "wasm32"))]
+    keepalive: Option<Duration>,
     mode: PhantomData<AsyncMode>,
 }
 
@@ -41,14 +59,28 @@ impl<AsyncMode> Builder<AsyncMode> {
     pub(crate) fn new(url: Url) -> Self {
         Self {
             url,
-            protocol_version: CURRENT_PROTOCOL_VERSION,
+            protocol_versions: SUPPORTED_PROTOCOL_VERSIONS,
             custom_apis: HashMap::new(),
             request_timeout: None,
             connect_timeout: None,
+            compression: None,
+            interceptor: None,
+            credential_provider: None,
+            replicas: Vec::new(),
+            replica_routing: ReplicaRouting::default(),
+            default_database: None,
             #[cfg(not(target_arch = "wasm32"))]
             certificate: None,
             #[cfg(not(target_arch = "wasm32"))]
+            certificate_fingerprint: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            certificate_authorities: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            fallbacks: Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
             tokio: None,
+            #[cfg(not(target_arch = "wasm32"))]
+            keepalive: None,
             mode: PhantomData,
         }
     }
@@ -87,11 +119,82 @@ impl<AsyncMode> Builder<AsyncMode> {
         self
     }
 
-    /// Overrides the protocol version. Only for testing purposes.
+    /// Requires the server's certificate to have a SHA-256 fingerprint of
+    /// `fingerprint`. The fingerprint is computed over the DER encoding of the
+    /// end-entity certificate presented by the server.
+    ///
+    /// This check is performed in addition to the normal verification of the
+    /// server's certificate. If the fingerprint doesn't match, the connection
+    /// is closed and requests fail with
+    /// [`networking::Error::CertificateFingerprintMismatch`](bonsaidb_core::networking::Error::CertificateFingerprintMismatch).
+    /// Only supported with BonsaiDb protocol-based connections.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[allow(clippy::missing_const_for_fn)]
+    pub fn with_certificate_fingerprint(mut self, fingerprint: [u8; 32]) -> Self {
+        self.certificate_fingerprint = Some(fingerprint);
+        self
+    }
+
+    /// Trusts `certificates` as certificate authorities when verifying the
+    /// server's certificate, in addition to the system's root certificates.
+    /// This allows connecting to servers whose certificates are issued by a
+    /// private certificate authority without pinning each server's
+    /// certificate. Supported with BonsaiDb protocol-based connections,
+    /// secure WebSocket (`wss`) connections, and secure HTTP (`https`)
+    /// connections.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_certificate_authorities(
+        mut self,
+        certificates: impl IntoIterator<Item = Certificate>,
+    ) -> Self {
+        self.certificate_authorities.extend(certificates);
+        self
+    }
+
+    /// Adds `url` as a fallback for connecting to the server. When a
+    /// connection to the server can't be established, the client tries each
+    /// fallback in the order they were added, and uses the first one that it
+    /// can connect to until that connection fails. If none can be connected
+    /// to, the request fails, and the next request starts over with the url
+    /// this builder was created with.
+    ///
+    /// Only failures to connect cause a fallback to be tried. Requests that
+    /// were sent before a connection was lost are never retried, since the
+    /// server may have already executed them.
+    ///
+    /// This allows a client to prefer the fastest transport while still
+    /// connecting in environments that block it, such as networks that don't
+    /// allow UDP traffic or proxies that don't support `WebSocket`
+    /// connections:
+    ///
+    /// ```rust
+    /// # use bonsaidb_client::{url::Url, AsyncClient};
+    /// # fn test_fn() -> anyhow::Result<()> {
+    /// let client = AsyncClient::build(Url::parse("bonsaidb://db.example.com")?)
+    ///     .with_fallback(Url::parse("wss://db.example.com")?)
+    ///     .with_fallback(Url::parse("https://db.example.com/bonsaidb")?)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// Fallbacks are connected to using the same settings as the primary
+    /// url, including its certificates and timeouts. Because each fallback is
+    /// only tried after the previous transport's connection attempt fails,
+    /// consider lowering the
+    /// [connect timeout](Self::with_connect_timeout) when using fallbacks.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_fallback(mut self, url: Url) -> Self {
+        self.fallbacks.push(url);
+        self
+    }
+
+    /// Overrides the protocol versions offered to the server, ordered from
+    /// most preferred to least preferred. Only for testing purposes.
     #[cfg(feature = "test-util")]
     #[allow(clippy::missing_const_for_fn)]
-    pub fn with_protocol_version(mut self, version: &'static str) -> Self {
-        self.protocol_version = version;
+    pub fn with_protocol_versions(mut self, versions: &'static [&'static str]) -> Self {
+        self.protocol_versions = versions;
         self
     }
 
@@ -112,17 +215,204 @@ impl<AsyncMode> Builder<AsyncMode> {
         self
     }
 
+    /// Enables keepalives: whenever `interval` elapses without the client
+    /// sending a request, the client pings the server.
+    ///
+    /// Servers can be configured to disconnect clients that have been idle
+    /// for too long using `ServerConfiguration::with_idle_timeout()`. To keep
+    /// an otherwise idle connection open, `interval` should be shorter than
+    /// the server's idle timeout, which can be checked using
+    /// [`AsyncClient::server_status()`]. Keepalives are not sent until the
+    /// client sends its first request.
+    ///
+    /// If not specified, keepalives are disabled.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_keepalive(mut self, interval: impl Into<Duration>) -> Self {
+        self.keepalive = Some(interval.into());
+        self
+    }
+
+    /// Requests that messages exchanged with the server be compressed using
+    /// `compression`. Messages of at least
+    /// [`TransportCompression::DEFAULT_THRESHOLD`] bytes are compressed unless
+    /// a different threshold is set using
+    /// [`with_compression_threshold()`](Self::with_compression_threshold).
+    ///
+    /// Compression is negotiated when connecting. If the server does not
+    /// support `compression`, the connection is established without
+    /// compression. Currently, only native `WebSocket` and HTTP connections
+    /// support compression.
+    #[cfg(feature = "compression")]
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        let threshold = self
+            .compression
+            .map_or(TransportCompression::DEFAULT_THRESHOLD, |compression| {
+                compression.threshold
+            });
+        self.compression = Some(TransportCompression::new(compression).with_threshold(threshold));
+        self
+    }
+
+    /// Sets the minimum size, in bytes, of a message before it is compressed.
+    /// Has no effect unless compression is enabled using
+    /// [`with_compression()`](Self::with_compression).
+    #[cfg(feature = "compression")]
+    pub fn with_compression_threshold(mut self, threshold: usize) -> Self {
+        if let Some(compression) = &mut self.compression {
+            compression.threshold = threshold;
+        }
+        self
+    }
+
+    /// Installs `interceptor`, which observes each request sent by the
+    /// client. See [`RequestInterceptor`] for more information.
+    ///
+    /// If not specified, no interceptor is installed.
+    pub fn with_interceptor(mut self, interceptor: Arc<dyn RequestInterceptor>) -> Self {
+        self.interceptor = Some(interceptor);
+        self
+    }
+
+    /// Installs `provider`, which is invoked to obtain the credentials used by
+    /// [`AsyncClient::authenticate_with_retry()`] and
+    /// [`BlockingClient::authenticate_with_retry()`]. The provider is invoked
+    /// again each time the server reports that the session has expired.
+    ///
+    /// ```rust
+    /// # use bonsaidb_client::{url::Url, AsyncClient};
+    /// # use bonsaidb_core::connection::{Authentication, SensitiveString};
+    /// # async fn test_fn() -> anyhow::Result<()> {
+    /// let client = AsyncClient::build(Url::parse("bonsaidb://localhost")?)
+    ///     .with_credential_provider(|| async {
+    ///         Authentication::password("ecton", SensitiveString::from("hunter2"))
+    ///     })
+    ///     .build()?
+    ///     .authenticate_with_retry()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_credential_provider<
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Authentication, bonsaidb_core::Error>> + Send + 'static,
+    >(
+        mut self,
+        provider: F,
+    ) -> Self {
+        self.credential_provider = Some(CredentialProvider::new(provider));
+        self
+    }
+
+    /// Adds read replicas of the server this client connects to. Requests
+    /// that only read documents or views, such as
+    /// [`get()`](bonsaidb_core::connection::AsyncLowLevelConnection::get_from_collection)
+    /// or [`query()`](bonsaidb_core::connection::AsyncLowLevelConnection::query_by_name),
+    /// are sent to one of `replicas`, chosen using the
+    /// [routing policy](Self::with_replica_routing). All other requests,
+    /// including every request that modifies data, are sent to the primary
+    /// server at the url this builder was created with.
+    ///
+    /// If a replica can't be reached, the read is retried on the remaining
+    /// replicas, and is sent to the primary server if no replica can be
+    /// reached. Errors returned by a replica's server, such as a database not
+    /// being found, are returned without retrying.
+    ///
+    /// Each replica is connected to using the same settings as the primary
+    /// server, including its certificates. Sessions only exist on the server
+    /// they were authenticated with, so requests made by an authenticated
+    /// client are always sent to the primary server.
+    ///
+    /// Replicas are usually kept up to date using
+    /// `CustomServer::replicate_from()`, and may lag behind the primary
+    /// server. Modifying a replicated database on a replica fails with
+    /// [`Error::ReadOnlyReplica`](bonsaidb_core::Error::ReadOnlyReplica).
+    ///
+    /// ```rust
+    /// # use bonsaidb_client::{url::Url, AsyncClient, ReplicaRouting};
+    /// # fn test_fn() -> anyhow::Result<()> {
+    /// let client = AsyncClient::build(Url::parse("bonsaidb://primary.internal")?)
+    ///     .with_replicas([
+    ///         Url::parse("bonsaidb://replica-1.internal")?,
+    ///         Url::parse("bonsaidb://replica-2.internal")?,
+    ///     ])
+    ///     .with_replica_routing(ReplicaRouting::LeastLoaded)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_replicas(mut self, replicas: impl IntoIterator<Item = Url>) -> Self {
+        self.replicas.extend(replicas);
+        self
+    }
+
+    /// Sets the policy used to choose which [replica](Self::with_replicas) a
+    /// read is sent to.
+    ///
+    /// If not specified, [`ReplicaRouting::RoundRobin`] is used.
+    pub fn with_replica_routing(mut self, routing: ReplicaRouting) -> Self {
+        self.replica_routing = routing;
+        self
+    }
+
+    /// Sets the name of the database returned by
+    /// [`AsyncClient::default_database()`] and
+    /// [`BlockingClient::default_database()`](crate::BlockingClient::default_database).
+    ///
+    /// This is useful for applications that only ever use one database, as
+    /// the database's name doesn't need to be repeated. The database isn't
+    /// created or validated when building the client.
+    pub fn with_default_database(mut self, name: impl Into<String>) -> Self {
+        self.default_database = Some(name.into());
+        self
+    }
+
     fn finish_internal(self) -> Result<AsyncClient, Error> {
+        let replicas = if self.replicas.is_empty() {
+            None
+        } else {
+            let replicas = self
+                .replicas
+                .iter()
+                .map(|url| self.connect(url.clone(), false, None))
+                .collect::<Result<Vec<_>, _>>()?;
+            Some(Arc::new(Replicas::new(replicas, self.replica_routing)))
+        };
+        self.connect(self.url.clone(), true, replicas)
+    }
+
+    fn connect(
+        &self,
+        url: Url,
+        primary: bool,
+        replicas: Option<Arc<Replicas>>,
+    ) -> Result<AsyncClient, Error> {
         AsyncClient::new_from_parts(
-            self.url,
-            self.protocol_version,
-            self.custom_apis,
+            url,
+            self.protocol_versions,
+            self.custom_apis.clone(),
             self.connect_timeout,
             self.request_timeout,
+            self.compression,
+            self.interceptor.clone(),
+            self.credential_provider.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            self.certificate.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            self.certificate_fingerprint,
+            #[cfg(not(target_arch = "wasm32"))]
+            self.certificate_authorities.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            if primary {
+                self.fallbacks.clone()
+            } else {
+                Vec::new()
+            },
             #[cfg(not(target_arch = "wasm32"))]
-            self.certificate,
+            self.tokio.clone().or_else(|| Handle::try_current().ok()),
             #[cfg(not(target_arch = "wasm32"))]
-            self.tokio.or_else(|| Handle::try_current().ok()),
+            self.keepalive,
+            replicas,
+            self.default_database.clone(),
         )
     }
 }
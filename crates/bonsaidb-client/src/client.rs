@@ -1,10 +1,12 @@
 use std::any::TypeId;
 use std::collections::HashMap;
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::ops::Deref;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
 
 use async_trait::async_trait;
 use bonsaidb_core::admin::{Admin, ADMIN_DATABASE_NAME};
@@ -12,14 +14,20 @@ use bonsaidb_core::api::{self, Api, ApiName};
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::arc_bytes::OwnedBytes;
 use bonsaidb_core::connection::{
-    AsyncStorageConnection, Database, HasSession, IdentityReference, Session,
+    AsyncStorageConnection, Authentication, Database, DatabaseCreation, DatabaseStats, HasSession,
+    IdentityReference, PermissionGroupInfo, SensitiveString, Session, SessionId, UserInfo,
 };
 use bonsaidb_core::networking::{
-    AlterUserPermissionGroupMembership, AlterUserRoleMembership, AssumeIdentity, CreateDatabase,
-    CreateUser, DeleteDatabase, DeleteUser, ListAvailableSchemas, ListDatabases, LogOutSession,
-    MessageReceived, Payload, UnregisterSubscriber, CURRENT_PROTOCOL_VERSION,
+    AcknowledgeMessages, AlterUserPermissionGroupMembership, AlterUserRoleMembership,
+    AssumeIdentity, CancelRequest, CreateDatabase, CreateUser, DeleteDatabase, DeleteUser,
+    GetDatabaseStats, GetServerStatus, ListAvailableSchemas, ListDatabases, ListUserGroups,
+    ListUsers, LogOutSession, MessageReceived, Payload, ServerStatus, TransportCompression,
+    UnregisterSubscriber, CLIENT_EVENTS_TOPIC, SERVER_EVENTS_TOPIC, SUPPORTED_PROTOCOL_VERSIONS,
+};
+use bonsaidb_core::permissions::{Permissions, Statement};
+use bonsaidb_core::pubsub::{
+    self, AsyncPubSub, AsyncSubscriber, BufferLimit, MessageSender, Receiver,
 };
-use bonsaidb_core::permissions::Permissions;
 use bonsaidb_core::schema::{Nameable, Schema, SchemaName, SchemaSummary, Schematic};
 use bonsaidb_utils::fast_async_lock;
 use flume::Sender;
@@ -30,16 +38,28 @@ use parking_lot::Mutex;
 use tokio::runtime::Handle;
 use url::Url;
 
+pub use self::batch::{Batch, BatchEntry, BatchResults};
+#[cfg(not(target_arch = "wasm32"))]
+use self::quic_worker::ServerCertificates;
 pub use self::remote_database::{AsyncRemoteDatabase, AsyncRemoteSubscriber};
+pub use self::replicas::{ReplicaRouting, Replicas};
+pub use self::server_events::ServerEvents;
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::sync::{BlockingClient, BlockingRemoteDatabase, BlockingRemoteSubscriber};
 use crate::builder::Async;
 use crate::error::Error;
-use crate::{ApiError, Builder};
+use crate::{ApiError, Builder, RequestInfo, RequestInterceptor};
 
+mod batch;
+#[cfg(not(target_arch = "wasm32"))]
+mod fallback;
+#[cfg(all(feature = "http", not(target_arch = "wasm32")))]
+mod http_worker;
 #[cfg(not(target_arch = "wasm32"))]
 mod quic_worker;
 mod remote_database;
+mod replicas;
+mod server_events;
 #[cfg(not(target_arch = "wasm32"))]
 mod sync;
 #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
@@ -48,7 +68,7 @@ mod tungstenite_worker;
 mod wasm_websocket_worker;
 
 #[derive(Debug, Clone, Default)]
-pub struct SubscriberMap(Arc<Mutex<HashMap<u64, flume::Sender<Message>>>>);
+pub struct SubscriberMap(Arc<Mutex<HashMap<u64, MessageSender>>>);
 
 impl SubscriberMap {
     pub fn clear(&self) {
@@ -58,15 +78,13 @@ impl SubscriberMap {
 }
 
 impl Deref for SubscriberMap {
-    type Target = Mutex<HashMap<u64, flume::Sender<Message>>>;
+    type Target = Mutex<HashMap<u64, MessageSender>>;
 
     fn deref(&self) -> &Self::Target {
         &self.0
     }
 }
 
-use bonsaidb_core::circulate::Message;
-
 #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
 pub type WebSocketError = tokio_tungstenite::tungstenite::Error;
 
@@ -130,6 +148,23 @@ pub type WebSocketError = wasm_websocket_worker::WebSocketError;
 /// # }
 /// ```
 ///
+/// ### With a Private Certificate Authority
+///
+/// When the server's certificate is issued by a certificate authority that
+/// isn't trusted by the system, the authority's certificate can be trusted
+/// when building the client:
+///
+/// ```rust
+/// # use bonsaidb_client::{AsyncClient, fabruic::Certificate, url::Url};
+/// # async fn test_fn() -> anyhow::Result<()> {
+/// let authority = Certificate::from_der(std::fs::read("my-ca.der")?)?;
+/// let client = AsyncClient::build(Url::parse("bonsaidb://my-server.internal")?)
+///     .with_certificate_authorities([authority])
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
 /// ## Connecting via WebSockets
 ///
 /// WebSockets are built atop the HTTP protocol. There are two URL schemes for
@@ -158,6 +193,37 @@ pub type WebSocketError = wasm_websocket_worker::WebSocketError;
 /// # }
 /// ```
 ///
+/// The server's certificate is verified using the system's root certificates.
+/// If the server's certificate is issued by a private certificate authority,
+/// the authority can be trusted using
+/// [`Builder::with_certificate_authorities()`]:
+///
+/// ```rust
+/// # use bonsaidb_client::{AsyncClient, fabruic::Certificate, url::Url};
+/// # async fn test_fn() -> anyhow::Result<()> {
+/// let authority = Certificate::from_der(std::fs::read("my-ca.der")?)?;
+/// let client = AsyncClient::build(Url::parse("wss://my-server.internal")?)
+///     .with_certificate_authorities([authority])
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ## Connecting via a Unix Domain Socket
+///
+/// On Unix platforms, the `unix` URL scheme connects to a server listening
+/// using `Server::listen_on_unix_socket()`. The path of the URL is the path of
+/// the socket. The WebSocket protocol is used over the socket, which requires
+/// the `websockets` feature.
+///
+/// ```rust
+/// # use bonsaidb_client::{AsyncClient, url::Url};
+/// # async fn test_fn() -> anyhow::Result<()> {
+/// let client = AsyncClient::build(Url::parse("unix:///var/run/bonsaidb.sock")?).build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
 /// ## Using a `Api`
 ///
 /// Our user guide has a [section on creating and
@@ -228,12 +294,23 @@ pub type WebSocketError = wasm_websocket_worker::WebSocketError;
 pub struct AsyncClient {
     pub(crate) data: Arc<Data>,
     session: ClientSession,
+    refreshable: Option<Arc<RefreshableSession>>,
     request_timeout: Duration,
 }
 
 impl Drop for AsyncClient {
     fn drop(&mut self) {
-        if self.session_is_current() && Arc::strong_count(&self.session.session) == 1 {
+        if let Some(refreshable) = &self.refreshable {
+            if Arc::strong_count(refreshable) == 1 {
+                let session = refreshable.current.lock().clone();
+                if session.connection_id == self.data.connection_counter.load(Ordering::SeqCst) {
+                    if let Some(session_id) = session.session.id {
+                        // Final reference to a refreshable session
+                        drop(self.invoke_blocking_api_request(&LogOutSession(session_id)));
+                    }
+                }
+            }
+        } else if self.session_is_current() && Arc::strong_count(&self.session.session) == 1 {
             if let Some(session_id) = self.session.session.id {
                 // Final reference to an authenticated session
                 drop(self.invoke_blocking_api_request(&LogOutSession(session_id)));
@@ -256,6 +333,16 @@ pub struct Data {
     connection_counter: Arc<AtomicU32>,
     request_id: AtomicU32,
     subscribers: SubscriberMap,
+    interceptor: Option<Arc<dyn RequestInterceptor>>,
+    credential_provider: Option<CredentialProvider>,
+    replicas: Option<Arc<Replicas>>,
+    default_database: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    keepalive: Option<Duration>,
+    /// The time the most recent request was sent, if keepalives are enabled
+    /// and a request has been sent.
+    #[cfg(not(target_arch = "wasm32"))]
+    last_request_at: Mutex<Option<Instant>>,
 }
 
 impl AsyncClient {
@@ -282,14 +369,27 @@ impl AsyncClient {
     pub fn new(url: Url) -> Result<Self, Error> {
         Self::new_from_parts(
             url,
-            CURRENT_PROTOCOL_VERSION,
+            SUPPORTED_PROTOCOL_VERSIONS,
             HashMap::default(),
             None,
             None,
+            None,
+            None,
+            None,
+            #[cfg(not(target_arch = "wasm32"))]
+            None,
             #[cfg(not(target_arch = "wasm32"))]
             None,
             #[cfg(not(target_arch = "wasm32"))]
+            Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
             Handle::try_current().ok(),
+            #[cfg(not(target_arch = "wasm32"))]
+            None,
+            None,
+            None,
         )
     }
 
@@ -308,14 +408,24 @@ impl AsyncClient {
     /// to recover and reconnect, each component of the apps built can adopt a
     /// "retry-to-recover" design, or "abort-and-fail" depending on how critical
     /// the database is to operation.
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new_from_parts(
         url: Url,
-        protocol_version: &'static str,
+        protocol_versions: &'static [&'static str],
         mut custom_apis: HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
         connect_timeout: Option<Duration>,
         request_timeout: Option<Duration>,
+        compression: Option<TransportCompression>,
+        interceptor: Option<Arc<dyn RequestInterceptor>>,
+        credential_provider: Option<CredentialProvider>,
         #[cfg(not(target_arch = "wasm32"))] certificate: Option<fabruic::Certificate>,
+        #[cfg(not(target_arch = "wasm32"))] certificate_fingerprint: Option<[u8; 32]>,
+        #[cfg(not(target_arch = "wasm32"))] certificate_authorities: Vec<fabruic::Certificate>,
+        #[cfg(not(target_arch = "wasm32"))] fallbacks: Vec<Url>,
         #[cfg(not(target_arch = "wasm32"))] tokio: Option<Handle>,
+        #[cfg(not(target_arch = "wasm32"))] keepalive: Option<Duration>,
+        replicas: Option<Arc<Replicas>>,
+        default_database: Option<String>,
     ) -> Result<Self, Error> {
         let subscribers = SubscriberMap::default();
         let callback_subscribers = subscribers.clone();
@@ -327,8 +437,11 @@ impl AsyncClient {
                     async move {
                         let mut subscribers = callback_subscribers.lock();
                         if let Some(sender) = subscribers.get(&message.subscriber_id) {
+                            if message.lagged > 0 {
+                                sender.add_lagged(message.lagged);
+                            }
                             if sender
-                                .send(bonsaidb_core::circulate::Message {
+                                .try_send(bonsaidb_core::circulate::Message {
                                     topic: OwnedBytes::from(message.topic.into_vec()),
                                     payload: OwnedBytes::from(message.payload.into_vec()),
                                 })
@@ -347,54 +460,94 @@ impl AsyncClient {
             subscribers,
             connect_timeout: connect_timeout.unwrap_or(Duration::from_secs(60)),
             request_timeout: request_timeout.unwrap_or(Duration::from_secs(60)),
+            compression,
+            interceptor,
+            credential_provider,
+            replicas,
+            default_database,
+            #[cfg(not(target_arch = "wasm32"))]
+            keepalive,
         };
-        match connection.url.scheme() {
+        Self::new_transport_client(
+            connection,
+            protocol_versions,
+            custom_apis,
             #[cfg(not(target_arch = "wasm32"))]
-            "bonsaidb" => Ok(Self::new_bonsai_client(
-                connection,
-                protocol_version,
-                certificate,
-                custom_apis,
-                tokio,
-            )),
-            #[cfg(feature = "websockets")]
-            "wss" | "ws" => Ok(Self::new_websocket_client(
-                connection,
-                protocol_version,
-                custom_apis,
-                #[cfg(not(target_arch = "wasm32"))]
-                tokio,
-            )),
-            other => Err(Error::InvalidUrl(format!("unsupported scheme {other}"))),
-        }
+            ServerCertificates {
+                pinned: certificate,
+                fingerprint: certificate_fingerprint,
+                authorities: certificate_authorities,
+            },
+            #[cfg(not(target_arch = "wasm32"))]
+            fallbacks,
+            #[cfg(not(target_arch = "wasm32"))]
+            tokio,
+        )
     }
 
+    /// Spawns the workers that connect to `server` and to each of
+    /// `fallbacks`, in order, when connecting fails.
     #[cfg(not(target_arch = "wasm32"))]
-    fn new_bonsai_client(
+    fn new_transport_client(
         server: ConnectionInfo,
-        protocol_version: &'static str,
-        certificate: Option<fabruic::Certificate>,
+        protocol_versions: &'static [&'static str],
         custom_apis: HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
+        certificates: ServerCertificates,
+        fallbacks: Vec<Url>,
         tokio: Option<Handle>,
-    ) -> Self {
+    ) -> Result<Self, Error> {
         let (request_sender, request_receiver) = flume::unbounded();
         let connection_counter = Arc::new(AtomicU32::default());
+        let custom_apis = Arc::new(custom_apis);
         let request_timeout = server.request_timeout;
         let subscribers = server.subscribers.clone();
-
-        sync::spawn_client(
-            quic_worker::reconnecting_client_loop(
+        let interceptor = server.interceptor.clone();
+        let credential_provider = server.credential_provider.clone();
+        let replicas = server.replicas.clone();
+        let default_database = server.default_database.clone();
+        let keepalive = server.keepalive;
+
+        if fallbacks.is_empty() {
+            let worker = transport_worker(
                 server,
-                protocol_version,
-                certificate,
+                protocol_versions,
+                &certificates,
                 request_receiver,
-                Arc::new(custom_apis),
-                connection_counter.clone(),
-            ),
-            tokio,
-        );
+                &custom_apis,
+                &connection_counter,
+            )?;
+            sync::spawn_client(worker, tokio.clone());
+        } else {
+            let mut transports = Vec::with_capacity(fallbacks.len() + 1);
+            let mut workers = Vec::with_capacity(fallbacks.len() + 1);
+            for url in std::iter::once(server.url.clone()).chain(fallbacks) {
+                let (transport_sender, transport_receiver) = flume::unbounded();
+                workers.push(transport_worker(
+                    server.with_url(url.clone()),
+                    protocol_versions,
+                    &certificates,
+                    transport_receiver,
+                    &custom_apis,
+                    &connection_counter,
+                )?);
+                transports.push(fallback::Transport {
+                    url,
+                    requests: transport_sender,
+                });
+            }
+            sync::spawn_client(
+                async move {
+                    futures::try_join!(
+                        fallback::fallback_client_loop(request_receiver, transports),
+                        futures::future::try_join_all(workers)
+                    )?;
+                    Ok(())
+                },
+                tokio.clone(),
+            );
+        }
 
-        Self {
+        let client = Self {
             data: Arc::new(Data {
                 request_sender,
                 schemas: Mutex::default(),
@@ -402,53 +555,50 @@ impl AsyncClient {
                 request_id: AtomicU32::default(),
                 effective_permissions: Mutex::default(),
                 subscribers,
+                interceptor,
+                credential_provider,
+                replicas,
+                default_database,
+                keepalive,
+                last_request_at: Mutex::default(),
             }),
             session: ClientSession::default(),
+            refreshable: None,
             request_timeout,
+        };
+        if let Some(interval) = keepalive {
+            sync::spawn_client(
+                send_keepalives(Arc::downgrade(&client.data), interval, request_timeout),
+                tokio,
+            );
         }
+        Ok(client)
     }
 
-    #[cfg(all(feature = "websockets", not(target_arch = "wasm32")))]
-    fn new_websocket_client(
+    #[cfg(target_arch = "wasm32")]
+    fn new_transport_client(
         server: ConnectionInfo,
-        protocol_version: &'static str,
+        protocol_versions: &'static [&'static str],
         custom_apis: HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
-        tokio: Option<Handle>,
-    ) -> Self {
-        let (request_sender, request_receiver) = flume::unbounded();
-        let connection_counter = Arc::new(AtomicU32::default());
-        let request_timeout = server.request_timeout;
-        let subscribers = server.subscribers.clone();
-
-        sync::spawn_client(
-            tungstenite_worker::reconnecting_client_loop(
+    ) -> Result<Self, Error> {
+        match server.url.scheme() {
+            #[cfg(feature = "websockets")]
+            "wss" | "ws" => Ok(Self::new_websocket_client(
                 server,
-                protocol_version,
-                request_receiver,
-                Arc::new(custom_apis),
-                connection_counter.clone(),
-            ),
-            tokio,
-        );
-
-        Self {
-            data: Arc::new(Data {
-                request_sender,
-                schemas: Mutex::default(),
-                request_id: AtomicU32::default(),
-                connection_counter,
-                effective_permissions: Mutex::default(),
-                subscribers,
-            }),
-            session: ClientSession::default(),
-            request_timeout,
+                protocol_versions,
+                custom_apis,
+            )),
+            "unix" => Err(Error::InvalidUrl(String::from(
+                "unix sockets are not supported on this platform",
+            ))),
+            other => Err(Error::InvalidUrl(format!("unsupported scheme {other}"))),
         }
     }
 
     #[cfg(all(feature = "websockets", target_arch = "wasm32"))]
     fn new_websocket_client(
         server: ConnectionInfo,
-        protocol_version: &'static str,
+        protocol_versions: &'static [&'static str],
         custom_apis: HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
     ) -> Self {
         let (request_sender, request_receiver) = flume::unbounded();
@@ -456,7 +606,7 @@ impl AsyncClient {
 
         wasm_websocket_worker::spawn_client(
             Arc::new(server.url),
-            protocol_version,
+            protocol_versions,
             request_receiver,
             Arc::new(custom_apis),
             server.subscribers.clone(),
@@ -476,36 +626,88 @@ impl AsyncClient {
                 connection_counter,
                 effective_permissions: Mutex::default(),
                 subscribers: server.subscribers,
+                interceptor: server.interceptor,
+                credential_provider: server.credential_provider,
+                replicas: server.replicas,
+                default_database: server.default_database,
                 #[cfg(feature = "test-util")]
                 background_task_running,
             }),
             session: ClientSession::default(),
+            refreshable: None,
             request_timeout: server.request_timeout,
         }
     }
 
+    /// Assigns an id to `request` and notifies the interceptor, if one is
+    /// installed, that it is about to be sent.
+    fn begin_request<'a, Api: api::Api>(
+        &self,
+        name: &'a ApiName,
+        request: &'a Api,
+    ) -> RequestInfo<'a> {
+        let info = RequestInfo {
+            id: self.data.request_id.fetch_add(1, Ordering::SeqCst),
+            api: name,
+            database: request.database(),
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.data.keepalive.is_some() {
+            *self.data.last_request_at.lock() = Some(Instant::now());
+        }
+        if let Some(interceptor) = &self.data.interceptor {
+            interceptor.before_request(&info);
+        }
+        info
+    }
+
+    /// Notifies the interceptor, if one is installed, that the request
+    /// described by `info` completed with `result`.
+    fn finish_request<T, E: Display>(&self, info: &RequestInfo<'_>, result: &Result<T, E>) {
+        if let Some(interceptor) = &self.data.interceptor {
+            interceptor.after_response(info, result.as_ref().err().map(|err| err as &dyn Display));
+        }
+    }
+
+    /// Sends a request with `id`, returning a receiver for its response.
     fn send_request_without_confirmation(
         &self,
+        id: u32,
+        session_id: Option<SessionId>,
         name: ApiName,
         bytes: Bytes,
     ) -> Result<flume::Receiver<Result<Bytes, Error>>, Error> {
         let (result_sender, result_receiver) = flume::bounded(1);
-        let id = self.data.request_id.fetch_add(1, Ordering::SeqCst);
         self.data.request_sender.send(PendingRequest {
             request: Payload {
-                session_id: self.session.session.id,
+                session_id,
                 id: Some(id),
                 name,
                 value: Ok(bytes),
             },
             responder: result_sender,
+            #[cfg(not(target_arch = "wasm32"))]
+            fallback: None,
         })?;
 
         Ok(result_receiver)
     }
 
-    async fn send_request_async(&self, name: ApiName, bytes: Bytes) -> Result<Bytes, Error> {
-        let result_receiver = self.send_request_without_confirmation(name, bytes)?;
+    /// Asks the server to stop working on the request with `id`. The response
+    /// to this request is not waited on.
+    fn cancel_request(&self, id: u32) {
+        drop(self.invoke_blocking_api_request(&CancelRequest { request_id: id }));
+    }
+
+    async fn send_request_async(
+        &self,
+        id: u32,
+        session_id: Option<SessionId>,
+        name: ApiName,
+        bytes: Bytes,
+    ) -> Result<Bytes, Error> {
+        let result_receiver =
+            self.send_request_without_confirmation(id, session_id, name, bytes)?;
 
         #[cfg(target_arch = "wasm32")]
         let result = {
@@ -537,24 +739,97 @@ impl AsyncClient {
 
         match result {
             Ok(response) => response?,
-            Err(_) => Err(Error::request_timeout()),
+            Err(_) => {
+                self.cancel_request(id);
+                Err(Error::request_timeout())
+            }
         }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    fn send_request(&self, name: ApiName, bytes: Bytes) -> Result<Bytes, Error> {
-        let result_receiver = self.send_request_without_confirmation(name, bytes)?;
-
-        result_receiver.recv_timeout(self.request_timeout)?
+    fn send_request(
+        &self,
+        id: u32,
+        session_id: Option<SessionId>,
+        name: ApiName,
+        bytes: Bytes,
+    ) -> Result<Bytes, Error> {
+        let result_receiver =
+            self.send_request_without_confirmation(id, session_id, name, bytes)?;
+
+        match result_receiver.recv_timeout(self.request_timeout) {
+            Ok(response) => response,
+            Err(flume::RecvTimeoutError::Timeout) => {
+                self.cancel_request(id);
+                Err(Error::request_timeout())
+            }
+            Err(err) => Err(Error::from(err)),
+        }
     }
 
     /// Sends an api `request`.
+    ///
+    /// If this client was returned from
+    /// [`authenticate_with_retry()`](Self::authenticate_with_retry) and the
+    /// server reports that its session has expired, the client authenticates
+    /// again and retries `request` once.
     pub async fn send_api_request<Api: api::Api>(
         &self,
         request: &Api,
+    ) -> Result<Api::Response, ApiError<Api::Error>> {
+        let session_id = self.session_id();
+        if let Some(replicas) = self.replicas_for::<Api>(session_id) {
+            if let Some(result) = replicas.send_api_request(request).await {
+                return result;
+            }
+        }
+        let result = self.send_api_request_once(session_id, request).await;
+        if let Some(refreshable) = &self.refreshable {
+            if session_expired(&result) {
+                self.refresh_session(refreshable, session_id)
+                    .await
+                    .map_err(Error::from)?;
+                return self.send_api_request_once(self.session_id(), request).await;
+            }
+        }
+        result
+    }
+
+    /// Returns the replicas a request for `Api` made with `session_id` can be
+    /// sent to. Sessions only exist on the server they were created on, so
+    /// requests made with a session are always sent to the primary.
+    fn replicas_for<Api: api::Api>(&self, session_id: Option<SessionId>) -> Option<&Replicas> {
+        self.data
+            .replicas
+            .as_deref()
+            .filter(|_| session_id.is_none() && replicas::is_read_only::<Api>())
+    }
+
+    async fn send_api_request_once<Api: api::Api>(
+        &self,
+        session_id: Option<SessionId>,
+        request: &Api,
+    ) -> Result<Api::Response, ApiError<Api::Error>> {
+        let name = Api::name();
+        let info = self.begin_request(&name, request);
+        let result = self
+            .send_api_request_with_id(info.id, session_id, name.clone(), request)
+            .await;
+        self.finish_request(&info, &result);
+        result
+    }
+
+    async fn send_api_request_with_id<Api: api::Api>(
+        &self,
+        id: u32,
+        session_id: Option<SessionId>,
+        name: ApiName,
+        request: &Api,
     ) -> Result<Api::Response, ApiError<Api::Error>> {
         let request = Bytes::from(pot::to_vec(request).map_err(Error::from)?);
-        let response = self.send_request_async(Api::name(), request).await?;
+        let response = self
+            .send_request_async(id, session_id, name, request)
+            .await?;
         let response =
             pot::from_slice::<Result<Api::Response, Api::Error>>(&response).map_err(Error::from)?;
         response.map_err(ApiError::Api)
@@ -564,21 +839,119 @@ impl AsyncClient {
     fn send_blocking_api_request<Api: api::Api>(
         &self,
         request: &Api,
+    ) -> Result<Api::Response, ApiError<Api::Error>> {
+        let session_id = self.session_id();
+        if let Some(replicas) = self.replicas_for::<Api>(session_id) {
+            if let Some(result) = replicas.send_blocking_api_request(request) {
+                return result;
+            }
+        }
+        let result = self.send_blocking_api_request_once(session_id, request);
+        if let Some(refreshable) = &self.refreshable {
+            if session_expired(&result) {
+                self.refresh_session_blocking(refreshable, session_id)
+                    .map_err(Error::from)?;
+                return self.send_blocking_api_request_once(self.session_id(), request);
+            }
+        }
+        result
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn send_blocking_api_request_once<Api: api::Api>(
+        &self,
+        session_id: Option<SessionId>,
+        request: &Api,
+    ) -> Result<Api::Response, ApiError<Api::Error>> {
+        let name = Api::name();
+        let info = self.begin_request(&name, request);
+        let result =
+            self.send_blocking_api_request_with_id(info.id, session_id, name.clone(), request);
+        self.finish_request(&info, &result);
+        result
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn send_blocking_api_request_with_id<Api: api::Api>(
+        &self,
+        id: u32,
+        session_id: Option<SessionId>,
+        name: ApiName,
+        request: &Api,
     ) -> Result<Api::Response, ApiError<Api::Error>> {
         let request = Bytes::from(pot::to_vec(request).map_err(Error::from)?);
-        let response = self.send_request(Api::name(), request)?;
+        let response = self.send_request(id, session_id, name, request)?;
 
         let response =
             pot::from_slice::<Result<Api::Response, Api::Error>>(&response).map_err(Error::from)?;
         response.map_err(ApiError::Api)
     }
 
+    /// Sends `request` without waiting for its response. The interceptor is
+    /// notified before the request is sent, but not after.
     fn invoke_blocking_api_request<Api: api::Api>(&self, request: &Api) -> Result<(), Error> {
+        let name = Api::name();
+        let info = self.begin_request(&name, request);
         let request = Bytes::from(pot::to_vec(request).map_err(Error::from)?);
-        self.send_request_without_confirmation(Api::name(), request)
+        self.send_request_without_confirmation(info.id, self.session_id(), name.clone(), request)
             .map(|_| ())
     }
 
+    /// Returns a new [`Batch`] of requests that will be sent to the server
+    /// using this client.
+    pub fn batch(&self) -> Batch {
+        Batch::new(self.clone())
+    }
+
+    /// Sends a minimal request to the server and returns the time it took to
+    /// receive the response.
+    ///
+    /// Pinging only requires
+    /// [`ServerAction::Connect`](bonsaidb_core::permissions::bonsai::ServerAction::Connect),
+    /// which every connected client has already been granted. Failures in the
+    /// underlying transport are returned as
+    /// [`bonsaidb_core::Error::Networking`], such as
+    /// [`networking::Error::Disconnected`](bonsaidb_core::networking::Error::Disconnected)
+    /// or
+    /// [`networking::Error::RequestTimeout`](bonsaidb_core::networking::Error::RequestTimeout).
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn ping(&self) -> Result<Duration, bonsaidb_core::Error> {
+        let start = std::time::Instant::now();
+        self.send_api_request(&bonsaidb_core::networking::Ping)
+            .await?;
+        Ok(start.elapsed())
+    }
+
+    /// Returns the status of the server, including the version of BonsaiDb it
+    /// was built with and how long it has been running.
+    ///
+    /// Requesting the status only requires
+    /// [`ServerAction::Connect`](bonsaidb_core::permissions::bonsai::ServerAction::Connect).
+    /// The database and connection counts are only included if this client is
+    /// permitted
+    /// [`ServerAction::GetServerStatus`](bonsaidb_core::permissions::bonsai::ServerAction::GetServerStatus).
+    pub async fn server_status(&self) -> Result<ServerStatus, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&GetServerStatus).await?)
+    }
+
+    /// Subscribes to the [`ServerEvent`](bonsaidb_core::networking::ServerEvent)s
+    /// the server publishes, such as clients connecting, authentication
+    /// failures, and databases being created or deleted.
+    ///
+    /// The events are published to
+    /// [`SERVER_EVENTS_TOPIC`](bonsaidb_core::networking::SERVER_EVENTS_TOPIC)
+    /// and
+    /// [`CLIENT_EVENTS_TOPIC`](bonsaidb_core::networking::CLIENT_EVENTS_TOPIC)
+    /// on the admin database. This client must be permitted to create a
+    /// subscriber on the admin database and to subscribe to both topics.
+    /// Events published before this function returns are not received.
+    pub async fn subscribe_to_server_events(&self) -> Result<ServerEvents, bonsaidb_core::Error> {
+        let subscriber = self.admin().await.create_subscriber().await?;
+        subscriber.subscribe_to(&SERVER_EVENTS_TOPIC).await?;
+        subscriber.subscribe_to(&CLIENT_EVENTS_TOPIC).await?;
+        Ok(ServerEvents::new(subscriber))
+    }
+
     /// Returns the current effective permissions for the client. Returns None
     /// if unauthenticated.
     #[must_use]
@@ -587,9 +960,40 @@ impl AsyncClient {
         effective_permissions.clone()
     }
 
-    pub(crate) fn register_subscriber(&self, id: u64, sender: flume::Sender<Message>) {
+    /// Registers the subscriber with `id`, returning the receiver of its
+    /// messages. If the subscriber was created with a `limit`, the server is
+    /// notified each time half of the buffer's capacity has been received,
+    /// allowing it to send more messages.
+    pub(crate) fn register_subscriber(
+        &self,
+        database: &str,
+        id: u64,
+        limit: Option<BufferLimit>,
+    ) -> Receiver {
+        let (sender, receiver) = pubsub::channel(None, false);
         let mut subscribers = self.data.subscribers.lock();
         subscribers.insert(id, sender);
+        drop(subscribers);
+
+        let Some(limit) = limit else {
+            return receiver;
+        };
+        let client = self.clone();
+        let database = database.to_string();
+        let threshold = (limit.capacity / 2).max(1) as u64;
+        let received = AtomicU64::new(0);
+        receiver.on_receive(move || {
+            if received.fetch_add(1, Ordering::Relaxed) + 1 >= threshold {
+                let count = received.swap(0, Ordering::Relaxed);
+                if count > 0 {
+                    drop(client.invoke_blocking_api_request(&AcknowledgeMessages {
+                        database: database.clone(),
+                        subscriber_id: id,
+                        count,
+                    }));
+                }
+            }
+        })
     }
 
     pub(crate) async fn unregister_subscriber_async(&self, database: String, id: u64) {
@@ -634,23 +1038,230 @@ impl AsyncClient {
         ))
     }
 
+    /// Returns the name of the database configured using
+    /// [`Builder::with_default_database()`], if any.
+    #[must_use]
+    pub fn default_database_name(&self) -> Option<&str> {
+        self.data.default_database.as_deref()
+    }
+
+    /// Returns the interval keepalives are sent at, as configured using
+    /// [`Builder::with_keepalive()`]. Returns `None` if keepalives are
+    /// disabled.
+    #[cfg(not(target_arch = "wasm32"))]
+    #[must_use]
+    pub fn keepalive(&self) -> Option<Duration> {
+        self.data.keepalive
+    }
+
+    /// Returns the database configured using
+    /// [`Builder::with_default_database()`], using the schema `DB`.
+    ///
+    /// This is equivalent to calling
+    /// [`AsyncStorageConnection::database()`] with the default database's
+    /// name. If no default database was configured,
+    /// [`Error::NoDefaultDatabase`] is returned.
+    pub fn default_database<DB: bonsaidb_core::schema::Schema>(
+        &self,
+    ) -> Result<AsyncRemoteDatabase, bonsaidb_core::Error> {
+        let name = self
+            .default_database_name()
+            .ok_or_else(|| bonsaidb_core::Error::from(Error::NoDefaultDatabase))?;
+        self.remote_database::<DB>(name)
+    }
+
+    /// Returns the session token the server issued when this client
+    /// authenticated, if any.
+    ///
+    /// The client does not store credentials, so its session is lost when it
+    /// reconnects. The token can be used to authenticate again using
+    /// [`Authentication::session_token()`](bonsaidb_core::connection::Authentication::session_token)
+    /// until it expires or is revoked, including after reconnecting.
+    #[must_use]
+    pub fn session_token(&self) -> Option<&SensitiveString> {
+        self.session.session_token.as_ref()
+    }
+
     fn session_is_current(&self) -> bool {
         self.session.session.id.is_none()
             || self.data.connection_counter.load(Ordering::SeqCst) == self.session.connection_id
     }
 
+    /// Returns the id of the session requests are currently made with.
+    fn session_id(&self) -> Option<SessionId> {
+        match &self.refreshable {
+            Some(refreshable) => refreshable.current.lock().session.id,
+            None => self.session.session.id,
+        }
+    }
+
+    /// Returns a clone of this client that makes requests without a session.
+    fn unauthenticated(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            session: ClientSession::default(),
+            refreshable: None,
+            request_timeout: self.request_timeout,
+        }
+    }
+
+    fn credential_provider(&self) -> Result<&CredentialProvider, bonsaidb_core::Error> {
+        self.data
+            .credential_provider
+            .as_ref()
+            .ok_or_else(|| bonsaidb_core::Error::from(Error::NoCredentialProvider))
+    }
+
+    /// Authenticates using the credentials returned by the credential provider
+    /// installed with
+    /// [`Builder::with_credential_provider()`](crate::Builder::with_credential_provider).
+    ///
+    /// Sessions only exist for the lifetime of the connection they were
+    /// authenticated on. When the server reports that the session of the
+    /// returned client has expired, such as after the client reconnects, the
+    /// credential provider is invoked again and the failed request is retried
+    /// using the new session. If multiple requests fail at the same time, the
+    /// credential provider is only invoked once.
+    ///
+    /// [`HasSession::session()`] and
+    /// [`session_token()`](Self::session_token) continue to return the
+    /// session from the initial authentication.
+    pub async fn authenticate_with_retry(&self) -> Result<Self, bonsaidb_core::Error> {
+        let authentication = self.credential_provider()?.credentials().await?;
+        let session = self
+            .unauthenticated()
+            .authenticated_session(authentication)
+            .await?;
+        Ok(self.with_refreshable_session(session))
+    }
+
+    fn with_refreshable_session(&self, session: ClientSession) -> Self {
+        Self {
+            data: self.data.clone(),
+            session: session.clone(),
+            refreshable: Some(Arc::new(RefreshableSession {
+                current: Mutex::new(session),
+                refreshing: async_lock::Mutex::new(()),
+            })),
+            request_timeout: self.request_timeout,
+        }
+    }
+
+    async fn authenticated_session(
+        &self,
+        authentication: Authentication,
+    ) -> Result<ClientSession, bonsaidb_core::Error> {
+        let authenticated = self
+            .send_api_request_once(
+                self.session_id(),
+                &bonsaidb_core::networking::Authenticate { authentication },
+            )
+            .await?;
+        Ok(ClientSession {
+            session: Arc::new(authenticated.session),
+            connection_id: self.data.connection_counter.load(Ordering::SeqCst),
+            session_token: authenticated.session_token,
+        })
+    }
+
+    /// Authenticates again using the credential provider, unless another
+    /// request has already replaced the `expired` session.
+    async fn refresh_session(
+        &self,
+        refreshable: &RefreshableSession,
+        expired: Option<SessionId>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let _refreshing = refreshable.refreshing.lock().await;
+        if refreshable.current.lock().session.id != expired {
+            return Ok(());
+        }
+
+        let authentication = self.credential_provider()?.credentials().await?;
+        let session = self
+            .unauthenticated()
+            .authenticated_session(authentication)
+            .await?;
+        *refreshable.current.lock() = session;
+        Ok(())
+    }
+
+    /// The blocking equivalent of [`Self::authenticated_session()`].
+    #[cfg(not(target_arch = "wasm32"))]
+    fn authenticated_session_blocking(
+        &self,
+        authentication: Authentication,
+    ) -> Result<ClientSession, bonsaidb_core::Error> {
+        let authenticated = self.send_blocking_api_request_once(
+            self.session_id(),
+            &bonsaidb_core::networking::Authenticate { authentication },
+        )?;
+        Ok(ClientSession {
+            session: Arc::new(authenticated.session),
+            connection_id: self.data.connection_counter.load(Ordering::SeqCst),
+            session_token: authenticated.session_token,
+        })
+    }
+
+    /// The blocking equivalent of [`Self::refresh_session()`]. The credential
+    /// provider's future is executed on the calling thread.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn refresh_session_blocking(
+        &self,
+        refreshable: &RefreshableSession,
+        expired: Option<SessionId>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let _refreshing = futures::executor::block_on(refreshable.refreshing.lock());
+        if refreshable.current.lock().session.id != expired {
+            return Ok(());
+        }
+
+        let authentication =
+            futures::executor::block_on(self.credential_provider()?.credentials())?;
+        let session = self
+            .unauthenticated()
+            .authenticated_session_blocking(authentication)?;
+        *refreshable.current.lock() = session;
+        Ok(())
+    }
+
     /// Sets this instance's request timeout.
     ///
     /// Each client has its own timeout. When cloning a client, this timeout
     /// setting will be copied to the clone.
+    ///
+    /// When a request times out, the client asks the server to cancel it and
+    /// returns
+    /// [`networking::Error::RequestTimeout`](bonsaidb_core::networking::Error::RequestTimeout).
     pub fn set_request_timeout(&mut self, timeout: impl Into<Duration>) {
         self.request_timeout = timeout.into();
     }
+
+    /// Returns a clone of this client that uses `timeout` for its requests.
+    /// This can be used to override the timeout for individual calls:
+    ///
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # use bonsaidb_client::AsyncClient;
+    /// # use bonsaidb_core::connection::AsyncStorageConnection;
+    /// # async fn test_fn(client: AsyncClient) -> anyhow::Result<()> {
+    /// let databases = client
+    ///     .with_request_timeout(Duration::from_secs(5))
+    ///     .list_databases()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn with_request_timeout(&self, timeout: impl Into<Duration>) -> Self {
+        let mut client = self.clone();
+        client.set_request_timeout(timeout);
+        client
+    }
 }
 
 impl HasSession for AsyncClient {
     fn session(&self) -> Option<&Session> {
-        self.session_is_current().then_some(&self.session.session)
+        (self.refreshable.is_some() || self.session_is_current()).then_some(&self.session.session)
     }
 }
 
@@ -663,21 +1274,24 @@ impl AsyncStorageConnection for AsyncClient {
         self.remote_database::<Admin>(ADMIN_DATABASE_NAME).unwrap()
     }
 
-    async fn create_database_with_schema(
+    async fn create_database_with_schema_and_permissions(
         &self,
         name: &str,
         schema: SchemaName,
         only_if_needed: bool,
-    ) -> Result<(), bonsaidb_core::Error> {
+        permissions: Vec<Statement>,
+    ) -> Result<DatabaseCreation, bonsaidb_core::Error> {
         self.send_api_request(&CreateDatabase {
             database: Database {
                 name: name.to_string(),
                 schema,
+                created_at: None,
             },
             only_if_needed,
+            permissions,
         })
-        .await?;
-        Ok(())
+        .await
+        .map_err(bonsaidb_core::Error::from)
     }
 
     async fn database<DB: Schema>(
@@ -703,6 +1317,14 @@ impl AsyncStorageConnection for AsyncClient {
         Ok(self.send_api_request(&ListAvailableSchemas).await?)
     }
 
+    async fn database_stats(&self, name: &str) -> Result<DatabaseStats, bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&GetDatabaseStats {
+                name: name.to_string(),
+            })
+            .await?)
+    }
+
     async fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         Ok(self
             .send_api_request(&CreateUser {
@@ -722,6 +1344,10 @@ impl AsyncStorageConnection for AsyncClient {
             .await?)
     }
 
+    async fn list_users(&self) -> Result<Vec<UserInfo>, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&ListUsers).await?)
+    }
+
     #[cfg(feature = "password-hashing")]
     async fn set_user_password<'user, U: Nameable<'user, u64> + Send + Sync>(
         &self,
@@ -736,20 +1362,14 @@ impl AsyncStorageConnection for AsyncClient {
             .await?)
     }
 
-    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
     async fn authenticate(
         &self,
         authentication: bonsaidb_core::connection::Authentication,
     ) -> Result<Self::Authenticated, bonsaidb_core::Error> {
-        let session = self
-            .send_api_request(&bonsaidb_core::networking::Authenticate { authentication })
-            .await?;
         Ok(Self {
             data: self.data.clone(),
-            session: ClientSession {
-                session: Arc::new(session),
-                connection_id: self.data.connection_counter.load(Ordering::SeqCst),
-            },
+            session: self.authenticated_session(authentication).await?,
+            refreshable: None,
             request_timeout: self.request_timeout,
         })
     }
@@ -766,7 +1386,9 @@ impl AsyncStorageConnection for AsyncClient {
             session: ClientSession {
                 session: Arc::new(session),
                 connection_id: self.data.connection_counter.load(Ordering::SeqCst),
+                session_token: None,
             },
+            refreshable: None,
             request_timeout: self.request_timeout,
         })
     }
@@ -809,6 +1431,17 @@ impl AsyncStorageConnection for AsyncClient {
         Ok(())
     }
 
+    async fn list_user_groups<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<PermissionGroupInfo>, bonsaidb_core::Error> {
+        Ok(self
+            .send_api_request(&ListUserGroups {
+                user: user.name()?.into_owned(),
+            })
+            .await?)
+    }
+
     async fn add_role_to_user<
         'user,
         'group,
@@ -856,6 +1489,22 @@ type PendingRequestResponder = Sender<Result<Bytes, Error>>;
 pub struct PendingRequest {
     request: Payload,
     responder: PendingRequestResponder,
+    #[cfg(not(target_arch = "wasm32"))]
+    fallback: Option<fallback::Route>,
+}
+
+impl PendingRequest {
+    /// Fails this request because the connection it was going to be sent
+    /// over couldn't be established. If the client has
+    /// [fallbacks](crate::Builder::with_fallback), the request is retried
+    /// using the next transport instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn connect_failed(mut self, err: Error) {
+        match self.fallback.take() {
+            Some(route) => route.retry(self, err),
+            None => drop(self.responder.send(Err(err))),
+        }
+    }
 }
 
 async fn process_response_payload(
@@ -971,6 +1620,56 @@ impl<Api: api::Api> AnyApiCallback for ApiCallback<Api> {
 pub struct ClientSession {
     session: Arc<Session>,
     connection_id: u32,
+    session_token: Option<SensitiveString>,
+}
+
+/// The session shared by the clones of a client returned from
+/// [`AsyncClient::authenticate_with_retry()`].
+#[derive(Debug)]
+pub struct RefreshableSession {
+    current: Mutex<ClientSession>,
+    /// Held while authenticating again, ensuring only one request refreshes
+    /// an expired session.
+    refreshing: async_lock::Mutex<()>,
+}
+
+/// Returns true if `result` failed because the session the request was made
+/// with has expired.
+fn session_expired<T, E>(result: &Result<T, ApiError<E>>) -> bool {
+    matches!(
+        result,
+        Err(ApiError::Client(Error::Core(
+            bonsaidb_core::Error::SessionExpired
+        )))
+    )
+}
+
+/// Obtains the credentials used by clients returned from
+/// [`AsyncClient::authenticate_with_retry()`].
+#[derive(Clone)]
+pub struct CredentialProvider(
+    Arc<dyn Fn() -> BoxFuture<'static, Result<Authentication, bonsaidb_core::Error>> + Send + Sync>,
+);
+
+impl CredentialProvider {
+    pub fn new<
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Authentication, bonsaidb_core::Error>> + Send + 'static,
+    >(
+        provider: F,
+    ) -> Self {
+        Self(Arc::new(move || provider().boxed()))
+    }
+
+    fn credentials(&self) -> BoxFuture<'static, Result<Authentication, bonsaidb_core::Error>> {
+        (self.0)()
+    }
+}
+
+impl Debug for CredentialProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CredentialProvider").finish_non_exhaustive()
+    }
 }
 
 async fn disconnect_pending_requests(
@@ -987,10 +1686,141 @@ async fn disconnect_pending_requests(
     }
 }
 
+/// Returns the worker that connects to `server` using the transport its url's
+/// scheme specifies, and sends the requests received from `request_receiver`
+/// over that connection.
+#[cfg(not(target_arch = "wasm32"))]
+fn transport_worker(
+    server: ConnectionInfo,
+    protocol_versions: &'static [&'static str],
+    certificates: &ServerCertificates,
+    request_receiver: flume::Receiver<PendingRequest>,
+    custom_apis: &Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
+    connection_counter: &Arc<AtomicU32>,
+) -> Result<BoxFuture<'static, Result<(), Error>>, Error> {
+    match server.url.scheme() {
+        "bonsaidb" => Ok(quic_worker::reconnecting_client_loop(
+            server,
+            protocol_versions,
+            certificates.clone(),
+            request_receiver,
+            custom_apis.clone(),
+            connection_counter.clone(),
+        )
+        .boxed()),
+        #[cfg(feature = "websockets")]
+        "wss" | "ws" => Ok(tungstenite_worker::reconnecting_client_loop(
+            server,
+            protocol_versions,
+            certificates.authorities.clone(),
+            request_receiver,
+            custom_apis.clone(),
+            connection_counter.clone(),
+        )
+        .boxed()),
+        #[cfg(all(feature = "websockets", unix))]
+        "unix" => Ok(tungstenite_worker::reconnecting_client_loop(
+            server,
+            protocol_versions,
+            Vec::new(),
+            request_receiver,
+            custom_apis.clone(),
+            connection_counter.clone(),
+        )
+        .boxed()),
+        #[cfg(feature = "http")]
+        "http" | "https" => Ok(http_worker::reconnecting_client_loop(
+            server,
+            protocol_versions,
+            certificates.authorities.clone(),
+            request_receiver,
+            custom_apis.clone(),
+            connection_counter.clone(),
+        )
+        .boxed()),
+        #[cfg(not(unix))]
+        "unix" => Err(Error::InvalidUrl(String::from(
+            "unix sockets are not supported on this platform",
+        ))),
+        other => Err(Error::InvalidUrl(format!("unsupported scheme {other}"))),
+    }
+}
+
+/// Pings the server whenever `interval` elapses without a request being sent,
+/// until the client is dropped. Nothing is sent until the client sends its
+/// first request, which avoids connecting clients that are never used.
+#[cfg(not(target_arch = "wasm32"))]
+async fn send_keepalives(
+    data: std::sync::Weak<Data>,
+    interval: Duration,
+    request_timeout: Duration,
+) -> Result<(), Error> {
+    loop {
+        let Some(data) = data.upgrade() else {
+            return Ok(());
+        };
+        let last_request_at = *data.last_request_at.lock();
+        match last_request_at {
+            Some(last_request_at) if last_request_at.elapsed() >= interval => {
+                let client = AsyncClient {
+                    data,
+                    session: ClientSession::default(),
+                    refreshable: None,
+                    request_timeout,
+                };
+                // Keepalives are always sent to the primary server, bypassing
+                // any replicas.
+                if let Err(err) = client
+                    .send_api_request_once(None, &bonsaidb_core::networking::Ping)
+                    .await
+                {
+                    log::warn!("Error sending keepalive: {err}");
+                }
+            }
+            last_request_at => {
+                drop(data);
+                let wait =
+                    last_request_at.map_or(interval, |at| interval.saturating_sub(at.elapsed()));
+                tokio::time::sleep(wait).await;
+            }
+        }
+    }
+}
+
 struct ConnectionInfo {
     pub url: Url,
     pub subscribers: SubscriberMap,
     #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
     pub connect_timeout: Duration,
     pub request_timeout: Duration,
+    #[cfg_attr(
+        any(target_arch = "wasm32", not(feature = "compression")),
+        allow(dead_code)
+    )]
+    pub compression: Option<TransportCompression>,
+    pub interceptor: Option<Arc<dyn RequestInterceptor>>,
+    pub credential_provider: Option<CredentialProvider>,
+    pub replicas: Option<Arc<Replicas>>,
+    pub default_database: Option<String>,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub keepalive: Option<Duration>,
+}
+
+impl ConnectionInfo {
+    /// Returns these settings for connecting to `url` instead.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn with_url(&self, url: Url) -> Self {
+        Self {
+            url,
+            subscribers: self.subscribers.clone(),
+            connect_timeout: self.connect_timeout,
+            request_timeout: self.request_timeout,
+            compression: self.compression,
+            interceptor: self.interceptor.clone(),
+            credential_provider: self.credential_provider.clone(),
+            replicas: self.replicas.clone(),
+            default_database: self.default_database.clone(),
+            keepalive: self.keepalive,
+        }
+    }
 }
@@ -0,0 +1,170 @@
+use std::marker::PhantomData;
+
+use bonsaidb_core::api;
+use bonsaidb_core::arc_bytes::serde::Bytes;
+use bonsaidb_core::networking::{BatchedRequest, ExecuteBatch};
+
+use crate::{ApiError, AsyncClient, Error};
+
+/// A set of api requests that are sent to the server together, requiring only
+/// a single round trip.
+///
+/// Requests are added using [`push()`](Self::push), which returns a
+/// [`BatchEntry`] that can be used to retrieve the request's response from
+/// the [`BatchResults`] once the batch has been sent. Any api can be included
+/// in a batch, allowing requests for multiple databases, collections, and
+/// key-value stores to be combined.
+///
+/// Unlike a [`Transaction`](bonsaidb_core::transaction::Transaction), a batch
+/// is not atomic. The server executes each request in the order it was pushed,
+/// and each request succeeds or fails independently of the others.
+///
+/// ```rust
+/// # use bonsaidb_client::AsyncClient;
+/// # use bonsaidb_core::document::DocumentId;
+/// # use bonsaidb_core::keyvalue::{Command, KeyOperation};
+/// # use bonsaidb_core::networking::{ExecuteKeyOperation, Get};
+/// # use bonsaidb_core::schema::{CollectionName, Qualified};
+/// # async fn test_fn(client: AsyncClient) -> anyhow::Result<()> {
+/// let mut batch = client.batch();
+/// let widget = batch.push(&Get {
+///     database: String::from("dashboard"),
+///     collection: CollectionName::new("example", "widgets"),
+///     id: DocumentId::from_u64(1),
+/// })?;
+/// let visits = batch.push(&ExecuteKeyOperation {
+///     database: String::from("metrics"),
+///     op: KeyOperation {
+///         namespace: None,
+///         key: String::from("visits"),
+///         command: Command::Get { delete: false },
+///     },
+/// })?;
+/// let results = batch.send().await?;
+/// let widget = results.get(widget)?;
+/// let visits = results.get(visits)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use]
+pub struct Batch {
+    client: AsyncClient,
+    requests: Vec<BatchedRequest>,
+}
+
+impl Batch {
+    pub(crate) const fn new(client: AsyncClient) -> Self {
+        Self {
+            client,
+            requests: Vec::new(),
+        }
+    }
+
+    /// Adds `request` to this batch. The returned entry can be passed to
+    /// [`BatchResults::get()`] to retrieve the response to this request.
+    pub fn push<Api: api::Api>(&mut self, request: &Api) -> Result<BatchEntry<Api>, Error> {
+        let index = self.requests.len();
+        self.requests.push(BatchedRequest {
+            name: Api::name(),
+            value: Bytes::from(pot::to_vec(request)?),
+        });
+        Ok(BatchEntry {
+            index,
+            _api: PhantomData,
+        })
+    }
+
+    /// Returns the number of requests in this batch.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    /// Returns true if this batch contains no requests.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Sends all requests in this batch to the server and waits for all of
+    /// their responses.
+    ///
+    /// An error is only returned if the batch itself could not be executed.
+    /// Errors from individual requests are returned from
+    /// [`BatchResults::get()`].
+    pub async fn send(self) -> Result<BatchResults, bonsaidb_core::Error> {
+        let results = self
+            .client
+            .send_api_request(&ExecuteBatch {
+                requests: self.requests,
+            })
+            .await?;
+        Ok(BatchResults { results })
+    }
+
+    /// Sends all requests in this batch to the server and blocks the current
+    /// thread until all of their responses have been received.
+    ///
+    /// See [`send()`](Self::send) for more information.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn send_blocking(self) -> Result<BatchResults, bonsaidb_core::Error> {
+        let results = self.client.send_blocking_api_request(&ExecuteBatch {
+            requests: self.requests,
+        })?;
+        Ok(BatchResults { results })
+    }
+}
+
+/// A request that was added to a [`Batch`].
+#[derive(Debug)]
+pub struct BatchEntry<Api> {
+    index: usize,
+    _api: PhantomData<fn() -> Api>,
+}
+
+impl<Api> Clone for BatchEntry<Api> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Api> Copy for BatchEntry<Api> {}
+
+/// The responses to the requests of a [`Batch`].
+#[derive(Debug)]
+pub struct BatchResults {
+    results: Vec<Result<Bytes, bonsaidb_core::Error>>,
+}
+
+impl BatchResults {
+    /// Returns the response to the request that `entry` was returned for.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `entry` was returned from a different [`Batch`] containing
+    /// more requests than the batch these results are for.
+    pub fn get<Api: api::Api>(
+        &self,
+        entry: BatchEntry<Api>,
+    ) -> Result<Api::Response, ApiError<Api::Error>> {
+        let response = self.results[entry.index]
+            .as_ref()
+            .map_err(|err| Error::from(err.clone()))?;
+        let response =
+            pot::from_slice::<Result<Api::Response, Api::Error>>(response).map_err(Error::from)?;
+        response.map_err(ApiError::Api)
+    }
+
+    /// Returns the number of responses contained.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Returns true if there are no responses contained.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
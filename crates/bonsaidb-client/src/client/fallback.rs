@@ -0,0 +1,89 @@
+use flume::{Receiver, SendError, Sender};
+use url::Url;
+
+use super::PendingRequest;
+use crate::Error;
+
+/// A transport that requests can be sent over.
+pub struct Transport {
+    pub url: Url,
+    pub requests: Sender<PendingRequest>,
+}
+
+/// Where a request is returned to when the connection it was going to be sent
+/// over can't be established.
+pub struct Route {
+    failures: Sender<ConnectFailure>,
+    transport: usize,
+    attempts: usize,
+}
+
+impl Route {
+    /// Returns `request` to the fallback loop to be retried on the next
+    /// transport.
+    pub fn retry(self, request: PendingRequest, error: Error) {
+        if let Err(SendError(failure)) = self.failures.send(ConnectFailure {
+            request,
+            error,
+            transport: self.transport,
+            attempts: self.attempts,
+        }) {
+            drop(failure.request.responder.send(Err(failure.error)));
+        }
+    }
+}
+
+struct ConnectFailure {
+    request: PendingRequest,
+    error: Error,
+    transport: usize,
+    attempts: usize,
+}
+
+/// Sends each request received from `request_receiver` to the active
+/// transport. When a transport can't connect, the next transport becomes
+/// active and the request is retried on it. Once a request has failed to
+/// connect on every transport, its error is returned and the first transport
+/// becomes active again.
+pub(super) async fn fallback_client_loop(
+    request_receiver: Receiver<PendingRequest>,
+    transports: Vec<Transport>,
+) -> Result<(), Error> {
+    let (failure_sender, failure_receiver) = flume::unbounded();
+    let mut active = 0;
+    loop {
+        let (mut request, attempts) = tokio::select! {
+            request = request_receiver.recv_async() => match request {
+                Ok(request) => (request, 0),
+                Err(_) => break,
+            },
+            Ok(failure) = failure_receiver.recv_async() => {
+                let ConnectFailure { request, error, transport, attempts } = failure;
+                log::warn!(
+                    "error connecting to {}: {error}",
+                    transports[transport].url
+                );
+                if transport == active {
+                    active = (active + 1) % transports.len();
+                }
+                if attempts + 1 >= transports.len() {
+                    active = 0;
+                    drop(request.responder.send(Err(error)));
+                    continue;
+                }
+                (request, attempts + 1)
+            }
+        };
+
+        request.fallback = Some(Route {
+            failures: failure_sender.clone(),
+            transport: active,
+            attempts,
+        });
+        if let Err(SendError(request)) = transports[active].requests.send(request) {
+            drop(request.responder.send(Err(Error::disconnected())));
+        }
+    }
+
+    Ok(())
+}
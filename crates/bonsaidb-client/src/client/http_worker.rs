@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bonsaidb_core::api::ApiName;
+use bonsaidb_core::networking::{
+    self, read_http_frames, write_http_frame, HTTP_CONNECTION_HEADER, HTTP_PROTOCOL_HEADER,
+};
+use bonsaidb_utils::fast_async_lock;
+use fabruic::Certificate;
+use flume::Receiver;
+use futures::{stream, Stream, TryStreamExt};
+use hyper::client::HttpConnector;
+use hyper::{Body, Method, Request, StatusCode};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use tokio_tungstenite::tungstenite::Message;
+use url::Url;
+
+use super::tungstenite_worker::{
+    encode_payload, offered_protocols, request_sender, requested_compression, response_processor,
+    root_certificates, server_protocol_versions,
+};
+use super::PendingRequest;
+use crate::client::{
+    disconnect_pending_requests, AnyApiCallback, ConnectionInfo, OutstandingRequestMapHandle,
+};
+use crate::Error;
+
+/// How long to wait for a response to a poll before considering the
+/// connection lost. Servers respond to polls within 20 seconds, even if no
+/// messages are available.
+const POLL_TIMEOUT: Duration = Duration::from_secs(60);
+
+type HttpClient = hyper::Client<HttpsConnector<HttpConnector>>;
+
+pub(super) async fn reconnecting_client_loop(
+    server: ConnectionInfo,
+    protocol_versions: &[&str],
+    certificate_authorities: Vec<Certificate>,
+    request_receiver: Receiver<PendingRequest>,
+    custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
+    connection_counter: Arc<AtomicU32>,
+) -> Result<(), Error> {
+    let client = http_client(&certificate_authorities);
+    let mut pending_error = None;
+    while let Ok(request) = {
+        server.subscribers.clear();
+        request_receiver.recv_async().await
+    } {
+        if let Some(pending_error) = pending_error.take() {
+            drop(request.responder.send(Err(pending_error)));
+            continue;
+        }
+
+        connection_counter.fetch_add(1, Ordering::SeqCst);
+        let requested_compression = requested_compression(&server);
+        let protocols = offered_protocols(protocol_versions, requested_compression.as_ref());
+        let (connection, negotiated_protocol) = match tokio::time::timeout(
+            server.connect_timeout,
+            connect(&client, &server.url, protocols, protocol_versions),
+        )
+        .await
+        {
+            Ok(Ok(result)) => result,
+            Ok(Err(err)) => {
+                request.connect_failed(err);
+                continue;
+            }
+            Err(_) => {
+                request.connect_failed(Error::connect_timeout());
+                continue;
+            }
+        };
+        let compression = requested_compression.filter(|compression| {
+            protocol_versions.iter().any(|version| {
+                negotiated_protocol == compression.compression.protocol_name(version)
+            })
+        });
+
+        let (sender, outgoing) = flume::unbounded();
+        let outstanding_requests = OutstandingRequestMapHandle::default();
+        {
+            let mut outstanding_requests = fast_async_lock!(outstanding_requests);
+            sender.send(Message::Binary(encode_payload(
+                &request.request,
+                compression.as_ref(),
+            )?))?;
+            outstanding_requests.insert(
+                request.request.id.expect("all requests must have ids"),
+                request,
+            );
+        }
+
+        if let Err(err) = tokio::try_join!(
+            request_sender(
+                &request_receiver,
+                sender.into_sink(),
+                outstanding_requests.clone(),
+                compression
+            ),
+            connection.send_messages(outgoing),
+            response_processor(
+                connection.messages(),
+                outstanding_requests.clone(),
+                &custom_apis,
+                compression.is_some()
+            )
+        ) {
+            // Our connection was lost, clear the outstanding requests before returning.
+            log::error!("Error on HTTP connection {:?}", err);
+            pending_error = Some(err);
+            disconnect_pending_requests(&outstanding_requests, &mut pending_error).await;
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the client used to make HTTP requests, which trusts the system's
+/// root certificates and `certificate_authorities` for `https` connections.
+fn http_client(certificate_authorities: &[Certificate]) -> HttpClient {
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_certificates(certificate_authorities))
+        .with_no_client_auth();
+    hyper::Client::builder().build(
+        HttpsConnectorBuilder::new()
+            .with_tls_config(config)
+            .https_or_http()
+            .enable_http1()
+            .build(),
+    )
+}
+
+/// Establishes a long-polling connection with the server at `url`, returning
+/// the connection and the protocol the server chose.
+async fn connect(
+    client: &HttpClient,
+    url: &Url,
+    protocols: String,
+    protocol_versions: &[&str],
+) -> Result<(HttpConnection, String), Error> {
+    let request = Request::post(url.as_str())
+        .header(HTTP_PROTOCOL_HEADER, protocols)
+        .body(Body::empty())
+        .map_err(http_error)?;
+    let response = client.request(request).await.map_err(http_error)?;
+    match response.status() {
+        StatusCode::OK => {}
+        StatusCode::NOT_ACCEPTABLE => {
+            return Err(Error::incompatible_protocol_version(
+                protocol_versions,
+                server_protocol_versions(response.headers()),
+            ))
+        }
+        StatusCode::SERVICE_UNAVAILABLE => {
+            return Err(Error::Core(bonsaidb_core::Error::Networking(
+                networking::Error::ConnectionLimitReached,
+            )))
+        }
+        status => return Err(http_error(format!("unexpected response status {status}"))),
+    }
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    };
+    let (Some(id), Some(protocol)) = (header(HTTP_CONNECTION_HEADER), header(HTTP_PROTOCOL_HEADER))
+    else {
+        return Err(http_error("server did not accept the connection"));
+    };
+
+    Ok((
+        HttpConnection {
+            client: client.clone(),
+            url: url.clone(),
+            id,
+        },
+        protocol,
+    ))
+}
+
+struct HttpConnection {
+    client: HttpClient,
+    url: Url,
+    id: String,
+}
+
+impl HttpConnection {
+    fn request(&self, method: Method, body: Body) -> Result<Request<Body>, Error> {
+        Request::builder()
+            .method(method)
+            .uri(self.url.as_str())
+            .header(HTTP_CONNECTION_HEADER, &self.id)
+            .body(body)
+            .map_err(http_error)
+    }
+
+    /// Sends the messages received from `outgoing` to the server. Messages
+    /// queued while a request is in flight are sent together in the next
+    /// request.
+    async fn send_messages(&self, outgoing: Receiver<Message>) -> Result<(), Error> {
+        while let Ok(message) = outgoing.recv_async().await {
+            let mut body = Vec::new();
+            for message in std::iter::once(message).chain(outgoing.try_iter()) {
+                if let Message::Binary(frame) = message {
+                    write_http_frame(&mut body, &frame);
+                }
+            }
+
+            let response = self
+                .client
+                .request(self.request(Method::POST, Body::from(body))?)
+                .await
+                .map_err(http_error)?;
+            if !response.status().is_success() {
+                return Err(Error::disconnected());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the messages sent by the server, which are received by
+    /// repeatedly polling the server.
+    fn messages(&self) -> impl Stream<Item = Result<Message, Error>> + '_ {
+        stream::try_unfold(self, |connection| async move {
+            let frames = connection.poll().await?;
+            let messages = frames
+                .into_iter()
+                .map(|frame| Ok::<_, Error>(Message::Binary(frame)));
+            Ok::<_, Error>(Some((stream::iter(messages), connection)))
+        })
+        .try_flatten()
+    }
+
+    /// Waits for the server to send messages. Returns an error if the
+    /// connection was closed by the server.
+    async fn poll(&self) -> Result<Vec<Vec<u8>>, Error> {
+        let response = tokio::time::timeout(
+            POLL_TIMEOUT,
+            self.client
+                .request(self.request(Method::GET, Body::empty())?),
+        )
+        .await
+        .map_err(|_| Error::disconnected())?
+        .map_err(http_error)?;
+        if response.status() != StatusCode::OK {
+            return Err(Error::disconnected());
+        }
+
+        let body = hyper::body::to_bytes(response.into_body())
+            .await
+            .map_err(http_error)?;
+        let frames =
+            read_http_frames(&body).ok_or_else(|| http_error("invalid response from server"))?;
+        Ok(frames.into_iter().map(<[u8]>::to_vec).collect())
+    }
+}
+
+fn http_error(error: impl Display) -> Error {
+    Error::Core(bonsaidb_core::Error::other("http", error))
+}
@@ -4,11 +4,12 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use bonsaidb_core::api::ApiName;
-use bonsaidb_core::networking::Payload;
+use bonsaidb_core::networking::{self, Payload};
 use bonsaidb_utils::fast_async_lock;
 use fabruic::{self, Certificate, Endpoint};
 use flume::Receiver;
 use futures::StreamExt;
+use sha2::{Digest, Sha256};
 use url::Url;
 
 use super::PendingRequest;
@@ -17,13 +18,25 @@ use crate::client::{
 };
 use crate::Error;
 
+/// The certificates used to verify the server's identity.
+#[derive(Clone)]
+pub struct ServerCertificates {
+    /// A certificate that the server's certificate must match exactly.
+    pub pinned: Option<Certificate>,
+    /// The SHA-256 fingerprint the server's certificate must have.
+    pub fingerprint: Option<[u8; 32]>,
+    /// Certificate authorities trusted to issue the server's certificate, in
+    /// addition to the system's root certificates.
+    pub authorities: Vec<Certificate>,
+}
+
 /// This function will establish a connection and try to keep it active. If an
 /// error occurs, any queries that come in while reconnecting will have the
 /// error replayed to them.
 pub(super) async fn reconnecting_client_loop(
     mut server: ConnectionInfo,
-    protocol_version: &'static str,
-    certificate: Option<Certificate>,
+    protocol_versions: &'static [&'static str],
+    certificates: ServerCertificates,
     request_receiver: Receiver<PendingRequest>,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
     connection_counter: Arc<AtomicU32>,
@@ -42,8 +55,8 @@ pub(super) async fn reconnecting_client_loop(
         connection_counter.fetch_add(1, Ordering::SeqCst);
         if let Err((failed_request, Some(err))) = connect_and_process(
             &server.url,
-            protocol_version,
-            certificate.as_ref(),
+            protocol_versions,
+            &certificates,
             request,
             &request_receiver,
             custom_apis.clone(),
@@ -52,7 +65,7 @@ pub(super) async fn reconnecting_client_loop(
         .await
         {
             if let Some(failed_request) = failed_request {
-                drop(failed_request.responder.send(Err(err)));
+                failed_request.connect_failed(err);
             } else {
                 pending_error = Some(err);
             }
@@ -64,21 +77,23 @@ pub(super) async fn reconnecting_client_loop(
 
 async fn connect_and_process(
     url: &Url,
-    protocol_version: &str,
-    certificate: Option<&Certificate>,
+    protocol_versions: &[&str],
+    certificates: &ServerCertificates,
     initial_request: PendingRequest,
     request_receiver: &Receiver<PendingRequest>,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
     connect_timeout: Duration,
 ) -> Result<(), (Option<PendingRequest>, Option<Error>)> {
-    let (_connection, payload_sender, payload_receiver) =
-        match tokio::time::timeout(connect_timeout, connect(url, certificate, protocol_version))
-            .await
-        {
-            Ok(Ok(result)) => result,
-            Ok(Err(err)) => return Err((Some(initial_request), Some(err))),
-            Err(_) => return Err((Some(initial_request), Some(Error::connect_timeout()))),
-        };
+    let (_connection, payload_sender, payload_receiver) = match tokio::time::timeout(
+        connect_timeout,
+        connect(url, certificates, protocol_versions),
+    )
+    .await
+    {
+        Ok(Ok(result)) => result,
+        Ok(Err(err)) => return Err((Some(initial_request), Some(err))),
+        Err(_) => return Err((Some(initial_request), Some(Error::connect_timeout()))),
+    };
 
     let outstanding_requests = OutstandingRequestMapHandle::default();
     let request_processor = tokio::spawn(process(
@@ -154,8 +169,8 @@ pub async fn process(
 
 async fn connect(
     url: &Url,
-    certificate: Option<&Certificate>,
-    protocol_version: &str,
+    certificates: &ServerCertificates,
+    protocol_versions: &[&str],
 ) -> Result<
     (
         fabruic::Connection<()>,
@@ -168,11 +183,21 @@ async fn connect(
     endpoint
         .set_max_idle_timeout(None)
         .map_err(|err| Error::Core(bonsaidb_core::Error::other("quic", err)))?;
-    endpoint.set_protocols([protocol_version.as_bytes().to_vec()]);
+    endpoint.set_protocols(
+        protocol_versions
+            .iter()
+            .map(|version| version.as_bytes().to_vec()),
+    );
+    if !certificates.authorities.is_empty() {
+        fabruic::dangerous::Builder::set_root_certificates(
+            &mut endpoint,
+            certificates.authorities.clone(),
+        );
+    }
     let endpoint = endpoint
         .build()
         .map_err(|err| Error::Core(bonsaidb_core::Error::other("quic", err)))?;
-    let connecting = if let Some(certificate) = certificate {
+    let connecting = if let Some(certificate) = &certificates.pinned {
         endpoint.connect_pinned(url, certificate, None).await?
     } else {
         endpoint.connect(url).await?
@@ -180,12 +205,38 @@ async fn connect(
 
     let connection = connecting.accept::<()>().await.map_err(|err| {
         if matches!(err, fabruic::error::Connecting::ProtocolMismatch) {
-            Error::ProtocolVersionMismatch
+            // QUIC doesn't report which protocols the server supports.
+            Error::incompatible_protocol_version(protocol_versions, Vec::new())
         } else {
             Error::from(err)
         }
     })?;
+    if let Some(expected) = certificates.fingerprint {
+        verify_fingerprint(&connection, expected)?;
+    }
     let (sender, receiver) = connection.open_stream(&()).await?;
 
     Ok((connection, sender, receiver))
 }
+
+/// Verifies that the end-entity certificate presented by the server has the
+/// SHA-256 fingerprint `expected`. The connection is closed when it is
+/// dropped by the caller on failure.
+fn verify_fingerprint(
+    connection: &fabruic::Connection<()>,
+    expected: [u8; 32],
+) -> Result<(), Error> {
+    let received = connection.peer_identity().and_then(|chain| {
+        chain
+            .iter()
+            .next()
+            .map(|certificate| <[u8; 32]>::from(Sha256::digest(certificate.as_ref())))
+    });
+    if received == Some(expected) {
+        Ok(())
+    } else {
+        Err(Error::Network(
+            networking::Error::CertificateFingerprintMismatch { expected, received },
+        ))
+    }
+}
@@ -1,19 +1,23 @@
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use bonsaidb_core::connection::{
-    AccessPolicy, AsyncConnection, AsyncLowLevelConnection, HasSchema, HasSession, Range,
-    SerializedQueryKey, Session, Sort,
+    AccessPolicy, AsyncConnection, AsyncLowLevelConnection, HasSchema, HasSession, QueryPlan,
+    Range, SerializedQueryKey, Session, Sort,
+};
+use bonsaidb_core::document::{
+    DocumentId, Header, HistoricalRevision, MaybeDeleted, OwnedDocument,
 };
-use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::networking::{
-    ApplyTransaction, Compact, CompactCollection, CompactKeyValueStore, Count, DeleteDocs, Get,
-    GetMultiple, LastTransactionId, List, ListExecutedTransactions, ListHeaders, Query,
-    QueryWithDocs, Reduce, ReduceGrouped,
+    ApplyTransaction, Compact, CompactCollection, CompactKeyValueStore, Count, DeleteDocs, Explain,
+    FilterDocuments, Get, GetMultiple, LastTransactionId, List, ListExecutedTransactions,
+    ListHeaders, ListIncludingDeleted, Query, QueryWithDocs, Reduce, ReduceGrouped, Restore,
+    RevisionHistory,
 };
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
-use bonsaidb_core::schema::{self, CollectionName, Schematic, ViewName};
+use bonsaidb_core::schema::{self, CollectionName, Filter, SchemaName, Schematic, ViewName};
 use bonsaidb_core::transaction::{Executed, OperationResult, Transaction};
 
 use crate::AsyncClient;
@@ -31,11 +35,34 @@ pub struct AsyncRemoteDatabase {
     pub(crate) schema: Arc<Schematic>,
 }
 impl AsyncRemoteDatabase {
-    /// Returns the name of the database.
+    /// Returns the name of the database. This is the name that was used to
+    /// open this database, and it never changes for the lifetime of this
+    /// instance.
     #[must_use]
     pub fn name(&self) -> &str {
         self.name.as_ref()
     }
+
+    /// Returns the name of the schema this database was opened with. Generic
+    /// code can compare this against [`Schema::schema_name()`] to verify that
+    /// a database uses the expected schema.
+    ///
+    /// [`Schema::schema_name()`]: bonsaidb_core::schema::Schema::schema_name
+    #[must_use]
+    pub fn schema_name(&self) -> &SchemaName {
+        &self.schema.name
+    }
+
+    /// Returns a clone of this database that uses `timeout` for its requests.
+    /// See [`AsyncClient::with_request_timeout()`].
+    #[must_use]
+    pub fn with_request_timeout(&self, timeout: impl Into<Duration>) -> Self {
+        Self {
+            client: self.client.with_request_timeout(timeout),
+            name: self.name.clone(),
+            schema: self.schema.clone(),
+        }
+    }
 }
 
 impl Deref for AsyncRemoteDatabase {
@@ -175,6 +202,23 @@ impl AsyncLowLevelConnection for AsyncRemoteDatabase {
             .await?)
     }
 
+    async fn filter_from_collection(
+        &self,
+        filter: &Filter,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<OwnedDocument>, bonsaidb_core::Error> {
+        Ok(self
+            .client
+            .send_api_request(&FilterDocuments {
+                database: self.name.to_string(),
+                collection: collection.clone(),
+                filter: filter.clone(),
+                limit,
+            })
+            .await?)
+    }
+
     async fn list_headers_from_collection(
         &self,
         ids: Range<DocumentId>,
@@ -194,6 +238,55 @@ impl AsyncLowLevelConnection for AsyncRemoteDatabase {
             .await?)
     }
 
+    async fn list_including_deleted_from_collection(
+        &self,
+        ids: Range<DocumentId>,
+        order: Sort,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<MaybeDeleted>, bonsaidb_core::Error> {
+        Ok(self
+            .client
+            .send_api_request(&ListIncludingDeleted(List {
+                database: self.name.to_string(),
+                collection: collection.clone(),
+                ids,
+                order,
+                limit,
+            }))
+            .await?)
+    }
+
+    async fn restore_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Header, bonsaidb_core::Error> {
+        Ok(self
+            .client
+            .send_api_request(&Restore {
+                database: self.name.to_string(),
+                collection: collection.clone(),
+                id,
+            })
+            .await?)
+    }
+
+    async fn revision_history_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Vec<HistoricalRevision>, bonsaidb_core::Error> {
+        Ok(self
+            .client
+            .send_api_request(&RevisionHistory {
+                database: self.name.to_string(),
+                collection: collection.clone(),
+                id,
+            })
+            .await?)
+    }
+
     async fn count_from_collection(
         &self,
         ids: Range<DocumentId>,
@@ -314,6 +407,25 @@ impl AsyncLowLevelConnection for AsyncRemoteDatabase {
             })
             .await?)
     }
+
+    async fn explain_by_name(
+        &self,
+        view: &ViewName,
+        key: Option<SerializedQueryKey>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, bonsaidb_core::Error> {
+        Ok(self
+            .client
+            .send_api_request(&Explain {
+                database: self.name.to_string(),
+                view: view.clone(),
+                key,
+                order,
+                limit,
+            })
+            .await?)
+    }
 }
 
 impl HasSchema for AsyncRemoteDatabase {
@@ -3,65 +3,100 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::networking::{
-    CreateSubscriber, Publish, PublishToAll, SubscribeTo, UnsubscribeFrom,
+    CreateSubscriber, Publish, PublishBatch, PublishToAll, SubscribeTo, SubscribeToPrefix,
+    UnsubscribeFrom, UnsubscribeFromPrefix,
 };
-use bonsaidb_core::pubsub::{AsyncPubSub, AsyncSubscriber, Receiver};
+use bonsaidb_core::pubsub::{AsyncPubSub, AsyncSubscriber, BufferLimit, PublishReceipt, Receiver};
 
 use crate::AsyncClient;
 
-#[async_trait]
-impl AsyncPubSub for super::AsyncRemoteDatabase {
-    type Subscriber = AsyncRemoteSubscriber;
-
-    async fn create_subscriber(&self) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+impl super::AsyncRemoteDatabase {
+    async fn create_subscriber_with(
+        &self,
+        limit: Option<BufferLimit>,
+    ) -> Result<AsyncRemoteSubscriber, bonsaidb_core::Error> {
         let subscriber_id = self
             .client
             .send_api_request(&CreateSubscriber {
                 database: self.name.to_string(),
+                limit,
             })
             .await?;
 
-        let (sender, receiver) = flume::unbounded();
-        self.client.register_subscriber(subscriber_id, sender);
         Ok(AsyncRemoteSubscriber {
             client: self.client.clone(),
             database: self.name.clone(),
             id: subscriber_id,
-            receiver: Receiver::new(receiver),
+            receiver: self
+                .client
+                .register_subscriber(&self.name, subscriber_id, limit),
             #[cfg(not(target_arch = "wasm32"))]
             tokio: tokio::runtime::Handle::try_current().ok().map(Arc::new),
         })
     }
+}
+
+#[async_trait]
+impl AsyncPubSub for super::AsyncRemoteDatabase {
+    type Subscriber = AsyncRemoteSubscriber;
+
+    async fn create_subscriber(&self) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+        self.create_subscriber_with(None).await
+    }
+
+    async fn create_subscriber_with_limit(
+        &self,
+        limit: BufferLimit,
+    ) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+        self.create_subscriber_with(Some(limit)).await
+    }
 
     async fn publish_bytes(
         &self,
         topic: Vec<u8>,
         payload: Vec<u8>,
-    ) -> Result<(), bonsaidb_core::Error> {
-        self.client
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        Ok(self
+            .client
             .send_api_request(&Publish {
                 database: self.name.to_string(),
                 topic: Bytes::from(topic),
                 payload: Bytes::from(payload),
             })
-            .await?;
-        Ok(())
+            .await?)
     }
 
     async fn publish_bytes_to_all(
         &self,
         topics: impl IntoIterator<Item = Vec<u8>> + Send + 'async_trait,
         payload: Vec<u8>,
-    ) -> Result<(), bonsaidb_core::Error> {
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
         let topics = topics.into_iter().map(Bytes::from).collect();
-        self.client
+        Ok(self
+            .client
             .send_api_request(&PublishToAll {
                 database: self.name.to_string(),
                 topics,
                 payload: Bytes::from(payload),
             })
-            .await?;
-        Ok(())
+            .await?)
+    }
+
+    async fn publish_batch_bytes(
+        &self,
+        messages: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        let messages = messages
+            .into_iter()
+            .map(|(topic, payload)| (Bytes::from(topic), Bytes::from(payload)))
+            .collect();
+        Ok(self
+            .client
+            .send_api_request(&PublishBatch {
+                database: self.name.to_string(),
+                messages,
+            })
+            .await?)
     }
 }
 
@@ -84,6 +119,22 @@ impl AsyncSubscriber for AsyncRemoteSubscriber {
                 database: self.database.to_string(),
                 subscriber_id: self.id,
                 topic: Bytes::from(topic),
+                replay: false,
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn subscribe_to_bytes_with_replay(
+        &self,
+        topic: Vec<u8>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.client
+            .send_api_request(&SubscribeTo {
+                database: self.database.to_string(),
+                subscriber_id: self.id,
+                topic: Bytes::from(topic),
+                replay: true,
             })
             .await?;
         Ok(())
@@ -100,6 +151,31 @@ impl AsyncSubscriber for AsyncRemoteSubscriber {
         Ok(())
     }
 
+    async fn subscribe_to_prefix_bytes(&self, prefix: Vec<u8>) -> Result<(), bonsaidb_core::Error> {
+        self.client
+            .send_api_request(&SubscribeToPrefix {
+                database: self.database.to_string(),
+                subscriber_id: self.id,
+                prefix: Bytes::from(prefix),
+            })
+            .await?;
+        Ok(())
+    }
+
+    async fn unsubscribe_from_prefix_bytes(
+        &self,
+        prefix: &[u8],
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.client
+            .send_api_request(&UnsubscribeFromPrefix {
+                database: self.database.to_string(),
+                subscriber_id: self.id,
+                prefix: Bytes::from(prefix),
+            })
+            .await?;
+        Ok(())
+    }
+
     fn receiver(&self) -> &Receiver {
         &self.receiver
     }
@@ -0,0 +1,149 @@
+use std::any::TypeId;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use bonsaidb_core::api;
+use bonsaidb_core::networking::{
+    Count, Explain, FilterDocuments, Get, GetMultiple, LastTransactionId, List,
+    ListExecutedTransactions, ListHeaders, ListIncludingDeleted, Query, QueryWithDocs, Reduce,
+    ReduceGrouped, RevisionHistory,
+};
+
+use crate::{ApiError, AsyncClient};
+
+/// Determines which replica a read is sent to first when a client is
+/// connected to [replicas](crate::Builder::with_replicas).
+///
+/// Regardless of the policy, if a replica can't be reached, the read is
+/// retried on each of the remaining replicas before being sent to the
+/// primary server.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum ReplicaRouting {
+    /// Reads are distributed evenly by cycling through the replicas in the
+    /// order they were added.
+    #[default]
+    RoundRobin,
+    /// Reads are sent to the replica with the fewest requests in flight from
+    /// this client. Ties are broken by cycling through the replicas.
+    LeastLoaded,
+}
+
+/// The read replicas of a client.
+#[derive(Debug)]
+pub struct Replicas {
+    replicas: Vec<Replica>,
+    routing: ReplicaRouting,
+    next: AtomicUsize,
+}
+
+impl Replicas {
+    pub fn new(clients: Vec<AsyncClient>, routing: ReplicaRouting) -> Self {
+        Self {
+            replicas: clients
+                .into_iter()
+                .map(|client| Replica {
+                    client,
+                    in_flight: AtomicUsize::new(0),
+                })
+                .collect(),
+            routing,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the replicas in the order they should be tried for the next
+    /// read.
+    fn route(&self) -> Vec<&Replica> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % self.replicas.len();
+        let mut order = self.replicas[start..]
+            .iter()
+            .chain(&self.replicas[..start])
+            .collect::<Vec<_>>();
+        if self.routing == ReplicaRouting::LeastLoaded {
+            // The sort is stable, keeping the round robin order for ties.
+            order.sort_by_key(|replica| replica.in_flight.load(Ordering::Relaxed));
+        }
+        order
+    }
+
+    /// Sends `request` to a replica, returning `None` if no replica could
+    /// be reached.
+    pub async fn send_api_request<Api: api::Api>(
+        &self,
+        request: &Api,
+    ) -> Option<Result<Api::Response, ApiError<Api::Error>>> {
+        for replica in self.route() {
+            let _in_flight = replica.begin_request();
+            match replica.client.send_api_request_once(None, request).await {
+                Err(ApiError::Client(err)) => {
+                    log::warn!("error sending request to replica: {err}");
+                }
+                result => return Some(result),
+            }
+        }
+        None
+    }
+
+    /// The blocking equivalent of [`Self::send_api_request()`].
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn send_blocking_api_request<Api: api::Api>(
+        &self,
+        request: &Api,
+    ) -> Option<Result<Api::Response, ApiError<Api::Error>>> {
+        for replica in self.route() {
+            let _in_flight = replica.begin_request();
+            match replica.client.send_blocking_api_request_once(None, request) {
+                Err(ApiError::Client(err)) => {
+                    log::warn!("error sending request to replica: {err}");
+                }
+                result => return Some(result),
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug)]
+struct Replica {
+    client: AsyncClient,
+    in_flight: AtomicUsize,
+}
+
+impl Replica {
+    fn begin_request(&self) -> InFlight<'_> {
+        self.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlight(&self.in_flight)
+    }
+}
+
+/// Decrements a replica's in-flight request count when dropped.
+struct InFlight<'a>(&'a AtomicUsize);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Returns true if `Api` only reads data, allowing it to be sent to a
+/// replica.
+pub fn is_read_only<Api: api::Api>() -> bool {
+    let api = TypeId::of::<Api>();
+    [
+        TypeId::of::<Get>(),
+        TypeId::of::<GetMultiple>(),
+        TypeId::of::<List>(),
+        TypeId::of::<ListHeaders>(),
+        TypeId::of::<ListIncludingDeleted>(),
+        TypeId::of::<Count>(),
+        TypeId::of::<Query>(),
+        TypeId::of::<QueryWithDocs>(),
+        TypeId::of::<Reduce>(),
+        TypeId::of::<ReduceGrouped>(),
+        TypeId::of::<Explain>(),
+        TypeId::of::<FilterDocuments>(),
+        TypeId::of::<ListExecutedTransactions>(),
+        TypeId::of::<LastTransactionId>(),
+        TypeId::of::<RevisionHistory>(),
+    ]
+    .contains(&api)
+}
@@ -0,0 +1,77 @@
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use bonsaidb_core::circulate::Message;
+use bonsaidb_core::networking::{ClientEvent, ServerEvent, CLIENT_EVENTS_TOPIC};
+use bonsaidb_core::pubsub::{AsyncSubscriber, Receiver};
+use futures::Stream;
+
+use crate::AsyncRemoteSubscriber;
+
+/// A stream of [`ServerEvent`]s published by the server. Returned from
+/// [`AsyncClient::subscribe_to_server_events()`](crate::AsyncClient::subscribe_to_server_events).
+///
+/// Events can be received by using this type as a [`Stream`] or an
+/// [`Iterator`]. Dropping this type unsubscribes from the server's events.
+#[derive(Debug)]
+#[must_use]
+pub struct ServerEvents {
+    receiver: Receiver,
+    _subscriber: AsyncRemoteSubscriber,
+}
+
+impl ServerEvents {
+    pub(crate) fn new(subscriber: AsyncRemoteSubscriber) -> Self {
+        Self {
+            receiver: subscriber.receiver().clone(),
+            _subscriber: subscriber,
+        }
+    }
+
+    fn event(message: &Message) -> Option<ServerEvent> {
+        let event = match message.topic::<String>() {
+            Ok(topic) if topic == CLIENT_EVENTS_TOPIC => {
+                message.payload::<ClientEvent>().map(ServerEvent::Client)
+            }
+            _ => message.payload::<ServerEvent>(),
+        };
+        match event {
+            Ok(event) => Some(event),
+            Err(err) => {
+                // Servers running a newer version may publish events this
+                // client doesn't recognize.
+                log::warn!("[client] Ignoring unrecognized server event: {err:?}");
+                None
+            }
+        }
+    }
+}
+
+impl Stream for ServerEvents {
+    type Item = ServerEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            let Some(message) = ready!(Pin::new(&mut this.receiver).poll_next(cx)) else {
+                return Poll::Ready(None);
+            };
+            if let Some(event) = Self::event(&message) {
+                return Poll::Ready(Some(event));
+            }
+        }
+    }
+}
+
+impl Iterator for ServerEvents {
+    type Item = ServerEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let message = self.receiver.receive().ok()?;
+            if let Some(event) = Self::event(&message) {
+                return Some(event);
+            }
+        }
+    }
+}
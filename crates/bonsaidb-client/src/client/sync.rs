@@ -1,28 +1,37 @@
 use std::collections::HashMap;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use bonsaidb_core::admin::{Admin, ADMIN_DATABASE_NAME};
 use bonsaidb_core::api;
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::connection::{
-    AccessPolicy, Connection, Database, HasSchema, HasSession, IdentityReference,
-    LowLevelConnection, Range, SerializedQueryKey, Sort, StorageConnection,
+    AccessPolicy, Connection, Database, DatabaseCreation, HasSchema, HasSession, IdentityReference,
+    LowLevelConnection, QueryPlan, Range, SensitiveString, SerializedQueryKey, Sort,
+    StorageConnection,
+};
+use bonsaidb_core::document::{
+    DocumentId, Header, HistoricalRevision, MaybeDeleted, OwnedDocument,
 };
-use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::keyvalue::KeyValue;
 use bonsaidb_core::networking::{
     AlterUserPermissionGroupMembership, AlterUserRoleMembership, ApplyTransaction, AssumeIdentity,
     Compact, CompactCollection, CompactKeyValueStore, Count, CreateDatabase, CreateSubscriber,
-    CreateUser, DeleteDatabase, DeleteDocs, DeleteUser, ExecuteKeyOperation, Get, GetMultiple,
-    LastTransactionId, List, ListAvailableSchemas, ListDatabases, ListExecutedTransactions,
-    ListHeaders, Publish, PublishToAll, Query, QueryWithDocs, Reduce, ReduceGrouped, SubscribeTo,
-    UnsubscribeFrom, CURRENT_PROTOCOL_VERSION,
+    CreateUser, DeleteDatabase, DeleteDocs, DeleteUser, ExecuteKeyOperation, Explain,
+    FilterDocuments, Get, GetDatabaseStats, GetMultiple, GetServerStatus, LastTransactionId, List,
+    ListAvailableSchemas, ListDatabases, ListExecutedTransactions, ListHeaders,
+    ListIncludingDeleted, ListUserGroups, ListUsers, Ping, Publish, PublishBatch, PublishToAll,
+    Query, QueryWithDocs, Reduce, ReduceGrouped, Restore, RevisionHistory, ServerStatus,
+    SubscribeTo, SubscribeToPrefix, UnsubscribeFrom, UnsubscribeFromPrefix, CLIENT_EVENTS_TOPIC,
+    SERVER_EVENTS_TOPIC, SUPPORTED_PROTOCOL_VERSIONS,
+};
+use bonsaidb_core::permissions::Statement;
+use bonsaidb_core::pubsub::{
+    AsyncSubscriber, BufferLimit, PubSub, PublishReceipt, Receiver, Subscriber,
 };
-use bonsaidb_core::pubsub::{AsyncSubscriber, PubSub, Receiver, Subscriber};
 use bonsaidb_core::schema::view::map;
-use bonsaidb_core::schema::{CollectionName, ViewName};
+use bonsaidb_core::schema::{CollectionName, Filter, SchemaName, ViewName};
 use futures::Future;
 use tokio::runtime::{Handle, Runtime};
 use tokio::sync::oneshot;
@@ -30,7 +39,7 @@ use tokio::task::JoinHandle;
 use url::Url;
 
 use crate::builder::Blocking;
-use crate::client::ClientSession;
+use crate::client::{ClientSession, ServerEvents};
 use crate::{ApiError, AsyncClient, AsyncRemoteDatabase, AsyncRemoteSubscriber, Builder, Error};
 
 /// A BonsaiDb client that blocks the current thread when performing requests.
@@ -61,14 +70,25 @@ impl BlockingClient {
     pub fn new(url: Url) -> Result<Self, Error> {
         AsyncClient::new_from_parts(
             url,
-            CURRENT_PROTOCOL_VERSION,
+            SUPPORTED_PROTOCOL_VERSIONS,
             HashMap::default(),
             None,
             None,
+            None,
+            None,
+            None,
             #[cfg(not(target_arch = "wasm32"))]
             None,
             #[cfg(not(target_arch = "wasm32"))]
+            None,
+            #[cfg(not(target_arch = "wasm32"))]
+            Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
+            Vec::new(),
+            #[cfg(not(target_arch = "wasm32"))]
             Handle::try_current().ok(),
+            None,
+            None,
         )
         .map(Self)
     }
@@ -84,10 +104,91 @@ impl BlockingClient {
     /// Sends an api `request` without waiting for a result. The response from
     /// the server will be ignored.
     pub fn invoke_api_request<Api: api::Api>(&self, request: &Api) -> Result<(), Error> {
-        let request = Bytes::from(pot::to_vec(request).map_err(Error::from)?);
-        self.0
-            .send_request_without_confirmation(Api::name(), request)
-            .map(|_| ())
+        self.0.invoke_blocking_api_request(request)
+    }
+
+    /// Returns a new [`Batch`](crate::Batch) of requests that will be sent to
+    /// the server using this client. Use
+    /// [`Batch::send_blocking()`](crate::Batch::send_blocking) to send the
+    /// batch from a blocking context.
+    pub fn batch(&self) -> crate::Batch {
+        self.0.batch()
+    }
+
+    /// Sends a minimal request to the server and returns the time it took to
+    /// receive the response.
+    ///
+    /// See [`AsyncClient::ping()`] for more information.
+    pub fn ping(&self) -> Result<Duration, bonsaidb_core::Error> {
+        let start = Instant::now();
+        self.send_api_request(&Ping)?;
+        Ok(start.elapsed())
+    }
+
+    /// Returns the status of the server. See [`AsyncClient::server_status()`]
+    /// for more information.
+    pub fn server_status(&self) -> Result<ServerStatus, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&GetServerStatus)?)
+    }
+
+    /// Subscribes to the [`ServerEvent`](bonsaidb_core::networking::ServerEvent)s
+    /// the server publishes. See [`AsyncClient::subscribe_to_server_events()`]
+    /// for more information.
+    pub fn subscribe_to_server_events(&self) -> Result<ServerEvents, bonsaidb_core::Error> {
+        let subscriber = self.admin().create_subscriber()?;
+        subscriber.subscribe_to(&SERVER_EVENTS_TOPIC)?;
+        subscriber.subscribe_to(&CLIENT_EVENTS_TOPIC)?;
+        Ok(ServerEvents::new(subscriber.0))
+    }
+
+    /// Returns the session token the server issued when this client
+    /// authenticated, if any. See [`AsyncClient::session_token()`] for more
+    /// information.
+    #[must_use]
+    pub fn session_token(&self) -> Option<&SensitiveString> {
+        self.0.session_token()
+    }
+
+    /// Returns the name of the database configured using
+    /// [`Builder::with_default_database()`](crate::Builder::with_default_database),
+    /// if any.
+    #[must_use]
+    pub fn default_database_name(&self) -> Option<&str> {
+        self.0.default_database_name()
+    }
+
+    /// Returns the interval keepalives are sent at, as configured using
+    /// [`Builder::with_keepalive()`](crate::Builder::with_keepalive). Returns
+    /// `None` if keepalives are disabled.
+    #[must_use]
+    pub fn keepalive(&self) -> Option<Duration> {
+        self.0.keepalive()
+    }
+
+    /// Returns the database configured using
+    /// [`Builder::with_default_database()`](crate::Builder::with_default_database),
+    /// using the schema `DB`. See [`AsyncClient::default_database()`] for more
+    /// information.
+    pub fn default_database<DB: bonsaidb_core::schema::Schema>(
+        &self,
+    ) -> Result<BlockingRemoteDatabase, bonsaidb_core::Error> {
+        self.0.default_database::<DB>().map(BlockingRemoteDatabase)
+    }
+
+    /// Authenticates using the credentials returned by the credential provider
+    /// installed with
+    /// [`Builder::with_credential_provider()`](crate::Builder::with_credential_provider).
+    /// The credential provider's future is executed on the calling thread.
+    ///
+    /// See [`AsyncClient::authenticate_with_retry()`] for more information.
+    pub fn authenticate_with_retry(&self) -> Result<Self, bonsaidb_core::Error> {
+        let authentication =
+            futures::executor::block_on(self.0.credential_provider()?.credentials())?;
+        let session = self
+            .0
+            .unauthenticated()
+            .authenticated_session_blocking(authentication)?;
+        Ok(Self(self.0.with_refreshable_session(session)))
     }
 
     /// Returns a reference to an async-compatible version of this client.
@@ -103,6 +204,13 @@ impl BlockingClient {
     pub fn set_request_timeout(&mut self, timeout: impl Into<Duration>) {
         self.0.request_timeout = timeout.into();
     }
+
+    /// Returns a clone of this client that uses `timeout` for its requests.
+    /// See [`AsyncClient::with_request_timeout()`].
+    #[must_use]
+    pub fn with_request_timeout(&self, timeout: impl Into<Duration>) -> Self {
+        Self(self.0.with_request_timeout(timeout))
+    }
 }
 
 impl From<AsyncClient> for BlockingClient {
@@ -138,20 +246,23 @@ impl StorageConnection for BlockingClient {
             .map(BlockingRemoteDatabase)
     }
 
-    fn create_database_with_schema(
+    fn create_database_with_schema_and_permissions(
         &self,
         name: &str,
         schema: bonsaidb_core::schema::SchemaName,
         only_if_needed: bool,
-    ) -> Result<(), bonsaidb_core::Error> {
+        permissions: Vec<Statement>,
+    ) -> Result<DatabaseCreation, bonsaidb_core::Error> {
         self.send_api_request(&CreateDatabase {
             database: Database {
                 name: name.to_string(),
                 schema,
+                created_at: None,
             },
             only_if_needed,
-        })?;
-        Ok(())
+            permissions,
+        })
+        .map_err(bonsaidb_core::Error::from)
     }
 
     fn delete_database(&self, name: &str) -> Result<(), bonsaidb_core::Error> {
@@ -173,6 +284,15 @@ impl StorageConnection for BlockingClient {
         Ok(self.send_api_request(&ListAvailableSchemas)?)
     }
 
+    fn database_stats(
+        &self,
+        name: &str,
+    ) -> Result<bonsaidb_core::connection::DatabaseStats, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&GetDatabaseStats {
+            name: name.to_string(),
+        })?)
+    }
+
     fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         Ok(self.send_api_request(&CreateUser {
             username: username.to_string(),
@@ -188,6 +308,10 @@ impl StorageConnection for BlockingClient {
         })?)
     }
 
+    fn list_users(&self) -> Result<Vec<bonsaidb_core::connection::UserInfo>, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&ListUsers)?)
+    }
+
     #[cfg(feature = "password-hashing")]
     fn set_user_password<'user, U: bonsaidb_core::schema::Nameable<'user, u64> + Send + Sync>(
         &self,
@@ -202,19 +326,14 @@ impl StorageConnection for BlockingClient {
         })?)
     }
 
-    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
     fn authenticate(
         &self,
         authentication: bonsaidb_core::connection::Authentication,
     ) -> Result<Self::Authenticated, bonsaidb_core::Error> {
-        let session =
-            self.send_api_request(&bonsaidb_core::networking::Authenticate { authentication })?;
         Ok(Self(AsyncClient {
             data: self.0.data.clone(),
-            session: ClientSession {
-                session: Arc::new(session),
-                connection_id: self.0.data.connection_counter.load(Ordering::SeqCst),
-            },
+            session: self.0.authenticated_session_blocking(authentication)?,
+            refreshable: None,
             request_timeout: self.0.request_timeout,
         }))
     }
@@ -229,7 +348,9 @@ impl StorageConnection for BlockingClient {
             session: ClientSession {
                 session: Arc::new(session),
                 connection_id: self.0.data.connection_counter.load(Ordering::SeqCst),
+                session_token: None,
             },
+            refreshable: None,
             request_timeout: self.0.request_timeout,
         }))
     }
@@ -270,6 +391,15 @@ impl StorageConnection for BlockingClient {
         Ok(())
     }
 
+    fn list_user_groups<'user, U: bonsaidb_core::schema::Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<bonsaidb_core::connection::PermissionGroupInfo>, bonsaidb_core::Error> {
+        Ok(self.send_api_request(&ListUserGroups {
+            user: user.name()?.into_owned(),
+        })?)
+    }
+
     fn add_role_to_user<
         'user,
         'role,
@@ -318,6 +448,33 @@ impl HasSession for BlockingClient {
 #[derive(Debug, Clone)]
 pub struct BlockingRemoteDatabase(AsyncRemoteDatabase);
 
+impl BlockingRemoteDatabase {
+    /// Returns the name of the database. This is the name that was used to
+    /// open this database, and it never changes for the lifetime of this
+    /// instance.
+    #[must_use]
+    pub fn name(&self) -> &str {
+        self.0.name()
+    }
+
+    /// Returns the name of the schema this database was opened with. Generic
+    /// code can compare this against [`Schema::schema_name()`] to verify that
+    /// a database uses the expected schema.
+    ///
+    /// [`Schema::schema_name()`]: bonsaidb_core::schema::Schema::schema_name
+    #[must_use]
+    pub fn schema_name(&self) -> &SchemaName {
+        self.0.schema_name()
+    }
+
+    /// Returns a clone of this database that uses `timeout` for its requests.
+    /// See [`AsyncClient::with_request_timeout()`].
+    #[must_use]
+    pub fn with_request_timeout(&self, timeout: impl Into<Duration>) -> Self {
+        Self(self.0.with_request_timeout(timeout))
+    }
+}
+
 impl Connection for BlockingRemoteDatabase {
     type Storage = BlockingClient;
 
@@ -415,6 +572,20 @@ impl LowLevelConnection for BlockingRemoteDatabase {
         })?)
     }
 
+    fn filter_from_collection(
+        &self,
+        filter: &Filter,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<OwnedDocument>, bonsaidb_core::Error> {
+        Ok(self.0.client.send_blocking_api_request(&FilterDocuments {
+            database: self.0.name.to_string(),
+            collection: collection.clone(),
+            filter: filter.clone(),
+            limit,
+        })?)
+    }
+
     fn list_headers_from_collection(
         &self,
         ids: Range<DocumentId>,
@@ -431,6 +602,49 @@ impl LowLevelConnection for BlockingRemoteDatabase {
         }))?)
     }
 
+    fn list_including_deleted_from_collection(
+        &self,
+        ids: Range<DocumentId>,
+        order: Sort,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<MaybeDeleted>, bonsaidb_core::Error> {
+        Ok(self
+            .0
+            .client
+            .send_blocking_api_request(&ListIncludingDeleted(List {
+                database: self.0.name.to_string(),
+                collection: collection.clone(),
+                ids,
+                order,
+                limit,
+            }))?)
+    }
+
+    fn restore_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Header, bonsaidb_core::Error> {
+        Ok(self.0.client.send_blocking_api_request(&Restore {
+            database: self.0.name.to_string(),
+            collection: collection.clone(),
+            id,
+        })?)
+    }
+
+    fn revision_history_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Vec<HistoricalRevision>, bonsaidb_core::Error> {
+        Ok(self.0.client.send_blocking_api_request(&RevisionHistory {
+            database: self.0.name.to_string(),
+            collection: collection.clone(),
+            id,
+        })?)
+    }
+
     fn count_from_collection(
         &self,
         ids: Range<bonsaidb_core::document::DocumentId>,
@@ -543,6 +757,22 @@ impl LowLevelConnection for BlockingRemoteDatabase {
             access_policy,
         })?)
     }
+
+    fn explain_by_name(
+        &self,
+        view: &bonsaidb_core::schema::ViewName,
+        key: Option<SerializedQueryKey>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, bonsaidb_core::Error> {
+        Ok(self.0.client.send_blocking_api_request(&Explain {
+            database: self.0.name.to_string(),
+            view: view.clone(),
+            key,
+            order,
+            limit,
+        })?)
+    }
 }
 
 impl HasSession for BlockingRemoteDatabase {
@@ -557,46 +787,80 @@ impl HasSchema for BlockingRemoteDatabase {
     }
 }
 
-impl PubSub for BlockingRemoteDatabase {
-    type Subscriber = BlockingRemoteSubscriber;
-
-    fn create_subscriber(&self) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+impl BlockingRemoteDatabase {
+    fn create_subscriber_with(
+        &self,
+        limit: Option<BufferLimit>,
+    ) -> Result<BlockingRemoteSubscriber, bonsaidb_core::Error> {
         let subscriber_id = self.0.client.send_blocking_api_request(&CreateSubscriber {
             database: self.0.name.to_string(),
+            limit,
         })?;
 
-        let (sender, receiver) = flume::unbounded();
-        self.0.client.register_subscriber(subscriber_id, sender);
         Ok(BlockingRemoteSubscriber(AsyncRemoteSubscriber {
             client: self.0.client.clone(),
             database: self.0.name.clone(),
             id: subscriber_id,
-            receiver: Receiver::new(receiver),
+            receiver: self
+                .0
+                .client
+                .register_subscriber(&self.0.name, subscriber_id, limit),
             tokio: None,
         }))
     }
+}
+
+impl PubSub for BlockingRemoteDatabase {
+    type Subscriber = BlockingRemoteSubscriber;
+
+    fn create_subscriber(&self) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+        self.create_subscriber_with(None)
+    }
+
+    fn create_subscriber_with_limit(
+        &self,
+        limit: BufferLimit,
+    ) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+        self.create_subscriber_with(Some(limit))
+    }
 
-    fn publish_bytes(&self, topic: Vec<u8>, payload: Vec<u8>) -> Result<(), bonsaidb_core::Error> {
-        self.0.client.send_blocking_api_request(&Publish {
+    fn publish_bytes(
+        &self,
+        topic: Vec<u8>,
+        payload: Vec<u8>,
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        Ok(self.0.client.send_blocking_api_request(&Publish {
             database: self.0.name.to_string(),
             topic: Bytes::from(topic),
             payload: Bytes::from(payload),
-        })?;
-        Ok(())
+        })?)
     }
 
     fn publish_bytes_to_all(
         &self,
         topics: impl IntoIterator<Item = Vec<u8>> + Send,
         payload: Vec<u8>,
-    ) -> Result<(), bonsaidb_core::Error> {
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
         let topics = topics.into_iter().map(Bytes::from).collect();
-        self.0.client.send_blocking_api_request(&PublishToAll {
+        Ok(self.0.client.send_blocking_api_request(&PublishToAll {
             database: self.0.name.to_string(),
             topics,
             payload: Bytes::from(payload),
-        })?;
-        Ok(())
+        })?)
+    }
+
+    fn publish_batch_bytes(
+        &self,
+        messages: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        let messages = messages
+            .into_iter()
+            .map(|(topic, payload)| (Bytes::from(topic), Bytes::from(payload)))
+            .collect();
+        Ok(self.0.client.send_blocking_api_request(&PublishBatch {
+            database: self.0.name.to_string(),
+            messages,
+        })?)
     }
 }
 
@@ -611,6 +875,17 @@ impl Subscriber for BlockingRemoteSubscriber {
             database: self.0.database.to_string(),
             subscriber_id: self.0.id,
             topic: Bytes::from(topic),
+            replay: false,
+        })?;
+        Ok(())
+    }
+
+    fn subscribe_to_bytes_with_replay(&self, topic: Vec<u8>) -> Result<(), bonsaidb_core::Error> {
+        self.0.client.send_blocking_api_request(&SubscribeTo {
+            database: self.0.database.to_string(),
+            subscriber_id: self.0.id,
+            topic: Bytes::from(topic),
+            replay: true,
         })?;
         Ok(())
     }
@@ -624,6 +899,28 @@ impl Subscriber for BlockingRemoteSubscriber {
         Ok(())
     }
 
+    fn subscribe_to_prefix_bytes(&self, prefix: Vec<u8>) -> Result<(), bonsaidb_core::Error> {
+        self.0
+            .client
+            .send_blocking_api_request(&SubscribeToPrefix {
+                database: self.0.database.to_string(),
+                subscriber_id: self.0.id,
+                prefix: Bytes::from(prefix),
+            })?;
+        Ok(())
+    }
+
+    fn unsubscribe_from_prefix_bytes(&self, prefix: &[u8]) -> Result<(), bonsaidb_core::Error> {
+        self.0
+            .client
+            .send_blocking_api_request(&UnsubscribeFromPrefix {
+                database: self.0.database.to_string(),
+                subscriber_id: self.0.id,
+                prefix: Bytes::from(prefix),
+            })?;
+        Ok(())
+    }
+
     fn receiver(&self) -> &Receiver {
         AsyncSubscriber::receiver(&self.0)
     }
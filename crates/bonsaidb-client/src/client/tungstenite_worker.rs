@@ -1,31 +1,38 @@
 use std::collections::HashMap;
+use std::pin::pin;
 use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Arc;
 
 use bonsaidb_core::api::ApiName;
-use bonsaidb_core::networking::Payload;
+use bonsaidb_core::networking::{Payload, TransportCompression, PROTOCOL_VERSIONS_HEADER};
 use bonsaidb_utils::fast_async_lock;
+use fabruic::Certificate;
 use flume::Receiver;
-use futures::stream::{SplitSink, SplitStream};
-use futures::{SinkExt, StreamExt};
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::net::TcpStream;
-use tokio_tungstenite::tungstenite::handshake::client::generate_key;
+use tokio_tungstenite::tungstenite::handshake::client::{generate_key, Request, Response};
+use tokio_tungstenite::tungstenite::http::HeaderMap;
 use tokio_tungstenite::tungstenite::Message;
-use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::{Connector, MaybeTlsStream, WebSocketStream};
+use url::Url;
 
 use super::PendingRequest;
 use crate::client::{
     disconnect_pending_requests, AnyApiCallback, ConnectionInfo, OutstandingRequestMapHandle,
+    WebSocketError,
 };
 use crate::Error;
 
 pub(super) async fn reconnecting_client_loop(
     server: ConnectionInfo,
-    protocol_version: &str,
+    protocol_versions: &[&str],
+    certificate_authorities: Vec<Certificate>,
     request_receiver: Receiver<PendingRequest>,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
     connection_counter: Arc<AtomicU32>,
 ) -> Result<(), Error> {
+    let connector = tls_connector(&certificate_authorities);
     let mut pending_error = None;
     while let Ok(request) = {
         server.subscribers.clear();
@@ -37,45 +44,48 @@ pub(super) async fn reconnecting_client_loop(
         }
 
         connection_counter.fetch_add(1, Ordering::SeqCst);
-        let (stream, _) = match tokio::time::timeout(
+        let requested_compression = requested_compression(&server);
+        let protocols = offered_protocols(protocol_versions, requested_compression.as_ref());
+        let (stream, response) = match tokio::time::timeout(
             server.connect_timeout,
-            tokio_tungstenite::connect_async(
-                tokio_tungstenite::tungstenite::handshake::client::Request::get(
-                    server.url.as_str(),
-                )
-                .header("Sec-WebSocket-Protocol", protocol_version)
-                .header("Sec-WebSocket-Version", "13")
-                .header("Sec-WebSocket-Key", generate_key())
-                .header("Host", server.url.host_str().expect("no host"))
-                .header("Connection", "Upgrade")
-                .header("Upgrade", "websocket")
-                .body(())
-                .unwrap(),
-            ),
+            connect(&server.url, protocols, connector.clone()),
         )
         .await
         {
             Ok(Ok(result)) => result,
             Ok(Err(err)) => {
-                drop(request.responder.send(Err(Error::from(err))));
+                request.connect_failed(connect_error(err, protocol_versions));
                 continue;
             }
             Err(_) => {
-                drop(request.responder.send(Err(Error::connect_timeout())));
+                request.connect_failed(Error::connect_timeout());
                 continue;
             }
         };
 
+        let negotiated_protocol = response
+            .headers()
+            .get("Sec-WebSocket-Protocol")
+            .and_then(|protocol| protocol.to_str().ok());
+        let compression = requested_compression.filter(|compression| {
+            protocol_versions.iter().any(|version| {
+                negotiated_protocol == Some(compression.compression.protocol_name(version).as_str())
+            })
+        });
+
         let (mut sender, receiver) = stream.split();
 
         let outstanding_requests = OutstandingRequestMapHandle::default();
         {
             let mut outstanding_requests = fast_async_lock!(outstanding_requests);
             if let Err(err) = sender
-                .send(Message::Binary(bincode::serialize(&request.request)?))
+                .send(Message::Binary(encode_payload(
+                    &request.request,
+                    compression.as_ref(),
+                )?))
                 .await
             {
-                drop(request.responder.send(Err(Error::from(err))));
+                request.connect_failed(Error::from(err));
                 continue;
             }
             outstanding_requests.insert(
@@ -85,8 +95,18 @@ pub(super) async fn reconnecting_client_loop(
         }
 
         if let Err(err) = tokio::try_join!(
-            request_sender(&request_receiver, sender, outstanding_requests.clone()),
-            response_processor(receiver, outstanding_requests.clone(), &custom_apis,)
+            request_sender(
+                &request_receiver,
+                sender,
+                outstanding_requests.clone(),
+                compression
+            ),
+            response_processor(
+                receiver,
+                outstanding_requests.clone(),
+                &custom_apis,
+                compression.is_some()
+            )
         ) {
             // Our socket was disconnected, clear the outstanding requests before returning.
             log::error!("Error on socket {:?}", err);
@@ -98,15 +118,161 @@ pub(super) async fn reconnecting_client_loop(
     Ok(())
 }
 
-async fn request_sender(
+/// Returns the list of protocols to offer the server, ordered from most
+/// preferred to least preferred.
+///
+/// When requesting compression, each version's uncompressed protocol is also
+/// offered so that servers without support can fall back to it.
+pub(super) fn offered_protocols(
+    protocol_versions: &[&str],
+    compression: Option<&TransportCompression>,
+) -> String {
+    let mut protocols = Vec::with_capacity(protocol_versions.len() * 2);
+    for version in protocol_versions {
+        if let Some(compression) = compression {
+            protocols.push(compression.compression.protocol_name(version));
+        }
+        protocols.push((*version).to_string());
+    }
+    protocols.join(", ")
+}
+
+/// Converts an error from establishing a connection, reporting the protocol
+/// versions supported by the server if it rejected all of the offered
+/// versions.
+fn connect_error(err: WebSocketError, protocol_versions: &[&str]) -> Error {
+    if let WebSocketError::Http(response) = &err {
+        if response.status() == 406 {
+            return Error::incompatible_protocol_version(
+                protocol_versions,
+                server_protocol_versions(response.headers()),
+            );
+        }
+    }
+
+    Error::from(err)
+}
+
+/// Returns the protocol versions a server reported supporting when it
+/// rejected a connection.
+pub(super) fn server_protocol_versions(headers: &HeaderMap) -> Vec<String> {
+    headers
+        .get(PROTOCOL_VERSIONS_HEADER)
+        .and_then(|versions| versions.to_str().ok())
+        .map(|versions| {
+            versions
+                .split(',')
+                .map(|version| version.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A connection that the WebSocket protocol can be spoken over.
+trait Socket: AsyncRead + AsyncWrite + Send + Unpin {}
+
+impl<T> Socket for T where T: AsyncRead + AsyncWrite + Send + Unpin {}
+
+type ClientStream = WebSocketStream<MaybeTlsStream<Box<dyn Socket>>>;
+
+/// Returns the TLS connector to use for `wss` connections. When no additional
+/// certificate authorities are trusted, `None` is returned, which causes the
+/// default connector that trusts the system's root certificates to be used.
+fn tls_connector(certificate_authorities: &[Certificate]) -> Option<Connector> {
+    if certificate_authorities.is_empty() {
+        return None;
+    }
+
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(root_certificates(certificate_authorities))
+        .with_no_client_auth();
+    Some(Connector::Rustls(Arc::new(config)))
+}
+
+/// Returns the system's root certificates along with
+/// `certificate_authorities`.
+pub(super) fn root_certificates(certificate_authorities: &[Certificate]) -> rustls::RootCertStore {
+    let mut roots = rustls::RootCertStore::empty();
+    match rustls_native_certs::load_native_certs() {
+        Ok(certificates) => {
+            let certificates = certificates
+                .into_iter()
+                .map(|certificate| certificate.0)
+                .collect::<Vec<_>>();
+            roots.add_parsable_certificates(&certificates);
+        }
+        Err(err) => log::error!("Error loading native root certificates: {:?}", err),
+    }
+    let authorities = certificate_authorities
+        .iter()
+        .map(|certificate| certificate.as_ref().to_vec())
+        .collect::<Vec<_>>();
+    let (_, ignored) = roots.add_parsable_certificates(&authorities);
+    if ignored > 0 {
+        log::error!("{ignored} certificate authorities could not be parsed");
+    }
+    roots
+}
+
+/// Connects to the server at `url` and performs the WebSocket handshake.
+///
+/// For `unix` URLs, the path of the URL is the path of the socket to connect
+/// to. Otherwise, a TCP connection is established, using TLS for `wss` URLs.
+async fn connect(
+    url: &Url,
+    protocols: String,
+    connector: Option<Connector>,
+) -> Result<(ClientStream, Response), WebSocketError> {
+    let (socket, uri, host): (Box<dyn Socket>, &str, &str) = match url.scheme() {
+        #[cfg(unix)]
+        "unix" => (
+            Box::new(tokio::net::UnixStream::connect(url.path()).await?),
+            "ws://localhost/",
+            "localhost",
+        ),
+        _ => {
+            let host = url.host_str().expect("no host");
+            let port = url
+                .port_or_known_default()
+                .expect("ws and wss have default ports");
+            // IPv6 hosts are surrounded by brackets in urls.
+            let address = host.trim_start_matches('[').trim_end_matches(']');
+            let socket = TcpStream::connect((address, port)).await?;
+            (Box::new(socket), url.as_str(), host)
+        }
+    };
+
+    let request = Request::get(uri)
+        .header("Sec-WebSocket-Protocol", protocols)
+        .header("Sec-WebSocket-Version", "13")
+        .header("Sec-WebSocket-Key", generate_key())
+        .header("Host", host)
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "websocket")
+        .body(())
+        .unwrap();
+    tokio_tungstenite::client_async_tls_with_config(request, socket, None, connector).await
+}
+
+pub(super) async fn request_sender<S>(
     request_receiver: &Receiver<PendingRequest>,
-    mut sender: SplitSink<WebSocketStream<MaybeTlsStream<TcpStream>>, Message>,
+    sender: S,
     outstanding_requests: OutstandingRequestMapHandle,
-) -> Result<(), Error> {
+    compression: Option<TransportCompression>,
+) -> Result<(), Error>
+where
+    S: Sink<Message>,
+    Error: From<S::Error>,
+{
+    let mut sender = pin!(sender);
     while let Ok(pending) = request_receiver.recv_async().await {
         let mut outstanding_requests = fast_async_lock!(outstanding_requests);
         sender
-            .send(Message::Binary(bincode::serialize(&pending.request)?))
+            .send(Message::Binary(encode_payload(
+                &pending.request,
+                compression.as_ref(),
+            )?))
             .await?;
 
         outstanding_requests.insert(
@@ -119,16 +285,22 @@ async fn request_sender(
 }
 
 #[allow(clippy::collapsible_else_if)] // not possible due to cfg statement
-async fn response_processor(
-    mut receiver: SplitStream<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+pub(super) async fn response_processor<R, E>(
+    receiver: R,
     outstanding_requests: OutstandingRequestMapHandle,
     custom_apis: &HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>,
-) -> Result<(), Error> {
+    compressed: bool,
+) -> Result<(), Error>
+where
+    R: Stream<Item = Result<Message, E>>,
+    Error: From<E>,
+{
+    let mut receiver = pin!(receiver);
     while let Some(message) = receiver.next().await {
         let message = message?;
         match message {
             Message::Binary(response) => {
-                let payload = bincode::deserialize::<Payload>(&response)?;
+                let payload = decode_payload(&response, compressed)?;
 
                 super::process_response_payload(payload, &outstanding_requests, custom_apis).await;
             }
@@ -140,3 +312,40 @@ async fn response_processor(
 
     Ok(())
 }
+
+#[cfg(feature = "compression")]
+pub(super) fn requested_compression(server: &ConnectionInfo) -> Option<TransportCompression> {
+    server.compression
+}
+
+#[cfg(not(feature = "compression"))]
+pub(super) const fn requested_compression(
+    _server: &ConnectionInfo,
+) -> Option<TransportCompression> {
+    None
+}
+
+pub(super) fn encode_payload(
+    payload: &Payload,
+    compression: Option<&TransportCompression>,
+) -> Result<Vec<u8>, Error> {
+    let encoded = bincode::serialize(payload)?;
+    match compression {
+        #[cfg(feature = "compression")]
+        Some(compression) => Ok(compression.encode(&encoded)?),
+        _ => Ok(encoded),
+    }
+}
+
+fn decode_payload(message: &[u8], compressed: bool) -> Result<Payload, Error> {
+    #[cfg(feature = "compression")]
+    if compressed {
+        return Ok(bincode::deserialize(&TransportCompression::decode(
+            message,
+        )?)?);
+    }
+    #[cfg(not(feature = "compression"))]
+    debug_assert!(!compressed);
+
+    Ok(bincode::deserialize(message)?)
+}
@@ -21,7 +21,7 @@ use crate::Error;
 #[allow(clippy::too_many_arguments)]
 pub fn spawn_client(
     url: Arc<Url>,
-    protocol_version: &'static str,
+    protocol_versions: &'static [&'static str],
     request_receiver: Receiver<PendingRequest>,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
     subscribers: SubscriberMap,
@@ -31,7 +31,7 @@ pub fn spawn_client(
 ) {
     wasm_bindgen_futures::spawn_local(create_websocket(
         url,
-        protocol_version,
+        protocol_versions,
         request_receiver,
         custom_apis,
         subscribers,
@@ -44,7 +44,7 @@ pub fn spawn_client(
 #[allow(clippy::too_many_arguments)]
 async fn create_websocket(
     url: Arc<Url>,
-    protocol_version: &'static str,
+    protocol_versions: &'static [&'static str],
     request_receiver: Receiver<PendingRequest>,
     custom_apis: Arc<HashMap<ApiName, Option<Arc<dyn AnyApiCallback>>>>,
     subscribers: SubscriberMap,
@@ -70,7 +70,11 @@ async fn create_websocket(
     // In wasm we're not going to have a real loop. We're going create a
     // websocket and store it in JS. This will allow us to get around Send/Sync
     // issues since each access of the websocket can pull it from js.
-    let ws = match WebSocket::new_with_str(&url.to_string(), protocol_version) {
+    let protocols = protocol_versions
+        .iter()
+        .map(|version| JsValue::from_str(version))
+        .collect::<js_sys::Array>();
+    let ws = match WebSocket::new_with_str_sequence(&url.to_string(), &protocols) {
         Ok(ws) => ws,
         Err(err) => {
             drop(
@@ -80,7 +84,7 @@ async fn create_websocket(
             );
             spawn_client(
                 url,
-                protocol_version,
+                protocol_versions,
                 request_receiver,
                 custom_apis.clone(),
                 subscribers,
@@ -130,7 +134,7 @@ async fn create_websocket(
 
     let onclose_callback = on_close_callback(
         url.clone(),
-        protocol_version,
+        protocol_versions,
         request_receiver.clone(),
         shutdown_sender,
         ws.clone(),
@@ -312,7 +316,7 @@ fn take_initial_request(initial_request: &Mutex<Option<PendingRequest>>) -> Opti
 #[allow(clippy::too_many_arguments)]
 fn on_close_callback(
     url: Arc<Url>,
-    protocol_version: &'static str,
+    protocol_versions: &'static [&'static str],
     request_receiver: Receiver<PendingRequest>,
     shutdown: flume::Sender<()>,
     ws: WebSocket,
@@ -346,7 +350,7 @@ fn on_close_callback(
 
             spawn_client(
                 url,
-                protocol_version,
+                protocol_versions,
                 request_receiver,
                 custom_apis.clone(),
                 subscribers,
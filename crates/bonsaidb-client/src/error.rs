@@ -1,6 +1,7 @@
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::networking;
 use bonsaidb_core::schema::Name;
+use bonsaidb_core::ErrorCode;
 
 /// Errors related to working with the BonsaiDb client.
 #[derive(thiserror::Error, Debug)]
@@ -32,12 +33,40 @@ pub enum Error {
         error: Bytes,
     },
 
-    /// The server is incompatible with this version of the client.
-    #[error("server incompatible with client protocol version")]
-    ProtocolVersionMismatch,
+    /// No client became available in a
+    /// [`ClientPool`](crate::ClientPool) before the checkout timeout elapsed.
+    #[error("timed out waiting for a pooled client")]
+    PoolTimeout,
+
+    /// Authenticating with retries requires a credential provider, which can
+    /// be installed using
+    /// [`Builder::with_credential_provider()`](crate::Builder::with_credential_provider).
+    #[error("no credential provider was installed")]
+    NoCredentialProvider,
+
+    /// A default database was requested, but none was configured using
+    /// [`Builder::with_default_database()`](crate::Builder::with_default_database).
+    #[error("no default database was configured")]
+    NoDefaultDatabase,
 }
 
 impl Error {
+    /// Returns the [`ErrorCode`] categorizing this error.
+    #[must_use]
+    pub const fn code(&self) -> ErrorCode {
+        match self {
+            Self::Core(err) => err.code(),
+            Self::Network(err) => err.code(),
+            Self::PoolTimeout => ErrorCode::Timeout,
+            Self::InvalidUrl(_) | Self::NoCredentialProvider | Self::NoDefaultDatabase => {
+                ErrorCode::InvalidInput
+            }
+            #[cfg(feature = "websockets")]
+            Self::WebSocket(_) => ErrorCode::Other,
+            Self::Api { .. } => ErrorCode::Other,
+        }
+    }
+
     pub(crate) fn disconnected() -> Self {
         Self::Core(bonsaidb_core::Error::Networking(
             networking::Error::Disconnected,
@@ -50,6 +79,17 @@ impl Error {
         ))
     }
 
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) fn incompatible_protocol_version(client: &[&str], server: Vec<String>) -> Self {
+        Self::Network(networking::Error::IncompatibleProtocolVersion {
+            client: client
+                .iter()
+                .map(|version| (*version).to_string())
+                .collect(),
+            server,
+        })
+    }
+
     pub(crate) fn connect_timeout() -> Self {
         Self::Core(bonsaidb_core::Error::Networking(
             networking::Error::ConnectTimeout,
@@ -82,6 +122,7 @@ impl From<Error> for bonsaidb_core::Error {
     fn from(other: Error) -> Self {
         match other {
             Error::Core(err) => err,
+            Error::Network(err) => Self::Networking(err),
             other => Self::other("bonsaidb-client", other),
         }
     }
@@ -118,8 +159,10 @@ impl From<crate::client::WebSocketError> for Error {
     #[cfg(not(target_arch = "wasm32"))]
     fn from(err: crate::client::WebSocketError) -> Self {
         if let crate::client::WebSocketError::Http(response) = &err {
-            if response.status() == 406 {
-                return Self::ProtocolVersionMismatch;
+            if response.status() == 503 {
+                return Self::Core(bonsaidb_core::Error::Networking(
+                    bonsaidb_core::networking::Error::ConnectionLimitReached,
+                ));
             }
         }
 
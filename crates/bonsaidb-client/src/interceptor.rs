@@ -0,0 +1,60 @@
+use std::fmt::{Debug, Display};
+
+use bonsaidb_core::api::ApiName;
+
+/// Observes the requests sent by a client. Installed using
+/// [`Builder::with_interceptor()`](crate::Builder::with_interceptor).
+///
+/// Interceptors can be used to log requests, measure how long they take, or
+/// record metrics. Each request is described by a [`RequestInfo`], which is
+/// passed to [`before_request()`](Self::before_request) before the request is
+/// sent and to [`after_response()`](Self::after_response) once it completes.
+/// Both functions are invoked by the task making the request, and should
+/// return quickly.
+///
+/// ```rust
+/// use std::fmt::Display;
+///
+/// use bonsaidb_client::{RequestInfo, RequestInterceptor};
+///
+/// #[derive(Debug)]
+/// struct Logger;
+///
+/// impl RequestInterceptor for Logger {
+///     fn before_request(&self, request: &RequestInfo<'_>) {
+///         println!("{} sending {}", request.id, request.api);
+///     }
+///
+///     fn after_response(&self, request: &RequestInfo<'_>, error: Option<&dyn Display>) {
+///         if let Some(error) = error {
+///             println!("{} failed: {error}", request.id);
+///         }
+///     }
+/// }
+/// ```
+pub trait RequestInterceptor: Debug + Send + Sync + 'static {
+    /// Invoked before `request` is sent.
+    fn before_request(&self, request: &RequestInfo<'_>) {
+        let _ = request;
+    }
+
+    /// Invoked after `request` completes. `error` is the error returned to
+    /// the caller, or `None` if the request succeeded.
+    fn after_response(&self, request: &RequestInfo<'_>, error: Option<&dyn Display>) {
+        let _ = (request, error);
+    }
+}
+
+/// Describes a request being sent by a client to a [`RequestInterceptor`].
+#[derive(Debug, Clone, Copy)]
+pub struct RequestInfo<'a> {
+    /// The id of this request. Ids are unique for each client, allowing
+    /// [`RequestInterceptor::before_request()`] and
+    /// [`RequestInterceptor::after_response()`] to be correlated.
+    pub id: u32,
+    /// The name of the api being invoked.
+    pub api: &'a ApiName,
+    /// The database the request operates on, if any. See
+    /// [`Api::database()`](bonsaidb_core::api::Api::database).
+    pub database: Option<&'a str>,
+}
@@ -20,12 +20,21 @@ pub use url;
 mod builder;
 mod client;
 mod error;
+mod interceptor;
+#[cfg(not(target_arch = "wasm32"))]
+mod pool;
 
 #[cfg(not(target_arch = "wasm32"))]
 pub use fabruic;
 
 pub use self::builder::Builder;
-pub use self::client::{ApiCallback, AsyncClient, AsyncRemoteDatabase, AsyncRemoteSubscriber};
+pub use self::client::{
+    ApiCallback, AsyncClient, AsyncRemoteDatabase, AsyncRemoteSubscriber, Batch, BatchEntry,
+    BatchResults, ReplicaRouting, ServerEvents,
+};
 #[cfg(not(target_arch = "wasm32"))]
 pub use self::client::{BlockingClient, BlockingRemoteDatabase, BlockingRemoteSubscriber};
 pub use self::error::{ApiError, Error};
+pub use self::interceptor::{RequestInfo, RequestInterceptor};
+#[cfg(not(target_arch = "wasm32"))]
+pub use self::pool::{ClientPool, ClientPoolBuilder, PooledClient};
@@ -0,0 +1,383 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use bonsaidb_core::connection::HasSession;
+use parking_lot::{Condvar, Mutex, MutexGuard};
+
+use crate::{BlockingClient, Error};
+
+type ConnectFn = dyn Fn() -> Result<BlockingClient, Error> + Send + Sync;
+
+/// A bounded pool of [`BlockingClient`]s, each with its own connection to the
+/// server.
+///
+/// Creating a client requires establishing a new network connection, which
+/// includes a TLS handshake and often authentication. When a blocking
+/// workload creates a client per request, this overhead is paid each time.
+/// `ClientPool` instead keeps connections open between requests: call
+/// [`get()`](Self::get) to check out a client, and drop the returned
+/// [`PooledClient`] to return it to the pool.
+///
+/// Connections are created by the function passed to
+/// [`ClientPool::build()`], which can authenticate the client before
+/// returning it. A client is only handed out by one [`PooledClient`] at a
+/// time, allowing each to have its own authenticated session.
+///
+/// ```rust
+/// # use bonsaidb_client::{BlockingClient, ClientPool, url::Url};
+/// # fn test_fn() -> anyhow::Result<()> {
+/// let url = Url::parse("bonsaidb://localhost")?;
+/// let pool = ClientPool::build(move || BlockingClient::build(url.clone()).build())
+///     .with_min_size(2)
+///     .with_max_size(10)
+///     .build()?;
+/// let client = pool.get()?;
+/// client.ping()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ClientPool {
+    data: Arc<PoolData>,
+}
+
+impl ClientPool {
+    /// Returns a builder for a pool that uses `connect` to create new
+    /// clients.
+    pub fn build<F>(connect: F) -> ClientPoolBuilder
+    where
+        F: Fn() -> Result<BlockingClient, Error> + Send + Sync + 'static,
+    {
+        ClientPoolBuilder {
+            connect: Box::new(connect),
+            config: PoolConfig::default(),
+        }
+    }
+
+    /// Checks out a client from this pool.
+    ///
+    /// Idle clients are reused when possible. A client that has been idle
+    /// longer than the
+    /// [health check interval](ClientPoolBuilder::with_health_check_interval)
+    /// is pinged before being returned, and is discarded if the ping fails or
+    /// if it has lost its authenticated session. If no idle clients are
+    /// available and fewer than the maximum number of clients are open, a new
+    /// client is created. Otherwise, this function blocks until a client is
+    /// returned to the pool, returning [`Error::PoolTimeout`] if none becomes
+    /// available before the
+    /// [checkout timeout](ClientPoolBuilder::with_checkout_timeout) elapses.
+    pub fn get(&self) -> Result<PooledClient, Error> {
+        let config = &self.data.config;
+        let deadline = Instant::now() + config.checkout_timeout;
+        let mut state = self.data.state.lock();
+        loop {
+            state.evict_expired(config);
+
+            if let Some(idle) = state.idle.pop_back() {
+                let healthy = idle.since.elapsed() < config.health_check_interval
+                    || MutexGuard::unlocked(&mut state, || idle.connection.is_healthy());
+                if healthy {
+                    return Ok(PooledClient::new(idle.connection, self.data.clone()));
+                }
+
+                state.open -= 1;
+                continue;
+            }
+
+            if state.open < config.max_size {
+                state.open += 1;
+                return match MutexGuard::unlocked(&mut state, || self.data.connect()) {
+                    Ok(connection) => Ok(PooledClient::new(connection, self.data.clone())),
+                    Err(err) => {
+                        state.open -= 1;
+                        self.data.available.notify_one();
+                        Err(err)
+                    }
+                };
+            }
+
+            if self
+                .data
+                .available
+                .wait_until(&mut state, deadline)
+                .timed_out()
+            {
+                return Err(Error::PoolTimeout);
+            }
+        }
+    }
+
+    /// Returns the number of clients currently open, including clients that
+    /// are checked out.
+    #[must_use]
+    pub fn open_connections(&self) -> usize {
+        self.data.state.lock().open
+    }
+
+    /// Returns the number of open clients that are waiting to be checked out.
+    #[must_use]
+    pub fn idle_connections(&self) -> usize {
+        self.data.state.lock().idle.len()
+    }
+}
+
+impl Debug for ClientPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.data.state.lock();
+        f.debug_struct("ClientPool")
+            .field("config", &self.data.config)
+            .field("open", &state.open)
+            .field("idle", &state.idle.len())
+            .finish()
+    }
+}
+
+/// Builder for a [`ClientPool`].
+#[must_use]
+pub struct ClientPoolBuilder {
+    connect: Box<ConnectFn>,
+    config: PoolConfig,
+}
+
+impl ClientPoolBuilder {
+    /// Sets the number of clients to keep open, even when idle. These clients
+    /// are created when the pool is built.
+    ///
+    /// If not specified, no clients are kept open.
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.config.min_size = min_size;
+        self
+    }
+
+    /// Sets the maximum number of clients that can be open at once.
+    ///
+    /// If not specified, at most 10 clients are opened.
+    pub fn with_max_size(mut self, max_size: usize) -> Self {
+        self.config.max_size = max_size;
+        self
+    }
+
+    /// Sets how long a client can be idle before it is closed. Clients are
+    /// never closed for being idle if doing so would leave fewer than the
+    /// [minimum number of clients](Self::with_min_size) open.
+    ///
+    /// If not specified, idle clients are closed after 10 minutes.
+    pub fn with_idle_timeout(mut self, timeout: impl Into<Duration>) -> Self {
+        self.config.idle_timeout = timeout.into();
+        self
+    }
+
+    /// Sets how long a client can be idle before it is pinged to check its
+    /// connection when it is checked out.
+    ///
+    /// If not specified, clients idle for 30 seconds or longer are checked.
+    pub fn with_health_check_interval(mut self, interval: impl Into<Duration>) -> Self {
+        self.config.health_check_interval = interval.into();
+        self
+    }
+
+    /// Sets how long [`ClientPool::get()`] waits for a client to be returned
+    /// when the pool is at its maximum size.
+    ///
+    /// If not specified, [`ClientPool::get()`] waits up to 30 seconds.
+    pub fn with_checkout_timeout(mut self, timeout: impl Into<Duration>) -> Self {
+        self.config.checkout_timeout = timeout.into();
+        self
+    }
+
+    /// Builds the pool, opening the
+    /// [minimum number of clients](Self::with_min_size).
+    pub fn build(self) -> Result<ClientPool, Error> {
+        let mut config = self.config;
+        config.max_size = config.max_size.max(1);
+        config.min_size = config.min_size.min(config.max_size);
+
+        let data = Arc::new(PoolData {
+            connect: self.connect,
+            config,
+            state: Mutex::new(PoolState::default()),
+            available: Condvar::new(),
+        });
+        for _ in 0..config.min_size {
+            let connection = data.connect()?;
+            let mut state = data.state.lock();
+            state.open += 1;
+            state.idle.push_back(IdleConnection {
+                connection,
+                since: Instant::now(),
+            });
+        }
+
+        Ok(ClientPool { data })
+    }
+}
+
+impl Debug for ClientPoolBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientPoolBuilder")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A [`BlockingClient`] checked out from a [`ClientPool`].
+///
+/// This type dereferences to [`BlockingClient`]. When dropped, the client is
+/// returned to the pool unless it has lost its authenticated session. If a
+/// request fails in a way that suggests the client should not be reused, call
+/// [`discard()`](Self::discard) instead.
+#[derive(Debug)]
+#[must_use]
+pub struct PooledClient {
+    connection: Option<Connection>,
+    pool: Arc<PoolData>,
+}
+
+impl PooledClient {
+    fn new(connection: Connection, pool: Arc<PoolData>) -> Self {
+        Self {
+            connection: Some(connection),
+            pool,
+        }
+    }
+
+    /// Closes this client rather than returning it to the pool.
+    pub fn discard(mut self) {
+        self.connection = None;
+    }
+}
+
+impl Deref for PooledClient {
+    type Target = BlockingClient;
+
+    fn deref(&self) -> &Self::Target {
+        &self
+            .connection
+            .as_ref()
+            .expect("only taken when dropped")
+            .client
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        let connection = self
+            .connection
+            .take()
+            .filter(|connection| !connection.lost_session());
+        let mut state = self.pool.state.lock();
+        if let Some(connection) = connection {
+            state.idle.push_back(IdleConnection {
+                connection,
+                since: Instant::now(),
+            });
+        } else {
+            state.open -= 1;
+        }
+        drop(state);
+        self.pool.available.notify_one();
+    }
+}
+
+struct PoolData {
+    connect: Box<ConnectFn>,
+    config: PoolConfig,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+impl PoolData {
+    fn connect(&self) -> Result<Connection, Error> {
+        let client = (self.connect)()?;
+        // Clients connect lazily. Ping to ensure the connection is
+        // established before it is used.
+        client.ping()?;
+        Ok(Connection::new(client))
+    }
+}
+
+impl Debug for PoolData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PoolData")
+            .field("config", &self.config)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PoolConfig {
+    min_size: usize,
+    max_size: usize,
+    idle_timeout: Duration,
+    health_check_interval: Duration,
+    checkout_timeout: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            min_size: 0,
+            max_size: 10,
+            idle_timeout: Duration::from_secs(600),
+            health_check_interval: Duration::from_secs(30),
+            checkout_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PoolState {
+    /// Idle connections, ordered from least to most recently returned.
+    idle: VecDeque<IdleConnection>,
+    open: usize,
+}
+
+impl PoolState {
+    fn evict_expired(&mut self, config: &PoolConfig) {
+        while self.open > config.min_size
+            && self
+                .idle
+                .front()
+                .map_or(false, |idle| idle.since.elapsed() >= config.idle_timeout)
+        {
+            self.idle.pop_front();
+            self.open -= 1;
+        }
+    }
+}
+
+struct IdleConnection {
+    connection: Connection,
+    since: Instant,
+}
+
+#[derive(Debug)]
+struct Connection {
+    client: BlockingClient,
+    authenticated: bool,
+}
+
+impl Connection {
+    fn new(client: BlockingClient) -> Self {
+        let authenticated = client
+            .session()
+            .map_or(false, |session| session.id.is_some());
+        Self {
+            client,
+            authenticated,
+        }
+    }
+
+    /// Returns true if the client was authenticated, but its session was lost
+    /// due to reconnecting.
+    fn lost_session(&self) -> bool {
+        self.authenticated && self.client.session().is_none()
+    }
+
+    fn is_healthy(&self) -> bool {
+        !self.lost_session() && self.client.ping().is_ok()
+    }
+}
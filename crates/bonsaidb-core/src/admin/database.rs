@@ -2,16 +2,25 @@ use serde::{Deserialize, Serialize};
 
 use crate::define_basic_unique_mapped_view;
 use crate::document::{CollectionDocument, Emit};
+use crate::key::time::TimestampAsNanoseconds;
+use crate::permissions::Statement;
 use crate::schema::{Collection, NamedCollection, SchemaName};
 
 /// A database stored in BonsaiDb.
-#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize, Collection)]
+#[derive(Debug, Clone, Deserialize, Serialize, Collection)]
 #[collection(authority = "bonsaidb", name = "databases", views = [ByName], core = crate)]
 pub struct Database {
     /// The name of the database.
     pub name: String,
     /// The schema defining the database.
     pub schema: SchemaName,
+    /// When the database was created. This is `None` for databases created
+    /// before creation times were recorded.
+    #[serde(default)]
+    pub created_at: Option<TimestampAsNanoseconds>,
+    /// Permissions granted to every session that accesses this database.
+    #[serde(default)]
+    pub permissions: Vec<Statement>,
 }
 
 define_basic_unique_mapped_view!(
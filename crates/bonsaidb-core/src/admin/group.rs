@@ -1,11 +1,18 @@
+use std::collections::HashSet;
+
 use serde::{Deserialize, Serialize};
 
+use crate::connection::{AsyncConnection, Connection};
 use crate::define_basic_unique_mapped_view;
-use crate::document::{CollectionDocument, Emit};
+use crate::document::{CollectionDocument, DocumentId, Emit};
 use crate::permissions::Statement;
-use crate::schema::{Collection, NamedCollection};
+use crate::schema::{Collection, NamedCollection, SerializedCollection};
 
 /// A named group of permissions statements.
+///
+/// A group can inherit the statements of another group by setting
+/// [`parent_group`](Self::parent_group). Users and roles that belong to a
+/// group are granted the statements of the group and all of its ancestors.
 #[derive(Clone, Debug, Serialize, Deserialize, Collection)]
 #[collection(name = "permission-group", authority="khonsulabs", views = [ByName], core = crate)]
 #[must_use]
@@ -14,6 +21,12 @@ pub struct PermissionGroup {
     pub name: String,
     /// The permission statements.
     pub statements: Vec<Statement>,
+    /// The ID of the group this group inherits statements from.
+    ///
+    /// Use [`PermissionGroup::set_parent_group()`] to change the parent of an
+    /// existing group, which ensures the assignment doesn't create a cycle.
+    #[serde(default)]
+    pub parent_group: Option<u64>,
 }
 
 impl PermissionGroup {
@@ -22,6 +35,7 @@ impl PermissionGroup {
         Self {
             name: name.into(),
             statements: Vec::new(),
+            parent_group: None,
         }
     }
 
@@ -30,6 +44,110 @@ impl PermissionGroup {
         self.statements = statements.into_iter().collect();
         self
     }
+
+    /// Builder-style method. Returns self after setting the parent group to
+    /// `parent_group`.
+    pub const fn with_parent_group(mut self, parent_group: u64) -> Self {
+        self.parent_group = Some(parent_group);
+        self
+    }
+
+    /// Sets the parent of `group` to `parent_group` and saves the group.
+    ///
+    /// Returns [`Error::PermissionGroupCycle`](crate::Error::PermissionGroupCycle)
+    /// if `group` is `parent_group` or one of its ancestors, or
+    /// [`Error::DocumentNotFound`](crate::Error::DocumentNotFound) if a group
+    /// in the chain of ancestors doesn't exist.
+    pub fn set_parent_group<C: Connection>(
+        group: &mut CollectionDocument<Self>,
+        parent_group: Option<u64>,
+        admin: &C,
+    ) -> Result<(), crate::Error> {
+        let mut ancestor = parent_group;
+        let mut visited = HashSet::new();
+        while let Some(id) = ancestor {
+            ancestor = if Self::check_ancestor(group.header.id, id, &mut visited)? {
+                Self::get(&id, admin)?
+                    .ok_or_else(|| Self::group_not_found(id))?
+                    .contents
+                    .parent_group
+            } else {
+                None
+            };
+        }
+
+        group.contents.parent_group = parent_group;
+        group.update(admin)
+    }
+
+    /// Sets the parent of `group` to `parent_group` and saves the group.
+    ///
+    /// See [`Self::set_parent_group()`] for more information.
+    pub async fn set_parent_group_async<C: AsyncConnection>(
+        group: &mut CollectionDocument<Self>,
+        parent_group: Option<u64>,
+        admin: &C,
+    ) -> Result<(), crate::Error> {
+        let mut ancestor = parent_group;
+        let mut visited = HashSet::new();
+        while let Some(id) = ancestor {
+            ancestor = if Self::check_ancestor(group.header.id, id, &mut visited)? {
+                Self::get_async(&id, admin)
+                    .await?
+                    .ok_or_else(|| Self::group_not_found(id))?
+                    .contents
+                    .parent_group
+            } else {
+                None
+            };
+        }
+
+        group.contents.parent_group = parent_group;
+        group.update_async(admin).await
+    }
+
+    /// Returns an error if `ancestor` is `group_id`. Returns false if
+    /// `ancestor` has already been visited.
+    fn check_ancestor(
+        group_id: u64,
+        ancestor: u64,
+        visited: &mut HashSet<u64>,
+    ) -> Result<bool, crate::Error> {
+        if ancestor == group_id {
+            Err(crate::Error::PermissionGroupCycle(group_id))
+        } else {
+            Ok(visited.insert(ancestor))
+        }
+    }
+
+    fn group_not_found(id: u64) -> crate::Error {
+        crate::Error::DocumentNotFound(Self::collection_name(), Box::new(DocumentId::from_u64(id)))
+    }
+
+    /// Retrieves the groups with `ids` along with all of their ancestors. Each
+    /// group is returned once, even if it is reachable through multiple
+    /// groups. Groups that don't exist are skipped.
+    pub(crate) fn get_with_ancestors<C: Connection>(
+        ids: impl IntoIterator<Item = u64>,
+        admin: &C,
+    ) -> Result<Vec<CollectionDocument<Self>>, crate::Error> {
+        let mut visited = HashSet::new();
+        let mut groups = Vec::new();
+        let mut pending = ids
+            .into_iter()
+            .filter(|id| visited.insert(*id))
+            .collect::<Vec<_>>();
+        while !pending.is_empty() {
+            let found = Self::get_multiple(&pending, admin)?;
+            pending = found
+                .iter()
+                .filter_map(|group| group.contents.parent_group)
+                .filter(|id| visited.insert(*id))
+                .collect();
+            groups.extend(found);
+        }
+        Ok(groups)
+    }
 }
 
 impl NamedCollection for PermissionGroup {
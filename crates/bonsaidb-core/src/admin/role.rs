@@ -55,7 +55,8 @@ impl Role {
         admin: &C,
         inherit_permissions: &Permissions,
     ) -> Result<Permissions, crate::Error> {
-        let groups = group::PermissionGroup::get_multiple(&self.groups, admin)?;
+        let groups =
+            group::PermissionGroup::get_with_ancestors(self.groups.iter().copied(), admin)?;
 
         // Combine the permissions from all the groups into one.
         let merged_permissions = Permissions::merged(
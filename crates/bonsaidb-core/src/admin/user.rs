@@ -67,7 +67,7 @@ impl User {
         let role_groups = if self.roles.is_empty() {
             Vec::default()
         } else {
-            let roles = role::Role::get_multiple(self.groups.iter(), admin)?;
+            let roles = role::Role::get_multiple(self.roles.iter(), admin)?;
             roles
                 .into_iter()
                 .flat_map(|doc| doc.contents.groups)
@@ -76,12 +76,11 @@ impl User {
         };
         // Retrieve all of the groups.
         let groups = if role_groups.is_empty() {
-            group::PermissionGroup::get_multiple(self.groups.iter(), admin)?
+            group::PermissionGroup::get_with_ancestors(self.groups.iter().copied(), admin)?
         } else {
             let mut all_groups = role_groups;
             all_groups.extend(self.groups.iter().copied());
-            all_groups.dedup();
-            group::PermissionGroup::get_multiple(&all_groups, admin)?
+            group::PermissionGroup::get_with_ancestors(all_groups, admin)?
         };
 
         // Combine the permissions from all the groups into one.
@@ -49,6 +49,13 @@ pub trait Api: Serialize + for<'de> Deserialize<'de> + Send + Sync + Debug + 'st
 
     /// Returns the unique name of this api.
     fn name() -> ApiName;
+
+    /// Returns the name of the database this request operates on, if any.
+    /// This is used to describe requests, such as to a client's
+    /// `RequestInterceptor`, and does not affect how requests are handled.
+    fn database(&self) -> Option<&str> {
+        None
+    }
 }
 /// An Error type that can be used in within an [`Api`] definition.
 ///
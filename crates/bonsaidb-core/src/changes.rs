@@ -0,0 +1,577 @@
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use arc_bytes::serde::Bytes;
+use futures::stream::BoxStream;
+use futures::{future, Stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::connection::{AsyncConnection, AsyncLowLevelConnection, Connection, LowLevelConnection};
+use crate::document::{CollectionHeader, DocumentId, Header, OwnedDocument};
+use crate::pubsub::{AsyncPubSub, AsyncSubscriber, PubSub, Subscriber};
+use crate::schema::{CollectionName, SerializedCollection};
+use crate::transaction::{ChangedDocument, Executed};
+use crate::Error;
+
+/// The prefix of every [change feed topic](change_feed_topic).
+const CHANGE_FEED_TOPIC_PREFIX: &str = "bonsaidb.changes.";
+
+/// Returns the `PubSub` topic that changes to documents in `collection` are
+/// published to.
+///
+/// Change events are published by the database as it executes transactions,
+/// and each change is only delivered to subscribers whose session is allowed
+/// to get the changed document. Change feed topics are reserved: publishing to
+/// one returns [`Error::ReservedTopic`].
+#[must_use]
+pub fn change_feed_topic(collection: &CollectionName) -> String {
+    format!("{CHANGE_FEED_TOPIC_PREFIX}{collection}")
+}
+
+/// Returns true if the serialized `topic` is a [change feed
+/// topic](change_feed_topic).
+///
+/// Topics that aren't serialized strings are checked using their bytes, as
+/// they are matched against prefix subscriptions using their bytes.
+#[must_use]
+pub fn is_change_feed_topic(topic: &[u8]) -> bool {
+    match pot::from_slice::<String>(topic) {
+        Ok(topic) => topic.starts_with(CHANGE_FEED_TOPIC_PREFIX),
+        Err(_) => topic.starts_with(CHANGE_FEED_TOPIC_PREFIX.as_bytes()),
+    }
+}
+
+/// The kind of change made to a document.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum ChangeOperation {
+    /// The document was inserted.
+    Insert,
+    /// The document was updated or overwritten.
+    Update,
+    /// The document was deleted.
+    Delete,
+}
+
+/// A change to a single document, as published to a collection's
+/// [change feed topic](change_feed_topic).
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Change {
+    /// The id of the transaction that made this change.
+    pub sequence: u64,
+    /// The kind of change made.
+    pub operation: ChangeOperation,
+    /// The id of the changed document.
+    pub id: DocumentId,
+    /// The header of the document. For deletes, this is the header of the
+    /// document before it was deleted. This is `None` for deletes that were
+    /// replayed from the transaction log.
+    pub header: Option<Header>,
+    /// The contents of the document after the change. This is `None` for
+    /// deletes.
+    pub contents: Option<Bytes>,
+}
+
+/// A change to a document in the collection `C`, returned from
+/// [`Connection::watch_collection()`] and
+/// [`AsyncConnection::watch_collection()`].
+#[derive(Clone, Debug)]
+pub struct ChangeEvent<C>
+where
+    C: SerializedCollection,
+{
+    /// The id of the transaction that made this change. Pass this to
+    /// [`WatchCollection::since_sequence()`] or
+    /// [`AsyncWatchCollection::since_sequence()`] to resume watching after
+    /// this event.
+    pub sequence: u64,
+    /// The kind of change made.
+    pub operation: ChangeOperation,
+    /// The id of the changed document.
+    pub id: C::PrimaryKey,
+    /// The header of the document. For deletes, this is the header of the
+    /// document before it was deleted. This is `None` for deletes that were
+    /// replayed from the transaction log.
+    pub header: Option<CollectionHeader<C::PrimaryKey>>,
+    /// The contents of the document after the change. This is `None` for
+    /// deletes.
+    pub contents: Option<C::Contents>,
+}
+
+impl<C> TryFrom<Change> for ChangeEvent<C>
+where
+    C: SerializedCollection,
+{
+    type Error = Error;
+
+    fn try_from(change: Change) -> Result<Self, Self::Error> {
+        Ok(Self {
+            sequence: change.sequence,
+            operation: change.operation,
+            id: change.id.deserialize()?,
+            header: change.header.map(CollectionHeader::try_from).transpose()?,
+            contents: change
+                .contents
+                .map(|contents| C::deserialize(&contents))
+                .transpose()?,
+        })
+    }
+}
+
+/// Watches a collection for changes. Returned from
+/// [`Connection::watch_collection()`].
+///
+/// This type implements [`Iterator`]. Nothing is subscribed to until the
+/// first change is requested, and requesting a change blocks until one is
+/// available.
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct WatchCollection<'a, Cn, C>
+where
+    Cn: PubSub,
+{
+    state: BlockingWatchState<'a, Cn>,
+    _collection: PhantomData<C>,
+}
+
+enum BlockingWatchState<'a, Cn>
+where
+    Cn: PubSub,
+{
+    Pending(WatchOptions<'a, Cn>),
+    Watching {
+        subscriber: Cn::Subscriber,
+        replayed: std::vec::IntoIter<Change>,
+        last_replayed: Option<u64>,
+        operations: Option<Vec<ChangeOperation>>,
+    },
+    Finished,
+}
+
+impl<'a, Cn, C> WatchCollection<'a, Cn, C>
+where
+    Cn: PubSub,
+{
+    pub(crate) const fn new(connection: &'a Cn) -> Self {
+        Self {
+            state: BlockingWatchState::Pending(WatchOptions::new(connection)),
+            _collection: PhantomData,
+        }
+    }
+
+    fn options(&mut self) -> &mut WatchOptions<'a, Cn> {
+        if let BlockingWatchState::Pending(options) = &mut self.state {
+            options
+        } else {
+            unreachable!("Attempted to use after watching began.")
+        }
+    }
+
+    /// Only returns changes whose operation is in `operations`.
+    pub fn operations(mut self, operations: impl IntoIterator<Item = ChangeOperation>) -> Self {
+        self.options().operations = Some(operations.into_iter().collect());
+        self
+    }
+
+    /// Resumes watching after the change with `sequence`, which is the
+    /// [`ChangeEvent::sequence`] of the last event that was processed.
+    ///
+    /// See [`AsyncWatchCollection::since_sequence()`] for more information.
+    pub fn since_sequence(mut self, sequence: u64) -> Self {
+        self.options().since_sequence = Some(sequence);
+        self
+    }
+}
+
+impl<'a, Cn, C> WatchCollection<'a, Cn, C>
+where
+    Cn: Connection + PubSub,
+    C: SerializedCollection,
+{
+    fn start(options: WatchOptions<'a, Cn>) -> Result<BlockingWatchState<'a, Cn>, Error> {
+        let WatchOptions {
+            connection,
+            operations,
+            since_sequence,
+        } = options;
+        let collection = C::collection_name();
+        // Subscribe before replaying so that no changes are missed between
+        // reading the transaction log and receiving new changes.
+        let subscriber = connection.create_subscriber()?;
+        subscriber.subscribe_to(&change_feed_topic(&collection))?;
+
+        let replayed = match since_sequence {
+            Some(sequence) => replay_changes_blocking(connection, &collection, sequence)?,
+            None => Vec::new(),
+        };
+        let last_replayed = replayed
+            .last()
+            .map(|change| change.sequence)
+            .or(since_sequence);
+        Ok(BlockingWatchState::Watching {
+            subscriber,
+            replayed: replayed.into_iter(),
+            last_replayed,
+            operations,
+        })
+    }
+
+    fn next_change(&mut self) -> Option<Result<Change, Error>> {
+        loop {
+            match &mut self.state {
+                BlockingWatchState::Pending(_) => {
+                    let BlockingWatchState::Pending(options) =
+                        std::mem::replace(&mut self.state, BlockingWatchState::Finished)
+                    else {
+                        unreachable!("state was just matched")
+                    };
+                    self.state = match Self::start(options) {
+                        Ok(state) => state,
+                        Err(err) => return Some(Err(err)),
+                    };
+                }
+                BlockingWatchState::Watching {
+                    subscriber,
+                    replayed,
+                    last_replayed,
+                    operations,
+                } => {
+                    let change = if let Some(change) = replayed.next() {
+                        change
+                    } else {
+                        let change = subscriber
+                            .receiver()
+                            .receive()
+                            .map_err(|err| Error::other("change feed", err))
+                            .and_then(|message| message.payload::<Change>().map_err(Error::from));
+                        match change {
+                            Ok(change)
+                                if last_replayed.map_or(true, |last| change.sequence > last) =>
+                            {
+                                change
+                            }
+                            Ok(_) => continue,
+                            Err(err) => {
+                                self.state = BlockingWatchState::Finished;
+                                return Some(Err(err));
+                            }
+                        }
+                    };
+                    if operations
+                        .as_ref()
+                        .map_or(true, |operations| operations.contains(&change.operation))
+                    {
+                        return Some(Ok(change));
+                    }
+                }
+                BlockingWatchState::Finished => return None,
+            }
+        }
+    }
+}
+
+impl<'a, Cn, C> Iterator for WatchCollection<'a, Cn, C>
+where
+    Cn: Connection + PubSub,
+    C: SerializedCollection,
+{
+    type Item = Result<ChangeEvent<C>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_change()
+            .map(|change| change.and_then(ChangeEvent::try_from))
+    }
+}
+
+/// Watches a collection for changes. Returned from
+/// [`AsyncConnection::watch_collection()`].
+///
+/// This type implements [`Stream`]. Nothing is subscribed to until the stream
+/// is first polled.
+#[must_use = "streams do nothing unless polled"]
+pub struct AsyncWatchCollection<'a, Cn, C>
+where
+    C: SerializedCollection,
+{
+    state: WatchState<'a, Cn, C>,
+}
+
+enum WatchState<'a, Cn, C>
+where
+    C: SerializedCollection,
+{
+    Pending(Option<WatchOptions<'a, Cn>>),
+    Streaming(BoxStream<'a, Result<ChangeEvent<C>, Error>>),
+}
+
+struct WatchOptions<'a, Cn> {
+    connection: &'a Cn,
+    operations: Option<Vec<ChangeOperation>>,
+    since_sequence: Option<u64>,
+}
+
+impl<'a, Cn> WatchOptions<'a, Cn> {
+    const fn new(connection: &'a Cn) -> Self {
+        Self {
+            connection,
+            operations: None,
+            since_sequence: None,
+        }
+    }
+}
+
+impl<'a, Cn, C> AsyncWatchCollection<'a, Cn, C>
+where
+    C: SerializedCollection,
+{
+    pub(crate) const fn new(connection: &'a Cn) -> Self {
+        Self {
+            state: WatchState::Pending(Some(WatchOptions::new(connection))),
+        }
+    }
+
+    fn options(&mut self) -> &mut WatchOptions<'a, Cn> {
+        if let WatchState::Pending(Some(options)) = &mut self.state {
+            options
+        } else {
+            unreachable!("Attempted to use after streaming began.")
+        }
+    }
+
+    /// Only returns changes whose operation is in `operations`.
+    pub fn operations(mut self, operations: impl IntoIterator<Item = ChangeOperation>) -> Self {
+        self.options().operations = Some(operations.into_iter().collect());
+        self
+    }
+
+    /// Resumes watching after the change with `sequence`, which is the
+    /// [`ChangeEvent::sequence`] of the last event that was processed.
+    ///
+    /// Changes made after `sequence` are replayed from the transaction log
+    /// before new changes are returned. Replayed events reflect the current
+    /// state of each document rather than its state at the time of the
+    /// change, and are returned as [`ChangeOperation::Insert`] if the document
+    /// has only ever been written once. Documents that have since been deleted
+    /// are only returned by the event for their deletion.
+    pub fn since_sequence(mut self, sequence: u64) -> Self {
+        self.options().since_sequence = Some(sequence);
+        self
+    }
+}
+
+impl<'a, Cn, C> Stream for AsyncWatchCollection<'a, Cn, C>
+where
+    Cn: AsyncConnection + AsyncPubSub,
+    Cn::Subscriber: 'a,
+    C: SerializedCollection + 'a,
+{
+    type Item = Result<ChangeEvent<C>, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match &mut self.state {
+                WatchState::Pending(options) => {
+                    let options = options
+                        .take()
+                        .expect("pending state should always have options");
+                    self.state = WatchState::Streaming(watch::<Cn, C>(options));
+                }
+                WatchState::Streaming(stream) => return stream.poll_next_unpin(cx),
+            }
+        }
+    }
+}
+
+fn watch<'a, Cn, C>(options: WatchOptions<'a, Cn>) -> BoxStream<'a, Result<ChangeEvent<C>, Error>>
+where
+    Cn: AsyncConnection + AsyncPubSub,
+    Cn::Subscriber: 'a,
+    C: SerializedCollection + 'a,
+{
+    let WatchOptions {
+        connection,
+        operations,
+        since_sequence,
+    } = options;
+    futures::stream::once(async move {
+        let collection = C::collection_name();
+        // Subscribe before replaying so that no changes are missed between
+        // reading the transaction log and receiving new changes.
+        let subscriber = connection.create_subscriber().await?;
+        subscriber
+            .subscribe_to(&change_feed_topic(&collection))
+            .await?;
+
+        let replayed = match since_sequence {
+            Some(sequence) => replay_changes(connection, &collection, sequence).await?,
+            None => Vec::new(),
+        };
+        let last_replayed = replayed
+            .last()
+            .map(|change| change.sequence)
+            .or(since_sequence);
+
+        let live = futures::stream::try_unfold(subscriber, |subscriber| async move {
+            let message = subscriber
+                .receiver()
+                .receive_async()
+                .await
+                .map_err(|err| Error::other("change feed", err))?;
+            let change = message.payload::<Change>()?;
+            Ok(Some((change, subscriber)))
+        })
+        .try_filter(move |change| {
+            future::ready(last_replayed.map_or(true, |last| change.sequence > last))
+        });
+
+        Ok::<_, Error>(futures::stream::iter(replayed.into_iter().map(Ok)).chain(live))
+    })
+    .try_flatten()
+    .try_filter(move |change| {
+        future::ready(
+            operations
+                .as_ref()
+                .map_or(true, |operations| operations.contains(&change.operation)),
+        )
+    })
+    .and_then(|change| future::ready(ChangeEvent::<C>::try_from(change)))
+    .boxed()
+}
+
+async fn replay_changes<Cn: AsyncConnection>(
+    connection: &Cn,
+    collection: &CollectionName,
+    since_sequence: u64,
+) -> Result<Vec<Change>, Error> {
+    let mut changes = Vec::new();
+    let mut starting_id = since_sequence.checked_add(1);
+    while let Some(start) = starting_id {
+        let executed = connection
+            .list_executed_transactions(Some(start), None)
+            .await?;
+        let Some(last) = executed.last() else {
+            break;
+        };
+        starting_id = last.id.checked_add(1);
+
+        for transaction in &executed {
+            let changed = changed_in_collection(transaction, collection);
+            let ids = ids_to_fetch(&changed);
+            let current = if ids.is_empty() {
+                Vec::new()
+            } else {
+                connection
+                    .get_multiple_from_collection(&ids, collection)
+                    .await?
+            };
+            push_replayed_changes(&mut changes, transaction.id, &changed, current);
+        }
+    }
+
+    Ok(changes)
+}
+
+fn replay_changes_blocking<Cn: Connection>(
+    connection: &Cn,
+    collection: &CollectionName,
+    since_sequence: u64,
+) -> Result<Vec<Change>, Error> {
+    let mut changes = Vec::new();
+    let mut starting_id = since_sequence.checked_add(1);
+    while let Some(start) = starting_id {
+        let executed = connection.list_executed_transactions(Some(start), None)?;
+        let Some(last) = executed.last() else {
+            break;
+        };
+        starting_id = last.id.checked_add(1);
+
+        for transaction in &executed {
+            changes.extend(changes_in_transaction(connection, transaction, collection)?);
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Returns the changes `transaction` made to documents in `collection`,
+/// loading the current contents of each changed document from `connection`.
+fn changes_in_transaction<Cn: Connection>(
+    connection: &Cn,
+    transaction: &Executed,
+    collection: &CollectionName,
+) -> Result<Vec<Change>, Error> {
+    let changed = changed_in_collection(transaction, collection);
+    let ids = ids_to_fetch(&changed);
+    let current = if ids.is_empty() {
+        Vec::new()
+    } else {
+        connection.get_multiple_from_collection(&ids, collection)?
+    };
+    let mut changes = Vec::new();
+    push_replayed_changes(&mut changes, transaction.id, &changed, current);
+    Ok(changes)
+}
+
+/// Returns the documents in `collection` that were changed by `transaction`.
+fn changed_in_collection<'a>(
+    transaction: &'a Executed,
+    collection: &CollectionName,
+) -> Vec<&'a ChangedDocument> {
+    transaction
+        .changes
+        .documents()
+        .map(|documents| {
+            documents
+                .iter()
+                .filter(|(changed_collection, _)| *changed_collection == collection)
+                .map(|(_, changed)| changed)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the ids of the documents whose current state must be loaded to
+/// replay `changed`.
+fn ids_to_fetch(changed: &[&ChangedDocument]) -> Vec<DocumentId> {
+    changed
+        .iter()
+        .filter(|changed| !changed.deleted)
+        .map(|changed| changed.id.clone())
+        .collect()
+}
+
+fn push_replayed_changes(
+    changes: &mut Vec<Change>,
+    sequence: u64,
+    changed: &[&ChangedDocument],
+    current: Vec<OwnedDocument>,
+) {
+    let current = current
+        .into_iter()
+        .map(|document| (document.header.id.clone(), document))
+        .collect::<HashMap<_, _>>();
+    for changed in changed {
+        if changed.deleted {
+            changes.push(Change {
+                sequence,
+                operation: ChangeOperation::Delete,
+                id: changed.id.clone(),
+                header: None,
+                contents: None,
+            });
+        } else if let Some(document) = current.get(&changed.id) {
+            let operation = if document.header.revision.id == 0 {
+                ChangeOperation::Insert
+            } else {
+                ChangeOperation::Update
+            };
+            changes.push(Change {
+                sequence,
+                operation,
+                id: changed.id.clone(),
+                header: Some(document.header.clone()),
+                contents: Some(document.contents.clone()),
+            });
+        }
+    }
+}
@@ -1,4 +1,6 @@
 use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
 use std::convert::Infallible;
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
@@ -9,7 +11,8 @@ use actionable::{Action, Identifier};
 use arc_bytes::serde::Bytes;
 use async_trait::async_trait;
 use futures::future::BoxFuture;
-use futures::{Future, FutureExt};
+use futures::stream::BoxStream;
+use futures::{Future, FutureExt, Stream, StreamExt, TryStreamExt};
 use serde::{Deserialize, Serialize};
 use zeroize::Zeroize;
 
@@ -17,20 +20,26 @@ use crate::admin::{Role, User};
 use crate::document::{
     CollectionDocument, CollectionHeader, Document, HasHeader, Header, OwnedDocument,
 };
+use crate::key::time::TimestampAsNanoseconds;
 use crate::key::{ByteSource, IntoPrefixRange, Key, KeyEncoding, KeyKind, KeyVisitor};
-use crate::permissions::Permissions;
-use crate::schema::view::map::{MappedDocuments, ViewMappings as ViewMappingsCurrent};
+use crate::limits::VIEW_QUERY_STREAM_CHUNK_SIZE;
+use crate::permissions::{Permissions, Statement};
+use crate::schema::view::map::{
+    self, CollectionMap, MappedDocuments, ViewMapping, ViewMappings as ViewMappingsCurrent,
+};
 use crate::schema::{
-    self, MappedValue, Nameable, NamedReference, Schema, SchemaName, SchemaSummary,
-    SerializedCollection,
+    self, CollectionName, MappedValue, Nameable, NamedReference, Schema, SchemaName, SchemaSummary,
+    SerializedCollection, ViewName,
 };
 use crate::{transaction, Error};
 
 mod has_session;
 mod lowlevel;
+mod search;
 
 pub use self::has_session::HasSession;
 pub use self::lowlevel::{AsyncLowLevelConnection, HasSchema, LowLevelConnection};
+pub use self::search::{AsyncSearch, Search};
 
 /// A connection to a database's [`Schema`](schema::Schema), giving access to
 /// [`Collection`s](crate::schema::Collection) and
@@ -54,6 +63,15 @@ pub trait Connection: LowLevelConnection + Sized + Send + Sync {
         View::new(self)
     }
 
+    /// Searches the full-text [`SearchIndex`](schema::view::search::SearchIndex)
+    /// `V` for documents containing the terms in `query`.
+    fn search<'a, V: schema::view::search::SearchIndex>(
+        &'a self,
+        query: &'a str,
+    ) -> Search<'a, Self, V> {
+        Search::new(self, query)
+    }
+
     /// Lists [executed transactions](transaction::Executed) from this
     /// [`Schema`](schema::Schema). By default, a maximum of 1000 entries will
     /// be returned, but that limit can be overridden by setting `result_limit`.
@@ -67,8 +85,87 @@ pub trait Connection: LowLevelConnection + Sized + Send + Sync {
     ) -> Result<Vec<transaction::Executed>, Error>;
 
     /// Fetches the last transaction id that has been committed, if any.
+    ///
+    /// Transaction ids increase monotonically and are persisted with the
+    /// database, which makes this useful for determining whether anything
+    /// has changed since a previous call. The returned id can be passed to
+    /// [`Self::list_executed_transactions()`] as `id + 1`, or used as the
+    /// starting point for [`WatchCollection::since_sequence()`](crate::changes::WatchCollection::since_sequence).
+    /// Key-value changes are included once they have been persisted, which
+    /// may be delayed depending on the database's key-value persistence
+    /// configuration.
     fn last_transaction_id(&self) -> Result<Option<u64>, Error>;
 
+    /// Returns an iterator of changes made to documents in the collection
+    /// `C`. Each call to [`Iterator::next()`] blocks until the next change is
+    /// available.
+    ///
+    /// Changes are delivered using [`PubSub`](crate::pubsub::PubSub) messages
+    /// published to [`change_feed_topic()`], which allows watching collections
+    /// over remote connections. Only changes to documents this connection is
+    /// allowed to get are delivered. Only changes made after the first change
+    /// is requested are returned, unless [`WatchCollection::since_sequence()`]
+    /// is used to resume from a previous event.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::changes::ChangeOperation;
+    /// # use bonsaidb_core::connection::Connection;
+    /// # use bonsaidb_core::pubsub::PubSub;
+    /// # fn test_fn<C: Connection + PubSub>(db: &C) -> Result<(), Error> {
+    /// for change in db
+    ///     .watch_collection::<MyCollection>()
+    ///     .operations([ChangeOperation::Insert, ChangeOperation::Update])
+    /// {
+    ///     let change = change?;
+    ///     println!("{:?} {:?}: {:?}", change.operation, change.id, change.contents);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`change_feed_topic()`]: crate::changes::change_feed_topic
+    /// [`WatchCollection::since_sequence()`]: crate::changes::WatchCollection::since_sequence
+    fn watch_collection<C: schema::SerializedCollection>(
+        &self,
+    ) -> crate::changes::WatchCollection<'_, Self, C>
+    where
+        Self: crate::pubsub::PubSub,
+    {
+        crate::changes::WatchCollection::new(self)
+    }
+
+    /// Builds a [`Transaction`](transaction::Transaction) by invoking `build`,
+    /// and applies it if `build` returns `Ok(())`. The operations may affect
+    /// any collection in this database's schema, and are applied atomically:
+    /// if any operation fails, such as an update returning
+    /// [`Error::DocumentConflict`], none of the changes are applied.
+    ///
+    /// No changes are made until `build` returns, and if `build` returns an
+    /// error, the transaction is discarded. When connected to a remote
+    /// database, the operations are sent to the server in a single request.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: &C) -> Result<(), Error> {
+    /// let results = db.transaction(|tx| {
+    ///     MyCollection::default().push_in_transaction(tx)?;
+    ///     MyCollection::default().push_in_transaction(tx)
+    /// })?;
+    /// assert_eq!(results.len(), 2);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn transaction<F>(&self, build: F) -> Result<Vec<transaction::OperationResult>, Error>
+    where
+        F: FnOnce(&mut transaction::Transaction) -> Result<(), Error>,
+    {
+        let mut transaction = transaction::Transaction::new();
+        build(&mut transaction)?;
+        self.apply_transaction(transaction)
+    }
+
     /// Compacts the entire database to reclaim unused disk space.
     ///
     /// This process is done by writing data to a new file and swapping the file
@@ -653,7 +750,8 @@ where
     /// Key filtering criteria.
     pub key: Option<QueryKey<'a, V::Key, Key>>,
 
-    /// The view's data access policy. The default value is [`AccessPolicy::UpdateBefore`].
+    /// The view's data access policy. The default value is returned from
+    /// [`Schematic::default_access_policy()`](schema::Schematic::default_access_policy).
     pub access_policy: AccessPolicy,
 
     /// The sort order of the query.
@@ -662,6 +760,12 @@ where
     /// The maximum number of results to return.
     pub limit: Option<u32>,
 
+    /// When set, only entries with keys that are sorted after this key are
+    /// returned.
+    pub after: Option<&'a V::Key>,
+
+    value_ordering: Option<fn(&V::Value, &V::Value) -> Ordering>,
+
     _view: PhantomData<V>,
 }
 
@@ -672,13 +776,15 @@ where
     Cn: Connection,
     Key: KeyEncoding<V::Key> + PartialEq + ?Sized,
 {
-    const fn new(connection: &'a Cn) -> Self {
+    fn new(connection: &'a Cn) -> Self {
         Self {
             connection,
             key: None,
-            access_policy: AccessPolicy::UpdateBefore,
+            access_policy: connection.schematic().default_access_policy::<V>(),
             sort: Sort::Ascending,
             limit: None,
+            after: None,
+            value_ordering: None,
             _view: PhantomData,
         }
     }
@@ -709,6 +815,8 @@ where
             access_policy: self.access_policy,
             sort: self.sort,
             limit: self.limit,
+            after: self.after,
+            value_ordering: self.value_ordering,
             _view: PhantomData,
         }
     }
@@ -742,6 +850,8 @@ where
             access_policy: self.access_policy,
             sort: self.sort,
             limit: self.limit,
+            after: self.after,
+            value_ordering: self.value_ordering,
             _view: PhantomData,
         }
     }
@@ -772,6 +882,8 @@ where
             access_policy: self.access_policy,
             sort: self.sort,
             limit: self.limit,
+            after: self.after,
+            value_ordering: self.value_ordering,
             _view: PhantomData,
         }
     }
@@ -806,6 +918,8 @@ where
             access_policy: self.access_policy,
             sort: self.sort,
             limit: self.limit,
+            after: self.after,
+            value_ordering: self.value_ordering,
             _view: PhantomData,
         }
     }
@@ -878,6 +992,46 @@ where
         self
     }
 
+    /// Returns the matching mappings ordered by their values rather than their
+    /// keys. The direction is controlled by [`Self::ascending()`] and
+    /// [`Self::descending()`], and mappings with equal values are returned in
+    /// ascending key order. Values that cannot be compared, such as `NaN`, are
+    /// treated as equal.
+    ///
+    /// Views are indexed by key, so sorting by key and applying
+    /// [`Self::limit()`] is performed while scanning the index. Values are
+    /// not indexed: sorting by value loads every mapping that matches the key
+    /// filter, sorts them in memory, and then applies the limit to the number
+    /// of mappings returned. Narrowing the query with a key filter reduces the
+    /// number of mappings that must be loaded.
+    ///
+    /// Because pagination is based on keys, sorting by value is not supported
+    /// by [`Self::query_page()`] or [`Self::query_iter()`].
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// // score is an f32 in this example
+    /// let top_scores = ScoresByRank::entries(&db)
+    ///     .sort_by_value()
+    ///     .descending()
+    ///     .limit(10)
+    ///     .query()?;
+    /// for mapping in top_scores {
+    ///     println!("Rank {} has a score of {:3}", mapping.key, mapping.value);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn sort_by_value(mut self) -> Self
+    where
+        V::Value: PartialOrd,
+    {
+        self.value_ordering = Some(compare_values::<V::Value>);
+        self
+    }
+
     /// Sets the maximum number of results to return.
     ///
     /// ```rust
@@ -895,6 +1049,43 @@ where
         self
     }
 
+    /// Only returns entries with keys that are sorted after `key` in the
+    /// query's sort order. Combined with [`Self::limit()`], this allows
+    /// paginating through a view using the
+    /// [`next_cursor`](ViewPage::next_cursor) returned from
+    /// [`Self::query_page()`].
+    ///
+    /// Because pages are bounded by key rather than by position, entries
+    /// added after the last page was read are returned by later pages rather
+    /// than shifting the contents of the pages.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// let mut page = ScoresByRank::entries(&db).limit(10).query_page()?;
+    /// while let Some(cursor) = page.next_cursor {
+    ///     page = ScoresByRank::entries(&db)
+    ///         .limit(10)
+    ///         .after(&cursor)
+    ///         .query_page()?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub const fn after(mut self, key: &'a V::Key) -> Self {
+        self.after = Some(key);
+        self
+    }
+
+    fn apply_after(mut self) -> Result<Self, Error> {
+        if let Some(after) = self.after.take() {
+            let key = self.key.as_ref().map(QueryKey::serialized).transpose()?;
+            self.key = Some(restrict_after(key, after, self.sort)?.deserialized_into()?);
+        }
+        Ok(self)
+    }
+
     /// Executes the query and retrieves the results.
     ///
     /// ```rust
@@ -909,8 +1100,105 @@ where
     /// # }
     /// ```
     pub fn query(self) -> Result<ViewMappingsCurrent<V>, Error> {
-        self.connection
-            .query::<V, Key>(self.key, self.sort, self.limit, self.access_policy)
+        let mut view = self.apply_after()?;
+        let value_ordering = view.take_value_ordering();
+        let mut mappings =
+            view.connection
+                .query::<V, Key>(view.key, view.sort, view.limit, view.access_policy)?;
+        if let Some(value_ordering) = value_ordering {
+            value_ordering.sort(&mut mappings);
+        }
+        Ok(mappings)
+    }
+
+    /// Removes the value ordering from this query, if one was requested. The
+    /// query is updated to scan the index in ascending key order without a
+    /// limit, and the returned ordering applies the requested sort and limit.
+    fn take_value_ordering(&mut self) -> Option<ValueOrdering<V>> {
+        let compare = self.value_ordering.take()?;
+        let ordering = ValueOrdering {
+            compare,
+            sort: self.sort,
+            limit: self.limit.take(),
+        };
+        self.sort = Sort::Ascending;
+        Some(ordering)
+    }
+
+    /// Executes the query and retrieves a page of results. If more results
+    /// exist after this page, [`ViewPage::next_cursor`] contains the key to
+    /// pass to [`Self::after()`] to retrieve the next page.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// // score is an f32 in this example
+    /// let page = ScoresByRank::entries(&db).limit(10).query_page()?;
+    /// for mapping in &page.mappings {
+    ///     println!("Rank {} has a score of {:3}", mapping.key, mapping.value);
+    /// }
+    /// if let Some(cursor) = page.next_cursor {
+    ///     println!("More results exist after rank {cursor}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_page(mut self) -> Result<ViewPage<V>, Error> {
+        if self.value_ordering.is_some() {
+            return Err(value_ordering_unsupported("query_page()"));
+        }
+        let page_size = self.limit;
+        self.limit = page_size.map(|limit| limit.saturating_add(1));
+        Ok(ViewPage::new(self.query()?, page_size))
+    }
+
+    /// Executes the query and returns an [`Iterator`] of the results.
+    ///
+    /// Rather than loading every matching mapping at once, the mappings are
+    /// requested in chunks containing up to
+    /// [`VIEW_QUERY_STREAM_CHUNK_SIZE`] keys. The next chunk is not requested
+    /// until the previous chunk has been consumed, which keeps memory usage
+    /// bounded.
+    ///
+    /// The results are not a snapshot of the view. Each chunk is requested
+    /// using a separate range query that resumes after the last key of the
+    /// previous chunk, and the access policy is only applied when requesting
+    /// the first chunk. Keys inserted, updated, or removed while the iterator
+    /// is being consumed will be reflected in the chunks that have not been
+    /// requested yet, while changes to keys that have already been returned
+    /// will not be observed. Sorting by value is not supported.
+    ///
+    /// This is the blocking equivalent of [`AsyncView::query_stream()`].
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// // score is an f32 in this example
+    /// for mapping in ScoresByRank::entries(&db).query_iter() {
+    ///     let mapping = mapping?;
+    ///     println!("Rank {} has a score of {:3}", mapping.key, mapping.value);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query_iter(self) -> ViewIter<'a, Cn, V> {
+        if self.value_ordering.is_some() {
+            return ViewIter::new(Err(value_ordering_unsupported("query_iter()")));
+        }
+        let state = self.apply_after().and_then(|view| {
+            Ok(ViewStreamState {
+                connection: view.connection,
+                view: view.connection.schematic().view::<V>()?.view_name(),
+                key: view.key.map(|key| key.serialized()).transpose()?,
+                order: view.sort,
+                remaining: view.limit,
+                access_policy: view.access_policy,
+                chunk_size: Some(VIEW_QUERY_STREAM_CHUNK_SIZE),
+            })
+        });
+        ViewIter::new(state)
     }
 
     /// Executes the query and retrieves the results with the associated [`Document`s](crate::document::OwnedDocument).
@@ -932,12 +1220,18 @@ where
     /// # }
     /// ```
     pub fn query_with_docs(self) -> Result<MappedDocuments<OwnedDocument, V>, Error> {
-        self.connection.query_with_docs::<V, Key>(
-            self.key,
-            self.sort,
-            self.limit,
-            self.access_policy,
-        )
+        let mut view = self.apply_after()?;
+        let value_ordering = view.take_value_ordering();
+        let mut mapped = view.connection.query_with_docs::<V, Key>(
+            view.key,
+            view.sort,
+            view.limit,
+            view.access_policy,
+        )?;
+        if let Some(value_ordering) = value_ordering {
+            value_ordering.sort_documents(&mut mapped);
+        }
+        Ok(mapped)
     }
 
     /// Executes the query and retrieves the results with the associated [`CollectionDocument`s](crate::document::CollectionDocument).
@@ -965,12 +1259,18 @@ where
         V::Collection: SerializedCollection,
         <V::Collection as SerializedCollection>::Contents: std::fmt::Debug,
     {
-        self.connection.query_with_collection_docs::<V, Key>(
-            self.key,
-            self.sort,
-            self.limit,
-            self.access_policy,
-        )
+        let mut view = self.apply_after()?;
+        let value_ordering = view.take_value_ordering();
+        let mut mapped = view.connection.query_with_collection_docs::<V, Key>(
+            view.key,
+            view.sort,
+            view.limit,
+            view.access_policy,
+        )?;
+        if let Some(value_ordering) = value_ordering {
+            value_ordering.sort_documents(&mut mapped);
+        }
+        Ok(mapped)
     }
 
     /// Executes a reduce over the results of the query
@@ -986,12 +1286,17 @@ where
     /// # }
     /// ```
     pub fn reduce(self) -> Result<V::Value, Error> {
-        self.connection
-            .reduce::<V, Key>(self.key, self.access_policy)
+        let view = self.apply_after()?;
+        view.connection
+            .reduce::<V, Key>(view.key, view.access_policy)
     }
 
     /// Executes a reduce over the results of the query, grouping by key.
     ///
+    /// Each key's reduced value is cached by the view as it is updated, so
+    /// only one value per matching key is computed and returned. To group by
+    /// a portion of a key, use a view that emits only that portion as its key.
+    ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
     /// # use bonsaidb_core::connection::Connection;
@@ -1007,8 +1312,9 @@ where
     /// # }
     /// ```
     pub fn reduce_grouped(self) -> Result<GroupedReductions<V>, Error> {
-        self.connection
-            .reduce_grouped::<V, Key>(self.key, self.access_policy)
+        let view = self.apply_after()?;
+        view.connection
+            .reduce_grouped::<V, Key>(view.key, view.access_policy)
     }
 
     /// Deletes all of the associated documents that match this view query.
@@ -1022,8 +1328,29 @@ where
     /// # }
     /// ```
     pub fn delete_docs(self) -> Result<u64, Error> {
-        self.connection
-            .delete_docs::<V, Key>(self.key, self.access_policy)
+        let view = self.apply_after()?;
+        view.connection
+            .delete_docs::<V, Key>(view.key, view.access_policy)
+    }
+
+    /// Describes how this query would be executed without retrieving any
+    /// entries. The view is not updated, regardless of the access policy.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::{Connection, IndexAccess};
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// let plan = ScoresByRank::entries(&db).with_key(&42).explain()?;
+    /// assert_eq!(plan.index_used, IndexAccess::KeyLookup);
+    /// println!("Estimated rows: {}", plan.estimated_rows);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn explain(self) -> Result<QueryPlan, Error> {
+        let mut view = self.apply_after()?;
+        view.take_value_ordering();
+        view.connection
+            .explain::<V, Key>(view.key, view.sort, view.limit)
     }
 }
 
@@ -1055,6 +1382,15 @@ pub trait AsyncConnection: AsyncLowLevelConnection + Sized + Send + Sync {
         AsyncView::new(self)
     }
 
+    /// Searches the full-text [`SearchIndex`](schema::view::search::SearchIndex)
+    /// `V` for documents containing the terms in `query`.
+    fn search<'a, V: schema::view::search::SearchIndex>(
+        &'a self,
+        query: &'a str,
+    ) -> AsyncSearch<'a, Self, V> {
+        AsyncSearch::new(self, query)
+    }
+
     /// Lists [executed transactions](transaction::Executed) from this [`Schema`](schema::Schema). By default, a maximum of
     /// 1000 entries will be returned, but that limit can be overridden by
     /// setting `result_limit`. A hard limit of 100,000 results will be
@@ -1067,8 +1403,93 @@ pub trait AsyncConnection: AsyncLowLevelConnection + Sized + Send + Sync {
     ) -> Result<Vec<transaction::Executed>, Error>;
 
     /// Fetches the last transaction id that has been committed, if any.
+    ///
+    /// Transaction ids increase monotonically and are persisted with the
+    /// database, which makes this useful for determining whether anything
+    /// has changed since a previous call. The returned id can be passed to
+    /// [`Self::list_executed_transactions()`] as `id + 1`, or used as the
+    /// starting point for [`AsyncWatchCollection::since_sequence()`](crate::changes::AsyncWatchCollection::since_sequence).
+    /// Key-value changes are included once they have been persisted, which
+    /// may be delayed depending on the database's key-value persistence
+    /// configuration.
     async fn last_transaction_id(&self) -> Result<Option<u64>, Error>;
 
+    /// Returns a stream of changes made to documents in the collection `C`.
+    ///
+    /// Changes are delivered using [`PubSub`](crate::pubsub::AsyncPubSub)
+    /// messages published to [`change_feed_topic()`], which allows
+    /// watching collections over remote connections. Only changes to
+    /// documents this connection is allowed to get are delivered. Only changes
+    /// made after the stream is first polled are returned, unless
+    /// [`AsyncWatchCollection::since_sequence()`] is used to resume from a
+    /// previous event.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::changes::ChangeOperation;
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # use bonsaidb_core::pubsub::AsyncPubSub;
+    /// # use futures::StreamExt;
+    /// # fn test_fn<C: AsyncConnection + AsyncPubSub>(db: &C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let mut changes = db
+    ///     .watch_collection::<MyCollection>()
+    ///     .operations([ChangeOperation::Insert, ChangeOperation::Update]);
+    /// while let Some(change) = changes.next().await {
+    ///     let change = change?;
+    ///     println!("{:?} {:?}: {:?}", change.operation, change.id, change.contents);
+    /// }
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    ///
+    /// [`change_feed_topic()`]: crate::changes::change_feed_topic
+    /// [`AsyncWatchCollection::since_sequence()`]: crate::changes::AsyncWatchCollection::since_sequence
+    fn watch_collection<C: schema::SerializedCollection>(
+        &self,
+    ) -> crate::changes::AsyncWatchCollection<'_, Self, C>
+    where
+        Self: crate::pubsub::AsyncPubSub,
+    {
+        crate::changes::AsyncWatchCollection::new(self)
+    }
+
+    /// Builds a [`Transaction`](transaction::Transaction) by invoking `build`,
+    /// and applies it if `build` returns `Ok(())`. The operations may affect
+    /// any collection in this database's schema, and are applied atomically:
+    /// if any operation fails, such as an update returning
+    /// [`Error::DocumentConflict`], none of the changes are applied.
+    ///
+    /// No changes are made until `build` returns, and if `build` returns an
+    /// error, the transaction is discarded. When connected to a remote
+    /// database, the operations are sent to the server in a single request.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: &C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let results = db
+    ///     .transaction(|tx| {
+    ///         MyCollection::default().push_in_transaction(tx)?;
+    ///         MyCollection::default().push_in_transaction(tx)
+    ///     })
+    ///     .await?;
+    /// assert_eq!(results.len(), 2);
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    async fn transaction<F>(&self, build: F) -> Result<Vec<transaction::OperationResult>, Error>
+    where
+        F: FnOnce(&mut transaction::Transaction) -> Result<(), Error> + Send,
+    {
+        let mut transaction = transaction::Transaction::new();
+        build(&mut transaction)?;
+        self.apply_transaction(transaction).await
+    }
+
     /// Compacts the entire database to reclaim unused disk space.
     ///
     /// This process is done by writing data to a new file and swapping the file
@@ -1825,7 +2246,8 @@ where
     /// Key filtering criteria.
     pub key: Option<QueryKey<'a, V::Key, Key>>,
 
-    /// The view's data access policy. The default value is [`AccessPolicy::UpdateBefore`].
+    /// The view's data access policy. The default value is returned from
+    /// [`Schematic::default_access_policy()`](schema::Schematic::default_access_policy).
     pub access_policy: AccessPolicy,
 
     /// The sort order of the query.
@@ -1834,6 +2256,12 @@ where
     /// The maximum number of results to return.
     pub limit: Option<u32>,
 
+    /// When set, only entries with keys that are sorted after this key are
+    /// returned.
+    pub after: Option<&'a V::Key>,
+
+    value_ordering: Option<fn(&V::Value, &V::Value) -> Ordering>,
+
     _view: PhantomData<V>,
 }
 
@@ -1844,13 +2272,15 @@ where
     Key: KeyEncoding<V::Key> + PartialEq + ?Sized,
     V::Key: Borrow<Key> + PartialEq<Key>,
 {
-    const fn new(connection: &'a Cn) -> Self {
+    fn new(connection: &'a Cn) -> Self {
         Self {
             connection,
             key: None,
-            access_policy: AccessPolicy::UpdateBefore,
+            access_policy: connection.schematic().default_access_policy::<V>(),
             sort: Sort::Ascending,
             limit: None,
+            after: None,
+            value_ordering: None,
             _view: PhantomData,
         }
     }
@@ -1887,6 +2317,8 @@ where
             access_policy: self.access_policy,
             sort: self.sort,
             limit: self.limit,
+            after: self.after,
+            value_ordering: self.value_ordering,
             _view: PhantomData,
         }
     }
@@ -1926,6 +2358,8 @@ where
             access_policy: self.access_policy,
             sort: self.sort,
             limit: self.limit,
+            after: self.after,
+            value_ordering: self.value_ordering,
             _view: PhantomData,
         }
     }
@@ -1964,6 +2398,8 @@ where
             access_policy: self.access_policy,
             sort: self.sort,
             limit: self.limit,
+            after: self.after,
+            value_ordering: self.value_ordering,
             _view: PhantomData,
         }
     }
@@ -2004,6 +2440,8 @@ where
             access_policy: self.access_policy,
             sort: self.sort,
             limit: self.limit,
+            after: self.after,
+            value_ordering: self.value_ordering,
             _view: PhantomData,
         }
     }
@@ -2087,7 +2525,16 @@ where
         self
     }
 
-    /// Sets the maximum number of results to return.
+    /// Returns the matching mappings ordered by their values rather than their
+    /// keys. The direction is controlled by [`Self::ascending()`] and
+    /// [`Self::descending()`], and mappings with equal values are returned in
+    /// ascending key order. Values that cannot be compared, such as `NaN`, are
+    /// treated as equal.
+    ///
+    /// Sorting by value requires loading every mapping that matches the key
+    /// filter before applying [`Self::limit()`]. It is not supported by
+    /// [`Self::query_page()`] or [`Self::query_stream()`]. See
+    /// [`View::sort_by_value()`] for more information.
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
@@ -2095,18 +2542,28 @@ where
     /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
     /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
     /// // score is an f32 in this example
-    /// let mappings = ScoresByRank::entries_async(&db).limit(10).query().await?;
-    /// assert!(mappings.len() <= 10);
+    /// for mapping in ScoresByRank::entries_async(&db)
+    ///     .sort_by_value()
+    ///     .descending()
+    ///     .limit(10)
+    ///     .query()
+    ///     .await?
+    /// {
+    ///     println!("Rank {} has a score of {:3}", mapping.key, mapping.value);
+    /// }
     /// # Ok(())
     /// # })
     /// # }
     /// ```
-    pub const fn limit(mut self, maximum_results: u32) -> Self {
-        self.limit = Some(maximum_results);
+    pub const fn sort_by_value(mut self) -> Self
+    where
+        V::Value: PartialOrd,
+    {
+        self.value_ordering = Some(compare_values::<V::Value>);
         self
     }
 
-    /// Executes the query and retrieves the results.
+    /// Sets the maximum number of results to return.
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
@@ -2114,80 +2571,103 @@ where
     /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
     /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
     /// // score is an f32 in this example
-    /// for mapping in ScoresByRank::entries_async(&db).query().await? {
-    ///     println!("Rank {} has a score of {:3}", mapping.key, mapping.value);
-    /// }
+    /// let mappings = ScoresByRank::entries_async(&db).limit(10).query().await?;
+    /// assert!(mappings.len() <= 10);
     /// # Ok(())
     /// # })
     /// # }
     /// ```
-    pub async fn query(self) -> Result<ViewMappingsCurrent<V>, Error> {
-        self.connection
-            .query::<V, Key>(self.key, self.sort, self.limit, self.access_policy)
-            .await
+    pub const fn limit(mut self, maximum_results: u32) -> Self {
+        self.limit = Some(maximum_results);
+        self
     }
 
-    /// Executes the query and retrieves the results with the associated [`Document`s](crate::document::OwnedDocument).
+    /// Only returns entries with keys that are sorted after `key` in the
+    /// query's sort order. Combined with [`Self::limit()`], this allows
+    /// paginating through a view using the
+    /// [`next_cursor`](ViewPage::next_cursor) returned from
+    /// [`Self::query_page()`].
+    ///
+    /// Because pages are bounded by key rather than by position, entries
+    /// added after the last page was read are returned by later pages rather
+    /// than shifting the contents of the pages.
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
     /// # use bonsaidb_core::connection::AsyncConnection;
     /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
     /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
-    /// for mapping in &ScoresByRank::entries_async(&db)
-    ///     .with_key_range(42..=44)
-    ///     .query_with_docs()
-    ///     .await?
-    /// {
-    ///     println!(
-    ///         "Mapping from #{} with rank: {} and score: {}. Document bytes: {:?}",
-    ///         mapping.document.header.id, mapping.key, mapping.value, mapping.document.contents
-    ///     );
+    /// let mut page = ScoresByRank::entries_async(&db)
+    ///     .limit(10)
+    ///     .query_page()
+    ///     .await?;
+    /// while let Some(cursor) = page.next_cursor {
+    ///     page = ScoresByRank::entries_async(&db)
+    ///         .limit(10)
+    ///         .after(&cursor)
+    ///         .query_page()
+    ///         .await?;
     /// }
     /// # Ok(())
     /// # })
     /// # }
     /// ```
-    pub async fn query_with_docs(self) -> Result<MappedDocuments<OwnedDocument, V>, Error> {
-        self.connection
-            .query_with_docs::<V, _>(self.key, self.sort, self.limit, self.access_policy)
-            .await
+    pub const fn after(mut self, key: &'a V::Key) -> Self {
+        self.after = Some(key);
+        self
     }
 
-    /// Executes the query and retrieves the results with the associated [`CollectionDocument`s](crate::document::CollectionDocument).
+    fn apply_after(mut self) -> Result<Self, Error> {
+        if let Some(after) = self.after.take() {
+            let key = self.key.as_ref().map(QueryKey::serialized).transpose()?;
+            self.key = Some(restrict_after(key, after, self.sort)?.deserialized_into()?);
+        }
+        Ok(self)
+    }
+
+    /// Executes the query and retrieves the results.
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
     /// # use bonsaidb_core::connection::AsyncConnection;
     /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
     /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
-    /// for mapping in &ScoresByRank::entries_async(&db)
-    ///     .with_key_range(42..=44)
-    ///     .query_with_collection_docs()
-    ///     .await?
-    /// {
-    ///     println!(
-    ///         "Mapping from #{} with rank: {} and score: {}. Deserialized Contents: {:?}",
-    ///         mapping.document.header.id, mapping.key, mapping.value, mapping.document.contents
-    ///     );
+    /// // score is an f32 in this example
+    /// for mapping in ScoresByRank::entries_async(&db).query().await? {
+    ///     println!("Rank {} has a score of {:3}", mapping.key, mapping.value);
     /// }
     /// # Ok(())
     /// # })
     /// # }
     /// ```
-    pub async fn query_with_collection_docs(
-        self,
-    ) -> Result<MappedDocuments<CollectionDocument<V::Collection>, V>, Error>
-    where
-        V::Collection: SerializedCollection,
-        <V::Collection as SerializedCollection>::Contents: std::fmt::Debug,
-    {
-        self.connection
-            .query_with_collection_docs::<V, _>(self.key, self.sort, self.limit, self.access_policy)
-            .await
+    pub async fn query(self) -> Result<ViewMappingsCurrent<V>, Error> {
+        let mut view = self.apply_after()?;
+        let value_ordering = view.take_value_ordering();
+        let mut mappings: ViewMappingsCurrent<V> =
+            view.stream_in_chunks(None).try_collect().await?;
+        if let Some(value_ordering) = value_ordering {
+            value_ordering.sort(&mut mappings);
+        }
+        Ok(mappings)
     }
 
-    /// Executes a reduce over the results of the query
+    /// Removes the value ordering from this query, if one was requested. The
+    /// query is updated to scan the index in ascending key order without a
+    /// limit, and the returned ordering applies the requested sort and limit.
+    fn take_value_ordering(&mut self) -> Option<ValueOrdering<V>> {
+        let compare = self.value_ordering.take()?;
+        let ordering = ValueOrdering {
+            compare,
+            sort: self.sort,
+            limit: self.limit.take(),
+        };
+        self.sort = Sort::Ascending;
+        Some(ordering)
+    }
+
+    /// Executes the query and retrieves a page of results. If more results
+    /// exist after this page, [`ViewPage::next_cursor`] contains the key to
+    /// pass to [`Self::after()`] to retrieve the next page.
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
@@ -2195,20 +2675,186 @@ where
     /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
     /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
     /// // score is an f32 in this example
-    /// let score = ScoresByRank::entries_async(&db).reduce().await?;
-    /// println!("Average score: {:3}", score);
+    /// let page = ScoresByRank::entries_async(&db)
+    ///     .limit(10)
+    ///     .query_page()
+    ///     .await?;
+    /// for mapping in &page.mappings {
+    ///     println!("Rank {} has a score of {:3}", mapping.key, mapping.value);
+    /// }
+    /// if let Some(cursor) = page.next_cursor {
+    ///     println!("More results exist after rank {cursor}");
+    /// }
     /// # Ok(())
     /// # })
     /// # }
     /// ```
-    pub async fn reduce(self) -> Result<V::Value, Error> {
-        self.connection
-            .reduce::<V, _>(self.key, self.access_policy)
-            .await
+    pub async fn query_page(mut self) -> Result<ViewPage<V>, Error> {
+        if self.value_ordering.is_some() {
+            return Err(value_ordering_unsupported("query_page()"));
+        }
+        let page_size = self.limit;
+        self.limit = page_size.map(|limit| limit.saturating_add(1));
+        Ok(ViewPage::new(self.query().await?, page_size))
+    }
+
+    /// Executes the query and returns a [`Stream`] of the results.
+    ///
+    /// Rather than loading every matching mapping at once, the mappings are
+    /// requested in chunks containing up to
+    /// [`VIEW_QUERY_STREAM_CHUNK_SIZE`] keys. The next chunk is not requested
+    /// until the previous chunk has been consumed, which keeps memory usage
+    /// bounded and prevents the database from producing results faster than
+    /// they are consumed.
+    ///
+    /// The results are not a snapshot of the view. Each chunk is requested
+    /// using a separate range query that resumes after the last key of the
+    /// previous chunk, and the access policy is only applied when requesting
+    /// the first chunk. When connected to a server, each chunk is a separate
+    /// request, and the server holds no cursor between them. Keys inserted,
+    /// updated, or removed while the stream is being consumed will be
+    /// reflected in the chunks that have not been requested yet, while changes
+    /// to keys that have already been returned will not be observed. Use
+    /// [`Self::query()`] when a consistent set of results is required.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # use futures::TryStreamExt;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// // score is an f32 in this example
+    /// let mut mappings = ScoresByRank::entries_async(&db).query_stream();
+    /// while let Some(mapping) = mappings.try_next().await? {
+    ///     println!("Rank {} has a score of {:3}", mapping.key, mapping.value);
+    /// }
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    pub fn query_stream(self) -> ViewStream<'a, V> {
+        if self.value_ordering.is_some() {
+            return ViewStream::new::<Cn>(Err(value_ordering_unsupported("query_stream()")));
+        }
+        self.stream_in_chunks(Some(VIEW_QUERY_STREAM_CHUNK_SIZE))
+    }
+
+    fn stream_in_chunks(self, chunk_size: Option<u32>) -> ViewStream<'a, V> {
+        let state = self.apply_after().and_then(|view| {
+            Ok(ViewStreamState {
+                connection: view.connection,
+                view: view.connection.schematic().view::<V>()?.view_name(),
+                key: view.key.map(|key| key.serialized()).transpose()?,
+                order: view.sort,
+                remaining: view.limit,
+                access_policy: view.access_policy,
+                chunk_size,
+            })
+        });
+        ViewStream::new(state)
+    }
+
+    /// Executes the query and retrieves the results with the associated [`Document`s](crate::document::OwnedDocument).
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// for mapping in &ScoresByRank::entries_async(&db)
+    ///     .with_key_range(42..=44)
+    ///     .query_with_docs()
+    ///     .await?
+    /// {
+    ///     println!(
+    ///         "Mapping from #{} with rank: {} and score: {}. Document bytes: {:?}",
+    ///         mapping.document.header.id, mapping.key, mapping.value, mapping.document.contents
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    pub async fn query_with_docs(self) -> Result<MappedDocuments<OwnedDocument, V>, Error> {
+        let mut view = self.apply_after()?;
+        let value_ordering = view.take_value_ordering();
+        let mut mapped = view
+            .connection
+            .query_with_docs::<V, _>(view.key, view.sort, view.limit, view.access_policy)
+            .await?;
+        if let Some(value_ordering) = value_ordering {
+            value_ordering.sort_documents(&mut mapped);
+        }
+        Ok(mapped)
+    }
+
+    /// Executes the query and retrieves the results with the associated [`CollectionDocument`s](crate::document::CollectionDocument).
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// for mapping in &ScoresByRank::entries_async(&db)
+    ///     .with_key_range(42..=44)
+    ///     .query_with_collection_docs()
+    ///     .await?
+    /// {
+    ///     println!(
+    ///         "Mapping from #{} with rank: {} and score: {}. Deserialized Contents: {:?}",
+    ///         mapping.document.header.id, mapping.key, mapping.value, mapping.document.contents
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    pub async fn query_with_collection_docs(
+        self,
+    ) -> Result<MappedDocuments<CollectionDocument<V::Collection>, V>, Error>
+    where
+        V::Collection: SerializedCollection,
+        <V::Collection as SerializedCollection>::Contents: std::fmt::Debug,
+    {
+        let mut view = self.apply_after()?;
+        let value_ordering = view.take_value_ordering();
+        let mut mapped = view
+            .connection
+            .query_with_collection_docs::<V, _>(view.key, view.sort, view.limit, view.access_policy)
+            .await?;
+        if let Some(value_ordering) = value_ordering {
+            value_ordering.sort_documents(&mut mapped);
+        }
+        Ok(mapped)
+    }
+
+    /// Executes a reduce over the results of the query
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// // score is an f32 in this example
+    /// let score = ScoresByRank::entries_async(&db).reduce().await?;
+    /// println!("Average score: {:3}", score);
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    pub async fn reduce(self) -> Result<V::Value, Error> {
+        let view = self.apply_after()?;
+        view.connection
+            .reduce::<V, _>(view.key, view.access_policy)
+            .await
     }
 
     /// Executes a reduce over the results of the query, grouping by key.
     ///
+    /// Each key's reduced value is cached by the view as it is updated, so
+    /// only one value per matching key is computed and returned. To group by
+    /// a portion of a key, use a view that emits only that portion as its key.
+    ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
     /// # use bonsaidb_core::connection::AsyncConnection;
@@ -2226,8 +2872,9 @@ where
     /// # }
     /// ```
     pub async fn reduce_grouped(self) -> Result<Vec<MappedValue<V::Key, V::Value>>, Error> {
-        self.connection
-            .reduce_grouped::<V, _>(self.key, self.access_policy)
+        let view = self.apply_after()?;
+        view.connection
+            .reduce_grouped::<V, _>(view.key, view.access_policy)
             .await
     }
 
@@ -2244,12 +2891,400 @@ where
     /// # }
     /// ```
     pub async fn delete_docs(self) -> Result<u64, Error> {
-        self.connection
-            .delete_docs::<V, _>(self.key, self.access_policy)
+        let view = self.apply_after()?;
+        view.connection
+            .delete_docs::<V, _>(view.key, view.access_policy)
+            .await
+    }
+
+    /// Describes how this query would be executed without retrieving any
+    /// entries. The view is not updated, regardless of the access policy.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::{AsyncConnection, IndexAccess};
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let plan = ScoresByRank::entries_async(&db)
+    ///     .with_key(&42)
+    ///     .explain()
+    ///     .await?;
+    /// assert_eq!(plan.index_used, IndexAccess::KeyLookup);
+    /// println!("Estimated rows: {}", plan.estimated_rows);
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    pub async fn explain(self) -> Result<QueryPlan, Error> {
+        let mut view = self.apply_after()?;
+        view.take_value_ordering();
+        view.connection
+            .explain::<V, _>(view.key, view.sort, view.limit)
             .await
     }
 }
 
+/// The in-memory ordering requested by [`View::sort_by_value()`] or
+/// [`AsyncView::sort_by_value()`].
+struct ValueOrdering<V: schema::View> {
+    compare: fn(&V::Value, &V::Value) -> Ordering,
+    sort: Sort,
+    limit: Option<u32>,
+}
+
+impl<V: schema::View> ValueOrdering<V> {
+    fn sort(&self, mappings: &mut ViewMappingsCurrent<V>) {
+        // The sort is stable, which keeps mappings with equal values in
+        // ascending key order.
+        mappings.sort_by(|a, b| match self.sort {
+            Sort::Ascending => (self.compare)(&a.value, &b.value),
+            Sort::Descending => (self.compare)(&b.value, &a.value),
+        });
+        if let Some(limit) = self.limit {
+            mappings.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+        }
+    }
+
+    fn sort_documents<D>(&self, mapped: &mut MappedDocuments<D, V>) {
+        self.sort(&mut mapped.mappings);
+        if self.limit.is_some() {
+            let MappedDocuments {
+                mappings,
+                documents,
+            } = mapped;
+            let referenced = mappings
+                .iter()
+                .map(|mapping| &mapping.source.id)
+                .collect::<BTreeSet<_>>();
+            documents.retain(|id, _| referenced.contains(id));
+        }
+    }
+}
+
+fn compare_values<T: PartialOrd>(a: &T, b: &T) -> Ordering {
+    a.partial_cmp(b).unwrap_or(Ordering::Equal)
+}
+
+fn value_ordering_unsupported(function: &str) -> Error {
+    Error::other(
+        "view query",
+        format!("sorting by value is not supported by {function}"),
+    )
+}
+
+/// A page of mappings returned from [`View::query_page()`] or
+/// [`AsyncView::query_page()`].
+pub struct ViewPage<V: schema::SerializedView> {
+    /// The mappings in this page.
+    pub mappings: ViewMappingsCurrent<V>,
+    /// If more results exist after this page, this is the key to pass to
+    /// [`View::after()`] or [`AsyncView::after()`] to retrieve the next page.
+    pub next_cursor: Option<V::Key>,
+}
+
+impl<V: schema::SerializedView> ViewPage<V> {
+    /// Returns a page containing the first `page_size` keys of `mappings`.
+    /// `mappings` should contain one more key than `page_size` if more results
+    /// exist.
+    fn new(mut mappings: ViewMappingsCurrent<V>, page_size: Option<u32>) -> Self {
+        let mut next_cursor = None;
+        if let Some(page_size) = page_size {
+            // Mappings are grouped by key, and the limit is applied to the
+            // number of keys rather than the number of mappings.
+            let mut keys_read = 0;
+            let end = mappings.iter().enumerate().position(|(index, mapping)| {
+                if index == 0 || mappings[index - 1].key != mapping.key {
+                    keys_read += 1;
+                }
+                keys_read > page_size
+            });
+            if let Some(end) = end {
+                mappings.truncate(end);
+                next_cursor = mappings.last().map(|mapping| mapping.key.clone());
+            }
+        }
+        Self {
+            mappings,
+            next_cursor,
+        }
+    }
+}
+
+impl<V: schema::SerializedView> std::fmt::Debug for ViewPage<V>
+where
+    V::Key: std::fmt::Debug,
+    V::Value: std::fmt::Debug,
+    <V::Collection as schema::Collection>::PrimaryKey: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ViewPage")
+            .field("mappings", &self.mappings)
+            .field("next_cursor", &self.next_cursor)
+            .finish()
+    }
+}
+
+/// Restricts `key` to only match keys that are sorted after `after` in
+/// `order`.
+fn restrict_after<K: for<'k> Key<'k>>(
+    key: Option<SerializedQueryKey>,
+    after: &K,
+    order: Sort,
+) -> Result<SerializedQueryKey, Error> {
+    if !K::ORDER_PRESERVING {
+        return Err(Error::KeyNotOrderPreserving(
+            std::any::type_name::<K>().to_string(),
+        ));
+    }
+    let after = after
+        .as_ord_bytes()
+        .map_err(|err| Error::other("key serialization", err))?;
+    let is_after = |key: &[u8]| match order {
+        Sort::Ascending => key > &*after,
+        Sort::Descending => key < &*after,
+    };
+    let after = Bytes::from(after.to_vec());
+    Ok(match key {
+        None => {
+            let mut range = Range {
+                start: Bound::Unbounded,
+                end: Bound::Unbounded,
+            };
+            match order {
+                Sort::Ascending => range.start = Bound::Excluded(after),
+                Sort::Descending => range.end = Bound::Excluded(after),
+            }
+            SerializedQueryKey::Range(range)
+        }
+        Some(SerializedQueryKey::Matches(key)) => {
+            if is_after(&key) {
+                SerializedQueryKey::Matches(key)
+            } else {
+                SerializedQueryKey::Multiple(Vec::new())
+            }
+        }
+        Some(SerializedQueryKey::Multiple(mut keys)) => {
+            keys.retain(|key| is_after(key));
+            SerializedQueryKey::Multiple(keys)
+        }
+        Some(SerializedQueryKey::Range(mut range)) => {
+            let bound = match order {
+                Sort::Ascending => &mut range.start,
+                Sort::Descending => &mut range.end,
+            };
+            // Only replace the existing bound if `after` is more restrictive.
+            let replace = match bound {
+                Bound::Unbounded => true,
+                Bound::Included(existing) | Bound::Excluded(existing) => !is_after(existing),
+            };
+            if replace {
+                *bound = Bound::Excluded(after);
+            }
+            SerializedQueryKey::Range(range)
+        }
+    })
+}
+
+/// A stream of mappings returned from [`AsyncView::query_stream()`].
+///
+/// Each chunk of mappings is requested using its own range query, so the
+/// stream does not observe a consistent snapshot of the view.
+#[must_use = "streams do nothing unless polled"]
+pub struct ViewStream<'a, V: schema::SerializedView> {
+    mappings: BoxStream<'a, Result<ViewMapping<V>, Error>>,
+}
+
+impl<'a, V> ViewStream<'a, V>
+where
+    V: schema::SerializedView,
+{
+    fn new<Cn: AsyncConnection>(state: Result<ViewStreamState<'a, Cn>, Error>) -> Self {
+        let mappings = match state {
+            Ok(state) => futures::stream::try_unfold(Some(state), |state| async move {
+                match state {
+                    Some(state) => state.next_chunk().await.map(Some),
+                    None => Ok(None),
+                }
+            })
+            .map_ok(|chunk| futures::stream::iter(chunk.into_iter().map(deserialize_mapping::<V>)))
+            .try_flatten()
+            .boxed(),
+            Err(err) => futures::stream::once(async { Err(err) }).boxed(),
+        };
+        Self { mappings }
+    }
+}
+
+impl<'a, V> Stream for ViewStream<'a, V>
+where
+    V: schema::SerializedView,
+{
+    type Item = Result<ViewMapping<V>, Error>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.mappings.poll_next_unpin(cx)
+    }
+}
+
+struct ViewStreamState<'a, Cn> {
+    connection: &'a Cn,
+    view: ViewName,
+    key: Option<SerializedQueryKey>,
+    order: Sort,
+    remaining: Option<u32>,
+    access_policy: AccessPolicy,
+    chunk_size: Option<u32>,
+}
+
+impl<'a, Cn> ViewStreamState<'a, Cn> {
+    /// Returns the limit to request the next chunk with.
+    fn chunk_limit(&self) -> Option<u32> {
+        match (self.chunk_size, self.remaining) {
+            (Some(chunk_size), Some(remaining)) => Some(chunk_size.min(remaining)),
+            (chunk_size, remaining) => chunk_size.or(remaining),
+        }
+    }
+
+    /// Advances past `mappings`, which were requested using `limit`. Returns
+    /// false if no more mappings are available.
+    fn advance(&mut self, limit: Option<u32>, mappings: &[map::Serialized]) -> bool {
+        self.access_policy = AccessPolicy::NoUpdate;
+
+        // Mappings are grouped by key, and the limit is applied to the number
+        // of keys rather than the number of mappings.
+        let keys_read =
+            itertools::Itertools::dedup_by(mappings.iter(), |a, b| a.key == b.key).count();
+        let keys_read = u32::try_from(keys_read).unwrap_or(u32::MAX);
+        let last_key = match (limit, mappings.last()) {
+            (Some(limit), Some(last)) if keys_read >= limit => last.key.clone(),
+            _ => return false,
+        };
+        if let Some(remaining) = &mut self.remaining {
+            *remaining = remaining.saturating_sub(keys_read);
+            if *remaining == 0 {
+                return false;
+            }
+        }
+
+        let mut range = match self.key.take() {
+            None => Range {
+                start: Bound::Unbounded,
+                end: Bound::Unbounded,
+            },
+            Some(SerializedQueryKey::Range(range)) => range,
+            // Queries for specific keys are always answered using a single
+            // request.
+            Some(SerializedQueryKey::Matches(_) | SerializedQueryKey::Multiple(_)) => return false,
+        };
+        match self.order {
+            Sort::Ascending => range.start = Bound::Excluded(last_key),
+            Sort::Descending => range.end = Bound::Excluded(last_key),
+        }
+        self.key = Some(SerializedQueryKey::Range(range));
+        true
+    }
+}
+
+impl<'a, Cn: AsyncConnection> ViewStreamState<'a, Cn> {
+    /// Requests the next chunk of mappings, returning the state to request the
+    /// chunk after it, if more mappings may be available.
+    async fn next_chunk(mut self) -> Result<(Vec<map::Serialized>, Option<Self>), Error> {
+        let limit = self.chunk_limit();
+        let mappings = self
+            .connection
+            .query_by_name(
+                &self.view,
+                self.key.clone(),
+                self.order,
+                limit,
+                self.access_policy,
+            )
+            .await?;
+        let more = self.advance(limit, &mappings);
+        Ok((mappings, more.then_some(self)))
+    }
+}
+
+/// An iterator of mappings returned from [`View::query_iter()`]. This is the
+/// blocking equivalent of [`ViewStream`].
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct ViewIter<'a, Cn, V: schema::SerializedView> {
+    state: Option<ViewStreamState<'a, Cn>>,
+    error: Option<Error>,
+    chunk: std::vec::IntoIter<map::Serialized>,
+    _view: PhantomData<V>,
+}
+
+impl<'a, Cn, V> ViewIter<'a, Cn, V>
+where
+    V: schema::SerializedView,
+{
+    fn new(state: Result<ViewStreamState<'a, Cn>, Error>) -> Self {
+        let (state, error) = match state {
+            Ok(state) => (Some(state), None),
+            Err(err) => (None, Some(err)),
+        };
+        Self {
+            state,
+            error,
+            chunk: Vec::new().into_iter(),
+            _view: PhantomData,
+        }
+    }
+}
+
+impl<'a, Cn, V> Iterator for ViewIter<'a, Cn, V>
+where
+    Cn: Connection,
+    V: schema::SerializedView,
+{
+    type Item = Result<ViewMapping<V>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+        loop {
+            if let Some(mapping) = self.chunk.next() {
+                return Some(deserialize_mapping::<V>(mapping));
+            }
+
+            let mut state = self.state.take()?;
+            let limit = state.chunk_limit();
+            match state.connection.query_by_name(
+                &state.view,
+                state.key.clone(),
+                state.order,
+                limit,
+                state.access_policy,
+            ) {
+                Ok(mappings) => {
+                    if state.advance(limit, &mappings) {
+                        self.state = Some(state);
+                    }
+                    self.chunk = mappings.into_iter();
+                }
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+fn deserialize_mapping<V: schema::SerializedView>(
+    mapping: map::Serialized,
+) -> Result<ViewMapping<V>, Error> {
+    Ok(CollectionMap {
+        key: <V::Key as Key>::from_ord_bytes(ByteSource::Borrowed(&mapping.key))
+            .map_err(schema::view::Error::key_serialization)
+            .map_err(Error::from)?,
+        value: V::deserialize(&mapping.value)?,
+        source: mapping.source.try_into()?,
+    })
+}
+
 /// A sort order.
 #[derive(Clone, Copy, Serialize, Deserialize, Debug)]
 pub enum Sort {
@@ -2259,6 +3294,31 @@ pub enum Sort {
     Descending,
 }
 
+/// A description of how a view query would be executed, returned from
+/// [`View::explain()`] and [`AsyncView::explain()`].
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct QueryPlan {
+    /// How the view's index is accessed.
+    pub index_used: IndexAccess,
+    /// The number of view entries that would be read. Each entry contains all
+    /// of the mappings for a single key.
+    pub scanned_entries: u64,
+    /// The estimated number of rows the query would return. Each key found is
+    /// counted once, although a key can have multiple mappings.
+    pub estimated_rows: u64,
+}
+
+/// How a view's index is accessed by a query.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub enum IndexAccess {
+    /// Every entry in the view is scanned.
+    FullScan,
+    /// Only entries within a range of keys are scanned.
+    RangeScan,
+    /// Entries are looked up by their exact keys.
+    KeyLookup,
+}
+
 /// Filters a [`View`] by key.
 #[derive(Clone, Debug)]
 pub enum QueryKey<'k, KOwned, KBorrowed = KOwned>
@@ -2288,11 +3348,20 @@ where
                 .as_ord_bytes()
                 .map_err(|err| Error::other("key serialization", err))
                 .map(|v| SerializedQueryKey::Matches(Bytes::from(v.to_vec()))),
-            Self::Range(range) => Ok(SerializedQueryKey::Range(
-                range
-                    .as_ord_bytes()
-                    .map_err(|err| Error::other("key serialization", err))?,
-            )),
+            Self::Range(range) => {
+                // Ranges are scanned using the encoded bytes, which is only
+                // correct if the encoding preserves ordering.
+                if !KBorrowed::ORDER_PRESERVING {
+                    return Err(Error::KeyNotOrderPreserving(
+                        std::any::type_name::<KOwned>().to_string(),
+                    ));
+                }
+                Ok(SerializedQueryKey::Range(
+                    range
+                        .as_ord_bytes()
+                        .map_err(|err| Error::other("key serialization", err))?,
+                ))
+            }
             Self::Multiple(keys) => {
                 let keys = keys
                     .iter()
@@ -2303,7 +3372,7 @@ where
                     })
                     .collect::<Result<Vec<_>, Error>>()?;
 
-                Ok(SerializedQueryKey::Multiple(keys))
+                Ok(SerializedQueryKey::multiple(keys))
             }
         }
     }
@@ -2318,15 +3387,34 @@ pub enum SerializedQueryKey {
     /// Matches all entires with keys in the range provided.
     Range(Range<Bytes>),
 
-    /// Matches all entries that have keys that are included in the set provided.
+    /// Matches all entries that have keys that are included in the set
+    /// provided. Use [`SerializedQueryKey::multiple()`] to ensure the keys are
+    /// sorted and unique.
     Multiple(Vec<Bytes>),
 }
 
 impl SerializedQueryKey {
+    /// Returns a [`SerializedQueryKey::Multiple`] that matches `keys`. The keys
+    /// are sorted, and duplicate keys are removed.
+    #[must_use]
+    pub fn multiple(mut keys: Vec<Bytes>) -> Self {
+        keys.sort();
+        keys.dedup();
+        Self::Multiple(keys)
+    }
+
     /// Deserializes the bytes into `K` via the [`Key`] trait.
     pub fn deserialized<K: for<'k> Key<'k> + PartialEq>(
         &self,
     ) -> Result<QueryKey<'static, K>, Error> {
+        self.deserialized_into()
+    }
+
+    fn deserialized_into<'a, K, KBorrowed>(&self) -> Result<QueryKey<'a, K, KBorrowed>, Error>
+    where
+        K: for<'k> Key<'k> + Borrow<KBorrowed> + PartialEq<KBorrowed>,
+        KBorrowed: PartialEq + ?Sized,
+    {
         match self {
             Self::Matches(key) => K::from_ord_bytes(ByteSource::Borrowed(key.as_ref()))
                 .map_err(|err| Error::other("key serialization", err))
@@ -2507,7 +3595,7 @@ impl Range<Bytes> {
     ) -> Result<Range<T>, <T as KeyEncoding<T>>::Error> {
         Ok(Range {
             start: self.start.deserialize()?,
-            end: self.start.deserialize()?,
+            end: self.end.deserialize()?,
         })
     }
 }
@@ -2937,7 +4025,9 @@ where
 /// Changes how the view's outdated data will be treated.
 #[derive(Copy, Clone, Serialize, Deserialize, Debug)]
 pub enum AccessPolicy {
-    /// Update any changed documents before returning a response.
+    /// Update any changed documents before returning a response. The results
+    /// reflect every transaction executed before the query, allowing changes
+    /// to be read immediately after they are written.
     UpdateBefore,
 
     /// Return the results, which may be out-of-date, and start an update job in
@@ -2984,10 +4074,77 @@ pub trait StorageConnection: HasSession + Sized + Send + Sync {
         self.database::<DB>(name)
     }
 
+    /// Creates a database named `name` with the `Schema` provided. Every
+    /// session that accesses the database is granted `permissions` in addition
+    /// to its own permissions, for operations performed on this database.
+    ///
+    /// The permissions are stored alongside the database's record, so there is
+    /// never a point where the database exists without them. If the database
+    /// already exists and `only_if_needed` is true, its permissions are left
+    /// unchanged.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::InvalidDatabaseName`]: `name` must begin with an alphanumeric
+    ///   character (`[a-zA-Z0-9]`), and all remaining characters must be
+    ///   alphanumeric, a period (`.`), or a hyphen (`-`).
+    /// * [`Error::DatabaseNameAlreadyTaken`]: `name` was already used for a
+    ///   previous database name. Returned if `only_if_needed` is false.
+    fn create_database_with_permissions<DB: Schema>(
+        &self,
+        name: &str,
+        only_if_needed: bool,
+        permissions: Vec<Statement>,
+    ) -> Result<Self::Database, crate::Error> {
+        self.create_database_with_schema_and_permissions(
+            name,
+            DB::schema_name(),
+            only_if_needed,
+            permissions,
+        )?;
+        self.database::<DB>(name)
+    }
+
+    /// Creates a database named `name` with the `Schema` provided if it
+    /// doesn't already exist, and returns it along with whether it was
+    /// created.
+    ///
+    /// This allows one-time initialization, such as seeding the database, to
+    /// only be performed when the database is first created, without a
+    /// separate check that could race with other clients.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::{DatabaseCreation, StorageConnection};
+    /// # fn test_fn<S: StorageConnection>(storage: &S) -> Result<(), Error> {
+    /// let (db, creation) = storage.ensure_database::<MySchema>("my-database")?;
+    /// if creation == DatabaseCreation::Created {
+    ///     // Seed the new database.
+    ///     MyCollection::default().push_into(&db)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::InvalidDatabaseName`]: `name` must begin with an alphanumeric
+    ///   character (`[a-zA-Z0-9]`), and all remaining characters must be
+    ///   alphanumeric, a period (`.`), or a hyphen (`-`).
+    fn ensure_database<DB: Schema>(
+        &self,
+        name: &str,
+    ) -> Result<(Self::Database, DatabaseCreation), crate::Error> {
+        let creation = self.create_database_with_schema(name, DB::schema_name(), true)?;
+        Ok((self.database::<DB>(name)?, creation))
+    }
+
     /// Returns a reference to database `name` with schema `DB`.
     fn database<DB: Schema>(&self, name: &str) -> Result<Self::Database, crate::Error>;
 
     /// Creates a database named `name` using the [`SchemaName`] `schema`.
+    /// Returns [`DatabaseCreation::AlreadyExisted`] if `only_if_needed` is
+    /// true and the database already exists.
     ///
     /// ## Errors
     ///
@@ -3001,7 +4158,31 @@ pub trait StorageConnection: HasSession + Sized + Send + Sync {
         name: &str,
         schema: SchemaName,
         only_if_needed: bool,
-    ) -> Result<(), crate::Error>;
+    ) -> Result<DatabaseCreation, crate::Error> {
+        self.create_database_with_schema_and_permissions(name, schema, only_if_needed, Vec::new())
+    }
+
+    /// Creates a database named `name` using the [`SchemaName`] `schema`.
+    /// Every session that accesses the database is granted `permissions` in
+    /// addition to its own permissions, for operations performed on this
+    /// database. See
+    /// [`create_database_with_permissions()`](Self::create_database_with_permissions)
+    /// for more information.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::InvalidDatabaseName`]: `name` must begin with an alphanumeric
+    ///   character (`[a-zA-Z0-9]`), and all remaining characters must be
+    ///   alphanumeric, a period (`.`), or a hyphen (`-`).
+    /// * [`Error::DatabaseNameAlreadyTaken`]: `name` was already used for a
+    ///   previous database name. Returned if `only_if_needed` is false.
+    fn create_database_with_schema_and_permissions(
+        &self,
+        name: &str,
+        schema: SchemaName,
+        only_if_needed: bool,
+        permissions: Vec<Statement>,
+    ) -> Result<DatabaseCreation, crate::Error>;
 
     /// Deletes a database named `name`.
     ///
@@ -3014,9 +4195,33 @@ pub trait StorageConnection: HasSession + Sized + Send + Sync {
     /// Lists the databases in this storage.
     fn list_databases(&self) -> Result<Vec<Database>, crate::Error>;
 
+    /// Returns true if a database named `name` exists in this storage.
+    ///
+    /// This function requires the same permissions as
+    /// [`list_databases()`](Self::list_databases).
+    fn database_exists(&self, name: &str) -> Result<bool, crate::Error> {
+        Ok(self
+            .list_databases()?
+            .iter()
+            .any(|database| database.name == name))
+    }
+
     /// Lists the [`SchemaName`]s registered with this storage.
     fn list_available_schemas(&self) -> Result<Vec<SchemaSummary>, crate::Error>;
 
+    /// Returns statistics about the storage used by the database named
+    /// `name`.
+    ///
+    /// This function requires
+    /// [`ServerAction::GetDatabaseStats`](crate::permissions::bonsai::ServerAction::GetDatabaseStats)
+    /// on the database's
+    /// [resource name](crate::permissions::bonsai::database_resource_name).
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::DatabaseNotFound`]: database `name` does not exist.
+    fn database_stats(&self, name: &str) -> Result<DatabaseStats, crate::Error>;
+
     /// Creates a user.
     fn create_user(&self, username: &str) -> Result<u64, crate::Error>;
 
@@ -3026,6 +4231,13 @@ pub trait StorageConnection: HasSession + Sized + Send + Sync {
         user: U,
     ) -> Result<(), crate::Error>;
 
+    /// Lists the users in this storage.
+    ///
+    /// This function requires
+    /// [`ServerAction::ListUsers`](crate::permissions::bonsai::ServerAction::ListUsers)
+    /// on [`bonsaidb_resource_name()`](crate::permissions::bonsai::bonsaidb_resource_name).
+    fn list_users(&self) -> Result<Vec<UserInfo>, crate::Error>;
+
     /// Sets a user's password.
     #[cfg(feature = "password-hashing")]
     fn set_user_password<'user, U: Nameable<'user, u64> + Send + Sync>(
@@ -3037,7 +4249,6 @@ pub trait StorageConnection: HasSession + Sized + Send + Sync {
     /// Authenticates using the active session, returning a connection with a
     /// new session upon success. The existing connection will remain usable
     /// with the existing authentication, if any.
-    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
     fn authenticate(
         &self,
         authentication: Authentication,
@@ -3120,6 +4331,22 @@ pub trait StorageConnection: HasSession + Sized + Send + Sync {
         permission_group: G,
     ) -> Result<(), crate::Error>;
 
+    /// Lists the permission groups `user` has been added to. Groups that are
+    /// inherited through roles or parent groups are not included.
+    ///
+    /// This function requires
+    /// [`ServerAction::ListUserGroups`](crate::permissions::bonsai::ServerAction::ListUserGroups)
+    /// on the user's
+    /// [resource name](crate::permissions::bonsai::user_resource_name).
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UserNotFound`]: `user` does not exist.
+    fn list_user_groups<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<PermissionGroupInfo>, crate::Error>;
+
     /// Adds a user to a permission group.
     fn add_role_to_user<
         'user,
@@ -3174,10 +4401,82 @@ pub trait AsyncStorageConnection: HasSession + Sized + Send + Sync {
         self.database::<DB>(name).await
     }
 
+    /// Creates a database named `name` with the `Schema` provided. Every
+    /// session that accesses the database is granted `permissions` in addition
+    /// to its own permissions, for operations performed on this database.
+    ///
+    /// The permissions are stored alongside the database's record, so there is
+    /// never a point where the database exists without them. If the database
+    /// already exists and `only_if_needed` is true, its permissions are left
+    /// unchanged.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::InvalidDatabaseName`]: `name` must begin with an alphanumeric
+    ///   character (`[a-zA-Z0-9]`), and all remaining characters must be
+    ///   alphanumeric, a period (`.`), or a hyphen (`-`).
+    /// * [`Error::DatabaseNameAlreadyTaken`]: `name` was already used for a
+    ///   previous database name. Returned if `only_if_needed` is false.
+    async fn create_database_with_permissions<DB: Schema>(
+        &self,
+        name: &str,
+        only_if_needed: bool,
+        permissions: Vec<Statement>,
+    ) -> Result<Self::Database, crate::Error> {
+        self.create_database_with_schema_and_permissions(
+            name,
+            DB::schema_name(),
+            only_if_needed,
+            permissions,
+        )
+        .await?;
+        self.database::<DB>(name).await
+    }
+
+    /// Creates a database named `name` with the `Schema` provided if it
+    /// doesn't already exist, and returns it along with whether it was
+    /// created.
+    ///
+    /// This allows one-time initialization, such as seeding the database, to
+    /// only be performed when the database is first created, without a
+    /// separate check that could race with other clients.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::{AsyncStorageConnection, DatabaseCreation};
+    /// # async fn test_fn<S: AsyncStorageConnection>(storage: &S) -> Result<(), Error> {
+    /// let (db, creation) = storage
+    ///     .ensure_database::<MySchema>("my-database")
+    ///     .await?;
+    /// if creation == DatabaseCreation::Created {
+    ///     // Seed the new database.
+    ///     MyCollection::default().push_into_async(&db).await?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::InvalidDatabaseName`]: `name` must begin with an alphanumeric
+    ///   character (`[a-zA-Z0-9]`), and all remaining characters must be
+    ///   alphanumeric, a period (`.`), or a hyphen (`-`).
+    async fn ensure_database<DB: Schema>(
+        &self,
+        name: &str,
+    ) -> Result<(Self::Database, DatabaseCreation), crate::Error> {
+        let creation = self
+            .create_database_with_schema(name, DB::schema_name(), true)
+            .await?;
+        Ok((self.database::<DB>(name).await?, creation))
+    }
+
     /// Returns a reference to database `name` with schema `DB`.
     async fn database<DB: Schema>(&self, name: &str) -> Result<Self::Database, crate::Error>;
 
     /// Creates a database named `name` using the [`SchemaName`] `schema`.
+    /// Returns [`DatabaseCreation::AlreadyExisted`] if `only_if_needed` is
+    /// true and the database already exists.
     ///
     /// ## Errors
     ///
@@ -3191,7 +4490,32 @@ pub trait AsyncStorageConnection: HasSession + Sized + Send + Sync {
         name: &str,
         schema: SchemaName,
         only_if_needed: bool,
-    ) -> Result<(), crate::Error>;
+    ) -> Result<DatabaseCreation, crate::Error> {
+        self.create_database_with_schema_and_permissions(name, schema, only_if_needed, Vec::new())
+            .await
+    }
+
+    /// Creates a database named `name` using the [`SchemaName`] `schema`.
+    /// Every session that accesses the database is granted `permissions` in
+    /// addition to its own permissions, for operations performed on this
+    /// database. See
+    /// [`create_database_with_permissions()`](Self::create_database_with_permissions)
+    /// for more information.
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::InvalidDatabaseName`]: `name` must begin with an alphanumeric
+    ///   character (`[a-zA-Z0-9]`), and all remaining characters must be
+    ///   alphanumeric, a period (`.`), or a hyphen (`-`).
+    /// * [`Error::DatabaseNameAlreadyTaken`]: `name` was already used for a
+    ///   previous database name. Returned if `only_if_needed` is false.
+    async fn create_database_with_schema_and_permissions(
+        &self,
+        name: &str,
+        schema: SchemaName,
+        only_if_needed: bool,
+        permissions: Vec<Statement>,
+    ) -> Result<DatabaseCreation, crate::Error>;
 
     /// Deletes a database named `name`.
     ///
@@ -3204,9 +4528,34 @@ pub trait AsyncStorageConnection: HasSession + Sized + Send + Sync {
     /// Lists the databases in this storage.
     async fn list_databases(&self) -> Result<Vec<Database>, crate::Error>;
 
+    /// Returns true if a database named `name` exists in this storage.
+    ///
+    /// This function requires the same permissions as
+    /// [`list_databases()`](Self::list_databases).
+    async fn database_exists(&self, name: &str) -> Result<bool, crate::Error> {
+        Ok(self
+            .list_databases()
+            .await?
+            .iter()
+            .any(|database| database.name == name))
+    }
+
     /// Lists the [`SchemaName`]s registered with this storage.
     async fn list_available_schemas(&self) -> Result<Vec<SchemaSummary>, crate::Error>;
 
+    /// Returns statistics about the storage used by the database named
+    /// `name`.
+    ///
+    /// This function requires
+    /// [`ServerAction::GetDatabaseStats`](crate::permissions::bonsai::ServerAction::GetDatabaseStats)
+    /// on the database's
+    /// [resource name](crate::permissions::bonsai::database_resource_name).
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::DatabaseNotFound`]: database `name` does not exist.
+    async fn database_stats(&self, name: &str) -> Result<DatabaseStats, crate::Error>;
+
     /// Creates a user.
     async fn create_user(&self, username: &str) -> Result<u64, crate::Error>;
 
@@ -3216,6 +4565,13 @@ pub trait AsyncStorageConnection: HasSession + Sized + Send + Sync {
         user: U,
     ) -> Result<(), crate::Error>;
 
+    /// Lists the users in this storage.
+    ///
+    /// This function requires
+    /// [`ServerAction::ListUsers`](crate::permissions::bonsai::ServerAction::ListUsers)
+    /// on [`bonsaidb_resource_name()`](crate::permissions::bonsai::bonsaidb_resource_name).
+    async fn list_users(&self) -> Result<Vec<UserInfo>, crate::Error>;
+
     /// Sets a user's password.
     #[cfg(feature = "password-hashing")]
     async fn set_user_password<'user, U: Nameable<'user, u64> + Send + Sync>(
@@ -3228,7 +4584,6 @@ pub trait AsyncStorageConnection: HasSession + Sized + Send + Sync {
     /// [`AuthenticationToken`](crate::admin::AuthenticationToken). If
     ///  successful, the returned instance will have the permissions from
     ///  `identity`.
-    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
     async fn authenticate(
         &self,
         authentication: Authentication,
@@ -3315,6 +4670,22 @@ pub trait AsyncStorageConnection: HasSession + Sized + Send + Sync {
         permission_group: G,
     ) -> Result<(), crate::Error>;
 
+    /// Lists the permission groups `user` has been added to. Groups that are
+    /// inherited through roles or parent groups are not included.
+    ///
+    /// This function requires
+    /// [`ServerAction::ListUserGroups`](crate::permissions::bonsai::ServerAction::ListUserGroups)
+    /// on the user's
+    /// [resource name](crate::permissions::bonsai::user_resource_name).
+    ///
+    /// ## Errors
+    ///
+    /// * [`Error::UserNotFound`]: `user` does not exist.
+    async fn list_user_groups<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<PermissionGroupInfo>, crate::Error>;
+
     /// Adds a user to a permission group.
     async fn add_role_to_user<
         'user,
@@ -3347,21 +4718,129 @@ pub struct Database {
     pub name: String,
     /// The schema defining the database.
     pub schema: SchemaName,
+    /// When the database was created. This is `None` for databases created
+    /// before creation times were recorded.
+    #[serde(default)]
+    pub created_at: Option<TimestampAsNanoseconds>,
+}
+
+/// The outcome of creating a database using
+/// [`StorageConnection::create_database_with_schema()`],
+/// [`StorageConnection::ensure_database()`], or their async equivalents.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize, Serialize)]
+pub enum DatabaseCreation {
+    /// A new database was created.
+    Created,
+    /// The database already existed, and `only_if_needed` was true.
+    AlreadyExisted,
+}
+
+/// A user stored in BonsaiDb, returned from
+/// [`StorageConnection::list_users()`].
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct UserInfo {
+    /// The unique id of the user.
+    pub id: u64,
+    /// The unique username of the user.
+    pub username: String,
+    /// The ids of the permission groups the user belongs to.
+    pub groups: Vec<u64>,
+    /// The ids of the roles the user has been assigned.
+    pub roles: Vec<u64>,
+}
+
+/// A permission group, returned from
+/// [`StorageConnection::list_user_groups()`].
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct PermissionGroupInfo {
+    /// The unique id of the group.
+    pub id: u64,
+    /// The unique name of the group.
+    pub name: String,
+    /// The id of the group this group inherits statements from.
+    pub parent_group: Option<u64>,
+}
+
+/// Statistics about the storage used by a database, returned from
+/// [`StorageConnection::database_stats()`].
+///
+/// These statistics are read from the size accounting kept by the storage
+/// engine, and do not require reading each document.
+#[derive(Debug, Clone, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct DatabaseStats {
+    /// The total number of bytes the database's files occupy on disk. This
+    /// includes data that has not been reclaimed by compaction yet, as well as
+    /// the transaction log and key-value store. This is always 0 for
+    /// memory-only storage.
+    pub total_bytes: u64,
+    /// Statistics for each collection in the database's schema.
+    pub collections: HashMap<CollectionName, CollectionStats>,
+}
+
+/// Statistics about the storage used by a collection. Part of
+/// [`DatabaseStats`].
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct CollectionStats {
+    /// The number of documents stored in the collection. Soft-deleted
+    /// documents are not included.
+    pub document_count: u64,
+    /// The number of bytes used by the contents of the collection's
+    /// documents.
+    pub document_bytes: u64,
+    /// The number of bytes used by the indexes of the collection's views.
+    pub index_bytes: u64,
 }
 
 /// A string containing sensitive (private) data. This struct automatically
 /// overwrites its contents with zeroes when dropped.
+///
+/// The contents are never included when formatting this type: [`Debug`]
+/// writes `SensitiveString(...)` and [`Display`](std::fmt::Display) writes
+/// `***`. This makes it safe to log structures that contain passwords or
+/// tokens. To access the contents, dereference this type or use the public
+/// field.
+///
+/// [`Debug`]: std::fmt::Debug
 #[derive(Clone, Default, Serialize, Deserialize, Zeroize, Eq, PartialEq)]
 #[zeroize(drop)]
 #[serde(transparent)]
 pub struct SensitiveString(pub String);
 
+impl SensitiveString {
+    /// Returns the value of the environment variable `name`.
+    pub fn from_env(name: impl AsRef<std::ffi::OsStr>) -> Result<Self, std::env::VarError> {
+        std::env::var(name).map(Self)
+    }
+
+    /// Reads the contents of the file at `path`. A single trailing newline is
+    /// removed, if present.
+    ///
+    /// This is useful for reading secrets that are mounted as files, such as
+    /// [Docker secrets](https://docs.docker.com/engine/swarm/secrets/).
+    pub fn from_file(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let mut contents = Self(std::fs::read_to_string(path)?);
+        if contents.0.ends_with('\n') {
+            contents.0.pop();
+            if contents.0.ends_with('\r') {
+                contents.0.pop();
+            }
+        }
+        Ok(contents)
+    }
+}
+
 impl std::fmt::Debug for SensitiveString {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str("SensitiveString(...)")
     }
 }
 
+impl std::fmt::Display for SensitiveString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("***")
+    }
+}
+
 impl Deref for SensitiveString {
     type Target = String;
 
@@ -3413,6 +4892,23 @@ impl<'a> From<&'a str> for SensitiveString {
     }
 }
 
+#[test]
+fn sensitive_string_redaction() {
+    let secret = SensitiveString::from("hunter2");
+    assert_eq!(format!("{secret:?}"), "SensitiveString(...)");
+    assert_eq!(format!("{secret}"), "***");
+    assert_eq!(secret.as_str(), "hunter2");
+}
+
+#[test]
+fn sensitive_string_from_file() {
+    let path = std::env::temp_dir().join(format!("bonsaidb-secret-{}", std::process::id()));
+    std::fs::write(&path, "hunter2\r\n").unwrap();
+    let secret = SensitiveString::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(secret.as_str(), "hunter2");
+}
+
 /// A buffer containing sensitive (private) data. This struct automatically
 /// overwrites its contents with zeroes when dropped.
 #[derive(Clone, Serialize, Deserialize, Zeroize, Eq, PartialEq)]
@@ -3494,6 +4990,15 @@ pub enum Authentication {
         /// The password of the user.
         password: SensitiveString,
     },
+    /// Authenticate using a custom authenticator registered with the server.
+    Custom {
+        /// The name the authenticator was registered with.
+        name: String,
+        /// The payload to pass to the authenticator.
+        payload: Bytes,
+    },
+    /// Authenticate using a session token previously issued by the server.
+    SessionToken(SensitiveString),
 }
 
 impl Authentication {
@@ -3509,6 +5014,24 @@ impl Authentication {
         })
     }
 
+    /// Returns an authentication instance that is handled by the custom
+    /// authenticator registered as `name`. The server passes `payload` to the
+    /// authenticator unmodified.
+    pub fn custom(name: impl Into<String>, payload: impl Into<Bytes>) -> Self {
+        Self::Custom {
+            name: name.into(),
+            payload: payload.into(),
+        }
+    }
+
+    /// Returns an authentication instance for a session token issued by a
+    /// server after a previous authentication. Session tokens are only
+    /// accepted by the server that issued them, and only until they expire or
+    /// are revoked.
+    pub fn session_token(token: SensitiveString) -> Self {
+        Self::SessionToken(token)
+    }
+
     /// Returns a token authentication initialization instance for this token.
     #[cfg(feature = "token-authentication")]
     pub fn token(id: u64, token: &SensitiveString) -> Result<Self, crate::Error> {
@@ -3701,6 +5224,12 @@ pub enum AuthenticationMethod {
     Token,
     /// Authenticate a user using password hashing (Argon2).
     PasswordHash,
+    /// Authenticate a user or role using a custom authenticator registered
+    /// with the server.
+    Custom,
+    /// Authenticate a user or role using a session token issued by the
+    /// server.
+    SessionToken,
 }
 
 /// A unique session ID.
@@ -3860,7 +5389,7 @@ impl<'name> IdentityReference<'name> {
 }
 
 /// An identity from the connected BonsaiDb instance.
-#[derive(Copy, Clone, Debug, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 #[non_exhaustive]
 pub enum IdentityId {
     /// A [`User`](crate::admin::User) id.
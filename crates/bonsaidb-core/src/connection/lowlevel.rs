@@ -6,17 +6,20 @@ use async_trait::async_trait;
 
 use super::GroupedReductions;
 use crate::connection::{
-    AccessPolicy, HasSession, QueryKey, Range, RangeRef, SerializedQueryKey, Sort,
+    AccessPolicy, HasSession, QueryKey, QueryPlan, Range, RangeRef, SerializedQueryKey, Sort,
 };
 use crate::document::{
-    CollectionDocument, CollectionHeader, Document, DocumentId, HasHeader, Header, OwnedDocument,
+    CollectionDocument, CollectionHeader, Document, DocumentId, HasHeader, Header,
+    HistoricalRevision, MaybeDeleted, OwnedDocument,
 };
 use crate::key::{self, ByteSource, Key, KeyEncoding};
 use crate::schema::view::map::{
     CollectionMap, MappedDocuments, MappedSerializedValue, ViewMappings,
 };
 use crate::schema::view::{self};
-use crate::schema::{self, CollectionName, MappedValue, Schematic, SerializedCollection, ViewName};
+use crate::schema::{
+    self, CollectionName, Filter, MappedValue, Schematic, SerializedCollection, ViewName,
+};
 use crate::transaction::{OperationResult, Transaction};
 use crate::Error;
 
@@ -189,6 +192,22 @@ pub trait LowLevelConnection: HasSchema + HasSession {
         self.list_from_collection(ids, order, limit, &C::collection_name())
     }
 
+    /// Retrieves the documents in collection `C` that match `filter`, in
+    /// ascending order by id. If `limit` is provided, no more than `limit`
+    /// documents are returned.
+    ///
+    /// Evaluating a filter requires reading every document in the
+    /// collection. See [`Filter`] for more information.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::filter()`].
+    fn filter<C>(&self, filter: &Filter, limit: Option<u32>) -> Result<Vec<OwnedDocument>, Error>
+    where
+        C: schema::Collection,
+    {
+        self.filter_from_collection(filter, limit, &C::collection_name())
+    }
+
     /// Retrieves all documents within the range of `ids`. To retrieve all
     /// documents, pass in `..` for `ids`.
     ///
@@ -215,6 +234,33 @@ pub trait LowLevelConnection: HasSchema + HasSession {
         self.list_headers_from_collection(ids, order, limit, &C::collection_name())
     }
 
+    /// Retrieves all documents within the range of `ids`, including documents
+    /// that have been soft-deleted. To retrieve all documents, pass in `..` for
+    /// `ids`. Each [`MaybeDeleted`] records when its document was deleted, if
+    /// it has been.
+    ///
+    /// For collections that do not enable
+    /// [`soft_delete`](schema::Collection::soft_delete), this returns the same
+    /// documents as [`list()`](Self::list).
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::list_including_deleted()`].
+    fn list_including_deleted<'id, C, R, PrimaryKey>(
+        &self,
+        ids: R,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<Vec<MaybeDeleted>, Error>
+    where
+        C: schema::Collection,
+        R: Into<RangeRef<'id, C::PrimaryKey, PrimaryKey>> + Send,
+        PrimaryKey: KeyEncoding<C::PrimaryKey> + PartialEq + 'id + ?Sized,
+        C::PrimaryKey: Borrow<PrimaryKey> + PartialEq<PrimaryKey>,
+    {
+        let ids = ids.into().map_result(|id| DocumentId::new(id))?;
+        self.list_including_deleted_from_collection(ids, order, limit, &C::collection_name())
+    }
+
     /// Counts the number of documents within the range of `ids`.
     ///
     /// This is a lower-level API. For better ergonomics, consider using one of:
@@ -258,6 +304,47 @@ pub trait LowLevelConnection: HasSchema + HasSession {
         }
     }
 
+    /// Restores the soft-deleted document with `id` in collection `C`,
+    /// returning the header of the restored document.
+    ///
+    /// If no soft-deleted document exists with `id`,
+    /// [`Error::DocumentNotFound`] is returned.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::restore()`].
+    fn restore<C, PrimaryKey>(&self, id: &PrimaryKey) -> Result<Header, Error>
+    where
+        C: schema::Collection,
+        PrimaryKey: KeyEncoding<C::PrimaryKey> + ?Sized,
+    {
+        self.restore_from_collection(DocumentId::new(id)?, &C::collection_name())
+    }
+
+    /// Returns the revisions of the document with `id` in collection `C`,
+    /// ordered from oldest to newest.
+    ///
+    /// Previous revisions are only returned if revision history is being
+    /// recorded by the storage, which is configured using
+    /// `Builder::revision_history()`. Otherwise, only the current revision is
+    /// returned. The history of a deleted document remains available until
+    /// the document's id is reused.
+    ///
+    /// If the document doesn't exist and has no recorded history,
+    /// [`Error::DocumentNotFound`] is returned.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`CollectionDocument::revision_history()`].
+    fn revision_history<C, PrimaryKey>(
+        &self,
+        id: &PrimaryKey,
+    ) -> Result<Vec<HistoricalRevision>, Error>
+    where
+        C: schema::Collection,
+        PrimaryKey: KeyEncoding<C::PrimaryKey> + ?Sized,
+    {
+        self.revision_history_from_collection(DocumentId::new(id)?, &C::collection_name())
+    }
+
     /// Queries for view entries matching [`View`](schema::View).
     ///
     /// This is a lower-level API. For better ergonomics, consider querying the
@@ -467,6 +554,35 @@ pub trait LowLevelConnection: HasSchema + HasSession {
         )
     }
 
+    /// Describes how a query for entries matching [`View`](schema::View)
+    /// would be executed, without retrieving the entries.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider explaining
+    /// the query using [`View::entries(self).explain()`](super::View::explain)
+    /// instead. The parameters for the query can be customized on the builder
+    /// returned from
+    /// [`SerializedView::entries()`](schema::SerializedView::entries),
+    /// [`SerializedView::entries_async()`](schema::SerializedView::entries_async),
+    /// or [`Connection::view()`](super::Connection::view).
+    fn explain<V: schema::SerializedView, Key>(
+        &self,
+        key: Option<QueryKey<'_, V::Key, Key>>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, Error>
+    where
+        Key: KeyEncoding<V::Key> + PartialEq + ?Sized,
+        V::Key: Borrow<Key> + PartialEq<Key>,
+    {
+        let view = self.schematic().view::<V>()?;
+        self.explain_by_name(
+            &view.view_name(),
+            key.map(|key| key.serialized()).transpose()?,
+            order,
+            limit,
+        )
+    }
+
     /// Applies a [`Transaction`] to the [`schema::Schema`]. If any operation in the
     /// [`Transaction`] fails, none of the operations will be applied to the
     /// [`schema::Schema`].
@@ -486,8 +602,10 @@ pub trait LowLevelConnection: HasSchema + HasSession {
     ) -> Result<Option<OwnedDocument>, Error>;
 
     /// Retrieves all documents matching `ids` from the named `collection`.
+    /// Documents are returned in the order their ids appear in `ids`.
     /// Documents that are not found are not returned, but no error will be
-    /// generated.
+    /// generated. Duplicate ids only return their document once, at the
+    /// position of the id's first occurrence.
     ///
     /// This is a lower-level API. For better ergonomics, consider using one of:
     ///
@@ -516,6 +634,19 @@ pub trait LowLevelConnection: HasSchema + HasSession {
         collection: &CollectionName,
     ) -> Result<Vec<OwnedDocument>, Error>;
 
+    /// Retrieves the documents in the named `collection` that match `filter`,
+    /// in ascending order by id. If `limit` is provided, no more than `limit`
+    /// documents are returned.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::filter()`].
+    fn filter_from_collection(
+        &self,
+        filter: &Filter,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<OwnedDocument>, Error>;
+
     /// Retrieves all headers within the range of `ids` from the named
     /// `collection`. To retrieve all documents, pass in `..` for `ids`.
     ///
@@ -533,6 +664,42 @@ pub trait LowLevelConnection: HasSchema + HasSession {
         collection: &CollectionName,
     ) -> Result<Vec<Header>, Error>;
 
+    /// Retrieves all documents within the range of `ids` from the named
+    /// `collection`, including documents that have been soft-deleted. To
+    /// retrieve all documents, pass in `..` for `ids`.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::list_including_deleted()`].
+    fn list_including_deleted_from_collection(
+        &self,
+        ids: Range<DocumentId>,
+        order: Sort,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<MaybeDeleted>, Error>;
+
+    /// Restores the soft-deleted document with `id` in the named `collection`,
+    /// returning the header of the restored document.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::restore()`].
+    fn restore_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Header, Error>;
+
+    /// Returns the revisions of the document with `id` in the named
+    /// `collection`, ordered from oldest to newest.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`CollectionDocument::revision_history()`].
+    fn revision_history_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Vec<HistoricalRevision>, Error>;
+
     /// Counts the number of documents within the range of `ids` from the named
     /// `collection`.
     ///
@@ -635,6 +802,21 @@ pub trait LowLevelConnection: HasSchema + HasSession {
         key: Option<SerializedQueryKey>,
         access_policy: AccessPolicy,
     ) -> Result<u64, Error>;
+
+    /// Describes how a query for entries from the named `view` would be
+    /// executed, without retrieving the entries. The view is not updated.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider explaining
+    /// the query using [`View::entries(self).explain()`](super::View::explain)
+    /// instead. The parameters for the query can be customized on the builder
+    /// returned from [`Connection::view()`](super::Connection::view).
+    fn explain_by_name(
+        &self,
+        view: &ViewName,
+        key: Option<SerializedQueryKey>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, Error>;
 }
 
 /// The low-level interface to a database's [`schema::Schema`], giving access to
@@ -816,6 +998,27 @@ pub trait AsyncLowLevelConnection: HasSchema + HasSession + Send + Sync {
             .await
     }
 
+    /// Retrieves the documents in collection `C` that match `filter`, in
+    /// ascending order by id. If `limit` is provided, no more than `limit`
+    /// documents are returned.
+    ///
+    /// Evaluating a filter requires reading every document in the
+    /// collection. See [`Filter`] for more information.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::filter_async()`].
+    async fn filter<C>(
+        &self,
+        filter: &Filter,
+        limit: Option<u32>,
+    ) -> Result<Vec<OwnedDocument>, Error>
+    where
+        C: schema::Collection,
+    {
+        self.filter_from_collection(filter, limit, &C::collection_name())
+            .await
+    }
+
     /// Retrieves all documents within the range of `ids`. To retrieve all
     /// documents, pass in `..` for `ids`.
     ///
@@ -843,6 +1046,34 @@ pub trait AsyncLowLevelConnection: HasSchema + HasSession + Send + Sync {
             .await
     }
 
+    /// Retrieves all documents within the range of `ids`, including documents
+    /// that have been soft-deleted. To retrieve all documents, pass in `..` for
+    /// `ids`. Each [`MaybeDeleted`] records when its document was deleted, if
+    /// it has been.
+    ///
+    /// For collections that do not enable
+    /// [`soft_delete`](schema::Collection::soft_delete), this returns the same
+    /// documents as [`list()`](Self::list).
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::list_including_deleted_async()`].
+    async fn list_including_deleted<'id, C, R, PrimaryKey>(
+        &self,
+        ids: R,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<Vec<MaybeDeleted>, Error>
+    where
+        C: schema::Collection,
+        R: Into<RangeRef<'id, C::PrimaryKey, PrimaryKey>> + Send,
+        PrimaryKey: KeyEncoding<C::PrimaryKey> + PartialEq + 'id + ?Sized,
+        C::PrimaryKey: Borrow<PrimaryKey> + PartialEq<PrimaryKey>,
+    {
+        let ids = ids.into().map_result(|id| DocumentId::new(id))?;
+        self.list_including_deleted_from_collection(ids, order, limit, &C::collection_name())
+            .await
+    }
+
     /// Counts the number of documents within the range of `ids`.
     ///
     /// This is the lower-level API. For better ergonomics, consider using
@@ -888,6 +1119,49 @@ pub trait AsyncLowLevelConnection: HasSchema + HasSession + Send + Sync {
             )
         }
     }
+
+    /// Restores the soft-deleted document with `id` in collection `C`,
+    /// returning the header of the restored document.
+    ///
+    /// If no soft-deleted document exists with `id`,
+    /// [`Error::DocumentNotFound`] is returned.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::restore_async()`].
+    async fn restore<C, PrimaryKey>(&self, id: &PrimaryKey) -> Result<Header, Error>
+    where
+        C: schema::Collection,
+        PrimaryKey: KeyEncoding<C::PrimaryKey> + ?Sized,
+    {
+        self.restore_from_collection(DocumentId::new(id)?, &C::collection_name())
+            .await
+    }
+
+    /// Returns the revisions of the document with `id` in collection `C`,
+    /// ordered from oldest to newest.
+    ///
+    /// Previous revisions are only returned if revision history is being
+    /// recorded by the storage, which is configured using
+    /// `Builder::revision_history()`. Otherwise, only the current revision is
+    /// returned. The history of a deleted document remains available until
+    /// the document's id is reused.
+    ///
+    /// If the document doesn't exist and has no recorded history,
+    /// [`Error::DocumentNotFound`] is returned.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`CollectionDocument::revision_history_async()`].
+    async fn revision_history<C, PrimaryKey>(
+        &self,
+        id: &PrimaryKey,
+    ) -> Result<Vec<HistoricalRevision>, Error>
+    where
+        C: schema::Collection,
+        PrimaryKey: KeyEncoding<C::PrimaryKey> + ?Sized,
+    {
+        self.revision_history_from_collection(DocumentId::new(id)?, &C::collection_name())
+            .await
+    }
     /// Queries for view entries matching [`View`](schema::View)(super::AsyncView).
     ///
     /// This is the lower-level API. For better ergonomics, consider querying
@@ -1094,6 +1368,34 @@ pub trait AsyncLowLevelConnection: HasSchema + HasSession + Send + Sync {
         .await
     }
 
+    /// Describes how a query for entries matching [`View`](schema::View)
+    /// would be executed, without retrieving the entries.
+    ///
+    /// This is the lower-level API. For better ergonomics, consider explaining
+    /// the query using
+    /// [`View::entries(self).explain()`](super::AsyncView::explain) instead.
+    /// The parameters for the query can be customized on the builder returned
+    /// from [`AsyncConnection::view()`](super::AsyncConnection::view).
+    async fn explain<V: schema::SerializedView, Key>(
+        &self,
+        key: Option<QueryKey<'_, V::Key, Key>>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, Error>
+    where
+        Key: KeyEncoding<V::Key> + PartialEq + ?Sized,
+        V::Key: Borrow<Key> + PartialEq<Key>,
+    {
+        let view = self.schematic().view::<V>()?;
+        self.explain_by_name(
+            &view.view_name(),
+            key.map(|key| key.serialized()).transpose()?,
+            order,
+            limit,
+        )
+        .await
+    }
+
     /// Applies a [`Transaction`] to the [`Schema`](schema::Schema). If any
     /// operation in the [`Transaction`] fails, none of the operations will be
     /// applied to the [`Schema`](schema::Schema).
@@ -1115,8 +1417,10 @@ pub trait AsyncLowLevelConnection: HasSchema + HasSession + Send + Sync {
     ) -> Result<Option<OwnedDocument>, Error>;
 
     /// Retrieves all documents matching `ids` from the named `collection`.
+    /// Documents are returned in the order their ids appear in `ids`.
     /// Documents that are not found are not returned, but no error will be
-    /// generated.
+    /// generated. Duplicate ids only return their document once, at the
+    /// position of the id's first occurrence.
     ///
     /// This is a lower-level API. For better ergonomics, consider using one of:
     ///
@@ -1145,6 +1449,19 @@ pub trait AsyncLowLevelConnection: HasSchema + HasSession + Send + Sync {
         collection: &CollectionName,
     ) -> Result<Vec<OwnedDocument>, Error>;
 
+    /// Retrieves the documents in the named `collection` that match `filter`,
+    /// in ascending order by id. If `limit` is provided, no more than `limit`
+    /// documents are returned.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::filter_async()`].
+    async fn filter_from_collection(
+        &self,
+        filter: &Filter,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<OwnedDocument>, Error>;
+
     /// Retrieves all headers within the range of `ids` from the named
     /// `collection`. To retrieve all documents, pass in `..` for `ids`.
     ///
@@ -1162,6 +1479,42 @@ pub trait AsyncLowLevelConnection: HasSchema + HasSession + Send + Sync {
         collection: &CollectionName,
     ) -> Result<Vec<Header>, Error>;
 
+    /// Retrieves all documents within the range of `ids` from the named
+    /// `collection`, including documents that have been soft-deleted. To
+    /// retrieve all documents, pass in `..` for `ids`.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::list_including_deleted_async()`].
+    async fn list_including_deleted_from_collection(
+        &self,
+        ids: Range<DocumentId>,
+        order: Sort,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<MaybeDeleted>, Error>;
+
+    /// Restores the soft-deleted document with `id` in the named `collection`,
+    /// returning the header of the restored document.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`SerializedCollection::restore_async()`].
+    async fn restore_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Header, Error>;
+
+    /// Returns the revisions of the document with `id` in the named
+    /// `collection`, ordered from oldest to newest.
+    ///
+    /// This is a lower-level API. For better ergonomics, consider using
+    /// [`CollectionDocument::revision_history_async()`].
+    async fn revision_history_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Vec<HistoricalRevision>, Error>;
+
     /// Counts the number of documents within the range of `ids` from the named
     /// `collection`.
     ///
@@ -1264,6 +1617,22 @@ pub trait AsyncLowLevelConnection: HasSchema + HasSession + Send + Sync {
         key: Option<SerializedQueryKey>,
         access_policy: AccessPolicy,
     ) -> Result<u64, Error>;
+
+    /// Describes how a query for entries from the named `view` would be
+    /// executed, without retrieving the entries. The view is not updated.
+    ///
+    /// This is the lower-level API. For better ergonomics, consider explaining
+    /// the query using
+    /// [`View::entries(self).explain()`](super::AsyncView::explain) instead.
+    /// The parameters for the query can be customized on the builder returned
+    /// from [`AsyncConnection::view()`](super::AsyncConnection::view).
+    async fn explain_by_name(
+        &self,
+        view: &ViewName,
+        key: Option<SerializedQueryKey>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, Error>;
 }
 
 /// Access to a connection's schema.
@@ -0,0 +1,215 @@
+use std::marker::PhantomData;
+
+use crate::connection::{AccessPolicy, AsyncConnection, Connection};
+use crate::schema::view::search::{query_terms, search_results, SearchIndex, SearchResult};
+use crate::schema::Collection;
+use crate::Error;
+
+/// A full-text search of a [`SearchIndex`]. Created using
+/// [`Connection::search()`].
+#[must_use]
+pub struct Search<'a, Cn, V> {
+    connection: &'a Cn,
+
+    /// The text to search for.
+    pub query: &'a str,
+
+    /// Whether to score and sort the results by relevance.
+    pub ranked: bool,
+
+    /// The maximum number of results to return.
+    pub limit: Option<u32>,
+
+    /// The index's data access policy. The default value is returned from
+    /// [`Schematic::default_access_policy()`](crate::schema::Schematic::default_access_policy).
+    pub access_policy: AccessPolicy,
+
+    _view: PhantomData<V>,
+}
+
+impl<'a, Cn, V> Search<'a, Cn, V>
+where
+    Cn: Connection,
+    V: SearchIndex,
+{
+    pub(crate) fn new(connection: &'a Cn, query: &'a str) -> Self {
+        Self {
+            connection,
+            query,
+            ranked: false,
+            limit: None,
+            access_policy: connection.schematic().default_access_policy::<V>(),
+            _view: PhantomData,
+        }
+    }
+
+    /// Scores each result using TF-IDF and sorts the results with the most
+    /// relevant documents first. Without ranking, results are sorted by
+    /// document id.
+    pub const fn with_ranking(mut self) -> Self {
+        self.ranked = true;
+        self
+    }
+
+    /// Sets the maximum number of results to return.
+    pub const fn limit(mut self, maximum_results: u32) -> Self {
+        self.limit = Some(maximum_results);
+        self
+    }
+
+    /// Sets the access policy for the search.
+    pub const fn with_access_policy(mut self, policy: AccessPolicy) -> Self {
+        self.access_policy = policy;
+        self
+    }
+
+    /// Executes the search, returning every document that contains at least
+    /// one of the query's terms.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # use bonsaidb_core::schema::view::search::SearchIndex;
+    /// # fn test_fn<C: Connection, MySearch: SearchIndex>(db: C) -> Result<(), Error> {
+    /// for result in db.search::<MySearch>("hello world").with_ranking().query()? {
+    ///     println!("Found a document with a score of {:?}", result.score);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn query(
+        self,
+    ) -> Result<Vec<SearchResult<<V::Collection as Collection>::PrimaryKey>>, Error> {
+        let terms = query_terms(self.query);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mappings = self
+            .connection
+            .view::<V>()
+            .with_keys(&terms)
+            .with_access_policy(self.access_policy)
+            .query()?;
+        let document_count = if self.ranked {
+            Some(
+                self.connection
+                    .collection::<V::Collection>()
+                    .all()
+                    .count()?,
+            )
+        } else {
+            None
+        };
+        search_results::<V>(mappings, document_count, self.limit)
+    }
+}
+
+/// A full-text search of a [`SearchIndex`]. Created using
+/// [`AsyncConnection::search()`].
+#[must_use]
+pub struct AsyncSearch<'a, Cn, V> {
+    connection: &'a Cn,
+
+    /// The text to search for.
+    pub query: &'a str,
+
+    /// Whether to score and sort the results by relevance.
+    pub ranked: bool,
+
+    /// The maximum number of results to return.
+    pub limit: Option<u32>,
+
+    /// The index's data access policy. The default value is returned from
+    /// [`Schematic::default_access_policy()`](crate::schema::Schematic::default_access_policy).
+    pub access_policy: AccessPolicy,
+
+    _view: PhantomData<V>,
+}
+
+impl<'a, Cn, V> AsyncSearch<'a, Cn, V>
+where
+    Cn: AsyncConnection,
+    V: SearchIndex,
+{
+    pub(crate) fn new(connection: &'a Cn, query: &'a str) -> Self {
+        Self {
+            connection,
+            query,
+            ranked: false,
+            limit: None,
+            access_policy: connection.schematic().default_access_policy::<V>(),
+            _view: PhantomData,
+        }
+    }
+
+    /// Scores each result using TF-IDF and sorts the results with the most
+    /// relevant documents first. Without ranking, results are sorted by
+    /// document id.
+    pub const fn with_ranking(mut self) -> Self {
+        self.ranked = true;
+        self
+    }
+
+    /// Sets the maximum number of results to return.
+    pub const fn limit(mut self, maximum_results: u32) -> Self {
+        self.limit = Some(maximum_results);
+        self
+    }
+
+    /// Sets the access policy for the search.
+    pub const fn with_access_policy(mut self, policy: AccessPolicy) -> Self {
+        self.access_policy = policy;
+        self
+    }
+
+    /// Executes the search, returning every document that contains at least
+    /// one of the query's terms.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # use bonsaidb_core::schema::view::search::SearchIndex;
+    /// # fn test_fn<C: AsyncConnection, MySearch: SearchIndex>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// for result in db
+    ///     .search::<MySearch>("hello world")
+    ///     .with_ranking()
+    ///     .query()
+    ///     .await?
+    /// {
+    ///     println!("Found a document with a score of {:?}", result.score);
+    /// }
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    pub async fn query(
+        self,
+    ) -> Result<Vec<SearchResult<<V::Collection as Collection>::PrimaryKey>>, Error> {
+        let terms = query_terms(self.query);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mappings = self
+            .connection
+            .view::<V>()
+            .with_keys(&terms)
+            .with_access_policy(self.access_policy)
+            .query()
+            .await?;
+        let document_count = if self.ranked {
+            Some(
+                self.connection
+                    .collection::<V::Collection>()
+                    .all()
+                    .count()
+                    .await?,
+            )
+        } else {
+            None
+        };
+        search_results::<V>(mappings, document_count, self.limit)
+    }
+}
@@ -29,6 +29,7 @@ use arc_bytes::serde::{Bytes, CowBytes};
 use serde::{Deserialize, Serialize};
 
 use crate::key::KeyEncoding;
+use crate::keyvalue::Timestamp;
 use crate::schema::{Collection, SerializedCollection};
 
 mod collection;
@@ -38,7 +39,7 @@ mod revision;
 pub use self::collection::{CollectionDocument, OwnedDocuments};
 pub use self::header::{AnyHeader, CollectionHeader, Emit, HasHeader, Header};
 pub use self::id::{DocumentId, InvalidHexadecimal};
-pub use self::revision::Revision;
+pub use self::revision::{HistoricalRevision, Revision};
 /// Contains a serialized document in the database.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct BorrowedDocument<'a> {
@@ -177,6 +178,42 @@ where
     }
 }
 
+/// A document that may have been soft-deleted, returned when listing
+/// documents including those that have been
+/// [soft-deleted](crate::schema::Collection::soft_delete).
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MaybeDeleted<D = OwnedDocument> {
+    /// The document.
+    pub document: D,
+    /// When the document was soft-deleted, or `None` if the document has not
+    /// been deleted.
+    pub deleted_at: Option<Timestamp>,
+}
+
+impl<D> MaybeDeleted<D> {
+    /// Returns true if the document has been soft-deleted.
+    #[must_use]
+    pub const fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+}
+
+impl MaybeDeleted<OwnedDocument> {
+    /// Deserializes the document's contents as `C`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the contents can't be deserialized.
+    pub fn collection_document<C: SerializedCollection>(
+        &self,
+    ) -> Result<MaybeDeleted<CollectionDocument<C>>, crate::Error> {
+        Ok(MaybeDeleted {
+            document: CollectionDocument::try_from(&self.document)?,
+            deleted_at: self.deleted_at,
+        })
+    }
+}
+
 impl AsRef<Header> for OwnedDocument {
     fn as_ref(&self) -> &Header {
         &self.header
@@ -6,12 +6,13 @@ use serde::de::{self, Visitor};
 use serde::ser::SerializeStruct;
 use serde::{Deserialize, Serialize};
 
-use crate::connection::{AsyncConnection, Connection};
+use crate::connection::{AsyncConnection, AsyncLowLevelConnection, Connection, LowLevelConnection};
 use crate::document::{
-    BorrowedDocument, CollectionHeader, DocumentId, HasHeader, Header, OwnedDocument,
+    BorrowedDocument, CollectionHeader, DocumentId, HasHeader, Header, HistoricalRevision,
+    OwnedDocument,
 };
-use crate::schema::SerializedCollection;
-use crate::transaction::{Operation, Transaction};
+use crate::schema::{DocumentPatch, SerializedCollection};
+use crate::transaction::{Operation, OperationResult, Transaction};
 use crate::Error;
 
 /// A document with serializable contents.
@@ -76,6 +77,13 @@ where
     /// Updates the document stored in the database with the contents of this
     /// collection document.
     ///
+    /// The update is only applied if the stored document's revision matches
+    /// `self.header.revision`. If the document has been changed since it was
+    /// retrieved, [`Error::DocumentConflict`] is returned containing the header
+    /// of the stored document. To replace the stored document regardless of
+    /// its revision, use [`SerializedCollection::overwrite()`]. To retry the
+    /// update with the latest contents, use [`Self::update_with_retry()`].
+    ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
     /// # use bonsaidb_core::connection::Connection;
@@ -141,6 +149,73 @@ where
         Ok(())
     }
 
+    /// Applies `patch` to the stored document and to `self.contents`. The
+    /// patch is applied by the storage layer using
+    /// [`Collection::apply_patch()`](crate::schema::Collection::apply_patch),
+    /// which means only the patch is sent to the database rather than the
+    /// entire document.
+    ///
+    /// The patch is only applied if the stored document's revision matches
+    /// `self.header.revision`. If the document has been changed since it was
+    /// retrieved, [`Error::DocumentConflict`] is returned. To apply a patch
+    /// regardless of the stored revision, use
+    /// [`SerializedCollection::patch()`].
+    pub fn patch<Cn: Connection, P: DocumentPatch<C>>(
+        &mut self,
+        patch: P,
+        connection: &Cn,
+    ) -> Result<(), Error> {
+        let results = Transaction::from(Operation::patch_serialized::<C, P, _>(
+            &self.header.id,
+            Some(self.header.revision),
+            &patch,
+        )?)
+        .apply(connection)?;
+        self.patched(patch, results)
+    }
+
+    /// Applies `patch` to the stored document and to `self.contents`. The
+    /// patch is applied by the storage layer using
+    /// [`Collection::apply_patch()`](crate::schema::Collection::apply_patch),
+    /// which means only the patch is sent to the database rather than the
+    /// entire document.
+    ///
+    /// The patch is only applied if the stored document's revision matches
+    /// `self.header.revision`. If the document has been changed since it was
+    /// retrieved, [`Error::DocumentConflict`] is returned. To apply a patch
+    /// regardless of the stored revision, use
+    /// [`SerializedCollection::patch_async()`].
+    pub async fn patch_async<Cn: AsyncConnection, P: DocumentPatch<C>>(
+        &mut self,
+        patch: P,
+        connection: &Cn,
+    ) -> Result<(), Error> {
+        let results = Transaction::from(Operation::patch_serialized::<C, P, _>(
+            &self.header.id,
+            Some(self.header.revision),
+            &patch,
+        )?)
+        .apply_async(connection)
+        .await?;
+        self.patched(patch, results)
+    }
+
+    fn patched<P: DocumentPatch<C>>(
+        &mut self,
+        patch: P,
+        results: Vec<OperationResult>,
+    ) -> Result<(), Error> {
+        if let Some(OperationResult::DocumentUpdated { header, .. }) = results.into_iter().next() {
+            self.header = CollectionHeader::try_from(header)?;
+            patch.apply(&mut self.contents);
+            Ok(())
+        } else {
+            unreachable!(
+                "apply_transaction on a single patch should yield a single DocumentUpdated entry"
+            )
+        }
+    }
+
     /// Modifies `self`, automatically retrying the modification if the document
     /// has been updated on the server.
     ///
@@ -154,20 +229,57 @@ where
     pub fn modify<Cn: Connection, Modifier: FnMut(&mut Self) + Send + Sync>(
         &mut self,
         connection: &Cn,
+        modifier: Modifier,
+    ) -> Result<(), Error>
+    where
+        C::Contents: Clone,
+    {
+        self.update_with_retry(connection, usize::MAX, modifier)
+    }
+
+    /// Invokes `modifier` and updates the document, retrying up to
+    /// `max_retries` times if the document has been updated on the server.
+    /// Before each retry, the current document is fetched and `modifier` is
+    /// invoked again.
+    ///
+    /// If the update still conflicts after `max_retries` retries,
+    /// [`Error::DocumentConflict`] is returned.
+    ///
+    /// ## Data loss warning
+    ///
+    /// If you've modified `self` before calling this function and a conflict
+    /// occurs, all changes to self will be lost when the current document is
+    /// fetched before retrying the process again. When you use this function,
+    /// you should limit the edits to the value to within the `modifier`
+    /// callback.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// if let Some(mut document) = MyCollection::get(&42, &db)? {
+    ///     document.update_with_retry(&db, 3, |document| {
+    ///         document.contents.rank += 1;
+    ///     })?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn update_with_retry<Cn: Connection, Modifier: FnMut(&mut Self) + Send + Sync>(
+        &mut self,
+        connection: &Cn,
+        max_retries: usize,
         mut modifier: Modifier,
     ) -> Result<(), Error>
     where
         C::Contents: Clone,
     {
-        let mut is_first_loop = true;
-        // TODO this should have a retry-limit.
+        let mut retries = 0;
         loop {
             // On the first attempt, we want to try sending the update to the
             // database without fetching new contents. If we receive a conflict,
             // on future iterations we will first re-load the data.
-            if is_first_loop {
-                is_first_loop = false;
-            } else {
+            if retries > 0 {
                 *self =
                     C::get(&self.header.id, connection)?.ok_or_else(|| {
                         match DocumentId::new(&self.header.id) {
@@ -178,7 +290,7 @@ where
             }
             modifier(&mut *self);
             match self.update(connection) {
-                Err(Error::DocumentConflict(..)) => {}
+                Err(Error::DocumentConflict(..)) if retries < max_retries => retries += 1,
                 other => return other,
             }
         }
@@ -197,20 +309,65 @@ where
     pub async fn modify_async<Cn: AsyncConnection, Modifier: FnMut(&mut Self) + Send + Sync>(
         &mut self,
         connection: &Cn,
+        modifier: Modifier,
+    ) -> Result<(), Error>
+    where
+        C::Contents: Clone,
+    {
+        self.update_with_retry_async(connection, usize::MAX, modifier)
+            .await
+    }
+
+    /// Invokes `modifier` and updates the document, retrying up to
+    /// `max_retries` times if the document has been updated on the server.
+    /// Before each retry, the current document is fetched and `modifier` is
+    /// invoked again.
+    ///
+    /// If the update still conflicts after `max_retries` retries,
+    /// [`Error::DocumentConflict`] is returned.
+    ///
+    /// ## Data loss warning
+    ///
+    /// If you've modified `self` before calling this function and a conflict
+    /// occurs, all changes to self will be lost when the current document is
+    /// fetched before retrying the process again. When you use this function,
+    /// you should limit the edits to the value to within the `modifier`
+    /// callback.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// if let Some(mut document) = MyCollection::get_async(&42, &db).await? {
+    ///     document
+    ///         .update_with_retry_async(&db, 3, |document| {
+    ///             document.contents.rank += 1;
+    ///         })
+    ///         .await?;
+    /// }
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    pub async fn update_with_retry_async<
+        Cn: AsyncConnection,
+        Modifier: FnMut(&mut Self) + Send + Sync,
+    >(
+        &mut self,
+        connection: &Cn,
+        max_retries: usize,
         mut modifier: Modifier,
     ) -> Result<(), Error>
     where
         C::Contents: Clone,
     {
-        let mut is_first_loop = true;
-        // TODO this should have a retry-limit.
+        let mut retries = 0;
         loop {
             // On the first attempt, we want to try sending the update to the
             // database without fetching new contents. If we receive a conflict,
             // on future iterations we will first re-load the data.
-            if is_first_loop {
-                is_first_loop = false;
-            } else {
+            if retries > 0 {
                 *self = C::get_async(&self.header.id, connection)
                     .await?
                     .ok_or_else(|| match DocumentId::new(&self.header.id) {
@@ -220,7 +377,7 @@ where
             }
             modifier(&mut *self);
             match self.update_async(connection).await {
-                Err(Error::DocumentConflict(..)) => {}
+                Err(Error::DocumentConflict(..)) if retries < max_retries => retries += 1,
                 other => return other,
             }
         }
@@ -266,6 +423,56 @@ where
         Ok(())
     }
 
+    /// Returns the revisions of this document, ordered from oldest to newest.
+    ///
+    /// Previous revisions are only returned if the storage is recording
+    /// revision history. Otherwise, only the current revision is returned.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// if let Some(document) = MyCollection::get(&42, &db)? {
+    ///     for historical in document.revision_history(&db)? {
+    ///         println!("{} at {:?}", historical.revision, historical.timestamp);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn revision_history<Cn: Connection>(
+        &self,
+        connection: &Cn,
+    ) -> Result<Vec<HistoricalRevision>, Error> {
+        connection.revision_history::<C, _>(&self.header.id)
+    }
+
+    /// Returns the revisions of this document, ordered from oldest to newest.
+    ///
+    /// Previous revisions are only returned if the storage is recording
+    /// revision history. Otherwise, only the current revision is returned.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// if let Some(document) = MyCollection::get_async(&42, &db).await? {
+    ///     for historical in document.revision_history_async(&db).await? {
+    ///         println!("{} at {:?}", historical.revision, historical.timestamp);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    pub async fn revision_history_async<Cn: AsyncConnection>(
+        &self,
+        connection: &Cn,
+    ) -> Result<Vec<HistoricalRevision>, Error> {
+        connection.revision_history::<C, _>(&self.header.id).await
+    }
+
     /// Pushes a delete [`Operation`] to the transaction for this document.
     ///
     /// The document will be deleted once the transaction is applied.
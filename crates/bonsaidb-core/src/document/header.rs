@@ -5,6 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::document::{BorrowedDocument, CollectionDocument, DocumentId, OwnedDocument, Revision};
 use crate::key::Key;
 use crate::schema::view::map::Mappings;
+use crate::schema::view::search::term_frequencies;
 use crate::schema::{Map, SerializedCollection};
 
 /// The header of a `Document`.
@@ -64,6 +65,33 @@ pub trait Emit {
         key: K,
         value: Value,
     ) -> Result<Mappings<K, Value>, crate::Error>;
+
+    /// Creates a `Map` result containing an entry for each key and value
+    /// pair in `entries`. This allows a single document to appear under
+    /// multiple keys, such as once for each tag it contains.
+    ///
+    /// When the document is updated, any keys that are no longer emitted are
+    /// removed from the view.
+    fn emit_all<K, Value, Entries>(
+        &self,
+        entries: Entries,
+    ) -> Result<Mappings<K, Value>, crate::Error>
+    where
+        Entries: IntoIterator<Item = (K, Value)>,
+    {
+        entries
+            .into_iter()
+            .map(|(key, value)| self.emit_key_and_value(key, value))
+            .collect()
+    }
+
+    /// Creates a `Map` result containing an entry for each unique term in
+    /// `text`, with the number of times the term appears as the value. This
+    /// is used to implement a
+    /// [`SearchIndex`](crate::schema::view::search::SearchIndex).
+    fn emit_search_terms(&self, text: &str) -> Result<Mappings<String, u32>, crate::Error> {
+        self.emit_all(term_frequencies(text))
+    }
 }
 
 impl Emit for Header {
@@ -225,6 +225,50 @@ impl<const N: usize> TryFrom<[u8; N]> for DocumentId {
     }
 }
 
+impl From<u64> for DocumentId {
+    fn from(id: u64) -> Self {
+        Self::from_u64(id)
+    }
+}
+
+impl From<u32> for DocumentId {
+    fn from(id: u32) -> Self {
+        Self::from_u32(id)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl From<uuid::Uuid> for DocumentId {
+    fn from(id: uuid::Uuid) -> Self {
+        Self(TinyVec::from(&id.as_bytes()[..]))
+    }
+}
+
+impl<'a> TryFrom<&'a DocumentId> for u64 {
+    type Error = crate::Error;
+
+    fn try_from(id: &'a DocumentId) -> Result<Self, Self::Error> {
+        id.deserialize()
+    }
+}
+
+impl<'a> TryFrom<&'a DocumentId> for u32 {
+    type Error = crate::Error;
+
+    fn try_from(id: &'a DocumentId) -> Result<Self, Self::Error> {
+        id.deserialize()
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl<'a> TryFrom<&'a DocumentId> for uuid::Uuid {
+    type Error = crate::Error;
+
+    fn try_from(id: &'a DocumentId) -> Result<Self, Self::Error> {
+        id.deserialize()
+    }
+}
+
 impl DocumentId {
     const INLINE_SIZE: usize = 16;
     /// The maximum size able to be stored in a document's unique id.
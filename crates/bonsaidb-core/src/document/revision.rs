@@ -3,6 +3,8 @@ use std::fmt::{Debug, Display, Write};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
+use crate::keyvalue::Timestamp;
+
 /// Information about a `Document`'s revision history.
 #[derive(Clone, Copy, Eq, PartialEq, Serialize, Deserialize, Hash)]
 pub struct Revision {
@@ -68,6 +70,21 @@ impl Display for Revision {
     }
 }
 
+/// A revision of a document, returned when requesting a document's revision
+/// history.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct HistoricalRevision {
+    /// The revision of the document.
+    pub revision: Revision,
+    /// The id of the transaction that created this revision. This is `None`
+    /// if revision history wasn't being recorded when this revision was
+    /// created.
+    pub transaction_id: Option<u64>,
+    /// When this revision was created. This is `None` if revision history
+    /// wasn't being recorded when this revision was created.
+    pub timestamp: Option<Timestamp>,
+}
+
 fn digest(payload: &[u8]) -> [u8; 32] {
     let mut hasher = Sha256::default();
     hasher.update(payload);
@@ -39,6 +39,13 @@ pub trait KeyEncoding<K = Self>: Send + Sync {
     /// number of bytes for each value, this should be `None`.
     const LENGTH: Option<usize>;
 
+    /// If true, the bytes produced by [`as_ord_bytes()`](Self::as_ord_bytes)
+    /// sort in the same order as the values they were encoded from. View
+    /// queries that rely on key ordering, such as range queries, return
+    /// [`Error::KeyNotOrderPreserving`](crate::Error::KeyNotOrderPreserving)
+    /// for keys whose encoding is not order-preserving.
+    const ORDER_PRESERVING: bool = true;
+
     /// Describes this type by invoking functions on `visitor` describing the
     /// key being encoded.
     ///
@@ -59,6 +66,22 @@ pub trait KeyEncoding<K = Self>: Send + Sync {
 /// A trait that enables a type to convert itself into a `memcmp`-compatible
 /// sequence of bytes.
 ///
+/// # Ordering requirements
+///
+/// View entries are stored sorted by their encoded bytes. For range queries,
+/// [`descending()`](crate::connection::View::descending) and
+/// [`after()`](crate::connection::View::after) to behave as expected,
+/// comparing two encoded keys byte-by-byte must produce the same result as
+/// comparing the values they were encoded from. Range queries are executed by
+/// scanning the requested range of the stored entries rather than filtering
+/// every entry, so a key whose encoding doesn't preserve ordering would
+/// silently return incorrect results.
+///
+/// All `Key` implementations provided by BonsaiDb preserve ordering. If an
+/// implementation cannot, it should set [`KeyEncoding::ORDER_PRESERVING`] to
+/// false, which causes queries that depend on ordering to return
+/// [`Error::KeyNotOrderPreserving`](crate::Error::KeyNotOrderPreserving).
+///
 /// # Deriving this trait
 ///
 /// This trait can be derived on structs and enums whose members all implement
@@ -183,7 +206,9 @@ pub trait KeyEncoding<K = Self>: Send + Sync {
 /// match.
 ///
 /// This null-byte edge case only applies to variable length [`Key`]s
-/// ([`KeyEncoding::LENGTH`] is `None`).
+/// ([`KeyEncoding::LENGTH`] is `None`). Because ordering can no longer be
+/// guaranteed, keys derived using `null_handling = allow` have
+/// [`KeyEncoding::ORDER_PRESERVING`] set to false.
 pub trait Key<'k>: KeyEncoding<Self> + Clone + Send + Sync {
     /// If true, this type can benefit from an owned `Vec<u8>`. This flag is
     /// used as a hint of whether to attempt to do memcpy operations in some
@@ -215,6 +240,7 @@ where
     type Error = KE::Error;
 
     const LENGTH: Option<usize> = K::LENGTH;
+    const ORDER_PRESERVING: bool = KE::ORDER_PRESERVING;
 
     fn describe<Visitor>(visitor: &mut Visitor)
     where
@@ -693,6 +719,7 @@ where
     type Error = TOwned::Error;
 
     const LENGTH: Option<usize> = TBorrowed::LENGTH;
+    const ORDER_PRESERVING: bool = TBorrowed::ORDER_PRESERVING;
 
     fn describe<Visitor>(visitor: &mut Visitor)
     where
@@ -1244,6 +1271,7 @@ macro_rules! impl_key_for_tuple {
                 ($(Some($varname)),+,) => Some($($varname +)+ 0),
                 _ => None,
             };
+            const ORDER_PRESERVING: bool = $($generic::ORDER_PRESERVING &&)+ true;
 
             fn describe<Visitor>(visitor: &mut Visitor)
             where
@@ -2161,6 +2189,7 @@ where
         Some(length) => Some(1 + length),
         None => None,
     };
+    const ORDER_PRESERVING: bool = T::ORDER_PRESERVING;
 
     fn describe<Visitor>(visitor: &mut Visitor)
     where
@@ -2225,6 +2254,7 @@ where
     type Error = <TBorrowed as KeyEncoding<T>>::Error;
 
     const LENGTH: Option<usize> = None;
+    const ORDER_PRESERVING: bool = TBorrowed::ORDER_PRESERVING && EBorrowed::ORDER_PRESERVING;
 
     fn describe<Visitor>(visitor: &mut Visitor)
     where
@@ -12,7 +12,9 @@ mod implementation {
     use futures::future::BoxFuture;
     use serde::Serialize;
 
-    use crate::keyvalue::{Command, KeyCheck, KeyOperation, KeyStatus, Output, Timestamp};
+    use crate::keyvalue::{
+        Command, KeyCheck, KeyOperation, KeyStatus, Output, SetCommand, Timestamp,
+    };
     use crate::Error;
 
     /// Types for executing get operations.
@@ -23,6 +25,8 @@ mod implementation {
     pub mod namespaced;
     /// Types for executing set operations.
     pub mod set;
+    /// Types for streaming values in chunks.
+    pub mod stream;
 
     use namespaced::Namespaced;
 
@@ -152,6 +156,45 @@ mod implementation {
             get::Builder::new(self, self.key_namespace().map(Into::into), key.into())
         }
 
+        /// Sets `key` to the bytes read from `reader`, returning the number of
+        /// bytes stored. Unlike [`set_binary_key()`](Self::set_binary_key),
+        /// the value is never fully loaded into memory. Instead, it is read
+        /// and sent in chunks of up to
+        /// [`KEY_VALUE_STREAM_CHUNK_SIZE`](crate::limits::KEY_VALUE_STREAM_CHUNK_SIZE)
+        /// bytes.
+        ///
+        /// The first chunk replaces any existing value using [`Command::Set`],
+        /// and each remaining chunk is appended using [`Command::Append`].
+        /// This means the value is not stored atomically: while this function
+        /// is executing, other readers can observe a partially written value.
+        /// If the write fails, the partially written value remains stored.
+        fn set_key_streaming<S: Into<String>, R: std::io::Read>(
+            &self,
+            key: S,
+            reader: R,
+        ) -> Result<u64, Error> {
+            stream::write(
+                self,
+                self.key_namespace().map(Into::into),
+                key.into(),
+                reader,
+            )
+        }
+
+        /// Returns a reader of the bytes stored at `key`, or `None` if `key`
+        /// is not present. Unlike [`get_key()`](Self::get_key), the value is
+        /// never fully loaded into memory. Instead, it is requested in chunks
+        /// using [`Command::GetRange`] as it is read.
+        ///
+        /// Because each chunk is requested separately, changes made to the key
+        /// while it is being read may be partially observed by the reader.
+        fn get_key_streaming<S: Into<String>>(
+            &'_ self,
+            key: S,
+        ) -> Result<Option<stream::ValueReader<'_, Self>>, Error> {
+            stream::ValueReader::new(self, self.key_namespace().map(Into::into), key.into())
+        }
+
         /// Deletes the value stored at `key`.
         fn delete_key<S: Into<String> + Send>(&'_ self, key: S) -> Result<KeyStatus, Error> {
             match self.execute_key_operation(KeyOperation {
@@ -160,7 +203,113 @@ mod implementation {
                 command: Command::Delete,
             })? {
                 Output::Status(status) => Ok(status),
-                Output::Value(_) => unreachable!("invalid output from delete operation"),
+                Output::Value(_) | Output::Expiration(_) | Output::Keys(_) | Output::Count(_) => {
+                    unreachable!("invalid output from delete operation")
+                }
+            }
+        }
+
+        /// Returns the expiration of the value stored at `key`. Returns `None`
+        /// if the key does not exist or does not have an expiration.
+        fn persisted_expiration<S: Into<String> + Send>(
+            &'_ self,
+            key: S,
+        ) -> Result<Option<Timestamp>, Error> {
+            match self.execute_key_operation(KeyOperation {
+                namespace: self.key_namespace().map(ToOwned::to_owned),
+                key: key.into(),
+                command: Command::GetExpiration,
+            })? {
+                Output::Expiration(expiration) => Ok(expiration),
+                Output::Status(_) | Output::Value(_) | Output::Keys(_) | Output::Count(_) => {
+                    unreachable!("invalid output from get expiration operation")
+                }
+            }
+        }
+
+        /// Atomically replaces the value stored at `key` with `new` if the
+        /// currently stored value is equal to `expected`. If `expected` is
+        /// `None`, `new` is only stored if `key` is not present. Returns true
+        /// if the value was replaced.
+        ///
+        /// If the key is updated, its existing expiration is kept. See
+        /// [`Command::CompareAndSwap`] for how values are compared.
+        fn compare_and_swap_key<S: Into<String> + Send>(
+            &'_ self,
+            key: S,
+            expected: Option<Value>,
+            new: Value,
+        ) -> Result<bool, Error> {
+            match self.execute_key_operation(KeyOperation {
+                namespace: self.key_namespace().map(ToOwned::to_owned),
+                key: key.into(),
+                command: Command::CompareAndSwap { expected, new },
+            })? {
+                Output::Status(status) => Ok(status != KeyStatus::NotChanged),
+                Output::Value(_) | Output::Expiration(_) | Output::Keys(_) | Output::Count(_) => {
+                    unreachable!("invalid output from compare and swap operation")
+                }
+            }
+        }
+
+        /// Atomically stores `value` in `key`, returning the previously stored
+        /// value. Returns `None` if the key was not present.
+        ///
+        /// If the key already exists, its existing expiration is kept.
+        fn get_and_set_key<S: Into<String> + Send>(
+            &'_ self,
+            key: S,
+            value: Value,
+        ) -> Result<Option<Value>, Error> {
+            match self.execute_key_operation(KeyOperation {
+                namespace: self.key_namespace().map(ToOwned::to_owned),
+                key: key.into(),
+                command: Command::Set(SetCommand {
+                    value,
+                    expiration: None,
+                    keep_existing_expiration: true,
+                    check: None,
+                    return_previous_value: true,
+                }),
+            })? {
+                Output::Value(value) => Ok(value),
+                Output::Status(_) | Output::Expiration(_) | Output::Keys(_) | Output::Count(_) => {
+                    unreachable!("invalid output from get and set operation")
+                }
+            }
+        }
+
+        /// Returns the keys stored in the current namespace, in ascending
+        /// order. Keys stored in nested namespaces created using
+        /// [`with_key_namespace()`](Self::with_key_namespace) are not
+        /// included.
+        fn list_keys(&'_ self) -> Result<Vec<String>, Error> {
+            match self.execute_key_operation(KeyOperation {
+                namespace: self.key_namespace().map(ToOwned::to_owned),
+                key: String::new(),
+                command: Command::ListKeys,
+            })? {
+                Output::Keys(keys) => Ok(keys),
+                Output::Status(_) | Output::Value(_) | Output::Expiration(_) | Output::Count(_) => {
+                    unreachable!("invalid output from list keys operation")
+                }
+            }
+        }
+
+        /// Deletes all keys stored in the current namespace in a single
+        /// operation, returning the number of keys deleted. Keys stored in
+        /// nested namespaces created using
+        /// [`with_key_namespace()`](Self::with_key_namespace) are not deleted.
+        fn delete_namespace(&'_ self) -> Result<u64, Error> {
+            match self.execute_key_operation(KeyOperation {
+                namespace: self.key_namespace().map(ToOwned::to_owned),
+                key: String::new(),
+                command: Command::DeleteNamespace,
+            })? {
+                Output::Count(count) => Ok(count),
+                Output::Status(_) | Output::Value(_) | Output::Expiration(_) | Output::Keys(_) => {
+                    unreachable!("invalid output from delete namespace operation")
+                }
             }
         }
 
@@ -303,6 +452,47 @@ mod implementation {
             get::AsyncBuilder::new(self, self.key_namespace().map(Into::into), key.into())
         }
 
+        /// Sets `key` to the bytes read from `reader`, returning the number of
+        /// bytes stored. Unlike [`set_binary_key()`](Self::set_binary_key),
+        /// the value is never fully loaded into memory. Instead, it is read
+        /// and sent in chunks of up to
+        /// [`KEY_VALUE_STREAM_CHUNK_SIZE`](crate::limits::KEY_VALUE_STREAM_CHUNK_SIZE)
+        /// bytes.
+        ///
+        /// The first chunk replaces any existing value using [`Command::Set`],
+        /// and each remaining chunk is appended using [`Command::Append`].
+        /// This means the value is not stored atomically: while this function
+        /// is executing, other readers can observe a partially written value.
+        /// If the write fails, the partially written value remains stored.
+        async fn set_key_streaming<S: Into<String> + Send, R: futures::AsyncRead + Unpin + Send>(
+            &self,
+            key: S,
+            reader: R,
+        ) -> Result<u64, Error> {
+            stream::write_async(
+                self,
+                self.key_namespace().map(Into::into),
+                key.into(),
+                reader,
+            )
+            .await
+        }
+
+        /// Returns a reader of the bytes stored at `key`, or `None` if `key`
+        /// is not present. Unlike [`get_key()`](Self::get_key), the value is
+        /// never fully loaded into memory. Instead, it is requested in chunks
+        /// using [`Command::GetRange`] as it is read.
+        ///
+        /// Because each chunk is requested separately, changes made to the key
+        /// while it is being read may be partially observed by the reader.
+        async fn get_key_streaming<S: Into<String> + Send>(
+            &'_ self,
+            key: S,
+        ) -> Result<Option<stream::AsyncValueReader<'_, Self>>, Error> {
+            stream::AsyncValueReader::new(self, self.key_namespace().map(Into::into), key.into())
+                .await
+        }
+
         /// Deletes the value stored at `key`.
         async fn delete_key<S: Into<String> + Send>(&'_ self, key: S) -> Result<KeyStatus, Error> {
             match self
@@ -314,7 +504,128 @@ mod implementation {
                 .await?
             {
                 Output::Status(status) => Ok(status),
-                Output::Value(_) => unreachable!("invalid output from delete operation"),
+                Output::Value(_) | Output::Expiration(_) | Output::Keys(_) | Output::Count(_) => {
+                    unreachable!("invalid output from delete operation")
+                }
+            }
+        }
+
+        /// Returns the expiration of the value stored at `key`. Returns `None`
+        /// if the key does not exist or does not have an expiration.
+        async fn persisted_expiration<S: Into<String> + Send>(
+            &'_ self,
+            key: S,
+        ) -> Result<Option<Timestamp>, Error> {
+            match self
+                .execute_key_operation(KeyOperation {
+                    namespace: self.key_namespace().map(ToOwned::to_owned),
+                    key: key.into(),
+                    command: Command::GetExpiration,
+                })
+                .await?
+            {
+                Output::Expiration(expiration) => Ok(expiration),
+                Output::Status(_) | Output::Value(_) | Output::Keys(_) | Output::Count(_) => {
+                    unreachable!("invalid output from get expiration operation")
+                }
+            }
+        }
+
+        /// Atomically replaces the value stored at `key` with `new` if the
+        /// currently stored value is equal to `expected`. If `expected` is
+        /// `None`, `new` is only stored if `key` is not present. Returns true
+        /// if the value was replaced.
+        ///
+        /// If the key is updated, its existing expiration is kept. See
+        /// [`Command::CompareAndSwap`] for how values are compared.
+        async fn compare_and_swap_key<S: Into<String> + Send>(
+            &'_ self,
+            key: S,
+            expected: Option<Value>,
+            new: Value,
+        ) -> Result<bool, Error> {
+            match self
+                .execute_key_operation(KeyOperation {
+                    namespace: self.key_namespace().map(ToOwned::to_owned),
+                    key: key.into(),
+                    command: Command::CompareAndSwap { expected, new },
+                })
+                .await?
+            {
+                Output::Status(status) => Ok(status != KeyStatus::NotChanged),
+                Output::Value(_) | Output::Expiration(_) | Output::Keys(_) | Output::Count(_) => {
+                    unreachable!("invalid output from compare and swap operation")
+                }
+            }
+        }
+
+        /// Atomically stores `value` in `key`, returning the previously stored
+        /// value. Returns `None` if the key was not present.
+        ///
+        /// If the key already exists, its existing expiration is kept.
+        async fn get_and_set_key<S: Into<String> + Send>(
+            &'_ self,
+            key: S,
+            value: Value,
+        ) -> Result<Option<Value>, Error> {
+            match self
+                .execute_key_operation(KeyOperation {
+                    namespace: self.key_namespace().map(ToOwned::to_owned),
+                    key: key.into(),
+                    command: Command::Set(SetCommand {
+                        value,
+                        expiration: None,
+                        keep_existing_expiration: true,
+                        check: None,
+                        return_previous_value: true,
+                    }),
+                })
+                .await?
+            {
+                Output::Value(value) => Ok(value),
+                Output::Status(_) | Output::Expiration(_) | Output::Keys(_) | Output::Count(_) => {
+                    unreachable!("invalid output from get and set operation")
+                }
+            }
+        }
+
+        /// Returns the keys stored in the current namespace, in ascending
+        /// order. Keys stored in nested namespaces created using
+        /// [`with_key_namespace()`](Self::with_key_namespace) are not
+        /// included.
+        async fn list_keys(&'_ self) -> Result<Vec<String>, Error> {
+            match self
+                .execute_key_operation(KeyOperation {
+                    namespace: self.key_namespace().map(ToOwned::to_owned),
+                    key: String::new(),
+                    command: Command::ListKeys,
+                })
+                .await?
+            {
+                Output::Keys(keys) => Ok(keys),
+                Output::Status(_) | Output::Value(_) | Output::Expiration(_) | Output::Count(_) => {
+                    unreachable!("invalid output from list keys operation")
+                }
+            }
+        }
+
+        /// Deletes all keys stored in the current namespace in a single
+        /// operation, returning the number of keys deleted. Keys stored in
+        /// nested namespaces created using
+        /// [`with_key_namespace()`](Self::with_key_namespace) are not deleted.
+        async fn delete_namespace(&'_ self) -> Result<u64, Error> {
+            match self
+                .execute_key_operation(KeyOperation {
+                    namespace: self.key_namespace().map(ToOwned::to_owned),
+                    key: String::new(),
+                    command: Command::DeleteNamespace,
+                })
+                .await?
+            {
+                Output::Count(count) => Ok(count),
+                Output::Status(_) | Output::Value(_) | Output::Expiration(_) | Output::Keys(_) => {
+                    unreachable!("invalid output from delete namespace operation")
+                }
             }
         }
 
@@ -411,8 +722,44 @@ pub enum Command {
         /// the type of `amount`.
         saturating: bool,
     },
+    /// Append `value` to the bytes stored in a key. If the key isn't present,
+    /// it is inserted without an expiration. Returns an error if the key
+    /// contains a numeric value.
+    Append {
+        /// The bytes to append.
+        value: Bytes,
+    },
+    /// Get up to `length` bytes of the value stored in a key, beginning at
+    /// `start`. If `start` is beyond the end of the value, no bytes are
+    /// returned. Returns an error if the key contains a numeric value.
+    GetRange {
+        /// The offset of the first byte to return.
+        start: u64,
+        /// The maximum number of bytes to return.
+        length: u64,
+    },
     /// Delete a key.
     Delete,
+    /// Get the expiration of a key.
+    GetExpiration,
+    /// Atomically replace the value stored in a key if the currently stored
+    /// value matches `expected`. Values are only equal if they are the same
+    /// type: `Numeric::Integer(1)` does not match `Numeric::UnsignedInteger(1)`.
+    ///
+    /// If the key is updated, its existing expiration is kept.
+    CompareAndSwap {
+        /// The value the key must currently contain. If `None`, the key must
+        /// not be present.
+        expected: Option<Value>,
+        /// The value to store.
+        new: Value,
+    },
+    /// List the keys stored in the operation's namespace. The operation's key
+    /// is ignored.
+    ListKeys,
+    /// Delete all keys stored in the operation's namespace, returning the
+    /// number of keys deleted. The operation's key is ignored.
+    DeleteNamespace,
 }
 
 /// Set a key/value pair.
@@ -727,6 +1074,12 @@ pub enum Output {
     Status(KeyStatus),
     /// A value was returned.
     Value(Option<Value>),
+    /// An expiration was returned.
+    Expiration(Option<Timestamp>),
+    /// A list of keys was returned.
+    Keys(Vec<String>),
+    /// The number of keys affected was returned.
+    Count(u64),
 }
 /// The status of an operation on a Key.
 #[derive(Copy, Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
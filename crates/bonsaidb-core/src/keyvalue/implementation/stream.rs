@@ -0,0 +1,290 @@
+use std::io::Read;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+
+use arc_bytes::serde::Bytes;
+use futures::future::BoxFuture;
+use futures::{AsyncRead, AsyncReadExt, FutureExt};
+
+use super::{Command, KeyOperation, KeyValue, Output, SetCommand};
+use crate::keyvalue::{AsyncKeyValue, Value};
+use crate::limits::KEY_VALUE_STREAM_CHUNK_SIZE;
+use crate::Error;
+
+/// A reader of a value stored in a key-value store, returned from
+/// [`KeyValue::get_key_streaming()`].
+///
+/// The value is requested in chunks of up to [`KEY_VALUE_STREAM_CHUNK_SIZE`]
+/// bytes. The next chunk is not requested until the current chunk has been
+/// read.
+#[must_use = "readers do nothing unless read from"]
+pub struct ValueReader<'a, KeyValue> {
+    kv: &'a KeyValue,
+    chunks: Chunks,
+}
+
+impl<'a, K> ValueReader<'a, K>
+where
+    K: KeyValue,
+{
+    pub(crate) fn new(
+        kv: &'a K,
+        namespace: Option<String>,
+        key: String,
+    ) -> Result<Option<Self>, Error> {
+        let mut chunks = Chunks::new(namespace, key);
+        let output = kv.execute_key_operation(chunks.next_operation())?;
+        Ok(chunks.receive_first(output)?.then_some(Self { kv, chunks }))
+    }
+}
+
+impl<'a, K> Read for ValueReader<'a, K>
+where
+    K: KeyValue,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            if let Some(read) = self.chunks.read(buf) {
+                return Ok(read);
+            }
+
+            let output = self
+                .kv
+                .execute_key_operation(self.chunks.next_operation())
+                .map_err(io_error)?;
+            self.chunks.receive(output).map_err(io_error)?;
+        }
+    }
+}
+
+/// A reader of a value stored in a key-value store, returned from
+/// [`AsyncKeyValue::get_key_streaming()`].
+///
+/// The value is requested in chunks of up to [`KEY_VALUE_STREAM_CHUNK_SIZE`]
+/// bytes. The next chunk is not requested until the current chunk has been
+/// read.
+#[must_use = "readers do nothing unless polled"]
+pub struct AsyncValueReader<'a, KeyValue> {
+    kv: &'a KeyValue,
+    chunks: Chunks,
+    fetching: Option<BoxFuture<'a, Result<Output, Error>>>,
+}
+
+impl<'a, K> AsyncValueReader<'a, K>
+where
+    K: AsyncKeyValue,
+{
+    pub(crate) async fn new(
+        kv: &'a K,
+        namespace: Option<String>,
+        key: String,
+    ) -> Result<Option<Self>, Error> {
+        let mut chunks = Chunks::new(namespace, key);
+        let output = kv.execute_key_operation(chunks.next_operation()).await?;
+        Ok(chunks.receive_first(output)?.then_some(Self {
+            kv,
+            chunks,
+            fetching: None,
+        }))
+    }
+}
+
+impl<'a, K> AsyncRead for AsyncValueReader<'a, K>
+where
+    K: AsyncKeyValue,
+{
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(fetching) = &mut this.fetching {
+                let output = ready!(fetching.poll_unpin(cx));
+                this.fetching = None;
+                this.chunks
+                    .receive(output.map_err(io_error)?)
+                    .map_err(io_error)?;
+            }
+
+            if let Some(read) = this.chunks.read(buf) {
+                return Poll::Ready(Ok(read));
+            }
+
+            this.fetching = Some(this.kv.execute_key_operation(this.chunks.next_operation()));
+        }
+    }
+}
+
+/// The state of reading a value in chunks.
+struct Chunks {
+    namespace: Option<String>,
+    key: String,
+    offset: u64,
+    chunk: Bytes,
+    position: usize,
+    complete: bool,
+}
+
+impl Chunks {
+    fn new(namespace: Option<String>, key: String) -> Self {
+        Self {
+            namespace,
+            key,
+            offset: 0,
+            chunk: Bytes::from(Vec::new()),
+            position: 0,
+            complete: false,
+        }
+    }
+
+    fn next_operation(&self) -> KeyOperation {
+        KeyOperation {
+            namespace: self.namespace.clone(),
+            key: self.key.clone(),
+            command: Command::GetRange {
+                start: self.offset,
+                length: KEY_VALUE_STREAM_CHUNK_SIZE as u64,
+            },
+        }
+    }
+
+    /// Receives the first chunk, returning false if the key isn't present.
+    fn receive_first(&mut self, output: Output) -> Result<bool, Error> {
+        if matches!(output, Output::Value(None)) {
+            Ok(false)
+        } else {
+            self.receive(output)?;
+            Ok(true)
+        }
+    }
+
+    fn receive(&mut self, output: Output) -> Result<(), Error> {
+        match output {
+            Output::Value(Some(Value::Bytes(chunk))) => {
+                self.offset += chunk.len() as u64;
+                self.complete = chunk.len() < KEY_VALUE_STREAM_CHUNK_SIZE;
+                self.chunk = chunk;
+                self.position = 0;
+                Ok(())
+            }
+            Output::Value(Some(Value::Numeric(_))) => Err(Error::other(
+                "key-value",
+                "key contains numeric value, not bytes",
+            )),
+            Output::Value(None) => Err(Error::other(
+                "key-value",
+                "key was removed while its value was being read",
+            )),
+            Output::Status(_) | Output::Expiration(_) | Output::Keys(_) | Output::Count(_) => {
+                unreachable!("invalid output from get range operation")
+            }
+        }
+    }
+
+    /// Copies bytes from the current chunk into `buf`. Returns `None` if the
+    /// next chunk needs to be requested.
+    fn read(&mut self, buf: &mut [u8]) -> Option<usize> {
+        let remaining = &self.chunk[self.position..];
+        if remaining.is_empty() && !self.complete {
+            return None;
+        }
+
+        let length = remaining.len().min(buf.len());
+        buf[..length].copy_from_slice(&remaining[..length]);
+        self.position += length;
+        Some(length)
+    }
+}
+
+fn io_error(err: Error) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, err)
+}
+
+/// Returns the command that stores `chunk`. The first chunk replaces the
+/// existing value, and the remaining chunks are appended to it.
+fn write_command(first: bool, chunk: Vec<u8>) -> Command {
+    if first {
+        Command::Set(SetCommand {
+            value: Value::Bytes(Bytes::from(chunk)),
+            expiration: None,
+            keep_existing_expiration: false,
+            check: None,
+            return_previous_value: false,
+        })
+    } else {
+        Command::Append {
+            value: Bytes::from(chunk),
+        }
+    }
+}
+
+pub(crate) fn write<K: KeyValue, R: Read>(
+    kv: &K,
+    namespace: Option<String>,
+    key: String,
+    mut reader: R,
+) -> Result<u64, Error> {
+    let mut written = 0;
+    let mut first = true;
+    loop {
+        let mut chunk = Vec::new();
+        reader
+            .by_ref()
+            .take(KEY_VALUE_STREAM_CHUNK_SIZE as u64)
+            .read_to_end(&mut chunk)
+            .map_err(|err| Error::other("key-value", err))?;
+        let length = chunk.len();
+        if length == 0 && !first {
+            break;
+        }
+
+        kv.execute_key_operation(KeyOperation {
+            namespace: namespace.clone(),
+            key: key.clone(),
+            command: write_command(first, chunk),
+        })?;
+        first = false;
+        written += length as u64;
+        if length < KEY_VALUE_STREAM_CHUNK_SIZE {
+            break;
+        }
+    }
+    Ok(written)
+}
+
+pub(crate) async fn write_async<K: AsyncKeyValue, R: AsyncRead + Unpin + Send>(
+    kv: &K,
+    namespace: Option<String>,
+    key: String,
+    mut reader: R,
+) -> Result<u64, Error> {
+    let mut written = 0;
+    let mut first = true;
+    loop {
+        let mut chunk = Vec::new();
+        (&mut reader)
+            .take(KEY_VALUE_STREAM_CHUNK_SIZE as u64)
+            .read_to_end(&mut chunk)
+            .await
+            .map_err(|err| Error::other("key-value", err))?;
+        let length = chunk.len();
+        if length == 0 && !first {
+            break;
+        }
+
+        kv.execute_key_operation(KeyOperation {
+            namespace: namespace.clone(),
+            key: key.clone(),
+            command: write_command(first, chunk),
+        })
+        .await?;
+        first = false;
+        written += length as u64;
+        if length < KEY_VALUE_STREAM_CHUNK_SIZE {
+            break;
+        }
+    }
+    Ok(written)
+}
@@ -45,11 +45,17 @@ pub mod networking;
 /// Types for Publish/Subscribe (`PubSub`) messaging.
 pub mod pubsub;
 
+/// Types for watching collections for changes.
+pub mod changes;
+
 use std::fmt::Display;
 use std::string::FromUtf8Error;
+use std::time::Duration;
 
-use schema::{view, CollectionName, SchemaName, ViewName};
+use schema::{view, CollectionName, Name, SchemaName, ViewName};
 use serde::{Deserialize, Serialize};
+#[cfg(feature = "uuid")]
+pub use uuid;
 pub use {
     actionable, arc_bytes, async_trait, circulate, num_traits, ordered_varint, transmog,
     transmog_pot,
@@ -124,8 +130,8 @@ pub enum Error {
     Networking(networking::Error),
 
     /// A `Collection` being added already exists. This can be caused by a collection name not being unique.
-    #[error("attempted to define a collection that already has been defined")]
-    CollectionAlreadyDefined,
+    #[error("collection '{0}' was already defined")]
+    CollectionAlreadyDefined(CollectionName),
 
     /// An attempt to update a document that doesn't exist.
     #[error("the requested document id {1} from collection {0} was not found")]
@@ -156,6 +162,48 @@ pub enum Error {
         existing_document: Box<Header>,
     },
 
+    /// A document was stored using a version of its collection's format that
+    /// can't be migrated to
+    /// [`Collection::CURRENT_VERSION`](schema::Collection::CURRENT_VERSION).
+    #[error("documents stored using version {version} of {collection} can't be migrated")]
+    DocumentVersionUnsupported {
+        /// The collection containing the document.
+        collection: CollectionName,
+        /// The version the document was stored with.
+        version: u32,
+    },
+
+    /// A document was stored using a serialization format that can't be
+    /// converted to
+    /// [`Collection::FORMAT_NAME`](schema::Collection::FORMAT_NAME).
+    #[error("documents stored using format {format:?} in {collection} can't be converted")]
+    DocumentFormatUnsupported {
+        /// The collection containing the document.
+        collection: CollectionName,
+        /// The name of the format the document was stored with.
+        format: Option<String>,
+    },
+
+    /// A document was patched in a collection that doesn't implement
+    /// [`Collection::apply_patch()`](schema::Collection::apply_patch).
+    #[error("{0} does not support patching documents")]
+    PatchUnsupported(CollectionName),
+
+    /// A [`Filter`](schema::Filter) was evaluated against a collection whose
+    /// documents aren't serialized using [`pot`].
+    #[error("{0} does not support filtering documents")]
+    FilterUnsupported(CollectionName),
+
+    /// A document was rejected by its collection's
+    /// [`Collection::validate()`](schema::Collection::validate) function.
+    #[error("document rejected by {collection}: {message}")]
+    ValidationFailed {
+        /// The collection the document was being written to.
+        collection: CollectionName,
+        /// The reason the document was rejected.
+        message: String,
+    },
+
     /// When pushing a document, an error occurred while generating the next unique id.
     #[error("an error occurred generating a new unique id for {0}: {1}")]
     DocumentPush(CollectionName, NextValueError),
@@ -198,6 +246,110 @@ pub enum Error {
     #[error("time error: {0}")]
     Time(#[from] TimeError),
 
+    /// The server rejected the request because the connection exceeded its
+    /// rate limit.
+    #[error("rate limit exceeded, retry after {retry_after:?}")]
+    RateLimited {
+        /// The amount of time to wait before the request would be allowed.
+        retry_after: Duration,
+    },
+
+    /// A view query required the view's keys to be ordered, but the key type's
+    /// encoding does not preserve ordering. See
+    /// [`KeyEncoding::ORDER_PRESERVING`](key::KeyEncoding::ORDER_PRESERVING).
+    #[error("key type `{0}` does not preserve ordering when encoded")]
+    KeyNotOrderPreserving(String),
+
+    /// The database is a replica of another server's database and can only be
+    /// modified by replication.
+    #[error("database '{0}' is a read-only replica")]
+    ReadOnlyReplica(String),
+
+    /// The storage was opened in read-only mode, and the requested operation
+    /// would modify it, or the requested operation would modify a
+    /// [`VirtualCollection`](schema::VirtualCollection).
+    #[error("the requested operation would modify read-only data")]
+    ReadOnly,
+
+    /// Assigning the parent group would cause the permission group with the
+    /// contained id to inherit from itself.
+    #[error("permission group {0} cannot inherit from itself")]
+    PermissionGroupCycle(u64),
+
+    /// The session a request was made with is no longer valid. This happens
+    /// when the session is logged out or when the client reconnects, as
+    /// sessions only exist for the lifetime of the connection they were
+    /// authenticated on.
+    #[error("the session is no longer valid")]
+    SessionExpired,
+
+    /// The password did not meet the requirements of the storage's password
+    /// policy. The contained string describes the unmet requirement.
+    #[error("password does not meet the password policy: {0}")]
+    WeakPassword(String),
+
+    /// A value stored in the key-value store was larger than the storage's
+    /// maximum value size.
+    #[error("value of {size} bytes exceeds the maximum value size of {maximum} bytes")]
+    ValueTooLarge {
+        /// The size of the value, in bytes.
+        size: u64,
+        /// The maximum size of a value, in bytes.
+        maximum: u64,
+    },
+
+    /// A `PubSub` message's payload was larger than the storage's maximum
+    /// payload size.
+    #[error("message of {size} bytes exceeds the maximum payload size of {maximum} bytes")]
+    MessageTooLarge {
+        /// The size of the payload, in bytes.
+        size: u64,
+        /// The maximum size of a payload, in bytes.
+        maximum: u64,
+    },
+
+    /// A `PubSub` message was published to a topic that is reserved for
+    /// messages published by BonsaiDb, such as a [change feed
+    /// topic](changes::change_feed_topic).
+    #[error("publishing to reserved topics is not allowed")]
+    ReservedTopic,
+
+    /// A document sent to a server was larger than the server's maximum
+    /// document size.
+    #[error("document of {size} bytes exceeds the maximum document size of {limit} bytes")]
+    DocumentTooLarge {
+        /// The size of the document, in bytes.
+        size: u64,
+        /// The maximum size of a document, in bytes.
+        limit: u64,
+    },
+
+    /// A [`Trigger`](schema::Trigger) with the same name was already
+    /// registered for the collection.
+    #[error("trigger '{name}' was already registered for collection '{collection}'")]
+    TriggerAlreadyRegistered {
+        /// The collection the trigger observes.
+        collection: CollectionName,
+        /// The name of the trigger.
+        name: Name,
+    },
+
+    /// A document couldn't be deleted because other documents belong to it
+    /// through a relationship using
+    /// [`OnDelete::Restrict`](schema::relationship::OnDelete::Restrict).
+    #[error("document {id} in {collection} can't be deleted because {referencing_documents} document(s) in {} belong to it through '{relationship}'", relationship.collection)]
+    DeleteRestricted {
+        /// The collection of the document that couldn't be deleted.
+        collection: CollectionName,
+        /// The id of the document that couldn't be deleted.
+        id: Box<DocumentId>,
+        /// The name of the relationship that prevented the deletion.
+        relationship: ViewName,
+        /// The number of documents that belong to the document through
+        /// `relationship`.
+        referencing_documents: u64,
+    },
+
     /// An error from another crate.
     #[error("error from {origin}: {error}")]
     Other {
@@ -209,6 +361,57 @@ pub enum Error {
 }
 
 impl Error {
+    /// Returns the [`ErrorCode`] categorizing this error. Unlike this error's
+    /// message, the code is stable and can be used to handle errors
+    /// programmatically.
+    #[must_use]
+    pub const fn code(&self) -> ErrorCode {
+        match self {
+            Self::SchemaMismatch { .. } => ErrorCode::SchemaMismatch,
+            Self::SchemaAlreadyRegistered(_)
+            | Self::ViewAlreadyRegistered(_)
+            | Self::TriggerAlreadyRegistered { .. }
+            | Self::CollectionAlreadyDefined(_) => ErrorCode::AlreadyDefined,
+            Self::SchemaNotRegistered(_)
+            | Self::DatabaseNotFound(_)
+            | Self::ViewNotFound
+            | Self::CollectionNotFound
+            | Self::ApiNotFound(_)
+            | Self::DocumentNotFound(..)
+            | Self::UserNotFound => ErrorCode::NotFound,
+            Self::InvalidDatabaseName(_) | Self::InvalidName(_) => ErrorCode::InvalidName,
+            Self::DatabaseNameAlreadyTaken(_) => ErrorCode::AlreadyExists,
+            Self::Networking(err) => err.code(),
+            Self::DocumentIdTooLong
+            | Self::InvalidUnicode(_)
+            | Self::NotANumber
+            | Self::Time(_)
+            | Self::KeyNotOrderPreserving(_)
+            | Self::PermissionGroupCycle(_)
+            | Self::WeakPassword(_)
+            | Self::ValueTooLarge { .. }
+            | Self::MessageTooLarge { .. }
+            | Self::DocumentTooLarge { .. } => ErrorCode::InvalidInput,
+            Self::DocumentConflict(..) => ErrorCode::DocumentConflict,
+            Self::UniqueKeyViolation { .. } => ErrorCode::UniqueKeyViolation,
+            Self::DeleteRestricted { .. } => ErrorCode::DeleteRestricted,
+            Self::DocumentVersionUnsupported { .. }
+            | Self::DocumentFormatUnsupported { .. }
+            | Self::PatchUnsupported(_)
+            | Self::FilterUnsupported(_) => ErrorCode::DocumentUnsupported,
+            Self::DocumentPush(..) => ErrorCode::DocumentPush,
+            Self::ValidationFailed { .. } => ErrorCode::ValidationFailed,
+            Self::PermissionDenied(_) | Self::ReservedTopic => ErrorCode::PermissionDenied,
+            Self::InvalidCredentials | Self::Password(_) | Self::SessionExpired => {
+                ErrorCode::InvalidCredentials
+            }
+            Self::ReduceUnimplemented => ErrorCode::ReduceUnimplemented,
+            Self::RateLimited { .. } => ErrorCode::RateLimited,
+            Self::ReadOnlyReplica(_) | Self::ReadOnly => ErrorCode::ReadOnly,
+            Self::Other { .. } => ErrorCode::Other,
+        }
+    }
+
     /// Returns an instance of [`Self::Other`] with the given parameters.
     pub fn other(origin: impl Display, error: impl Display) -> Self {
         Self::Other {
@@ -244,6 +447,68 @@ impl Error {
     }
 }
 
+/// A stable category for an [`Error`], returned from [`Error::code()`].
+///
+/// Because [`Error`] is serialized when returned from a server, the code of an
+/// error received from a remote connection matches the code of the error that
+/// occurred on the server.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[non_exhaustive]
+pub enum ErrorCode {
+    /// The database was created with a different schema.
+    SchemaMismatch,
+    /// A schema, collection, or view was defined more than once.
+    AlreadyDefined,
+    /// The requested schema, database, collection, view, api, document, or
+    /// user was not found.
+    NotFound,
+    /// An invalid name was provided.
+    InvalidName,
+    /// A database with the same name already exists.
+    AlreadyExists,
+    /// A value provided was invalid.
+    InvalidInput,
+    /// A document was modified since the revision provided.
+    DocumentConflict,
+    /// A document emitted a key that is already emitted by another document
+    /// in a unique view.
+    UniqueKeyViolation,
+    /// A document couldn't be deleted because other documents belong to it.
+    DeleteRestricted,
+    /// A stored document's version or format can't be converted, or its
+    /// collection doesn't support the requested operation.
+    DocumentUnsupported,
+    /// A unique id could not be generated for a document being pushed.
+    DocumentPush,
+    /// A document was rejected by its collection's validation function.
+    ValidationFailed,
+    /// Permission was denied.
+    PermissionDenied,
+    /// The credentials provided were not valid.
+    InvalidCredentials,
+    /// A view's reduce function is not implemented.
+    ReduceUnimplemented,
+    /// The connection exceeded its rate limit.
+    RateLimited,
+    /// The database or storage can't be modified.
+    ReadOnly,
+    /// The server's protocol version is incompatible with the client.
+    ProtocolMismatch,
+    /// A timeout elapsed while connecting or waiting for a response.
+    Timeout,
+    /// The request was cancelled before it completed.
+    Cancelled,
+    /// The connection was lost.
+    Disconnected,
+    /// The server sent an unexpected response.
+    UnexpectedResponse,
+    /// The server refused the connection because it has too many open
+    /// connections.
+    ConnectionLimitReached,
+    /// An error that doesn't fit any other category.
+    Other,
+}
+
 impl From<pot::Error> for Error {
     fn from(err: pot::Error) -> Self {
         Self::other("pot", err)
@@ -46,9 +46,67 @@
 //! The serialized representation of all mappings emitted for a single `Key`
 //! must be less than 4 gigabytes in size.
 //!
+//! When streaming the results of a view query using
+//! [`View::query_iter()`](crate::connection::View::query_iter) or
+//! [`AsyncView::query_stream()`](crate::connection::AsyncView::query_stream),
+//! mappings are requested in chunks of up to [`VIEW_QUERY_STREAM_CHUNK_SIZE`]
+//! keys.
+//!
+//! # Collection Limits
+//!
+//! When iterating over a collection using
+//! [`SerializedCollection::scan()`](crate::schema::SerializedCollection::scan)
+//! or
+//! [`SerializedCollection::scan_async()`](crate::schema::SerializedCollection::scan_async),
+//! documents are read in batches of up to [`DEFAULT_SCAN_BATCH_SIZE`]
+//! documents unless another batch size is specified.
+//!
+//! # Key-Value Limits
+//!
+//! By default, the size of values stored in the key-value store is only limited
+//! by the available memory, as values are loaded into memory when accessed. A
+//! limit can be configured using the storage's `key_value_max_value_size`. Operations that would store a larger value
+//! fail with [`Error::ValueTooLarge`](crate::Error::ValueTooLarge).
+//!
+//! When streaming values using
+//! [`KeyValue::set_key_streaming()`](crate::keyvalue::KeyValue::set_key_streaming)
+//! or
+//! [`KeyValue::get_key_streaming()`](crate::keyvalue::KeyValue::get_key_streaming),
+//! values are sent in chunks of up to [`KEY_VALUE_STREAM_CHUNK_SIZE`] bytes.
+//!
+//! # `PubSub` Limits
+//!
+//! By default, the size of `PubSub` payloads is only limited by the available
+//! memory. A limit can be configured using the storage's
+//! `pubsub_max_payload_size`. Publishing a larger payload fails with
+//! [`Error::MessageTooLarge`](crate::Error::MessageTooLarge).
+//!
+//! # Server Limits
+//!
+//! By default, the size of documents sent to a server is only limited by the
+//! available memory. A limit can be configured using
+//! `ServerConfiguration::with_max_document_size()`. Inserting or updating a
+//! larger document fails with
+//! [`Error::DocumentTooLarge`](crate::Error::DocumentTooLarge). Requests
+//! received over `WebSocket` and HTTP connections that are more than 64
+//! kilobytes larger than the limit are rejected while being received, which
+//! disconnects the client.
+//!
 //! [nebari]: https://github.com/khonsulabs/nebari
 
 /// The maximum number of results allowed to be returned from `list_executed_transactions`.
 pub const LIST_TRANSACTIONS_MAX_RESULTS: u32 = 1000;
 /// If no `result_limit` is specified, this value is the limit used by default.
 pub const LIST_TRANSACTIONS_DEFAULT_RESULT_COUNT: u32 = 100;
+/// The maximum number of keys requested at once by
+/// [`View::query_iter()`](crate::connection::View::query_iter) and
+/// [`AsyncView::query_stream()`](crate::connection::AsyncView::query_stream).
+pub const VIEW_QUERY_STREAM_CHUNK_SIZE: u32 = 1000;
+/// The number of documents read at once when scanning a collection, unless
+/// another batch size is specified.
+pub const DEFAULT_SCAN_BATCH_SIZE: u32 = 1000;
+/// The maximum number of bytes sent at once by
+/// [`KeyValue::set_key_streaming()`](crate::keyvalue::KeyValue::set_key_streaming)
+/// and
+/// [`KeyValue::get_key_streaming()`](crate::keyvalue::KeyValue::get_key_streaming).
+pub const KEY_VALUE_STREAM_CHUNK_SIZE: usize = 1024 * 1024;
@@ -1,19 +1,222 @@
+use std::net::SocketAddr;
+use std::time::Duration;
+
 use arc_bytes::serde::Bytes;
 use serde::{Deserialize, Serialize};
 
 use crate::api::{Api, ApiName};
 use crate::connection::{
-    AccessPolicy, Database, IdentityReference, Range, SerializedQueryKey, Session, SessionId, Sort,
+    AccessPolicy, Database, DatabaseCreation, DatabaseStats, IdentityReference,
+    PermissionGroupInfo, QueryPlan, Range, SerializedQueryKey, Session, SessionId, Sort, UserInfo,
 };
-use crate::document::{DocumentId, Header, OwnedDocument};
+use crate::document::{DocumentId, Header, HistoricalRevision, MaybeDeleted, OwnedDocument};
 use crate::keyvalue::{KeyOperation, Output};
+use crate::permissions::Statement;
+use crate::pubsub::{BufferLimit, PublishReceipt};
 use crate::schema::view::map::{self, MappedSerializedDocuments};
-use crate::schema::{CollectionName, NamedReference, Qualified, SchemaSummary, ViewName};
+use crate::schema::{
+    CollectionName, Filter, NamedReference, Qualified, SchemaName, SchemaSummary, ViewName,
+};
 use crate::transaction::{Executed, OperationResult, Transaction};
 
 /// The current protocol version.
 pub const CURRENT_PROTOCOL_VERSION: &str = "bonsai-pre-1";
 
+/// All protocol versions supported by this version of BonsaiDb, ordered from
+/// most preferred to least preferred.
+///
+/// While establishing a connection, the client offers every version it
+/// supports, and the server chooses its most preferred version that the client
+/// offered. This allows clients and servers to be upgraded independently of
+/// each other as long as they support at least one common version.
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &[CURRENT_PROTOCOL_VERSION];
+
+/// The HTTP header a server includes when rejecting a `WebSocket` connection
+/// because none of the client's protocol versions are supported. The value is
+/// a comma-separated list of the protocol versions the server supports.
+pub const PROTOCOL_VERSIONS_HEADER: &str = "X-BonsaiDb-Protocol-Versions";
+
+/// The HTTP header used to negotiate the protocol of an HTTP long-polling
+/// connection. When connecting, the client sends a comma-separated list of the
+/// protocols it offers, and the server responds with the protocol it chose.
+pub const HTTP_PROTOCOL_HEADER: &str = "X-BonsaiDb-Protocol";
+
+/// The HTTP header identifying which HTTP long-polling connection a request
+/// belongs to. The value is assigned by the server when connecting.
+pub const HTTP_CONNECTION_HEADER: &str = "X-BonsaiDb-Connection";
+
+/// Appends `frame` to `body` using the framing of HTTP long-polling
+/// connections: the length of the frame as a big-endian `u32`, followed by the
+/// frame itself. Each frame contains a message encoded exactly as it would be
+/// sent over a `WebSocket`.
+pub fn write_http_frame(body: &mut Vec<u8>, frame: &[u8]) {
+    let length = u32::try_from(frame.len()).expect("frame too large");
+    body.extend_from_slice(&length.to_be_bytes());
+    body.extend_from_slice(frame);
+}
+
+/// Returns the frames contained in `body`, which was written using
+/// [`write_http_frame()`]. Returns `None` if `body` isn't a valid sequence of
+/// frames.
+#[must_use]
+pub fn read_http_frames(mut body: &[u8]) -> Option<Vec<&[u8]>> {
+    let mut frames = Vec::new();
+    while !body.is_empty() {
+        if body.len() < 4 {
+            return None;
+        }
+        let (length, remaining) = body.split_at(4);
+        let length = usize::try_from(u32::from_be_bytes(length.try_into().ok()?)).ok()?;
+        if remaining.len() < length {
+            return None;
+        }
+        let (frame, remaining) = remaining.split_at(length);
+        frames.push(frame);
+        body = remaining;
+    }
+    Some(frames)
+}
+
+/// A compression algorithm that a client and server can negotiate while
+/// establishing a connection.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// Compress messages using [Zstandard](https://facebook.github.io/zstd/).
+    Zstd {
+        /// The compression level. Valid levels are 1 through 22, and 0 uses
+        /// zstd's default level.
+        level: i32,
+    },
+}
+
+impl Compression {
+    /// Returns the protocol name that requests this compression algorithm for
+    /// `protocol_version`.
+    #[must_use]
+    pub fn protocol_name(&self, protocol_version: &str) -> String {
+        let algorithm = match self {
+            Self::Zstd { .. } => "zstd",
+        };
+        format!("{protocol_version}+{algorithm}")
+    }
+}
+
+/// Settings for compressing messages sent over a connection.
+///
+/// When compression has been negotiated, every message sent over the
+/// connection begins with a single byte identifying whether the remainder of
+/// the message is compressed. Messages smaller than
+/// [`threshold`](Self::threshold) bytes are sent uncompressed to avoid the
+/// overhead of compressing small payloads.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[must_use]
+pub struct TransportCompression {
+    /// The algorithm to compress messages with.
+    pub compression: Compression,
+    /// The minimum size of a message, in bytes, before it is compressed.
+    pub threshold: usize,
+}
+
+impl TransportCompression {
+    /// The default [`threshold`](Self::threshold): 1 kilobyte.
+    pub const DEFAULT_THRESHOLD: usize = 1024;
+
+    #[cfg(feature = "transport-compression")]
+    const UNCOMPRESSED: u8 = 0;
+    #[cfg(feature = "transport-compression")]
+    const ZSTD: u8 = 1;
+
+    /// Returns settings that compress messages using `compression` once they
+    /// are at least [`Self::DEFAULT_THRESHOLD`] bytes.
+    pub const fn new(compression: Compression) -> Self {
+        Self {
+            compression,
+            threshold: Self::DEFAULT_THRESHOLD,
+        }
+    }
+
+    /// Sets [`threshold`](Self::threshold) and returns self.
+    pub const fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Encodes `message` to be sent over a connection that negotiated
+    /// compression.
+    #[cfg(feature = "transport-compression")]
+    pub fn encode(&self, message: &[u8]) -> Result<Vec<u8>, crate::Error> {
+        let mut encoded;
+        if message.len() < self.threshold {
+            encoded = Vec::with_capacity(message.len() + 1);
+            encoded.push(Self::UNCOMPRESSED);
+            encoded.extend_from_slice(message);
+        } else {
+            match self.compression {
+                Compression::Zstd { level } => {
+                    encoded = vec![Self::ZSTD];
+                    zstd::stream::copy_encode(message, &mut encoded, level)
+                        .map_err(|err| crate::Error::other("zstd", err))?;
+                }
+            }
+        }
+        Ok(encoded)
+    }
+
+    /// Decodes `message` received over a connection that negotiated
+    /// compression.
+    #[cfg(feature = "transport-compression")]
+    pub fn decode(message: &[u8]) -> Result<std::borrow::Cow<'_, [u8]>, crate::Error> {
+        match message.split_first() {
+            Some((&Self::UNCOMPRESSED, message)) => Ok(std::borrow::Cow::Borrowed(message)),
+            Some((&Self::ZSTD, message)) => zstd::stream::decode_all(message)
+                .map(std::borrow::Cow::Owned)
+                .map_err(|err| crate::Error::other("zstd", err)),
+            _ => Err(crate::Error::other(
+                "transport-compression",
+                "unknown message encoding",
+            )),
+        }
+    }
+
+    /// Decodes `message` received over a connection that negotiated
+    /// compression, returning an error if the decoded message is larger than
+    /// `max_size` bytes. Decoding stops once the limit is exceeded.
+    #[cfg(feature = "transport-compression")]
+    pub fn decode_with_limit(
+        message: &[u8],
+        max_size: usize,
+    ) -> Result<std::borrow::Cow<'_, [u8]>, crate::Error> {
+        use std::io::Read;
+
+        let decoded = match message.split_first() {
+            Some((&Self::ZSTD, message)) => {
+                let mut decoded = Vec::new();
+                zstd::stream::read::Decoder::with_buffer(message)
+                    .and_then(|decoder| {
+                        decoder
+                            .take(
+                                u64::try_from(max_size)
+                                    .unwrap_or(u64::MAX)
+                                    .saturating_add(1),
+                            )
+                            .read_to_end(&mut decoded)
+                    })
+                    .map_err(|err| crate::Error::other("zstd", err))?;
+                std::borrow::Cow::Owned(decoded)
+            }
+            _ => Self::decode(message)?,
+        };
+        if decoded.len() > max_size {
+            return Err(crate::Error::other(
+                "transport-compression",
+                format!("decoded message exceeds the maximum size of {max_size} bytes"),
+            ));
+        }
+        Ok(decoded)
+    }
+}
+
 /// A payload with an associated id.
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct Payload {
@@ -34,15 +237,22 @@ pub struct CreateDatabase {
     pub database: Database,
     /// Only attempts to create the database if it doesn't already exist.
     pub only_if_needed: bool,
+    /// Permissions granted to every session that accesses the database.
+    #[serde(default)]
+    pub permissions: Vec<Statement>,
 }
 
 impl Api for CreateDatabase {
     type Error = crate::Error;
-    type Response = ();
+    type Response = DatabaseCreation;
 
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "CreateDatabase")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database.name)
+    }
 }
 
 /// Deletes the database named `name`
@@ -59,6 +269,10 @@ impl Api for DeleteDatabase {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "DeleteDatabase")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.name)
+    }
 }
 
 /// Lists all databases.
@@ -74,6 +288,26 @@ impl Api for ListDatabases {
     }
 }
 
+/// Returns statistics about the storage used by a database.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct GetDatabaseStats {
+    /// The name of the database.
+    pub name: String,
+}
+
+impl Api for GetDatabaseStats {
+    type Error = crate::Error;
+    type Response = DatabaseStats;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "GetDatabaseStats")
+    }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+}
+
 /// Lists available schemas.
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct ListAvailableSchemas;
@@ -87,6 +321,192 @@ impl Api for ListAvailableSchemas {
     }
 }
 
+/// Checks that the connection to the server is healthy. The server responds
+/// immediately without performing any other work.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Ping;
+
+impl Api for Ping {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "Ping")
+    }
+}
+
+/// Returns information about the server, such as its version and how long it
+/// has been running.
+///
+/// Every connected client may request the server's status. The counts in
+/// [`ServerStatus`] are only included if the client is permitted
+/// [`ServerAction::GetServerStatus`](crate::permissions::bonsai::ServerAction::GetServerStatus).
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct GetServerStatus;
+
+impl Api for GetServerStatus {
+    type Error = crate::Error;
+    type Response = ServerStatus;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "GetServerStatus")
+    }
+}
+
+/// Information about a running server.
+#[derive(Clone, Deserialize, Serialize, Debug, Eq, PartialEq)]
+pub struct ServerStatus {
+    /// The version of `bonsaidb-server` the server was built with.
+    pub version: String,
+    /// The [`CURRENT_PROTOCOL_VERSION`] of the server's build.
+    pub protocol_version: String,
+    /// The amount of time since the server was opened.
+    pub uptime: Duration,
+    /// The number of databases hosted by the server. This is `None` unless
+    /// the client is permitted
+    /// [`ServerAction::GetServerStatus`](crate::permissions::bonsai::ServerAction::GetServerStatus).
+    pub database_count: Option<u64>,
+    /// The number of clients connected to the server. This is `None` unless
+    /// the client is permitted
+    /// [`ServerAction::GetServerStatus`](crate::permissions::bonsai::ServerAction::GetServerStatus).
+    pub connection_count: Option<u64>,
+    /// How long clients can go without sending a request before the server
+    /// disconnects them. If `None`, idle clients remain connected.
+    #[serde(default)]
+    pub idle_timeout: Option<Duration>,
+}
+
+/// The [`PubSub`](crate::pubsub::PubSub) topic that [`ServerEvent`]s are
+/// published to on the [admin database](crate::admin::ADMIN_DATABASE_NAME).
+///
+/// Receiving these events requires permission to create a subscriber on the
+/// admin database and
+/// [`PubSubAction::SubscribeTo`](crate::permissions::bonsai::PubSubAction::SubscribeTo)
+/// for this topic.
+pub const SERVER_EVENTS_TOPIC: &str = "bonsaidb.server.events";
+
+/// The [`PubSub`](crate::pubsub::PubSub) topic that [`ClientEvent`]s are
+/// published to on the [admin database](crate::admin::ADMIN_DATABASE_NAME).
+pub const CLIENT_EVENTS_TOPIC: &str = "bonsaidb.server.clients";
+
+/// An event published to [`CLIENT_EVENTS_TOPIC`] on the admin database when a
+/// client connects to or disconnects from the server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClientEvent {
+    /// A client was accepted by the server.
+    Connected {
+        /// The id of the client.
+        id: u32,
+        /// The address of the client.
+        address: SocketAddr,
+    },
+    /// A client has disconnected from the server.
+    Disconnected {
+        /// The id of the client.
+        id: u32,
+    },
+}
+
+/// An event published by a server, intended for monitoring the server.
+///
+/// [`ServerEvent::Client`] events are published to [`CLIENT_EVENTS_TOPIC`] as
+/// [`ClientEvent`]s. Every other event is published to
+/// [`SERVER_EVENTS_TOPIC`].
+#[derive(Clone, Deserialize, Serialize, Debug)]
+#[non_exhaustive]
+pub enum ServerEvent {
+    /// A client connected to or disconnected from the server.
+    Client(ClientEvent),
+    /// A client failed to authenticate.
+    AuthenticationFailed {
+        /// The id of the client.
+        client_id: u32,
+        /// The address of the client.
+        address: SocketAddr,
+        /// The user the client attempted to authenticate as, if the client
+        /// authenticated using a password.
+        user: Option<NamedReference<'static, u64>>,
+        /// The error returned to the client.
+        error: crate::Error,
+    },
+    /// A database was created.
+    DatabaseCreated {
+        /// The name of the database.
+        name: String,
+        /// The schema of the database.
+        schema: SchemaName,
+    },
+    /// A database was deleted.
+    DatabaseDeleted {
+        /// The name of the database.
+        name: String,
+    },
+    /// The server failed to handle a client's request due to an unexpected
+    /// error, which is returned to the client as
+    /// [`Error::Other`](crate::Error::Other). Errors returned as part of a
+    /// request's normal operation, such as a document not being found or
+    /// permission being denied, are not published.
+    RequestFailed {
+        /// The id of the client.
+        client_id: u32,
+        /// The name of the request's [`Api`].
+        request: ApiName,
+        /// The error returned to the client.
+        error: crate::Error,
+    },
+}
+
+/// Executes multiple api requests using a single round trip to the server.
+///
+/// Unlike a [`Transaction`], a batch is not atomic. Each request is executed
+/// in order, and each request's result is returned independently of the
+/// others. A failing request does not prevent the remaining requests from
+/// being executed.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ExecuteBatch {
+    /// The requests to execute.
+    pub requests: Vec<BatchedRequest>,
+}
+
+impl Api for ExecuteBatch {
+    type Error = crate::Error;
+    type Response = Vec<Result<Bytes, crate::Error>>;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "ExecuteBatch")
+    }
+}
+
+/// A single request contained in an [`ExecuteBatch`].
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct BatchedRequest {
+    /// The unique name of the api being requested.
+    pub name: ApiName,
+    /// The serialized request.
+    pub value: Bytes,
+}
+
+/// Cancels a request that was previously sent on the same connection. This is
+/// sent by clients when a request times out, allowing the server to stop
+/// working on a response that will never be read.
+///
+/// The server responds to the cancelled request with
+/// [`Error::RequestCancelled`] if it was still queued or executing.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct CancelRequest {
+    /// The [`Payload::id`] of the request to cancel.
+    pub request_id: u32,
+}
+
+impl Api for CancelRequest {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "CancelRequest")
+    }
+}
+
 /// Creates a user.
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct CreateUser {
@@ -119,6 +539,35 @@ impl Api for DeleteUser {
     }
 }
 
+/// Lists all users.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ListUsers;
+
+impl Api for ListUsers {
+    type Error = crate::Error;
+    type Response = Vec<UserInfo>;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "ListUsers")
+    }
+}
+
+/// Lists the permission groups a user belongs to.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ListUserGroups {
+    /// The username or id of the user.
+    pub user: NamedReference<'static, u64>,
+}
+
+impl Api for ListUserGroups {
+    type Error = crate::Error;
+    type Response = Vec<PermissionGroupInfo>;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "ListUserGroups")
+    }
+}
+
 /// Set's a user's password.
 #[cfg(feature = "password-hashing")]
 #[derive(Clone, Deserialize, Serialize, Debug)]
@@ -140,23 +589,32 @@ impl Api for SetUserPassword {
 }
 
 /// Authenticate the current connection.
-#[cfg(any(feature = "password-hashing", feature = "token-authentication"))]
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct Authenticate {
     /// The method of authentication.
     pub authentication: crate::connection::Authentication,
 }
 
-#[cfg(any(feature = "password-hashing", feature = "token-authentication"))]
 impl Api for Authenticate {
     type Error = crate::Error;
-    type Response = Session;
+    type Response = Authenticated;
 
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "Authenticate")
     }
 }
 
+/// The result of an [`Authenticate`] request.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Authenticated {
+    /// The authenticated session.
+    pub session: Session,
+    /// A token that can be used to authenticate future connections using
+    /// [`Authentication::session_token()`](crate::connection::Authentication::session_token),
+    /// if the server issued one.
+    pub session_token: Option<crate::connection::SensitiveString>,
+}
+
 /// Assume an identity.
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct AssumeIdentity(pub IdentityReference<'static>);
@@ -245,6 +703,10 @@ impl Api for Get {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "Get")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Retrieve multiple documents.
@@ -265,6 +727,10 @@ impl Api for GetMultiple {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "GetMultiple")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Retrieve multiple documents.
@@ -289,6 +755,10 @@ impl Api for List {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "List")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Retrieve multiple document headers.
@@ -302,6 +772,101 @@ impl Api for ListHeaders {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "ListHeaders")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.0.database)
+    }
+}
+
+/// Retrieve multiple documents, including soft-deleted documents.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct ListIncludingDeleted(pub List);
+
+impl Api for ListIncludingDeleted {
+    type Error = crate::Error;
+    type Response = Vec<MaybeDeleted>;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "ListIncludingDeleted")
+    }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.0.database)
+    }
+}
+
+/// Restores a soft-deleted document.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Restore {
+    /// The name of the database.
+    pub database: String,
+    /// The collection of the document.
+    pub collection: CollectionName,
+    /// The id of the document.
+    pub id: DocumentId,
+}
+
+impl Api for Restore {
+    type Error = crate::Error;
+    type Response = Header;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "Restore")
+    }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
+}
+
+/// Retrieves the documents in a collection that match a [`Filter`].
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct FilterDocuments {
+    /// The name of the database.
+    pub database: String,
+    /// The collection to filter.
+    pub collection: CollectionName,
+    /// The filter to evaluate.
+    pub filter: Filter,
+    /// The maximum number of results to return.
+    pub limit: Option<u32>,
+}
+
+impl Api for FilterDocuments {
+    type Error = crate::Error;
+    type Response = Vec<OwnedDocument>;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "FilterDocuments")
+    }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
+}
+
+/// Returns the revision history of a document.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct RevisionHistory {
+    /// The name of the database.
+    pub database: String,
+    /// The collection of the document.
+    pub collection: CollectionName,
+    /// The id of the document.
+    pub id: DocumentId,
+}
+
+impl Api for RevisionHistory {
+    type Error = crate::Error;
+    type Response = Vec<HistoricalRevision>;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "RevisionHistory")
+    }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Counts the number of documents in the specified range.
@@ -322,6 +887,10 @@ impl Api for Count {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "Count")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Queries a view.
@@ -348,6 +917,10 @@ impl Api for Query {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "Query")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Queries a view with the associated documents.
@@ -361,6 +934,10 @@ impl Api for QueryWithDocs {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "QueryWithDocs")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.0.database)
+    }
 }
 
 /// Reduces a view.
@@ -383,6 +960,10 @@ impl Api for Reduce {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "Reduce")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Reduces a view, grouping the reduced values by key.
@@ -396,6 +977,10 @@ impl Api for ReduceGrouped {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "ReduceGrouped")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.0.database)
+    }
 }
 
 /// Deletes the associated documents resulting from the view query.
@@ -418,6 +1003,38 @@ impl Api for DeleteDocs {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "DeleteDocs")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
+}
+
+/// Describes how a view query would be executed.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct Explain {
+    /// The name of the database.
+    pub database: String,
+    /// The name of the view.
+    pub view: ViewName,
+    /// The filter for the view.
+    pub key: Option<SerializedQueryKey>,
+    /// The order for the query into the view.
+    pub order: Sort,
+    /// The maximum number of results to return.
+    pub limit: Option<u32>,
+}
+
+impl Api for Explain {
+    type Error = crate::Error;
+    type Response = QueryPlan;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "Explain")
+    }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Applies a transaction.
@@ -436,6 +1053,10 @@ impl Api for ApplyTransaction {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "ApplyTransaction")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Lists executed transactions.
@@ -456,6 +1077,10 @@ impl Api for ListExecutedTransactions {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "ListExecutedTransactions")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Queries the last transaction id.
@@ -472,6 +1097,10 @@ impl Api for LastTransactionId {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "LastTransactionId")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Creates a `PubSub` [`Subscriber`](crate::pubsub::Subscriber)
@@ -479,6 +1108,9 @@ impl Api for LastTransactionId {
 pub struct CreateSubscriber {
     /// The name of the database.
     pub database: String,
+    /// The limit of messages buffered for the subscriber that haven't been
+    /// received yet.
+    pub limit: Option<BufferLimit>,
 }
 
 impl Api for CreateSubscriber {
@@ -488,6 +1120,10 @@ impl Api for CreateSubscriber {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "CreateSubscriber")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Publishes `payload` to all subscribers of `topic`.
@@ -503,11 +1139,15 @@ pub struct Publish {
 
 impl Api for Publish {
     type Error = crate::Error;
-    type Response = ();
+    type Response = PublishReceipt;
 
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "Publish")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Publishes `payload` to all subscribers of all `topics`.
@@ -523,11 +1163,37 @@ pub struct PublishToAll {
 
 impl Api for PublishToAll {
     type Error = crate::Error;
-    type Response = ();
+    type Response = PublishReceipt;
 
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "PublishToAll")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
+}
+
+/// Publishes each `(topic, payload)` pair in `messages` as a single batch.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct PublishBatch {
+    /// The name of the database.
+    pub database: String,
+    /// The topics and payloads to publish.
+    pub messages: Vec<(Bytes, Bytes)>,
+}
+
+impl Api for PublishBatch {
+    type Error = crate::Error;
+    type Response = PublishReceipt;
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "PublishBatch")
+    }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Subscribes `subscriber_id` to messages for `topic`.
@@ -539,6 +1205,9 @@ pub struct SubscribeTo {
     pub subscriber_id: u64,
     /// The topic to subscribe to.
     pub topic: Bytes,
+    /// If true, any messages retained for `topic` are sent to the subscriber
+    /// before messages published after the subscription was made.
+    pub replay: bool,
 }
 
 impl Api for SubscribeTo {
@@ -548,6 +1217,10 @@ impl Api for SubscribeTo {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "SubscribeTo")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// A PubSub message was received.
@@ -559,6 +1232,9 @@ pub struct MessageReceived {
     pub topic: Bytes,
     /// The message payload.
     pub payload: Bytes,
+    /// The number of messages dropped since the previous message was sent
+    /// because the subscriber's [`BufferLimit`] was exceeded.
+    pub lagged: u64,
 }
 
 impl Api for MessageReceived {
@@ -588,6 +1264,86 @@ impl Api for UnsubscribeFrom {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "UnsubscribeFrom")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
+}
+
+/// Subscribes `subscriber_id` to messages for all topics starting with
+/// `prefix`.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct SubscribeToPrefix {
+    /// The name of the database.
+    pub database: String,
+    /// The id of the [`Subscriber`](crate::pubsub::Subscriber).
+    pub subscriber_id: u64,
+    /// The topic prefix to subscribe to.
+    pub prefix: Bytes,
+}
+
+impl Api for SubscribeToPrefix {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "SubscribeToPrefix")
+    }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
+}
+
+/// Unsubscribes `subscriber_id` from messages for topics starting with
+/// `prefix`.
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct UnsubscribeFromPrefix {
+    /// The name of the database.
+    pub database: String,
+    /// The id of the [`Subscriber`](crate::pubsub::Subscriber).
+    pub subscriber_id: u64,
+    /// The topic prefix to unsubscribe from.
+    pub prefix: Bytes,
+}
+
+impl Api for UnsubscribeFromPrefix {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "UnsubscribeFromPrefix")
+    }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
+}
+
+/// Notifies the server that `count` messages sent to `subscriber_id` have
+/// been received, allowing the server to send more messages to a subscriber
+/// created with a [`BufferLimit`].
+#[derive(Clone, Deserialize, Serialize, Debug)]
+pub struct AcknowledgeMessages {
+    /// The name of the database.
+    pub database: String,
+    /// The id of the [`Subscriber`](crate::pubsub::Subscriber).
+    pub subscriber_id: u64,
+    /// The number of messages received.
+    pub count: u64,
+}
+
+impl Api for AcknowledgeMessages {
+    type Error = crate::Error;
+    type Response = ();
+
+    fn name() -> ApiName {
+        ApiName::new("bonsaidb", "AcknowledgeMessages")
+    }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Unregisters the subscriber.
@@ -606,6 +1362,10 @@ impl Api for UnregisterSubscriber {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "UnregisterSubscriber")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Excutes a key-value store operation.
@@ -624,6 +1384,10 @@ impl Api for ExecuteKeyOperation {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "ExecuteKeyOperation")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Compacts the collection.
@@ -642,6 +1406,10 @@ impl Api for CompactCollection {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "CompactCollection")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Compacts the key-value store.
@@ -658,6 +1426,10 @@ impl Api for CompactKeyValueStore {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "CompactKeyValueStore")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// Compacts the entire database.
@@ -674,6 +1446,10 @@ impl Api for Compact {
     fn name() -> ApiName {
         ApiName::new("bonsaidb", "Compact")
     }
+
+    fn database(&self) -> Option<&str> {
+        Some(&self.database)
+    }
 }
 
 /// A networking error.
@@ -692,7 +1468,55 @@ pub enum Error {
     #[error("request timeout")]
     RequestTimeout,
 
+    /// The request was cancelled by the client before it completed.
+    #[error("request cancelled")]
+    RequestCancelled,
+
     /// The connection was interrupted.
     #[error("unexpected disconnection")]
     Disconnected,
+
+    /// The server does not support any of the client's protocol versions.
+    #[error(
+        "server protocol versions {server:?} incompatible with client protocol versions {client:?}"
+    )]
+    IncompatibleProtocolVersion {
+        /// The protocol versions offered by the client.
+        client: Vec<String>,
+        /// The protocol versions supported by the server. This is empty if the
+        /// server did not report which versions it supports.
+        server: Vec<String>,
+    },
+
+    /// The server refused the connection because it has reached its limit of
+    /// concurrent connections.
+    #[error("the server's connection limit has been reached")]
+    ConnectionLimitReached,
+
+    /// The SHA-256 fingerprint of the certificate presented by the server did
+    /// not match the fingerprint the client was configured to expect.
+    #[error("the server's certificate fingerprint did not match the expected fingerprint")]
+    CertificateFingerprintMismatch {
+        /// The fingerprint the client expected.
+        expected: [u8; 32],
+        /// The fingerprint of the certificate presented by the server. This is
+        /// `None` if the server did not present a certificate.
+        received: Option<[u8; 32]>,
+    },
+}
+
+impl Error {
+    /// Returns the [`ErrorCode`](crate::ErrorCode) categorizing this error.
+    #[must_use]
+    pub const fn code(&self) -> crate::ErrorCode {
+        match self {
+            Self::UnexpectedResponse(_) => crate::ErrorCode::UnexpectedResponse,
+            Self::ConnectTimeout | Self::RequestTimeout => crate::ErrorCode::Timeout,
+            Self::RequestCancelled => crate::ErrorCode::Cancelled,
+            Self::Disconnected => crate::ErrorCode::Disconnected,
+            Self::IncompatibleProtocolVersion { .. } => crate::ErrorCode::ProtocolMismatch,
+            Self::ConnectionLimitReached => crate::ErrorCode::ConnectionLimitReached,
+            Self::CertificateFingerprintMismatch { .. } => crate::ErrorCode::Other,
+        }
+    }
 }
@@ -126,6 +126,12 @@ pub enum ServerAction {
     ListAvailableSchemas,
     /// Permits [`StorageConnection::list_databases`](crate::connection::StorageConnection::list_databases).
     ListDatabases,
+    /// Permits [`StorageConnection::database_stats`](crate::connection::StorageConnection::database_stats).
+    GetDatabaseStats,
+    /// Permits including the database and connection counts when requesting
+    /// a server's status using
+    /// [`GetServerStatus`](crate::networking::GetServerStatus).
+    GetServerStatus,
     /// Permits [`StorageConnection::create_database`](crate::connection::StorageConnection::create_database).
     CreateDatabase,
     /// Permits [`StorageConnection::delete_database`](crate::connection::StorageConnection::delete_database).
@@ -134,6 +140,10 @@ pub enum ServerAction {
     CreateUser,
     /// Permits [`StorageConnection::delete_user`](crate::connection::StorageConnection::delete_user).
     DeleteUser,
+    /// Permits [`StorageConnection::list_users`](crate::connection::StorageConnection::list_users).
+    ListUsers,
+    /// Permits [`StorageConnection::list_user_groups`](crate::connection::StorageConnection::list_user_groups).
+    ListUserGroups,
     /// Permits [`StorageConnection::set_user_password`](crate::connection::StorageConnection::set_user_password).
     SetPassword,
     /// Permits the ability to log in with a password.
@@ -184,6 +194,11 @@ pub enum DocumentAction {
     /// [`collection_resource_name()`] for the format of collection resource
     /// names.
     ListHeaders,
+    /// Allows listing documents, including soft-deleted documents, through
+    /// [`Connection::list_including_deleted()`](crate::connection::LowLevelConnection::list_including_deleted).
+    /// See [`collection_resource_name()`] for the format of collection resource
+    /// names.
+    ListIncludingDeleted,
     /// Allows counting documents through
     /// [`Connection::count()`](crate::connection::LowLevelConnection::count). See
     /// [`collection_resource_name()`] for the format of collection resource
@@ -209,6 +224,11 @@ pub enum DocumentAction {
     /// See [`document_resource_name()`] for the format of document resource
     /// names.
     Delete,
+    /// Allows restoring a soft-deleted document through
+    /// [`Connection::restore()`](crate::connection::LowLevelConnection::restore).
+    /// See [`document_resource_name()`] for the format of document resource
+    /// names.
+    Restore,
 }
 
 /// Actions that operate on a view.
@@ -226,6 +246,9 @@ pub enum ViewAction {
     /// [`Connection::delete_docs()`](crate::connection::LowLevelConnection::delete_docs).
     /// See [`view_resource_name`] for the format of view resource names.
     DeleteDocs,
+    /// Allows rebuilding a view's index with `Database::rebuild_view()`. See
+    /// [`view_resource_name`] for the format of view resource names.
+    Rebuild,
 }
 
 /// Actions that operate on transactions.
@@ -1,6 +1,13 @@
+use std::pin::{pin, Pin};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{ready, Context, Poll};
+
 use async_trait::async_trait;
 use circulate::{flume, Message};
-use serde::Serialize;
+use futures::future::{self, BoxFuture, Either};
+use futures::{FutureExt, Stream};
+use serde::{Deserialize, Serialize};
 
 use crate::Error;
 
@@ -12,19 +19,29 @@ pub trait PubSub {
     /// Create a new [`Subscriber`] for this relay.
     fn create_subscriber(&self) -> Result<Self::Subscriber, Error>;
 
-    /// Publishes a `payload` to all subscribers of `topic`.
+    /// Create a new [`Subscriber`] for this relay whose buffer of messages
+    /// that haven't been received is limited by `limit`.
+    fn create_subscriber_with_limit(&self, limit: BufferLimit) -> Result<Self::Subscriber, Error>;
+
+    /// Publishes a `payload` to all subscribers of `topic`, returning a
+    /// [`PublishReceipt`] describing how many subscribers it was delivered to.
+    ///
+    /// If the storage limits the size of payloads, a larger payload is
+    /// rejected with [`Error::MessageTooLarge`].
     fn publish<Topic: Serialize, Payload: Serialize>(
         &self,
         topic: &Topic,
         payload: &Payload,
-    ) -> Result<(), Error> {
+    ) -> Result<PublishReceipt, Error> {
         self.publish_bytes(pot::to_vec(topic)?, pot::to_vec(payload)?)
     }
 
-    /// Publishes a `payload` to all subscribers of `topic`.
-    fn publish_bytes(&self, topic: Vec<u8>, payload: Vec<u8>) -> Result<(), Error>;
+    /// Publishes a `payload` to all subscribers of `topic`. See
+    /// [`Self::publish()`] for more information.
+    fn publish_bytes(&self, topic: Vec<u8>, payload: Vec<u8>) -> Result<PublishReceipt, Error>;
 
-    /// Publishes a `payload` to all subscribers of all `topics`.
+    /// Publishes a `payload` to all subscribers of all `topics`. See
+    /// [`Self::publish()`] for more information.
     fn publish_to_all<
         'topics,
         Topics: IntoIterator<Item = &'topics Topic> + 'topics,
@@ -34,7 +51,7 @@ pub trait PubSub {
         &self,
         topics: Topics,
         payload: &Payload,
-    ) -> Result<(), Error> {
+    ) -> Result<PublishReceipt, Error> {
         let topics = topics
             .into_iter()
             .map(pot::to_vec)
@@ -42,12 +59,45 @@ pub trait PubSub {
         self.publish_bytes_to_all(topics, pot::to_vec(payload)?)
     }
 
-    /// Publishes a `payload` to all subscribers of all `topics`.
+    /// Publishes a `payload` to all subscribers of all `topics`. See
+    /// [`Self::publish()`] for more information.
     fn publish_bytes_to_all(
         &self,
         topics: impl IntoIterator<Item = Vec<u8>> + Send,
         payload: Vec<u8>,
-    ) -> Result<(), Error>;
+    ) -> Result<PublishReceipt, Error>;
+
+    /// Publishes each `(topic, payload)` pair in `messages` as a single
+    /// batch.
+    ///
+    /// Each subscriber receives the messages of the batch that match its
+    /// subscriptions contiguously and in order. Messages published
+    /// concurrently are received either before or after the entire batch, but
+    /// different subscribers may observe concurrent publishes in a different
+    /// order relative to the batch. A batch only occupies one message of a
+    /// subscriber's [`BufferLimit`], and if it is dropped due to the limit,
+    /// all of its messages are dropped.
+    ///
+    /// The returned [`PublishReceipt`] counts each message delivered to each
+    /// subscriber. If any payload is larger than the storage allows, the
+    /// entire batch is rejected with [`Error::MessageTooLarge`].
+    fn publish_batch<Topic: Serialize, Payload: Serialize>(
+        &self,
+        messages: &[(Topic, Payload)],
+    ) -> Result<PublishReceipt, Error> {
+        let messages = messages
+            .iter()
+            .map(|(topic, payload)| Ok((pot::to_vec(topic)?, pot::to_vec(payload)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.publish_batch_bytes(messages)
+    }
+
+    /// Publishes each `(topic, payload)` pair in `messages` as a single batch.
+    /// See [`Self::publish_batch()`] for more information.
+    fn publish_batch_bytes(
+        &self,
+        messages: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<PublishReceipt, Error>;
 }
 
 /// A subscriber to one or more topics.
@@ -60,6 +110,18 @@ pub trait Subscriber {
     /// Subscribe to [`Message`]s published to `topic`.
     fn subscribe_to_bytes(&self, topic: Vec<u8>) -> Result<(), Error>;
 
+    /// Subscribe to [`Message`]s published to `topic`. If the server retains
+    /// messages for `topic`, the retained messages are delivered before any
+    /// messages published after this call.
+    fn subscribe_to_with_replay<Topic: Serialize>(&self, topic: &Topic) -> Result<(), Error> {
+        self.subscribe_to_bytes_with_replay(pot::to_vec(topic)?)
+    }
+
+    /// Subscribe to [`Message`]s published to `topic`. If the server retains
+    /// messages for `topic`, the retained messages are delivered before any
+    /// messages published after this call.
+    fn subscribe_to_bytes_with_replay(&self, topic: Vec<u8>) -> Result<(), Error>;
+
     /// Unsubscribe from [`Message`]s published to `topic`.
     fn unsubscribe_from<Topic: Serialize>(&self, topic: &Topic) -> Result<(), Error> {
         self.unsubscribe_from_bytes(&pot::to_vec(topic)?)
@@ -68,6 +130,35 @@ pub trait Subscriber {
     /// Unsubscribe from [`Message`]s published to `topic`.
     fn unsubscribe_from_bytes(&self, topic: &[u8]) -> Result<(), Error>;
 
+    /// Subscribe to [`Message`]s published to any string topic that starts
+    /// with `prefix`. For example, subscribing to the prefix `orders.` receives
+    /// messages published to `orders.created` and `orders.shipped`.
+    ///
+    /// A message is only received once, even if its topic matches multiple
+    /// subscriptions.
+    fn subscribe_to_prefix(&self, prefix: &str) -> Result<(), Error> {
+        self.subscribe_to_prefix_bytes(prefix.as_bytes().to_vec())
+    }
+
+    /// Subscribe to [`Message`]s published to any topic that starts with
+    /// `prefix`. Topics that are serialized strings are matched against the
+    /// string's bytes. All other topics are matched against their serialized
+    /// bytes.
+    ///
+    /// A message is only received once, even if its topic matches multiple
+    /// subscriptions.
+    fn subscribe_to_prefix_bytes(&self, prefix: Vec<u8>) -> Result<(), Error>;
+
+    /// Unsubscribe from [`Message`]s published to topics that start with
+    /// `prefix`.
+    fn unsubscribe_from_prefix(&self, prefix: &str) -> Result<(), Error> {
+        self.unsubscribe_from_prefix_bytes(prefix.as_bytes())
+    }
+
+    /// Unsubscribe from [`Message`]s published to topics that start with
+    /// `prefix`.
+    fn unsubscribe_from_prefix_bytes(&self, prefix: &[u8]) -> Result<(), Error>;
+
     /// Returns the receiver to receive [`Message`]s.
     fn receiver(&self) -> &Receiver;
 }
@@ -81,21 +172,38 @@ pub trait AsyncPubSub: Send + Sync {
     /// Create a new [`Subscriber`] for this relay.
     async fn create_subscriber(&self) -> Result<Self::Subscriber, Error>;
 
-    /// Publishes a `payload` to all subscribers of `topic`.
+    /// Create a new [`Subscriber`] for this relay whose buffer of messages
+    /// that haven't been received is limited by `limit`.
+    async fn create_subscriber_with_limit(
+        &self,
+        limit: BufferLimit,
+    ) -> Result<Self::Subscriber, Error>;
+
+    /// Publishes a `payload` to all subscribers of `topic`, returning a
+    /// [`PublishReceipt`] describing how many subscribers it was delivered to.
+    ///
+    /// If the storage limits the size of payloads, a larger payload is
+    /// rejected with [`Error::MessageTooLarge`].
     async fn publish<Topic: Serialize + Send + Sync, Payload: Serialize + Send + Sync>(
         &self,
         topic: &Topic,
         payload: &Payload,
-    ) -> Result<(), Error> {
+    ) -> Result<PublishReceipt, Error> {
         let topic = pot::to_vec(topic)?;
         let payload = pot::to_vec(payload)?;
         self.publish_bytes(topic, payload).await
     }
 
-    /// Publishes a `payload` to all subscribers of `topic`.
-    async fn publish_bytes(&self, topic: Vec<u8>, payload: Vec<u8>) -> Result<(), Error>;
+    /// Publishes a `payload` to all subscribers of `topic`. See
+    /// [`Self::publish()`] for more information.
+    async fn publish_bytes(
+        &self,
+        topic: Vec<u8>,
+        payload: Vec<u8>,
+    ) -> Result<PublishReceipt, Error>;
 
-    /// Publishes a `payload` to all subscribers of all `topics`.
+    /// Publishes a `payload` to all subscribers of all `topics`. See
+    /// [`Self::publish()`] for more information.
     async fn publish_to_all<
         'topics,
         Topics: IntoIterator<Item = &'topics Topic> + Send + 'topics,
@@ -105,7 +213,7 @@ pub trait AsyncPubSub: Send + Sync {
         &self,
         topics: Topics,
         payload: &Payload,
-    ) -> Result<(), Error> {
+    ) -> Result<PublishReceipt, Error> {
         let topics = topics
             .into_iter()
             .map(|topic| pot::to_vec(topic))
@@ -114,12 +222,33 @@ pub trait AsyncPubSub: Send + Sync {
             .await
     }
 
-    /// Publishes a `payload` to all subscribers of all `topics`.
+    /// Publishes a `payload` to all subscribers of all `topics`. See
+    /// [`Self::publish()`] for more information.
     async fn publish_bytes_to_all(
         &self,
         topics: impl IntoIterator<Item = Vec<u8>> + Send + 'async_trait,
         payload: Vec<u8>,
-    ) -> Result<(), Error>;
+    ) -> Result<PublishReceipt, Error>;
+
+    /// Publishes each `(topic, payload)` pair in `messages` as a single
+    /// batch. See [`PubSub::publish_batch()`] for more information.
+    async fn publish_batch<Topic: Serialize + Send + Sync, Payload: Serialize + Send + Sync>(
+        &self,
+        messages: &[(Topic, Payload)],
+    ) -> Result<PublishReceipt, Error> {
+        let messages = messages
+            .iter()
+            .map(|(topic, payload)| Ok((pot::to_vec(topic)?, pot::to_vec(payload)?)))
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.publish_batch_bytes(messages).await
+    }
+
+    /// Publishes each `(topic, payload)` pair in `messages` as a single batch.
+    /// See [`PubSub::publish_batch()`] for more information.
+    async fn publish_batch_bytes(
+        &self,
+        messages: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<PublishReceipt, Error>;
 }
 
 /// A subscriber to one or more topics.
@@ -136,6 +265,22 @@ pub trait AsyncSubscriber: Send + Sync {
     /// Subscribe to [`Message`]s published to `topic`.
     async fn subscribe_to_bytes(&self, topic: Vec<u8>) -> Result<(), Error>;
 
+    /// Subscribe to [`Message`]s published to `topic`. If the server retains
+    /// messages for `topic`, the retained messages are delivered before any
+    /// messages published after this call.
+    async fn subscribe_to_with_replay<Topic: Serialize + Send + Sync>(
+        &self,
+        topic: &Topic,
+    ) -> Result<(), Error> {
+        self.subscribe_to_bytes_with_replay(pot::to_vec(topic)?)
+            .await
+    }
+
+    /// Subscribe to [`Message`]s published to `topic`. If the server retains
+    /// messages for `topic`, the retained messages are delivered before any
+    /// messages published after this call.
+    async fn subscribe_to_bytes_with_replay(&self, topic: Vec<u8>) -> Result<(), Error>;
+
     /// Unsubscribe from [`Message`]s published to `topic`.
     async fn unsubscribe_from<Topic: Serialize + Send + Sync>(
         &self,
@@ -147,66 +292,428 @@ pub trait AsyncSubscriber: Send + Sync {
     /// Unsubscribe from [`Message`]s published to `topic`.
     async fn unsubscribe_from_bytes(&self, topic: &[u8]) -> Result<(), Error>;
 
+    /// Subscribe to [`Message`]s published to any string topic that starts
+    /// with `prefix`. For example, subscribing to the prefix `orders.` receives
+    /// messages published to `orders.created` and `orders.shipped`.
+    ///
+    /// A message is only received once, even if its topic matches multiple
+    /// subscriptions.
+    async fn subscribe_to_prefix(&self, prefix: &str) -> Result<(), Error> {
+        self.subscribe_to_prefix_bytes(prefix.as_bytes().to_vec())
+            .await
+    }
+
+    /// Subscribe to [`Message`]s published to any topic that starts with
+    /// `prefix`. Topics that are serialized strings are matched against the
+    /// string's bytes. All other topics are matched against their serialized
+    /// bytes.
+    ///
+    /// A message is only received once, even if its topic matches multiple
+    /// subscriptions.
+    async fn subscribe_to_prefix_bytes(&self, prefix: Vec<u8>) -> Result<(), Error>;
+
+    /// Unsubscribe from [`Message`]s published to topics that start with
+    /// `prefix`.
+    async fn unsubscribe_from_prefix(&self, prefix: &str) -> Result<(), Error> {
+        self.unsubscribe_from_prefix_bytes(prefix.as_bytes()).await
+    }
+
+    /// Unsubscribe from [`Message`]s published to topics that start with
+    /// `prefix`.
+    async fn unsubscribe_from_prefix_bytes(&self, prefix: &[u8]) -> Result<(), Error>;
+
     /// Returns the receiver to receive [`Message`]s.
     fn receiver(&self) -> &Receiver;
 }
 
-/// Receiver of PubSub [`Message`]s.
+/// The outcome of publishing a message, returned from [`PubSub::publish()`]
+/// and [`AsyncPubSub::publish()`].
+///
+/// Delivery is best-effort. A message is delivered once it is added to a
+/// subscriber's buffer, and can still be lost if the subscriber is dropped or
+/// its [`BufferLimit`] drops the oldest messages before it is received.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PublishReceipt {
+    /// The number of subscribers the message was delivered to. When
+    /// publishing to multiple topics, a subscriber that receives the message
+    /// for more than one topic is counted once for each topic. When publishing
+    /// a batch, each message is counted once for each subscriber it was
+    /// delivered to.
+    pub delivered_to: usize,
+}
+
+/// Limits the number of messages buffered for a [`Subscriber`] that haven't
+/// been received yet.
+///
+/// Subscribers created without a limit buffer every message published to
+/// their topics until it is received, which allows a slow subscriber to
+/// consume an unbounded amount of memory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BufferLimit {
+    /// The maximum number of messages buffered. A capacity of 0 is treated as
+    /// a capacity of 1.
+    pub capacity: usize,
+    /// The behavior when a message is published while the buffer is full.
+    pub overflow: Overflow,
+}
+
+impl BufferLimit {
+    /// Returns a limit of `capacity` messages that drops the oldest buffered
+    /// message when the buffer is full.
+    #[must_use]
+    pub const fn drop_oldest(capacity: usize) -> Self {
+        Self {
+            capacity,
+            overflow: Overflow::DropOldest,
+        }
+    }
+
+    /// Returns a limit of `capacity` messages that makes publishers wait
+    /// when the buffer is full.
+    #[must_use]
+    pub const fn backpressure(capacity: usize) -> Self {
+        Self {
+            capacity,
+            overflow: Overflow::Backpressure,
+        }
+    }
+}
+
+/// The behavior of a [`BufferLimit`] when a message is published while the
+/// buffer is full.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Overflow {
+    /// The oldest buffered message is dropped to make room for the new
+    /// message. The number of messages dropped is returned from
+    /// [`Receiver::take_lagged()`].
+    DropOldest,
+    /// Publishing waits until the subscriber has received enough messages to
+    /// make room for the new message. A subscriber that stops receiving
+    /// messages prevents publishing to any of its topics from completing.
+    ///
+    /// Messages published by BonsaiDb, such as change feed events, never
+    /// wait: they are dropped and counted as lagged instead. Servers create
+    /// subscribers requested by clients using [`Overflow::DropOldest`], so that
+    /// a slow client can't delay publishers.
+    Backpressure,
+}
+
+/// Creates a subscriber's buffer of messages, returning the sending half
+/// used when publishing and the [`Receiver`]. This is an internal API, which
+/// is why the documentation is hidden.
+#[doc(hidden)]
+#[must_use]
+pub fn channel(limit: Option<BufferLimit>, strip_database: bool) -> (MessageSender, Receiver) {
+    let (sender, receiver) = match limit {
+        Some(limit) => flume::bounded(limit.capacity.max(1)),
+        None => flume::unbounded(),
+    };
+    let (direct_sender, direct) = flume::unbounded();
+    let lagged = Arc::new(AtomicU64::new(0));
+    let oldest = matches!(
+        limit,
+        Some(BufferLimit {
+            overflow: Overflow::DropOldest,
+            ..
+        })
+    )
+    .then(|| receiver.clone());
+    (
+        MessageSender {
+            sender,
+            oldest,
+            lagged: lagged.clone(),
+        },
+        Receiver {
+            receiver,
+            direct_sender,
+            direct,
+            lagged,
+            strip_database,
+            on_receive: None,
+            pending: PendingReceive::default(),
+        },
+    )
+}
+
+/// The sending half of a subscriber's buffer of messages, created by
+/// [`channel()`].
+#[doc(hidden)]
 #[derive(Clone, Debug)]
+pub struct MessageSender {
+    sender: flume::Sender<Delivery>,
+    /// Used to drop the oldest message when the buffer is full, if the
+    /// subscriber's overflow is [`Overflow::DropOldest`].
+    oldest: Option<flume::Receiver<Delivery>>,
+    lagged: Arc<AtomicU64>,
+}
+
+/// A single entry in a subscriber's buffer.
+#[derive(Debug)]
+enum Delivery {
+    Message(Message),
+    /// Messages published using [`PubSub::publish_batch()`], which must be
+    /// received contiguously. Batches are never empty.
+    Batch(Vec<Message>),
+}
+
+impl Delivery {
+    fn len(&self) -> usize {
+        match self {
+            Self::Message(_) => 1,
+            Self::Batch(messages) => messages.len(),
+        }
+    }
+
+    fn into_message(self) -> Message {
+        match self {
+            Self::Message(message) => message,
+            Self::Batch(_) => unreachable!("expected a single message"),
+        }
+    }
+
+    fn into_batch(self) -> Vec<Message> {
+        match self {
+            Self::Batch(messages) => messages,
+            Self::Message(_) => unreachable!("expected a batch"),
+        }
+    }
+}
+
+impl MessageSender {
+    /// Sends `message` if it can be sent without waiting. If the buffer is
+    /// full and applies backpressure, [`flume::TrySendError::Full`] is
+    /// returned.
+    pub fn try_send(&self, message: Message) -> Result<(), flume::TrySendError<Message>> {
+        self.try_send_delivery(Delivery::Message(message))
+            .map_err(|err| map_try_send_error(err, Delivery::into_message))
+    }
+
+    /// Sends `messages` as a batch that is received contiguously, if it can
+    /// be sent without waiting. If the buffer is full and applies
+    /// backpressure, [`flume::TrySendError::Full`] is returned.
+    ///
+    /// `messages` must not be empty.
+    pub fn try_send_batch(
+        &self,
+        messages: Vec<Message>,
+    ) -> Result<(), flume::TrySendError<Vec<Message>>> {
+        debug_assert!(!messages.is_empty());
+        self.try_send_delivery(Delivery::Batch(messages))
+            .map_err(|err| map_try_send_error(err, Delivery::into_batch))
+    }
+
+    fn try_send_delivery(
+        &self,
+        mut delivery: Delivery,
+    ) -> Result<(), flume::TrySendError<Delivery>> {
+        loop {
+            match self.sender.try_send(delivery) {
+                Err(flume::TrySendError::Full(full)) => {
+                    let Some(oldest) = &self.oldest else {
+                        return Err(flume::TrySendError::Full(full));
+                    };
+                    if let Ok(dropped) = oldest.try_recv() {
+                        self.lagged
+                            .fetch_add(dropped.len() as u64, Ordering::Relaxed);
+                    }
+                    delivery = full;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends `message`, blocking the current thread while the buffer is full.
+    pub fn send(&self, message: Message) -> Result<(), Disconnected> {
+        self.send_delivery(Delivery::Message(message))
+    }
+
+    /// Sends `messages` as a batch that is received contiguously, blocking
+    /// the current thread while the buffer is full.
+    ///
+    /// `messages` must not be empty.
+    pub fn send_batch(&self, messages: Vec<Message>) -> Result<(), Disconnected> {
+        debug_assert!(!messages.is_empty());
+        self.send_delivery(Delivery::Batch(messages))
+    }
+
+    fn send_delivery(&self, delivery: Delivery) -> Result<(), Disconnected> {
+        match self.try_send_delivery(delivery) {
+            Ok(()) => Ok(()),
+            Err(flume::TrySendError::Full(delivery)) => {
+                self.sender.send(delivery).map_err(|_| Disconnected)
+            }
+            Err(flume::TrySendError::Disconnected(_)) => Err(Disconnected),
+        }
+    }
+
+    /// Sends `message`, waiting while the buffer is full.
+    pub async fn send_async(&self, message: Message) -> Result<(), Disconnected> {
+        self.send_delivery_async(Delivery::Message(message)).await
+    }
+
+    /// Sends `messages` as a batch that is received contiguously, waiting
+    /// while the buffer is full.
+    ///
+    /// `messages` must not be empty.
+    pub async fn send_batch_async(&self, messages: Vec<Message>) -> Result<(), Disconnected> {
+        debug_assert!(!messages.is_empty());
+        self.send_delivery_async(Delivery::Batch(messages)).await
+    }
+
+    async fn send_delivery_async(&self, delivery: Delivery) -> Result<(), Disconnected> {
+        match self.try_send_delivery(delivery) {
+            Ok(()) => Ok(()),
+            Err(flume::TrySendError::Full(delivery)) => self
+                .sender
+                .send_async(delivery)
+                .await
+                .map_err(|_| Disconnected),
+            Err(flume::TrySendError::Disconnected(_)) => Err(Disconnected),
+        }
+    }
+
+    /// Adds `count` messages that were dropped before reaching this buffer to
+    /// the count returned from [`Receiver::take_lagged()`].
+    pub fn add_lagged(&self, count: u64) {
+        self.lagged.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+fn map_try_send_error<T>(
+    err: flume::TrySendError<Delivery>,
+    unwrap: impl FnOnce(Delivery) -> T,
+) -> flume::TrySendError<T> {
+    match err {
+        flume::TrySendError::Full(delivery) => flume::TrySendError::Full(unwrap(delivery)),
+        flume::TrySendError::Disconnected(delivery) => {
+            flume::TrySendError::Disconnected(unwrap(delivery))
+        }
+    }
+}
+
+/// Receiver of PubSub [`Message`]s.
+///
+/// Messages replayed by
+/// [`Subscriber::subscribe_to_with_replay()`] are always received before any
+/// messages published to the same topic after the subscription was made.
+///
+/// In addition to the blocking and async receive functions, messages can be
+/// received by using the receiver as an [`Iterator`] or a [`Stream`].
 #[must_use]
 pub struct Receiver {
-    receiver: flume::Receiver<Message>,
+    receiver: flume::Receiver<Delivery>,
+    /// Keeps `direct` connected, ensuring only disconnecting `receiver`
+    /// interrupts receiving.
+    direct_sender: flume::Sender<Message>,
+    direct: flume::Receiver<Message>,
+    lagged: Arc<AtomicU64>,
     strip_database: bool,
+    on_receive: Option<Arc<dyn Fn() + Send + Sync>>,
+    pending: PendingReceive,
 }
 
 impl Receiver {
+    /// Invokes `callback` each time a message is received by this receiver
+    /// or any of its clones.
     #[doc(hidden)]
-    pub fn new_stripping_prefixes(receiver: flume::Receiver<Message>) -> Self {
-        Self {
-            receiver,
-            strip_database: true,
-        }
+    pub fn on_receive<F: Fn() + Send + Sync + 'static>(mut self, callback: F) -> Self {
+        self.on_receive = Some(Arc::new(callback));
+        self
     }
 
+    /// Queues `messages` to be received ahead of any messages that were
+    /// published and have not been received yet.
     #[doc(hidden)]
-    pub fn new(receiver: flume::Receiver<Message>) -> Self {
-        Self {
-            receiver,
-            strip_database: false,
+    pub fn deliver(&self, messages: impl IntoIterator<Item = Message>) {
+        for message in messages {
+            // The receiving half is owned by `self`, so sending can't fail.
+            drop(self.direct_sender.send(message));
         }
     }
 
+    /// Returns the number of messages that have been dropped because this
+    /// receiver's [`BufferLimit`] was exceeded since this function was last
+    /// called. The count is shared by all clones of this receiver.
+    ///
+    /// Messages are only dropped for subscribers created with a limit whose
+    /// overflow is [`Overflow::DropOldest`].
+    #[must_use]
+    pub fn take_lagged(&self) -> u64 {
+        self.lagged.swap(0, Ordering::Relaxed)
+    }
+
     /// Receive the next [`Message`]. Blocks the current thread until a message
     /// is available. If the receiver becomes disconnected, an error will be
     /// returned.
     pub fn receive(&self) -> Result<Message, Disconnected> {
-        self.receiver
-            .recv()
-            .map(|message| self.remove_database_prefix(message))
-            .map_err(|_| Disconnected)
+        let message = match self.direct.try_recv() {
+            Ok(message) => message,
+            Err(_) => flume::Selector::new()
+                .recv(&self.direct, Result::ok)
+                .recv(&self.receiver, |delivery| {
+                    delivery.ok().map(|delivery| self.unpack(delivery))
+                })
+                .wait()
+                .ok_or(Disconnected)?,
+        };
+        Ok(self.received(message))
     }
 
     /// Receive the next [`Message`]. Blocks the current task until a new
     /// message is available. If the receiver becomes disconnected, an error
     /// will be returned.
     pub async fn receive_async(&self) -> Result<Message, Disconnected> {
-        self.receiver
-            .recv_async()
-            .await
-            .map(|message| self.remove_database_prefix(message))
-            .map_err(|_| Disconnected)
+        let message = match self.direct.try_recv() {
+            Ok(message) => message,
+            Err(_) => {
+                let direct = pin!(self.direct.recv_async());
+                let received = pin!(self.receiver.recv_async());
+                match future::select(direct, received).await {
+                    Either::Left((message, _)) => message.map_err(|_| Disconnected)?,
+                    Either::Right((delivery, _)) => {
+                        self.unpack(delivery.map_err(|_| Disconnected)?)
+                    }
+                }
+            }
+        };
+        Ok(self.received(message))
     }
 
     /// Try to receive the next [`Message`]. This function will not block, and
     /// only returns a message if one is already available.
     pub fn try_receive(&self) -> Result<Message, TryReceiveError> {
-        self.receiver
+        self.direct
             .try_recv()
-            .map(|message| self.remove_database_prefix(message))
+            .or_else(|_| {
+                self.receiver
+                    .try_recv()
+                    .map(|delivery| self.unpack(delivery))
+            })
+            .map(|message| self.received(message))
             .map_err(TryReceiveError::from)
     }
 
-    fn remove_database_prefix(&self, mut message: Message) -> Message {
+    /// Returns the first message of `delivery`, queueing the remaining
+    /// messages of a batch to be received next.
+    fn unpack(&self, delivery: Delivery) -> Message {
+        match delivery {
+            Delivery::Message(message) => message,
+            Delivery::Batch(messages) => {
+                let mut messages = messages.into_iter();
+                let first = messages.next().expect("batches are never empty");
+                self.deliver(messages);
+                first
+            }
+        }
+    }
+
+    fn received(&self, mut message: Message) -> Message {
+        if let Some(on_receive) = &self.on_receive {
+            on_receive();
+        }
+
         if self.strip_database {
             if let Some(database_length) = message.topic.iter().position(|b| b == 0) {
                 message.topic.0.read_bytes(database_length + 1).unwrap();
@@ -217,6 +724,31 @@ impl Receiver {
     }
 }
 
+impl Clone for Receiver {
+    fn clone(&self) -> Self {
+        Self {
+            receiver: self.receiver.clone(),
+            direct_sender: self.direct_sender.clone(),
+            direct: self.direct.clone(),
+            lagged: self.lagged.clone(),
+            strip_database: self.strip_database,
+            on_receive: self.on_receive.clone(),
+            pending: PendingReceive::default(),
+        }
+    }
+}
+
+impl std::fmt::Debug for Receiver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Receiver")
+            .field("receiver", &self.receiver)
+            .field("direct", &self.direct)
+            .field("lagged", &self.lagged)
+            .field("strip_database", &self.strip_database)
+            .finish_non_exhaustive()
+    }
+}
+
 impl Iterator for Receiver {
     type Item = Message;
 
@@ -225,6 +757,37 @@ impl Iterator for Receiver {
     }
 }
 
+impl Stream for Receiver {
+    type Item = Message;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(pending) = this.pending.get_mut() {
+                let result = ready!(pending.poll_unpin(cx));
+                *this.pending.get_mut() = None;
+                return Poll::Ready(result.ok());
+            }
+
+            let receiver = this.clone();
+            *this.pending.get_mut() = Some(async move { receiver.receive_async().await }.boxed());
+        }
+    }
+}
+
+/// The receive in progress when a [`Receiver`] is polled as a [`Stream`].
+///
+/// The future is wrapped in a mutex so that [`Receiver`] remains `Sync`. The
+/// mutex is never locked, as the future is only accessed through `&mut self`.
+#[derive(Default)]
+struct PendingReceive(Mutex<Option<BoxFuture<'static, Result<Message, Disconnected>>>>);
+
+impl PendingReceive {
+    fn get_mut(&mut self) -> &mut Option<BoxFuture<'static, Result<Message, Disconnected>>> {
+        self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
 /// The [`Receiver`] was disconnected
 #[derive(thiserror::Error, Debug, Clone, Eq, PartialEq)]
 #[error("the receiver is disconnected")]
@@ -281,8 +844,13 @@ macro_rules! define_async_pubsub_test_suite {
                 let pubsub = harness.connect().await?;
                 let subscriber = AsyncPubSub::create_subscriber(&pubsub).await?;
                 AsyncSubscriber::subscribe_to(&subscriber, &"mytopic").await?;
-                AsyncPubSub::publish(&pubsub, &"mytopic", &String::from("test")).await?;
-                AsyncPubSub::publish(&pubsub, &"othertopic", &String::from("test")).await?;
+                let receipt =
+                    AsyncPubSub::publish(&pubsub, &"mytopic", &String::from("test")).await?;
+                assert_eq!(receipt.delivered_to, 1);
+                // Publishing to a topic without subscribers delivers nothing.
+                let receipt =
+                    AsyncPubSub::publish(&pubsub, &"othertopic", &String::from("test")).await?;
+                assert_eq!(receipt.delivered_to, 0);
                 let receiver = subscriber.receiver().clone();
                 let message = receiver.receive_async().await.expect("No message received");
                 assert_eq!(message.topic::<String>()?, "mytopic");
@@ -445,6 +1013,137 @@ macro_rules! define_async_pubsub_test_suite {
 
                 Ok(())
             }
+
+            #[tokio::test]
+            async fn prefix_test() -> anyhow::Result<()> {
+                let harness = $harness::new($crate::test_util::HarnessTest::PubSubPrefix).await?;
+                let pubsub = harness.connect().await?;
+                let subscriber = AsyncPubSub::create_subscriber(&pubsub).await?;
+                // Overlapping subscriptions must not deliver a message twice.
+                AsyncSubscriber::subscribe_to_prefix(&subscriber, "orders.").await?;
+                AsyncSubscriber::subscribe_to_prefix(&subscriber, "orders.s").await?;
+                AsyncSubscriber::subscribe_to(&subscriber, &"orders.shipped").await?;
+
+                AsyncPubSub::publish(&pubsub, &"orders.created", &1_u32).await?;
+                AsyncPubSub::publish(&pubsub, &"orders.shipped", &2_u32).await?;
+                AsyncPubSub::publish(&pubsub, &"invoices.created", &3_u32).await?;
+                AsyncPubSub::publish(&pubsub, &"orders", &4_u32).await?;
+
+                let mut received = Vec::new();
+                for _ in 0..2_u8 {
+                    let message = subscriber.receiver().receive_async().await?;
+                    received.push((message.topic::<String>()?, message.payload::<u32>()?));
+                }
+                received.sort();
+                assert_eq!(
+                    received,
+                    [
+                        (String::from("orders.created"), 1),
+                        (String::from("orders.shipped"), 2)
+                    ]
+                );
+
+                AsyncSubscriber::unsubscribe_from_prefix(&subscriber, "orders.").await?;
+                AsyncPubSub::publish(&pubsub, &"orders.created", &5_u32).await?;
+                AsyncPubSub::publish(&pubsub, &"orders.sold", &6_u32).await?;
+                let message = subscriber.receiver().receive_async().await?;
+                assert_eq!(message.topic::<String>()?, "orders.sold");
+                assert_eq!(message.payload::<u32>()?, 6);
+                assert!(matches!(
+                    subscriber.receiver().try_receive(),
+                    Err($crate::pubsub::TryReceiveError::Empty)
+                ));
+
+                Ok(())
+            }
+
+            #[tokio::test]
+            async fn buffer_limit_test() -> anyhow::Result<()> {
+                use $crate::pubsub::BufferLimit;
+
+                let harness =
+                    $harness::new($crate::test_util::HarnessTest::PubSubBufferLimits).await?;
+                let pubsub = harness.connect().await?;
+                let subscriber = AsyncPubSub::create_subscriber_with_limit(
+                    &pubsub,
+                    BufferLimit::backpressure(2),
+                )
+                .await?;
+                AsyncSubscriber::subscribe_to(&subscriber, &"lossless").await?;
+
+                // Publishing more messages than the buffer can hold waits for
+                // the subscriber to make room rather than dropping messages.
+                // Servers don't allow remote subscribers to apply
+                // backpressure, so messages a remote subscriber misses are
+                // reported as lagged instead.
+                let publisher = tokio::spawn({
+                    let pubsub = pubsub.clone();
+                    async move {
+                        for value in 0_u32..10 {
+                            AsyncPubSub::publish(&pubsub, &"lossless", &value).await?;
+                        }
+                        Ok::<_, $crate::Error>(())
+                    }
+                });
+                let mut received = Vec::new();
+                while received.last() != Some(&9) {
+                    let message = subscriber.receiver().receive_async().await?;
+                    received.push(message.payload::<u32>()?);
+                }
+                publisher.await??;
+                assert!(received.windows(2).all(|pair| pair[0] < pair[1]));
+                assert_eq!(
+                    received.len() as u64 + subscriber.receiver().take_lagged(),
+                    10
+                );
+
+                Ok(())
+            }
+
+            #[tokio::test]
+            async fn publish_batch_test() -> anyhow::Result<()> {
+                let harness =
+                    $harness::new($crate::test_util::HarnessTest::PubSubPublishBatch).await?;
+                let pubsub = harness.connect().await?;
+                let both = AsyncPubSub::create_subscriber(&pubsub).await?;
+                AsyncSubscriber::subscribe_to(&both, &"a").await?;
+                AsyncSubscriber::subscribe_to(&both, &"b").await?;
+                let only_b = AsyncPubSub::create_subscriber(&pubsub).await?;
+                AsyncSubscriber::subscribe_to(&only_b, &"b").await?;
+
+                let receipt =
+                    AsyncPubSub::publish_batch(&pubsub, &[("a", 1_u32), ("b", 2), ("a", 3)])
+                        .await?;
+                assert_eq!(receipt.delivered_to, 4);
+                AsyncPubSub::publish(&pubsub, &"b", &4_u32).await?;
+
+                for (topic, value) in [("a", 1_u32), ("b", 2), ("a", 3), ("b", 4)] {
+                    let message = both.receiver().receive_async().await?;
+                    assert_eq!(message.topic::<String>()?, topic);
+                    assert_eq!(message.payload::<u32>()?, value);
+                }
+                for value in [2_u32, 4] {
+                    let message = only_b.receiver().receive_async().await?;
+                    assert_eq!(message.payload::<u32>()?, value);
+                }
+                assert!(matches!(
+                    only_b.receiver().try_receive(),
+                    Err($crate::pubsub::TryReceiveError::Empty)
+                ));
+
+                // Publishing an empty batch delivers nothing.
+                let receipt = AsyncPubSub::publish_batch::<&str, u32>(&pubsub, &[]).await?;
+                assert_eq!(receipt.delivered_to, 0);
+
+                Ok(())
+            }
+
+            #[tokio::test]
+            async fn watch_collection_test() -> anyhow::Result<()> {
+                let harness = $harness::new($crate::test_util::HarnessTest::ChangeFeed).await?;
+                let db = harness.connect().await?;
+                $crate::test_util::watch_collection_tests(&db).await
+            }
         }
     };
 }
@@ -465,8 +1164,11 @@ macro_rules! define_blocking_pubsub_test_suite {
                 let pubsub = harness.connect()?;
                 let subscriber = PubSub::create_subscriber(&pubsub)?;
                 Subscriber::subscribe_to(&subscriber, &"mytopic")?;
-                PubSub::publish(&pubsub, &"mytopic", &String::from("test"))?;
-                PubSub::publish(&pubsub, &"othertopic", &String::from("test"))?;
+                let receipt = PubSub::publish(&pubsub, &"mytopic", &String::from("test"))?;
+                assert_eq!(receipt.delivered_to, 1);
+                // Publishing to a topic without subscribers delivers nothing.
+                let receipt = PubSub::publish(&pubsub, &"othertopic", &String::from("test"))?;
+                assert_eq!(receipt.delivered_to, 0);
                 let receiver = subscriber.receiver().clone();
                 let message = receiver.receive().expect("No message received");
                 assert_eq!(message.topic::<String>()?, "mytopic");
@@ -594,6 +1296,87 @@ macro_rules! define_blocking_pubsub_test_suite {
 
                 Ok(())
             }
+
+            #[test]
+            fn prefix_test() -> anyhow::Result<()> {
+                let harness = $harness::new($crate::test_util::HarnessTest::PubSubPrefix)?;
+                let pubsub = harness.connect()?;
+                let subscriber = PubSub::create_subscriber(&pubsub)?;
+                // Overlapping subscriptions must not deliver a message twice.
+                Subscriber::subscribe_to_prefix(&subscriber, "orders.")?;
+                Subscriber::subscribe_to_prefix(&subscriber, "orders.s")?;
+                Subscriber::subscribe_to(&subscriber, &"orders.shipped")?;
+
+                PubSub::publish(&pubsub, &"orders.created", &1_u32)?;
+                PubSub::publish(&pubsub, &"orders.shipped", &2_u32)?;
+                PubSub::publish(&pubsub, &"invoices.created", &3_u32)?;
+                PubSub::publish(&pubsub, &"orders", &4_u32)?;
+
+                let mut received = Vec::new();
+                for _ in 0..2_u8 {
+                    let message = subscriber.receiver().receive()?;
+                    received.push((message.topic::<String>()?, message.payload::<u32>()?));
+                }
+                received.sort();
+                assert_eq!(
+                    received,
+                    [
+                        (String::from("orders.created"), 1),
+                        (String::from("orders.shipped"), 2)
+                    ]
+                );
+
+                Subscriber::unsubscribe_from_prefix(&subscriber, "orders.")?;
+                PubSub::publish(&pubsub, &"orders.created", &5_u32)?;
+                PubSub::publish(&pubsub, &"orders.sold", &6_u32)?;
+                let message = subscriber.receiver().receive()?;
+                assert_eq!(message.topic::<String>()?, "orders.sold");
+                assert_eq!(message.payload::<u32>()?, 6);
+                assert!(matches!(
+                    subscriber.receiver().try_receive(),
+                    Err($crate::pubsub::TryReceiveError::Empty)
+                ));
+
+                Ok(())
+            }
+
+            #[test]
+            fn publish_batch_test() -> anyhow::Result<()> {
+                let harness = $harness::new($crate::test_util::HarnessTest::PubSubPublishBatch)?;
+                let pubsub = harness.connect()?;
+                let both = PubSub::create_subscriber(&pubsub)?;
+                Subscriber::subscribe_to(&both, &"a")?;
+                Subscriber::subscribe_to(&both, &"b")?;
+                let only_b = PubSub::create_subscriber(&pubsub)?;
+                Subscriber::subscribe_to(&only_b, &"b")?;
+
+                let receipt = PubSub::publish_batch(&pubsub, &[("a", 1_u32), ("b", 2), ("a", 3)])?;
+                assert_eq!(receipt.delivered_to, 4);
+                PubSub::publish(&pubsub, &"b", &4_u32)?;
+
+                for (topic, value) in [("a", 1_u32), ("b", 2), ("a", 3), ("b", 4)] {
+                    let message = both.receiver().receive()?;
+                    assert_eq!(message.topic::<String>()?, topic);
+                    assert_eq!(message.payload::<u32>()?, value);
+                }
+                for value in [2_u32, 4] {
+                    let message = only_b.receiver().receive()?;
+                    assert_eq!(message.payload::<u32>()?, value);
+                }
+                assert!(matches!(
+                    only_b.receiver().try_receive(),
+                    Err($crate::pubsub::TryReceiveError::Empty)
+                ));
+
+                Ok(())
+            }
+
+            #[test]
+            fn watch_collection_test() -> anyhow::Result<()> {
+                let harness = $harness::new($crate::test_util::HarnessTest::ChangeFeed)?;
+                let db = harness.connect()?;
+                $crate::test_util::blocking_watch_collection_tests(&db)
+            }
         }
     };
 }
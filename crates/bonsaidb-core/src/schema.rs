@@ -1,27 +1,39 @@
 mod collection;
+mod filter;
 mod names;
+/// Types for defining relationships between collections.
+pub mod relationship;
 mod schematic;
 mod summary;
+/// Types for defining [`Trigger`]s.
+pub mod trigger;
 /// Types for defining map/reduce-powered `View`s.
 pub mod view;
+/// Types for defining [`VirtualCollection`]s.
+pub mod virtual_collection;
 
 pub use bonsaidb_macros::{Collection, Schema, View, ViewSchema};
 
 pub use self::collection::{
-    AsyncEntry, AsyncList, Collection, DefaultSerialization, InsertError, List, Nameable,
-    NamedCollection, NamedReference, SerializedCollection,
+    AsyncEntry, AsyncList, AsyncScan, Collection, DefaultSerialization, DocumentPatch, InsertError,
+    InsertManyError, List, Nameable, NamedCollection, NamedReference, Scan, SerializedCollection,
+    ValidationError,
 };
+pub use self::filter::{CompiledFilter, Filter};
 pub use self::names::{
     Authority, CollectionName, InvalidNameError, Name, Qualified, QualifiedName, SchemaName,
     ViewName,
 };
 pub use self::schematic::Schematic;
 pub use self::summary::{CollectionSummary, SchemaSummary, ViewSummary};
+pub use self::trigger::Trigger;
 pub use self::view::map::{Map, MappedValue, ViewMappedValue};
 pub use self::view::{
     CollectionMapReduce, DefaultViewSerialization, MapReduce, ReduceResult, SerializedView, View,
     ViewMapResult, ViewSchema,
 };
+pub use self::virtual_collection::VirtualCollection;
+use crate::connection::AccessPolicy;
 use crate::Error;
 
 /// Defines a group of collections that are stored into a single database.
@@ -69,6 +81,35 @@ use crate::Error;
 /// #    pub score: f32,
 /// # }
 /// ```
+///
+/// Schemas can also be composed from other schemas. Using `include`, the
+/// collections and views of each listed schema are added using
+/// [`Schematic::include()`]:
+///
+/// ```rust
+/// use bonsaidb_core::schema::Schema;
+///
+/// #[derive(Schema)]
+/// #[schema(name = "AppSchema", collections = [AppCollection], include = [SharedSchema])]
+/// # #[schema(core = bonsaidb_core)]
+/// pub struct AppSchema;
+///
+/// #[derive(Schema)]
+/// #[schema(name = "SharedSchema", collections = [SharedCollection])]
+/// # #[schema(core = bonsaidb_core)]
+/// pub struct SharedSchema;
+///
+/// # use serde::{Deserialize, Serialize};
+/// # use bonsaidb_core::schema::Collection;
+/// # #[derive(Serialize, Deserialize, Default, Collection)]
+/// # #[collection(name = "AppCollection")]
+/// # #[collection(core = bonsaidb_core)]
+/// # pub struct AppCollection;
+/// # #[derive(Serialize, Deserialize, Default, Collection)]
+/// # #[collection(name = "SharedCollection")]
+/// # #[collection(core = bonsaidb_core)]
+/// # pub struct SharedCollection;
+/// ```
 pub trait Schema: Send + Sync + 'static {
     /// Returns the unique [`SchemaName`] for this schema.
     fn schema_name() -> SchemaName;
@@ -76,10 +117,45 @@ pub trait Schema: Send + Sync + 'static {
     /// Defines the `Collection`s into `schema`.
     fn define_collections(schema: &mut Schematic) -> Result<(), Error>;
 
+    /// Validates the name of each collection defined in this schema. The
+    /// [`Schematic`] returns [`Error::InvalidName`] when a collection is
+    /// defined whose name is rejected, allowing misconfigured names to be
+    /// detected when the schema is first used.
+    ///
+    /// The default implementation rejects names with an empty authority or
+    /// name. When deriving this trait, a custom policy can be provided using
+    /// `#[schema(name_policy = path::to::function)]`.
+    fn validate_collection_name(name: &CollectionName) -> Result<(), InvalidNameError> {
+        name.validate()
+    }
+
+    /// Returns the [`AccessPolicy`] used when querying this schema's views if
+    /// none of the query, the view, or the view's collection specify one. The
+    /// provided implementation returns [`AccessPolicy::UpdateBefore`]. When
+    /// deriving this trait, it can be set using
+    /// `#[schema(access_policy = UpdateAfter)]`.
+    ///
+    /// Only the policy of the schema being queried is used. The policies of
+    /// schemas [included](Schematic::include) by it are ignored.
+    #[must_use]
+    fn default_view_access_policy() -> AccessPolicy {
+        AccessPolicy::UpdateBefore
+    }
+
     /// Retrieves the [`Schematic`] for this schema.
     fn schematic() -> Result<Schematic, Error> {
         Schematic::from_schema::<Self>()
     }
+
+    /// Returns summaries of all views defined in this schema, ordered by
+    /// name. When called on a [`Collection`], only the views of that
+    /// collection are returned.
+    fn views() -> Result<Vec<ViewSummary>, Error> {
+        let schematic = Self::schematic()?;
+        let mut views = schematic.views().map(ViewSummary::from).collect::<Vec<_>>();
+        views.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(views)
+    }
 }
 
 /// This implementation is for accessing databases when interacting with
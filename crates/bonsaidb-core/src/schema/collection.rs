@@ -2,22 +2,28 @@ use std::borrow::{Borrow, Cow};
 use std::fmt::Debug;
 use std::marker::PhantomData;
 use std::task::Poll;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use futures::future::BoxFuture;
-use futures::{ready, Future, FutureExt};
+use futures::stream::BoxStream;
+use futures::{ready, Future, FutureExt, Stream, StreamExt, TryStreamExt};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use transmog::{Format, OwnedDeserializer};
 use transmog_pot::Pot;
 
-use crate::connection::{self, AsyncConnection, Connection, RangeRef};
+use crate::connection::{
+    self, AccessPolicy, AsyncConnection, AsyncLowLevelConnection, Connection, LowLevelConnection,
+    RangeRef,
+};
 use crate::document::{
     BorrowedDocument, CollectionDocument, CollectionHeader, Document, DocumentId, Header, KeyId,
-    OwnedDocument, OwnedDocuments, Revision,
+    MaybeDeleted, OwnedDocument, OwnedDocuments, Revision,
 };
 use crate::key::{IntoPrefixRange, Key, KeyEncoding};
-use crate::schema::{CollectionName, Schematic};
+use crate::limits::DEFAULT_SCAN_BATCH_SIZE;
+use crate::schema::{CollectionName, Filter, Schematic};
 use crate::transaction::{Operation, OperationResult, Transaction};
 use crate::Error;
 
@@ -109,6 +115,28 @@ use crate::Error;
 /// pub struct MyCollection;
 /// ```
 ///
+/// [`Key`] is implemented for integers, strings, byte arrays, tuples, and
+/// `Uuid` (when the `uuid` feature is enabled), among other types. Keys are
+/// encoded such that their byte representations sort in the same order as
+/// their values, which allows range queries over primary keys. To prevent
+/// mixing up ids from different collections, a newtype can be used as the
+/// primary key by deriving [`Key`]:
+///
+/// ```rust
+/// use bonsaidb_core::key::Key;
+/// use bonsaidb_core::schema::Collection;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Key, Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+/// # #[key(core = bonsaidb_core)]
+/// pub struct AccountId(u64);
+///
+/// #[derive(Serialize, Deserialize, Default, Collection)]
+/// #[collection(name = "accounts", primary_key = AccountId)]
+/// # #[collection(core = bonsaidb_core)]
+/// pub struct Account;
+/// ```
+///
 /// If the data being stored has a ["natural key"][natural-key], the field can
 /// be annotated with `#[natural_id]` to use the field's contents as the primary
 /// key when doing a push operation:
@@ -194,6 +222,102 @@ use crate::Error;
 /// pub struct MyCollection;
 /// ```
 ///
+/// ### Soft deletes
+///
+/// Passing `soft_delete` keeps deleted documents as tombstones that can later
+/// be restored. See [`Collection::soft_delete()`] for more information.
+///
+/// ```rust
+/// use bonsaidb_core::schema::Collection;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, Default, Collection)]
+/// #[collection(name = "MyCollection", soft_delete)]
+/// # #[collection(core = bonsaidb_core)]
+/// pub struct MyCollection;
+/// ```
+///
+/// ### Versioning
+///
+/// Passing `version` sets [`Collection::CURRENT_VERSION`], and `migrate`
+/// specifies the function used to implement [`Collection::migrate()`].
+///
+/// ```rust
+/// use bonsaidb_core::schema::Collection;
+/// use bonsaidb_core::Error;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, Default, Collection)]
+/// #[collection(name = "MyCollection", version = 1, migrate = Self::upgrade)]
+/// # #[collection(core = bonsaidb_core)]
+/// pub struct MyCollection;
+///
+/// impl MyCollection {
+///     fn upgrade(from: u32, contents: &[u8]) -> Result<Vec<u8>, Error> {
+///         // Convert `contents` from the format used by version `from`.
+///         Ok(contents.to_vec())
+///     }
+/// }
+/// ```
+///
+/// ### Validating documents
+///
+/// Passing `validate` specifies a function that is given the deserialized
+/// contents of each document being inserted or updated. If it returns an
+/// error, the write is rejected with
+/// [`Error::ValidationFailed`](crate::Error::ValidationFailed). See
+/// [`Collection::validate()`] for more information.
+///
+/// ```rust
+/// use bonsaidb_core::schema::{Collection, ValidationError};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, Default, Collection)]
+/// #[collection(name = "MyCollection", validate = Self::check)]
+/// # #[collection(core = bonsaidb_core)]
+/// pub struct MyCollection {
+///     pub rank: u32,
+/// }
+///
+/// impl MyCollection {
+///     fn check(&self) -> Result<(), ValidationError> {
+///         if self.rank > 0 {
+///             Ok(())
+///         } else {
+///             Err(ValidationError::new("rank must be positive"))
+///         }
+///     }
+/// }
+/// ```
+///
+/// ### Patching documents
+///
+/// Passing `patch` specifies a type implementing [`DocumentPatch`], which
+/// allows documents to be modified by the storage layer using
+/// [`SerializedCollection::patch()`] without first retrieving them. See
+/// [`Collection::apply_patch()`] for more information.
+///
+/// ```rust
+/// use bonsaidb_core::schema::{Collection, DocumentPatch};
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, Default, Collection)]
+/// #[collection(name = "MyCollection", patch = AddVisits)]
+/// # #[collection(core = bonsaidb_core)]
+/// pub struct MyCollection {
+///     pub visits: u64,
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// pub struct AddVisits(u64);
+///
+/// impl DocumentPatch<MyCollection> for AddVisits {
+///     fn apply(self, contents: &mut MyCollection) {
+///         contents.visits += self.0;
+///     }
+/// }
+/// ```
+///
 /// ### Changing the serialization strategy
 ///
 /// BonsaiDb uses [`transmog`](https://github.com/khonsulabs/transmog) to allow
@@ -212,6 +336,35 @@ use crate::Error;
 /// pub struct MyCollection;
 /// ```
 ///
+/// Each collection can use its own format. Passing `format_name` records the
+/// name of the format in each stored document, and `convert_format` specifies
+/// the function used to implement [`Collection::convert_format()`], which is
+/// used to read documents that were stored using a different format:
+///
+/// ```rust
+/// use bonsaidb_core::schema::Collection;
+/// use bonsaidb_core::transmog::Format;
+/// use bonsaidb_core::Error;
+/// use serde::{Deserialize, Serialize};
+///
+/// #[derive(Serialize, Deserialize, Default, Collection)]
+/// #[collection(name = "MyCollection", serialization = transmog_bincode::Bincode)]
+/// #[collection(format_name = "bincode", convert_format = Self::convert)]
+/// # #[collection(core = bonsaidb_core)]
+/// pub struct MyCollection;
+///
+/// impl MyCollection {
+///     fn convert(from: Option<&str>, contents: &[u8]) -> Result<Vec<u8>, Error> {
+///         // Documents stored without a format name used the default format.
+///         assert!(from.is_none());
+///         let contents: Self = pot::from_slice(contents)?;
+///         transmog_bincode::Bincode::default()
+///             .serialize(&contents)
+///             .map_err(|err| Error::other("bincode", err))
+///     }
+/// }
+/// ```
+///
 /// To manually implement `SerializedCollection` you can pass `None` to
 /// `serialization`:
 ///
@@ -252,6 +405,154 @@ pub trait Collection: Send + Sync {
     fn encryption_key() -> Option<KeyId> {
         None
     }
+
+    /// If true, deleting a document from this collection records a tombstone
+    /// with the time of deletion instead of erasing the document.
+    ///
+    /// Soft-deleted documents are not returned by `get`, `list`, or `all`,
+    /// and are removed from views unless the view opts in using
+    /// [`ViewSchema::include_soft_deleted()`](crate::schema::ViewSchema::include_soft_deleted).
+    /// They can be retrieved using
+    /// [`SerializedCollection::list_including_deleted()`] and brought back
+    /// using [`SerializedCollection::restore()`].
+    ///
+    /// Automatically assigned ids are never reused while a tombstone exists.
+    /// Inserting or overwriting a document using the id of a soft-deleted
+    /// document permanently discards the tombstone.
+    #[must_use]
+    fn soft_delete() -> bool {
+        false
+    }
+
+    /// If a duration is returned, documents in this collection expire once
+    /// this much time has passed since they were last inserted or updated.
+    ///
+    /// Expired documents are not returned by `get`, `get_multiple`, `list`, or
+    /// `filter`, and are deleted by a background job that runs periodically
+    /// while the database is open. Until an expired document has been
+    /// deleted, it is still included in views and counts.
+    ///
+    /// Documents stored before a collection began expiring documents never
+    /// expire unless they are updated.
+    #[must_use]
+    fn expire_after() -> Option<Duration> {
+        None
+    }
+
+    /// Returns the [`AccessPolicy`] used when querying this collection's views
+    /// if neither the query nor the view specify one. If `None`, which the
+    /// provided implementation returns, the
+    /// [`default_view_access_policy()`](crate::schema::Schema::default_view_access_policy)
+    /// of the schema being queried is used.
+    ///
+    /// See [`View::default_access_policy()`](crate::schema::View::default_access_policy)
+    /// for how access policies are resolved.
+    #[must_use]
+    fn default_access_policy() -> Option<AccessPolicy> {
+        None
+    }
+
+    /// The version of the format this collection's documents are serialized
+    /// with. Each stored document records the version it was written with.
+    ///
+    /// When the serialized format of a collection changes, this version should
+    /// be incremented and [`Self::migrate()`] implemented. Documents stored
+    /// with an older version are migrated when they are read, and can be
+    /// eagerly rewritten using `Database::migrate_collection()`.
+    const CURRENT_VERSION: u32 = 0;
+
+    /// Migrates `contents` from a document that was stored using version
+    /// `from` to [`Self::CURRENT_VERSION`], returning the migrated contents.
+    ///
+    /// This function is only invoked for documents whose version is older than
+    /// [`Self::CURRENT_VERSION`]. The default implementation returns
+    /// [`Error::DocumentVersionUnsupported`].
+    fn migrate(from: u32, contents: &[u8]) -> Result<Vec<u8>, Error> {
+        let _ = contents;
+        Err(Error::DocumentVersionUnsupported {
+            collection: Self::collection_name(),
+            version: from,
+        })
+    }
+
+    /// The name of the format this collection's documents are serialized
+    /// with. When set, each stored document records the name of the format
+    /// it was written with.
+    ///
+    /// When a collection's [`SerializedCollection::Format`] changes, this
+    /// name should be changed and [`Self::convert_format()`] implemented.
+    /// Documents stored using a different format are converted when they are
+    /// read, and can be eagerly rewritten using
+    /// `Database::migrate_collection()`. Documents stored before a name was
+    /// set are treated as having no name.
+    const FORMAT_NAME: Option<&'static str> = None;
+
+    /// Converts `contents` from a document that was stored using the format
+    /// named `from` to the format named [`Self::FORMAT_NAME`], returning the
+    /// converted contents.
+    ///
+    /// This function is only invoked for documents whose format name differs
+    /// from [`Self::FORMAT_NAME`]. Conversion happens before
+    /// [`Self::migrate()`], which means `contents` may have been serialized
+    /// using an older [version](Self::CURRENT_VERSION) of the collection. The
+    /// default implementation returns [`Error::DocumentFormatUnsupported`].
+    fn convert_format(from: Option<&str>, contents: &[u8]) -> Result<Vec<u8>, Error> {
+        let _ = contents;
+        Err(Error::DocumentFormatUnsupported {
+            collection: Self::collection_name(),
+            format: from.map(String::from),
+        })
+    }
+
+    /// Validates the serialized `contents` of a document being inserted,
+    /// updated, or overwritten in this collection. If an error is returned,
+    /// the transaction is rejected with
+    /// [`Error::ValidationFailed`](crate::Error::ValidationFailed) and none of
+    /// its changes are persisted.
+    ///
+    /// Validation is performed by the storage layer, which means documents
+    /// written by remote clients are validated by the server. Documents
+    /// written through replication or restored from a backup are not
+    /// validated. The default implementation accepts all documents.
+    fn validate(contents: &[u8]) -> Result<(), ValidationError> {
+        let _ = contents;
+        Ok(())
+    }
+
+    /// Applies the serialized `patch` to the serialized `contents` of a
+    /// document in this collection, returning the updated contents.
+    ///
+    /// Patches are applied by the storage layer while executing a
+    /// [`Command::Patch`](crate::transaction::Command::Patch), which means
+    /// documents can be modified without being retrieved first. The patched
+    /// contents are validated using [`Self::validate()`]. The default
+    /// implementation returns
+    /// [`Error::PatchUnsupported`](crate::Error::PatchUnsupported). When
+    /// deriving this trait, `#[collection(patch = PatchType)]` implements this
+    /// function using [`DocumentPatch::apply_serialized()`].
+    fn apply_patch(contents: &[u8], patch: &[u8]) -> Result<Vec<u8>, Error> {
+        let _ = (contents, patch);
+        Err(Error::PatchUnsupported(Self::collection_name()))
+    }
+}
+
+/// A modification to the contents of a document in the collection `C` that
+/// is applied by the storage layer. See [`Collection::apply_patch()`].
+pub trait DocumentPatch<C: SerializedCollection>:
+    Serialize + DeserializeOwned + Send + Sync
+{
+    /// Applies this patch to `contents`.
+    fn apply(self, contents: &mut C::Contents);
+
+    /// Deserializes a patch from `patch` and applies it to the serialized
+    /// `contents`, returning the serialized result. This can be used to
+    /// implement [`Collection::apply_patch()`].
+    fn apply_serialized(contents: &[u8], patch: &[u8]) -> Result<Vec<u8>, Error> {
+        let patch = pot::from_slice::<Self>(patch)?;
+        let mut contents = C::deserialize(contents)?;
+        patch.apply(&mut contents);
+        C::serialize(&contents)
+    }
 }
 
 /// A collection that knows how to serialize and deserialize documents to an associated type.
@@ -281,6 +582,13 @@ pub trait SerializedCollection: Collection {
     /// Returns the natural identifier of `contents`. This is called when
     /// pushing values into a collection, before attempting to automatically
     /// assign a unique id.
+    ///
+    /// When an id is returned, the document is inserted using that id. Because
+    /// primary keys are unique, this removes the need for a separate unique
+    /// view to look up documents by a natural key. If a document already
+    /// exists with the same id, the push fails with
+    /// [`Error::DocumentConflict`](crate::Error::DocumentConflict), which
+    /// contains the header of the existing document.
     #[allow(unused_variables)]
     fn natural_id(contents: &Self::Contents) -> Option<Self::PrimaryKey>
     where
@@ -384,8 +692,12 @@ pub trait SerializedCollection: Collection {
         Ok(possible_doc.as_ref().map(TryInto::try_into).transpose()?)
     }
 
-    /// Retrieves all documents matching `ids`. Documents that are not found
-    /// are not returned, but no error will be generated.
+    /// Retrieves all documents matching `ids` using a single request.
+    /// Documents are returned in the order their ids appear in `ids`.
+    ///
+    /// Documents that are not found are not returned, but no error will be
+    /// generated. If an id is present in `ids` more than once, its document is
+    /// only returned once, at the position of the id's first occurrence.
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
@@ -417,8 +729,12 @@ pub trait SerializedCollection: Collection {
             .and_then(|docs| docs.collection_documents())
     }
 
-    /// Retrieves all documents matching `ids`. Documents that are not found
-    /// are not returned, but no error will be generated.
+    /// Retrieves all documents matching `ids` using a single request.
+    /// Documents are returned in the order their ids appear in `ids`.
+    ///
+    /// Documents that are not found are not returned, but no error will be
+    /// generated. If an id is present in `ids` more than once, its document is
+    /// only returned once, at the position of the id's first occurrence.
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
@@ -656,228 +972,805 @@ pub trait SerializedCollection: Collection {
         ))
     }
 
-    /// Pushes this value into the collection, returning the created document.
-    /// This function is useful when `Self != Self::Contents`.
-    ///
-    /// ## Automatic ID Assignment
+    /// Returns an iterator over all documents in ascending order by primary
+    /// key.
     ///
-    /// This function calls [`Self::natural_id()`] to try to retrieve a primary
-    /// key value from `contents`. If an id is returned, the item is inserted
-    /// with that id. If an id is not returned, an id will be automatically
-    /// assigned, if possible, by the storage backend, which uses the [`Key`]
-    /// trait to assign ids.
+    /// Unlike [`all()`](Self::all), documents are read in batches of up to
+    /// [`DEFAULT_SCAN_BATCH_SIZE`](crate::limits::DEFAULT_SCAN_BATCH_SIZE)
+    /// documents, and the next batch is not read until the previous batch has
+    /// been consumed. This allows processing collections that are too large to
+    /// load into memory at once. The batch size can be changed using
+    /// [`Scan::batch_size()`].
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
     /// # use bonsaidb_core::connection::Connection;
     /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
-    /// let document = MyCollection::push(MyCollection::default(), &db)?;
-    /// println!(
-    ///     "Inserted {:?} with id {} with revision {}",
-    ///     document.contents, document.header.id, document.header.revision
-    /// );
+    /// for doc in MyCollection::scan(&db).batch_size(100) {
+    ///     let doc = doc?;
+    ///     println!(
+    ///         "Retrieved #{} with deserialized contents: {:?}",
+    ///         doc.header.id, doc.contents
+    ///     );
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    fn push<Cn: Connection>(
-        contents: Self::Contents,
-        connection: &Cn,
-    ) -> Result<CollectionDocument<Self>, InsertError<Self::Contents>>
+    fn scan<C: Connection>(connection: &C) -> Scan<'_, C, Self>
     where
-        Self: Sized + 'static,
+        Self: Sized,
     {
-        let header = match connection.collection::<Self>().push(&contents) {
-            Ok(header) => header,
-            Err(error) => return Err(InsertError { contents, error }),
-        };
-        Ok(CollectionDocument { header, contents })
+        Scan::new(connection)
     }
 
-    /// Pushes this value into the collection, returning the created document.
-    /// This function is useful when `Self != Self::Contents`.
+    /// Returns a [`Stream`](futures::Stream) of all documents in ascending
+    /// order by primary key.
     ///
-    /// ## Automatic ID Assignment
+    /// Unlike [`all_async()`](Self::all_async), documents are read in batches
+    /// of up to
+    /// [`DEFAULT_SCAN_BATCH_SIZE`](crate::limits::DEFAULT_SCAN_BATCH_SIZE)
+    /// documents, and the next batch is not read until the previous batch has
+    /// been consumed. This allows processing collections that are too large to
+    /// load into memory at once. The batch size can be changed using
+    /// [`AsyncScan::batch_size()`].
     ///
-    /// This function calls [`Self::natural_id()`] to try to retrieve a primary
-    /// key value from `contents`. If an id is returned, the item is inserted
-    /// with that id. If an id is not returned, an id will be automatically
-    /// assigned, if possible, by the storage backend, which uses the [`Key`]
-    /// trait to assign ids.
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # use futures::TryStreamExt;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let mut docs = MyCollection::scan_async(&db).batch_size(100);
+    /// while let Some(doc) = docs.try_next().await? {
+    ///     println!(
+    ///         "Retrieved #{} with deserialized contents: {:?}",
+    ///         doc.header.id, doc.contents
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    fn scan_async<C: AsyncConnection>(connection: &C) -> AsyncScan<'_, C, Self>
+    where
+        Self: Sized,
+    {
+        AsyncScan::new(connection)
+    }
+
+    /// Retrieves the documents that match `filter`, in ascending order by
+    /// primary key.
+    ///
+    /// The filter is evaluated by the storage layer, which avoids sending
+    /// documents that don't match to the client. However, every document in
+    /// the collection is read to evaluate the filter. See [`Filter`] for more
+    /// information.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # use bonsaidb_core::schema::Filter;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// for doc in MyCollection::filter(&Filter::range("rank", 10_u32..)?, &db)? {
+    ///     println!(
+    ///         "Retrieved #{} with deserialized contents: {:?}",
+    ///         doc.header.id, doc.contents
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn filter<C: Connection>(
+        filter: &Filter,
+        connection: &C,
+    ) -> Result<Vec<CollectionDocument<Self>>, Error>
+    where
+        Self: Sized,
+    {
+        connection
+            .filter::<Self>(filter, None)
+            .and_then(|docs| docs.collection_documents())
+    }
+
+    /// Retrieves the documents that match `filter`, in ascending order by
+    /// primary key.
+    ///
+    /// The filter is evaluated by the storage layer, which avoids sending
+    /// documents that don't match to the client. However, every document in
+    /// the collection is read to evaluate the filter. See [`Filter`] for more
+    /// information.
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
     /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # use bonsaidb_core::schema::Filter;
     /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
     /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
-    /// let document = MyCollection::push_async(MyCollection::default(), &db).await?;
-    /// println!(
-    ///     "Inserted {:?} with id {} with revision {}",
-    ///     document.contents, document.header.id, document.header.revision
-    /// );
+    /// for doc in MyCollection::filter_async(&Filter::range("rank", 10_u32..)?, &db).await? {
+    ///     println!(
+    ///         "Retrieved #{} with deserialized contents: {:?}",
+    ///         doc.header.id, doc.contents
+    ///     );
+    /// }
     /// # Ok(())
     /// # })
     /// # }
     /// ```
-    async fn push_async<Cn: AsyncConnection>(
-        contents: Self::Contents,
-        connection: &Cn,
-    ) -> Result<CollectionDocument<Self>, InsertError<Self::Contents>>
+    async fn filter_async<C: AsyncConnection>(
+        filter: &Filter,
+        connection: &C,
+    ) -> Result<Vec<CollectionDocument<Self>>, Error>
     where
-        Self: Sized + 'static,
-        Self::Contents: 'async_trait,
+        Self: Sized,
     {
-        let header = match connection.collection::<Self>().push(&contents).await {
-            Ok(header) => header,
-            Err(error) => return Err(InsertError { contents, error }),
-        };
-        Ok(CollectionDocument { header, contents })
+        connection
+            .filter::<Self>(filter, None)
+            .await
+            .and_then(|docs| docs.collection_documents())
+    }
+
+    /// Retrieves all documents matching the range of `ids`, including
+    /// documents that have been soft-deleted. Each [`MaybeDeleted`] records
+    /// when its document was deleted, if it has been.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// for doc in MyCollection::list_including_deleted(.., &db)? {
+    ///     println!(
+    ///         "Retrieved #{} (deleted at {:?}) with deserialized contents: {:?}",
+    ///         doc.document.header.id, doc.deleted_at, doc.document.contents
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn list_including_deleted<'id, R, PrimaryKey, C>(
+        ids: R,
+        connection: &C,
+    ) -> Result<Vec<MaybeDeleted<CollectionDocument<Self>>>, Error>
+    where
+        R: Into<RangeRef<'id, Self::PrimaryKey, PrimaryKey>> + Send,
+        C: Connection,
+        PrimaryKey: KeyEncoding<Self::PrimaryKey> + PartialEq + 'id + ?Sized,
+        Self::PrimaryKey: Borrow<PrimaryKey> + PartialEq<PrimaryKey>,
+        Self: Sized,
+    {
+        connection
+            .list_including_deleted::<Self, _, _>(ids, connection::Sort::Ascending, None)?
+            .iter()
+            .map(MaybeDeleted::collection_document)
+            .collect()
+    }
+
+    /// Retrieves all documents matching the range of `ids`, including
+    /// documents that have been soft-deleted. Each [`MaybeDeleted`] records
+    /// when its document was deleted, if it has been.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// for doc in MyCollection::list_including_deleted_async(.., &db).await? {
+    ///     println!(
+    ///         "Retrieved #{} (deleted at {:?}) with deserialized contents: {:?}",
+    ///         doc.document.header.id, doc.deleted_at, doc.document.contents
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    async fn list_including_deleted_async<'id, R, PrimaryKey, C>(
+        ids: R,
+        connection: &C,
+    ) -> Result<Vec<MaybeDeleted<CollectionDocument<Self>>>, Error>
+    where
+        R: Into<RangeRef<'id, Self::PrimaryKey, PrimaryKey>> + Send,
+        C: AsyncConnection,
+        PrimaryKey: KeyEncoding<Self::PrimaryKey> + PartialEq + 'id + ?Sized,
+        Self::PrimaryKey: Borrow<PrimaryKey> + PartialEq<PrimaryKey>,
+        Self: Sized,
+    {
+        connection
+            .list_including_deleted::<Self, _, _>(ids, connection::Sort::Ascending, None)
+            .await?
+            .iter()
+            .map(MaybeDeleted::collection_document)
+            .collect()
+    }
+
+    /// Restores the soft-deleted document with `id`, returning the restored
+    /// document.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// let doc = MyCollection::restore(&42, &db)?;
+    /// println!("Restored #{}: {:?}", doc.header.id, doc.contents);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn restore<C, PrimaryKey>(
+        id: &PrimaryKey,
+        connection: &C,
+    ) -> Result<CollectionDocument<Self>, Error>
+    where
+        C: Connection,
+        PrimaryKey: KeyEncoding<Self::PrimaryKey>,
+        Self: Sized,
+    {
+        let header = connection.restore::<Self, _>(id)?;
+        match connection.get::<Self, _>(id)? {
+            Some(doc) => CollectionDocument::try_from(&doc),
+            None => Err(Error::DocumentNotFound(
+                Self::collection_name(),
+                Box::new(header.id),
+            )),
+        }
+    }
+
+    /// Restores the soft-deleted document with `id`, returning the restored
+    /// document.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let doc = MyCollection::restore_async(&42, &db).await?;
+    /// println!("Restored #{}: {:?}", doc.header.id, doc.contents);
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    async fn restore_async<C, PrimaryKey>(
+        id: &PrimaryKey,
+        connection: &C,
+    ) -> Result<CollectionDocument<Self>, Error>
+    where
+        C: AsyncConnection,
+        PrimaryKey: KeyEncoding<Self::PrimaryKey>,
+        Self: Sized,
+    {
+        let header = connection.restore::<Self, _>(id).await?;
+        match connection.get::<Self, _>(id).await? {
+            Some(doc) => CollectionDocument::try_from(&doc),
+            None => Err(Error::DocumentNotFound(
+                Self::collection_name(),
+                Box::new(header.id),
+            )),
+        }
+    }
+
+    /// Pushes this value into the collection, returning the created document.
+    /// This function is useful when `Self != Self::Contents`.
+    ///
+    /// ## Automatic ID Assignment
+    ///
+    /// This function calls [`Self::natural_id()`] to try to retrieve a primary
+    /// key value from `contents`. If an id is returned, the item is inserted
+    /// with that id. If an id is not returned, an id will be automatically
+    /// assigned, if possible, by the storage backend, which uses the [`Key`]
+    /// trait to assign ids.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// let document = MyCollection::push(MyCollection::default(), &db)?;
+    /// println!(
+    ///     "Inserted {:?} with id {} with revision {}",
+    ///     document.contents, document.header.id, document.header.revision
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn push<Cn: Connection>(
+        contents: Self::Contents,
+        connection: &Cn,
+    ) -> Result<CollectionDocument<Self>, InsertError<Self::Contents>>
+    where
+        Self: Sized + 'static,
+    {
+        let header = match connection.collection::<Self>().push(&contents) {
+            Ok(header) => header,
+            Err(error) => return Err(InsertError { contents, error }),
+        };
+        Ok(CollectionDocument { header, contents })
+    }
+
+    /// Pushes this value into the collection, returning the created document.
+    /// This function is useful when `Self != Self::Contents`.
+    ///
+    /// ## Automatic ID Assignment
+    ///
+    /// This function calls [`Self::natural_id()`] to try to retrieve a primary
+    /// key value from `contents`. If an id is returned, the item is inserted
+    /// with that id. If an id is not returned, an id will be automatically
+    /// assigned, if possible, by the storage backend, which uses the [`Key`]
+    /// trait to assign ids.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let document = MyCollection::push_async(MyCollection::default(), &db).await?;
+    /// println!(
+    ///     "Inserted {:?} with id {} with revision {}",
+    ///     document.contents, document.header.id, document.header.revision
+    /// );
+    /// # Ok(())
+    /// # })
+    /// # }
+    /// ```
+    async fn push_async<Cn: AsyncConnection>(
+        contents: Self::Contents,
+        connection: &Cn,
+    ) -> Result<CollectionDocument<Self>, InsertError<Self::Contents>>
+    where
+        Self: Sized + 'static,
+        Self::Contents: 'async_trait,
+    {
+        let header = match connection.collection::<Self>().push(&contents).await {
+            Ok(header) => header,
+            Err(error) => return Err(InsertError { contents, error }),
+        };
+        Ok(CollectionDocument { header, contents })
+    }
+
+    /// Pushes all `contents` in a single transaction. If successful, all
+    /// collection documents will be returned. If an error occurs during this
+    /// operation, no documents will be pushed.
+    ///
+    /// ## Automatic ID Assignment
+    ///
+    /// This function calls [`Self::natural_id()`] to try to retrieve a primary
+    /// key value from each instance of `contents`. If an id is returned, the
+    /// item is inserted with that id. If an id is not returned, an id will be
+    /// automatically assigned, if possible, by the storage backend, which uses
+    /// the [`Key`] trait to assign ids.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// let documents = MyCollection::push_all(
+    ///     [
+    ///         MyCollection::default(),
+    ///         MyCollection::default(),
+    ///         MyCollection::default(),
+    ///     ],
+    ///     &db,
+    /// )?;
+    /// for document in documents {
+    ///     println!(
+    ///         "Inserted {:?} with id {} with revision {}",
+    ///         document.contents, document.header.id, document.header.revision
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn push_all<Contents: IntoIterator<Item = Self::Contents>, Cn: Connection>(
+        contents: Contents,
+        connection: &Cn,
+    ) -> Result<Vec<CollectionDocument<Self>>, Error>
+    where
+        Self: Sized + 'static,
+        Self::PrimaryKey: Default,
+    {
+        let mut tx = Transaction::new();
+        let contents = contents.into_iter();
+        let mut results = Vec::with_capacity(contents.size_hint().0);
+        for contents in contents {
+            tx.push(Operation::push_serialized::<Self>(&contents)?);
+            results.push(CollectionDocument {
+                header: CollectionHeader {
+                    id: <<Self as Collection>::PrimaryKey as Default>::default(),
+                    revision: Revision {
+                        id: 0,
+                        sha256: [0; 32],
+                    },
+                },
+                contents,
+            });
+        }
+        for (result, document) in tx.apply(connection)?.into_iter().zip(&mut results) {
+            match result {
+                OperationResult::DocumentUpdated { header, .. } => {
+                    document.header = CollectionHeader::try_from(header)?;
+                }
+                _ => unreachable!("invalid result from transaction"),
+            }
+        }
+        Ok(results)
+    }
+
+    /// Pushes all `contents` in a single transaction. If successful, all
+    /// collection documents will be returned. If an error occurs during this
+    /// operation, no documents will be pushed.
+    ///
+    /// ## Automatic ID Assignment
+    ///
+    /// This function calls [`Self::natural_id()`] to try to retrieve a primary
+    /// key value from each instance of `contents`. If an id is returned, the
+    /// item is inserted with that id. If an id is not returned, an id will be
+    /// automatically assigned, if possible, by the storage backend, which uses
+    /// the [`Key`] trait to assign ids.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// let documents = MyCollection::push_all_async(
+    ///     [
+    ///         MyCollection::default(),
+    ///         MyCollection::default(),
+    ///         MyCollection::default(),
+    ///     ],
+    ///     &db,
+    /// )
+    /// .await?;
+    /// for document in documents {
+    ///     println!(
+    ///         "Inserted {:?} with id {} with revision {}",
+    ///         document.contents, document.header.id, document.header.revision
+    ///     );
+    /// }
+    /// # Ok(())
+    /// # })}
+    /// ```
+    async fn push_all_async<
+        Contents: IntoIterator<Item = Self::Contents> + Send,
+        Cn: AsyncConnection,
+    >(
+        contents: Contents,
+        connection: &Cn,
+    ) -> Result<Vec<CollectionDocument<Self>>, Error>
+    where
+        Self: Sized + 'static,
+        Self::PrimaryKey: Default,
+        Contents::IntoIter: Send,
+    {
+        let mut tx = Transaction::new();
+        let contents = contents.into_iter();
+        let mut results = Vec::with_capacity(contents.size_hint().0);
+        for contents in contents {
+            tx.push(Operation::push_serialized::<Self>(&contents)?);
+            results.push(CollectionDocument {
+                header: CollectionHeader {
+                    id: <<Self as Collection>::PrimaryKey as Default>::default(),
+                    revision: Revision {
+                        id: 0,
+                        sha256: [0; 32],
+                    },
+                },
+                contents,
+            });
+        }
+        for (result, document) in tx
+            .apply_async(connection)
+            .await?
+            .into_iter()
+            .zip(&mut results)
+        {
+            match result {
+                OperationResult::DocumentUpdated { header, .. } => {
+                    document.header = CollectionHeader::try_from(header)?;
+                }
+                _ => unreachable!("invalid result from transaction"),
+            }
+        }
+        Ok(results)
+    }
+
+    /// Inserts all `contents` in a single transaction. If successful, the
+    /// inserted documents are returned in the same order as `contents`.
+    ///
+    /// If any document can't be inserted, no documents are inserted and an
+    /// [`InsertManyError`] is returned. The error contains the original
+    /// contents and, when it can be determined, the index of the document
+    /// that caused the failure. To insert the documents that can be inserted
+    /// and collect the errors for the rest, use
+    /// [`Self::insert_many_allowing_failures()`].
+    ///
+    /// ## Automatic ID Assignment
+    ///
+    /// This function calls [`Self::natural_id()`] to try to retrieve a primary
+    /// key value from each instance of `contents`. If an id is returned, the
+    /// item is inserted with that id. If an id is not returned, an id will be
+    /// automatically assigned, if possible, by the storage backend, which uses
+    /// the [`Key`] trait to assign ids.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::Connection;
+    /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
+    /// match MyCollection::insert_many([MyCollection::default(), MyCollection::default()], &db) {
+    ///     Ok(documents) => {
+    ///         for document in documents {
+    ///             println!("Inserted {}", document.header.id);
+    ///         }
+    ///     }
+    ///     Err(err) => {
+    ///         println!("Error inserting document {:?}: {}", err.index, err.error);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn insert_many<Contents: IntoIterator<Item = Self::Contents>, Cn: Connection>(
+        contents: Contents,
+        connection: &Cn,
+    ) -> Result<Vec<CollectionDocument<Self>>, InsertManyError<Self::Contents>>
+    where
+        Self: Sized + 'static,
+    {
+        let contents = contents.into_iter().collect::<Vec<_>>();
+        let (tx, pending) = match prepare_insert_many::<Self>(&contents) {
+            Ok(prepared) => prepared,
+            Err((index, error)) => {
+                return Err(InsertManyError {
+                    index: Some(index),
+                    contents,
+                    error,
+                })
+            }
+        };
+        match tx.apply(connection).and_then(inserted_headers::<Self>) {
+            Ok(headers) => Ok(headers
+                .into_iter()
+                .zip(contents)
+                .map(|(header, contents)| CollectionDocument { header, contents })
+                .collect()),
+            Err(error) => Err(InsertManyError {
+                index: failed_insert_index(&pending, &error),
+                contents,
+                error,
+            }),
+        }
+    }
+
+    /// Inserts all `contents` in a single transaction. If successful, the
+    /// inserted documents are returned in the same order as `contents`.
+    ///
+    /// If any document can't be inserted, no documents are inserted and an
+    /// [`InsertManyError`] is returned. The error contains the original
+    /// contents and, when it can be determined, the index of the document
+    /// that caused the failure. To insert the documents that can be inserted
+    /// and collect the errors for the rest, use
+    /// [`Self::insert_many_allowing_failures_async()`].
+    ///
+    /// ## Automatic ID Assignment
+    ///
+    /// This function calls [`Self::natural_id()`] to try to retrieve a primary
+    /// key value from each instance of `contents`. If an id is returned, the
+    /// item is inserted with that id. If an id is not returned, an id will be
+    /// automatically assigned, if possible, by the storage backend, which uses
+    /// the [`Key`] trait to assign ids.
+    ///
+    /// ```rust
+    /// # bonsaidb_core::__doctest_prelude!();
+    /// # use bonsaidb_core::connection::AsyncConnection;
+    /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
+    /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
+    /// match MyCollection::insert_many_async(
+    ///     [MyCollection::default(), MyCollection::default()],
+    ///     &db,
+    /// )
+    /// .await
+    /// {
+    ///     Ok(documents) => {
+    ///         for document in documents {
+    ///             println!("Inserted {}", document.header.id);
+    ///         }
+    ///     }
+    ///     Err(err) => {
+    ///         println!("Error inserting document {:?}: {}", err.index, err.error);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # })}
+    /// ```
+    async fn insert_many_async<
+        Contents: IntoIterator<Item = Self::Contents> + Send,
+        Cn: AsyncConnection,
+    >(
+        contents: Contents,
+        connection: &Cn,
+    ) -> Result<Vec<CollectionDocument<Self>>, InsertManyError<Self::Contents>>
+    where
+        Self: Sized + 'static,
+        Contents::IntoIter: Send,
+    {
+        let contents = contents.into_iter().collect::<Vec<_>>();
+        let (tx, pending) = match prepare_insert_many::<Self>(&contents) {
+            Ok(prepared) => prepared,
+            Err((index, error)) => {
+                return Err(InsertManyError {
+                    index: Some(index),
+                    contents,
+                    error,
+                })
+            }
+        };
+        match tx
+            .apply_async(connection)
+            .await
+            .and_then(inserted_headers::<Self>)
+        {
+            Ok(headers) => Ok(headers
+                .into_iter()
+                .zip(contents)
+                .map(|(header, contents)| CollectionDocument { header, contents })
+                .collect()),
+            Err(error) => Err(InsertManyError {
+                index: failed_insert_index(&pending, &error),
+                contents,
+                error,
+            }),
+        }
     }
 
-    /// Pushes all `contents` in a single transaction. If successful, all
-    /// collection documents will be returned. If an error occurs during this
-    /// operation, no documents will be pushed.
-    ///
-    /// ## Automatic ID Assignment
+    /// Inserts as many of `contents` as possible, returning the result of
+    /// each insert in the same order as `contents`.
     ///
-    /// This function calls [`Self::natural_id()`] to try to retrieve a primary
-    /// key value from each instance of `contents`. If an id is returned, the
-    /// item is inserted with that id. If an id is not returned, an id will be
-    /// automatically assigned, if possible, by the storage backend, which uses
-    /// the [`Key`] trait to assign ids.
+    /// The documents are inserted in a single transaction. When a document
+    /// causes the transaction to fail, its error is recorded and the
+    /// transaction is retried without it. If the document that caused a
+    /// failure can't be identified, the remaining documents are inserted one
+    /// at a time.
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
     /// # use bonsaidb_core::connection::Connection;
     /// # fn test_fn<C: Connection>(db: C) -> Result<(), Error> {
-    /// let documents = MyCollection::push_all(
-    ///     [
-    ///         MyCollection::default(),
-    ///         MyCollection::default(),
-    ///         MyCollection::default(),
-    ///     ],
+    /// let results = MyCollection::insert_many_allowing_failures(
+    ///     [MyCollection::default(), MyCollection::default()],
     ///     &db,
-    /// )?;
-    /// for document in documents {
-    ///     println!(
-    ///         "Inserted {:?} with id {} with revision {}",
-    ///         document.contents, document.header.id, document.header.revision
-    ///     );
+    /// );
+    /// for (index, result) in results.into_iter().enumerate() {
+    ///     match result {
+    ///         Ok(document) => println!("Inserted {index} as {}", document.header.id),
+    ///         Err(err) => println!("Error inserting {index}: {}", err.error),
+    ///     }
     /// }
     /// # Ok(())
     /// # }
     /// ```
-    fn push_all<Contents: IntoIterator<Item = Self::Contents>, Cn: Connection>(
+    fn insert_many_allowing_failures<
+        Contents: IntoIterator<Item = Self::Contents>,
+        Cn: Connection,
+    >(
         contents: Contents,
         connection: &Cn,
-    ) -> Result<Vec<CollectionDocument<Self>>, Error>
+    ) -> Vec<Result<CollectionDocument<Self>, InsertError<Self::Contents>>>
     where
         Self: Sized + 'static,
-        Self::PrimaryKey: Default,
     {
-        let mut tx = Transaction::new();
-        let contents = contents.into_iter();
-        let mut results = Vec::with_capacity(contents.size_hint().0);
-        for contents in contents {
-            tx.push(Operation::push_serialized::<Self>(&contents)?);
-            results.push(CollectionDocument {
-                header: CollectionHeader {
-                    id: <<Self as Collection>::PrimaryKey as Default>::default(),
-                    revision: Revision {
-                        id: 0,
-                        sha256: [0; 32],
-                    },
-                },
-                contents,
-            });
-        }
-        for (result, document) in tx.apply(connection)?.into_iter().zip(&mut results) {
-            match result {
-                OperationResult::DocumentUpdated { header, .. } => {
-                    document.header = CollectionHeader::try_from(header)?;
+        let mut pending_contents = contents.into_iter().enumerate().collect::<Vec<_>>();
+        let mut results = Vec::new();
+        results.resize_with(pending_contents.len(), || None);
+        while !pending_contents.is_empty() {
+            let (tx, pending) = match prepare_insert_many::<Self>(
+                pending_contents.iter().map(|(_, contents)| contents),
+            ) {
+                Ok(prepared) => prepared,
+                Err((position, error)) => {
+                    let (index, contents) = pending_contents.remove(position);
+                    results[index] = Some(Err(InsertError { contents, error }));
+                    continue;
+                }
+            };
+
+            match tx.apply(connection) {
+                Ok(tx_results) => {
+                    for ((index, contents), result) in pending_contents.drain(..).zip(tx_results) {
+                        results[index] = Some(inserted_document(result, contents));
+                    }
+                }
+                Err(error) => {
+                    if let Some(position) = failed_insert_index(&pending, &error) {
+                        let (index, contents) = pending_contents.remove(position);
+                        results[index] = Some(Err(InsertError { contents, error }));
+                    } else {
+                        for (index, contents) in pending_contents.drain(..) {
+                            results[index] = Some(Self::push(contents, connection));
+                        }
+                    }
                 }
-                _ => unreachable!("invalid result from transaction"),
             }
         }
-        Ok(results)
+
+        results
+            .into_iter()
+            .map(|result| result.expect("every document is inserted or fails"))
+            .collect()
     }
 
-    /// Pushes all `contents` in a single transaction. If successful, all
-    /// collection documents will be returned. If an error occurs during this
-    /// operation, no documents will be pushed.
-    ///
-    /// ## Automatic ID Assignment
+    /// Inserts as many of `contents` as possible, returning the result of
+    /// each insert in the same order as `contents`.
     ///
-    /// This function calls [`Self::natural_id()`] to try to retrieve a primary
-    /// key value from each instance of `contents`. If an id is returned, the
-    /// item is inserted with that id. If an id is not returned, an id will be
-    /// automatically assigned, if possible, by the storage backend, which uses
-    /// the [`Key`] trait to assign ids.
+    /// The documents are inserted in a single transaction. When a document
+    /// causes the transaction to fail, its error is recorded and the
+    /// transaction is retried without it. If the document that caused a
+    /// failure can't be identified, the remaining documents are inserted one
+    /// at a time.
     ///
     /// ```rust
     /// # bonsaidb_core::__doctest_prelude!();
     /// # use bonsaidb_core::connection::AsyncConnection;
     /// # fn test_fn<C: AsyncConnection>(db: C) -> Result<(), Error> {
     /// # tokio::runtime::Runtime::new().unwrap().block_on(async {
-    /// let documents = MyCollection::push_all_async(
-    ///     [
-    ///         MyCollection::default(),
-    ///         MyCollection::default(),
-    ///         MyCollection::default(),
-    ///     ],
+    /// let results = MyCollection::insert_many_allowing_failures_async(
+    ///     [MyCollection::default(), MyCollection::default()],
     ///     &db,
     /// )
-    /// .await?;
-    /// for document in documents {
-    ///     println!(
-    ///         "Inserted {:?} with id {} with revision {}",
-    ///         document.contents, document.header.id, document.header.revision
-    ///     );
+    /// .await;
+    /// for (index, result) in results.into_iter().enumerate() {
+    ///     match result {
+    ///         Ok(document) => println!("Inserted {index} as {}", document.header.id),
+    ///         Err(err) => println!("Error inserting {index}: {}", err.error),
+    ///     }
     /// }
     /// # Ok(())
     /// # })}
     /// ```
-    async fn push_all_async<
+    async fn insert_many_allowing_failures_async<
         Contents: IntoIterator<Item = Self::Contents> + Send,
         Cn: AsyncConnection,
     >(
         contents: Contents,
         connection: &Cn,
-    ) -> Result<Vec<CollectionDocument<Self>>, Error>
+    ) -> Vec<Result<CollectionDocument<Self>, InsertError<Self::Contents>>>
     where
         Self: Sized + 'static,
-        Self::PrimaryKey: Default,
         Contents::IntoIter: Send,
     {
-        let mut tx = Transaction::new();
-        let contents = contents.into_iter();
-        let mut results = Vec::with_capacity(contents.size_hint().0);
-        for contents in contents {
-            tx.push(Operation::push_serialized::<Self>(&contents)?);
-            results.push(CollectionDocument {
-                header: CollectionHeader {
-                    id: <<Self as Collection>::PrimaryKey as Default>::default(),
-                    revision: Revision {
-                        id: 0,
-                        sha256: [0; 32],
-                    },
-                },
-                contents,
-            });
-        }
-        for (result, document) in tx
-            .apply_async(connection)
-            .await?
-            .into_iter()
-            .zip(&mut results)
-        {
-            match result {
-                OperationResult::DocumentUpdated { header, .. } => {
-                    document.header = CollectionHeader::try_from(header)?;
+        let mut pending_contents = contents.into_iter().enumerate().collect::<Vec<_>>();
+        let mut results = Vec::new();
+        results.resize_with(pending_contents.len(), || None);
+        while !pending_contents.is_empty() {
+            let (tx, pending) = match prepare_insert_many::<Self>(
+                pending_contents.iter().map(|(_, contents)| contents),
+            ) {
+                Ok(prepared) => prepared,
+                Err((position, error)) => {
+                    let (index, contents) = pending_contents.remove(position);
+                    results[index] = Some(Err(InsertError { contents, error }));
+                    continue;
+                }
+            };
+
+            match tx.apply_async(connection).await {
+                Ok(tx_results) => {
+                    for ((index, contents), result) in pending_contents.drain(..).zip(tx_results) {
+                        results[index] = Some(inserted_document(result, contents));
+                    }
+                }
+                Err(error) => {
+                    if let Some(position) = failed_insert_index(&pending, &error) {
+                        let (index, contents) = pending_contents.remove(position);
+                        results[index] = Some(Err(InsertError { contents, error }));
+                    } else {
+                        for (index, contents) in pending_contents.drain(..) {
+                            results[index] = Some(Self::push_async(contents, connection).await);
+                        }
+                    }
                 }
-                _ => unreachable!("invalid result from transaction"),
             }
         }
-        Ok(results)
+
+        results
+            .into_iter()
+            .map(|result| result.expect("every document is inserted or fails"))
+            .collect()
     }
 
     /// Pushes this value into the collection, returning the created document.
@@ -1178,6 +2071,65 @@ pub trait SerializedCollection: Collection {
         Ok(CollectionDocument { header, contents })
     }
 
+    /// Applies `patch` to the document with `id`, returning the updated
+    /// header. The patch is applied atomically by the storage layer using
+    /// [`Collection::apply_patch()`], without retrieving the document first.
+    ///
+    /// To only apply the patch if the document hasn't changed since it was
+    /// retrieved, use [`CollectionDocument::patch()`].
+    fn patch<PrimaryKey, P, Cn>(
+        id: &PrimaryKey,
+        patch: &P,
+        connection: &Cn,
+    ) -> Result<CollectionHeader<Self::PrimaryKey>, Error>
+    where
+        PrimaryKey: KeyEncoding<Self::PrimaryKey>,
+        P: DocumentPatch<Self>,
+        Cn: Connection,
+        Self: Sized + 'static,
+    {
+        let results =
+            Transaction::from(Operation::patch_serialized::<Self, P, _>(id, None, patch)?)
+                .apply(connection)?;
+        if let Some(OperationResult::DocumentUpdated { header, .. }) = results.into_iter().next() {
+            CollectionHeader::try_from(header)
+        } else {
+            unreachable!(
+                "apply_transaction on a single patch should yield a single DocumentUpdated entry"
+            )
+        }
+    }
+
+    /// Applies `patch` to the document with `id`, returning the updated
+    /// header. The patch is applied atomically by the storage layer using
+    /// [`Collection::apply_patch()`], without retrieving the document first.
+    ///
+    /// To only apply the patch if the document hasn't changed since it was
+    /// retrieved, use [`CollectionDocument::patch_async()`].
+    async fn patch_async<PrimaryKey, P, Cn>(
+        id: &PrimaryKey,
+        patch: &P,
+        connection: &Cn,
+    ) -> Result<CollectionHeader<Self::PrimaryKey>, Error>
+    where
+        PrimaryKey: KeyEncoding<Self::PrimaryKey>,
+        P: DocumentPatch<Self>,
+        Cn: AsyncConnection,
+        Self: Sized + 'static,
+    {
+        let results =
+            Transaction::from(Operation::patch_serialized::<Self, P, _>(id, None, patch)?)
+                .apply_async(connection)
+                .await?;
+        if let Some(OperationResult::DocumentUpdated { header, .. }) = results.into_iter().next() {
+            CollectionHeader::try_from(header)
+        } else {
+            unreachable!(
+                "apply_transaction on a single patch should yield a single DocumentUpdated entry"
+            )
+        }
+    }
+
     /// Overwrites this value into the collection with the given `id`, returning
     /// the created or updated document.
     ///
@@ -1262,6 +2214,8 @@ pub trait DefaultSerialization: Collection {
     /// Returns the natural identifier of `contents`. This is called when
     /// pushing values into a collection, before attempting to automatically
     /// assign a unique id.
+    ///
+    /// See [`SerializedCollection::natural_id()`] for more information.
     fn natural_id(&self) -> Option<Self::PrimaryKey> {
         None
     }
@@ -1283,6 +2237,19 @@ where
     }
 }
 
+/// A document was rejected by [`Collection::validate()`].
+#[derive(thiserror::Error, Debug, Clone, Serialize, Deserialize)]
+#[error("{0}")]
+pub struct ValidationError(pub String);
+
+impl ValidationError {
+    /// Returns a new error with `message` describing why the document is
+    /// invalid.
+    pub fn new(message: impl std::fmt::Display) -> Self {
+        Self(message.to_string())
+    }
+}
+
 /// An error from inserting a [`CollectionDocument`].
 #[derive(thiserror::Error, Debug)]
 #[error("{error}")]
@@ -1293,6 +2260,103 @@ pub struct InsertError<T> {
     pub error: Error,
 }
 
+/// An error from inserting multiple documents using
+/// [`SerializedCollection::insert_many()`]. No documents were inserted.
+#[derive(thiserror::Error, Debug)]
+#[error("{error}")]
+pub struct InsertManyError<T> {
+    /// The index of the document that caused the error, if it could be
+    /// determined.
+    pub index: Option<usize>,
+    /// The original values being inserted, in the order they were provided.
+    pub contents: Vec<T>,
+    /// The error that occurred while inserting.
+    pub error: Error,
+}
+
+/// The identifying information of a document being inserted by
+/// `insert_many()`, used to find which document caused a transaction to fail.
+struct PendingInsert {
+    id: Option<DocumentId>,
+    revision: Revision,
+}
+
+fn prepare_insert_many<'a, C: SerializedCollection>(
+    contents: impl IntoIterator<Item = &'a C::Contents>,
+) -> Result<(Transaction, Vec<PendingInsert>), (usize, Error)>
+where
+    C::Contents: 'a,
+{
+    let mut tx = Transaction::new();
+    let mut pending = Vec::new();
+    for (index, contents) in contents.into_iter().enumerate() {
+        let prepared = C::natural_id(contents)
+            .as_ref()
+            .map(DocumentId::new)
+            .transpose()
+            .and_then(|id| Ok((id, C::serialize(contents)?)));
+        let (id, serialized) = prepared.map_err(|error| (index, error))?;
+        pending.push(PendingInsert {
+            id: id.clone(),
+            revision: Revision::new(&serialized),
+        });
+        tx.push(Operation::insert(C::collection_name(), id, serialized));
+    }
+    Ok((tx, pending))
+}
+
+/// Returns the index of the document in `pending` that caused `error`. If no
+/// document, or more than one document, matches the error, `None` is
+/// returned.
+fn failed_insert_index(pending: &[PendingInsert], error: &Error) -> Option<usize> {
+    let mut candidates = pending
+        .iter()
+        .enumerate()
+        .filter(|(_, insert)| match error {
+            Error::UniqueKeyViolation {
+                conflicting_document,
+                ..
+            } => {
+                insert.revision == conflicting_document.revision
+                    && match &insert.id {
+                        Some(id) => id == &conflicting_document.id,
+                        None => true,
+                    }
+            }
+            Error::DocumentConflict(_, header) => insert.id.as_ref() == Some(&header.id),
+            _ => false,
+        })
+        .map(|(index, _)| index);
+    let index = candidates.next()?;
+    candidates.next().is_none().then_some(index)
+}
+
+fn inserted_headers<C: Collection>(
+    results: Vec<OperationResult>,
+) -> Result<Vec<CollectionHeader<C::PrimaryKey>>, Error> {
+    results
+        .into_iter()
+        .map(|result| match result {
+            OperationResult::DocumentUpdated { header, .. } => CollectionHeader::try_from(header),
+            _ => unreachable!("invalid result from transaction"),
+        })
+        .collect()
+}
+
+fn inserted_document<C: SerializedCollection>(
+    result: OperationResult,
+    contents: C::Contents,
+) -> Result<CollectionDocument<C>, InsertError<C::Contents>> {
+    match result {
+        OperationResult::DocumentUpdated { header, .. } => match CollectionHeader::try_from(header)
+        {
+            Ok(header) => Ok(CollectionDocument { header, contents }),
+            Err(error) => Err(InsertError { contents, error }),
+        },
+        _ => unreachable!("invalid result from transaction"),
+    }
+}
+
 /// A collection with a unique name column.
 ///
 /// ## Finding a document by unique name
@@ -2248,3 +3312,214 @@ where
         Poll::Ready(result.and_then(|docs| docs.collection_documents()))
     }
 }
+
+/// Tracks the progress of a [`Scan`] or [`AsyncScan`] through a collection.
+#[derive(Debug, Clone)]
+struct ScanPosition {
+    batch_size: u32,
+    /// The start of the next batch, or `None` if all documents have been read.
+    next_start: Option<connection::Bound<DocumentId>>,
+}
+
+impl ScanPosition {
+    const fn new() -> Self {
+        Self {
+            batch_size: DEFAULT_SCAN_BATCH_SIZE,
+            next_start: Some(connection::Bound::Unbounded),
+        }
+    }
+
+    /// Returns the range of ids to request the next batch from, if more
+    /// documents may be available.
+    fn next_range(&self) -> Option<connection::Range<DocumentId>> {
+        self.next_start.clone().map(|start| connection::Range {
+            start,
+            end: connection::Bound::Unbounded,
+        })
+    }
+
+    /// Advances past `batch`, which was read from [`Self::next_range()`].
+    fn advance(&mut self, batch: &[OwnedDocument]) {
+        self.next_start = match batch.last() {
+            Some(last) if batch.len() >= self.batch_size as usize => {
+                Some(connection::Bound::Excluded(last.header.id.clone()))
+            }
+            _ => None,
+        };
+    }
+}
+
+/// An iterator over all documents in a collection, returned from
+/// [`SerializedCollection::scan()`].
+///
+/// Each batch is read independently. Documents inserted or deleted while the
+/// iterator is being consumed may or may not be returned, depending on whether
+/// their batch has already been read.
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Scan<'a, Cn, Cl> {
+    connection: &'a Cn,
+    position: ScanPosition,
+    batch: std::vec::IntoIter<OwnedDocument>,
+    _collection: PhantomData<Cl>,
+}
+
+impl<'a, Cn, Cl> Scan<'a, Cn, Cl> {
+    fn new(connection: &'a Cn) -> Self {
+        Self {
+            connection,
+            position: ScanPosition::new(),
+            batch: Vec::new().into_iter(),
+            _collection: PhantomData,
+        }
+    }
+
+    /// Sets the maximum number of documents read from the collection at once.
+    ///
+    /// If not specified,
+    /// [`DEFAULT_SCAN_BATCH_SIZE`](crate::limits::DEFAULT_SCAN_BATCH_SIZE) is
+    /// used.
+    pub fn batch_size(mut self, batch_size: u32) -> Self {
+        self.position.batch_size = batch_size.max(1);
+        self
+    }
+}
+
+impl<'a, Cn, Cl> Iterator for Scan<'a, Cn, Cl>
+where
+    Cn: Connection,
+    Cl: SerializedCollection,
+{
+    type Item = Result<CollectionDocument<Cl>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(document) = self.batch.next() {
+                return Some(CollectionDocument::try_from(&document));
+            }
+
+            let range = self.position.next_range()?;
+            match self.connection.list_from_collection(
+                range,
+                connection::Sort::Ascending,
+                Some(self.position.batch_size),
+                &Cl::collection_name(),
+            ) {
+                Ok(batch) => {
+                    self.position.advance(&batch);
+                    self.batch = batch.into_iter();
+                }
+                Err(err) => {
+                    self.position.next_start = None;
+                    return Some(Err(err));
+                }
+            }
+        }
+    }
+}
+
+/// A stream of all documents in a collection, returned from
+/// [`SerializedCollection::scan_async()`].
+///
+/// Each batch is read independently. Documents inserted or deleted while the
+/// stream is being consumed may or may not be returned, depending on whether
+/// their batch has already been read.
+#[must_use = "streams do nothing unless polled"]
+pub struct AsyncScan<'a, Cn, Cl>
+where
+    Cl: SerializedCollection,
+{
+    state: AsyncScanState<'a, Cn, Cl>,
+}
+
+enum AsyncScanState<'a, Cn, Cl>
+where
+    Cl: SerializedCollection,
+{
+    Pending(Option<(&'a Cn, ScanPosition)>),
+    Streaming(BoxStream<'a, Result<CollectionDocument<Cl>, Error>>),
+}
+
+impl<'a, Cn, Cl> AsyncScan<'a, Cn, Cl>
+where
+    Cl: SerializedCollection,
+{
+    const fn new(connection: &'a Cn) -> Self {
+        Self {
+            state: AsyncScanState::Pending(Some((connection, ScanPosition::new()))),
+        }
+    }
+
+    /// Sets the maximum number of documents read from the collection at once.
+    ///
+    /// If not specified,
+    /// [`DEFAULT_SCAN_BATCH_SIZE`](crate::limits::DEFAULT_SCAN_BATCH_SIZE) is
+    /// used.
+    pub fn batch_size(mut self, batch_size: u32) -> Self {
+        if let AsyncScanState::Pending(Some((_, position))) = &mut self.state {
+            position.batch_size = batch_size.max(1);
+        } else {
+            unreachable!("Attempted to use after streaming began.")
+        }
+        self
+    }
+}
+
+impl<'a, Cn, Cl> Stream for AsyncScan<'a, Cn, Cl>
+where
+    Cn: AsyncConnection,
+    Cl: SerializedCollection + 'a,
+{
+    type Item = Result<CollectionDocument<Cl>, Error>;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            match &mut self.state {
+                AsyncScanState::Pending(pending) => {
+                    let (connection, position) = pending
+                        .take()
+                        .expect("pending state should always have a connection");
+                    self.state =
+                        AsyncScanState::Streaming(scan_batches::<Cn, Cl>(connection, position));
+                }
+                AsyncScanState::Streaming(stream) => return stream.poll_next_unpin(cx),
+            }
+        }
+    }
+}
+
+fn scan_batches<'a, Cn, Cl>(
+    connection: &'a Cn,
+    position: ScanPosition,
+) -> BoxStream<'a, Result<CollectionDocument<Cl>, Error>>
+where
+    Cn: AsyncConnection,
+    Cl: SerializedCollection + 'a,
+{
+    futures::stream::try_unfold(position, move |mut position| async move {
+        let Some(range) = position.next_range() else {
+            return Ok(None);
+        };
+        let batch = connection
+            .list_from_collection(
+                range,
+                connection::Sort::Ascending,
+                Some(position.batch_size),
+                &Cl::collection_name(),
+            )
+            .await?;
+        position.advance(&batch);
+        Ok(Some((batch, position)))
+    })
+    .map_ok(|batch| {
+        futures::stream::iter(
+            batch
+                .into_iter()
+                .map(|document| CollectionDocument::<Cl>::try_from(&document)),
+        )
+    })
+    .try_flatten()
+    .boxed()
+}
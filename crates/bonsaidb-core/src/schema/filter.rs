@@ -0,0 +1,308 @@
+use std::cmp::Ordering;
+
+use arc_bytes::serde::Bytes;
+use pot::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::connection::{Bound, Range};
+use crate::Error;
+
+/// A predicate on the fields of a document's contents, evaluated by the
+/// storage layer without defining a [`View`](crate::schema::View).
+///
+/// Filters are built on the client and serialized with the request, which
+/// means only the documents that match are returned from the server. Fields
+/// are referenced by name, and fields of nested structures can be referenced
+/// by joining the names with a period, such as `"address.city"`.
+///
+/// ```rust
+/// use bonsaidb_core::schema::Filter;
+///
+/// # fn test_fn() -> Result<(), bonsaidb_core::Error> {
+/// let filter = Filter::equal("category", &"tools")?.and(Filter::range("price", 10_u32..50)?);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// ## Performance
+///
+/// Evaluating a filter requires reading and deserializing every document in
+/// the collection, which makes its cost proportional to the size of the
+/// collection rather than the number of matching documents. Filters are
+/// intended for exploratory or infrequent queries where maintaining an index
+/// isn't worthwhile. Queries that are executed regularly should use a
+/// [`View`](crate::schema::View), whose index is updated incrementally as
+/// documents change.
+///
+/// Filters can only be evaluated against documents serialized using
+/// [`pot`], which is the default format for collections. For predicates that
+/// can't be expressed as a filter, the documents returned from
+/// [`SerializedCollection::scan()`](crate::schema::SerializedCollection::scan)
+/// can be filtered using [`Iterator::filter()`], at the expense of sending
+/// every document to the client.
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Filter {
+    /// Matches documents whose `field` is equal to `value`.
+    Equal {
+        /// The name of the field.
+        field: String,
+        /// The value, serialized using [`pot`].
+        value: Bytes,
+    },
+    /// Matches documents whose `field` is contained in `range`.
+    Range {
+        /// The name of the field.
+        field: String,
+        /// The range, whose bounds are serialized using [`pot`].
+        range: Range<Bytes>,
+    },
+    /// Matches documents that match all of the contained filters.
+    All(Vec<Filter>),
+    /// Matches documents that match any of the contained filters.
+    Any(Vec<Filter>),
+    /// Matches documents that don't match the contained filter.
+    Not(Box<Filter>),
+}
+
+impl Filter {
+    /// Returns a filter matching documents whose `field` is equal to `value`.
+    pub fn equal<T: Serialize + ?Sized>(
+        field: impl Into<String>,
+        value: &T,
+    ) -> Result<Self, Error> {
+        Ok(Self::Equal {
+            field: field.into(),
+            value: Bytes::from(pot::to_vec(&value)?),
+        })
+    }
+
+    /// Returns a filter matching documents whose `field` is contained in
+    /// `range`.
+    ///
+    /// Integers and floating point numbers can be compared with each other.
+    /// Strings and byte arrays are compared lexicographically. Values of
+    /// other types never match a range.
+    pub fn range<T: Serialize>(
+        field: impl Into<String>,
+        range: impl Into<Range<T>>,
+    ) -> Result<Self, Error> {
+        Ok(Self::Range {
+            field: field.into(),
+            range: range
+                .into()
+                .map_result(|bound| pot::to_vec(&bound).map(Bytes::from))?,
+        })
+    }
+
+    /// Returns a filter matching documents that match both this filter and
+    /// `other`.
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        match self {
+            Self::All(mut filters) => {
+                filters.push(other);
+                Self::All(filters)
+            }
+            filter => Self::All(vec![filter, other]),
+        }
+    }
+
+    /// Returns a filter matching documents that match either this filter or
+    /// `other`.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        match self {
+            Self::Any(mut filters) => {
+                filters.push(other);
+                Self::Any(filters)
+            }
+            filter => Self::Any(vec![filter, other]),
+        }
+    }
+
+    /// Deserializes the values contained in this filter, returning a
+    /// [`CompiledFilter`] that can be evaluated against many documents.
+    pub fn compile(&self) -> Result<CompiledFilter<'_>, Error> {
+        Ok(match self {
+            Self::Equal { field, value } => CompiledFilter::Equal {
+                field,
+                value: pot::from_slice(value)?,
+            },
+            Self::Range { field, range } => CompiledFilter::Range {
+                field,
+                range: range
+                    .map_ref(|bound| &bound[..])
+                    .map_result(pot::from_slice)?,
+            },
+            Self::All(filters) => CompiledFilter::All(
+                filters
+                    .iter()
+                    .map(Self::compile)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Self::Any(filters) => CompiledFilter::Any(
+                filters
+                    .iter()
+                    .map(Self::compile)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Self::Not(filter) => CompiledFilter::Not(Box::new(filter.compile()?)),
+        })
+    }
+}
+
+impl std::ops::Not for Filter {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        match self {
+            Self::Not(filter) => *filter,
+            filter => Self::Not(Box::new(filter)),
+        }
+    }
+}
+
+/// A [`Filter`] whose values have been deserialized.
+#[derive(Debug)]
+pub enum CompiledFilter<'a> {
+    /// Matches documents whose `field` is equal to `value`.
+    Equal {
+        /// The name of the field.
+        field: &'a str,
+        /// The value.
+        value: Value<'a>,
+    },
+    /// Matches documents whose `field` is contained in `range`.
+    Range {
+        /// The name of the field.
+        field: &'a str,
+        /// The range.
+        range: Range<Value<'a>>,
+    },
+    /// Matches documents that match all of the contained filters.
+    All(Vec<CompiledFilter<'a>>),
+    /// Matches documents that match any of the contained filters.
+    Any(Vec<CompiledFilter<'a>>),
+    /// Matches documents that don't match the contained filter.
+    Not(Box<CompiledFilter<'a>>),
+}
+
+impl CompiledFilter<'_> {
+    /// Returns true if `contents` matches this filter. `contents` should be
+    /// the deserialized contents of a document.
+    #[must_use]
+    pub fn matches(&self, contents: &Value<'_>) -> bool {
+        match self {
+            Self::Equal { field, value } => {
+                lookup(contents, field).map_or(false, |field| equal(field, value))
+            }
+            Self::Range { field, range } => {
+                lookup(contents, field).map_or(false, |field| contains(range, field))
+            }
+            Self::All(filters) => filters.iter().all(|filter| filter.matches(contents)),
+            Self::Any(filters) => filters.iter().any(|filter| filter.matches(contents)),
+            Self::Not(filter) => !filter.matches(contents),
+        }
+    }
+}
+
+/// Returns the value at `path` within `value`, where `path` is a list of field
+/// names separated by periods.
+fn lookup<'v, 'a>(value: &'v Value<'a>, path: &str) -> Option<&'v Value<'a>> {
+    path.split('.').try_fold(value, |value, name| match value {
+        Value::Mappings(mappings) => mappings.iter().find_map(|(key, value)| match key {
+            Value::String(key) if key == name => Some(value),
+            _ => None,
+        }),
+        _ => None,
+    })
+}
+
+fn equal(a: &Value<'_>, b: &Value<'_>) -> bool {
+    compare(a, b).map_or_else(|| a == b, Ordering::is_eq)
+}
+
+fn contains(range: &Range<Value<'_>>, value: &Value<'_>) -> bool {
+    let after_start = match &range.start {
+        Bound::Unbounded => true,
+        Bound::Included(start) => compare(value, start).map_or(false, Ordering::is_ge),
+        Bound::Excluded(start) => compare(value, start).map_or(false, Ordering::is_gt),
+    };
+    let before_end = match &range.end {
+        Bound::Unbounded => true,
+        Bound::Included(end) => compare(value, end).map_or(false, Ordering::is_le),
+        Bound::Excluded(end) => compare(value, end).map_or(false, Ordering::is_lt),
+    };
+    after_start && before_end
+}
+
+/// Compares two scalar values. Returns `None` if the values can't be compared.
+fn compare(a: &Value<'_>, b: &Value<'_>) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => match (a.as_i128(), b.as_i128()) {
+            (Ok(a), Ok(b)) => Some(a.cmp(&b)),
+            // Only unsigned integers can be too large for an i128.
+            (Err(_), Ok(_)) => Some(Ordering::Greater),
+            (Ok(_), Err(_)) => Some(Ordering::Less),
+            (Err(_), Err(_)) => Some(a.as_u128().ok()?.cmp(&b.as_u128().ok()?)),
+        },
+        (Value::Float(a), Value::Float(b)) => a.as_f64().partial_cmp(&b.as_f64()),
+        (Value::Integer(a), Value::Float(b)) => a.as_f64().ok()?.partial_cmp(&b.as_f64()),
+        (Value::Float(a), Value::Integer(b)) => a.as_f64().partial_cmp(&b.as_f64().ok()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bytes(a), Value::Bytes(b)) => Some(a.cmp(b)),
+        (Value::Bool(a), Value::Bool(b)) => Some(a.cmp(b)),
+        (Value::None, Value::None) | (Value::Unit, Value::Unit) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+#[test]
+fn filter_matching() {
+    #[derive(Serialize)]
+    struct Address {
+        city: &'static str,
+    }
+
+    #[derive(Serialize)]
+    struct Person {
+        name: &'static str,
+        age: u8,
+        height: f32,
+        address: Address,
+        nickname: Option<&'static str>,
+    }
+
+    let person = pot::to_vec(&Person {
+        name: "Ada",
+        age: 36,
+        height: 1.6,
+        address: Address { city: "London" },
+        nickname: None,
+    })
+    .unwrap();
+    let person = pot::from_slice::<Value<'_>>(&person).unwrap();
+    let matches = |filter: Filter| filter.compile().unwrap().matches(&person);
+
+    assert!(matches(Filter::equal("name", "Ada").unwrap()));
+    assert!(!matches(Filter::equal("name", "Grace").unwrap()));
+    assert!(matches(Filter::equal("age", &36_u64).unwrap()));
+    assert!(matches(Filter::equal("address.city", "London").unwrap()));
+    assert!(!matches(Filter::equal("address.country", "UK").unwrap()));
+    assert!(matches(Filter::equal("nickname", &None::<&str>).unwrap()));
+    assert!(matches(Filter::range("age", 30..=36).unwrap()));
+    assert!(!matches(Filter::range("age", 30..36).unwrap()));
+    assert!(matches(Filter::range("height", 1..2).unwrap()));
+    assert!(matches(Filter::range("name", "A"..).unwrap()));
+    assert!(!matches(Filter::range("name", 0..).unwrap()));
+    assert!(matches(
+        Filter::equal("name", "Grace")
+            .unwrap()
+            .or(Filter::range("age", ..40).unwrap())
+    ));
+    assert!(!matches(
+        Filter::equal("name", "Ada")
+            .unwrap()
+            .and(!Filter::range("age", ..40).unwrap())
+    ));
+}
@@ -200,6 +200,22 @@ pub struct QualifiedName {
     pub name: Name,
 }
 
+impl QualifiedName {
+    /// Validates that neither the authority nor the name is empty.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNameError`] containing this name if either the
+    /// authority or name is empty.
+    pub fn validate(&self) -> Result<(), InvalidNameError> {
+        if self.authority.as_ref().is_empty() || self.name.as_ref().is_empty() {
+            Err(InvalidNameError(self.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
 impl Display for QualifiedName {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         Display::fmt(&self.authority, f)?;
@@ -229,6 +245,26 @@ pub trait Qualified: Display + Sized {
     #[must_use]
     fn new<A: Into<Authority>, N: Into<Name>>(authority: A, name: N) -> Self;
 
+    /// Parses a name in the format `authority.name`, such as
+    /// `"khonsulabs.users"`. Unlike [`Self::parse_encoded()`], the authority
+    /// and name are used as-is rather than being decoded.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InvalidNameError`] if `name` does not contain exactly one
+    /// period, or if the authority or name is empty.
+    fn parse(name: &str) -> Result<Self, InvalidNameError> {
+        let mut parts = name.split('.');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(authority), Some(local_name), None)
+                if !authority.is_empty() && !local_name.is_empty() =>
+            {
+                Ok(Self::new(authority.to_string(), local_name.to_string()))
+            }
+            _ => Err(InvalidNameError(name.to_string())),
+        }
+    }
+
     /// Parses a schema name that was previously encoded via
     /// [`Self::encoded()`].
     ///
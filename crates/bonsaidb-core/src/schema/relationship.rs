@@ -0,0 +1,74 @@
+use std::fmt::Debug;
+
+use derive_where::derive_where;
+
+use crate::schema::{CollectionName, ViewName};
+
+/// What happens to the documents that belong to a document when it is
+/// deleted. See
+/// [`Schematic::define_belongs_to()`](crate::schema::Schematic::define_belongs_to).
+#[derive_where(Clone, Copy)]
+pub enum OnDelete<Contents> {
+    /// The documents that belong to the deleted document are also deleted.
+    /// Relationships referencing the deleted documents' collection are applied
+    /// to them in turn.
+    Cascade,
+    /// The document can't be deleted while any documents belong to it.
+    /// Deleting it fails with
+    /// [`Error::DeleteRestricted`](crate::Error::DeleteRestricted).
+    Restrict,
+    /// The documents that belong to the deleted document are updated using
+    /// the provided function, which should clear the field referencing the
+    /// deleted document.
+    SetNull(fn(&mut Contents)),
+}
+
+impl<Contents> OnDelete<Contents> {
+    /// Returns the [`DeleteAction`] performed by this policy.
+    #[must_use]
+    pub const fn action(&self) -> DeleteAction {
+        match self {
+            Self::Cascade => DeleteAction::Cascade,
+            Self::Restrict => DeleteAction::Restrict,
+            Self::SetNull(_) => DeleteAction::SetNull,
+        }
+    }
+}
+
+impl<Contents> Debug for OnDelete<Contents> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.action(), f)
+    }
+}
+
+/// The action an [`OnDelete`] policy performs, without the collection's types.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum DeleteAction {
+    /// See [`OnDelete::Cascade`].
+    Cascade,
+    /// See [`OnDelete::Restrict`].
+    Restrict,
+    /// See [`OnDelete::SetNull`].
+    SetNull,
+}
+
+/// Wraps a relationship defined using
+/// [`Schematic::define_belongs_to()`](crate::schema::Schematic::define_belongs_to)
+/// to erase its types.
+pub trait Serialized: Debug + Send + Sync {
+    /// Returns the name of the eager view mapping each document in
+    /// [`Self::collection()`] to the id of the document it belongs to.
+    fn view_name(&self) -> ViewName;
+    /// Returns the collection of the documents that belong to documents in
+    /// [`Self::parent()`].
+    fn collection(&self) -> CollectionName;
+    /// Returns the collection of the documents that other documents belong
+    /// to.
+    fn parent(&self) -> CollectionName;
+    /// Returns the action performed when a document in [`Self::parent()`] is
+    /// deleted.
+    fn on_delete(&self) -> DeleteAction;
+    /// Applies [`OnDelete::SetNull`] to the serialized `contents` of a
+    /// document in [`Self::collection()`], returning the updated contents.
+    fn set_null(&self, contents: &[u8]) -> Result<Vec<u8>, crate::Error>;
+}
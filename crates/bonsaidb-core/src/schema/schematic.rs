@@ -1,18 +1,29 @@
 use std::any::TypeId;
-use std::collections::{hash_map, HashMap};
+use std::cmp::Ordering;
+use std::collections::{hash_map, HashMap, HashSet};
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::time::Duration;
 
+use arc_bytes::serde::Bytes;
 use derive_where::derive_where;
 
+use crate::changes::Change;
+use crate::connection::AccessPolicy;
 use crate::document::{BorrowedDocument, DocumentId, KeyId};
-use crate::key::{ByteSource, Key, KeyDescription};
-use crate::schema::collection::Collection;
+use crate::key::{ByteSource, Key, KeyDescription, KeyEncoding};
+use crate::schema::collection::{Collection, ValidationError};
+use crate::schema::relationship::{self, DeleteAction, OnDelete};
+use crate::schema::trigger::{self, Trigger};
 use crate::schema::view::map::{self, MappedValue};
 use crate::schema::view::{
     self, MapReduce, Serialized, SerializedView, ViewSchema, ViewUpdatePolicy,
 };
-use crate::schema::{CollectionName, Schema, SchemaName, View, ViewName};
+use crate::schema::virtual_collection::{self, Sources, VirtualCollection};
+use crate::schema::{
+    CollectionName, InvalidNameError, Name, Schema, SchemaName, SerializedCollection, View,
+    ViewName,
+};
 use crate::Error;
 
 /// A collection of defined collections and views.
@@ -22,11 +33,22 @@ pub struct Schematic {
     contained_collections: HashMap<CollectionName, KeyDescription>,
     collections_by_type_id: HashMap<TypeId, CollectionName>,
     collection_encryption_keys: HashMap<CollectionName, KeyId>,
+    soft_delete_collections: HashSet<CollectionName>,
+    collection_expirations: HashMap<CollectionName, Duration>,
+    collection_versions: HashMap<CollectionName, CollectionVersion>,
+    collection_formats: HashMap<CollectionName, CollectionFormat>,
+    collection_validators: HashMap<CollectionName, fn(&[u8]) -> Result<(), ValidationError>>,
+    collection_patchers: HashMap<CollectionName, fn(&[u8], &[u8]) -> Result<Vec<u8>, Error>>,
     collection_id_generators: HashMap<CollectionName, Box<dyn IdGenerator>>,
-    views: HashMap<TypeId, Box<dyn view::Serialized>>,
-    views_by_name: HashMap<ViewName, TypeId>,
-    views_by_collection: HashMap<CollectionName, Vec<TypeId>>,
-    eager_views_by_collection: HashMap<CollectionName, Vec<TypeId>>,
+    views: HashMap<ViewName, Box<dyn view::Serialized>>,
+    views_by_type_id: HashMap<TypeId, ViewName>,
+    views_by_collection: HashMap<CollectionName, Vec<ViewName>>,
+    eager_views_by_collection: HashMap<CollectionName, Vec<ViewName>>,
+    triggers_by_collection: HashMap<CollectionName, Vec<Box<dyn trigger::Serialized>>>,
+    relationships_by_parent: HashMap<CollectionName, Vec<Box<dyn relationship::Serialized>>>,
+    virtual_collections: HashMap<CollectionName, Box<dyn virtual_collection::Serialized>>,
+    validate_collection_name: fn(&CollectionName) -> Result<(), InvalidNameError>,
+    default_access_policy: AccessPolicy,
 }
 
 impl Schematic {
@@ -37,19 +59,34 @@ impl Schematic {
             contained_collections: HashMap::new(),
             collections_by_type_id: HashMap::new(),
             collection_encryption_keys: HashMap::new(),
+            soft_delete_collections: HashSet::new(),
+            collection_expirations: HashMap::new(),
+            collection_versions: HashMap::new(),
+            collection_formats: HashMap::new(),
+            collection_validators: HashMap::new(),
+            collection_patchers: HashMap::new(),
             collection_id_generators: HashMap::new(),
             views: HashMap::new(),
-            views_by_name: HashMap::new(),
+            views_by_type_id: HashMap::new(),
             views_by_collection: HashMap::new(),
             eager_views_by_collection: HashMap::new(),
+            triggers_by_collection: HashMap::new(),
+            relationships_by_parent: HashMap::new(),
+            virtual_collections: HashMap::new(),
+            validate_collection_name: S::validate_collection_name,
+            default_access_policy: S::default_view_access_policy(),
         };
         S::define_collections(&mut schematic)?;
         Ok(schematic)
     }
 
     /// Adds the collection `C` and its views.
+    ///
+    /// Returns [`Error::InvalidName`] if the collection's name is rejected by
+    /// [`Schema::validate_collection_name()`].
     pub fn define_collection<C: Collection + 'static>(&mut self) -> Result<(), Error> {
         let name = C::collection_name();
+        (self.validate_collection_name)(&name)?;
         match self.contained_collections.entry(name.clone()) {
             hash_map::Entry::Vacant(entry) => {
                 self.collections_by_type_id
@@ -57,15 +94,54 @@ impl Schematic {
                 if let Some(key) = C::encryption_key() {
                     self.collection_encryption_keys.insert(name.clone(), key);
                 }
+                if C::soft_delete() {
+                    self.soft_delete_collections.insert(name.clone());
+                }
+                if let Some(expire_after) = C::expire_after() {
+                    self.collection_expirations
+                        .insert(name.clone(), expire_after);
+                }
+                if C::CURRENT_VERSION > 0 {
+                    self.collection_versions.insert(
+                        name.clone(),
+                        CollectionVersion {
+                            current: C::CURRENT_VERSION,
+                            migrate: C::migrate,
+                        },
+                    );
+                }
+                self.collection_formats.insert(
+                    name.clone(),
+                    CollectionFormat {
+                        name: C::FORMAT_NAME,
+                        convert: C::convert_format,
+                    },
+                );
+                self.collection_validators.insert(name.clone(), C::validate);
+                self.collection_patchers
+                    .insert(name.clone(), C::apply_patch);
                 self.collection_id_generators
                     .insert(name, Box::<KeyIdGenerator<C>>::default());
                 entry.insert(KeyDescription::for_key::<C::PrimaryKey>());
                 C::define_views(self)
             }
-            hash_map::Entry::Occupied(_) => Err(Error::CollectionAlreadyDefined),
+            hash_map::Entry::Occupied(entry) => {
+                Err(Error::CollectionAlreadyDefined(entry.key().clone()))
+            }
         }
     }
 
+    /// Adds the collections and views of the schema `S`, allowing schemas to
+    /// be composed from reusable bundles of collections.
+    ///
+    /// Returns [`Error::CollectionAlreadyDefined`] if `S` contains a
+    /// collection whose name has already been defined in this schematic.
+    /// Collection names are validated using the policy of the schema this
+    /// schematic was created from rather than `S`'s policy.
+    pub fn include<S: Schema + ?Sized>(&mut self) -> Result<(), Error> {
+        S::define_collections(self)
+    }
+
     /// Adds the view `V`.
     pub fn define_view<V: MapReduce + ViewSchema<View = V> + SerializedView + Clone + 'static>(
         &mut self,
@@ -83,31 +159,220 @@ impl Schematic {
         view: V,
         schema: S,
     ) -> Result<(), Error> {
-        let instance = ViewInstance { view, schema };
-        let name = instance.view_name();
-        if self.views_by_name.contains_key(&name) {
-            return Err(Error::ViewAlreadyRegistered(name));
+        let name = self.insert_view(Box::new(ViewInstance { view, schema }))?;
+        self.views_by_type_id.insert(TypeId::of::<V>(), name);
+        Ok(())
+    }
+
+    /// Adds a unique index named `name` to the collection `C`. The key of each
+    /// document in the index is returned by `key`.
+    ///
+    /// When a document is saved whose key is already used by another document
+    /// in the collection, [`Error::UniqueKeyViolation`] is returned with the
+    /// name of the index as its `view`. The index is checked within the
+    /// transaction saving the document, which means none of the transaction's
+    /// changes are applied.
+    ///
+    /// A unique index is equivalent to a view using
+    /// [`ViewUpdatePolicy::Unique`] that emits a single key for each document,
+    /// except that it only enforces the constraint and can't be queried.
+    ///
+    /// ```rust
+    /// # use bonsaidb_core::schema::{Collection, CollectionName, Qualified, Schematic};
+    /// # use bonsaidb_core::Error;
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct User {
+    ///     username: String,
+    /// }
+    ///
+    /// impl Collection for User {
+    ///     type PrimaryKey = u64;
+    ///
+    ///     fn collection_name() -> CollectionName {
+    ///         CollectionName::new("example", "users")
+    ///     }
+    ///
+    ///     fn define_views(schema: &mut Schematic) -> Result<(), Error> {
+    ///         schema.define_unique_index::<Self, _>("unique-username", |user| {
+    ///             user.username.clone()
+    ///         })
+    ///     }
+    /// }
+    /// # impl bonsaidb_core::schema::DefaultSerialization for User {}
+    /// ```
+    pub fn define_unique_index<C, K>(
+        &mut self,
+        name: impl Into<Name>,
+        key: fn(&C::Contents) -> K,
+    ) -> Result<(), Error>
+    where
+        C: SerializedCollection + 'static,
+        K: for<'k> Key<'k> + 'static,
+    {
+        self.insert_view(Box::new(FnIndex::<C, K> {
+            name: ViewName {
+                collection: C::collection_name(),
+                name: name.into(),
+            },
+            update_policy: ViewUpdatePolicy::Unique,
+            key: IndexKey::Required(key),
+        }))
+        .map(|_| ())
+    }
+
+    /// Adds a relationship named `name` where documents in the collection `C`
+    /// belong to documents in the collection `P`. The id of the document each
+    /// document belongs to is returned by `parent`.
+    ///
+    /// When a document in `P` is deleted, `on_delete` is applied to the
+    /// documents that belong to it. This happens within the transaction
+    /// deleting the document: if the transaction fails, none of the changes
+    /// made by `on_delete` are applied. The documents changed by `on_delete`
+    /// are subject to the same permission checks as the documents the
+    /// transaction changes directly.
+    ///
+    /// The relationship is tracked using an eager view named `name` in `C`,
+    /// which means it can't share its name with another view in `C`. Saving a
+    /// document in `C` does not check that the document it belongs to exists.
+    ///
+    /// ```rust
+    /// # use bonsaidb_core::schema::relationship::OnDelete;
+    /// # use bonsaidb_core::schema::{Collection, CollectionName, Qualified, Schematic};
+    /// # use bonsaidb_core::Error;
+    /// # use serde::{Deserialize, Serialize};
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct Post {
+    ///     title: String,
+    /// }
+    ///
+    /// impl Collection for Post {
+    ///     type PrimaryKey = u64;
+    ///
+    ///     fn collection_name() -> CollectionName {
+    ///         CollectionName::new("example", "posts")
+    ///     }
+    ///
+    ///     fn define_views(_schema: &mut Schematic) -> Result<(), Error> {
+    ///         Ok(())
+    ///     }
+    /// }
+    /// # impl bonsaidb_core::schema::DefaultSerialization for Post {}
+    ///
+    /// #[derive(Debug, Serialize, Deserialize)]
+    /// struct Comment {
+    ///     post_id: u64,
+    ///     body: String,
+    /// }
+    ///
+    /// impl Collection for Comment {
+    ///     type PrimaryKey = u64;
+    ///
+    ///     fn collection_name() -> CollectionName {
+    ///         CollectionName::new("example", "comments")
+    ///     }
+    ///
+    ///     fn define_views(schema: &mut Schematic) -> Result<(), Error> {
+    ///         schema.define_belongs_to::<Self, Post>(
+    ///             "post",
+    ///             |comment| Some(comment.post_id),
+    ///             OnDelete::Cascade,
+    ///         )
+    ///     }
+    /// }
+    /// # impl bonsaidb_core::schema::DefaultSerialization for Comment {}
+    /// ```
+    pub fn define_belongs_to<C, P>(
+        &mut self,
+        name: impl Into<Name>,
+        parent: fn(&C::Contents) -> Option<P::PrimaryKey>,
+        on_delete: OnDelete<C::Contents>,
+    ) -> Result<(), Error>
+    where
+        C: SerializedCollection + 'static,
+        P: Collection + 'static,
+    {
+        let name = ViewName {
+            collection: C::collection_name(),
+            name: name.into(),
+        };
+        self.insert_view(Box::new(FnIndex::<C, P::PrimaryKey> {
+            name: name.clone(),
+            update_policy: ViewUpdatePolicy::Eager,
+            key: IndexKey::Optional(parent),
+        }))?;
+        self.relationships_by_parent
+            .entry(P::collection_name())
+            .or_default()
+            .push(Box::new(RelationshipInstance::<C, P> {
+                name,
+                on_delete,
+                _parent: PhantomData,
+            }));
+        Ok(())
+    }
+
+    /// Adds `trigger`, which is invoked after changes to documents in
+    /// `T::Collection` are committed. See [`Trigger`] for more information.
+    ///
+    /// Returns [`Error::TriggerAlreadyRegistered`] if the collection already
+    /// has a trigger with the same name.
+    pub fn define_trigger<T: Trigger>(&mut self, trigger: T) -> Result<(), Error> {
+        let collection = T::Collection::collection_name();
+        let name = trigger.name();
+        let triggers = self
+            .triggers_by_collection
+            .entry(collection.clone())
+            .or_default();
+        if triggers.iter().any(|existing| existing.name() == name) {
+            return Err(Error::TriggerAlreadyRegistered { collection, name });
+        }
+
+        triggers.push(Box::new(TriggerInstance(trigger)));
+        Ok(())
+    }
+
+    /// Makes `C` a virtual collection, whose documents are computed from the
+    /// documents of other collections. See [`VirtualCollection`] for more
+    /// information.
+    ///
+    /// Returns [`Error::CollectionAlreadyDefined`] if `C` was already made a
+    /// virtual collection.
+    pub fn define_virtual_collection<C: VirtualCollection>(&mut self) -> Result<(), Error> {
+        match self.virtual_collections.entry(C::collection_name()) {
+            hash_map::Entry::Occupied(entry) => {
+                Err(Error::CollectionAlreadyDefined(entry.key().clone()))
+            }
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(Box::new(VirtualCollectionInstance::<C>(PhantomData)));
+                Ok(())
+            }
         }
+    }
 
-        let collection = instance.collection();
-        let eager = instance.update_policy().is_eager();
-        self.views.insert(TypeId::of::<V>(), Box::new(instance));
-        self.views_by_name.insert(name, TypeId::of::<V>());
+    fn insert_view(&mut self, view: Box<dyn view::Serialized>) -> Result<ViewName, Error> {
+        let name = view.view_name();
+        if name.name.as_ref().is_empty() {
+            return Err(Error::InvalidName(InvalidNameError(name.to_string())));
+        }
+        if self.views.contains_key(&name) {
+            return Err(Error::ViewAlreadyRegistered(name));
+        }
 
-        if eager {
-            let unique_views = self
-                .eager_views_by_collection
+        let collection = view.collection();
+        if view.update_policy().is_eager() {
+            self.eager_views_by_collection
                 .entry(collection.clone())
-                .or_insert_with(Vec::new);
-            unique_views.push(TypeId::of::<V>());
+                .or_default()
+                .push(name.clone());
         }
-        let views = self
-            .views_by_collection
+        self.views_by_collection
             .entry(collection)
-            .or_insert_with(Vec::new);
-        views.push(TypeId::of::<V>());
+            .or_default()
+            .push(name.clone());
+        self.views.insert(name.clone(), view);
 
-        Ok(())
+        Ok(name)
     }
 
     /// Returns `true` if this schema contains the collection `C`.
@@ -142,21 +407,31 @@ impl Schematic {
 
     /// Looks up a [`view::Serialized`] by name.
     pub fn view_by_name(&self, name: &ViewName) -> Result<&'_ dyn view::Serialized, Error> {
-        self.views_by_name
+        self.views
             .get(name)
-            .and_then(|type_id| self.views.get(type_id))
             .map(AsRef::as_ref)
             .ok_or(Error::ViewNotFound)
     }
 
     /// Looks up a [`view::Serialized`] through the the type `V`.
     pub fn view<V: View + 'static>(&self) -> Result<&'_ dyn view::Serialized, Error> {
-        self.views
+        self.views_by_type_id
             .get(&TypeId::of::<V>())
+            .and_then(|name| self.views.get(name))
             .map(AsRef::as_ref)
             .ok_or(Error::ViewNotFound)
     }
 
+    /// Returns the [`AccessPolicy`] used when querying the view `V` without
+    /// specifying a policy. This is the view's
+    /// [`default_access_policy()`](View::default_access_policy) if it returns
+    /// a policy, otherwise this schema's
+    /// [`default_view_access_policy()`](Schema::default_view_access_policy).
+    #[must_use]
+    pub fn default_access_policy<V: View>(&self) -> AccessPolicy {
+        V::default_access_policy().unwrap_or(self.default_access_policy)
+    }
+
     /// Iterates over all registered views.
     pub fn views(&self) -> impl Iterator<Item = &'_ dyn view::Serialized> {
         self.views.values().map(AsRef::as_ref)
@@ -170,10 +445,10 @@ impl Schematic {
         self.views_by_collection
             .get(collection)
             .into_iter()
-            .flat_map(|view_ids| {
-                view_ids
+            .flat_map(|names| {
+                names
                     .iter()
-                    .filter_map(|id| self.views.get(id).map(AsRef::as_ref))
+                    .filter_map(|name| self.views.get(name).map(AsRef::as_ref))
             })
     }
 
@@ -186,19 +461,185 @@ impl Schematic {
         self.eager_views_by_collection
             .get(collection)
             .into_iter()
-            .flat_map(|view_ids| {
-                view_ids
+            .flat_map(|names| {
+                names
                     .iter()
-                    .filter_map(|id| self.views.get(id).map(AsRef::as_ref))
+                    .filter_map(|name| self.views.get(name).map(AsRef::as_ref))
             })
     }
 
+    /// Returns an iterator over all triggers registered for `collection`.
+    pub fn triggers_in_collection(
+        &self,
+        collection: &CollectionName,
+    ) -> impl Iterator<Item = &'_ dyn trigger::Serialized> {
+        self.triggers_by_collection
+            .get(collection)
+            .into_iter()
+            .flatten()
+            .map(AsRef::as_ref)
+    }
+
+    /// Returns an iterator over all relationships where documents belong to
+    /// documents in `collection`.
+    pub fn relationships_referencing(
+        &self,
+        collection: &CollectionName,
+    ) -> impl Iterator<Item = &'_ dyn relationship::Serialized> {
+        self.relationships_by_parent
+            .get(collection)
+            .into_iter()
+            .flatten()
+            .map(AsRef::as_ref)
+    }
+
+    /// Returns the definition of `collection` if it is a virtual collection.
+    #[must_use]
+    pub fn virtual_collection(
+        &self,
+        collection: &CollectionName,
+    ) -> Option<&'_ dyn virtual_collection::Serialized> {
+        self.virtual_collections.get(collection).map(AsRef::as_ref)
+    }
+
+    /// Returns an iterator over all virtual collections.
+    pub fn virtual_collections(
+        &self,
+    ) -> impl Iterator<Item = &'_ dyn virtual_collection::Serialized> {
+        self.virtual_collections.values().map(AsRef::as_ref)
+    }
+
     /// Returns a collection's default encryption key, if one was defined.
     #[must_use]
     pub fn encryption_key_for_collection(&self, collection: &CollectionName) -> Option<&KeyId> {
         self.collection_encryption_keys.get(collection)
     }
 
+    /// Returns true if documents deleted from `collection` are kept as
+    /// tombstones. See [`Collection::soft_delete()`].
+    #[must_use]
+    pub fn collection_uses_soft_delete(&self, collection: &CollectionName) -> bool {
+        self.soft_delete_collections.contains(collection)
+    }
+
+    /// Returns how long after being updated documents in `collection` expire,
+    /// if the collection expires documents. See
+    /// [`Collection::expire_after()`].
+    #[must_use]
+    pub fn collection_expiration(&self, collection: &CollectionName) -> Option<Duration> {
+        self.collection_expirations.get(collection).copied()
+    }
+
+    /// Returns an iterator over all collections that expire documents, along
+    /// with how long after being updated their documents expire.
+    pub fn expiring_collections(&self) -> impl Iterator<Item = (&CollectionName, Duration)> {
+        self.collection_expirations
+            .iter()
+            .map(|(collection, expire_after)| (collection, *expire_after))
+    }
+
+    /// Returns the version documents in `collection` are stored with. See
+    /// [`Collection::CURRENT_VERSION`].
+    #[must_use]
+    pub fn collection_version(&self, collection: &CollectionName) -> u32 {
+        self.collection_versions
+            .get(collection)
+            .map_or(0, |version| version.current)
+    }
+
+    /// Migrates `contents` of a document in `collection` that was stored using
+    /// `version` to the collection's current version. Returns `None` if the
+    /// document is already using the current version.
+    ///
+    /// Returns [`Error::DocumentVersionUnsupported`] if `version` is newer than
+    /// the collection's current version.
+    pub fn migrate_contents(
+        &self,
+        collection: &CollectionName,
+        version: u32,
+        contents: &[u8],
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let current = self.collection_version(collection);
+        match version.cmp(&current) {
+            Ordering::Equal => Ok(None),
+            Ordering::Less => {
+                let migrate = self.collection_versions[collection].migrate;
+                migrate(version, contents).map(Some)
+            }
+            Ordering::Greater => Err(Error::DocumentVersionUnsupported {
+                collection: collection.clone(),
+                version,
+            }),
+        }
+    }
+
+    /// Returns the name of the format documents in `collection` are stored
+    /// with. See [`Collection::FORMAT_NAME`].
+    #[must_use]
+    pub fn collection_format(&self, collection: &CollectionName) -> Option<&'static str> {
+        self.collection_formats
+            .get(collection)
+            .and_then(|format| format.name)
+    }
+
+    /// Converts `contents` of a document in `collection` that was stored using
+    /// the format named `format` to the collection's current format. Returns
+    /// `None` if the document is already using the current format.
+    pub fn convert_contents(
+        &self,
+        collection: &CollectionName,
+        format: Option<&str>,
+        contents: &[u8],
+    ) -> Result<Option<Vec<u8>>, Error> {
+        match self.collection_formats.get(collection) {
+            Some(current) if current.name != format => {
+                (current.convert)(format, contents).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Validates `contents` using [`Collection::validate()`] for
+    /// `collection`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::ValidationFailed`] if `contents` is rejected.
+    pub fn validate_contents(
+        &self,
+        collection: &CollectionName,
+        contents: &[u8],
+    ) -> Result<(), Error> {
+        match self.collection_validators.get(collection) {
+            Some(validate) => validate(contents).map_err(|err| Error::ValidationFailed {
+                collection: collection.clone(),
+                message: err.0,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Applies the serialized `patch` to the serialized `contents` of a
+    /// document in `collection` using [`Collection::apply_patch()`], returning
+    /// the patched contents.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Error::CollectionNotFound`] if `collection` isn't part of this
+    /// schematic, or any error returned while applying the patch.
+    pub fn apply_patch(
+        &self,
+        collection: &CollectionName,
+        contents: &[u8],
+        patch: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let apply = self
+            .collection_patchers
+            .get(collection)
+            .ok_or(Error::CollectionNotFound)?;
+        apply(contents, patch)
+    }
+
     /// Returns a list of all collections contained in this schematic.
     pub fn collections(&self) -> impl Iterator<Item = &CollectionName> {
         self.contained_collections.keys()
@@ -222,15 +663,88 @@ impl Debug for Schematic {
                 "collection_encryption_keys",
                 &self.collection_encryption_keys,
             )
+            .field("soft_delete_collections", &self.soft_delete_collections)
+            .field("collection_expirations", &self.collection_expirations)
+            .field("collection_versions", &self.collection_versions)
+            .field("collection_formats", &self.collection_formats)
+            .field(
+                "collection_validators",
+                &self.collection_validators.keys().collect::<Vec<_>>(),
+            )
+            .field(
+                "collection_patchers",
+                &self.collection_patchers.keys().collect::<Vec<_>>(),
+            )
             .field("collection_id_generators", &self.collection_id_generators)
             .field("views", &views)
-            .field("views_by_name", &self.views_by_name)
+            .field("views_by_type_id", &self.views_by_type_id)
             .field("views_by_collection", &self.views_by_collection)
             .field("eager_views_by_collection", &self.eager_views_by_collection)
+            .field("triggers_by_collection", &self.triggers_by_collection)
+            .field("relationships_by_parent", &self.relationships_by_parent)
+            .field("virtual_collections", &self.virtual_collections)
+            .field("default_access_policy", &self.default_access_policy)
             .finish()
     }
 }
 
+#[derive(Debug)]
+struct CollectionVersion {
+    current: u32,
+    migrate: fn(u32, &[u8]) -> Result<Vec<u8>, Error>,
+}
+
+#[derive(Debug)]
+struct CollectionFormat {
+    name: Option<&'static str>,
+    convert: fn(Option<&str>, &[u8]) -> Result<Vec<u8>, Error>,
+}
+
+#[derive(Debug)]
+struct TriggerInstance<T>(T);
+
+impl<T> trigger::Serialized for TriggerInstance<T>
+where
+    T: Trigger,
+{
+    fn collection(&self) -> CollectionName {
+        T::Collection::collection_name()
+    }
+
+    fn name(&self) -> Name {
+        self.0.name()
+    }
+
+    fn on_change(&self, change: Change) -> Result<(), Error> {
+        self.0.on_change(change.try_into()?)
+    }
+}
+
+#[derive_where(Debug)]
+struct VirtualCollectionInstance<C>(PhantomData<C>);
+
+impl<C> virtual_collection::Serialized for VirtualCollectionInstance<C>
+where
+    C: VirtualCollection,
+{
+    fn collection(&self) -> CollectionName {
+        C::collection_name()
+    }
+
+    fn sources(&self) -> Vec<CollectionName> {
+        C::sources()
+    }
+
+    fn compute(&self, sources: &Sources) -> Result<Vec<(DocumentId, Bytes)>, Error> {
+        C::compute(sources)?
+            .into_iter()
+            .map(|(id, contents)| {
+                Ok((DocumentId::new(&id)?, Bytes::from(C::serialize(&contents)?)))
+            })
+            .collect()
+    }
+}
+
 #[derive(Debug)]
 struct ViewInstance<V, S> {
     view: V,
@@ -250,6 +764,10 @@ where
         KeyDescription::for_key::<<V as View>::Key>()
     }
 
+    fn value_type_name(&self) -> &'static str {
+        std::any::type_name::<<V as View>::Value>()
+    }
+
     fn update_policy(&self) -> ViewUpdatePolicy {
         self.schema.update_policy()
     }
@@ -258,6 +776,10 @@ where
         self.schema.version()
     }
 
+    fn include_soft_deleted(&self) -> bool {
+        self.schema.include_soft_deleted()
+    }
+
     fn view_name(&self) -> ViewName {
         self.view.view_name()
     }
@@ -292,6 +814,130 @@ where
     }
 }
 
+/// A view whose key is produced by a function pointer, used to implement
+/// [`Schematic::define_unique_index()`] and
+/// [`Schematic::define_belongs_to()`].
+struct FnIndex<C: SerializedCollection, K> {
+    name: ViewName,
+    update_policy: ViewUpdatePolicy,
+    key: IndexKey<C::Contents, K>,
+}
+
+/// The function returning the key of a document in a [`FnIndex`].
+enum IndexKey<Contents, K> {
+    /// Every document is mapped.
+    Required(fn(&Contents) -> K),
+    /// Documents are only mapped if a key is returned.
+    Optional(fn(&Contents) -> Option<K>),
+}
+
+impl<C, K> Serialized for FnIndex<C, K>
+where
+    C: SerializedCollection,
+    K: for<'k> Key<'k>,
+{
+    fn collection(&self) -> CollectionName {
+        C::collection_name()
+    }
+
+    fn key_description(&self) -> KeyDescription {
+        KeyDescription::for_key::<K>()
+    }
+
+    fn value_type_name(&self) -> &'static str {
+        std::any::type_name::<()>()
+    }
+
+    fn update_policy(&self) -> ViewUpdatePolicy {
+        self.update_policy
+    }
+
+    fn version(&self) -> u64 {
+        0
+    }
+
+    fn include_soft_deleted(&self) -> bool {
+        false
+    }
+
+    fn view_name(&self) -> ViewName {
+        self.name.clone()
+    }
+
+    fn map(&self, document: &BorrowedDocument<'_>) -> Result<Vec<map::Serialized>, view::Error> {
+        let contents = C::deserialize(&document.contents)?;
+        let key = match &self.key {
+            IndexKey::Required(key) => key(&contents),
+            IndexKey::Optional(key) => match key(&contents) {
+                Some(key) => key,
+                None => return Ok(Vec::new()),
+            },
+        };
+        Ok(vec![map::Serialized {
+            source: document.header.clone(),
+            key: Bytes::from(
+                key.as_ord_bytes()
+                    .map_err(view::Error::key_serialization)?
+                    .to_vec(),
+            ),
+            value: Bytes::from(pot::to_vec(&())?),
+        }])
+    }
+
+    fn reduce(
+        &self,
+        _mappings: &[(&[u8], &[u8])],
+        _rereduce: bool,
+    ) -> Result<Vec<u8>, view::Error> {
+        Err(view::Error::Core(Error::ReduceUnimplemented))
+    }
+}
+
+/// A relationship defined using [`Schematic::define_belongs_to()`]. The
+/// documents belonging to each parent are found using a [`FnIndex`] with the
+/// same name.
+#[derive_where(Debug)]
+struct RelationshipInstance<C: SerializedCollection, P: Collection> {
+    name: ViewName,
+    on_delete: OnDelete<C::Contents>,
+    #[derive_where(skip(Debug))]
+    _parent: PhantomData<P>,
+}
+
+impl<C, P> relationship::Serialized for RelationshipInstance<C, P>
+where
+    C: SerializedCollection,
+    P: Collection,
+{
+    fn view_name(&self) -> ViewName {
+        self.name.clone()
+    }
+
+    fn collection(&self) -> CollectionName {
+        C::collection_name()
+    }
+
+    fn parent(&self) -> CollectionName {
+        P::collection_name()
+    }
+
+    fn on_delete(&self) -> DeleteAction {
+        self.on_delete.action()
+    }
+
+    fn set_null(&self, contents: &[u8]) -> Result<Vec<u8>, Error> {
+        let OnDelete::SetNull(set_null) = self.on_delete else {
+            return Err(Error::other(
+                "relationship",
+                format!("{} does not use OnDelete::SetNull", self.name),
+            ));
+        };
+        let mut contents = C::deserialize(contents)?;
+        set_null(&mut contents);
+        C::serialize(&contents)
+    }
+}
+
 pub trait IdGenerator: Debug + Send + Sync {
     fn next_id(&self, id: Option<DocumentId>) -> Result<DocumentId, Error>;
 }
@@ -330,9 +976,84 @@ fn schema_tests() -> anyhow::Result<()> {
     );
     assert_eq!(schema.views.len(), 6);
     assert_eq!(
-        schema.views[&TypeId::of::<BasicCount>()].view_name(),
+        schema.view::<BasicCount>()?.view_name(),
         View::view_name(&BasicCount)
     );
 
     Ok(())
 }
+
+#[test]
+fn name_validation() {
+    use serde::{Deserialize, Serialize};
+
+    use crate::schema::{Collection, Qualified, Schema};
+    use crate::test_util::Basic;
+
+    #[derive(Debug, Serialize, Deserialize, Collection)]
+    #[collection(name = "", authority = "khonsulabs", core = crate)]
+    struct Unnamed;
+
+    #[derive(Debug, Schema)]
+    #[schema(name = "unnamed", collections = [Unnamed], core = crate)]
+    struct UnnamedSchema;
+
+    #[derive(Debug, Schema)]
+    #[schema(name = "strict", collections = [Basic], name_policy = require_khonsulabs, core = crate)]
+    struct StrictSchema;
+
+    fn require_khonsulabs(name: &CollectionName) -> Result<(), InvalidNameError> {
+        if name.authority.as_ref() == "khonsulabs" {
+            Ok(())
+        } else {
+            Err(InvalidNameError(name.to_string()))
+        }
+    }
+
+    assert!(matches!(
+        Schematic::from_schema::<UnnamedSchema>(),
+        Err(Error::InvalidName(InvalidNameError(name))) if name == "khonsulabs."
+    ));
+    assert!(matches!(
+        Schematic::from_schema::<StrictSchema>(),
+        Err(Error::InvalidName(InvalidNameError(name))) if name == Basic::collection_name().to_string()
+    ));
+
+    assert_eq!(
+        CollectionName::parse("khonsulabs.users").unwrap(),
+        CollectionName::new("khonsulabs", "users")
+    );
+    assert!(CollectionName::parse("users").is_err());
+    assert!(CollectionName::parse("khonsulabs.").is_err());
+    assert!(CollectionName::parse("khonsulabs.users.admins").is_err());
+}
+
+#[test]
+fn include() {
+    use serde::{Deserialize, Serialize};
+
+    use crate::schema::{Collection, Schema};
+    use crate::test_util::{Basic, BasicByParentId, BasicSchema, Unique};
+
+    #[derive(Debug, Schema)]
+    #[schema(name = "composed", collections = [Composed], include = [BasicSchema], core = crate)]
+    struct ComposedSchema;
+
+    #[derive(Debug, Serialize, Deserialize, Collection)]
+    #[collection(name = "composed", core = crate)]
+    struct Composed;
+
+    #[derive(Debug, Schema)]
+    #[schema(name = "conflicting", collections = [Unique], include = [BasicSchema], core = crate)]
+    struct ConflictingSchema;
+
+    let schematic = Schematic::from_schema::<ComposedSchema>().unwrap();
+    assert!(schematic.contains_collection::<Composed>());
+    assert!(schematic.contains_collection::<Basic>());
+    assert!(schematic.view::<BasicByParentId>().is_ok());
+
+    assert!(matches!(
+        Schematic::from_schema::<ConflictingSchema>(),
+        Err(Error::CollectionAlreadyDefined(name)) if name == Unique::collection_name()
+    ));
+}
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 
 use crate::key::KeyDescription;
-use crate::schema::view::ViewUpdatePolicy;
+use crate::schema::view::{self, ViewUpdatePolicy};
 use crate::schema::{CollectionName, SchemaName, Schematic, ViewName};
 
 /// A summary of a [`Schema`](crate::schema::Schema)/[`Schematic`].
@@ -52,16 +52,8 @@ impl<'a> From<&'a Schematic> for SchemaSummary {
                     views: HashMap::new(),
                 });
             for view in schematic.views_in_collection(collection_name) {
-                let name = view.view_name();
-                collection.views.insert(
-                    name.clone(),
-                    ViewSummary {
-                        name,
-                        key: view.key_description(),
-                        policy: view.update_policy(),
-                        version: view.version(),
-                    },
-                );
+                let view = ViewSummary::from(view);
+                collection.views.insert(view.name.clone(), view);
             }
         }
 
@@ -100,6 +92,11 @@ pub struct ViewSummary {
     pub name: ViewName,
     /// The description of [`View::Key`](crate::schema::View::Key).
     pub key: KeyDescription,
+    /// The name of [`View::Value`](crate::schema::View::Value), as returned
+    /// by [`std::any::type_name()`]. This name is intended for display
+    /// purposes, and its contents may change between versions of Rust.
+    #[serde(default)]
+    pub value_type: String,
     /// The result of
     /// [`ViewSchema::update_policy()`](crate::schema::ViewSchema::update_policy)
     /// for this view.
@@ -109,3 +106,15 @@ pub struct ViewSummary {
     /// view.
     pub version: u64,
 }
+
+impl<'a> From<&'a dyn view::Serialized> for ViewSummary {
+    fn from(view: &'a dyn view::Serialized) -> Self {
+        Self {
+            name: view.view_name(),
+            key: view.key_description(),
+            value_type: view.value_type_name().to_string(),
+            policy: view.update_policy(),
+            version: view.version(),
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use std::fmt::Debug;
+
+use crate::changes::{Change, ChangeEvent};
+use crate::schema::{CollectionName, Name, SerializedCollection};
+
+/// A callback invoked after changes to documents in a collection are
+/// committed.
+///
+/// Triggers are useful for maintaining derived data that can't be expressed
+/// as a [`View`](crate::schema::View), such as an external search index. They
+/// are registered using [`Schematic::define_trigger()`], which is usually
+/// called from [`Collection::define_views()`](crate::schema::Collection::define_views).
+///
+/// Triggers are executed by the storage's background job workers, which means
+/// they never delay the transactions that caused them. The changes made to a
+/// collection with triggers are recorded as part of each transaction, and
+/// each trigger processes them in order, starting after the last transaction
+/// it processed successfully:
+///
+/// - A document's changes are delivered in the order they were committed.
+/// - Each change contains the contents written by the transaction that made
+///   it, even if the document has since been changed or deleted. Deletes
+///   contain the deleted document's header.
+/// - Each change is delivered at least once. If [`Self::on_change()`] returns
+///   an error, it is retried a few times before the trigger stops. Delivery
+///   resumes from the failed change the next time the collection is modified
+///   or the database is opened.
+/// - Recorded changes are removed once every trigger in the collection has
+///   processed them. When a trigger is added to a collection, it receives the
+///   recorded changes that haven't been removed yet.
+///
+/// [`Schematic::define_trigger()`]: crate::schema::Schematic::define_trigger
+pub trait Trigger: Debug + Send + Sync + 'static {
+    /// The collection this trigger observes.
+    type Collection: SerializedCollection;
+
+    /// The name of this trigger, which must be unique among the triggers of
+    /// [`Self::Collection`]. The progress of each trigger is stored using its
+    /// name, so renaming a trigger causes every change to be delivered again.
+    fn name(&self) -> Name;
+
+    /// Invoked with each change made to a document in [`Self::Collection`].
+    ///
+    /// Returning an error causes `change` to be delivered again later.
+    fn on_change(&self, change: ChangeEvent<Self::Collection>) -> Result<(), crate::Error>;
+}
+
+/// Wraps a [`Trigger`] with serialization to erase the associated types.
+pub trait Serialized: Debug + Send + Sync {
+    /// Wraps returing [`<Trigger::Collection as Collection>::collection_name()`](crate::schema::Collection::collection_name)
+    fn collection(&self) -> CollectionName;
+    /// Wraps [`Trigger::name`]
+    fn name(&self) -> Name;
+    /// Deserializes `change` and invokes [`Trigger::on_change()`].
+    fn on_change(&self, change: Change) -> Result<(), crate::Error>;
+}
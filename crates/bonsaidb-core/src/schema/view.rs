@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use transmog::{Format, OwnedDeserializer};
 use transmog_pot::Pot;
 
-use crate::connection::{self, AsyncConnection, Connection};
+use crate::connection::{self, AccessPolicy, AsyncConnection, Connection};
 use crate::document::{BorrowedDocument, CollectionDocument};
 use crate::key::{ByteSource, Key, KeyDescription};
 use crate::schema::view::map::{MappedValue, Mappings, ViewMappedValue};
@@ -14,6 +14,8 @@ use crate::AnyError;
 
 /// Types for defining a `Map` within a `View`.
 pub mod map;
+/// Types for full-text searching using a `View`.
+pub mod search;
 
 /// Errors that arise when interacting with views.
 #[derive(thiserror::Error, Debug)]
@@ -88,6 +90,27 @@ pub trait View: Sized + Send + Sync + 'static {
             name: self.name(),
         }
     }
+
+    /// Returns the [`AccessPolicy`] used when querying this view if no policy
+    /// is specified using
+    /// [`with_access_policy()`](connection::View::with_access_policy). The
+    /// provided implementation returns the collection's
+    /// [`default_access_policy()`](Collection::default_access_policy).
+    ///
+    /// If `None` is returned, the
+    /// [`default_view_access_policy()`](crate::schema::Schema::default_view_access_policy)
+    /// of the schema being queried is used. A policy specified when querying
+    /// takes precedence over the view's policy, which takes precedence over
+    /// the collection's policy, which takes precedence over the schema's
+    /// policy.
+    ///
+    /// Views whose queries can tolerate stale results can return
+    /// [`AccessPolicy::UpdateAfter`] or [`AccessPolicy::NoUpdate`] to avoid
+    /// waiting for the view to be updated.
+    #[must_use]
+    fn default_access_policy() -> Option<AccessPolicy> {
+        Self::Collection::default_access_policy()
+    }
 }
 
 /// Schema information for a [`View`].
@@ -196,6 +219,17 @@ pub trait ViewSchema: Send + Sync + 'static {
     fn version(&self) -> u64 {
         0
     }
+
+    /// Returns true if documents that have been soft-deleted should continue
+    /// to be mapped by this view. The provided implementation returns false,
+    /// which removes soft-deleted documents from the view until they are
+    /// restored.
+    ///
+    /// This only has an effect on views whose collection uses
+    /// [soft deletion](crate::schema::Collection::soft_delete).
+    fn include_soft_deleted(&self) -> bool {
+        false
+    }
 }
 
 /// The policy under which a [`View`] is updated when documents are saved.
@@ -215,6 +249,17 @@ pub enum ViewUpdatePolicy {
     /// of them fail, the document will not be allowed to update and an
     /// [`Error::UniqueKeyViolation`](crate::Error::UniqueKeyViolation) will be
     /// returned.
+    ///
+    /// The check is performed as part of the transaction that saves the
+    /// document. If a violation is detected, the entire transaction is rolled
+    /// back, leaving no partially updated documents or view entries behind.
+    ///
+    /// Uniqueness is enforced between documents, not between mappings. A
+    /// `map()` function that emits multiple keys for a single document
+    /// requires each of those keys to be unused by every other document. If a
+    /// single document emits the same key more than once, this is not
+    /// considered a violation, and only the last emitted value is stored for
+    /// that key.
     Unique,
 }
 
@@ -378,11 +423,16 @@ pub trait Serialized: Send + Sync {
     fn collection(&self) -> CollectionName;
     /// Returns the description of the view's `Key`.
     fn key_description(&self) -> KeyDescription;
+    /// Returns the name of the view's `Value` type, as returned by
+    /// [`std::any::type_name()`].
+    fn value_type_name(&self) -> &'static str;
     /// Wraps [`ViewSchema::update_policy`]
     fn update_policy(&self) -> ViewUpdatePolicy;
 
     /// Wraps [`ViewSchema::version`]
     fn version(&self) -> u64;
+    /// Wraps [`ViewSchema::include_soft_deleted`]
+    fn include_soft_deleted(&self) -> bool;
     /// Wraps [`View::view_name`]
     fn view_name(&self) -> ViewName;
     /// Wraps [`MapReduce::map`]
@@ -67,12 +67,13 @@ pub struct CollectionMap<PrimaryKey, K = (), V = ()> {
 /// - The key emitted during the map function.
 /// - The value emitted during the map function.
 /// - The source document header that the mappings originated from.
-pub type ViewMappings<V> = Vec<
-    CollectionMap<
-        <<V as View>::Collection as Collection>::PrimaryKey,
-        <V as View>::Key,
-        <V as View>::Value,
-    >,
+pub type ViewMappings<V> = Vec<ViewMapping<V>>;
+
+/// A single mapping returned from querying the view `V`.
+pub type ViewMapping<V> = CollectionMap<
+    <<V as View>::Collection as Collection>::PrimaryKey,
+    <V as View>::Key,
+    <V as View>::Value,
 >;
 
 /// A collection of [`Map`]s.
@@ -273,6 +274,28 @@ impl<D, V: View> MappedDocuments<D, V> {
             None
         }
     }
+
+    /// Returns the mapped documents grouped by key. Each key is returned once,
+    /// in the order the keys were returned by the query.
+    ///
+    /// This is useful when querying multiple keys using
+    /// [`View::with_keys()`](crate::connection::View::with_keys), as the
+    /// results for each key can be processed separately. Keys that matched no
+    /// documents are not included.
+    #[must_use]
+    pub fn grouped_by_key(&self) -> Vec<MappedDocumentGroup<'_, D, V::Key, V::Value>> {
+        let mut groups: Vec<MappedDocumentGroup<'_, D, V::Key, V::Value>> = Vec::new();
+        for mapped in self {
+            match groups.last_mut() {
+                Some(group) if group.key == mapped.key => group.documents.push(mapped),
+                _ => groups.push(MappedDocumentGroup {
+                    key: mapped.key,
+                    documents: vec![mapped],
+                }),
+            }
+        }
+        groups
+    }
 }
 
 impl<D, V: View> Debug for MappedDocuments<D, V>
@@ -328,6 +351,15 @@ pub struct MappedDocument<'a, D, K, V> {
     pub document: &'a D,
 }
 
+/// The mapped documents returned from a view query that share the same key.
+/// Returned from [`MappedDocuments::grouped_by_key()`].
+pub struct MappedDocumentGroup<'a, D, K, V> {
+    /// The key that these documents mapped to.
+    pub key: &'a K,
+    /// The documents that mapped to `key`.
+    pub documents: Vec<MappedDocument<'a, D, K, V>>,
+}
+
 /// Represents a document's entry in a View's mappings, serialized and ready to store.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Serialized {
@@ -0,0 +1,183 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::document::{CollectionHeader, DocumentId, Header};
+use crate::schema::view::map::ViewMappings;
+use crate::schema::{Collection, SerializedView};
+use crate::Error;
+
+/// A [`View`](crate::schema::View) that indexes the terms of its documents for
+/// full-text search.
+///
+/// Each mapping's key is a term and its value is the number of times the term
+/// appears in the source document. The easiest way to produce these mappings
+/// is to call
+/// [`Emit::emit_search_terms()`](crate::document::Emit::emit_search_terms)
+/// from the view's map function. To index multiple fields, combine their
+/// mappings using [`Mappings::and()`](crate::schema::view::map::Mappings::and).
+///
+/// ```rust
+/// # mod collection {
+/// # bonsaidb_core::__doctest_prelude!();
+/// # }
+/// # use collection::MyCollection;
+/// use bonsaidb_core::document::{CollectionDocument, Emit};
+/// use bonsaidb_core::schema::view::search::SearchIndex;
+/// use bonsaidb_core::schema::view::ViewMapResult;
+/// use bonsaidb_core::schema::{CollectionMapReduce, View, ViewSchema};
+///
+/// #[derive(Debug, Clone, View, ViewSchema)]
+/// #[view(collection = MyCollection, key = String, value = u32, name = "search")]
+/// # #[view(core = bonsaidb_core)]
+/// # #[view_schema(core = bonsaidb_core)]
+/// struct MyCollectionSearch;
+///
+/// impl CollectionMapReduce for MyCollectionSearch {
+///     fn map<'doc>(
+///         &self,
+///         document: CollectionDocument<<Self::View as View>::Collection>,
+///     ) -> ViewMapResult<'doc, Self::View> {
+///         document.header.emit_search_terms(&document.contents.name)
+///     }
+/// }
+///
+/// impl SearchIndex for MyCollectionSearch {}
+/// ```
+///
+/// Like any other view, a search index is updated lazily when it is queried,
+/// unless its [`ViewUpdatePolicy`](crate::schema::view::ViewUpdatePolicy) says
+/// otherwise. Search indexes are queried using
+/// [`Connection::search()`](crate::connection::Connection::search) or
+/// [`AsyncConnection::search()`](crate::connection::AsyncConnection::search).
+///
+/// ## Tokenization
+///
+/// Text is split into terms using [`tokenize()`], which is intentionally
+/// simple:
+///
+/// - Terms are runs of alphanumeric characters, as determined by
+///   [`char::is_alphanumeric()`]. All other characters separate terms.
+/// - Terms are lowercased using [`str::to_lowercase()`].
+/// - No stemming is performed, so "run" and "running" are different terms.
+/// - Stop words, such as "the", are not removed.
+/// - Languages that don't separate words with spaces or punctuation, such as
+///   Chinese or Japanese, are not segmented into words. A run of these
+///   characters is indexed as a single term.
+pub trait SearchIndex: SerializedView<Key = String, Value = u32> {}
+
+/// Splits `text` into lowercase search terms. See [`SearchIndex`] for a
+/// description of how text is tokenized.
+///
+/// ```rust
+/// use bonsaidb_core::schema::view::search::tokenize;
+///
+/// assert_eq!(
+///     tokenize("Hello, World! Hello-again.").collect::<Vec<_>>(),
+///     vec!["hello", "world", "hello", "again"]
+/// );
+/// ```
+pub fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|ch: char| !ch.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Returns each term in `text` along with the number of times it appears.
+#[must_use]
+pub fn term_frequencies(text: &str) -> BTreeMap<String, u32> {
+    let mut frequencies = BTreeMap::new();
+    for term in tokenize(text) {
+        *frequencies.entry(term).or_insert(0) += 1;
+    }
+    frequencies
+}
+
+/// Returns the unique terms in `query`, sorted.
+pub(crate) fn query_terms(query: &str) -> Vec<String> {
+    term_frequencies(query).into_keys().collect()
+}
+
+/// A document that matched a search.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SearchResult<PrimaryKey> {
+    /// The header of the matching document.
+    pub header: CollectionHeader<PrimaryKey>,
+    /// The relevance of the document to the search, if ranking was requested.
+    /// Higher scores are more relevant.
+    pub score: Option<f32>,
+}
+
+/// Returns the results of a search from the `mappings` that matched the
+/// search's terms.
+///
+/// If `document_count` is provided, each document is scored using TF-IDF and
+/// the results are sorted by their scores. Each matched term contributes
+/// `(1 + ln(tf)) * (1 + ln((1 + n) / (1 + df)))`, where `tf` is the number of
+/// times the term appears in the document, `n` is `document_count`, and `df`
+/// is the number of documents containing the term. Otherwise, the results are
+/// sorted by document id.
+pub(crate) fn search_results<V: SearchIndex>(
+    mappings: ViewMappings<V>,
+    document_count: Option<u64>,
+    limit: Option<u32>,
+) -> Result<Vec<SearchResult<<V::Collection as Collection>::PrimaryKey>>, Error> {
+    let mut term_documents = HashMap::<String, u32>::new();
+    let mut documents = BTreeMap::<DocumentId, (SearchResult<_>, BTreeMap<String, u32>)>::new();
+    for mapping in mappings {
+        let id = Header::try_from(&mapping.source)?.id;
+        let (_, terms) = documents.entry(id).or_insert_with(|| {
+            (
+                SearchResult {
+                    header: mapping.source,
+                    score: None,
+                },
+                BTreeMap::new(),
+            )
+        });
+        let frequency = terms.entry(mapping.key).or_insert_with_key(|term| {
+            *term_documents.entry(term.clone()).or_insert(0) += 1;
+            0
+        });
+        *frequency = frequency.saturating_add(mapping.value);
+    }
+
+    let mut results = documents
+        .into_values()
+        .map(|(mut result, terms)| {
+            if let Some(document_count) = document_count {
+                #[allow(clippy::cast_precision_loss)]
+                let document_count = document_count as f32;
+                result.score = Some(
+                    terms
+                        .iter()
+                        .filter(|(_, frequency)| **frequency > 0)
+                        .map(|(term, frequency)| {
+                            #[allow(clippy::cast_precision_loss)]
+                            let frequency = *frequency as f32;
+                            #[allow(clippy::cast_precision_loss)]
+                            let containing = term_documents[term] as f32;
+                            let inverse_document_frequency =
+                                ((1. + document_count) / (1. + containing)).ln() + 1.;
+                            (1. + frequency.ln()) * inverse_document_frequency
+                        })
+                        .sum(),
+                );
+            }
+            result
+        })
+        .collect::<Vec<_>>();
+    if document_count.is_some() {
+        // The sort is stable, so documents with equal scores remain ordered
+        // by id.
+        results.sort_by(|a, b| {
+            b.score
+                .unwrap_or_default()
+                .total_cmp(&a.score.unwrap_or_default())
+        });
+    }
+    if let Some(limit) = limit {
+        results.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+    }
+    Ok(results)
+}
@@ -0,0 +1,95 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+
+use arc_bytes::serde::Bytes;
+
+use crate::document::{CollectionDocument, DocumentId, OwnedDocument};
+use crate::schema::{CollectionName, SerializedCollection};
+use crate::Error;
+
+/// A read-only collection whose documents are computed from the documents of
+/// other collections.
+///
+/// Virtual collections are useful for exposing derived data, such as the
+/// result of joining two collections, using the same APIs as any other
+/// collection. They are registered using
+/// [`Schematic::define_virtual_collection()`], which is usually called from
+/// [`Collection::define_views()`](crate::schema::Collection::define_views).
+///
+/// Virtual collections can be read using [`SerializedCollection`] and can
+/// have views, but attempting to modify one returns [`Error::ReadOnly`].
+///
+/// ## Caching
+///
+/// The documents returned by [`Self::compute()`] are stored like any other
+/// collection's documents. They are recomputed when the virtual collection is
+/// read for the first time after any of its [sources](Self::sources) have
+/// been modified, or for the first time after the database is opened. A read
+/// never returns documents computed from outdated sources.
+///
+/// Refreshing loads every document of every source collection, so virtual
+/// collections are best suited to sources that are small or change rarely.
+/// Only documents whose contents changed are written when refreshing, which
+/// means views and [`Trigger`](crate::schema::Trigger)s of a virtual
+/// collection only observe the documents that were actually affected.
+///
+/// [`Schematic::define_virtual_collection()`]: crate::schema::Schematic::define_virtual_collection
+pub trait VirtualCollection: SerializedCollection {
+    /// Returns the collections this collection's documents are computed from.
+    /// Sources may be other virtual collections, but a virtual collection may
+    /// not depend on itself.
+    fn sources() -> Vec<CollectionName>;
+
+    /// Returns the documents of this collection, computed from the documents
+    /// of [`Self::sources()`].
+    fn compute(sources: &Sources) -> Result<BTreeMap<Self::PrimaryKey, Self::Contents>, Error>;
+}
+
+/// The documents of a [`VirtualCollection`]'s sources.
+#[derive(Debug, Default)]
+pub struct Sources {
+    documents: HashMap<CollectionName, Vec<OwnedDocument>>,
+}
+
+impl Sources {
+    /// Returns a new instance containing the documents of each source
+    /// collection.
+    #[must_use]
+    pub fn new(documents: HashMap<CollectionName, Vec<OwnedDocument>>) -> Self {
+        Self { documents }
+    }
+
+    /// Returns the documents of `C`, ordered by id.
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`Error::CollectionNotFound`] if `C` is not a source of the
+    /// virtual collection being computed.
+    pub fn documents<C: SerializedCollection>(&self) -> Result<Vec<CollectionDocument<C>>, Error> {
+        self.serialized_documents(&C::collection_name())
+            .ok_or(Error::CollectionNotFound)?
+            .iter()
+            .map(CollectionDocument::try_from)
+            .collect()
+    }
+
+    /// Returns the serialized documents of `collection`, ordered by id.
+    /// Returns `None` if `collection` is not a source of the virtual
+    /// collection being computed.
+    #[must_use]
+    pub fn serialized_documents(&self, collection: &CollectionName) -> Option<&[OwnedDocument]> {
+        self.documents.get(collection).map(Vec::as_slice)
+    }
+}
+
+/// Wraps a [`VirtualCollection`] with serialization to erase the associated
+/// types.
+pub trait Serialized: Debug + Send + Sync {
+    /// Wraps returing [`<VirtualCollection as Collection>::collection_name()`](crate::schema::Collection::collection_name)
+    fn collection(&self) -> CollectionName;
+    /// Wraps [`VirtualCollection::sources`]
+    fn sources(&self) -> Vec<CollectionName>;
+    /// Invokes [`VirtualCollection::compute()`] and serializes the resulting
+    /// documents.
+    fn compute(&self, sources: &Sources) -> Result<Vec<(DocumentId, Bytes)>, Error>;
+}
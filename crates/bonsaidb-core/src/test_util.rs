@@ -6,27 +6,39 @@ use std::io::ErrorKind;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 
+use async_trait::async_trait;
+use futures::{StreamExt, TryStreamExt};
 use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
 use transmog_pot::Pot;
 
 use crate::admin::{PermissionGroup, Role, User};
+use crate::changes::ChangeOperation;
 use crate::connection::{
-    AccessPolicy, AsyncConnection, AsyncStorageConnection, Connection, StorageConnection,
+    AccessPolicy, AsyncConnection, AsyncStorageConnection, Connection, DatabaseCreation,
+    IndexAccess, QueryPlan, StorageConnection,
 };
 use crate::document::{
     BorrowedDocument, CollectionDocument, CollectionHeader, DocumentId, Emit, Header, KeyId,
 };
-use crate::keyvalue::{AsyncKeyValue, KeyValue};
-use crate::limits::{LIST_TRANSACTIONS_DEFAULT_RESULT_COUNT, LIST_TRANSACTIONS_MAX_RESULTS};
-use crate::schema::view::map::{Mappings, ViewMappedValue};
+use crate::keyvalue::{AsyncKeyValue, KeyValue, Timestamp};
+use crate::limits::{
+    LIST_TRANSACTIONS_DEFAULT_RESULT_COUNT, LIST_TRANSACTIONS_MAX_RESULTS,
+    VIEW_QUERY_STREAM_CHUNK_SIZE,
+};
+use crate::permissions::{Permissions, Statement};
+use crate::pubsub::{AsyncPubSub, PubSub};
+use crate::schema::view::map::{Mappings, ViewMappedValue, ViewMapping};
+use crate::schema::view::search::SearchIndex;
 use crate::schema::view::{MapReduce, ReduceResult, SerializedView, ViewUpdatePolicy};
 use crate::schema::{
-    Collection, CollectionName, MappedValue, NamedCollection, Qualified, Schema, SchemaName,
-    Schematic, SerializedCollection, View, ViewMapResult, ViewSchema,
+    Collection, CollectionName, Filter, InsertError, MappedValue, NamedCollection, Qualified,
+    Schema, SchemaName, Schematic, SerializedCollection, View, ViewMapResult, ViewSchema,
 };
 use crate::transaction::{Operation, OperationResult, Transaction};
 use crate::Error;
@@ -334,7 +346,7 @@ impl MapReduce for EncryptedBasicByCategory {
 }
 
 #[derive(Debug, Schema)]
-#[schema(name = "basic", collections = [Basic, EncryptedBasic, Unique], core = crate)]
+#[schema(name = "basic", collections = [Basic, EncryptedBasic, Unique, SoftDeleted, Article], core = crate)]
 pub struct BasicSchema;
 
 #[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, Default, Collection)]
@@ -367,6 +379,60 @@ impl NamedCollection for Unique {
     type ByNameView = UniqueValue;
 }
 
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, Default, Collection)]
+#[collection(name = "soft-deleted", authority = "khonsulabs", views = [SoftDeletedByValue], soft_delete, core = crate)]
+pub struct SoftDeleted {
+    pub value: String,
+}
+
+impl SoftDeleted {
+    pub fn new(value: impl Display) -> Self {
+        Self {
+            value: value.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, View, ViewSchema)]
+#[view(collection = SoftDeleted, key = String, value = (), name = "by-value", core = crate)]
+#[view_schema(core = crate)]
+pub struct SoftDeletedByValue;
+
+impl MapReduce for SoftDeletedByValue {
+    fn map<'doc>(&self, document: &'doc BorrowedDocument<'_>) -> ViewMapResult<'doc, Self> {
+        let entry = SoftDeleted::document_contents(document)?;
+        document.header.emit_key(entry.value)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq, Default, Collection)]
+#[collection(name = "articles", authority = "khonsulabs", views = [ArticleSearch], core = crate)]
+pub struct Article {
+    pub body: String,
+}
+
+impl Article {
+    pub fn new(body: impl Display) -> Self {
+        Self {
+            body: body.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, View, ViewSchema)]
+#[view(collection = Article, key = String, value = u32, name = "search", core = crate)]
+#[view_schema(core = crate)]
+pub struct ArticleSearch;
+
+impl MapReduce for ArticleSearch {
+    fn map<'doc>(&self, document: &'doc BorrowedDocument<'_>) -> ViewMapResult<'doc, Self> {
+        let article = Article::document_contents(document)?;
+        document.header.emit_search_terms(&article.body)
+    }
+}
+
+impl SearchIndex for ArticleSearch {}
+
 #[derive(Debug)]
 pub struct TestDirectory(pub PathBuf);
 
@@ -466,10 +532,12 @@ pub enum HarnessTest {
     NoUpdate,
     GetMultiple,
     List,
+    SoftDelete,
     ListTransactions,
     Transactions,
     TransactionCheck,
     ViewQuery,
+    ViewQueryStream,
     UnassociatedCollection,
     Compact,
     ViewUpdate,
@@ -495,6 +563,13 @@ pub enum HarnessTest {
     KvExpiration,
     KvDeleteExpire,
     KvTransactions,
+    PubSubPrefix,
+    ChangeFeed,
+    KvCompareAndSwap,
+    KvNamespaces,
+    PubSubBufferLimits,
+    PubSubPublishBatch,
+    Search,
 }
 
 impl HarnessTest {
@@ -502,6 +577,61 @@ impl HarnessTest {
     pub const fn port(self, base: u16) -> u16 {
         base + self as u16
     }
+
+    /// Seeds the randomness used by the test harnesses, making the values
+    /// generated by [`seeded_rng()`] reproducible. Returns false if the seed
+    /// was already chosen, either by a previous call or by generating a
+    /// random value.
+    ///
+    /// The seed can also be provided using the `BONSAIDB_TEST_SEED`
+    /// environment variable.
+    pub fn set_seed(seed: u64) -> bool {
+        TEST_SEED.set(seed).is_ok()
+    }
+
+    /// Returns the seed used by [`seeded_rng()`]. If no seed has been set, the
+    /// `BONSAIDB_TEST_SEED` environment variable is used. If it isn't set, a
+    /// random seed is chosen and printed, allowing a failing run to be
+    /// reproduced.
+    #[must_use]
+    pub fn seed() -> u64 {
+        *TEST_SEED.get_or_init(|| {
+            if let Some(seed) = std::env::var(TEST_SEED_VAR)
+                .ok()
+                .and_then(|seed| seed.parse().ok())
+            {
+                seed
+            } else {
+                let seed = rand::random();
+                eprintln!("Test seed: {seed}. Set {TEST_SEED_VAR}={seed} to reproduce.");
+                seed
+            }
+        })
+    }
+
+    /// Returns a random number generator for this test. See
+    /// [`seeded_rng()`].
+    #[must_use]
+    pub fn rng(self) -> StdRng {
+        seeded_rng(&self.to_string())
+    }
+}
+
+const TEST_SEED_VAR: &str = "BONSAIDB_TEST_SEED";
+
+static TEST_SEED: OnceLock<u64> = OnceLock::new();
+
+/// Returns a random number generator derived from [`HarnessTest::seed()`]
+/// and `label`. Each label produces its own sequence of values, which is the
+/// same on every run using the same seed.
+#[must_use]
+pub fn seeded_rng(label: &str) -> StdRng {
+    // FNV-1a is used rather than `DefaultHasher` because its output is
+    // guaranteed to be the same across platforms and Rust versions.
+    let label_hash = label.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    StdRng::seed_from_u64(HarnessTest::seed() ^ label_hash)
 }
 
 impl Display for HarnessTest {
@@ -510,6 +640,91 @@ impl Display for HarnessTest {
     }
 }
 
+/// A storage implementation that can be opened within the current process,
+/// allowing [`InProcessTestHarness`] to run the test suites without any
+/// networking.
+#[async_trait]
+pub trait InProcessStorage: AsyncStorageConnection {
+    /// Opens a new storage that keeps its data in memory and has the schema
+    /// `S` registered.
+    async fn open_in_memory<S: Schema>() -> anyhow::Result<Self>;
+
+    /// Returns an instance of `database` whose permissions are restricted to
+    /// `permissions`. Returns `None` if `database` is already authenticated.
+    fn with_effective_permissions(
+        database: &Self::Database,
+        permissions: Permissions,
+    ) -> Option<Self::Database>;
+}
+
+/// A test harness that drives an [`InProcessStorage`] directly, without TLS
+/// or sockets. This harness can be used with the test suite macros, such as
+/// [`define_async_connection_test_suite!`](crate::define_async_connection_test_suite),
+/// through a type alias:
+///
+/// ```rust,ignore
+/// type InProcessHarness = InProcessTestHarness<AsyncStorage>;
+/// bonsaidb_core::define_async_connection_test_suite!(InProcessHarness);
+/// ```
+#[derive(Debug)]
+pub struct InProcessTestHarness<S: InProcessStorage> {
+    storage: S,
+    db: S::Database,
+}
+
+impl<S> InProcessTestHarness<S>
+where
+    S: InProcessStorage,
+    S::Database: Clone,
+{
+    /// Opens a new in-memory storage with [`BasicSchema`] registered and
+    /// creates the "tests" database used by the test suites.
+    pub async fn new(_test: HarnessTest) -> anyhow::Result<Self> {
+        let storage = S::open_in_memory::<BasicSchema>().await?;
+        let db = storage
+            .create_database::<BasicSchema>("tests", false)
+            .await?;
+
+        Ok(Self { storage, db })
+    }
+
+    /// Returns the name used to distinguish this harness from others.
+    #[must_use]
+    pub const fn server_name() -> &'static str {
+        "in-process"
+    }
+
+    /// Returns the storage being tested.
+    #[must_use]
+    pub const fn server(&self) -> &S {
+        &self.storage
+    }
+
+    /// Returns the "tests" database.
+    #[allow(clippy::unused_async)] // The test suites await this function.
+    pub async fn connect(&self) -> anyhow::Result<S::Database> {
+        Ok(self.db.clone())
+    }
+
+    /// Returns the "tests" database, restricted to `permissions`.
+    #[allow(clippy::unused_async)] // The test suites await this function.
+    pub async fn connect_with_permissions(
+        &self,
+        permissions: Vec<Statement>,
+        _label: &str,
+    ) -> anyhow::Result<S::Database> {
+        S::with_effective_permissions(&self.db, Permissions::from(permissions))
+            .ok_or_else(|| anyhow::anyhow!("the tests database is already authenticated"))
+    }
+
+    /// Shuts down the harness. The storage is closed when the harness is
+    /// dropped.
+    #[allow(clippy::unused_async)] // The test suites await this function.
+    pub async fn shutdown(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
 /// Compares two f64's accounting for the epsilon.
 #[macro_export]
 macro_rules! assert_f64_eq {
@@ -602,6 +817,15 @@ macro_rules! define_async_connection_test_suite {
                 harness.shutdown().await
             }
 
+            #[tokio::test]
+            async fn soft_delete() -> anyhow::Result<()> {
+                let harness = $harness::new($crate::test_util::HarnessTest::SoftDelete).await?;
+                let db = harness.connect().await?;
+
+                $crate::test_util::soft_delete_tests(&db).await?;
+                harness.shutdown().await
+            }
+
             #[tokio::test]
             async fn list() -> anyhow::Result<()> {
                 let harness = $harness::new($crate::test_util::HarnessTest::List).await?;
@@ -649,6 +873,16 @@ macro_rules! define_async_connection_test_suite {
                 harness.shutdown().await
             }
 
+            #[tokio::test]
+            async fn view_query_stream() -> anyhow::Result<()> {
+                let harness =
+                    $harness::new($crate::test_util::HarnessTest::ViewQueryStream).await?;
+                let db = harness.connect().await?;
+
+                $crate::test_util::view_query_stream_tests(&db).await?;
+                harness.shutdown().await
+            }
+
             #[tokio::test]
             async fn unassociated_collection() -> anyhow::Result<()> {
                 let harness =
@@ -706,6 +940,15 @@ macro_rules! define_async_connection_test_suite {
                 harness.shutdown().await
             }
 
+            #[tokio::test]
+            async fn search() -> anyhow::Result<()> {
+                let harness = $harness::new($crate::test_util::HarnessTest::Search).await?;
+                let db = harness.connect().await?;
+
+                $crate::test_util::search_tests(&db).await?;
+                harness.shutdown().await
+            }
+
             #[tokio::test]
             async fn named_collection() -> anyhow::Result<()> {
                 let harness =
@@ -847,6 +1090,15 @@ macro_rules! define_blocking_connection_test_suite {
                 harness.shutdown()
             }
 
+            #[test]
+            fn soft_delete() -> anyhow::Result<()> {
+                let harness = $harness::new($crate::test_util::HarnessTest::SoftDelete)?;
+                let db = harness.connect()?;
+
+                $crate::test_util::blocking_soft_delete_tests(&db)?;
+                harness.shutdown()
+            }
+
             #[test]
             fn list() -> anyhow::Result<()> {
                 let harness = $harness::new($crate::test_util::HarnessTest::List)?;
@@ -892,6 +1144,15 @@ macro_rules! define_blocking_connection_test_suite {
                 harness.shutdown()
             }
 
+            #[test]
+            fn view_query_iter() -> anyhow::Result<()> {
+                let harness = $harness::new($crate::test_util::HarnessTest::ViewQueryStream)?;
+                let db = harness.connect()?;
+
+                $crate::test_util::blocking_view_query_iter_tests(&db)?;
+                harness.shutdown()
+            }
+
             #[test]
             fn unassociated_collection() -> anyhow::Result<()> {
                 let harness =
@@ -948,6 +1209,15 @@ macro_rules! define_blocking_connection_test_suite {
                 harness.shutdown()
             }
 
+            #[test]
+            fn search() -> anyhow::Result<()> {
+                let harness = $harness::new($crate::test_util::HarnessTest::Search)?;
+                let db = harness.connect()?;
+
+                $crate::test_util::blocking_search_tests(&db)?;
+                harness.shutdown()
+            }
+
             #[test]
             fn cow_views() -> anyhow::Result<()> {
                 let harness = $harness::new($crate::test_util::HarnessTest::ViewCow)?;
@@ -1104,6 +1374,42 @@ pub async fn store_retrieve_update_delete_tests<C: AsyncConnection>(db: &C) -> a
     assert_eq!(docs[0].contents.value, "44");
     assert_eq!(docs[1].contents.value, "45");
 
+    // Test that a failing bulk insert reports the failing document and rolls
+    // back the entire batch.
+    Unique::new("insert-many-existing")
+        .push_into_async(db)
+        .await
+        .unwrap();
+    let batch = || {
+        [
+            Unique::new("insert-many-a"),
+            Unique::new("insert-many-existing"),
+            Unique::new("insert-many-b"),
+        ]
+    };
+    let err = Unique::insert_many_async(batch(), db).await.unwrap_err();
+    assert_eq!(err.index, Some(1));
+    assert_eq!(err.contents, batch());
+    assert!(matches!(err.error, Error::UniqueKeyViolation { .. }));
+    assert!(Unique::load_async("insert-many-a", db).await?.is_none());
+
+    // Continuing past the failure inserts the rest of the batch.
+    let results = Unique::insert_many_allowing_failures_async(batch(), db).await;
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap().contents.value, "insert-many-a");
+    assert!(matches!(
+        results[1],
+        Err(InsertError {
+            error: Error::UniqueKeyViolation { .. },
+            ..
+        })
+    ));
+    assert_eq!(results[2].as_ref().unwrap().contents.value, "insert-many-b");
+    let docs = Unique::insert_many_async([Unique::new("insert-many-c")], db)
+        .await
+        .unwrap();
+    assert_eq!(docs[0].contents.value, "insert-many-c");
+
     Ok(())
 }
 
@@ -1191,6 +1497,37 @@ pub fn blocking_store_retrieve_update_delete_tests<C: Connection>(db: &C) -> any
     assert_eq!(docs[0].contents.value, "44");
     assert_eq!(docs[1].contents.value, "45");
 
+    // Test that a failing bulk insert reports the failing document and rolls
+    // back the entire batch.
+    Unique::new("insert-many-existing").push_into(db).unwrap();
+    let batch = || {
+        [
+            Unique::new("insert-many-a"),
+            Unique::new("insert-many-existing"),
+            Unique::new("insert-many-b"),
+        ]
+    };
+    let err = Unique::insert_many(batch(), db).unwrap_err();
+    assert_eq!(err.index, Some(1));
+    assert_eq!(err.contents, batch());
+    assert!(matches!(err.error, Error::UniqueKeyViolation { .. }));
+    assert!(Unique::load("insert-many-a", db)?.is_none());
+
+    // Continuing past the failure inserts the rest of the batch.
+    let results = Unique::insert_many_allowing_failures(batch(), db);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap().contents.value, "insert-many-a");
+    assert!(matches!(
+        results[1],
+        Err(InsertError {
+            error: Error::UniqueKeyViolation { .. },
+            ..
+        })
+    ));
+    assert_eq!(results[2].as_ref().unwrap().contents.value, "insert-many-b");
+    let docs = Unique::insert_many([Unique::new("insert-many-c")], db).unwrap();
+    assert_eq!(docs[0].contents.value, "insert-many-c");
+
     Ok(())
 }
 
@@ -1333,6 +1670,27 @@ pub async fn conflict_tests<C: AsyncConnection>(db: &C) -> anyhow::Result<()> {
     let doc = Basic::get_async(&doc.header.id, db).await?.unwrap();
     assert_eq!(doc.contents.value, "modify worked");
 
+    // Updating a stale document fails once the retry limit is exhausted, and
+    // succeeds when a retry is allowed.
+    let mut stale = doc.clone();
+    let mut current = doc;
+    current.contents.value = String::from("current");
+    current.update_async(db).await?;
+    let result = stale
+        .clone()
+        .update_with_retry_async(db, 0, |doc| {
+            doc.contents.value = String::from("retried");
+        })
+        .await;
+    assert!(matches!(result, Err(Error::DocumentConflict(..))));
+    stale
+        .update_with_retry_async(db, 1, |doc| {
+            doc.contents.value = String::from("retried");
+        })
+        .await?;
+    let doc = Basic::get_async(&stale.header.id, db).await?.unwrap();
+    assert_eq!(doc.contents.value, "retried");
+
     Ok(())
 }
 
@@ -1372,6 +1730,22 @@ pub fn blocking_conflict_tests<C: Connection>(db: &C) -> anyhow::Result<()> {
     let doc = Basic::get(&doc.header.id, db)?.unwrap();
     assert_eq!(doc.contents.value, "modify worked");
 
+    // Updating a stale document fails once the retry limit is exhausted, and
+    // succeeds when a retry is allowed.
+    let mut stale = doc.clone();
+    let mut current = doc;
+    current.contents.value = String::from("current");
+    current.update(db)?;
+    let result = stale.clone().update_with_retry(db, 0, |doc| {
+        doc.contents.value = String::from("retried");
+    });
+    assert!(matches!(result, Err(Error::DocumentConflict(..))));
+    stale.update_with_retry(db, 1, |doc| {
+        doc.contents.value = String::from("retried");
+    })?;
+    let doc = Basic::get(&stale.header.id, db)?.unwrap();
+    assert_eq!(doc.contents.value, "retried");
+
     Ok(())
 }
 
@@ -1440,22 +1814,24 @@ pub async fn get_multiple_tests<C: AsyncConnection>(db: &C) -> anyhow::Result<()
 
     let both_docs = Basic::get_multiple_async([&doc1.id, &doc2.id], db).await?;
     assert_eq!(both_docs.len(), 2);
+    assert_eq!(both_docs[0].header.id, doc1.id);
+    assert_eq!(both_docs[0].contents.value, doc1_value.value);
+    assert_eq!(both_docs[1].header.id, doc2.id);
+    assert_eq!(both_docs[1].contents.value, doc2_value.value);
 
+    // Documents are returned in the order requested.
     let out_of_order = Basic::get_multiple_async([&doc2.id, &doc1.id], db).await?;
     assert_eq!(out_of_order.len(), 2);
+    assert_eq!(out_of_order[0].header.id, doc2.id);
+    assert_eq!(out_of_order[1].header.id, doc1.id);
 
-    // The order of get_multiple isn't guaranteed, so these two checks are done
-    // with iterators instead of direct indexing
-    let doc1 = both_docs
-        .iter()
-        .find(|doc| doc.header.id == doc1.id)
-        .expect("Couldn't find doc1");
-    assert_eq!(doc1.contents.value, doc1_value.value);
-    let doc2 = both_docs
-        .iter()
-        .find(|doc| doc.header.id == doc2.id)
-        .expect("Couldn't find doc2");
-    assert_eq!(doc2.contents.value, doc2_value.value);
+    // Missing ids are skipped, and duplicate ids only return the document once.
+    let missing_id = doc1.id.max(doc2.id) + 1;
+    let with_missing_and_duplicates =
+        Basic::get_multiple_async([&doc2.id, &missing_id, &doc1.id, &doc2.id], db).await?;
+    assert_eq!(with_missing_and_duplicates.len(), 2);
+    assert_eq!(with_missing_and_duplicates[0].header.id, doc2.id);
+    assert_eq!(with_missing_and_duplicates[1].header.id, doc1.id);
 
     Ok(())
 }
@@ -1470,22 +1846,24 @@ pub fn blocking_get_multiple_tests<C: Connection>(db: &C) -> anyhow::Result<()>
 
     let both_docs = Basic::get_multiple([&doc1.id, &doc2.id], db)?;
     assert_eq!(both_docs.len(), 2);
+    assert_eq!(both_docs[0].header.id, doc1.id);
+    assert_eq!(both_docs[0].contents.value, doc1_value.value);
+    assert_eq!(both_docs[1].header.id, doc2.id);
+    assert_eq!(both_docs[1].contents.value, doc2_value.value);
 
+    // Documents are returned in the order requested.
     let out_of_order = Basic::get_multiple([&doc2.id, &doc1.id], db)?;
     assert_eq!(out_of_order.len(), 2);
+    assert_eq!(out_of_order[0].header.id, doc2.id);
+    assert_eq!(out_of_order[1].header.id, doc1.id);
 
-    // The order of get_multiple isn't guaranteed, so these two checks are done
-    // with iterators instead of direct indexing
-    let doc1 = both_docs
-        .iter()
-        .find(|doc| doc.header.id == doc1.id)
-        .expect("Couldn't find doc1");
-    assert_eq!(doc1.contents.value, doc1_value.value);
-    let doc2 = both_docs
-        .iter()
-        .find(|doc| doc.header.id == doc2.id)
-        .expect("Couldn't find doc2");
-    assert_eq!(doc2.contents.value, doc2_value.value);
+    // Missing ids are skipped, and duplicate ids only return the document once.
+    let missing_id = doc1.id.max(doc2.id) + 1;
+    let with_missing_and_duplicates =
+        Basic::get_multiple([&doc2.id, &missing_id, &doc1.id, &doc2.id], db)?;
+    assert_eq!(with_missing_and_duplicates.len(), 2);
+    assert_eq!(with_missing_and_duplicates[0].header.id, doc2.id);
+    assert_eq!(with_missing_and_duplicates[1].header.id, doc1.id);
 
     Ok(())
 }
@@ -1523,6 +1901,41 @@ pub async fn list_tests<C: AsyncConnection>(db: &C) -> anyhow::Result<()> {
     assert_eq!(limited.len(), 1);
     assert_eq!(limited[0].contents.value, doc2_value.value);
 
+    for i in 0..5 {
+        collection.push(&Basic::new(format!("scanned-{i}"))).await?;
+    }
+    let all_docs = Basic::all_async(db).await?;
+    assert_eq!(all_docs.len(), 7);
+    // Batch sizes that evenly divide the collection must still terminate.
+    for batch_size in [1, 2, 7, 100] {
+        let scanned = Basic::scan_async(db)
+            .batch_size(batch_size)
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert_eq!(scanned, all_docs);
+    }
+    assert_eq!(
+        Basic::scan_async(db).try_collect::<Vec<_>>().await?,
+        all_docs
+    );
+
+    let filtered = Basic::filter_async(&Filter::equal("value", "second_value")?, db).await?;
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].header.id, doc2.id);
+    let scanned = Filter::range("value", "scanned-1"..="scanned-3")?;
+    let filtered = Basic::filter_async(&scanned, db).await?;
+    assert_eq!(
+        filtered
+            .iter()
+            .map(|doc| doc.contents.value.as_str())
+            .collect::<Vec<_>>(),
+        ["scanned-1", "scanned-2", "scanned-3"]
+    );
+    let limited = db.filter::<Basic>(&scanned, Some(2)).await?;
+    assert_eq!(limited.len(), 2);
+    let excluded = Basic::filter_async(&!scanned, db).await?;
+    assert_eq!(excluded.len(), 4);
+
     Ok(())
 }
 
@@ -1559,6 +1972,164 @@ pub fn blocking_list_tests<C: Connection>(db: &C) -> anyhow::Result<()> {
     assert_eq!(limited.len(), 1);
     assert_eq!(limited[0].contents.value, doc2_value.value);
 
+    for i in 0..5 {
+        collection.push(&Basic::new(format!("scanned-{i}")))?;
+    }
+    let all_docs = Basic::all(db).query()?;
+    assert_eq!(all_docs.len(), 7);
+    // Batch sizes that evenly divide the collection must still terminate.
+    for batch_size in [1, 2, 7, 100] {
+        let scanned = Basic::scan(db)
+            .batch_size(batch_size)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(scanned, all_docs);
+    }
+    assert_eq!(Basic::scan(db).collect::<Result<Vec<_>, _>>()?, all_docs);
+
+    let filtered = Basic::filter(&Filter::equal("value", "second_value")?, db)?;
+    assert_eq!(filtered.len(), 1);
+    assert_eq!(filtered[0].header.id, doc2.id);
+    let scanned = Filter::range("value", "scanned-1"..="scanned-3")?;
+    let filtered = Basic::filter(&scanned, db)?;
+    assert_eq!(
+        filtered
+            .iter()
+            .map(|doc| doc.contents.value.as_str())
+            .collect::<Vec<_>>(),
+        ["scanned-1", "scanned-2", "scanned-3"]
+    );
+    let limited = db.filter::<Basic>(&scanned, Some(2))?;
+    assert_eq!(limited.len(), 2);
+    let excluded = Basic::filter(&!scanned, db)?;
+    assert_eq!(excluded.len(), 4);
+
+    Ok(())
+}
+
+pub async fn soft_delete_tests<C: AsyncConnection>(db: &C) -> anyhow::Result<()> {
+    let a = SoftDeleted::new("a").push_into_async(db).await?;
+    let b = SoftDeleted::new("b").push_into_async(db).await?;
+    let c = SoftDeleted::new("c").push_into_async(db).await?;
+    // Query the view once so that it is mapped before the deletions.
+    assert_eq!(db.view::<SoftDeletedByValue>().query().await?.len(), 3);
+
+    b.delete_async(db).await?;
+    c.delete_async(db).await?;
+
+    // Deleted documents are hidden from get, list, and views.
+    assert!(SoftDeleted::get_async(&b.header.id, db).await?.is_none());
+    let remaining = SoftDeleted::all_async(db).await?;
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].header.id, a.header.id);
+    let mapped = db.view::<SoftDeletedByValue>().query().await?;
+    assert_eq!(mapped.len(), 1);
+    assert_eq!(mapped[0].key, "a");
+
+    let everything = SoftDeleted::list_including_deleted_async(.., db).await?;
+    assert_eq!(
+        everything
+            .iter()
+            .map(|doc| (doc.document.contents.value.as_str(), doc.is_deleted()))
+            .collect::<Vec<_>>(),
+        [("a", false), ("b", true), ("c", true)]
+    );
+    assert!(everything[1].deleted_at.unwrap() <= Timestamp::now());
+
+    // The id of a soft-deleted document must not be reused.
+    let d = SoftDeleted::new("d").push_into_async(db).await?;
+    assert!(d.header.id > c.header.id);
+
+    let restored = SoftDeleted::restore_async(&b.header.id, db).await?;
+    assert_eq!(restored.contents, b.contents);
+    assert_eq!(SoftDeleted::all_async(db).count().await?, 3);
+    let mapped = db.view::<SoftDeletedByValue>().query().await?;
+    assert_eq!(
+        mapped
+            .iter()
+            .map(|map| map.key.as_str())
+            .collect::<Vec<_>>(),
+        ["a", "b", "d"]
+    );
+    assert_eq!(
+        SoftDeleted::list_including_deleted_async(.., db)
+            .await?
+            .len(),
+        4
+    );
+
+    // Restoring a document that isn't deleted fails.
+    let result = SoftDeleted::restore_async(&b.header.id, db)
+        .await
+        .unwrap_err();
+    assert!(matches!(result, Error::DocumentNotFound(_, _)));
+
+    // Collections without soft deletes have nothing to restore.
+    let basic = Basic::new("basic").push_into_async(db).await?;
+    basic.delete_async(db).await?;
+    let result = Basic::restore_async(&basic.header.id, db)
+        .await
+        .unwrap_err();
+    assert!(matches!(result, Error::DocumentNotFound(_, _)));
+
+    Ok(())
+}
+
+pub fn blocking_soft_delete_tests<C: Connection>(db: &C) -> anyhow::Result<()> {
+    let a = SoftDeleted::new("a").push_into(db)?;
+    let b = SoftDeleted::new("b").push_into(db)?;
+    let c = SoftDeleted::new("c").push_into(db)?;
+    // Query the view once so that it is mapped before the deletions.
+    assert_eq!(db.view::<SoftDeletedByValue>().query()?.len(), 3);
+
+    b.delete(db)?;
+    c.delete(db)?;
+
+    // Deleted documents are hidden from get, list, and views.
+    assert!(SoftDeleted::get(&b.header.id, db)?.is_none());
+    let remaining = SoftDeleted::all(db).query()?;
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].header.id, a.header.id);
+    let mapped = db.view::<SoftDeletedByValue>().query()?;
+    assert_eq!(mapped.len(), 1);
+    assert_eq!(mapped[0].key, "a");
+
+    let everything = SoftDeleted::list_including_deleted(.., db)?;
+    assert_eq!(
+        everything
+            .iter()
+            .map(|doc| (doc.document.contents.value.as_str(), doc.is_deleted()))
+            .collect::<Vec<_>>(),
+        [("a", false), ("b", true), ("c", true)]
+    );
+    assert!(everything[1].deleted_at.unwrap() <= Timestamp::now());
+
+    // The id of a soft-deleted document must not be reused.
+    let d = SoftDeleted::new("d").push_into(db)?;
+    assert!(d.header.id > c.header.id);
+
+    let restored = SoftDeleted::restore(&b.header.id, db)?;
+    assert_eq!(restored.contents, b.contents);
+    assert_eq!(SoftDeleted::all(db).count()?, 3);
+    let mapped = db.view::<SoftDeletedByValue>().query()?;
+    assert_eq!(
+        mapped
+            .iter()
+            .map(|map| map.key.as_str())
+            .collect::<Vec<_>>(),
+        ["a", "b", "d"]
+    );
+    assert_eq!(SoftDeleted::list_including_deleted(.., db)?.len(), 4);
+
+    // Restoring a document that isn't deleted fails.
+    let result = SoftDeleted::restore(&b.header.id, db).unwrap_err();
+    assert!(matches!(result, Error::DocumentNotFound(_, _)));
+
+    // Collections without soft deletes have nothing to restore.
+    let basic = Basic::new("basic").push_into(db)?;
+    basic.delete(db)?;
+    let result = Basic::restore(&basic.header.id, db).unwrap_err();
+    assert!(matches!(result, Error::DocumentNotFound(_, _)));
+
     Ok(())
 }
 
@@ -1617,6 +2188,165 @@ pub async fn list_transactions_tests<C: AsyncConnection>(db: &C) -> anyhow::Resu
     Ok(())
 }
 
+pub async fn watch_collection_tests<C: AsyncConnection + AsyncPubSub>(
+    db: &C,
+) -> anyhow::Result<()> {
+    Basic::new("unwatched").push_into_async(db).await?;
+    let since = db
+        .last_transaction_id()
+        .await?
+        .expect("a transaction was just executed");
+
+    let mut a = Basic::new("a").push_into_async(db).await?;
+    let mut b = Basic::new("b").push_into_async(db).await?;
+    b.contents.value = String::from("b2");
+    b.update_async(db).await?;
+    let c = Basic::new("c").push_into_async(db).await?;
+    c.delete_async(db).await?;
+
+    // Changes made before watching are replayed using the current state of
+    // each document.
+    let mut changes = db.watch_collection::<Basic>().since_sequence(since);
+    let mut replayed = Vec::new();
+    for _ in 0..4 {
+        let change = changes.next().await.expect("stream ended")?;
+        replayed.push((
+            change.operation,
+            change.id,
+            change.contents.map(|contents| contents.value),
+        ));
+    }
+    assert_eq!(
+        replayed,
+        [
+            (
+                ChangeOperation::Insert,
+                a.header.id,
+                Some(String::from("a"))
+            ),
+            (
+                ChangeOperation::Update,
+                b.header.id,
+                Some(String::from("b2"))
+            ),
+            (
+                ChangeOperation::Update,
+                b.header.id,
+                Some(String::from("b2"))
+            ),
+            (ChangeOperation::Delete, c.header.id, None),
+        ]
+    );
+
+    // New changes are delivered as they are executed.
+    let mut deletes = db
+        .watch_collection::<Basic>()
+        .operations([ChangeOperation::Delete])
+        .since_sequence(since);
+    let change = deletes.next().await.expect("stream ended")?;
+    assert_eq!(change.operation, ChangeOperation::Delete);
+    assert_eq!(change.id, c.header.id);
+    assert!(change.header.is_none());
+
+    a.contents.value = String::from("a2");
+    a.update_async(db).await?;
+    a.delete_async(db).await?;
+
+    let change = changes.next().await.expect("stream ended")?;
+    assert_eq!(change.operation, ChangeOperation::Update);
+    assert_eq!(change.header, Some(a.header.clone()));
+    assert_eq!(change.contents.unwrap().value, "a2");
+    let change = changes.next().await.expect("stream ended")?;
+    assert_eq!(change.operation, ChangeOperation::Delete);
+    assert_eq!(change.header, Some(a.header.clone()));
+    assert!(change.contents.is_none());
+
+    let change = deletes.next().await.expect("stream ended")?;
+    assert_eq!(change.operation, ChangeOperation::Delete);
+    assert_eq!(change.id, a.header.id);
+
+    Ok(())
+}
+
+pub fn blocking_watch_collection_tests<C: Connection + PubSub>(db: &C) -> anyhow::Result<()> {
+    Basic::new("unwatched").push_into(db)?;
+    let since = db
+        .last_transaction_id()?
+        .expect("a transaction was just executed");
+
+    let mut a = Basic::new("a").push_into(db)?;
+    let mut b = Basic::new("b").push_into(db)?;
+    b.contents.value = String::from("b2");
+    b.update(db)?;
+    let c = Basic::new("c").push_into(db)?;
+    c.delete(db)?;
+
+    // Changes made before watching are replayed using the current state of
+    // each document.
+    let mut changes = db.watch_collection::<Basic>().since_sequence(since);
+    let replayed = changes
+        .by_ref()
+        .take(4)
+        .map(|change| {
+            change.map(|change| {
+                (
+                    change.operation,
+                    change.id,
+                    change.contents.map(|contents| contents.value),
+                )
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        replayed,
+        [
+            (
+                ChangeOperation::Insert,
+                a.header.id,
+                Some(String::from("a"))
+            ),
+            (
+                ChangeOperation::Update,
+                b.header.id,
+                Some(String::from("b2"))
+            ),
+            (
+                ChangeOperation::Update,
+                b.header.id,
+                Some(String::from("b2"))
+            ),
+            (ChangeOperation::Delete, c.header.id, None),
+        ]
+    );
+
+    // New changes are delivered as they are executed.
+    let mut deletes = db
+        .watch_collection::<Basic>()
+        .operations([ChangeOperation::Delete])
+        .since_sequence(since);
+    let change = deletes.next().expect("iterator ended")?;
+    assert_eq!(change.operation, ChangeOperation::Delete);
+    assert_eq!(change.id, c.header.id);
+
+    a.contents.value = String::from("a2");
+    a.update(db)?;
+    a.delete(db)?;
+
+    let change = changes.next().expect("iterator ended")?;
+    assert_eq!(change.operation, ChangeOperation::Update);
+    assert_eq!(change.header, Some(a.header.clone()));
+    assert_eq!(change.contents.unwrap().value, "a2");
+    let change = changes.next().expect("iterator ended")?;
+    assert_eq!(change.operation, ChangeOperation::Delete);
+    assert!(change.contents.is_none());
+
+    let change = deletes.next().expect("iterator ended")?;
+    assert_eq!(change.operation, ChangeOperation::Delete);
+    assert_eq!(change.id, a.header.id);
+
+    Ok(())
+}
+
 pub fn blocking_list_transactions_tests<C: Connection + Clone + 'static>(
     db: &C,
 ) -> anyhow::Result<()> {
@@ -1712,6 +2442,31 @@ pub async fn transaction_tests<C: AsyncConnection + 'static>(db: &C) -> anyhow::
     doc.delete_in_transaction(&mut tx)?;
     tx.apply_async(db).await?;
 
+    // Change multiple collections in a single transaction.
+    let results = db
+        .transaction(|tx| {
+            Basic::new("atomic").push_in_transaction(tx)?;
+            Unique::new("atomic").push_in_transaction(tx)
+        })
+        .await?;
+    assert_eq!(results.len(), 2);
+    let unique_count = Unique::all_async(db).count().await?;
+
+    // A conflict in one collection prevents the changes to the other
+    // collection from being applied.
+    let mut doc = Basic::new("test").push_into_async(db).await?;
+    let stale = doc.clone();
+    doc.contents.value = String::from("updated");
+    doc.update_async(db).await?;
+    let result = db
+        .transaction(|tx| {
+            Unique::new("conflicted").push_in_transaction(tx)?;
+            stale.update_in_transaction(tx)
+        })
+        .await;
+    assert!(matches!(result, Err(Error::DocumentConflict(..))));
+    assert_eq!(Unique::all_async(db).count().await?, unique_count);
+
     Ok(())
 }
 
@@ -1745,6 +2500,27 @@ pub fn blocking_transaction_tests<C: Connection + 'static>(db: &C) -> anyhow::Re
     doc.delete_in_transaction(&mut tx)?;
     tx.apply(db)?;
 
+    // Change multiple collections in a single transaction.
+    let results = db.transaction(|tx| {
+        Basic::new("atomic").push_in_transaction(tx)?;
+        Unique::new("atomic").push_in_transaction(tx)
+    })?;
+    assert_eq!(results.len(), 2);
+    let unique_count = Unique::all(db).count()?;
+
+    // A conflict in one collection prevents the changes to the other
+    // collection from being applied.
+    let mut doc = Basic::new("test").push_into(db)?;
+    let stale = doc.clone();
+    doc.contents.value = String::from("updated");
+    doc.update(db)?;
+    let result = db.transaction(|tx| {
+        Unique::new("conflicted").push_in_transaction(tx)?;
+        stale.update_in_transaction(tx)
+    });
+    assert!(matches!(result, Err(Error::DocumentConflict(..))));
+    assert_eq!(Unique::all(db).count()?, unique_count);
+
     Ok(())
 }
 
@@ -1828,6 +2604,132 @@ pub fn blocking_transaction_check_tests<C: Connection + 'static>(db: &C) -> anyh
     Ok(())
 }
 
+pub async fn view_query_stream_tests<C: AsyncConnection>(db: &C) -> anyhow::Result<()> {
+    // Insert enough documents to require multiple chunks.
+    let total = VIEW_QUERY_STREAM_CHUNK_SIZE * 2 + 42;
+    let mut tx = Transaction::new();
+    for i in 0..total {
+        tx.push(Operation::push_serialized::<Unique>(&Unique::new(
+            format!("{i:05}"),
+        ))?);
+    }
+    tx.apply_async(db).await?;
+
+    let keys = |mappings: Vec<ViewMapping<UniqueValue>>| {
+        mappings
+            .into_iter()
+            .map(|mapping| mapping.key)
+            .collect::<Vec<_>>()
+    };
+    let expected = (0..total).map(|i| format!("{i:05}")).collect::<Vec<_>>();
+
+    let streamed = db
+        .view::<UniqueValue>()
+        .query_stream()
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(keys(streamed), expected);
+    // query() is built on the same stream and must agree.
+    assert_eq!(keys(db.view::<UniqueValue>().query().await?), expected);
+
+    let descending = db
+        .view::<UniqueValue>()
+        .descending()
+        .query_stream()
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(
+        keys(descending),
+        expected.iter().rev().cloned().collect::<Vec<_>>()
+    );
+
+    let limit = VIEW_QUERY_STREAM_CHUNK_SIZE + 1;
+    let limited = db
+        .view::<UniqueValue>()
+        .limit(limit)
+        .query_stream()
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(keys(limited), expected[..limit as usize]);
+
+    let start = String::from("00010");
+    let end = format!("{:05}", VIEW_QUERY_STREAM_CHUNK_SIZE + 20);
+    let ranged = db
+        .view::<UniqueValue>()
+        .with_key_range(start.clone()..end.clone())
+        .query_stream()
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(
+        keys(ranged),
+        expected
+            .iter()
+            .filter(|key| **key >= start && **key < end)
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+
+    // The stream can be consumed incrementally.
+    let mut stream = db.view::<UniqueValue>().query_stream();
+    let first = stream
+        .try_next()
+        .await?
+        .expect("stream should not be empty");
+    assert_eq!(first.key, expected[0]);
+
+    Ok(())
+}
+
+pub fn blocking_view_query_iter_tests<C: Connection>(db: &C) -> anyhow::Result<()> {
+    // Insert enough documents to require multiple chunks.
+    let total = VIEW_QUERY_STREAM_CHUNK_SIZE * 2 + 42;
+    let mut tx = Transaction::new();
+    for i in 0..total {
+        tx.push(Operation::push_serialized::<Unique>(&Unique::new(
+            format!("{i:05}"),
+        ))?);
+    }
+    tx.apply(db)?;
+
+    let keys = |mappings: Vec<ViewMapping<UniqueValue>>| {
+        mappings
+            .into_iter()
+            .map(|mapping| mapping.key)
+            .collect::<Vec<_>>()
+    };
+    let expected = (0..total).map(|i| format!("{i:05}")).collect::<Vec<_>>();
+
+    let iterated = db
+        .view::<UniqueValue>()
+        .query_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(keys(iterated), expected);
+
+    let descending = db
+        .view::<UniqueValue>()
+        .descending()
+        .query_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        keys(descending),
+        expected.iter().rev().cloned().collect::<Vec<_>>()
+    );
+
+    let limit = VIEW_QUERY_STREAM_CHUNK_SIZE + 1;
+    let limited = db
+        .view::<UniqueValue>()
+        .limit(limit)
+        .query_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(keys(limited), expected[..limit as usize]);
+
+    let mut iter = db.view::<UniqueValue>().query_iter();
+    let first = iter.next().expect("iterator should not be empty")?;
+    assert_eq!(first.key, expected[0]);
+
+    Ok(())
+}
+
 pub async fn view_query_tests<C: AsyncConnection>(db: &C) -> anyhow::Result<()> {
     let collection = db.collection::<Basic>();
     let a = collection.push(&Basic::new("A")).await?;
@@ -1883,6 +2785,31 @@ pub async fn view_query_tests<C: AsyncConnection>(db: &C) -> anyhow::Result<()>
         .await?;
     assert_eq!(a_and_b_children.len(), 3);
 
+    // Test duplicate keys and grouping the results by key
+    let a_and_b_children = db
+        .view::<BasicByParentId>()
+        .with_keys([&Some(a.id), &Some(b.id), &Some(a.id)])
+        .query_with_collection_docs()
+        .await?;
+    assert_eq!(a_and_b_children.len(), 3);
+    let groups = a_and_b_children.grouped_by_key();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].key, &Some(a.id));
+    assert_eq!(groups[0].documents.len(), 1);
+    assert_eq!(groups[1].key, &Some(b.id));
+    assert_eq!(groups[1].documents.len(), 2);
+
+    // Test limiting and descending order with multiple keys
+    let last_children = db
+        .view::<BasicByParentId>()
+        .with_keys([&Some(a.id), &Some(b.id)])
+        .descending()
+        .limit(1)
+        .query()
+        .await?;
+    assert_eq!(last_children.len(), 2);
+    assert!(last_children.iter().all(|m| m.key == Some(b.id)));
+
     let has_parent = db
         .view::<BasicByParentId>()
         .with_key_range(Some(0)..=Some(u64::MAX))
@@ -1908,6 +2835,96 @@ pub async fn view_query_tests<C: AsyncConnection>(db: &C) -> anyhow::Result<()>
     let items_with_categories = db.view::<BasicByCategory>().query().await?;
     assert_eq!(items_with_categories.len(), 3);
 
+    // Test paginating using cursors. Documents without a parent are mapped to
+    // the `None` key, which sorts first.
+    let first_page = db.view::<BasicByParentId>().limit(2).query_page().await?;
+    assert_eq!(
+        first_page
+            .mappings
+            .iter()
+            .map(|m| m.key)
+            .unique()
+            .collect::<Vec<_>>(),
+        vec![None, Some(a.id)]
+    );
+    assert_eq!(first_page.next_cursor, Some(Some(a.id)));
+    let second_page = db
+        .view::<BasicByParentId>()
+        .limit(2)
+        .after(&Some(a.id))
+        .query_page()
+        .await?;
+    assert_eq!(second_page.mappings.len(), 2);
+    assert!(second_page.mappings.iter().all(|m| m.key == Some(b.id)));
+    assert_eq!(second_page.next_cursor, None);
+    let empty_page = db
+        .view::<BasicByParentId>()
+        .limit(2)
+        .after(&Some(b.id))
+        .query_page()
+        .await?;
+    assert!(empty_page.mappings.is_empty());
+    assert_eq!(empty_page.next_cursor, None);
+
+    // Documents inserted after the cursor are returned by later pages.
+    let c = collection.push(&Basic::new("C")).await?;
+    collection
+        .push(&Basic::new("C.1").with_parent_id(c.id))
+        .await?;
+    let tail_page = db
+        .view::<BasicByParentId>()
+        .limit(2)
+        .after(&Some(b.id))
+        .query_page()
+        .await?;
+    assert_eq!(tail_page.mappings.len(), 1);
+    assert_eq!(tail_page.mappings[0].key, Some(c.id));
+    assert_eq!(tail_page.next_cursor, None);
+
+    // Cursors also work in descending order.
+    let descending_page = db
+        .view::<BasicByParentId>()
+        .descending()
+        .limit(1)
+        .after(&Some(c.id))
+        .query_page()
+        .await?;
+    assert!(descending_page.mappings.iter().all(|m| m.key == Some(b.id)));
+    assert_eq!(descending_page.next_cursor, Some(Some(b.id)));
+
+    // Test explaining queries. The view contains the keys None, a, b, and c.
+    let plan = db.view::<BasicByParentId>().explain().await?;
+    assert_eq!(
+        plan,
+        QueryPlan {
+            index_used: IndexAccess::FullScan,
+            scanned_entries: 4,
+            estimated_rows: 4,
+        }
+    );
+    let plan = db.view::<BasicByParentId>().limit(2).explain().await?;
+    assert_eq!(plan.scanned_entries, 2);
+    let plan = db
+        .view::<BasicByParentId>()
+        .with_key_range(Some(0)..=Some(u64::MAX))
+        .explain()
+        .await?;
+    assert_eq!(plan.index_used, IndexAccess::RangeScan);
+    assert_eq!(plan.scanned_entries, 3);
+    let plan = db
+        .view::<BasicByParentId>()
+        .with_keys([&Some(a.id), &Some(u64::MAX)])
+        .explain()
+        .await?;
+    assert_eq!(
+        plan,
+        QueryPlan {
+            index_used: IndexAccess::KeyLookup,
+            scanned_entries: 2,
+            estimated_rows: 1,
+        }
+    );
+
     // Test deleting
     let deleted_count = db
         .view::<BasicByParentId>()
@@ -1965,6 +2982,29 @@ pub fn blocking_view_query_tests<C: Connection>(db: &C) -> anyhow::Result<()> {
         .query()?;
     assert_eq!(a_and_b_children.len(), 3);
 
+    // Test duplicate keys and grouping the results by key
+    let a_and_b_children = db
+        .view::<BasicByParentId>()
+        .with_keys(&[Some(a.id), Some(b.id), Some(a.id)])
+        .query_with_collection_docs()?;
+    assert_eq!(a_and_b_children.len(), 3);
+    let groups = a_and_b_children.grouped_by_key();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].key, &Some(a.id));
+    assert_eq!(groups[0].documents.len(), 1);
+    assert_eq!(groups[1].key, &Some(b.id));
+    assert_eq!(groups[1].documents.len(), 2);
+
+    // Test limiting and descending order with multiple keys
+    let last_children = db
+        .view::<BasicByParentId>()
+        .with_keys(&[Some(a.id), Some(b.id)])
+        .descending()
+        .limit(1)
+        .query()?;
+    assert_eq!(last_children.len(), 2);
+    assert!(last_children.iter().all(|m| m.key == Some(b.id)));
+
     let has_parent = db
         .view::<BasicByParentId>()
         .with_key_range(Some(0)..=Some(u64::MAX))
@@ -1988,6 +3028,88 @@ pub fn blocking_view_query_tests<C: Connection>(db: &C) -> anyhow::Result<()> {
     let items_with_categories = db.view::<BasicByCategory>().query()?;
     assert_eq!(items_with_categories.len(), 3);
 
+    // Test paginating using cursors. Documents without a parent are mapped to
+    // the `None` key, which sorts first.
+    let first_page = db.view::<BasicByParentId>().limit(2).query_page()?;
+    assert_eq!(
+        first_page
+            .mappings
+            .iter()
+            .map(|m| m.key)
+            .unique()
+            .collect::<Vec<_>>(),
+        vec![None, Some(a.id)]
+    );
+    assert_eq!(first_page.next_cursor, Some(Some(a.id)));
+    let second_page = db
+        .view::<BasicByParentId>()
+        .limit(2)
+        .after(&Some(a.id))
+        .query_page()?;
+    assert_eq!(second_page.mappings.len(), 2);
+    assert!(second_page.mappings.iter().all(|m| m.key == Some(b.id)));
+    assert_eq!(second_page.next_cursor, None);
+    let empty_page = db
+        .view::<BasicByParentId>()
+        .limit(2)
+        .after(&Some(b.id))
+        .query_page()?;
+    assert!(empty_page.mappings.is_empty());
+    assert_eq!(empty_page.next_cursor, None);
+
+    // Documents inserted after the cursor are returned by later pages.
+    let c = collection.push(&Basic::new("C"))?;
+    collection.push(&Basic::new("C.1").with_parent_id(c.id))?;
+    let tail_page = db
+        .view::<BasicByParentId>()
+        .limit(2)
+        .after(&Some(b.id))
+        .query_page()?;
+    assert_eq!(tail_page.mappings.len(), 1);
+    assert_eq!(tail_page.mappings[0].key, Some(c.id));
+    assert_eq!(tail_page.next_cursor, None);
+
+    // Cursors also work in descending order.
+    let descending_page = db
+        .view::<BasicByParentId>()
+        .descending()
+        .limit(1)
+        .after(&Some(c.id))
+        .query_page()?;
+    assert!(descending_page.mappings.iter().all(|m| m.key == Some(b.id)));
+    assert_eq!(descending_page.next_cursor, Some(Some(b.id)));
+
+    // Test explaining queries. The view contains the keys None, a, b, and c.
+    let plan = db.view::<BasicByParentId>().explain()?;
+    assert_eq!(
+        plan,
+        QueryPlan {
+            index_used: IndexAccess::FullScan,
+            scanned_entries: 4,
+            estimated_rows: 4,
+        }
+    );
+    let plan = db.view::<BasicByParentId>().limit(2).explain()?;
+    assert_eq!(plan.scanned_entries, 2);
+    let plan = db
+        .view::<BasicByParentId>()
+        .with_key_range(Some(0)..=Some(u64::MAX))
+        .explain()?;
+    assert_eq!(plan.index_used, IndexAccess::RangeScan);
+    assert_eq!(plan.scanned_entries, 3);
+    let plan = db
+        .view::<BasicByParentId>()
+        .with_keys(&[Some(a.id), Some(u64::MAX)])
+        .explain()?;
+    assert_eq!(
+        plan,
+        QueryPlan {
+            index_used: IndexAccess::KeyLookup,
+            scanned_entries: 2,
+            estimated_rows: 1,
+        }
+    );
+
     // Test deleting
     let deleted_count = db
         .view::<BasicByParentId>()
@@ -2619,6 +3741,112 @@ pub fn blocking_unique_view_tests<C: Connection>(db: &C) -> anyhow::Result<()> {
     Ok(())
 }
 
+pub async fn search_tests<C: AsyncConnection>(db: &C) -> anyhow::Result<()> {
+    let brown_fox = Article::new("The quick brown fox")
+        .push_into_async(db)
+        .await?;
+    let mut lazy_dog = Article::new("the lazy dog").push_into_async(db).await?;
+    let quick_fox = Article::new("Quick, quick fox! Fox?")
+        .push_into_async(db)
+        .await?;
+    Article::new("unrelated").push_into_async(db).await?;
+
+    // Without ranking, results are ordered by id.
+    let results = db.search::<ArticleSearch>("QUICK fox").query().await?;
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| result.header.id)
+            .collect::<Vec<_>>(),
+        vec![brown_fox.header.id, quick_fox.header.id]
+    );
+    assert!(results.iter().all(|result| result.score.is_none()));
+
+    let results = db
+        .search::<ArticleSearch>("quick fox")
+        .with_ranking()
+        .query()
+        .await?;
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].header.id, quick_fox.header.id);
+    assert_eq!(results[1].header.id, brown_fox.header.id);
+    assert!(results[0].score.unwrap() > results[1].score.unwrap());
+
+    let results = db
+        .search::<ArticleSearch>("quick fox")
+        .with_ranking()
+        .limit(1)
+        .query()
+        .await?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].header.id, quick_fox.header.id);
+
+    let results = db.search::<ArticleSearch>("dog").query().await?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].header.id, lazy_dog.header.id);
+
+    assert!(db.search::<ArticleSearch>("cat").query().await?.is_empty());
+    assert!(db.search::<ArticleSearch>(" ,. ").query().await?.is_empty());
+
+    // Updating a document updates the index.
+    lazy_dog.contents.body = String::from("the lazy cat");
+    lazy_dog.update_async(db).await?;
+    assert!(db.search::<ArticleSearch>("dog").query().await?.is_empty());
+    assert_eq!(db.search::<ArticleSearch>("cat").query().await?.len(), 1);
+
+    Ok(())
+}
+
+pub fn blocking_search_tests<C: Connection>(db: &C) -> anyhow::Result<()> {
+    let brown_fox = Article::new("The quick brown fox").push_into(db)?;
+    let mut lazy_dog = Article::new("the lazy dog").push_into(db)?;
+    let quick_fox = Article::new("Quick, quick fox! Fox?").push_into(db)?;
+    Article::new("unrelated").push_into(db)?;
+
+    // Without ranking, results are ordered by id.
+    let results = db.search::<ArticleSearch>("QUICK fox").query()?;
+    assert_eq!(
+        results
+            .iter()
+            .map(|result| result.header.id)
+            .collect::<Vec<_>>(),
+        vec![brown_fox.header.id, quick_fox.header.id]
+    );
+    assert!(results.iter().all(|result| result.score.is_none()));
+
+    let results = db
+        .search::<ArticleSearch>("quick fox")
+        .with_ranking()
+        .query()?;
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].header.id, quick_fox.header.id);
+    assert_eq!(results[1].header.id, brown_fox.header.id);
+    assert!(results[0].score.unwrap() > results[1].score.unwrap());
+
+    let results = db
+        .search::<ArticleSearch>("quick fox")
+        .with_ranking()
+        .limit(1)
+        .query()?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].header.id, quick_fox.header.id);
+
+    let results = db.search::<ArticleSearch>("dog").query()?;
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].header.id, lazy_dog.header.id);
+
+    assert!(db.search::<ArticleSearch>("cat").query()?.is_empty());
+    assert!(db.search::<ArticleSearch>(" ,. ").query()?.is_empty());
+
+    // Updating a document updates the index.
+    lazy_dog.contents.body = String::from("the lazy cat");
+    lazy_dog.update(db)?;
+    assert!(db.search::<ArticleSearch>("dog").query()?.is_empty());
+    assert_eq!(db.search::<ArticleSearch>("cat").query()?.len(), 1);
+
+    Ok(())
+}
+
 pub fn blocking_cow_views<C: Connection>(db: &C) -> anyhow::Result<()> {
     db.collection::<Basic>()
         .push(&Basic::new("test").with_category("category"))?;
@@ -3067,6 +4295,107 @@ macro_rules! define_async_kv_test_suite {
                     Some(2_u32),
                 );
 
+                // Values larger than a chunk are streamed in multiple chunks.
+                let large = b"bonsaidb"
+                    .iter()
+                    .copied()
+                    .cycle()
+                    .take($crate::limits::KEY_VALUE_STREAM_CHUNK_SIZE * 5 / 2)
+                    .collect::<Vec<u8>>();
+                assert_eq!(
+                    kv.set_key_streaming("large", &large[..]).await?,
+                    large.len() as u64
+                );
+                let mut reader = kv.get_key_streaming("large").await?.expect("key not found");
+                let mut read = Vec::new();
+                futures::AsyncReadExt::read_to_end(&mut reader, &mut read).await?;
+                assert_eq!(read, large);
+                assert!(kv.get_key_streaming("missing").await?.is_none());
+
+                harness.shutdown().await?;
+
+                Ok(())
+            }
+
+            #[tokio::test]
+            async fn kv_compare_and_swap_tests() -> anyhow::Result<()> {
+                use $crate::keyvalue::{AsyncKeyValue, Numeric, Value};
+                let harness =
+                    $harness::new($crate::test_util::HarnessTest::KvCompareAndSwap).await?;
+                let db = harness.connect().await?;
+                let kv = db.with_key_namespace("cas");
+
+                let zero = Value::Numeric(Numeric::UnsignedInteger(0));
+                let one = Value::Numeric(Numeric::UnsignedInteger(1));
+                assert!(
+                    !kv.compare_and_swap_key("a", Some(zero.clone()), one.clone())
+                        .await?
+                );
+                assert!(kv.compare_and_swap_key("a", None, zero.clone()).await?);
+                assert!(!kv.compare_and_swap_key("a", None, one.clone()).await?);
+                // Values of differing types never match.
+                assert!(
+                    !kv.compare_and_swap_key(
+                        "a",
+                        Some(Value::Numeric(Numeric::Integer(0))),
+                        one.clone()
+                    )
+                    .await?
+                );
+                assert!(
+                    kv.compare_and_swap_key("a", Some(zero.clone()), one.clone())
+                        .await?
+                );
+                assert_eq!(kv.get_key("a").into_u64().await?, Some(1));
+                // The namespace keeps the key separate from the unnamespaced key.
+                assert_eq!(db.get_key("a").await?, None);
+                assert!(db.compare_and_swap_key("a", None, zero.clone()).await?);
+
+                assert_eq!(
+                    kv.get_and_set_key("a", zero.clone()).await?,
+                    Some(one.clone())
+                );
+                assert_eq!(kv.get_and_set_key("b", one.clone()).await?, None);
+                assert_eq!(kv.get_key("b").await?, Some(one));
+                assert_eq!(kv.get_key("a").await?, Some(zero));
+
+                harness.shutdown().await?;
+
+                Ok(())
+            }
+
+            #[tokio::test]
+            async fn kv_namespace_tests() -> anyhow::Result<()> {
+                use $crate::keyvalue::{AsyncKeyValue, KeyStatus};
+                let harness = $harness::new($crate::test_util::HarnessTest::KvNamespaces).await?;
+                let db = harness.connect().await?;
+                let cache = db.with_key_namespace("cache");
+                let nested = cache.with_key_namespace("nested");
+
+                db.set_key("root", &0_u32).await?;
+                cache.set_key("b", &0_u32).await?;
+                cache.set_key("a", &0_u32).await?;
+                nested.set_key("c", &0_u32).await?;
+
+                assert_eq!(
+                    cache.list_keys().await?,
+                    vec![String::from("a"), String::from("b")]
+                );
+                assert_eq!(nested.list_keys().await?, vec![String::from("c")]);
+                assert_eq!(db.list_keys().await?, vec![String::from("root")]);
+
+                assert_eq!(cache.delete_namespace().await?, 2);
+                assert!(cache.list_keys().await?.is_empty());
+                assert_eq!(cache.get_key("a").await?, None);
+                assert_eq!(cache.delete_namespace().await?, 0);
+                // Other namespaces are unaffected.
+                assert_eq!(nested.get_key("c").into().await?, Some(0_u32));
+                assert_eq!(db.get_key("root").into().await?, Some(0_u32));
+
+                // Deleted namespaces can be reused.
+                assert_eq!(cache.set_key("a", &1_u32).await?, KeyStatus::Inserted);
+                assert_eq!(cache.list_keys().await?, vec![String::from("a")]);
+
                 harness.shutdown().await?;
 
                 Ok(())
@@ -3299,6 +4628,16 @@ macro_rules! define_async_kv_test_suite {
 
                     let a = kv.get_key("a").into().await?;
                     assert_eq!(a, Some(1u32), "a shouldn't have expired yet");
+                    let a_expiration = kv
+                        .persisted_expiration("a")
+                        .await?
+                        .expect("a has no expiration");
+                    let b_expiration = kv
+                        .persisted_expiration("b")
+                        .await?
+                        .expect("b has no expiration");
+                    assert!(a_expiration > b_expiration, "a's expiration wasn't updated");
+                    assert_eq!(kv.persisted_expiration("missing").await?, None);
 
                     // Before checking the value, make sure we haven't elapsed too
                     // much time. If so, just restart the test.
@@ -3538,6 +4877,94 @@ macro_rules! define_blocking_kv_test_suite {
                     Some(2_u32),
                 );
 
+                // Values larger than a chunk are streamed in multiple chunks.
+                let large = b"bonsaidb"
+                    .iter()
+                    .copied()
+                    .cycle()
+                    .take($crate::limits::KEY_VALUE_STREAM_CHUNK_SIZE * 5 / 2)
+                    .collect::<Vec<u8>>();
+                assert_eq!(
+                    kv.set_key_streaming("large", &large[..])?,
+                    large.len() as u64
+                );
+                let mut reader = kv.get_key_streaming("large")?.expect("key not found");
+                let mut read = Vec::new();
+                std::io::Read::read_to_end(&mut reader, &mut read)?;
+                assert_eq!(read, large);
+                assert!(kv.get_key_streaming("missing")?.is_none());
+
+                harness.shutdown()?;
+
+                Ok(())
+            }
+
+            #[test]
+            fn kv_compare_and_swap_tests() -> anyhow::Result<()> {
+                use $crate::keyvalue::{KeyValue, Numeric, Value};
+                let harness = $harness::new($crate::test_util::HarnessTest::KvCompareAndSwap)?;
+                let db = harness.connect()?;
+                let kv = db.with_key_namespace("cas");
+
+                let zero = Value::Numeric(Numeric::UnsignedInteger(0));
+                let one = Value::Numeric(Numeric::UnsignedInteger(1));
+                assert!(!kv.compare_and_swap_key("a", Some(zero.clone()), one.clone())?);
+                assert!(kv.compare_and_swap_key("a", None, zero.clone())?);
+                assert!(!kv.compare_and_swap_key("a", None, one.clone())?);
+                // Values of differing types never match.
+                assert!(!kv.compare_and_swap_key(
+                    "a",
+                    Some(Value::Numeric(Numeric::Integer(0))),
+                    one.clone()
+                )?);
+                assert!(kv.compare_and_swap_key("a", Some(zero.clone()), one.clone())?);
+                assert_eq!(kv.get_key("a").into_u64()?, Some(1));
+                // The namespace keeps the key separate from the unnamespaced key.
+                assert_eq!(db.get_key("a").query()?, None);
+                assert!(db.compare_and_swap_key("a", None, zero.clone())?);
+
+                assert_eq!(kv.get_and_set_key("a", zero.clone())?, Some(one.clone()));
+                assert_eq!(kv.get_and_set_key("b", one.clone())?, None);
+                assert_eq!(kv.get_key("b").query()?, Some(one));
+                assert_eq!(kv.get_key("a").query()?, Some(zero));
+
+                harness.shutdown()?;
+
+                Ok(())
+            }
+
+            #[test]
+            fn kv_namespace_tests() -> anyhow::Result<()> {
+                use $crate::keyvalue::{KeyStatus, KeyValue};
+                let harness = $harness::new($crate::test_util::HarnessTest::KvNamespaces)?;
+                let db = harness.connect()?;
+                let cache = db.with_key_namespace("cache");
+                let nested = cache.with_key_namespace("nested");
+
+                db.set_key("root", &0_u32).execute()?;
+                cache.set_key("b", &0_u32).execute()?;
+                cache.set_key("a", &0_u32).execute()?;
+                nested.set_key("c", &0_u32).execute()?;
+
+                assert_eq!(
+                    cache.list_keys()?,
+                    vec![String::from("a"), String::from("b")]
+                );
+                assert_eq!(nested.list_keys()?, vec![String::from("c")]);
+                assert_eq!(db.list_keys()?, vec![String::from("root")]);
+
+                assert_eq!(cache.delete_namespace()?, 2);
+                assert!(cache.list_keys()?.is_empty());
+                assert_eq!(cache.get_key("a").query()?, None);
+                assert_eq!(cache.delete_namespace()?, 0);
+                // Other namespaces are unaffected.
+                assert_eq!(nested.get_key("c").into()?, Some(0_u32));
+                assert_eq!(db.get_key("root").into()?, Some(0_u32));
+
+                // Deleted namespaces can be reused.
+                assert_eq!(cache.set_key("a", &1_u32).execute()?, KeyStatus::Inserted);
+                assert_eq!(cache.list_keys()?, vec![String::from("a")]);
+
                 harness.shutdown()?;
 
                 Ok(())
@@ -3757,6 +5184,10 @@ macro_rules! define_blocking_kv_test_suite {
 
                     let a = kv.get_key("a").into()?;
                     assert_eq!(a, Some(1u32), "a shouldn't have expired yet");
+                    let a_expiration = kv.persisted_expiration("a")?.expect("a has no expiration");
+                    let b_expiration = kv.persisted_expiration("b")?.expect("b has no expiration");
+                    assert!(a_expiration > b_expiration, "a's expiration wasn't updated");
+                    assert_eq!(kv.persisted_expiration("missing")?, None);
 
                     // Before checking the value, make sure we haven't elapsed too
                     // much time. If so, just restart the test.
@@ -3971,13 +5402,25 @@ pub async fn basic_server_connection_tests<C: AsyncStorageConnection>(
     server
         .create_database::<BasicSchema>(newdb_name, false)
         .await?;
+    let databases = server.list_databases().await?;
+    let newdb = databases.iter().find(|db| db.name == newdb_name).unwrap();
+    assert_eq!(newdb.schema, BasicSchema::schema_name());
+    assert!(newdb.created_at.is_some());
+    assert!(server.database_exists(newdb_name).await?);
     server.delete_database(newdb_name).await?;
+    assert!(!server.database_exists(newdb_name).await?);
 
     assert!(matches!(
         server.delete_database(newdb_name).await,
         Err(Error::DatabaseNotFound(_))
     ));
 
+    let (_, creation) = server.ensure_database::<BasicSchema>(newdb_name).await?;
+    assert_eq!(creation, DatabaseCreation::Created);
+    let (_, creation) = server.ensure_database::<BasicSchema>(newdb_name).await?;
+    assert_eq!(creation, DatabaseCreation::AlreadyExisted);
+    server.delete_database(newdb_name).await?;
+
     assert!(matches!(
         server.create_database::<BasicSchema>("tests", false).await,
         Err(Error::DatabaseNameAlreadyTaken(_))
@@ -4032,13 +5475,25 @@ pub fn blocking_basic_server_connection_tests<C: StorageConnection>(
     assert!(databases.iter().any(|db| db.name == "tests"));
 
     server.create_database::<BasicSchema>(newdb_name, false)?;
+    let databases = server.list_databases()?;
+    let newdb = databases.iter().find(|db| db.name == newdb_name).unwrap();
+    assert_eq!(newdb.schema, BasicSchema::schema_name());
+    assert!(newdb.created_at.is_some());
+    assert!(server.database_exists(newdb_name)?);
     server.delete_database(newdb_name)?;
+    assert!(!server.database_exists(newdb_name)?);
 
     assert!(matches!(
         server.delete_database(newdb_name),
         Err(Error::DatabaseNotFound(_))
     ));
 
+    let (_, creation) = server.ensure_database::<BasicSchema>(newdb_name)?;
+    assert_eq!(creation, DatabaseCreation::Created);
+    let (_, creation) = server.ensure_database::<BasicSchema>(newdb_name)?;
+    assert_eq!(creation, DatabaseCreation::AlreadyExisted);
+    server.delete_database(newdb_name)?;
+
     assert!(matches!(
         server.create_database::<BasicSchema>("tests", false),
         Err(Error::DatabaseNameAlreadyTaken(_))
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use crate::connection::{AsyncLowLevelConnection, LowLevelConnection};
 use crate::document::{CollectionHeader, DocumentId, HasHeader, Header, Revision};
 use crate::key::KeyEncoding;
-use crate::schema::{Collection, CollectionName, SerializedCollection};
+use crate::schema::{Collection, CollectionName, DocumentPatch, SerializedCollection};
 use crate::Error;
 
 /// A list of operations to execute as a single unit. If any operation fails,
@@ -267,6 +267,44 @@ impl Operation {
         ))
     }
 
+    /// Patches a document in `collection` by applying the serialized `patch`.
+    /// If `revision` is provided, the patch is only applied if the stored
+    /// document's revision matches.
+    pub fn patch(
+        collection: CollectionName,
+        id: DocumentId,
+        revision: Option<Revision>,
+        patch: impl Into<Bytes>,
+    ) -> Self {
+        Self {
+            collection,
+            command: Command::Patch {
+                id,
+                revision,
+                patch: patch.into(),
+            },
+        }
+    }
+
+    /// Patches a document in `collection` by applying `patch`. If `revision`
+    /// is provided, the patch is only applied if the stored document's
+    /// revision matches.
+    pub fn patch_serialized<C: SerializedCollection, P: DocumentPatch<C>, Key>(
+        id: &Key,
+        revision: Option<Revision>,
+        patch: &P,
+    ) -> Result<Self, Error>
+    where
+        Key: KeyEncoding<C::PrimaryKey> + ?Sized,
+    {
+        Ok(Self::patch(
+            C::collection_name(),
+            DocumentId::new(id)?,
+            revision,
+            pot::to_vec(patch)?,
+        ))
+    }
+
     /// Deletes a document from a `collection`.
     pub const fn delete(collection: CollectionName, header: Header) -> Self {
         Self {
@@ -360,6 +398,21 @@ pub enum Command {
         contents: Bytes,
     },
 
+    /// Apply a serialized patch to an existing `Document` identified by `id`.
+    /// The patch is applied by the storage layer using
+    /// [`Collection::apply_patch()`], which avoids reading the document before
+    /// updating it. If `revision` is provided and does not match the currently
+    /// stored `Revision`, the command will fail with a `DocumentConflict`
+    /// error.
+    Patch {
+        /// The id of the document to patch.
+        id: DocumentId,
+        /// The revision the document must currently have, if any.
+        revision: Option<Revision>,
+        /// The serialized patch to apply.
+        patch: Bytes,
+    },
+
     /// Delete an existing `Document` identified by `id`. `revision` must match
     /// the currently stored revision on the `Document`. If it does not, the
     /// command fill fail with a `DocumentConflict` error.
@@ -1,25 +1,31 @@
+use std::io::{Read, Write};
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use bonsaidb_core::connection::{
     self, AccessPolicy, AsyncConnection, AsyncLowLevelConnection, AsyncStorageConnection,
-    Connection, HasSchema, HasSession, IdentityReference, LowLevelConnection, Range,
-    SerializedQueryKey, Session, Sort, StorageConnection,
+    Connection, DatabaseCreation, HasSchema, HasSession, IdentityReference, LowLevelConnection,
+    QueryPlan, Range, SerializedQueryKey, Session, Sort, StorageConnection,
+};
+use bonsaidb_core::document::{
+    DocumentId, Header, HistoricalRevision, MaybeDeleted, OwnedDocument,
 };
-use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::keyvalue::{AsyncKeyValue, KeyOperation, KeyValue, Output};
-use bonsaidb_core::permissions::Permissions;
-use bonsaidb_core::pubsub::{self, AsyncPubSub, AsyncSubscriber, PubSub, Receiver};
+use bonsaidb_core::permissions::{Action, Identifier, Permissions, Statement};
+use bonsaidb_core::pubsub::{
+    self, AsyncPubSub, AsyncSubscriber, BufferLimit, PubSub, PublishReceipt, Receiver,
+};
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
 use bonsaidb_core::schema::{
-    self, CollectionName, Nameable, Schema, SchemaName, SchemaSummary, Schematic, ViewName,
+    self, CollectionName, Filter, Nameable, Schema, SchemaName, SchemaSummary, Schematic, ViewName,
 };
 use bonsaidb_core::transaction::{self, OperationResult, Transaction};
 
 use crate::config::StorageConfiguration;
 use crate::database::DatabaseNonBlocking;
 use crate::storage::{AnyBackupLocation, StorageNonBlocking};
-use crate::{Database, Error, Storage, Subscriber};
+use crate::tasks::manager::TasksStatus;
+use crate::{Database, Error, Handle, IntegrityReport, Storage, Subscriber};
 
 /// A file-based, multi-database, multi-user database engine. This type is
 /// designed for use with [Tokio](https://tokio.rs). For blocking
@@ -151,6 +157,69 @@ impl AsyncStorage {
             .await?
     }
 
+    /// Writes a copy of all data in this instance to `writer` as a single
+    /// archive. See [`Storage::backup_to()`] for more information.
+    pub async fn backup_to<W: Write + Send + 'static>(&self, writer: W) -> Result<(), Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.backup_to(writer))
+            .await?
+    }
+
+    /// Restores all data from an archive previously written by
+    /// [`AsyncStorage::backup_to()`]. See [`Storage::restore_from()`] for more
+    /// information.
+    pub async fn restore_from<R: Read + Send + 'static>(&self, reader: R) -> Result<(), Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.restore_from(reader))
+            .await?
+    }
+
+    /// Compacts every database in this storage, returning the number of bytes
+    /// reclaimed. See [`Storage::compact()`] for more information.
+    pub async fn compact(&self) -> Result<u64, Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.compact())
+            .await?
+    }
+
+    /// Compacts the collection `C` in every database whose schema contains it,
+    /// returning the number of bytes reclaimed. See
+    /// [`Storage::compact_collection()`] for more information.
+    pub async fn compact_collection<C: schema::Collection + 'static>(&self) -> Result<u64, Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.compact_collection::<C>())
+            .await?
+    }
+
+    /// Removes all stored data for `collection` from the database named
+    /// `database`. See [`Storage::delete_collection()`] for more information.
+    pub async fn delete_collection(
+        &self,
+        database: &str,
+        collection: &CollectionName,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let database = database.to_string();
+        let collection = collection.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.delete_collection(&database, &collection))
+            .await
+            .map_err(Error::from)?
+    }
+
+    /// Verifies that every view in this storage is consistent with the
+    /// documents it indexes. See [`Storage::verify()`] for more information.
+    pub async fn verify(&self) -> Result<IntegrityReport, Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.verify())
+            .await?
+    }
+
     /// Restricts an unauthenticated instance to having `effective_permissions`.
     /// Returns `None` if a session has already been established.
     #[must_use]
@@ -163,6 +232,27 @@ impl AsyncStorage {
             })
     }
 
+    /// Authenticates as `identity`, which has already been verified using
+    /// `method` outside of this storage. See
+    /// [`Storage::authenticate_identity`] for more information.
+    pub async fn authenticate_identity(
+        &self,
+        identity: IdentityReference<'_>,
+        method: bonsaidb_core::connection::AuthenticationMethod,
+    ) -> Result<Self, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let identity = identity.into_owned();
+        self.runtime
+            .spawn_blocking(move || {
+                task_self
+                    .storage
+                    .authenticate_identity(identity, method)
+                    .map(Storage::into_async)
+            })
+            .await
+            .map_err(Error::from)?
+    }
+
     #[cfg(feature = "internal-apis")]
     #[doc(hidden)]
     pub async fn database_without_schema(&self, name: &str) -> Result<AsyncDatabase, Error> {
@@ -214,6 +304,14 @@ impl StorageNonBlocking for AsyncStorage {
         self.storage.path()
     }
 
+    fn set_tasks_worker_count(&self, worker_count: usize) {
+        self.storage.set_tasks_worker_count(worker_count);
+    }
+
+    fn tasks_status(&self) -> TasksStatus {
+        self.storage.tasks_status()
+    }
+
     fn assume_session(&self, session: Session) -> Result<Self, bonsaidb_core::Error> {
         self.storage.assume_session(session).map(|storage| Self {
             storage,
@@ -301,6 +399,97 @@ impl AsyncDatabase {
             })
     }
 
+    /// Rewrites every document in the collection `C` that was stored using a
+    /// version older than
+    /// [`Collection::CURRENT_VERSION`](schema::Collection::CURRENT_VERSION).
+    /// Returns the number of documents that were migrated.
+    ///
+    /// See [`Database::migrate_collection()`] for more information.
+    pub async fn migrate_collection<C: schema::Collection + 'static>(
+        &self,
+    ) -> Result<usize, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.migrate_collection::<C>())
+            .await
+            .map_err(Error::from)?
+    }
+
+    /// Deletes every document in the collection `C` in a single transaction,
+    /// returning the number of documents that were deleted.
+    ///
+    /// See [`Database::clear_collection()`] for more information.
+    pub async fn clear_collection<C: schema::Collection + 'static>(
+        &self,
+    ) -> Result<u64, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.clear_collection::<C>())
+            .await
+            .map_err(Error::from)?
+    }
+
+    /// Removes all stored data for `collection`, which must no longer be
+    /// defined in the schema.
+    ///
+    /// See [`Database::delete_collection()`] for more information.
+    pub async fn delete_collection(
+        &self,
+        collection: &CollectionName,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let collection = collection.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.delete_collection(&collection))
+            .await
+            .map_err(Error::from)?
+    }
+
+    /// Rebuilds the index of the view `V` by mapping every document in its
+    /// collection again. Returns a [`Handle`] to the job performing the
+    /// rebuild.
+    ///
+    /// See [`Database::rebuild_view()`] for more information.
+    pub async fn rebuild_view<V: schema::View + 'static>(
+        &self,
+    ) -> Result<Handle<(), Error>, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.rebuild_view::<V>())
+            .await
+            .map_err(Error::from)?
+    }
+
+    #[cfg(feature = "internal-apis")]
+    #[doc(hidden)]
+    pub async fn apply_replicated_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> Result<Vec<OperationResult>, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || {
+                task_self
+                    .database
+                    .apply_replicated_transaction(&transaction)
+            })
+            .await
+            .map_err(Error::from)?
+    }
+
+    #[cfg(feature = "internal-apis")]
+    #[doc(hidden)]
+    pub async fn execute_replicated_key_operation(
+        &self,
+        op: KeyOperation,
+    ) -> Result<Output, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.execute_replicated_key_operation(op))
+            .await
+            .map_err(Error::from)?
+    }
+
     /// Converts this instance into its blocking version, which is able to be
     /// used without async.
     #[must_use]
@@ -362,21 +551,23 @@ impl AsyncStorageConnection for AsyncStorage {
             .into_async()
     }
 
-    async fn create_database_with_schema(
+    async fn create_database_with_schema_and_permissions(
         &self,
         name: &str,
         schema: SchemaName,
         only_if_needed: bool,
-    ) -> Result<(), bonsaidb_core::Error> {
+        permissions: Vec<Statement>,
+    ) -> Result<DatabaseCreation, bonsaidb_core::Error> {
         let task_self = self.clone();
         let name = name.to_owned();
         self.runtime
             .spawn_blocking(move || {
-                StorageConnection::create_database_with_schema(
+                StorageConnection::create_database_with_schema_and_permissions(
                     &task_self.storage,
                     &name,
                     schema,
                     only_if_needed,
+                    permissions,
                 )
             })
             .await
@@ -425,6 +616,18 @@ impl AsyncStorageConnection for AsyncStorage {
             .map_err(Error::from)?
     }
 
+    async fn database_stats(
+        &self,
+        name: &str,
+    ) -> Result<connection::DatabaseStats, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let name = name.to_owned();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.database_stats(&name))
+            .await
+            .map_err(Error::from)?
+    }
+
     async fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         let task_self = self.clone();
         let username = username.to_owned();
@@ -446,6 +649,14 @@ impl AsyncStorageConnection for AsyncStorage {
             .map_err(Error::from)?
     }
 
+    async fn list_users(&self) -> Result<Vec<connection::UserInfo>, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.list_users())
+            .await
+            .map_err(Error::from)?
+    }
+
     #[cfg(feature = "password-hashing")]
     async fn set_user_password<'user, U: Nameable<'user, u64> + Send + Sync>(
         &self,
@@ -460,7 +671,6 @@ impl AsyncStorageConnection for AsyncStorage {
             .map_err(Error::from)?
     }
 
-    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
     async fn authenticate(
         &self,
         authentication: bonsaidb_core::connection::Authentication,
@@ -536,6 +746,18 @@ impl AsyncStorageConnection for AsyncStorage {
             .map_err(Error::from)?
     }
 
+    async fn list_user_groups<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<connection::PermissionGroupInfo>, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let user = user.name()?.into_owned();
+        self.runtime
+            .spawn_blocking(move || task_self.storage.list_user_groups(user))
+            .await
+            .map_err(Error::from)?
+    }
+
     async fn add_role_to_user<
         'user,
         'group,
@@ -579,6 +801,22 @@ impl HasSession for AsyncDatabase {
     fn session(&self) -> Option<&Session> {
         self.database.session()
     }
+
+    fn allowed_to<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> bool {
+        self.database.allowed_to(resource_name, action)
+    }
+
+    fn check_permission<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.database.check_permission(resource_name, action)
+    }
 }
 
 #[async_trait]
@@ -663,20 +901,46 @@ impl AsyncPubSub for AsyncDatabase {
         PubSub::create_subscriber(&self.database)
     }
 
+    async fn create_subscriber_with_limit(
+        &self,
+        limit: BufferLimit,
+    ) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+        PubSub::create_subscriber_with_limit(&self.database, limit)
+    }
+
     async fn publish_bytes(
         &self,
         topic: Vec<u8>,
         payload: Vec<u8>,
-    ) -> Result<(), bonsaidb_core::Error> {
-        PubSub::publish_bytes(&self.database, topic, payload)
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        Ok(self
+            .database
+            .begin_publish([topic], payload)?
+            .wait_async()
+            .await)
     }
 
     async fn publish_bytes_to_all(
         &self,
         topics: impl IntoIterator<Item = Vec<u8>> + Send + 'async_trait,
         payload: Vec<u8>,
-    ) -> Result<(), bonsaidb_core::Error> {
-        PubSub::publish_bytes_to_all(&self.database, topics, payload)
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        Ok(self
+            .database
+            .begin_publish(topics, payload)?
+            .wait_async()
+            .await)
+    }
+
+    async fn publish_batch_bytes(
+        &self,
+        messages: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        Ok(self
+            .database
+            .begin_publish_batch(messages)?
+            .wait_async()
+            .await)
     }
 }
 
@@ -686,10 +950,28 @@ impl AsyncSubscriber for Subscriber {
         pubsub::Subscriber::subscribe_to_bytes(self, topic)
     }
 
+    async fn subscribe_to_bytes_with_replay(
+        &self,
+        topic: Vec<u8>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        pubsub::Subscriber::subscribe_to_bytes_with_replay(self, topic)
+    }
+
     async fn unsubscribe_from_bytes(&self, topic: &[u8]) -> Result<(), bonsaidb_core::Error> {
         pubsub::Subscriber::unsubscribe_from_bytes(self, topic)
     }
 
+    async fn subscribe_to_prefix_bytes(&self, prefix: Vec<u8>) -> Result<(), bonsaidb_core::Error> {
+        pubsub::Subscriber::subscribe_to_prefix_bytes(self, prefix)
+    }
+
+    async fn unsubscribe_from_prefix_bytes(
+        &self,
+        prefix: &[u8],
+    ) -> Result<(), bonsaidb_core::Error> {
+        pubsub::Subscriber::unsubscribe_from_prefix_bytes(self, prefix)
+    }
+
     fn receiver(&self) -> &Receiver {
         pubsub::Subscriber::receiver(self)
     }
@@ -746,6 +1028,25 @@ impl AsyncLowLevelConnection for AsyncDatabase {
             .map_err(Error::from)?
     }
 
+    async fn filter_from_collection(
+        &self,
+        filter: &Filter,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<OwnedDocument>, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let filter = filter.clone();
+        let collection = collection.clone();
+        self.runtime
+            .spawn_blocking(move || {
+                task_self
+                    .database
+                    .filter_from_collection(&filter, limit, &collection)
+            })
+            .await
+            .map_err(Error::from)?
+    }
+
     async fn list_headers_from_collection(
         &self,
         ids: Range<DocumentId>,
@@ -765,6 +1066,58 @@ impl AsyncLowLevelConnection for AsyncDatabase {
             .map_err(Error::from)?
     }
 
+    async fn list_including_deleted_from_collection(
+        &self,
+        ids: Range<DocumentId>,
+        order: Sort,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<MaybeDeleted>, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let collection = collection.clone();
+        self.runtime
+            .spawn_blocking(move || {
+                task_self.database.list_including_deleted_from_collection(
+                    ids,
+                    order,
+                    limit,
+                    &collection,
+                )
+            })
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn restore_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Header, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let collection = collection.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.restore_from_collection(id, &collection))
+            .await
+            .map_err(Error::from)?
+    }
+
+    async fn revision_history_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Vec<HistoricalRevision>, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let collection = collection.clone();
+        self.runtime
+            .spawn_blocking(move || {
+                task_self
+                    .database
+                    .revision_history_from_collection(id, &collection)
+            })
+            .await
+            .map_err(Error::from)?
+    }
+
     async fn count_from_collection(
         &self,
         ids: Range<DocumentId>,
@@ -897,4 +1250,36 @@ impl AsyncLowLevelConnection for AsyncDatabase {
             .await
             .map_err(Error::from)?
     }
+
+    async fn explain_by_name(
+        &self,
+        view: &ViewName,
+        key: Option<SerializedQueryKey>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, bonsaidb_core::Error> {
+        let task_self = self.clone();
+        let view = view.clone();
+        self.runtime
+            .spawn_blocking(move || task_self.database.explain_by_name(&view, key, order, limit))
+            .await
+            .map_err(Error::from)?
+    }
+}
+
+#[cfg(any(test, feature = "test-util"))]
+#[async_trait]
+impl bonsaidb_core::test_util::InProcessStorage for AsyncStorage {
+    async fn open_in_memory<S: Schema>() -> anyhow::Result<Self> {
+        use crate::config::Builder;
+
+        Ok(Self::open(StorageConfiguration::in_memory().with_schema::<S>()?).await?)
+    }
+
+    fn with_effective_permissions(
+        database: &AsyncDatabase,
+        permissions: Permissions,
+    ) -> Option<AsyncDatabase> {
+        database.with_effective_permissions(permissions)
+    }
 }
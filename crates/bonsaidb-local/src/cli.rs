@@ -1,4 +1,5 @@
-use std::io;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
 use std::path::PathBuf;
 
 use clap::Subcommand;
@@ -35,6 +36,11 @@ pub enum Location {
         /// The path to the backup directory.
         path: PathBuf,
     },
+    /// A single-file archive.
+    Archive {
+        /// The path to the archive file.
+        path: PathBuf,
+    },
 }
 
 impl StorageCommand {
@@ -71,6 +77,7 @@ impl Location {
     pub fn backup(&self, storage: &Storage) -> Result<(), Error> {
         match self {
             Location::Path { path } => storage.backup(path),
+            Location::Archive { path } => storage.backup_to(BufWriter::new(File::create(path)?)),
         }
     }
 
@@ -78,6 +85,7 @@ impl Location {
     pub fn restore(&self, storage: &Storage) -> Result<(), Error> {
         match self {
             Location::Path { path } => storage.restore(path),
+            Location::Archive { path } => storage.restore_from(BufReader::new(File::open(path)?)),
         }
     }
 
@@ -86,6 +94,9 @@ impl Location {
     pub async fn backup_async(&self, storage: &crate::AsyncStorage) -> Result<(), Error> {
         match self {
             Location::Path { path } => storage.backup(path.clone()).await,
+            Location::Archive { path } => {
+                storage.backup_to(BufWriter::new(File::create(path)?)).await
+            }
         }
     }
 
@@ -94,6 +105,11 @@ impl Location {
     pub async fn restore_async(&self, storage: &crate::AsyncStorage) -> Result<(), Error> {
         match self {
             Location::Path { path } => storage.restore(path.clone()).await,
+            Location::Archive { path } => {
+                storage
+                    .restore_from(BufReader::new(File::open(path)?))
+                    .await
+            }
         }
     }
 }
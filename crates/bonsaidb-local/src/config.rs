@@ -9,6 +9,7 @@ use bonsaidb_core::permissions::Permissions;
 use bonsaidb_core::schema::{Schema, SchemaName};
 use sysinfo::{CpuRefreshKind, RefreshKind, System, SystemExt};
 
+use crate::metrics::MetricsRecorder;
 use crate::storage::{DatabaseOpener, StorageSchemaOpener};
 #[cfg(feature = "encryption")]
 use crate::vault::AnyVaultKeyStorage;
@@ -18,6 +19,10 @@ use crate::Error;
 mod argon;
 #[cfg(feature = "password-hashing")]
 pub use argon::*;
+#[cfg(feature = "password-hashing")]
+mod password_policy;
+#[cfg(feature = "password-hashing")]
+pub use password_policy::*;
 
 /// Configuration options for [`Storage`](crate::storage::Storage).
 #[derive(Clone)]
@@ -27,10 +32,26 @@ pub struct StorageConfiguration {
     pub path: Option<PathBuf>,
 
     /// Prevents storing data on the disk. This is intended for testing purposes
-    /// primarily. Keep in mind that the underlying storage format is
-    /// append-only.
+    /// and ephemeral caches primarily. No files or directories are created,
+    /// including at [`path`](Self#structfield.path), and all data is discarded
+    /// when the storage is dropped. Keep in mind that the underlying storage
+    /// format is append-only.
     pub memory_only: bool,
 
+    /// Opens the storage without allowing any modifications. Any operation
+    /// that would modify the storage, such as executing a transaction,
+    /// setting a key-value entry, or compacting, returns
+    /// [`bonsaidb_core::Error::ReadOnly`]. The storage must already exist.
+    ///
+    /// Multiple processes can open the same storage in read-only mode at the
+    /// same time, but not while it is opened by a process that can write to
+    /// it.
+    ///
+    /// Views are not updated in read-only mode. Queries return results from
+    /// whatever index exists on disk, which may not reflect all documents if
+    /// the view was not up-to-date when the storage was last written to.
+    pub read_only: bool,
+
     /// The unique id of the server. If not specified, the server will randomly
     /// generate a unique id on startup. If the server generated an id and this
     /// value is subsequently set, the generated id will be overridden by the
@@ -40,7 +61,10 @@ pub struct StorageConfiguration {
     /// The vault key storage to use. If not specified,
     /// [`LocalVaultKeyStorage`](crate::vault::LocalVaultKeyStorage) will be
     /// used with the server's data folder as the path. This is **incredibly
-    /// insecure and should not be used outside of testing**.
+    /// insecure and should not be used outside of testing**. For
+    /// [memory-only](Self#structfield.memory_only) storage,
+    /// [`MemoryVaultKeyStorage`](crate::vault::MemoryVaultKeyStorage) is used
+    /// instead.
     ///
     /// For secure encryption, it is important to store the vault keys in a
     /// location that is separate from the database. If the keys are on the same
@@ -62,9 +86,28 @@ pub struct StorageConfiguration {
     /// Configuration options related to views.
     pub views: Views,
 
+    /// Controls whether the consistency of every view with the documents it
+    /// indexes is verified when the storage is opened. Inconsistent views are
+    /// rebuilt using background jobs before
+    /// [`Storage::open()`](crate::storage::Storage::open) returns, and are
+    /// logged as warnings. Read-only storage only logs inconsistencies.
+    ///
+    /// The default is [`IntegrityCheck::None`]. The same verification can be
+    /// performed at any time using
+    /// [`Storage::verify()`](crate::storage::Storage::verify).
+    pub integrity_check: IntegrityCheck,
+
     /// Controls how the key-value store persists keys, on a per-database basis.
     pub key_value_persistence: KeyValuePersistence,
 
+    /// The maximum size, in bytes, of a value stored in the key-value store.
+    /// Operations that would store a larger value fail with
+    /// [`Error::ValueTooLarge`](bonsaidb_core::Error::ValueTooLarge).
+    /// Numeric values are not affected by this limit.
+    ///
+    /// By default, the size of values is not limited.
+    pub key_value_max_value_size: Option<usize>,
+
     /// Sets the default compression algorithm.
     #[cfg(feature = "compression")]
     pub default_compression: Option<Compression>,
@@ -76,6 +119,48 @@ pub struct StorageConfiguration {
     #[cfg(feature = "password-hashing")]
     pub argon: ArgonConfiguration,
 
+    /// The requirements passwords must meet to be set. By default, any
+    /// password is accepted.
+    #[cfg(feature = "password-hashing")]
+    pub password_policy: PasswordPolicy,
+
+    /// The recorder that receives metrics, such as the depth of the background
+    /// job queue. If `None`, no metrics are gathered.
+    pub metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
+
+    /// The number of `PubSub` messages to retain per topic. The keys are
+    /// topics serialized using [`pot`], and apply to the topic in every
+    /// database. Retained messages are delivered to subscribers that
+    /// subscribe using
+    /// [`Subscriber::subscribe_to_with_replay()`](bonsaidb_core::pubsub::Subscriber::subscribe_to_with_replay).
+    ///
+    /// Retained messages are only kept in memory. By default, no messages are
+    /// retained.
+    pub pubsub_retention: HashMap<Vec<u8>, PubSubRetention>,
+
+    /// The maximum size, in bytes, of a `PubSub` message's payload. Publishing
+    /// a larger payload fails with
+    /// [`Error::MessageTooLarge`](bonsaidb_core::Error::MessageTooLarge).
+    ///
+    /// By default, the size of payloads is not limited.
+    pub pubsub_max_payload_size: Option<usize>,
+
+    /// The maximum number of revisions to record for each document. Recorded
+    /// revisions are returned from
+    /// [`CollectionDocument::revision_history()`](bonsaidb_core::document::CollectionDocument::revision_history),
+    /// and the oldest revisions are discarded once a document has more than
+    /// this many.
+    ///
+    /// When 0, no revision history is recorded. This is the default.
+    pub revision_history: usize,
+
+    /// How often documents that have expired are deleted from collections
+    /// that expire documents. See
+    /// [`Collection::expire_after()`](bonsaidb_core::schema::Collection::expire_after).
+    ///
+    /// The default interval is 60 seconds.
+    pub document_expiration_interval: Duration,
+
     pub(crate) initial_schemas: HashMap<SchemaName, Arc<dyn DatabaseOpener>>,
 }
 
@@ -89,6 +174,7 @@ impl Default for StorageConfiguration {
         Self {
             path: None,
             memory_only: false,
+            read_only: false,
             unique_id: None,
             #[cfg(feature = "encryption")]
             vault_key_storage: None,
@@ -98,10 +184,19 @@ impl Default for StorageConfiguration {
             default_compression: None,
             workers: Tasks::default_for(&system),
             views: Views::default(),
+            integrity_check: IntegrityCheck::default(),
             key_value_persistence: KeyValuePersistence::default(),
+            key_value_max_value_size: None,
             authenticated_permissions: Permissions::default(),
             #[cfg(feature = "password-hashing")]
             argon: ArgonConfiguration::default_for(&system),
+            #[cfg(feature = "password-hashing")]
+            password_policy: PasswordPolicy::default(),
+            metrics_recorder: None,
+            pubsub_retention: HashMap::default(),
+            pubsub_max_payload_size: None,
+            revision_history: 0,
+            document_expiration_interval: Duration::from_secs(60),
             initial_schemas: HashMap::default(),
         }
     }
@@ -114,11 +209,22 @@ impl std::fmt::Debug for StorageConfiguration {
         let mut f = f.debug_struct("StorageConfiguration");
         f.field("path", &self.path)
             .field("memory_only", &self.memory_only)
+            .field("read_only", &self.read_only)
             .field("unique_id", &self.unique_id)
             .field("workers", &self.workers)
             .field("views", &self.views)
+            .field("integrity_check", &self.integrity_check)
             .field("key_value_persistence", &self.key_value_persistence)
+            .field("key_value_max_value_size", &self.key_value_max_value_size)
             .field("authenticated_permissions", &self.authenticated_permissions)
+            .field("metrics_recorder", &self.metrics_recorder)
+            .field("pubsub_retention", &self.pubsub_retention)
+            .field("pubsub_max_payload_size", &self.pubsub_max_payload_size)
+            .field("revision_history", &self.revision_history)
+            .field(
+                "document_expiration_interval",
+                &self.document_expiration_interval,
+            )
             .field("initial_schemas", &schemas);
 
         #[cfg(feature = "encryption")]
@@ -129,7 +235,8 @@ impl std::fmt::Debug for StorageConfiguration {
         f.field("default_compression", &self.default_compression);
 
         #[cfg(feature = "password-hashing")]
-        f.field("argon", &self.argon);
+        f.field("argon", &self.argon)
+            .field("password_policy", &self.password_policy);
 
         f.finish()
     }
@@ -151,6 +258,9 @@ pub struct Tasks {
     /// Defines how many workers should be spawned to process tasks. This
     /// defaults to the 2x the number of cpu cores available to the system or 2,
     /// whichever is larger.
+    ///
+    /// The number of workers can be changed after opening storage using
+    /// [`StorageNonBlocking::set_tasks_worker_count()`](crate::StorageNonBlocking::set_tasks_worker_count).
     pub worker_count: usize,
 
     /// Defines how many simultaneous threads should be used when a task is
@@ -184,6 +294,35 @@ pub struct Views {
     pub check_integrity_on_open: bool,
 }
 
+/// How thoroughly views are verified when a [`Storage`](crate::storage::Storage)
+/// is opened. See [`StorageConfiguration::integrity_check`].
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum IntegrityCheck {
+    /// Views are not verified.
+    #[default]
+    None,
+    /// Every document is checked to have been mapped by each view of its
+    /// collection, and every deleted document is checked to have been removed
+    /// from them. Views that haven't been updated since their version changed
+    /// are skipped, as they are rebuilt before they are next queried.
+    Quick,
+    /// In addition to the checks performed by [`IntegrityCheck::Quick`],
+    /// every entry stored in each view is checked against the mappings
+    /// recorded for the documents it was produced from. This requires reading
+    /// every view entry.
+    Full,
+}
+
+/// Controls how many `PubSub` messages are retained for a topic. See
+/// [`StorageConfiguration::pubsub_retention`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PubSubRetention {
+    /// Retain the most recent `n` messages.
+    Count(usize),
+    /// Retain all messages published within this duration.
+    Duration(Duration),
+}
+
 /// Rules for persisting key-value changes. Default persistence is to
 /// immediately persist all changes. While this ensures data integrity, the
 /// overhead of the key-value store can be significantly reduced by utilizing
@@ -348,12 +487,25 @@ pub trait Builder: Sized {
     {
         Self::default().path(path)
     }
+    /// Creates a default configuration that keeps all data in memory. See
+    /// [`StorageConfiguration::memory_only`](StorageConfiguration#structfield.memory_only)
+    /// for more information.
+    #[must_use]
+    fn in_memory() -> Self
+    where
+        Self: Default,
+    {
+        Self::default().memory_only()
+    }
     /// Registers the schema and returns self.
     fn with_schema<S: Schema>(self) -> Result<Self, Error>;
 
     /// Sets [`StorageConfiguration::memory_only`](StorageConfiguration#structfield.memory_only) to true and returns self.
     #[must_use]
     fn memory_only(self) -> Self;
+    /// Sets [`StorageConfiguration::read_only`](StorageConfiguration#structfield.read_only) to `read_only` and returns self.
+    #[must_use]
+    fn read_only(self, read_only: bool) -> Self;
     /// Sets [`StorageConfiguration::path`](StorageConfiguration#structfield.path) to `path` and returns self.
     #[must_use]
     fn path<P: AsRef<Path>>(self, path: P) -> Self;
@@ -380,6 +532,9 @@ pub trait Builder: Sized {
     /// Sets [`Views::check_integrity_on_open`] to `check` and returns self.
     #[must_use]
     fn check_view_integrity_on_open(self, check: bool) -> Self;
+    /// Sets [`StorageConfiguration::integrity_check`](StorageConfiguration#structfield.integrity_check) to `check` and returns self.
+    #[must_use]
+    fn integrity_check(self, check: IntegrityCheck) -> Self;
     /// Sets [`StorageConfiguration::default_compression`](StorageConfiguration#structfield.default_compression) to `path` and returns self.
     #[cfg(feature = "compression")]
     #[must_use]
@@ -394,6 +549,16 @@ pub trait Builder: Sized {
     #[cfg(feature = "password-hashing")]
     #[must_use]
     fn argon(self, argon: ArgonConfiguration) -> Self;
+    /// Sets [`StorageConfiguration::password_policy`](StorageConfiguration#structfield.password_policy) to `policy` and returns self.
+    #[cfg(feature = "password-hashing")]
+    #[must_use]
+    fn password_policy(self, policy: PasswordPolicy) -> Self;
+    /// Sets [`StorageConfiguration::revision_history`](StorageConfiguration#structfield.revision_history) to `max_per_document` and returns self.
+    #[must_use]
+    fn revision_history(self, max_per_document: usize) -> Self;
+    /// Sets [`StorageConfiguration::document_expiration_interval`](StorageConfiguration#structfield.document_expiration_interval) to `interval` and returns self.
+    #[must_use]
+    fn document_expiration_interval(self, interval: Duration) -> Self;
 }
 
 impl Builder for StorageConfiguration {
@@ -407,6 +572,11 @@ impl Builder for StorageConfiguration {
         self
     }
 
+    fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
     fn path<P: AsRef<Path>>(mut self, path: P) -> Self {
         self.path = Some(path.as_ref().to_owned());
         self
@@ -453,6 +623,11 @@ impl Builder for StorageConfiguration {
         self
     }
 
+    fn integrity_check(mut self, check: IntegrityCheck) -> Self {
+        self.integrity_check = check;
+        self
+    }
+
     fn key_value_persistence(mut self, persistence: KeyValuePersistence) -> Self {
         self.key_value_persistence = persistence;
         self
@@ -471,6 +646,22 @@ impl Builder for StorageConfiguration {
         self.argon = argon;
         self
     }
+
+    #[cfg(feature = "password-hashing")]
+    fn password_policy(mut self, policy: PasswordPolicy) -> Self {
+        self.password_policy = policy;
+        self
+    }
+
+    fn revision_history(mut self, max_per_document: usize) -> Self {
+        self.revision_history = max_per_document;
+        self
+    }
+
+    fn document_expiration_interval(mut self, interval: Duration) -> Self {
+        self.document_expiration_interval = interval;
+        self
+    }
 }
 
 pub(crate) trait SystemDefault: Sized {
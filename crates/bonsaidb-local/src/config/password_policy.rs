@@ -0,0 +1,66 @@
+/// Requirements that passwords must meet before they can be set using
+/// [`StorageConnection::set_user_password()`](bonsaidb_core::connection::StorageConnection::set_user_password).
+///
+/// Passwords that don't meet the policy are rejected with
+/// [`Error::WeakPassword`](bonsaidb_core::Error::WeakPassword). The default
+/// policy accepts any password. Passwords that have already been set are not
+/// checked when the policy changes.
+///
+/// ```rust
+/// use bonsaidb_local::config::{Builder, PasswordPolicy, StorageConfiguration};
+///
+/// let config = StorageConfiguration::default().password_policy(PasswordPolicy {
+///     min_length: 12,
+///     require_mixed_case: true,
+///     ..PasswordPolicy::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct PasswordPolicy {
+    /// The minimum number of characters a password must contain.
+    pub min_length: usize,
+    /// If true, passwords must contain both uppercase and lowercase letters.
+    pub require_mixed_case: bool,
+    /// If true, passwords must contain at least one numeric digit.
+    pub require_digit: bool,
+    /// If true, passwords must contain at least one character that is neither
+    /// a letter nor a digit.
+    pub require_symbol: bool,
+}
+
+impl PasswordPolicy {
+    /// Checks `password` against this policy, returning
+    /// [`Error::WeakPassword`](bonsaidb_core::Error::WeakPassword) describing
+    /// the first requirement that isn't met.
+    pub fn check(&self, password: &str) -> Result<(), bonsaidb_core::Error> {
+        if password.chars().count() < self.min_length {
+            return Err(bonsaidb_core::Error::WeakPassword(format!(
+                "passwords must be at least {} characters long",
+                self.min_length
+            )));
+        }
+
+        if self.require_mixed_case
+            && !(password.chars().any(char::is_uppercase)
+                && password.chars().any(char::is_lowercase))
+        {
+            return Err(bonsaidb_core::Error::WeakPassword(String::from(
+                "passwords must contain uppercase and lowercase letters",
+            )));
+        }
+
+        if self.require_digit && !password.chars().any(|ch| ch.is_ascii_digit()) {
+            return Err(bonsaidb_core::Error::WeakPassword(String::from(
+                "passwords must contain a digit",
+            )));
+        }
+
+        if self.require_symbol && password.chars().all(char::is_alphanumeric) {
+            return Err(bonsaidb_core::Error::WeakPassword(String::from(
+                "passwords must contain a symbol",
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -1,19 +1,24 @@
 use std::borrow::{Borrow, Cow};
+use std::cell::Cell;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::Infallible;
-use std::ops::{self, Deref};
+use std::fmt::Debug;
+use std::ops::{self, Deref, RangeBounds};
 use std::sync::Arc;
 use std::u8;
 
-use bonsaidb_core::arc_bytes::serde::CowBytes;
+use bonsaidb_core::arc_bytes::serde::{Bytes, CowBytes};
 use bonsaidb_core::arc_bytes::ArcBytes;
+use bonsaidb_core::changes::{change_feed_topic, Change, ChangeOperation};
 use bonsaidb_core::connection::{
-    self, AccessPolicy, Connection, HasSchema, HasSession, LowLevelConnection, Range,
-    SerializedQueryKey, Session, Sort, StorageConnection,
+    self, AccessPolicy, CollectionStats, Connection, HasSchema, HasSession, IndexAccess,
+    LowLevelConnection, QueryPlan, Range, SerializedQueryKey, Session, Sort, StorageConnection,
 };
 #[cfg(any(feature = "encryption", feature = "compression"))]
 use bonsaidb_core::document::KeyId;
-use bonsaidb_core::document::{BorrowedDocument, DocumentId, Header, OwnedDocument, Revision};
+use bonsaidb_core::document::{
+    BorrowedDocument, DocumentId, Header, HistoricalRevision, MaybeDeleted, OwnedDocument, Revision,
+};
 use bonsaidb_core::keyvalue::{KeyOperation, Output, Timestamp};
 use bonsaidb_core::limits::{
     LIST_TRANSACTIONS_DEFAULT_RESULT_COUNT, LIST_TRANSACTIONS_MAX_RESULTS,
@@ -23,10 +28,10 @@ use bonsaidb_core::permissions::bonsai::{
     view_resource_name, BonsaiAction, DatabaseAction, DocumentAction, TransactionAction,
     ViewAction,
 };
-use bonsaidb_core::permissions::Permissions;
+use bonsaidb_core::permissions::{Action, Identifier, Permissions};
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
 use bonsaidb_core::schema::view::{self};
-use bonsaidb_core::schema::{self, CollectionName, Schema, Schematic, ViewName};
+use bonsaidb_core::schema::{self, CollectionName, Filter, Schema, Schematic, ViewName};
 use bonsaidb_core::transaction::{
     self, ChangedDocument, Changes, Command, DocumentChanges, Operation, OperationResult,
     Transaction,
@@ -44,21 +49,27 @@ use watchable::Watchable;
 
 use crate::config::{Builder, KeyValuePersistence, StorageConfiguration};
 use crate::database::keyvalue::BackgroundWorkerProcessTarget;
+use crate::database::virtual_collections::VirtualCollections;
 use crate::error::Error;
 use crate::open_trees::OpenTrees;
 use crate::storage::StorageLock;
 #[cfg(feature = "encryption")]
 use crate::storage::TreeVault;
+use crate::tasks::handle::Handle;
 use crate::views::{
     mapper, view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
-    ViewEntry,
+    view_versions_tree_name, ViewEntry,
 };
 use crate::Storage;
 
 pub mod keyvalue;
 
 pub(crate) mod compat;
+pub(crate) mod expiration;
 pub mod pubsub;
+mod relationships;
+pub(crate) mod triggers;
+mod virtual_collections;
 
 /// A database stored in BonsaiDb. This type blocks the current thread when
 /// used. See [`AsyncDatabase`](crate::AsyncDatabase) for this type's async counterpart.
@@ -121,6 +132,8 @@ pub struct Data {
     pub name: Arc<Cow<'static, str>>,
     context: Context,
     pub(crate) schema: Arc<Schematic>,
+    /// Permissions granted to all sessions accessing this database.
+    permissions: Option<Permissions>,
 }
 
 impl Database {
@@ -132,12 +145,14 @@ impl Database {
     ) -> Result<Self, Error> {
         let name = name.into();
         let schema = Arc::new(DB::schematic()?);
+        let permissions = storage.instance.database_permissions(&name);
         let db = Self {
             storage: storage.clone(),
             data: Arc::new(Data {
                 name: Arc::new(name),
                 context,
                 schema,
+                permissions,
             }),
         };
 
@@ -151,6 +166,8 @@ impl Database {
             .instance
             .tasks()
             .spawn_key_value_expiration_loader(&db);
+        storage.instance.tasks().spawn_triggers_on_open(&db);
+        storage.instance.tasks().register_expiring_database(&db);
 
         Ok(db)
     }
@@ -193,6 +210,179 @@ impl Database {
         &self.data.schema
     }
 
+    /// Rewrites every document in the collection `C` that was stored using a
+    /// version older than
+    /// [`Collection::CURRENT_VERSION`](schema::Collection::CURRENT_VERSION) or
+    /// a format other than
+    /// [`Collection::FORMAT_NAME`](schema::Collection::FORMAT_NAME). Returns
+    /// the number of documents that were migrated.
+    ///
+    /// Documents are migrated automatically when they are read, so calling this
+    /// function is optional. Once all documents have been rewritten,
+    /// [`Collection::migrate()`](schema::Collection::migrate) and
+    /// [`Collection::convert_format()`](schema::Collection::convert_format)
+    /// will no longer be invoked when reading documents from this collection.
+    ///
+    /// Each migrated document is updated using its current revision. If a
+    /// document is modified while the migration is running,
+    /// [`bonsaidb_core::Error::DocumentConflict`] may be returned, and this
+    /// function can be called again to migrate the remaining documents.
+    pub fn migrate_collection<C: schema::Collection>(&self) -> Result<usize, bonsaidb_core::Error> {
+        const BATCH_SIZE: usize = 1024;
+
+        let collection = C::collection_name();
+        let tree = self
+            .roots()
+            .tree(
+                self.collection_tree::<Versioned, _>(&collection, document_tree_name(&collection))?,
+            )
+            .map_err(Error::from)?;
+        let mut operations = Vec::new();
+        tree.scan(
+            &(..),
+            true,
+            |_, _, _| ScanEvaluation::ReadData,
+            |_, _| ScanEvaluation::ReadData,
+            |_, _, bytes| {
+                let (document, encoding) =
+                    deserialize_versioned_document(&bytes).map_err(AbortError::Other)?;
+                if let Some(contents) = encoding
+                    .upgrade(&collection, &self.data.schema, &document.contents)
+                    .map_err(|err| AbortError::Other(Error::from(err)))?
+                {
+                    operations.push(Operation::update(
+                        collection.clone(),
+                        document.header,
+                        contents,
+                    ));
+                }
+                Ok(())
+            },
+        )
+        .map_err(|err| match err {
+            AbortError::Other(err) => err,
+            AbortError::Nebari(err) => crate::Error::from(err),
+        })?;
+
+        let migrated = operations.len();
+        for batch in &operations.into_iter().chunks(BATCH_SIZE) {
+            self.apply_transaction(Transaction {
+                operations: batch.collect(),
+            })?;
+        }
+
+        Ok(migrated)
+    }
+
+    /// Deletes every document in the collection `C`, returning the number of
+    /// documents that were deleted.
+    ///
+    /// The documents are read and deleted within a single transaction, so
+    /// other connections observe either the full collection or an empty one,
+    /// and no write to `C` can be applied between reading and deleting its
+    /// documents. Views of `C` are invalidated by the transaction and will be
+    /// empty the next time they are queried. Collections using soft deletes
+    /// keep a tombstone for each deleted document. Relationships referencing
+    /// `C` are applied to each deleted document.
+    pub fn clear_collection<C: schema::Collection>(&self) -> Result<u64, bonsaidb_core::Error> {
+        self.check_writable()?;
+        let collection = C::collection_name();
+        if self.data.schema.virtual_collection(&collection).is_some() {
+            return Err(bonsaidb_core::Error::ReadOnly);
+        }
+        let results =
+            self.execute_transaction_inner(&Transaction::new(), true, Some(&collection))?;
+        Ok(u64::try_from(results.len()).unwrap_or(u64::MAX))
+    }
+
+    /// Removes all stored data for `collection`, including its documents,
+    /// tombstones, and view indexes.
+    ///
+    /// This is intended for cleaning up after a collection has been removed
+    /// from the schema. If `collection` is still defined in this database's
+    /// schema, [`Error::CollectionStillDefined`] is returned. Use
+    /// [`Database::clear_collection()`] to remove the documents of a
+    /// collection that is still in use.
+    pub fn delete_collection(
+        &self,
+        collection: &CollectionName,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.check_permission(
+            collection_resource_name(self.name(), collection),
+            &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Delete)),
+        )?;
+        self.storage.instance.check_writable()?;
+        if self
+            .data
+            .schema
+            .collections()
+            .any(|name| name == collection)
+        {
+            return Err(bonsaidb_core::Error::from(Error::CollectionStillDefined(
+                collection.clone(),
+            )));
+        }
+
+        let roots = self.roots();
+        let view_prefix = format!("view.{collection:#}.");
+        for tree in roots.tree_names().map_err(Error::from)? {
+            if tree.starts_with(&view_prefix) {
+                roots.delete_tree(tree).map_err(Error::from)?;
+            }
+        }
+        for tree in [
+            document_tree_name(collection),
+            deleted_document_tree_name(collection),
+            document_revisions_tree_name(collection),
+            document_expirations_tree_name(collection),
+            document_changes_tree_name(collection),
+            view_versions_tree_name(collection),
+        ] {
+            roots.delete_tree(tree).map_err(Error::from)?;
+        }
+
+        Ok(())
+    }
+
+    /// Rebuilds the index of the view `V` by mapping every document in its
+    /// collection again. Returns a [`Handle`] to the job performing the
+    /// rebuild, which can be used to wait for the rebuild to complete and to
+    /// watch its progress.
+    ///
+    /// Views are normally updated as they are queried, and are only rebuilt
+    /// completely when [`ViewSchema::version()`](schema::ViewSchema::version)
+    /// changes. This function is useful when a view's index should be
+    /// regenerated immediately, such as after deploying a new view.
+    ///
+    /// The view can be queried while it is being rebuilt. Queries return the
+    /// existing index, and each document's entries are replaced as the
+    /// document is mapped again.
+    pub fn rebuild_view<V: schema::View + 'static>(
+        &self,
+    ) -> Result<Handle<(), Error>, bonsaidb_core::Error> {
+        let view = self.data.schema.view::<V>()?;
+        self.check_permission(
+            view_resource_name(self.name(), &view.view_name()),
+            &BonsaiAction::Database(DatabaseAction::View(ViewAction::Rebuild)),
+        )?;
+        self.storage.instance.check_writable()?;
+        // Rebuilding must happen after the integrity check, which clears the
+        // view if its version has changed.
+        if let Some(integrity_check) = self
+            .storage
+            .instance
+            .tasks()
+            .spawn_integrity_check(view, self)
+        {
+            integrity_check
+                .receive()
+                .map_err(Error::from)?
+                .map_err(Error::from)?;
+        }
+
+        Ok(self.storage.instance.tasks().rebuild_view(view, self))
+    }
+
     pub(crate) fn roots(&self) -> &'_ nebari::Roots<AnyFile> {
         &self.data.context.roots
     }
@@ -206,6 +396,7 @@ impl Database {
         access_policy: AccessPolicy,
         mut callback: F,
     ) -> Result<(), bonsaidb_core::Error> {
+        self.refresh_virtual_collection(&view.collection())?;
         if matches!(access_policy, AccessPolicy::UpdateBefore) {
             self.storage
                 .instance
@@ -254,13 +445,93 @@ impl Database {
         Ok(())
     }
 
-    fn open_trees_for_transaction(&self, transaction: &Transaction) -> Result<OpenTrees, Error> {
+    /// Applies `transaction` after ensuring all eager views of the affected
+    /// collections are up-to-date. Permissions are not checked.
+    fn execute_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Vec<OperationResult>, bonsaidb_core::Error> {
+        self.execute_transaction_inner(transaction, true, None)
+    }
+
+    /// Applies `transaction`, applying the relationships of the documents it
+    /// deletes if `apply_relationships` is true.
+    ///
+    /// If `clear` is provided, every document in that collection is deleted
+    /// within the same transaction before `transaction`'s operations are
+    /// applied, and the results of those deletions are returned first.
+    fn execute_transaction_inner(
+        &self,
+        transaction: &Transaction,
+        apply_relationships: bool,
+        clear: Option<&CollectionName>,
+    ) -> Result<Vec<OperationResult>, bonsaidb_core::Error> {
+        let mut eager_view_tasks = Vec::new();
+        let mut collections = transaction
+            .operations
+            .iter()
+            .map(|op| op.collection.clone())
+            .chain(clear.cloned())
+            .collect::<HashSet<_>>();
+        if apply_relationships {
+            collections.extend(
+                self.related_collections(relationships::deleted_collections(transaction, clear)),
+            );
+        }
+        for collection_name in &collections {
+            for view in self.data.schema.eager_views_in_collection(collection_name) {
+                if let Some(task) = self
+                    .storage
+                    .instance
+                    .tasks()
+                    .spawn_integrity_check(view, self)
+                {
+                    eager_view_tasks.push(task);
+                }
+            }
+        }
+
+        let mut eager_view_mapping_tasks = Vec::new();
+        for task in eager_view_tasks {
+            if let Some(spawned_task) = task.receive().map_err(Error::from)?.map_err(Error::from)? {
+                eager_view_mapping_tasks.push(spawned_task);
+            }
+        }
+
+        for task in eager_view_mapping_tasks {
+            let mut task = task.lock();
+            if let Some(task) = task.take() {
+                task.receive().map_err(Error::from)?.map_err(Error::from)?;
+            }
+        }
+
+        self.apply_transaction_to_roots(transaction, apply_relationships, clear)
+            .map_err(bonsaidb_core::Error::from)
+    }
+
+    fn open_trees_for_transaction(
+        &self,
+        transaction: &Transaction,
+        apply_relationships: bool,
+        clear: Option<&CollectionName>,
+    ) -> Result<OpenTrees, Error> {
         let mut open_trees = OpenTrees::default();
-        for op in &transaction.operations {
+        let related_collections = if apply_relationships {
+            self.related_collections(relationships::deleted_collections(transaction, clear))
+        } else {
+            HashSet::new()
+        };
+        for collection in transaction
+            .operations
+            .iter()
+            .map(|op| op.collection.clone())
+            .chain(clear.cloned())
+            .chain(related_collections)
+        {
             if self
                 .data
                 .schema
-                .collection_primary_key_description(&op.collection)
+                .collection_primary_key_description(&collection)
                 .is_none()
             {
                 return Err(Error::Core(bonsaidb_core::Error::CollectionNotFound));
@@ -268,7 +539,7 @@ impl Database {
 
             #[cfg(any(feature = "encryption", feature = "compression"))]
             let vault = if let Some(encryption_key) =
-                self.collection_encryption_key(&op.collection).cloned()
+                self.collection_encryption_key(&collection).cloned()
             {
                 #[cfg(feature = "encryption")]
                 if let Some(mut vault) = self.storage().tree_vault().cloned() {
@@ -293,8 +564,9 @@ impl Database {
             };
 
             open_trees.open_trees_for_document_change(
-                &op.collection,
+                &collection,
                 &self.data.schema,
+                self.storage.instance.revision_history() > 0,
                 #[cfg(any(feature = "encryption", feature = "compression"))]
                 vault,
             );
@@ -306,8 +578,11 @@ impl Database {
     fn apply_transaction_to_roots(
         &self,
         transaction: &Transaction,
+        apply_relationships: bool,
+        clear: Option<&CollectionName>,
     ) -> Result<Vec<OperationResult>, Error> {
-        let open_trees = self.open_trees_for_transaction(transaction)?;
+        let open_trees =
+            self.open_trees_for_transaction(transaction, apply_relationships, clear)?;
 
         let mut roots_transaction = self
             .data
@@ -315,17 +590,73 @@ impl Database {
             .roots
             .transaction::<_, dyn AnyTreeRoot<AnyFile>>(&open_trees.trees)?;
 
+        // The documents being cleared are read within the transaction, so no
+        // other writer can change the collection between reading and deleting
+        // them.
+        let mut operations = match clear {
+            Some(collection) => self
+                .clear_operations(
+                    collection,
+                    &mut roots_transaction,
+                    &open_trees.trees_index_by_name,
+                )?
+                .into_iter()
+                .map(Cow::Owned)
+                .collect::<Vec<_>>(),
+            None => Vec::new(),
+        };
+        let requested_operations = operations.len() + transaction.operations.len();
+        // Relationships append the operations they cause to the end of the
+        // transaction's operations.
+        operations.extend(transaction.operations.iter().map(Cow::Borrowed));
         let mut results = Vec::new();
         let mut changed_documents = Vec::new();
         let mut collection_indexes = HashMap::new();
         let mut collections = Vec::new();
-        for op in &transaction.operations {
+        let mut patched_contents = Vec::new();
+        let mut deleted = HashSet::new();
+        while let Some(op) = operations.get(results.len()) {
+            // The operations caused by relationships may target documents the
+            // transaction has already deleted, such as a child deleted
+            // alongside its parent.
+            if results.len() >= requested_operations {
+                if let Command::Delete { header } | Command::Update { header, .. } = &op.command {
+                    if deleted.contains(&(op.collection.clone(), header.id.clone())) {
+                        results.push(OperationResult::Success);
+                        continue;
+                    }
+                }
+            }
+
             let result = self.execute_operation(
                 op,
                 &mut roots_transaction,
                 &open_trees.trees_index_by_name,
+                &mut patched_contents,
             )?;
 
+            match &result {
+                OperationResult::DocumentDeleted { collection, id } if apply_relationships => {
+                    deleted.insert((collection.clone(), id.clone()));
+                    let related = self.related_operations(
+                        collection,
+                        id,
+                        &mut roots_transaction,
+                        &open_trees.trees_index_by_name,
+                    )?;
+                    operations.extend(related.into_iter().map(Cow::Owned));
+                }
+                OperationResult::DocumentUpdated { header, collection } => {
+                    self.record_revision(
+                        header,
+                        collection,
+                        &mut roots_transaction,
+                        &open_trees.trees_index_by_name,
+                    )?;
+                }
+                _ => {}
+            }
+
             if let Some((collection, id, deleted)) = match &result {
                 OperationResult::DocumentUpdated { header, collection } => {
                     Some((collection, header.id.clone(), false))
@@ -363,6 +694,15 @@ impl Database {
             &changed_documents,
         )?;
 
+        let document_changes =
+            DocumentChange::from_operations(&operations, &results, &patched_contents)
+                .collect::<Vec<_>>();
+        triggers::record_changes(
+            &document_changes,
+            &mut roots_transaction,
+            &open_trees.trees_index_by_name,
+        )?;
+
         roots_transaction
             .entry_mut()
             .set_data(compat::serialize_executed_transaction_changes(
@@ -372,11 +712,126 @@ impl Database {
                 }),
             )?)?;
 
+        let transaction_id = roots_transaction.entry().id;
         roots_transaction.commit()?;
 
+        self.publish_changes(transaction_id, &document_changes);
+        self.data
+            .context
+            .virtual_collections
+            .invalidate(&self.data.schema, &collections);
+        self.storage
+            .instance
+            .tasks()
+            .spawn_triggers(self, &collections);
+
+        // Only return the results of the operations in the transaction.
+        results.truncate(requested_operations);
         Ok(results)
     }
 
+    /// Returns operations deleting every document in `collection`, reading
+    /// the documents from `transaction`.
+    fn clear_operations(
+        &self,
+        collection: &CollectionName,
+        transaction: &mut ExecutingTransaction<AnyFile>,
+        tree_index_map: &HashMap<String, usize>,
+    ) -> Result<Vec<Operation>, Error> {
+        let documents = transaction
+            .tree::<Versioned>(tree_index_map[&document_tree_name(collection)])
+            .unwrap()
+            .get_range(&(..))?;
+        documents
+            .into_iter()
+            .map(|(_, stored)| {
+                let header = deserialize_document(&stored)?.header;
+                self.check_permission(
+                    document_resource_name(self.name(), collection, &header.id),
+                    &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Delete)),
+                )?;
+                Ok(Operation::delete(collection.clone(), header))
+            })
+            .collect()
+    }
+
+    /// Records `header`'s revision in the revision history of its document, if
+    /// revision history is enabled. Once the document has more revisions
+    /// than the configured maximum, the oldest revisions are removed.
+    fn record_revision(
+        &self,
+        header: &Header,
+        collection: &CollectionName,
+        transaction: &mut ExecutingTransaction<AnyFile>,
+        tree_index_map: &HashMap<String, usize>,
+    ) -> Result<(), Error> {
+        let Some(revisions_index) = tree_index_map.get(&document_revisions_tree_name(collection))
+        else {
+            return Ok(());
+        };
+        let max_revisions = self.storage.instance.revision_history();
+        let transaction_id = transaction.entry().id;
+        let revision = pot::to_vec(&HistoricalRevision {
+            revision: header.revision,
+            transaction_id: Some(transaction_id),
+            timestamp: Some(Timestamp::now()),
+        })?;
+
+        let first = revision_history_key(&header.id, 0);
+        let last = revision_history_key(&header.id, u64::MAX);
+
+        let mut revisions = transaction.tree::<Unversioned>(*revisions_index).unwrap();
+        revisions.set(revision_history_key(&header.id, transaction_id), revision)?;
+        let recorded = revisions.get_range(&(first.as_slice()..=last.as_slice()))?;
+        if recorded.len() > max_revisions {
+            let excess = recorded.len() - max_revisions;
+            let expired = recorded
+                .into_iter()
+                .take(excess)
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>();
+            revisions.modify(expired, nebari::tree::Operation::Remove)?;
+        }
+
+        Ok(())
+    }
+
+    /// Publishes `changes` to each changed collection's [change feed
+    /// topic](change_feed_topic).
+    ///
+    /// Each change is only delivered to subscribers allowed to get the changed
+    /// document. This is called after the transaction has been committed, so
+    /// errors are logged rather than returned.
+    fn publish_changes(&self, transaction_id: u64, changes: &[DocumentChange<'_>]) {
+        let get = BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get));
+        let mut topics = HashMap::<&CollectionName, Option<Vec<u8>>>::new();
+        for change in changes {
+            let topic = topics.entry(change.collection).or_insert_with(|| {
+                pot::to_vec(&change_feed_topic(change.collection))
+                    .map_err(|err| {
+                        log::error!("[database] error serializing change feed topic: {err}");
+                    })
+                    .ok()
+            });
+            let Some(topic) = topic else {
+                continue;
+            };
+            let resource =
+                document_resource_name(self.name(), change.collection, &change.header.id);
+            if let Err(err) = self.storage.instance.publish_internal(
+                &self.data.name,
+                topic,
+                |subscriber| subscriber.allowed_to(&resource, &get),
+                || Ok(pot::to_vec(&change.to_change(transaction_id))?),
+            ) {
+                log::error!(
+                    "[database] error publishing change to {}: {err}",
+                    change.collection
+                );
+            }
+        }
+    }
+
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     fn invalidate_changed_documents(
         &self,
@@ -417,6 +872,7 @@ impl Database {
         operation: &Operation,
         transaction: &mut ExecutingTransaction<AnyFile>,
         tree_index_map: &HashMap<String, usize>,
+        patched_contents: &mut Vec<Vec<u8>>,
     ) -> Result<OperationResult, Error> {
         match &operation.command {
             Command::Insert { id, contents } => {
@@ -433,6 +889,22 @@ impl Database {
             Command::Overwrite { id, contents } => {
                 self.execute_update(operation, transaction, tree_index_map, id, None, contents)
             }
+            Command::Patch {
+                id,
+                revision,
+                patch,
+            } => {
+                let (result, contents) = self.execute_patch(
+                    operation,
+                    transaction,
+                    tree_index_map,
+                    id,
+                    revision.as_ref(),
+                    patch,
+                )?;
+                patched_contents.push(contents);
+                Ok(result)
+            }
             Command::Delete { header } => {
                 self.execute_delete(operation, transaction, tree_index_map, header)
             }
@@ -471,6 +943,7 @@ impl Database {
             .tree::<Versioned>(tree_index_map[&document_tree_name(&operation.collection)])
             .unwrap();
         let document_id = ArcBytes::from(id.to_vec());
+        let encoding = ContentsEncoding::current(&operation.collection, &self.data.schema);
         let mut result = None;
         let mut updated = false;
         documents.modify(
@@ -494,10 +967,13 @@ impl Database {
                                 id: id.clone(),
                                 revision: updated_revision,
                             };
-                            let serialized_doc = match serialize_document(&BorrowedDocument {
-                                header: updated_header.clone(),
-                                contents: CowBytes::from(contents),
-                            }) {
+                            let serialized_doc = match serialize_document(
+                                &BorrowedDocument {
+                                    header: updated_header.clone(),
+                                    contents: CowBytes::from(contents),
+                                },
+                                &encoding,
+                            ) {
                                 Ok(bytes) => bytes,
                                 Err(err) => {
                                     result = Some(Err(Error::from(err)));
@@ -528,7 +1004,7 @@ impl Database {
                     }
                 } else if check_revision.is_none() {
                     let doc = BorrowedDocument::new(id.clone(), contents);
-                    match serialize_document(&doc).map(|bytes| (doc, bytes)) {
+                    match serialize_document(&doc, &encoding).map(|bytes| (doc, bytes)) {
                         Ok((doc, serialized)) => {
                             result = Some(Ok(OperationResult::DocumentUpdated {
                                 collection: operation.collection.clone(),
@@ -553,12 +1029,74 @@ impl Database {
         drop(documents);
 
         if updated {
+            Self::remove_tombstone(&document_id, operation, transaction, tree_index_map)?;
+            Self::record_document_update(&document_id, operation, transaction, tree_index_map)?;
             self.update_eager_views(&document_id, operation, transaction, tree_index_map)?;
         }
 
         result.expect("nebari should invoke the callback even when the key isn't found")
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "trace",
+            skip(self, operation, transaction, tree_index_map, patch),
+            fields(
+                database = self.name(),
+                collection.name = operation.collection.name.as_ref(),
+                collection.authority = operation.collection.authority.as_ref()
+            )
+        )
+    )]
+    fn execute_patch(
+        &self,
+        operation: &Operation,
+        transaction: &mut ExecutingTransaction<AnyFile>,
+        tree_index_map: &HashMap<String, usize>,
+        id: &DocumentId,
+        check_revision: Option<&Revision>,
+        patch: &[u8],
+    ) -> Result<(OperationResult, Vec<u8>), Error> {
+        let stored = transaction
+            .tree::<Versioned>(tree_index_map[&document_tree_name(&operation.collection)])
+            .unwrap()
+            .get(id.as_ref())?
+            .ok_or_else(|| {
+                Error::Core(bonsaidb_core::Error::DocumentNotFound(
+                    operation.collection.clone(),
+                    Box::new(id.clone()),
+                ))
+            })?;
+        let document =
+            deserialize_current_document(&stored, &operation.collection, &self.data.schema)?;
+        if check_revision.map_or(false, |revision| revision != &document.header.revision) {
+            return Err(Error::Core(bonsaidb_core::Error::DocumentConflict(
+                operation.collection.clone(),
+                Box::new(document.header),
+            )));
+        }
+
+        // The patch is applied while the transaction is executing, ensuring no
+        // other writes can change the document between reading and updating it.
+        let contents =
+            self.data
+                .schema
+                .apply_patch(&operation.collection, &document.contents, patch)?;
+        self.data
+            .schema
+            .validate_contents(&operation.collection, &contents)?;
+        let result = self.execute_update(
+            operation,
+            transaction,
+            tree_index_map,
+            id,
+            Some(&document.header.revision),
+            &contents,
+        )?;
+        Ok((result, contents))
+    }
+
     #[cfg_attr(
         feature = "tracing",
         tracing::instrument(
@@ -579,25 +1117,45 @@ impl Database {
         id: Option<DocumentId>,
         contents: &[u8],
     ) -> Result<OperationResult, Error> {
-        let mut documents = transaction
-            .tree::<Versioned>(tree_index_map[&document_tree_name(&operation.collection)])
-            .unwrap();
+        let documents_index = tree_index_map[&document_tree_name(&operation.collection)];
         let id = if let Some(id) = id {
             id
-        } else if let Some(last_key) = documents.last_key()? {
-            let id = DocumentId::try_from(last_key.as_slice())?;
-            self.data
-                .schema
-                .next_id_for_collection(&operation.collection, Some(id))?
         } else {
-            self.data
-                .schema
-                .next_id_for_collection(&operation.collection, None)?
+            let mut last_key = transaction
+                .tree::<Versioned>(documents_index)
+                .unwrap()
+                .last_key()?;
+            // Ids of soft-deleted documents must not be reassigned, otherwise
+            // the deleted documents could no longer be restored.
+            if let Some(tombstones_index) =
+                tree_index_map.get(&deleted_document_tree_name(&operation.collection))
+            {
+                let last_deleted_key = transaction
+                    .tree::<Unversioned>(*tombstones_index)
+                    .unwrap()
+                    .last_key()?;
+                last_key = last_key.max(last_deleted_key);
+            }
+
+            if let Some(last_key) = last_key {
+                let id = DocumentId::try_from(last_key.as_slice())?;
+                self.data
+                    .schema
+                    .next_id_for_collection(&operation.collection, Some(id))?
+            } else {
+                self.data
+                    .schema
+                    .next_id_for_collection(&operation.collection, None)?
+            }
         };
 
         let doc = BorrowedDocument::new(id, contents);
-        let serialized: Vec<u8> = serialize_document(&doc)?;
+        let serialized: Vec<u8> = serialize_document(
+            &doc,
+            &ContentsEncoding::current(&operation.collection, &self.data.schema),
+        )?;
         let document_id = ArcBytes::from(doc.header.id.as_ref().to_vec());
+        let mut documents = transaction.tree::<Versioned>(documents_index).unwrap();
         if let Some(document) = documents.replace(document_id.clone(), serialized)? {
             let doc = deserialize_document(&document)?;
             Err(Error::Core(bonsaidb_core::Error::DocumentConflict(
@@ -606,6 +1164,8 @@ impl Database {
             )))
         } else {
             drop(documents);
+            Self::remove_tombstone(&document_id, operation, transaction, tree_index_map)?;
+            Self::record_document_update(&document_id, operation, transaction, tree_index_map)?;
             self.update_eager_views(&document_id, operation, transaction, tree_index_map)?;
 
             Ok(OperationResult::DocumentUpdated {
@@ -636,15 +1196,32 @@ impl Database {
             .unwrap();
         if let Some(vec) = documents.remove(header.id.as_ref())? {
             drop(documents);
-            let doc = deserialize_document(&vec)?;
+            let (doc, encoding) = deserialize_versioned_document(&vec)?;
             if &doc.header == header {
-                self.update_eager_views(
-                    &ArcBytes::from(doc.header.id.to_vec()),
+                let document_id = ArcBytes::from(header.id.to_vec());
+                if let Some(tombstones_index) =
+                    tree_index_map.get(&deleted_document_tree_name(&operation.collection))
+                {
+                    let tombstone = serialize_tombstone(&Tombstone {
+                        deleted_at: Timestamp::now(),
+                        document: doc,
+                        version: encoding.version,
+                        format: encoding.format,
+                    })?;
+                    transaction
+                        .tree::<Unversioned>(*tombstones_index)
+                        .unwrap()
+                        .set(document_id.clone(), tombstone)?;
+                }
+                Self::remove_document_expiration(
+                    &document_id,
                     operation,
                     transaction,
                     tree_index_map,
                 )?;
 
+                self.update_eager_views(&document_id, operation, transaction, tree_index_map)?;
+
                 Ok(OperationResult::DocumentDeleted {
                     collection: operation.collection.clone(),
                     id: header.id.clone(),
@@ -663,6 +1240,23 @@ impl Database {
         }
     }
 
+    fn remove_tombstone(
+        document_id: &ArcBytes<'static>,
+        operation: &Operation,
+        transaction: &mut ExecutingTransaction<AnyFile>,
+        tree_index_map: &HashMap<String, usize>,
+    ) -> Result<(), Error> {
+        if let Some(tombstones_index) =
+            tree_index_map.get(&deleted_document_tree_name(&operation.collection))
+        {
+            transaction
+                .tree::<Unversioned>(*tombstones_index)
+                .unwrap()
+                .remove(document_id.as_slice())?;
+        }
+        Ok(())
+    }
+
     #[cfg_attr(feature = "tracing", tracing::instrument(
         level = "trace",
         skip(self, operation, transaction, tree_index_map),
@@ -696,6 +1290,13 @@ impl Database {
                 let view_entries = transaction
                     .unlocked_tree(tree_index_map[&view_entries_tree_name(&name)])
                     .unwrap();
+                let tombstones = if view.include_soft_deleted() {
+                    tree_index_map
+                        .get(&deleted_document_tree_name(&operation.collection))
+                        .map(|index| transaction.unlocked_tree(*index).unwrap())
+                } else {
+                    None
+                };
                 mapper::DocumentRequest {
                     database: self,
                     document_ids: vec![document_id.clone()],
@@ -706,6 +1307,7 @@ impl Database {
                     },
                     document_map,
                     documents,
+                    tombstones,
                     view_entries,
                     view,
                 }
@@ -794,14 +1396,17 @@ impl Database {
                     values.extend(view_entries.get(&key)?);
                 }
                 SerializedQueryKey::Multiple(mut list) => {
+                    // Clients may send unsorted or duplicate keys.
                     list.sort();
+                    list.dedup();
 
-                    values.extend(
-                        view_entries
-                            .get_multiple(list.iter().map(|bytes| bytes.as_slice()))?
-                            .into_iter()
-                            .map(|(_, value)| value),
-                    );
+                    let mut entries =
+                        view_entries.get_multiple(list.iter().map(|bytes| bytes.as_slice()))?;
+                    if !forwards {
+                        entries.reverse();
+                    }
+                    let limit = limit.map_or(usize::MAX, |limit| limit as usize);
+                    values.extend(entries.into_iter().take(limit).map(|(_, value)| value));
                 }
             }
         } else {
@@ -831,23 +1436,255 @@ impl Database {
             .collect::<Result<Vec<_>, Error>>()
     }
 
-    #[cfg(any(feature = "encryption", feature = "compression"))]
-    pub(crate) fn collection_encryption_key(&self, collection: &CollectionName) -> Option<&KeyId> {
-        self.schematic()
-            .encryption_key_for_collection(collection)
-            .or_else(|| self.storage.default_encryption_key())
-    }
-
-    #[cfg_attr(
-        not(feature = "encryption"),
-        allow(
-            unused_mut,
-            unused_variables,
-            clippy::unused_self,
-            clippy::let_and_return
-        )
-    )]
-    #[allow(clippy::unnecessary_wraps)]
+    fn explain_view_query(
+        view_entries: &Tree<Unversioned, AnyFile>,
+        key: Option<SerializedQueryKey>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, Error> {
+        let forwards = matches!(order, Sort::Ascending);
+        let plan = match key {
+            None => {
+                let scanned_entries = Self::count_view_keys(view_entries, &(..), forwards, limit)?;
+                QueryPlan {
+                    index_used: IndexAccess::FullScan,
+                    scanned_entries,
+                    estimated_rows: scanned_entries,
+                }
+            }
+            Some(SerializedQueryKey::Range(range)) => {
+                let scanned_entries = Self::count_view_keys(
+                    view_entries,
+                    &range.map_ref(|bytes| &bytes[..]),
+                    forwards,
+                    limit,
+                )?;
+                QueryPlan {
+                    index_used: IndexAccess::RangeScan,
+                    scanned_entries,
+                    estimated_rows: scanned_entries,
+                }
+            }
+            Some(SerializedQueryKey::Matches(key)) => QueryPlan {
+                index_used: IndexAccess::KeyLookup,
+                scanned_entries: 1,
+                estimated_rows: Self::count_view_keys(
+                    view_entries,
+                    &(&key[..]..=&key[..]),
+                    forwards,
+                    None,
+                )?,
+            },
+            Some(SerializedQueryKey::Multiple(mut keys)) => {
+                keys.sort();
+                keys.dedup();
+                let mut estimated_rows = 0;
+                for key in &keys {
+                    estimated_rows += Self::count_view_keys(
+                        view_entries,
+                        &(&key[..]..=&key[..]),
+                        forwards,
+                        None,
+                    )?;
+                }
+                QueryPlan {
+                    index_used: IndexAccess::KeyLookup,
+                    scanned_entries: keys.len() as u64,
+                    estimated_rows,
+                }
+            }
+        };
+        Ok(plan)
+    }
+
+    /// Counts the keys in `range` without reading any of the view entries.
+    fn count_view_keys<'a, R>(
+        view_entries: &Tree<Unversioned, AnyFile>,
+        range: &'a R,
+        forwards: bool,
+        limit: Option<u32>,
+    ) -> Result<u64, Error>
+    where
+        R: RangeBounds<&'a [u8]> + Debug + ?Sized,
+    {
+        let mut keys = 0;
+        view_entries.scan::<Infallible, _, _, _, _>(
+            range,
+            forwards,
+            |_, _, _| ScanEvaluation::ReadData,
+            |_, _| {
+                if limit.map_or(false, |limit| keys >= u64::from(limit)) {
+                    return ScanEvaluation::Stop;
+                }
+                keys += 1;
+                ScanEvaluation::Skip
+            },
+            |_, _, _| Ok(()),
+        )?;
+        Ok(keys)
+    }
+
+    fn scan_documents<R: Root, T, F>(
+        &self,
+        collection: &CollectionName,
+        tree_name: String,
+        ids: Range<DocumentId>,
+        sort: Sort,
+        limit: Option<u32>,
+        mut deserialize: F,
+    ) -> Result<Vec<T>, Error>
+    where
+        F: FnMut(&[u8]) -> Result<T, Error>,
+    {
+        let tree = self
+            .data
+            .context
+            .roots
+            .tree(self.collection_tree::<R, _>(collection, tree_name)?)?;
+        let mut found_docs = Vec::new();
+        let mut keys_read = 0;
+        let ids = DocumentIdRange(ids);
+        tree.scan(
+            &ids.borrow_as_bytes(),
+            match sort {
+                Sort::Ascending => true,
+                Sort::Descending => false,
+            },
+            |_, _, _| ScanEvaluation::ReadData,
+            |_, _| {
+                if let Some(limit) = limit {
+                    if keys_read >= limit {
+                        return ScanEvaluation::Stop;
+                    }
+
+                    keys_read += 1;
+                }
+                ScanEvaluation::ReadData
+            },
+            |_, _, doc| {
+                found_docs.push(deserialize(&doc).map_err(AbortError::Other)?);
+                Ok(())
+            },
+        )
+        .map_err(|err| match err {
+            AbortError::Other(err) => err,
+            AbortError::Nebari(err) => crate::Error::from(err),
+        })?;
+
+        Ok(found_docs)
+    }
+
+    /// Checks that the session is allowed to apply `transaction` to this
+    /// database, and validates the contents of each document it writes.
+    fn check_transaction(&self, transaction: &Transaction) -> Result<(), bonsaidb_core::Error> {
+        for op in &transaction.operations {
+            let (resource, action) = match &op.command {
+                Command::Insert { .. } => (
+                    collection_resource_name(self.name(), &op.collection),
+                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Insert)),
+                ),
+                Command::Update { header, .. } => (
+                    document_resource_name(self.name(), &op.collection, &header.id),
+                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Update)),
+                ),
+                Command::Overwrite { id, .. } => (
+                    document_resource_name(self.name(), &op.collection, id),
+                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Overwrite)),
+                ),
+                Command::Patch { id, .. } => (
+                    document_resource_name(self.name(), &op.collection, id),
+                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Update)),
+                ),
+                Command::Delete { header } => (
+                    document_resource_name(self.name(), &op.collection, &header.id),
+                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Delete)),
+                ),
+                Command::Check { id, .. } => (
+                    document_resource_name(self.name(), &op.collection, id),
+                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get)),
+                ),
+            };
+            self.check_permission(resource, &action)?;
+        }
+        self.check_writable()?;
+        if transaction.operations.iter().any(|op| {
+            self.data
+                .schema
+                .virtual_collection(&op.collection)
+                .is_some()
+        }) {
+            return Err(bonsaidb_core::Error::ReadOnly);
+        }
+
+        for op in &transaction.operations {
+            if let Command::Insert { contents, .. }
+            | Command::Update { contents, .. }
+            | Command::Overwrite { contents, .. } = &op.command
+            {
+                self.data
+                    .schema
+                    .validate_contents(&op.collection, contents)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn check_writable(&self) -> Result<(), bonsaidb_core::Error> {
+        self.storage.instance.check_writable()?;
+        if self.storage.instance.is_replica(self.name()) {
+            Err(bonsaidb_core::Error::ReadOnlyReplica(
+                self.name().to_string(),
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Applies `transaction` to this database, even if this database is a
+    /// replica. No permission checks are performed.
+    ///
+    /// Relationships are not applied, because the replicated changes already
+    /// include the changes the leader's relationships made.
+    #[cfg(feature = "internal-apis")]
+    #[doc(hidden)]
+    pub fn apply_replicated_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<Vec<OperationResult>, bonsaidb_core::Error> {
+        self.storage.instance.check_writable()?;
+        self.execute_transaction_inner(transaction, false, None)
+    }
+
+    /// Executes `op` against this database's key-value store, even if this
+    /// database is a replica. No permission checks are performed.
+    #[cfg(feature = "internal-apis")]
+    #[doc(hidden)]
+    pub fn execute_replicated_key_operation(
+        &self,
+        op: KeyOperation,
+    ) -> Result<Output, bonsaidb_core::Error> {
+        self.storage.instance.check_writable()?;
+        self.data.context.perform_kv_operation(op)
+    }
+
+    #[cfg(any(feature = "encryption", feature = "compression"))]
+    pub(crate) fn collection_encryption_key(&self, collection: &CollectionName) -> Option<&KeyId> {
+        self.schematic()
+            .encryption_key_for_collection(collection)
+            .or_else(|| self.storage.default_encryption_key())
+    }
+
+    #[cfg_attr(
+        not(feature = "encryption"),
+        allow(
+            unused_mut,
+            unused_variables,
+            clippy::unused_self,
+            clippy::let_and_return
+        )
+    )]
+    #[allow(clippy::unnecessary_wraps)]
     pub(crate) fn collection_tree<R: Root, S: Into<Cow<'static, str>>>(
         &self,
         collection: &CollectionName,
@@ -896,6 +1733,45 @@ impl Database {
         Ok(tree)
     }
 
+    /// Returns statistics about the storage used by `collection`, using the
+    /// statistics tracked by each tree's index.
+    pub(crate) fn collection_stats(
+        &self,
+        collection: &CollectionName,
+    ) -> Result<CollectionStats, Error> {
+        let all = DocumentIdRange(Range {
+            start: connection::Bound::Unbounded,
+            end: connection::Bound::Unbounded,
+        });
+        let documents = self
+            .roots()
+            .tree(
+                self.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?,
+            )?
+            .reduce(&all.borrow_as_bytes())?;
+
+        let mut index_bytes = 0;
+        for view in self.schematic().views_in_collection(collection) {
+            let view_name = view.view_name();
+            for tree_name in [
+                view_entries_tree_name(&view_name),
+                view_document_map_tree_name(&view_name),
+            ] {
+                let index = self
+                    .roots()
+                    .tree(self.collection_tree::<Unversioned, _>(collection, tree_name)?)?
+                    .reduce(&all.borrow_as_bytes())?;
+                index_bytes += index.total_indexed_bytes;
+            }
+        }
+
+        Ok(CollectionStats {
+            document_count: documents.alive_keys,
+            document_bytes: documents.total_indexed_bytes,
+            index_bytes,
+        })
+    }
+
     pub(crate) fn update_key_expiration<'key>(
         &self,
         tree_key: impl Into<Cow<'key, str>>,
@@ -965,32 +1841,221 @@ struct LegacyDocument<'a> {
     contents: &'a [u8],
 }
 
+/// A document as it is stored in a collection's document tree.
+#[derive(Serialize, Deserialize)]
+struct StoredDocument<'a> {
+    header: Header,
+    #[serde(borrow)]
+    contents: CowBytes<'a>,
+    /// The [`Collection::CURRENT_VERSION`](schema::Collection::CURRENT_VERSION)
+    /// the contents were serialized with. Documents written before versioning
+    /// was introduced have no version, which is treated as 0.
+    #[serde(default, skip_serializing_if = "is_unversioned")]
+    version: u32,
+    /// The [`Collection::FORMAT_NAME`](schema::Collection::FORMAT_NAME) the
+    /// contents were serialized with, if the collection named its format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // serde requires a reference
+const fn is_unversioned(version: &u32) -> bool {
+    *version == 0
+}
+
+/// How the contents of a stored document were serialized.
+#[derive(Default)]
+struct ContentsEncoding {
+    version: u32,
+    format: Option<String>,
+}
+
+impl ContentsEncoding {
+    /// Returns the encoding new documents in `collection` are stored with.
+    fn current(collection: &CollectionName, schema: &Schematic) -> Self {
+        Self {
+            version: schema.collection_version(collection),
+            format: schema.collection_format(collection).map(String::from),
+        }
+    }
+
+    /// Converts `contents` to the collection's current format, and then
+    /// migrates it to the collection's current version. Returns `None` if
+    /// `contents` is already using the current encoding.
+    fn upgrade(
+        &self,
+        collection: &CollectionName,
+        schema: &Schematic,
+        contents: &[u8],
+    ) -> Result<Option<Vec<u8>>, bonsaidb_core::Error> {
+        let converted = schema.convert_contents(collection, self.format.as_deref(), contents)?;
+        let migrated = schema.migrate_contents(
+            collection,
+            self.version,
+            converted.as_deref().unwrap_or(contents),
+        )?;
+        Ok(migrated.or(converted))
+    }
+}
+
+/// Deserializes a stored document without migrating its contents. This should
+/// only be used when the contents of the document are not needed.
 pub(crate) fn deserialize_document(bytes: &[u8]) -> Result<BorrowedDocument<'_>, Error> {
-    match pot::from_slice::<BorrowedDocument<'_>>(bytes) {
-        Ok(document) => Ok(document),
+    deserialize_versioned_document(bytes).map(|(document, _)| document)
+}
+
+fn deserialize_versioned_document(
+    bytes: &[u8],
+) -> Result<(BorrowedDocument<'_>, ContentsEncoding), Error> {
+    match pot::from_slice::<StoredDocument<'_>>(bytes) {
+        Ok(document) => Ok((
+            BorrowedDocument {
+                header: document.header,
+                contents: document.contents,
+            },
+            ContentsEncoding {
+                version: document.version,
+                format: document.format,
+            },
+        )),
         Err(err) => match bincode::deserialize::<LegacyDocument<'_>>(bytes) {
-            Ok(legacy_doc) => Ok(BorrowedDocument {
-                header: Header {
-                    id: DocumentId::from_u64(legacy_doc.header.id),
-                    revision: legacy_doc.header.revision,
+            Ok(legacy_doc) => Ok((
+                BorrowedDocument {
+                    header: Header {
+                        id: DocumentId::from_u64(legacy_doc.header.id),
+                        revision: legacy_doc.header.revision,
+                    },
+                    contents: CowBytes::from(legacy_doc.contents),
                 },
-                contents: CowBytes::from(legacy_doc.contents),
-            }),
+                ContentsEncoding::default(),
+            )),
             Err(_) => Err(Error::from(err)),
         },
     }
 }
 
-fn serialize_document(document: &BorrowedDocument<'_>) -> Result<Vec<u8>, bonsaidb_core::Error> {
-    pot::to_vec(document)
-        .map_err(Error::from)
-        .map_err(bonsaidb_core::Error::from)
+/// Deserializes a stored document from `collection`, migrating its contents to
+/// the collection's current format and version if needed.
+pub(crate) fn deserialize_current_document<'a>(
+    bytes: &'a [u8],
+    collection: &CollectionName,
+    schema: &Schematic,
+) -> Result<BorrowedDocument<'a>, Error> {
+    let (document, encoding) = deserialize_versioned_document(bytes)?;
+    migrate_document(document, &encoding, collection, schema)
+}
+
+fn migrate_document<'a>(
+    mut document: BorrowedDocument<'a>,
+    encoding: &ContentsEncoding,
+    collection: &CollectionName,
+    schema: &Schematic,
+) -> Result<BorrowedDocument<'a>, Error> {
+    if let Some(contents) = encoding.upgrade(collection, schema, &document.contents)? {
+        document.contents = CowBytes::from(contents);
+    }
+    Ok(document)
+}
+
+fn serialize_document(
+    document: &BorrowedDocument<'_>,
+    encoding: &ContentsEncoding,
+) -> Result<Vec<u8>, bonsaidb_core::Error> {
+    pot::to_vec(&StoredDocument {
+        header: document.header.clone(),
+        contents: CowBytes::from(&document.contents[..]),
+        version: encoding.version,
+        format: encoding.format.clone(),
+    })
+    .map_err(Error::from)
+    .map_err(bonsaidb_core::Error::from)
+}
+
+/// A document that was deleted from a collection that uses soft deletes.
+#[derive(Serialize, Deserialize)]
+struct Tombstone<'a> {
+    deleted_at: Timestamp,
+    #[serde(borrow)]
+    document: BorrowedDocument<'a>,
+    #[serde(default)]
+    version: u32,
+    #[serde(default)]
+    format: Option<String>,
+}
+
+fn deserialize_tombstone(bytes: &[u8]) -> Result<Tombstone<'_>, Error> {
+    pot::from_slice(bytes).map_err(Error::from)
+}
+
+/// Deserializes the document stored in a tombstone from `collection`,
+/// migrating its contents to the collection's current format and version if
+/// needed.
+pub(crate) fn deserialize_deleted_document<'a>(
+    bytes: &'a [u8],
+    collection: &CollectionName,
+    schema: &Schematic,
+) -> Result<BorrowedDocument<'a>, Error> {
+    let tombstone = deserialize_tombstone(bytes)?;
+    migrate_document(
+        tombstone.document,
+        &ContentsEncoding {
+            version: tombstone.version,
+            format: tombstone.format,
+        },
+        collection,
+        schema,
+    )
+}
+
+fn serialize_tombstone(tombstone: &Tombstone<'_>) -> Result<Vec<u8>, Error> {
+    pot::to_vec(tombstone).map_err(Error::from)
 }
 
 impl HasSession for Database {
     fn session(&self) -> Option<&Session> {
         self.storage.session()
     }
+
+    fn allowed_to<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> bool {
+        let resource_name = resource_name.as_ref();
+        self.session()
+            .map_or(true, |session| session.allowed_to(resource_name, action))
+            || self.database_allows(resource_name, action)
+    }
+
+    fn check_permission<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let resource_name = resource_name.as_ref();
+        match self.session() {
+            Some(session) => session
+                .check_permission(resource_name, action)
+                .or_else(|err| {
+                    if self.database_allows(resource_name, action) {
+                        Ok(())
+                    } else {
+                        Err(err)
+                    }
+                }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Database {
+    /// Returns true if the permissions this database was created with allow
+    /// `action` against `resource_name`.
+    fn database_allows<P: Action>(&self, resource_name: &[Identifier<'_>], action: &P) -> bool {
+        self.data.permissions.as_ref().map_or(false, |permissions| {
+            permissions.allowed_to(resource_name, action)
+        })
+    }
 }
 
 impl Connection for Database {
@@ -1090,6 +2155,7 @@ impl Connection for Database {
             database_resource_name(self.name()),
             &BonsaiAction::Database(DatabaseAction::Compact),
         )?;
+        self.storage.instance.check_writable()?;
         self.storage()
             .instance
             .tasks()
@@ -1109,6 +2175,7 @@ impl Connection for Database {
             kv_resource_name(self.name()),
             &BonsaiAction::Database(DatabaseAction::Compact),
         )?;
+        self.storage.instance.check_writable()?;
         self.storage()
             .instance
             .tasks()
@@ -1129,67 +2196,8 @@ impl LowLevelConnection for Database {
         &self,
         transaction: Transaction,
     ) -> Result<Vec<OperationResult>, bonsaidb_core::Error> {
-        for op in &transaction.operations {
-            let (resource, action) = match &op.command {
-                Command::Insert { .. } => (
-                    collection_resource_name(self.name(), &op.collection),
-                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Insert)),
-                ),
-                Command::Update { header, .. } => (
-                    document_resource_name(self.name(), &op.collection, &header.id),
-                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Update)),
-                ),
-                Command::Overwrite { id, .. } => (
-                    document_resource_name(self.name(), &op.collection, id),
-                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Overwrite)),
-                ),
-                Command::Delete { header } => (
-                    document_resource_name(self.name(), &op.collection, &header.id),
-                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Delete)),
-                ),
-                Command::Check { id, .. } => (
-                    document_resource_name(self.name(), &op.collection, id),
-                    BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get)),
-                ),
-            };
-            self.check_permission(resource, &action)?;
-        }
-
-        let mut eager_view_tasks = Vec::new();
-        for collection_name in transaction
-            .operations
-            .iter()
-            .map(|op| &op.collection)
-            .collect::<HashSet<_>>()
-        {
-            for view in self.data.schema.eager_views_in_collection(collection_name) {
-                if let Some(task) = self
-                    .storage
-                    .instance
-                    .tasks()
-                    .spawn_integrity_check(view, self)
-                {
-                    eager_view_tasks.push(task);
-                }
-            }
-        }
-
-        let mut eager_view_mapping_tasks = Vec::new();
-        for task in eager_view_tasks {
-            if let Some(spawned_task) = task.receive().map_err(Error::from)?.map_err(Error::from)? {
-                eager_view_mapping_tasks.push(spawned_task);
-            }
-        }
-
-        for task in eager_view_mapping_tasks {
-            let mut task = task.lock();
-            if let Some(task) = task.take() {
-                task.receive().map_err(Error::from)?.map_err(Error::from)?;
-            }
-        }
-
-        self.apply_transaction_to_roots(&transaction)
-            .map_err(bonsaidb_core::Error::from)
+        self.check_transaction(&transaction)?;
+        self.execute_transaction(&transaction)
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(
@@ -1210,17 +2218,21 @@ impl LowLevelConnection for Database {
             document_resource_name(self.name(), collection, &id),
             &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get)),
         )?;
+        self.refresh_virtual_collection(collection)?;
         let tree = self
             .data
             .context
             .roots
             .tree(self.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?)
             .map_err(Error::from)?;
+        let mut documents = Vec::new();
         if let Some(vec) = tree.get(id.as_ref()).map_err(Error::from)? {
-            Ok(Some(deserialize_document(&vec)?.into_owned()))
-        } else {
-            Ok(None)
+            documents.push(
+                deserialize_current_document(&vec, collection, &self.data.schema)?.into_owned(),
+            );
         }
+        self.retain_unexpired(collection, &mut documents, |doc| &doc.header.id)?;
+        Ok(documents.pop())
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(
@@ -1243,6 +2255,43 @@ impl LowLevelConnection for Database {
             collection_resource_name(self.name(), collection),
             &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::List)),
         )?;
+        self.refresh_virtual_collection(collection)?;
+        let mut documents = self.scan_documents::<Versioned, _, _>(
+            collection,
+            document_tree_name(collection),
+            ids,
+            sort,
+            limit,
+            |bytes| {
+                deserialize_current_document(bytes, collection, &self.data.schema)
+                    .map(BorrowedDocument::into_owned)
+            },
+        )?;
+        self.retain_unexpired(collection, &mut documents, |doc| &doc.header.id)?;
+        Ok(documents)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "trace",
+        skip(self, filter, collection),
+        fields(
+            database = self.name(),
+            collection.name = collection.name.as_ref(),
+            collection.authority = collection.authority.as_ref(),
+        )
+    ))]
+    fn filter_from_collection(
+        &self,
+        filter: &Filter,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<OwnedDocument>, bonsaidb_core::Error> {
+        self.check_permission(
+            collection_resource_name(self.name(), collection),
+            &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::List)),
+        )?;
+        self.refresh_virtual_collection(collection)?;
+        let filter = filter.compile()?;
         let tree = self
             .data
             .context
@@ -1250,31 +2299,33 @@ impl LowLevelConnection for Database {
             .tree(self.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?)
             .map_err(Error::from)?;
         let mut found_docs = Vec::new();
-        let mut keys_read = 0;
-        let ids = DocumentIdRange(ids);
+        // The key evaluator stops the scan once enough documents have matched,
+        // which is tracked separately from `found_docs` because both closures
+        // need access to it.
+        let matched = Cell::new(0_u32);
         tree.scan(
-            &ids.borrow_as_bytes(),
-            match sort {
-                Sort::Ascending => true,
-                Sort::Descending => false,
-            },
+            &(..),
+            true,
             |_, _, _| ScanEvaluation::ReadData,
             |_, _| {
-                if let Some(limit) = limit {
-                    if keys_read >= limit {
-                        return ScanEvaluation::Stop;
-                    }
-
-                    keys_read += 1;
+                if limit.map_or(false, |limit| matched.get() >= limit) {
+                    ScanEvaluation::Stop
+                } else {
+                    ScanEvaluation::ReadData
                 }
-                ScanEvaluation::ReadData
             },
-            |_, _, doc| {
-                found_docs.push(
-                    deserialize_document(&doc)
-                        .map(BorrowedDocument::into_owned)
-                        .map_err(AbortError::Other)?,
-                );
+            |_, _, bytes| {
+                let doc = deserialize_current_document(&bytes, collection, &self.data.schema)
+                    .map_err(AbortError::Other)?;
+                let contents = pot::from_slice::<pot::Value<'_>>(&doc.contents).map_err(|_| {
+                    AbortError::Other(Error::Core(bonsaidb_core::Error::FilterUnsupported(
+                        collection.clone(),
+                    )))
+                })?;
+                if filter.matches(&contents) {
+                    matched.set(matched.get() + 1);
+                    found_docs.push(doc.into_owned());
+                }
                 Ok(())
             },
         )
@@ -1283,6 +2334,87 @@ impl LowLevelConnection for Database {
             AbortError::Nebari(err) => crate::Error::from(err),
         })?;
 
+        self.retain_unexpired(collection, &mut found_docs, |doc| &doc.header.id)?;
+
+        Ok(found_docs)
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "trace",
+        skip(self, collection),
+        fields(
+            database = self.name(),
+            collection.name = collection.name.as_ref(),
+            collection.authority = collection.authority.as_ref(),
+        )
+    ))]
+    fn list_including_deleted_from_collection(
+        &self,
+        ids: Range<DocumentId>,
+        sort: Sort,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<MaybeDeleted>, bonsaidb_core::Error> {
+        self.check_permission(
+            collection_resource_name(self.name(), collection),
+            &BonsaiAction::Database(DatabaseAction::Document(
+                DocumentAction::ListIncludingDeleted,
+            )),
+        )?;
+        self.refresh_virtual_collection(collection)?;
+        let mut found_docs = self.scan_documents::<Versioned, _, _>(
+            collection,
+            document_tree_name(collection),
+            ids.clone(),
+            sort,
+            limit,
+            |bytes| {
+                Ok(MaybeDeleted {
+                    document: deserialize_current_document(bytes, collection, &self.data.schema)?
+                        .into_owned(),
+                    deleted_at: None,
+                })
+            },
+        )?;
+
+        if self.data.schema.collection_uses_soft_delete(collection) {
+            found_docs.extend(self.scan_documents::<Unversioned, _, _>(
+                collection,
+                deleted_document_tree_name(collection),
+                ids,
+                sort,
+                limit,
+                |bytes| {
+                    let tombstone = deserialize_tombstone(bytes)?;
+                    let deleted_at = tombstone.deleted_at;
+                    let document = migrate_document(
+                        tombstone.document,
+                        &ContentsEncoding {
+                            version: tombstone.version,
+                            format: tombstone.format,
+                        },
+                        collection,
+                        &self.data.schema,
+                    )?;
+                    Ok(MaybeDeleted {
+                        document: document.into_owned(),
+                        deleted_at: Some(deleted_at),
+                    })
+                },
+            )?);
+            match sort {
+                Sort::Ascending => {
+                    found_docs.sort_by(|a, b| a.document.header.id.cmp(&b.document.header.id));
+                }
+                Sort::Descending => {
+                    found_docs.sort_by(|a, b| b.document.header.id.cmp(&a.document.header.id));
+                }
+            }
+            if let Some(limit) = limit {
+                found_docs.truncate(usize::try_from(limit).unwrap_or(usize::MAX));
+            }
+        }
+
         Ok(found_docs)
     }
 
@@ -1306,6 +2438,7 @@ impl LowLevelConnection for Database {
             collection_resource_name(self.name(), collection),
             &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::ListHeaders)),
         )?;
+        self.refresh_virtual_collection(collection)?;
         let tree = self
             .data
             .context
@@ -1345,10 +2478,133 @@ impl LowLevelConnection for Database {
             AbortError::Other(err) => err,
             AbortError::Nebari(err) => crate::Error::from(err),
         })?;
+        self.retain_unexpired(collection, &mut found_headers, |header| &header.id)?;
 
         Ok(found_headers)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "trace",
+        skip(self, collection),
+        fields(
+            database = self.name(),
+            collection.name = collection.name.as_ref(),
+            collection.authority = collection.authority.as_ref(),
+        )
+    ))]
+    fn restore_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Header, bonsaidb_core::Error> {
+        self.check_permission(
+            document_resource_name(self.name(), collection, &id),
+            &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Restore)),
+        )?;
+        let not_found =
+            || bonsaidb_core::Error::DocumentNotFound(collection.clone(), Box::new(id.clone()));
+        if !self.data.schema.collection_uses_soft_delete(collection) {
+            return Err(not_found());
+        }
+
+        let tree = self
+            .data
+            .context
+            .roots
+            .tree(self.collection_tree::<Unversioned, _>(
+                collection,
+                deleted_document_tree_name(collection),
+            )?)
+            .map_err(Error::from)?;
+        let Some(tombstone) = tree.get(id.as_ref()).map_err(Error::from)? else {
+            return Err(not_found());
+        };
+        let document = deserialize_deleted_document(&tombstone, collection, &self.data.schema)?;
+
+        // Inserting the document removes its tombstone as part of the same
+        // transaction. The insert is checked the same way as any other
+        // transaction, because the collection's permissions or validation
+        // may have changed since the document was deleted.
+        let transaction = Transaction::insert(
+            collection.clone(),
+            Some(id.clone()),
+            document.contents.to_vec(),
+        );
+        self.check_transaction(&transaction)?;
+        let results = self.execute_transaction(&transaction)?;
+        match results.into_iter().next() {
+            Some(OperationResult::DocumentUpdated { header, .. }) => Ok(header),
+            _ => unreachable!("a single insert should yield a single DocumentUpdated entry"),
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "trace",
+        skip(self, collection),
+        fields(
+            database = self.name(),
+            collection.name = collection.name.as_ref(),
+            collection.authority = collection.authority.as_ref(),
+        )
+    ))]
+    fn revision_history_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Vec<HistoricalRevision>, bonsaidb_core::Error> {
+        self.check_permission(
+            document_resource_name(self.name(), collection, &id),
+            &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get)),
+        )?;
+        self.refresh_virtual_collection(collection)?;
+        let mut history = Vec::<HistoricalRevision>::new();
+        if self.storage.instance.revision_history() > 0 {
+            let tree = self
+                .data
+                .context
+                .roots
+                .tree(self.collection_tree::<Unversioned, _>(
+                    collection,
+                    document_revisions_tree_name(collection),
+                )?)
+                .map_err(Error::from)?;
+            let first = revision_history_key(&id, 0);
+            let last = revision_history_key(&id, u64::MAX);
+            for (_, revision) in tree
+                .get_range(&(first.as_slice()..=last.as_slice()))
+                .map_err(Error::from)?
+            {
+                history.push(pot::from_slice(&revision).map_err(Error::from)?);
+            }
+        }
+
+        let documents = self
+            .data
+            .context
+            .roots
+            .tree(self.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?)
+            .map_err(Error::from)?;
+        if let Some(vec) = documents.get(id.as_ref()).map_err(Error::from)? {
+            let current = deserialize_document(&vec)?.header.revision;
+            // Revisions created before history was enabled aren't recorded, so
+            // the current revision is appended when it is missing.
+            if history.last().map_or(true, |last| last.revision != current) {
+                history.push(HistoricalRevision {
+                    revision: current,
+                    transaction_id: None,
+                    timestamp: None,
+                });
+            }
+        } else if history.is_empty() {
+            return Err(bonsaidb_core::Error::DocumentNotFound(
+                collection.clone(),
+                Box::new(id),
+            ));
+        }
+
+        Ok(history)
+    }
+
     #[cfg_attr(feature = "tracing", tracing::instrument(
         level = "trace",
         skip(self, collection),
@@ -1367,6 +2623,7 @@ impl LowLevelConnection for Database {
             collection_resource_name(self.name(), collection),
             &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Count)),
         )?;
+        self.refresh_virtual_collection(collection)?;
         let tree = self
             .data
             .context
@@ -1399,7 +2656,8 @@ impl LowLevelConnection for Database {
                 &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Get)),
             )?;
         }
-        let mut ids = ids.to_vec();
+        self.refresh_virtual_collection(collection)?;
+        let mut sorted_ids = ids.to_vec();
         let collection = collection.clone();
         let tree = self
             .data
@@ -1409,16 +2667,32 @@ impl LowLevelConnection for Database {
                 self.collection_tree::<Versioned, _>(&collection, document_tree_name(&collection))?,
             )
             .map_err(Error::from)?;
-        ids.sort();
+        sorted_ids.sort();
+        sorted_ids.dedup();
         let keys_and_values = tree
-            .get_multiple(ids.iter().map(|id| id.as_ref()))
+            .get_multiple(sorted_ids.iter().map(|id| id.as_ref()))
             .map_err(Error::from)?;
 
-        keys_and_values
+        let mut documents = keys_and_values
             .into_iter()
-            .map(|(_, value)| deserialize_document(&value).map(BorrowedDocument::into_owned))
-            .collect::<Result<Vec<_>, Error>>()
-            .map_err(bonsaidb_core::Error::from)
+            .map(|(_, value)| {
+                deserialize_current_document(&value, &collection, &self.data.schema)
+                    .map(BorrowedDocument::into_owned)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.retain_unexpired(&collection, &mut documents, |doc| &doc.header.id)?;
+        let mut documents_by_id = documents
+            .into_iter()
+            .map(|document| (document.header.id.clone(), document))
+            .collect::<HashMap<_, _>>();
+
+        // Return the documents in the order they were requested. Removing each
+        // document as it is found ensures duplicate ids only return the
+        // document once.
+        Ok(ids
+            .iter()
+            .filter_map(|id| documents_by_id.remove(id))
+            .collect())
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(
@@ -1438,6 +2712,7 @@ impl LowLevelConnection for Database {
             collection_resource_name(self.name(), &collection),
             &BonsaiAction::Database(DatabaseAction::Compact),
         )?;
+        self.storage.instance.check_writable()?;
         self.storage()
             .instance
             .tasks()
@@ -1623,6 +2898,39 @@ impl LowLevelConnection for Database {
 
         Ok(results.len() as u64)
     }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(
+        level = "trace",
+        skip(self, view),
+        fields(
+            database = self.name(),
+            view.collection.name = view.collection.name.as_ref(),
+            view.collection.authority = view.collection.authority.as_ref(),
+            view.name = view.name.as_ref(),
+        )
+    ))]
+    fn explain_by_name(
+        &self,
+        view: &ViewName,
+        key: Option<SerializedQueryKey>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, bonsaidb_core::Error> {
+        let view = self.schematic().view_by_name(view)?;
+        self.check_permission(
+            view_resource_name(self.name(), &view.view_name()),
+            &BonsaiAction::Database(DatabaseAction::View(ViewAction::Query)),
+        )?;
+        let view_entries = self
+            .roots()
+            .tree(self.collection_tree(
+                &view.collection(),
+                view_entries_tree_name(&view.view_name()),
+            )?)
+            .map_err(Error::from)?;
+
+        Ok(Self::explain_view_query(&view_entries, key, order, limit)?)
+    }
 }
 
 impl HasSchema for Database {
@@ -1666,6 +2974,7 @@ impl Deref for Context {
 pub(crate) struct ContextData {
     pub(crate) roots: Roots<AnyFile>,
     key_value_state: Arc<Mutex<keyvalue::KeyValueState>>,
+    virtual_collections: VirtualCollections,
 }
 
 impl Borrow<Roots<AnyFile>> for Context {
@@ -1678,12 +2987,14 @@ impl Context {
     pub(crate) fn new(
         roots: Roots<AnyFile>,
         key_value_persistence: KeyValuePersistence,
+        key_value_max_value_size: Option<usize>,
         storage_lock: Option<StorageLock>,
     ) -> Self {
         let background_worker_target = Watchable::new(BackgroundWorkerProcessTarget::Never);
         let mut background_worker_target_watcher = background_worker_target.watch();
         let key_value_state = Arc::new(Mutex::new(keyvalue::KeyValueState::new(
             key_value_persistence,
+            key_value_max_value_size,
             roots.clone(),
             background_worker_target,
         )));
@@ -1692,6 +3003,7 @@ impl Context {
             data: Arc::new(ContextData {
                 roots,
                 key_value_state,
+                virtual_collections: VirtualCollections::default(),
             }),
         };
         std::thread::Builder::new()
@@ -1746,6 +3058,116 @@ pub fn document_tree_name(collection: &CollectionName) -> String {
     format!("collection.{collection:#}")
 }
 
+/// A change made to a document by an operation in a transaction.
+pub(crate) struct DocumentChange<'a> {
+    pub collection: &'a CollectionName,
+    pub operation: ChangeOperation,
+    pub header: &'a Header,
+    /// The contents written by the operation. This is `None` for deletes.
+    pub contents: Option<&'a [u8]>,
+}
+
+impl<'a> DocumentChange<'a> {
+    /// Returns the document changes made by `operations`, whose results are
+    /// `results`. `patched_contents` contains the resulting contents of each
+    /// [`Command::Patch`] in `operations`, in order.
+    fn from_operations(
+        operations: &'a [Cow<'_, Operation>],
+        results: &'a [OperationResult],
+        patched_contents: &'a [Vec<u8>],
+    ) -> impl Iterator<Item = Self> + 'a {
+        let mut patched_contents = patched_contents.iter();
+        operations
+            .iter()
+            .zip(results)
+            .filter_map(move |(op, result)| {
+                let (operation, header, contents) = match (&op.command, result) {
+                    (
+                        Command::Insert { contents, .. }
+                        | Command::Update { contents, .. }
+                        | Command::Overwrite { contents, .. },
+                        OperationResult::DocumentUpdated { header, .. },
+                    ) => {
+                        let operation = match &op.command {
+                            Command::Insert { .. } => ChangeOperation::Insert,
+                            Command::Overwrite { .. } if header.revision.id == 0 => {
+                                ChangeOperation::Insert
+                            }
+                            _ => ChangeOperation::Update,
+                        };
+                        (operation, header, Some(&contents[..]))
+                    }
+                    (Command::Patch { .. }, OperationResult::DocumentUpdated { header, .. }) => (
+                        ChangeOperation::Update,
+                        header,
+                        patched_contents.next().map(Vec::as_slice),
+                    ),
+                    (Command::Delete { header }, OperationResult::DocumentDeleted { .. }) => {
+                        (ChangeOperation::Delete, header, None)
+                    }
+                    _ => return None,
+                };
+                Some(Self {
+                    collection: &op.collection,
+                    operation,
+                    header,
+                    contents,
+                })
+            })
+    }
+
+    /// Returns this change as a [`Change`] made by the transaction
+    /// `transaction_id`.
+    pub fn to_change(&self, transaction_id: u64) -> Change {
+        Change {
+            sequence: transaction_id,
+            operation: self.operation,
+            id: self.header.id.clone(),
+            header: Some(self.header.clone()),
+            contents: self.contents.map(|contents| Bytes::from(contents.to_vec())),
+        }
+    }
+}
+
+/// Used to store tombstones of documents deleted from collections that use
+/// soft deletes.
+pub fn deleted_document_tree_name(collection: &CollectionName) -> String {
+    format!("collection.{collection:#}.deleted")
+}
+
+/// Used to store when each document was last updated in collections that
+/// expire documents.
+pub fn document_expirations_tree_name(collection: &CollectionName) -> String {
+    format!("collection.{collection:#}.expirations")
+}
+
+/// Used to store the changes made to documents in collections with triggers
+/// until every trigger has processed them.
+pub fn document_changes_tree_name(collection: &CollectionName) -> String {
+    format!("collection.{collection:#}.changes")
+}
+
+/// Used to store the revision history of documents when
+/// [`StorageConfiguration::revision_history`] is non-zero.
+pub fn document_revisions_tree_name(collection: &CollectionName) -> String {
+    format!("collection.{collection:#}.revisions")
+}
+
+/// Returns the key in the revisions tree for the revision of the document
+/// `id` created by `transaction_id`. Each key is the big endian length of the
+/// id, the id, and the big endian transaction id, which keeps a document's
+/// revisions together and ordered from oldest to newest.
+fn revision_history_key(id: &DocumentId, transaction_id: u64) -> Vec<u8> {
+    let id: &[u8] = id;
+    // Document ids are at most `DocumentId::MAX_LENGTH` bytes long.
+    let id_length = u16::try_from(id.len()).expect("document ids fit in a u16");
+    let mut key = Vec::with_capacity(id.len() + 10);
+    key.extend_from_slice(&id_length.to_be_bytes());
+    key.extend_from_slice(id);
+    key.extend_from_slice(&transaction_id.to_be_bytes());
+    key
+}
+
 pub struct DocumentIdRange(Range<DocumentId>);
 
 impl<'a> BorrowByteRange<'a> for DocumentIdRange {
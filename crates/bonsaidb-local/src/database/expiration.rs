@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+use bonsaidb_core::arc_bytes::ArcBytes;
+use bonsaidb_core::document::{DocumentId, Header};
+use bonsaidb_core::keyvalue::Timestamp;
+use bonsaidb_core::schema::CollectionName;
+use bonsaidb_core::transaction::{Operation, Transaction};
+use nebari::io::any::AnyFile;
+use nebari::tree::{ScanEvaluation, Unversioned, Versioned};
+use nebari::{AbortError, ExecutingTransaction};
+
+use super::{deserialize_document, document_expirations_tree_name, document_tree_name};
+use crate::tasks::{Job, JobContext, Keyed, Task};
+use crate::{Database, Error};
+
+/// Deletes the expired documents from every collection in `database` that
+/// expires documents.
+#[derive(Debug)]
+pub struct DocumentReaper {
+    pub database: Database,
+}
+
+impl Keyed<Task> for DocumentReaper {
+    fn key(&self) -> Task {
+        Task::DocumentReaper(self.database.data.name.clone())
+    }
+}
+
+impl Job for DocumentReaper {
+    type Error = Error;
+    type Output = ();
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn execute(&mut self, _context: &JobContext) -> Result<Self::Output, Self::Error> {
+        // Replicas only delete documents when the deletions are replicated.
+        if self.database.check_writable().is_err() {
+            return Ok(());
+        }
+
+        for (collection, expire_after) in self.database.data.schema.expiring_collections() {
+            self.database
+                .reap_expired_documents(collection, expire_after)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn is_expired(updated_at: &[u8], expire_after: Duration, now: Timestamp) -> Result<bool, Error> {
+    let updated_at = pot::from_slice::<Timestamp>(updated_at)?;
+    Ok((now - updated_at).map_or(false, |age| age >= expire_after))
+}
+
+impl Database {
+    /// Records that the document `document_id` was updated, if the
+    /// operation's collection expires documents.
+    pub(super) fn record_document_update(
+        document_id: &ArcBytes<'static>,
+        operation: &Operation,
+        transaction: &mut ExecutingTransaction<AnyFile>,
+        tree_index_map: &HashMap<String, usize>,
+    ) -> Result<(), Error> {
+        if let Some(expirations_index) =
+            tree_index_map.get(&document_expirations_tree_name(&operation.collection))
+        {
+            transaction
+                .tree::<Unversioned>(*expirations_index)
+                .unwrap()
+                .set(document_id.clone(), pot::to_vec(&Timestamp::now())?)?;
+        }
+        Ok(())
+    }
+
+    /// Forgets when the document `document_id` was last updated, if the
+    /// operation's collection expires documents.
+    pub(super) fn remove_document_expiration(
+        document_id: &ArcBytes<'static>,
+        operation: &Operation,
+        transaction: &mut ExecutingTransaction<AnyFile>,
+        tree_index_map: &HashMap<String, usize>,
+    ) -> Result<(), Error> {
+        if let Some(expirations_index) =
+            tree_index_map.get(&document_expirations_tree_name(&operation.collection))
+        {
+            transaction
+                .tree::<Unversioned>(*expirations_index)
+                .unwrap()
+                .remove(document_id.as_slice())?;
+        }
+        Ok(())
+    }
+
+    /// Returns the ids of the documents in `collection` that have expired.
+    /// If `ids` is provided, only those documents are checked.
+    fn expired_documents(
+        &self,
+        collection: &CollectionName,
+        expire_after: Duration,
+        ids: Option<Vec<&[u8]>>,
+    ) -> Result<HashSet<DocumentId>, Error> {
+        let tree = self
+            .data
+            .context
+            .roots
+            .tree(self.collection_tree::<Unversioned, _>(
+                collection,
+                document_expirations_tree_name(collection),
+            )?)?;
+        let now = Timestamp::now();
+        let mut expired = HashSet::new();
+        if let Some(mut ids) = ids {
+            ids.sort_unstable();
+            ids.dedup();
+            for (id, updated_at) in tree.get_multiple(ids.into_iter())? {
+                if is_expired(&updated_at, expire_after, now)? {
+                    expired.insert(DocumentId::try_from(id.as_slice())?);
+                }
+            }
+        } else {
+            tree.scan(
+                &(..),
+                true,
+                |_, _, _| ScanEvaluation::ReadData,
+                |_, _| ScanEvaluation::ReadData,
+                |id, _, updated_at| {
+                    if is_expired(&updated_at, expire_after, now).map_err(AbortError::Other)? {
+                        expired.insert(
+                            DocumentId::try_from(id.as_slice())
+                                .map_err(|err| AbortError::Other(Error::from(err)))?,
+                        );
+                    }
+                    Ok(())
+                },
+            )
+            .map_err(|err| match err {
+                AbortError::Other(err) => err,
+                AbortError::Nebari(err) => crate::Error::from(err),
+            })?;
+        }
+        Ok(expired)
+    }
+
+    /// Removes the entries of `documents` whose documents have expired but
+    /// haven't been deleted yet.
+    pub(super) fn retain_unexpired<T>(
+        &self,
+        collection: &CollectionName,
+        documents: &mut Vec<T>,
+        id: impl Fn(&T) -> &DocumentId,
+    ) -> Result<(), Error> {
+        let Some(expire_after) = self.data.schema.collection_expiration(collection) else {
+            return Ok(());
+        };
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let expired = self.expired_documents(
+            collection,
+            expire_after,
+            Some(documents.iter().map(|document| &id(document)[..]).collect()),
+        )?;
+        documents.retain(|document| !expired.contains(id(document)));
+        Ok(())
+    }
+
+    /// Deletes the documents in `collection` that were last updated at least
+    /// `expire_after` ago.
+    fn reap_expired_documents(
+        &self,
+        collection: &CollectionName,
+        expire_after: Duration,
+    ) -> Result<(), Error> {
+        let candidates = self.expired_documents(collection, expire_after, None)?;
+        if candidates.is_empty() {
+            return Ok(());
+        }
+
+        let documents = self.data.context.roots.tree(
+            self.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?,
+        )?;
+        let mut ids = candidates.iter().map(|id| &id[..]).collect::<Vec<_>>();
+        ids.sort_unstable();
+        let headers = documents
+            .get_multiple(ids.iter().copied())?
+            .into_iter()
+            .map(|(_, document)| deserialize_document(&document).map(|document| document.header))
+            .collect::<Result<Vec<Header>, Error>>()?;
+        // A document may have been updated after it was found to be expired.
+        // Updates write the document and its update time in the same
+        // transaction, so checking again after reading the headers ensures
+        // only the revisions that expired are deleted.
+        let expired = self.expired_documents(collection, expire_after, Some(ids))?;
+        let headers = headers
+            .into_iter()
+            .filter(|header| expired.contains(&header.id))
+            .collect::<Vec<_>>();
+        if headers.is_empty() {
+            return Ok(());
+        }
+
+        let mut transaction = Transaction::new();
+        for header in &headers {
+            transaction.push(Operation::delete(collection.clone(), header.clone()));
+        }
+        match self.execute_transaction(&transaction) {
+            Ok(_) => Ok(()),
+            // A document was updated or deleted after its header was read.
+            // Delete the remaining documents individually so that the
+            // conflict doesn't prevent them from being deleted.
+            Err(
+                bonsaidb_core::Error::DocumentConflict(..)
+                | bonsaidb_core::Error::DocumentNotFound(..),
+            ) => {
+                for header in headers {
+                    match self.execute_transaction(&Transaction::from(Operation::delete(
+                        collection.clone(),
+                        header,
+                    ))) {
+                        Ok(_)
+                        | Err(
+                            bonsaidb_core::Error::DocumentConflict(..)
+                            | bonsaidb_core::Error::DocumentNotFound(..),
+                        ) => {}
+                        Err(err) => return Err(Error::from(err)),
+                    }
+                }
+                Ok(())
+            }
+            Err(err) => Err(Error::from(err)),
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::collections::{btree_map, BTreeMap, VecDeque};
 use std::sync::{Arc, Weak};
 use std::time::Duration;
 
+use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::connection::{Connection, HasSession};
 use bonsaidb_core::keyvalue::{
     Command, KeyCheck, KeyOperation, KeyStatus, KeyValue, Numeric, Output, SetCommand, Timestamp,
@@ -22,7 +23,7 @@ use watchable::{Watchable, Watcher};
 use crate::config::KeyValuePersistence;
 use crate::database::compat;
 use crate::storage::StorageLock;
-use crate::tasks::{Job, Keyed, Task};
+use crate::tasks::{Job, JobContext, Keyed, Task};
 use crate::{Database, DatabaseNonBlocking, Error};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -34,6 +35,11 @@ pub struct Entry {
 }
 
 impl Entry {
+    pub(crate) fn has_expired(&self, now: Timestamp) -> bool {
+        self.expiration
+            .map_or(false, |expiration| expiration <= now)
+    }
+
     pub(crate) fn restore(
         self,
         namespace: Option<String>,
@@ -61,6 +67,12 @@ impl KeyValue for Database {
             keyvalue_key_resource_name(self.name(), op.namespace.as_deref(), &op.key),
             &BonsaiAction::Database(DatabaseAction::KeyValue(KeyValueAction::ExecuteOperation)),
         )?;
+        if !matches!(
+            op.command,
+            Command::Get { delete: false } | Command::GetRange { .. } | Command::ListKeys
+        ) {
+            self.check_writable()?;
+        }
         self.data.context.perform_kv_operation(op)
     }
 }
@@ -208,6 +220,7 @@ fn decrement(existing: &Numeric, amount: &Numeric, saturating: bool) -> Numeric
 pub struct KeyValueState {
     roots: Roots<AnyFile>,
     persistence: KeyValuePersistence,
+    max_value_size: Option<usize>,
     last_commit: Timestamp,
     background_worker_target: Watchable<BackgroundWorkerProcessTarget>,
     expiring_keys: BTreeMap<String, Timestamp>,
@@ -221,12 +234,14 @@ pub struct KeyValueState {
 impl KeyValueState {
     pub fn new(
         persistence: KeyValuePersistence,
+        max_value_size: Option<usize>,
         roots: Roots<AnyFile>,
         background_worker_target: Watchable<BackgroundWorkerProcessTarget>,
     ) -> Self {
         Self {
             roots,
             persistence,
+            max_value_size,
             last_commit: Timestamp::now(),
             expiring_keys: BTreeMap::new(),
             background_worker_target,
@@ -263,7 +278,16 @@ impl KeyValueState {
             Command::Get { delete } => {
                 self.execute_get_operation(op.namespace.as_deref(), &op.key, delete)
             }
+            Command::GetRange { start, length } => {
+                self.execute_get_range_operation(op.namespace.as_deref(), &op.key, start, length)
+            }
+            Command::Append { value } => {
+                self.execute_append_operation(op.namespace.as_deref(), &op.key, &value, now)
+            }
             Command::Delete => self.execute_delete_operation(op.namespace.as_deref(), &op.key),
+            Command::GetExpiration => {
+                self.execute_get_expiration_operation(op.namespace.as_deref(), &op.key)
+            }
             Command::Increment { amount, saturating } => self.execute_increment_operation(
                 op.namespace.as_deref(),
                 &op.key,
@@ -278,6 +302,17 @@ impl KeyValueState {
                 saturating,
                 now,
             ),
+            Command::CompareAndSwap { expected, new } => self.execute_compare_and_swap_operation(
+                op.namespace.as_deref(),
+                &op.key,
+                expected.as_ref(),
+                new,
+                now,
+            ),
+            Command::ListKeys => self.execute_list_keys_operation(op.namespace.as_deref()),
+            Command::DeleteNamespace => {
+                self.execute_delete_namespace_operation(op.namespace.as_deref())
+            }
         };
         if result.is_ok() {
             if self.needs_commit(now) {
@@ -299,8 +334,10 @@ impl KeyValueState {
         set: SetCommand,
         now: Timestamp,
     ) -> Result<Output, bonsaidb_core::Error> {
+        let value = set.value.validate()?;
+        self.check_value_size(&value)?;
         let mut entry = Entry {
-            value: set.value.validate()?,
+            value,
             expiration: set.expiration,
             last_updated: now,
         };
@@ -436,6 +473,117 @@ impl KeyValueState {
         Ok(Output::Value(entry.map(|e| e.value)))
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    fn execute_get_range_operation(
+        &mut self,
+        namespace: Option<&str>,
+        key: &str,
+        start: u64,
+        length: u64,
+    ) -> Result<Output, bonsaidb_core::Error> {
+        let full_key = full_key(namespace, key);
+        match self.get(&full_key).map_err(Error::from)? {
+            Some(Entry {
+                value: Value::Bytes(bytes),
+                ..
+            }) => {
+                let start = usize::try_from(start)
+                    .unwrap_or(usize::MAX)
+                    .min(bytes.len());
+                let end = start
+                    .saturating_add(usize::try_from(length).unwrap_or(usize::MAX))
+                    .min(bytes.len());
+                Ok(Output::Value(Some(Value::Bytes(Bytes::from(
+                    &bytes[start..end],
+                )))))
+            }
+            Some(Entry {
+                value: Value::Numeric(_),
+                ..
+            }) => Err(bonsaidb_core::Error::other(
+                "bonsaidb-local",
+                "type of stored `Value` is not `Bytes`",
+            )),
+            None => Ok(Output::Value(None)),
+        }
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, value, now))
+    )]
+    fn execute_append_operation(
+        &mut self,
+        namespace: Option<&str>,
+        key: &str,
+        value: &[u8],
+        now: Timestamp,
+    ) -> Result<Output, bonsaidb_core::Error> {
+        let full_key = full_key(namespace, key);
+        let (status, existing, expiration) = match self.get(&full_key).map_err(Error::from)? {
+            Some(Entry {
+                value: Value::Bytes(existing),
+                expiration,
+                ..
+            }) => (KeyStatus::Updated, existing, expiration),
+            Some(Entry {
+                value: Value::Numeric(_),
+                ..
+            }) => {
+                return Err(bonsaidb_core::Error::other(
+                    "bonsaidb-local",
+                    "type of stored `Value` is not `Bytes`",
+                ))
+            }
+            None => (KeyStatus::Inserted, Bytes::default(), None),
+        };
+        let size = existing.len() + value.len();
+        self.check_size(size)?;
+
+        let mut appended = Vec::with_capacity(size);
+        appended.extend_from_slice(&existing);
+        appended.extend_from_slice(value);
+        self.set(
+            full_key,
+            Entry {
+                value: Value::Bytes(Bytes::from(appended)),
+                expiration,
+                last_updated: now,
+            },
+        );
+        Ok(Output::Status(status))
+    }
+
+    /// Returns an error if `value` is larger than the maximum value size.
+    fn check_value_size(&self, value: &Value) -> Result<(), bonsaidb_core::Error> {
+        match value {
+            Value::Bytes(bytes) => self.check_size(bytes.len()),
+            Value::Numeric(_) => Ok(()),
+        }
+    }
+
+    fn check_size(&self, size: usize) -> Result<(), bonsaidb_core::Error> {
+        match self.max_value_size {
+            Some(maximum) if size > maximum => Err(bonsaidb_core::Error::ValueTooLarge {
+                size: size as u64,
+                maximum: maximum as u64,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    fn execute_get_expiration_operation(
+        &mut self,
+        namespace: Option<&str>,
+        key: &str,
+    ) -> Result<Output, bonsaidb_core::Error> {
+        let full_key = full_key(namespace, key);
+        let entry = self.get(&full_key).map_err(Error::from)?;
+
+        Ok(Output::Expiration(entry.and_then(|e| e.expiration)))
+    }
+
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
     fn execute_delete_operation(
         &mut self,
@@ -451,6 +599,86 @@ impl KeyValueState {
         }
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    fn execute_list_keys_operation(
+        &mut self,
+        namespace: Option<&str>,
+    ) -> Result<Output, bonsaidb_core::Error> {
+        let prefix_length = full_key(namespace, "").len();
+        let keys = self
+            .namespace_keys(namespace)?
+            .into_iter()
+            .map(|full_key| full_key[prefix_length..].to_string())
+            .collect();
+        Ok(Output::Keys(keys))
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    fn execute_delete_namespace_operation(
+        &mut self,
+        namespace: Option<&str>,
+    ) -> Result<Output, bonsaidb_core::Error> {
+        let keys = self.namespace_keys(namespace)?;
+        let deleted = keys.len() as u64;
+        for key in keys {
+            self.update_key_expiration(&key, None);
+            self.dirty_keys.insert(key, None);
+        }
+        Ok(Output::Count(deleted))
+    }
+
+    /// Returns the full keys of all unexpired entries stored directly within
+    /// `namespace`, in ascending order.
+    fn namespace_keys(&self, namespace: Option<&str>) -> Result<Vec<String>, Error> {
+        let prefix = full_key(namespace, "");
+        let mut entries = BTreeMap::new();
+        self.roots
+            .tree(Unversioned::tree(KEY_TREE))?
+            .scan::<Error, _, _, _, _>(
+                &(prefix.as_bytes()..),
+                true,
+                |_, _, _| ScanEvaluation::ReadData,
+                |key, _| {
+                    if key.starts_with(prefix.as_bytes()) {
+                        ScanEvaluation::ReadData
+                    } else {
+                        ScanEvaluation::Stop
+                    }
+                },
+                |key, _, entry: ArcBytes<'static>| {
+                    let entry = bincode::deserialize::<Entry>(&entry)
+                        .map_err(|err| AbortError::Other(Error::from(err)))?;
+                    let full_key = std::str::from_utf8(&key)
+                        .map_err(|err| AbortError::Other(Error::from(err)))?;
+                    entries.insert(full_key.to_string(), Some(entry));
+                    Ok(())
+                },
+            )?;
+
+        // Pending writes take precedence over what is stored on disk.
+        let pending_keys = self
+            .keys_being_persisted
+            .iter()
+            .flat_map(|keys| keys.iter())
+            .chain(self.dirty_keys.iter());
+        for (key, entry) in pending_keys {
+            if key.starts_with(&prefix) {
+                entries.insert(key.clone(), entry.clone());
+            }
+        }
+
+        let now = Timestamp::now();
+        Ok(entries
+            .into_iter()
+            .filter_map(|(key, entry)| {
+                let entry = entry?;
+                // Keys containing another separator belong to a nested
+                // namespace.
+                (!entry.has_expired(now) && !key[prefix.len()..].contains('\0')).then_some(key)
+            })
+            .collect())
+    }
+
     #[cfg_attr(
         feature = "tracing",
         tracing::instrument(level = "trace", skip(self, amount, saturating, now))
@@ -481,6 +709,41 @@ impl KeyValueState {
         self.execute_numeric_operation(namespace, key, amount, saturating, now, decrement)
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "trace", skip(self, expected, new, now))
+    )]
+    fn execute_compare_and_swap_operation(
+        &mut self,
+        namespace: Option<&str>,
+        key: &str,
+        expected: Option<&Value>,
+        new: Value,
+        now: Timestamp,
+    ) -> Result<Output, bonsaidb_core::Error> {
+        self.check_value_size(&new)?;
+        let full_key = full_key(namespace, key);
+        let current = self.get(&full_key).map_err(Error::from)?;
+        if current.as_ref().map(|entry| &entry.value) != expected {
+            return Ok(Output::Status(KeyStatus::NotChanged));
+        }
+
+        let status = if current.is_some() {
+            KeyStatus::Updated
+        } else {
+            KeyStatus::Inserted
+        };
+        self.set(
+            full_key,
+            Entry {
+                value: new.validate()?,
+                expiration: current.and_then(|entry| entry.expiration),
+                last_updated: now,
+            },
+        );
+        Ok(Output::Status(status))
+    }
+
     fn execute_numeric_operation<F: Fn(&Numeric, &Numeric, bool) -> Numeric>(
         &mut self,
         namespace: Option<&str>,
@@ -536,17 +799,21 @@ impl KeyValueState {
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
     fn get(&self, key: &str) -> Result<Option<Entry>, nebari::Error> {
-        if let Some(entry) = self.dirty_keys.get(key) {
-            Ok(entry.clone())
+        let entry = if let Some(entry) = self.dirty_keys.get(key) {
+            entry.clone()
         } else if let Some(persisting_entry) = self
             .keys_being_persisted
             .as_ref()
             .and_then(|keys| keys.get(key))
         {
-            Ok(persisting_entry.clone())
+            persisting_entry.clone()
         } else {
-            Self::retrieve_key_from_disk(&self.roots, key)
-        }
+            Self::retrieve_key_from_disk(&self.roots, key)?
+        };
+
+        // Keys loaded from disk may have expired before the expiration loader
+        // has had a chance to register them for removal.
+        Ok(entry.filter(|entry| !entry.has_expired(Timestamp::now())))
     }
 
     fn set(&mut self, key: String, value: Entry) {
@@ -848,7 +1115,7 @@ impl Job for ExpirationLoader {
     type Output = ();
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
-    fn execute(&mut self) -> Result<Self::Output, Self::Error> {
+    fn execute(&mut self, _context: &JobContext) -> Result<Self::Output, Self::Error> {
         let database = self.database.clone();
         let launched_at = self.launched_at;
 
@@ -893,7 +1160,7 @@ mod tests {
             .file_manager(AnyFileManager::std())
             .open()?;
 
-        let context = Context::new(sled.clone(), persistence, None);
+        let context = Context::new(sled.clone(), persistence, None, None);
 
         test_contents(context, sled)?;
 
@@ -1178,6 +1445,7 @@ mod tests {
             sled,
             KeyValuePersistence::lazy([PersistenceThreshold::after_changes(2)]),
             None,
+            None,
         );
         context
             .perform_kv_operation(KeyOperation {
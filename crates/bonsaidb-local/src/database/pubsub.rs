@@ -1,59 +1,141 @@
-use bonsaidb_core::arc_bytes::OwnedBytes;
+use bonsaidb_core::changes;
 pub use bonsaidb_core::circulate::Relay;
 use bonsaidb_core::connection::{Connection, HasSession};
 use bonsaidb_core::permissions::bonsai::{
     database_resource_name, pubsub_topic_resource_name, BonsaiAction, DatabaseAction, PubSubAction,
 };
-use bonsaidb_core::pubsub::{self, database_topic, PubSub, Receiver};
-use bonsaidb_core::{circulate, Error};
+use bonsaidb_core::pubsub::{self, BufferLimit, PubSub, PublishReceipt, Receiver};
+use bonsaidb_core::Error;
+#[cfg(feature = "internal-apis")]
+use serde::Serialize;
 
+use crate::storage::BackpressuredMessages;
 use crate::{Database, DatabaseNonBlocking};
 
-impl PubSub for super::Database {
-    type Subscriber = Subscriber;
-
-    fn create_subscriber(&self) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+impl super::Database {
+    fn register_subscriber(&self, limit: Option<BufferLimit>) -> Result<Subscriber, Error> {
         self.check_permission(
             database_resource_name(self.name()),
             &BonsaiAction::Database(DatabaseAction::PubSub(PubSubAction::CreateSuscriber)),
         )?;
+        Ok(self.storage().instance.register_subscriber(
+            self.session().and_then(|session| session.id),
+            self.clone(),
+            limit,
+        ))
+    }
+
+    /// Publishes `payload` to all `topics`, returning the messages that must
+    /// be waited on because a subscriber applies backpressure.
+    pub(crate) fn begin_publish(
+        &self,
+        topics: impl IntoIterator<Item = Vec<u8>>,
+        payload: Vec<u8>,
+    ) -> Result<BackpressuredMessages, Error> {
+        self.storage.instance.check_payload_size(&payload)?;
+        let topics = topics
+            .into_iter()
+            .map(|topic| {
+                check_topic_not_reserved(&topic)?;
+                self.check_permission(
+                    pubsub_topic_resource_name(self.name(), &topic),
+                    &BonsaiAction::Database(DatabaseAction::PubSub(PubSubAction::Publish)),
+                )
+                .map(|_| topic)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
         Ok(self
-            .storage()
+            .storage
             .instance
-            .register_subscriber(self.session().and_then(|session| session.id), self.clone()))
+            .publish_to_all(&self.data.name, topics, payload))
     }
 
-    fn publish_bytes(&self, topic: Vec<u8>, payload: Vec<u8>) -> Result<(), bonsaidb_core::Error> {
-        self.check_permission(
-            pubsub_topic_resource_name(self.name(), &topic),
-            &BonsaiAction::Database(DatabaseAction::PubSub(PubSubAction::Publish)),
-        )?;
-        self.storage
+    /// Publishes `payload` to `topic` on behalf of BonsaiDb. No permission
+    /// checks are performed, reserved topics are allowed, and the message is
+    /// never retained.
+    ///
+    /// Publishing never waits: subscribers whose buffers are full and apply
+    /// backpressure miss the message, which is counted as lagged.
+    #[cfg(feature = "internal-apis")]
+    #[doc(hidden)]
+    pub fn publish_internal<Topic: Serialize, Payload: Serialize>(
+        &self,
+        topic: &Topic,
+        payload: &Payload,
+    ) -> Result<(), Error> {
+        self.storage.instance.publish_internal(
+            &self.data.name,
+            &pot::to_vec(topic)?,
+            |_| true,
+            || Ok(pot::to_vec(payload)?),
+        )
+    }
+
+    /// Publishes `messages` as a single batch, returning the messages that
+    /// must be waited on because a subscriber applies backpressure.
+    pub(crate) fn begin_publish_batch(
+        &self,
+        messages: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<BackpressuredMessages, Error> {
+        for (topic, payload) in &messages {
+            self.storage.instance.check_payload_size(payload)?;
+            check_topic_not_reserved(topic)?;
+            self.check_permission(
+                pubsub_topic_resource_name(self.name(), topic),
+                &BonsaiAction::Database(DatabaseAction::PubSub(PubSubAction::Publish)),
+            )?;
+        }
+        Ok(self
+            .storage
             .instance
-            .relay()
-            .publish_raw(database_topic(&self.data.name, &topic), payload);
+            .publish_batch(&self.data.name, messages))
+    }
+}
+
+/// Returns an error if `topic` is reserved for messages published by BonsaiDb.
+fn check_topic_not_reserved(topic: &[u8]) -> Result<(), Error> {
+    if changes::is_change_feed_topic(topic) {
+        Err(Error::ReservedTopic)
+    } else {
         Ok(())
     }
+}
+
+impl PubSub for super::Database {
+    type Subscriber = Subscriber;
+
+    fn create_subscriber(&self) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+        self.register_subscriber(None)
+    }
+
+    fn create_subscriber_with_limit(
+        &self,
+        limit: BufferLimit,
+    ) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+        self.register_subscriber(Some(limit))
+    }
+
+    fn publish_bytes(
+        &self,
+        topic: Vec<u8>,
+        payload: Vec<u8>,
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        Ok(self.begin_publish([topic], payload)?.wait())
+    }
 
     fn publish_bytes_to_all(
         &self,
         topics: impl IntoIterator<Item = Vec<u8>> + Send,
         payload: Vec<u8>,
-    ) -> Result<(), bonsaidb_core::Error> {
-        self.storage.instance.relay().publish_raw_to_all(
-            topics
-                .into_iter()
-                .map(|topic| {
-                    self.check_permission(
-                        pubsub_topic_resource_name(self.name(), &topic),
-                        &BonsaiAction::Database(DatabaseAction::PubSub(PubSubAction::Publish)),
-                    )
-                    .map(|_| OwnedBytes::from(database_topic(&self.data.name, &topic)))
-                })
-                .collect::<Result<Vec<_>, _>>()?,
-            payload,
-        );
-        Ok(())
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        Ok(self.begin_publish(topics, payload)?.wait())
+    }
+
+    fn publish_batch_bytes(
+        &self,
+        messages: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        Ok(self.begin_publish_batch(messages)?.wait())
     }
 }
 
@@ -62,7 +144,6 @@ impl PubSub for super::Database {
 pub struct Subscriber {
     pub(crate) id: u64,
     pub(crate) database: Database,
-    pub(crate) subscriber: circulate::Subscriber,
     pub(crate) receiver: Receiver,
 }
 
@@ -86,8 +167,19 @@ impl pubsub::Subscriber for Subscriber {
             pubsub_topic_resource_name(self.database.name(), &topic),
             &BonsaiAction::Database(DatabaseAction::PubSub(PubSubAction::SubscribeTo)),
         )?;
-        self.subscriber
-            .subscribe_to_raw(database_topic(self.database.name(), &topic));
+        self.database.storage().instance.subscribe(self, &topic);
+        Ok(())
+    }
+
+    fn subscribe_to_bytes_with_replay(&self, topic: Vec<u8>) -> Result<(), Error> {
+        self.database.check_permission(
+            pubsub_topic_resource_name(self.database.name(), &topic),
+            &BonsaiAction::Database(DatabaseAction::PubSub(PubSubAction::SubscribeTo)),
+        )?;
+        self.database
+            .storage()
+            .instance
+            .subscribe_with_replay(self, &topic);
         Ok(())
     }
 
@@ -96,8 +188,31 @@ impl pubsub::Subscriber for Subscriber {
             pubsub_topic_resource_name(self.database.name(), topic),
             &BonsaiAction::Database(DatabaseAction::PubSub(PubSubAction::UnsubscribeFrom)),
         )?;
-        self.subscriber
-            .unsubscribe_from_raw(&database_topic(self.database.name(), topic));
+        self.database.storage().instance.unsubscribe(self, topic);
+        Ok(())
+    }
+
+    fn subscribe_to_prefix_bytes(&self, prefix: Vec<u8>) -> Result<(), Error> {
+        self.database.check_permission(
+            pubsub_topic_resource_name(self.database.name(), &prefix),
+            &BonsaiAction::Database(DatabaseAction::PubSub(PubSubAction::SubscribeTo)),
+        )?;
+        self.database
+            .storage()
+            .instance
+            .subscribe_to_prefix(self, &prefix);
+        Ok(())
+    }
+
+    fn unsubscribe_from_prefix_bytes(&self, prefix: &[u8]) -> Result<(), Error> {
+        self.database.check_permission(
+            pubsub_topic_resource_name(self.database.name(), prefix),
+            &BonsaiAction::Database(DatabaseAction::PubSub(PubSubAction::UnsubscribeFrom)),
+        )?;
+        self.database
+            .storage()
+            .instance
+            .unsubscribe_from_prefix(self, prefix);
         Ok(())
     }
 
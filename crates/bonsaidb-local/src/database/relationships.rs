@@ -0,0 +1,154 @@
+use std::collections::{HashMap, HashSet};
+
+use bonsaidb_core::document::DocumentId;
+use bonsaidb_core::permissions::bonsai::{
+    document_resource_name, BonsaiAction, DatabaseAction, DocumentAction,
+};
+use bonsaidb_core::schema::relationship::DeleteAction;
+use bonsaidb_core::schema::CollectionName;
+use bonsaidb_core::transaction::{Command, Operation, Transaction};
+use nebari::io::any::AnyFile;
+use nebari::tree::{Unversioned, Versioned};
+use nebari::ExecutingTransaction;
+
+use super::{deserialize_current_document, document_tree_name};
+use crate::views::{view_entries_tree_name, ViewEntry};
+use crate::{Database, DatabaseNonBlocking, Error};
+
+/// Returns the collections that `transaction` deletes documents from,
+/// including the collection being cleared, if any.
+pub(super) fn deleted_collections<'a>(
+    transaction: &'a Transaction,
+    clear: Option<&'a CollectionName>,
+) -> impl Iterator<Item = &'a CollectionName> + 'a {
+    transaction
+        .operations
+        .iter()
+        .filter(|op| matches!(op.command, Command::Delete { .. }))
+        .map(|op| &op.collection)
+        .chain(clear)
+}
+
+impl Database {
+    /// Returns the collections that relationships may read or change when
+    /// documents in `deleted` are deleted, excluding the collections in
+    /// `deleted` themselves.
+    pub(super) fn related_collections<'a>(
+        &self,
+        deleted: impl IntoIterator<Item = &'a CollectionName>,
+    ) -> HashSet<CollectionName> {
+        let mut pending = deleted.into_iter().cloned().collect::<Vec<_>>();
+        let mut visited = pending.iter().cloned().collect::<HashSet<_>>();
+        let mut related = HashSet::new();
+        while let Some(collection) = pending.pop() {
+            for relationship in self.data.schema.relationships_referencing(&collection) {
+                let child = relationship.collection();
+                related.insert(child.clone());
+                // Only cascading deletes can cause further relationships to
+                // be applied.
+                if relationship.on_delete() == DeleteAction::Cascade
+                    && visited.insert(child.clone())
+                {
+                    pending.push(child);
+                }
+            }
+        }
+        related
+    }
+
+    /// Returns the operations that apply the relationships referencing
+    /// `collection` after the document `id` has been deleted.
+    ///
+    /// Each relationship's view is eager, so its entries reflect every change
+    /// made earlier in `transaction`.
+    pub(super) fn related_operations(
+        &self,
+        collection: &CollectionName,
+        id: &DocumentId,
+        transaction: &mut ExecutingTransaction<AnyFile>,
+        tree_index_map: &HashMap<String, usize>,
+    ) -> Result<Vec<Operation>, Error> {
+        let mut operations = Vec::new();
+        for relationship in self.data.schema.relationships_referencing(collection) {
+            let view_name = relationship.view_name();
+            let entry = transaction
+                .tree::<Unversioned>(tree_index_map[&view_entries_tree_name(&view_name)])
+                .unwrap()
+                .get(id.as_ref())?;
+            let Some(entry) = entry else {
+                continue;
+            };
+            let entry = bincode::deserialize::<ViewEntry>(&entry)?;
+            if entry.mappings.is_empty() {
+                continue;
+            }
+
+            let child_collection = relationship.collection();
+            match relationship.on_delete() {
+                DeleteAction::Restrict => {
+                    return Err(Error::Core(bonsaidb_core::Error::DeleteRestricted {
+                        collection: collection.clone(),
+                        id: Box::new(id.clone()),
+                        relationship: view_name,
+                        referencing_documents: u64::try_from(entry.mappings.len())
+                            .unwrap_or(u64::MAX),
+                    }));
+                }
+                DeleteAction::Cascade => {
+                    for mapping in entry.mappings {
+                        self.check_permission(
+                            document_resource_name(
+                                self.name(),
+                                &child_collection,
+                                &mapping.source.id,
+                            ),
+                            &BonsaiAction::Database(DatabaseAction::Document(
+                                DocumentAction::Delete,
+                            )),
+                        )?;
+                        operations
+                            .push(Operation::delete(child_collection.clone(), mapping.source));
+                    }
+                }
+                DeleteAction::SetNull => {
+                    for mapping in entry.mappings {
+                        self.check_permission(
+                            document_resource_name(
+                                self.name(),
+                                &child_collection,
+                                &mapping.source.id,
+                            ),
+                            &BonsaiAction::Database(DatabaseAction::Document(
+                                DocumentAction::Update,
+                            )),
+                        )?;
+                        let stored = transaction
+                            .tree::<Versioned>(
+                                tree_index_map[&document_tree_name(&child_collection)],
+                            )
+                            .unwrap()
+                            .get(mapping.source.id.as_ref())?;
+                        let Some(stored) = stored else {
+                            continue;
+                        };
+                        let document = deserialize_current_document(
+                            &stored,
+                            &child_collection,
+                            &self.data.schema,
+                        )?;
+                        let contents = relationship.set_null(&document.contents)?;
+                        self.data
+                            .schema
+                            .validate_contents(&child_collection, &contents)?;
+                        operations.push(Operation::update(
+                            child_collection.clone(),
+                            document.header,
+                            contents,
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(operations)
+    }
+}
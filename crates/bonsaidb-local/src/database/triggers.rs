@@ -0,0 +1,250 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bonsaidb_core::changes::Change;
+use bonsaidb_core::schema::{trigger, CollectionName, Name};
+use nebari::io::any::AnyFile;
+use nebari::tree::Unversioned;
+use nebari::{ExecutingTransaction, Tree};
+use parking_lot::Mutex;
+
+use crate::database::{document_changes_tree_name, DocumentChange};
+use crate::tasks::{Job, JobContext};
+use crate::{Database, Error, Storage};
+
+/// The tree that stores the id of the last transaction each trigger has
+/// processed.
+const TRIGGER_PROGRESS_TREE: &str = "triggers";
+
+/// The number of times a failing change is delivered before the trigger stops
+/// until it is spawned again.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// The delay before retrying a failed change for the first time. Each retry
+/// doubles the delay.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(10);
+
+/// Identifies a trigger within a storage.
+#[derive(Debug, Hash, Eq, PartialEq, Clone)]
+pub struct TriggerKey {
+    pub database: Arc<Cow<'static, str>>,
+    pub collection: CollectionName,
+    pub name: Name,
+}
+
+fn progress_key(collection: &CollectionName, name: &Name) -> String {
+    format!("{collection}/{name}")
+}
+
+/// Returns the key of the `index`th change recorded by the transaction
+/// `transaction_id`.
+fn change_key(transaction_id: u64, index: u64) -> [u8; 16] {
+    let mut key = [0; 16];
+    key[..8].copy_from_slice(&transaction_id.to_be_bytes());
+    key[8..].copy_from_slice(&index.to_be_bytes());
+    key
+}
+
+/// Records `changes` in the changes tree of each changed collection that has
+/// triggers, so that the triggers receive the contents written by
+/// `transaction` rather than the contents at the time they execute.
+pub(crate) fn record_changes(
+    changes: &[DocumentChange<'_>],
+    transaction: &mut ExecutingTransaction<AnyFile>,
+    tree_index_map: &HashMap<String, usize>,
+) -> Result<(), Error> {
+    let transaction_id = transaction.entry().id;
+    let mut tree_indexes = HashMap::<&CollectionName, Option<usize>>::new();
+    for (index, change) in (0_u64..).zip(changes) {
+        let tree_index = *tree_indexes.entry(change.collection).or_insert_with(|| {
+            tree_index_map
+                .get(&document_changes_tree_name(change.collection))
+                .copied()
+        });
+        if let Some(tree_index) = tree_index {
+            transaction.tree::<Unversioned>(tree_index).unwrap().set(
+                change_key(transaction_id, index).to_vec(),
+                pot::to_vec(&change.to_change(transaction_id))?,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Reads the trigger progress stored at `key`.
+fn read_progress(progress: &Tree<Unversioned, AnyFile>, key: &str) -> Result<Option<u64>, Error> {
+    progress
+        .get(key.as_bytes())?
+        .map(|id| {
+            <[u8; 8]>::try_from(id.as_slice())
+                .map(u64::from_be_bytes)
+                .map_err(|_| {
+                    Error::from(bonsaidb_core::Error::other(
+                        "bonsaidb-local",
+                        "invalid trigger progress",
+                    ))
+                })
+        })
+        .transpose()
+}
+
+/// Delivers the changes made since a trigger last executed.
+///
+/// Runners are debounced rather than keyed, because a runner that has already
+/// read the transaction log would miss transactions committed while it
+/// executes. Instead, a new runner is enqueued after one starts, and `lock`
+/// ensures only one runner per trigger executes at a time.
+#[derive(Debug, Clone)]
+pub struct TriggerRunner {
+    pub database: Database,
+    pub key: TriggerKey,
+    pub lock: Arc<Mutex<()>>,
+}
+
+impl TriggerRunner {
+    /// Returns a runner for `key` that reads `database` without the
+    /// permission restrictions of the session that caused it to be spawned.
+    pub fn new(database: &Database, key: TriggerKey, lock: Arc<Mutex<()>>) -> Self {
+        Self {
+            database: Database {
+                data: database.data.clone(),
+                storage: Storage::from(database.storage.instance.clone()),
+            },
+            key,
+            lock,
+        }
+    }
+
+    /// Removes the recorded changes every trigger in the collection has
+    /// processed.
+    fn prune_changes(
+        &self,
+        progress: &Tree<Unversioned, AnyFile>,
+        changes: &Tree<Unversioned, AnyFile>,
+    ) -> Result<(), Error> {
+        let mut processed = u64::MAX;
+        for trigger in self
+            .database
+            .data
+            .schema
+            .triggers_in_collection(&self.key.collection)
+        {
+            match read_progress(
+                progress,
+                &progress_key(&self.key.collection, &trigger.name()),
+            )? {
+                Some(id) => processed = processed.min(id),
+                None => return Ok(()),
+            }
+        }
+        let end = processed
+            .checked_add(1)
+            .map_or([u8::MAX; 16], |id| change_key(id, 0));
+        let pruned = changes
+            .get_range(&(..&end[..]))?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+        if !pruned.is_empty() {
+            changes.modify(pruned, nebari::tree::Operation::Remove)?;
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for TriggerRunner {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for TriggerRunner {}
+
+impl Hash for TriggerRunner {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.key.hash(state);
+    }
+}
+
+impl Job for TriggerRunner {
+    type Error = Error;
+    type Output = ();
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn execute(&mut self, context: &JobContext) -> Result<Self::Output, Self::Error> {
+        let _guard = self.lock.lock();
+        let Some(trigger) = self
+            .database
+            .data
+            .schema
+            .triggers_in_collection(&self.key.collection)
+            .find(|trigger| trigger.name() == self.key.name)
+        else {
+            return Ok(());
+        };
+
+        let progress = self
+            .database
+            .roots()
+            .tree(Unversioned::tree(TRIGGER_PROGRESS_TREE))?;
+        let changes =
+            self.database
+                .roots()
+                .tree(self.database.collection_tree::<Unversioned, _>(
+                    &self.key.collection,
+                    document_changes_tree_name(&self.key.collection),
+                )?)?;
+        let progress_key = progress_key(&self.key.collection, &self.key.name);
+        let mut last_processed = read_progress(&progress, &progress_key)?;
+
+        loop {
+            let Some(start) = last_processed.map_or(Some(0), |id| id.checked_add(1)) else {
+                break;
+            };
+            let start = change_key(start, 0);
+            let recorded = changes.get_range(&(&start[..]..))?;
+            if recorded.is_empty() {
+                break;
+            }
+
+            let mut recorded = recorded.into_iter().peekable();
+            while let Some((key, change)) = recorded.next() {
+                if context.is_cancelled() {
+                    return Ok(());
+                }
+
+                deliver(trigger, pot::from_slice(&change)?)?;
+
+                // Only record progress once every change in the transaction
+                // has been delivered.
+                let mut transaction_id = [0; 8];
+                transaction_id.copy_from_slice(&key[..8]);
+                if !matches!(recorded.peek(), Some((next, _)) if next[..8] == transaction_id) {
+                    progress.set(progress_key.as_bytes().to_vec(), transaction_id.to_vec())?;
+                    last_processed = Some(u64::from_be_bytes(transaction_id));
+                }
+            }
+        }
+
+        self.prune_changes(&progress, &changes)
+    }
+}
+
+/// Delivers `change` to `trigger`, retrying with an increasing delay if the
+/// trigger returns an error.
+fn deliver(trigger: &dyn trigger::Serialized, change: Change) -> Result<(), Error> {
+    let mut attempt = 1;
+    loop {
+        match trigger.on_change(change.clone()) {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt >= MAX_ATTEMPTS => return Err(Error::from(err)),
+            Err(_) => {
+                std::thread::sleep(INITIAL_RETRY_DELAY * 2_u32.pow(attempt - 1));
+                attempt += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use bonsaidb_core::connection::{Range, Sort};
+use bonsaidb_core::document::{BorrowedDocument, DocumentId, OwnedDocument};
+use bonsaidb_core::schema::virtual_collection::Sources;
+use bonsaidb_core::schema::{CollectionName, Schematic};
+use bonsaidb_core::transaction::{Operation, Transaction};
+use nebari::tree::Versioned;
+use parking_lot::Mutex;
+
+use super::{deserialize_current_document, document_tree_name};
+use crate::{Database, Error};
+
+/// Tracks whether the stored documents of each virtual collection were
+/// computed from the current contents of its sources.
+#[derive(Debug, Default)]
+pub(crate) struct VirtualCollections {
+    states: Mutex<HashMap<CollectionName, Arc<VirtualCollectionState>>>,
+}
+
+#[derive(Debug, Default)]
+struct VirtualCollectionState {
+    /// Incremented each time one of the collection's sources is modified.
+    generation: AtomicU64,
+    /// The generation the stored documents were computed from. This is locked
+    /// while refreshing to ensure only one refresh executes at a time.
+    refreshed: Mutex<Option<u64>>,
+}
+
+impl VirtualCollections {
+    fn state(&self, collection: &CollectionName) -> Arc<VirtualCollectionState> {
+        let mut states = self.states.lock();
+        states.entry(collection.clone()).or_default().clone()
+    }
+
+    /// Marks every virtual collection with a source in `changed` as needing
+    /// to be refreshed.
+    pub(crate) fn invalidate(&self, schema: &Schematic, changed: &[CollectionName]) {
+        for virtual_collection in schema.virtual_collections() {
+            if virtual_collection
+                .sources()
+                .iter()
+                .any(|source| changed.contains(source))
+            {
+                self.state(&virtual_collection.collection())
+                    .generation
+                    .fetch_add(1, Ordering::AcqRel);
+            }
+        }
+    }
+}
+
+impl Database {
+    /// Recomputes the documents of `collection` if it is a virtual collection
+    /// whose sources have been modified since it was last refreshed.
+    pub(crate) fn refresh_virtual_collection(
+        &self,
+        collection: &CollectionName,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.refresh_virtual_collection_and_sources(collection, &mut Vec::new())
+    }
+
+    fn refresh_virtual_collection_and_sources(
+        &self,
+        collection: &CollectionName,
+        refreshing: &mut Vec<CollectionName>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let Some(definition) = self.data.schema.virtual_collection(collection) else {
+            return Ok(());
+        };
+        if refreshing.contains(collection) {
+            return Err(bonsaidb_core::Error::other(
+                "bonsaidb-local",
+                format!("virtual collection {collection} depends on itself"),
+            ));
+        }
+        // Read-only storage and replicas return the documents as they were
+        // last stored.
+        if self.check_writable().is_err() {
+            return Ok(());
+        }
+
+        let state = self.data.context.virtual_collections.state(collection);
+        let mut refreshed = state.refreshed.lock();
+        let generation = state.generation.load(Ordering::Acquire);
+        if *refreshed == Some(generation) {
+            return Ok(());
+        }
+
+        refreshing.push(collection.clone());
+        let mut sources = HashMap::new();
+        for source in definition.sources() {
+            self.refresh_virtual_collection_and_sources(&source, refreshing)?;
+            let documents = self.all_documents(&source)?;
+            sources.insert(source, documents);
+        }
+        refreshing.pop();
+
+        let mut existing = self
+            .all_documents(collection)?
+            .into_iter()
+            .map(|document| (document.header.id.clone(), document))
+            .collect::<HashMap<DocumentId, OwnedDocument>>();
+        let mut transaction = Transaction::new();
+        for (id, contents) in definition.compute(&Sources::new(sources))? {
+            match existing.remove(&id) {
+                Some(document) if document.contents == contents => {}
+                _ => transaction.push(Operation::overwrite(collection.clone(), id, contents)),
+            }
+        }
+        for (_, document) in existing {
+            transaction.push(Operation::delete(collection.clone(), document.header));
+        }
+        if !transaction.operations.is_empty() {
+            self.execute_transaction(&transaction)?;
+        }
+
+        *refreshed = Some(generation);
+        Ok(())
+    }
+
+    fn all_documents(&self, collection: &CollectionName) -> Result<Vec<OwnedDocument>, Error> {
+        self.scan_documents::<Versioned, _>(
+            collection,
+            document_tree_name(collection),
+            Range::from(..),
+            Sort::Ascending,
+            None,
+            |bytes| {
+                deserialize_current_document(bytes, collection, &self.data.schema)
+                    .map(BorrowedDocument::into_owned)
+            },
+        )
+    }
+}
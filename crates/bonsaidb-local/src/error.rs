@@ -6,11 +6,12 @@ use std::sync::Arc;
 
 use bonsaidb_core::permissions::PermissionDenied;
 use bonsaidb_core::pubsub::{Disconnected, TryReceiveError};
-use bonsaidb_core::schema::{view, InsertError, InvalidNameError};
+use bonsaidb_core::schema::{view, CollectionName, InsertError, InvalidNameError};
 use bonsaidb_core::AnyError;
 use nebari::AbortError;
 
 use crate::database::compat::UnknownVersion;
+use crate::tasks::handle::JobCancelled;
 
 /// Errors that can occur from interacting with storage.
 #[derive(thiserror::Error, Debug)]
@@ -63,6 +64,10 @@ pub enum Error {
     #[error("an IO error occurred: {0}")]
     Io(#[from] std::io::Error),
 
+    /// A background job was cancelled before it completed.
+    #[error("a background job was cancelled")]
+    JobCancelled,
+
     /// An error occurred from a job and couldn't be unwrapped due to clones.
     #[error("an error from a job occurred: {0}")]
     Job(Arc<Error>),
@@ -71,6 +76,11 @@ pub enum Error {
     #[error("a backup error: {0}")]
     Backup(Box<dyn AnyError>),
 
+    /// A collection's data cannot be deleted because the collection is still
+    /// defined in the database's schema.
+    #[error("collection '{0}' is still defined in the schema")]
+    CollectionStillDefined(CollectionName),
+
     /// An error occurred with a password hash.
     #[cfg(all(feature = "password-hashing", feature = "cli"))]
     #[error("error reading password: {0}")]
@@ -95,6 +105,12 @@ impl From<flume::RecvError> for Error {
     }
 }
 
+impl From<JobCancelled> for Error {
+    fn from(_: JobCancelled) -> Self {
+        Self::JobCancelled
+    }
+}
+
 impl From<TryReceiveError> for Error {
     fn from(_: TryReceiveError) -> Self {
         Self::InternalCommunication
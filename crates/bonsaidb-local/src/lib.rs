@@ -22,6 +22,8 @@ pub mod cli;
 pub mod config;
 mod database;
 mod error;
+/// Types for observing the operation of BonsaiDb.
+pub mod metrics;
 mod open_trees;
 mod storage;
 mod tasks;
@@ -37,7 +39,12 @@ pub use bonsaidb_core as core;
 pub use self::database::pubsub::Subscriber;
 pub use self::database::{Database, DatabaseNonBlocking};
 pub use self::error::Error;
-pub use self::storage::{BackupLocation, Storage, StorageId, StorageNonBlocking};
+pub use self::storage::{
+    BackupLocation, IntegrityReport, Storage, StorageId, StorageNonBlocking, ViewIntegrity,
+};
+pub use self::tasks::handle::{Handle, JobCancelled};
+pub use self::tasks::manager::TasksStatus;
+pub use self::tasks::JobProgress;
 
 #[cfg(feature = "async")]
 mod r#async;
@@ -0,0 +1,222 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Write};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use bonsaidb_core::api::ApiName;
+use parking_lot::Mutex;
+
+/// Receives metrics about the operation of BonsaiDb.
+///
+/// A recorder can be installed using
+/// [`StorageConfiguration::metrics_recorder`](crate::config::StorageConfiguration::metrics_recorder).
+/// Every function has an empty default implementation, allowing recorders to
+/// only implement the callbacks they are interested in. When no recorder is
+/// installed, no metrics are gathered.
+pub trait MetricsRecorder: Debug + Send + Sync + 'static {
+    /// Invoked after a server finishes handling a request for `api`, which
+    /// took `duration` to complete.
+    #[allow(unused_variables)]
+    fn record_request(&self, api: &ApiName, duration: Duration) {}
+
+    /// Invoked when a client connects to a server.
+    fn connection_opened(&self) {}
+
+    /// Invoked when a client disconnects from a server.
+    fn connection_closed(&self) {}
+
+    /// Invoked when a server rejects a connection because a connection limit
+    /// has been reached.
+    fn connection_rejected(&self) {}
+
+    /// Invoked after a background job is enqueued. `queue_depth` is the number
+    /// of jobs waiting to be executed, including the new job.
+    #[allow(unused_variables)]
+    fn job_enqueued(&self, queue_depth: usize) {}
+
+    /// Invoked after a background job completes. `queue_depth` is the number
+    /// of jobs waiting to be executed.
+    #[allow(unused_variables)]
+    fn job_completed(&self, queue_depth: usize) {}
+
+    /// Invoked when the number of background job workers, or the number of
+    /// workers executing a job, changes.
+    #[allow(unused_variables)]
+    fn job_workers_changed(&self, workers: usize, busy_workers: usize) {}
+
+    /// Returns a snapshot of the recorded metrics using the [Prometheus text
+    /// exposition format][format]. The default implementation returns an
+    /// empty string.
+    ///
+    /// [format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+    fn snapshot(&self) -> String {
+        String::new()
+    }
+}
+
+/// A [`MetricsRecorder`] that keeps running totals of all metrics, which can
+/// be exported using [`MetricsRecorder::snapshot()`].
+#[derive(Debug, Default)]
+pub struct PrometheusRecorder {
+    requests: Mutex<BTreeMap<ApiName, RequestTotals>>,
+    connections_opened: AtomicU64,
+    connections_closed: AtomicU64,
+    connections_rejected: AtomicU64,
+    jobs_enqueued: AtomicU64,
+    jobs_completed: AtomicU64,
+    job_queue_depth: AtomicUsize,
+    job_workers: AtomicUsize,
+    job_workers_busy: AtomicUsize,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct RequestTotals {
+    count: u64,
+    duration: Duration,
+}
+
+impl MetricsRecorder for PrometheusRecorder {
+    fn record_request(&self, api: &ApiName, duration: Duration) {
+        let mut requests = self.requests.lock();
+        let totals = requests.entry(api.clone()).or_default();
+        totals.count += 1;
+        totals.duration += duration;
+    }
+
+    fn connection_opened(&self) {
+        self.connections_opened.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn connection_closed(&self) {
+        self.connections_closed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn connection_rejected(&self) {
+        self.connections_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn job_enqueued(&self, queue_depth: usize) {
+        self.jobs_enqueued.fetch_add(1, Ordering::Relaxed);
+        self.job_queue_depth.store(queue_depth, Ordering::Relaxed);
+    }
+
+    fn job_completed(&self, queue_depth: usize) {
+        self.jobs_completed.fetch_add(1, Ordering::Relaxed);
+        self.job_queue_depth.store(queue_depth, Ordering::Relaxed);
+    }
+
+    fn job_workers_changed(&self, workers: usize, busy_workers: usize) {
+        self.job_workers.store(workers, Ordering::Relaxed);
+        self.job_workers_busy.store(busy_workers, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> String {
+        let mut snapshot = String::new();
+        let requests = self.requests.lock().clone();
+        write_metric_header(
+            &mut snapshot,
+            "bonsaidb_request_duration_seconds",
+            "summary",
+            "The time spent handling requests, by API.",
+        );
+        for (api, totals) in &requests {
+            let api = escape_label(&api.to_string());
+            writeln!(
+                snapshot,
+                "bonsaidb_request_duration_seconds_sum{{api=\"{api}\"}} {}",
+                totals.duration.as_secs_f64()
+            )
+            .unwrap();
+            writeln!(
+                snapshot,
+                "bonsaidb_request_duration_seconds_count{{api=\"{api}\"}} {}",
+                totals.count
+            )
+            .unwrap();
+        }
+
+        let opened = self.connections_opened.load(Ordering::Relaxed);
+        let closed = self.connections_closed.load(Ordering::Relaxed);
+        write_metric(
+            &mut snapshot,
+            "bonsaidb_connections_total",
+            "counter",
+            "The number of connections that have been opened.",
+            opened,
+        );
+        write_metric(
+            &mut snapshot,
+            "bonsaidb_connections_active",
+            "gauge",
+            "The number of currently open connections.",
+            opened.saturating_sub(closed),
+        );
+        write_metric(
+            &mut snapshot,
+            "bonsaidb_connections_rejected_total",
+            "counter",
+            "The number of connections rejected because a connection limit was reached.",
+            self.connections_rejected.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut snapshot,
+            "bonsaidb_jobs_enqueued_total",
+            "counter",
+            "The number of background jobs that have been enqueued.",
+            self.jobs_enqueued.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut snapshot,
+            "bonsaidb_jobs_completed_total",
+            "counter",
+            "The number of background jobs that have completed.",
+            self.jobs_completed.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut snapshot,
+            "bonsaidb_job_queue_depth",
+            "gauge",
+            "The number of background jobs waiting to be executed.",
+            self.job_queue_depth.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut snapshot,
+            "bonsaidb_job_workers",
+            "gauge",
+            "The number of workers executing background jobs.",
+            self.job_workers.load(Ordering::Relaxed),
+        );
+        write_metric(
+            &mut snapshot,
+            "bonsaidb_job_workers_busy",
+            "gauge",
+            "The number of workers currently executing a background job.",
+            self.job_workers_busy.load(Ordering::Relaxed),
+        );
+
+        snapshot
+    }
+}
+
+fn write_metric_header(snapshot: &mut String, name: &str, kind: &str, help: &str) {
+    writeln!(snapshot, "# HELP {name} {help}").unwrap();
+    writeln!(snapshot, "# TYPE {name} {kind}").unwrap();
+}
+
+fn write_metric(
+    snapshot: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    value: impl std::fmt::Display,
+) {
+    write_metric_header(snapshot, name, kind, help);
+    writeln!(snapshot, "{name} {value}").unwrap();
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
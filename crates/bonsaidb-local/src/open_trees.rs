@@ -4,7 +4,10 @@ use bonsaidb_core::schema::{CollectionName, Schematic};
 use nebari::io::any::AnyFile;
 use nebari::tree::{AnyTreeRoot, Root, Unversioned, Versioned};
 
-use crate::database::document_tree_name;
+use crate::database::{
+    deleted_document_tree_name, document_changes_tree_name, document_expirations_tree_name,
+    document_revisions_tree_name, document_tree_name,
+};
 #[cfg(any(feature = "encryption", feature = "compression"))]
 use crate::storage::TreeVault;
 use crate::views::{
@@ -44,6 +47,7 @@ impl OpenTrees {
         &mut self,
         collection: &CollectionName,
         schema: &Schematic,
+        record_revisions: bool,
         #[cfg(any(feature = "encryption", feature = "compression"))] vault: Option<TreeVault>,
     ) {
         self.open_tree::<Versioned>(
@@ -52,6 +56,38 @@ impl OpenTrees {
             vault.clone(),
         );
 
+        if schema.collection_uses_soft_delete(collection) {
+            self.open_tree::<Unversioned>(
+                &deleted_document_tree_name(collection),
+                #[cfg(any(feature = "encryption", feature = "compression"))]
+                vault.clone(),
+            );
+        }
+
+        if schema.collection_expiration(collection).is_some() {
+            self.open_tree::<Unversioned>(
+                &document_expirations_tree_name(collection),
+                #[cfg(any(feature = "encryption", feature = "compression"))]
+                vault.clone(),
+            );
+        }
+
+        if schema.triggers_in_collection(collection).next().is_some() {
+            self.open_tree::<Unversioned>(
+                &document_changes_tree_name(collection),
+                #[cfg(any(feature = "encryption", feature = "compression"))]
+                vault.clone(),
+            );
+        }
+
+        if record_revisions {
+            self.open_tree::<Unversioned>(
+                &document_revisions_tree_name(collection),
+                #[cfg(any(feature = "encryption", feature = "compression"))]
+                vault.clone(),
+            );
+        }
+
         for view in schema.views_in_collection(collection) {
             let view_name = view.view_name();
             if view.update_policy().is_eager() {
@@ -6,26 +6,30 @@ use std::io::{Read, Write};
 use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 
-use bonsaidb_core::admin::database::{self, ByName, Database as DatabaseRecord};
+use bonsaidb_core::admin::database::{self, Database as DatabaseRecord};
 use bonsaidb_core::admin::user::User;
 use bonsaidb_core::admin::{self, Admin, PermissionGroup, Role, ADMIN_DATABASE_NAME};
-use bonsaidb_core::circulate;
+use bonsaidb_core::circulate::flume;
 pub use bonsaidb_core::circulate::Relay;
 use bonsaidb_core::connection::{
-    self, Connection, HasSession, Identity, IdentityReference, LowLevelConnection, Session,
-    SessionAuthentication, SessionId, StorageConnection,
+    self, Connection, DatabaseCreation, HasSession, Identity, IdentityReference,
+    LowLevelConnection, Session, SessionAuthentication, SessionId, StorageConnection,
 };
 use bonsaidb_core::document::CollectionDocument;
 #[cfg(any(feature = "encryption", feature = "compression"))]
 use bonsaidb_core::document::KeyId;
+use bonsaidb_core::key::time::TimestampAsNanoseconds;
 use bonsaidb_core::permissions::bonsai::{
     bonsaidb_resource_name, database_resource_name, role_resource_name, user_resource_name,
     BonsaiAction, ServerAction,
 };
-use bonsaidb_core::permissions::Permissions;
+use bonsaidb_core::permissions::{Permissions, Statement};
+use bonsaidb_core::pubsub::MessageSender;
 use bonsaidb_core::schema::{
-    Nameable, NamedCollection, Schema, SchemaName, SchemaSummary, Schematic,
+    Collection, CollectionName, Nameable, NamedCollection, Schema, SchemaName, SchemaSummary,
+    Schematic, SerializedCollection,
 };
 use fs2::FileExt;
 use itertools::Itertools;
@@ -37,12 +41,14 @@ use rand::{thread_rng, Rng};
 
 #[cfg(feature = "compression")]
 use crate::config::Compression;
-use crate::config::{KeyValuePersistence, StorageConfiguration};
+#[cfg(feature = "password-hashing")]
+use crate::config::PasswordPolicy;
+use crate::config::{KeyValuePersistence, PubSubRetention, StorageConfiguration};
 use crate::database::Context;
-use crate::tasks::manager::Manager;
+use crate::tasks::manager::{Manager, TasksStatus};
 use crate::tasks::TaskManager;
 #[cfg(feature = "encryption")]
-use crate::vault::{self, LocalVaultKeyStorage, Vault};
+use crate::vault::{self, LocalVaultKeyStorage, MemoryVaultKeyStorage, Vault};
 use crate::{Database, Error};
 
 #[cfg(feature = "password-hashing")]
@@ -51,8 +57,13 @@ mod argon;
 mod token_authentication;
 
 mod backup;
+mod integrity;
 mod pubsub;
 pub use backup::{AnyBackupLocation, BackupLocation};
+pub use integrity::{IntegrityReport, ViewIntegrity};
+
+pub(crate) use self::pubsub::BackpressuredMessages;
+use self::pubsub::{RetainedMessages, TopicTrie};
 
 /// A file-based, multi-database, multi-user database engine. This type blocks
 /// the current thread when used. See [`AsyncStorage`](crate::AsyncStorage) for
@@ -165,14 +176,16 @@ pub struct AuthenticatedSession {
 pub struct SessionSubscribers {
     pub subscribers: HashMap<u64, SessionSubscriber>,
     pub subscribers_by_session: HashMap<SessionId, HashSet<u64>>,
+    /// The subscribers of each namespaced topic.
+    pub topics: HashMap<Vec<u8>, HashSet<u64>>,
+    pub prefixes: TopicTrie,
     pub last_id: u64,
 }
 
 impl SessionSubscribers {
     pub fn unregister(&mut self, subscriber_id: u64) {
         if let Some(session_id) = self
-            .subscribers
-            .remove(&subscriber_id)
+            .remove_subscriber(subscriber_id)
             .and_then(|sub| sub.session_id)
         {
             if let Some(session_subscribers) = self.subscribers_by_session.get_mut(&session_id) {
@@ -180,12 +193,39 @@ impl SessionSubscribers {
             }
         }
     }
+
+    fn remove_subscriber(&mut self, subscriber_id: u64) -> Option<SessionSubscriber> {
+        let subscriber = self.subscribers.remove(&subscriber_id)?;
+        for topic in &subscriber.topics {
+            self.remove_topic_subscriber(topic, subscriber_id);
+        }
+        for prefix in &subscriber.prefixes {
+            self.prefixes.remove(prefix, subscriber_id);
+        }
+        Some(subscriber)
+    }
+
+    pub fn remove_topic_subscriber(&mut self, topic: &[u8], subscriber_id: u64) {
+        if let Some(subscribers) = self.topics.get_mut(topic) {
+            subscribers.remove(&subscriber_id);
+            if subscribers.is_empty() {
+                self.topics.remove(topic);
+            }
+        }
+    }
 }
 
 #[derive(Debug)]
 pub struct SessionSubscriber {
     pub session_id: Option<SessionId>,
-    pub subscriber: circulate::Subscriber,
+    /// The database this subscriber was created with, whose session
+    /// determines which messages published by BonsaiDb it may receive.
+    pub database: Database,
+    pub sender: MessageSender,
+    /// The namespaced topics this subscriber is subscribed to directly.
+    pub topics: HashSet<Vec<u8>>,
+    /// The namespaced prefixes this subscriber is subscribed to.
+    pub prefixes: HashSet<Vec<u8>>,
 }
 
 impl Drop for AuthenticatedSession {
@@ -205,7 +245,7 @@ impl Drop for AuthenticatedSession {
                     .into_iter()
                     .flatten()
                 {
-                    sessions.subscribers.remove(&id);
+                    sessions.remove_subscriber(id);
                 }
             }
         }
@@ -239,9 +279,12 @@ struct Data {
     parallelization: usize,
     threadpool: ThreadPool<AnyFile>,
     file_manager: AnyFileManager,
+    memory_only: bool,
+    read_only: bool,
     pub(crate) tasks: TaskManager,
     schemas: RwLock<HashMap<SchemaName, Arc<dyn DatabaseOpener>>>,
-    available_databases: RwLock<HashMap<String, SchemaName>>,
+    available_databases: RwLock<HashMap<String, connection::Database>>,
+    database_permissions: RwLock<HashMap<String, Permissions>>,
     open_roots: Mutex<HashMap<String, Context>>,
     // cfg check matches `Connection::authenticate`
     authenticated_permissions: Permissions,
@@ -249,6 +292,8 @@ struct Data {
     pub(crate) subscribers: Arc<RwLock<SessionSubscribers>>,
     #[cfg(feature = "password-hashing")]
     argon: argon::Hasher,
+    #[cfg(feature = "password-hashing")]
+    password_policy: PasswordPolicy,
     #[cfg(feature = "encryption")]
     pub(crate) vault: Arc<Vault>,
     #[cfg(feature = "encryption")]
@@ -256,9 +301,17 @@ struct Data {
     #[cfg(any(feature = "compression", feature = "encryption"))]
     tree_vault: Option<TreeVault>,
     pub(crate) key_value_persistence: KeyValuePersistence,
+    key_value_max_value_size: Option<usize>,
     chunk_cache: ChunkCache,
     pub(crate) check_view_integrity_on_database_open: bool,
-    relay: Relay,
+    revision_history: usize,
+    pubsub_retention: HashMap<Vec<u8>, PubSubRetention>,
+    pubsub_max_payload_size: Option<usize>,
+    retained_messages: Mutex<HashMap<Vec<u8>, RetainedMessages>>,
+    replica_databases: RwLock<HashSet<String>>,
+    /// Dropping this sender stops the thread that periodically deletes
+    /// expired documents.
+    _document_reaper_shutdown: flume::Sender<()>,
 }
 
 impl Storage {
@@ -274,20 +327,34 @@ impl Storage {
             AnyFileManager::std()
         };
 
-        let manager = Manager::default();
-        for _ in 0..configuration.workers.worker_count {
-            manager.spawn_worker();
-        }
+        let manager = Manager::new(configuration.workers.worker_count);
+        manager.set_metrics_recorder(configuration.metrics_recorder.clone());
         let tasks = TaskManager::new(manager);
 
-        fs::create_dir_all(&owned_path)?;
+        let storage_lock = if configuration.memory_only {
+            // Memory-only storage never touches the disk, so there is no id
+            // file to load or lock.
+            StorageLock::new(
+                StorageId(
+                    configuration
+                        .unique_id
+                        .unwrap_or_else(|| thread_rng().gen::<u64>()),
+                ),
+                None,
+            )
+        } else {
+            if !configuration.read_only {
+                fs::create_dir_all(&owned_path)?;
+            }
 
-        let storage_lock = Self::lookup_or_create_id(&configuration, &owned_path)?;
+            Self::lookup_or_create_id(&configuration, &owned_path)?
+        };
 
         #[cfg(feature = "encryption")]
         let vault = {
             let vault_key_storage = match configuration.vault_key_storage {
                 Some(storage) => storage,
+                None if configuration.memory_only => Arc::new(MemoryVaultKeyStorage::default()),
                 None => Arc::new(
                     LocalVaultKeyStorage::new(owned_path.join("vault-keys"))
                         .map_err(|err| Error::Vault(vault::Error::Initializing(err.to_string())))?,
@@ -296,16 +363,19 @@ impl Storage {
 
             Arc::new(Vault::initialize(
                 storage_lock.id(),
-                &owned_path,
+                (!configuration.memory_only).then_some(owned_path.as_path()),
                 vault_key_storage,
             )?)
         };
 
         let parallelization = configuration.workers.parallelization;
         let check_view_integrity_on_database_open = configuration.views.check_integrity_on_open;
+        let integrity_check = configuration.integrity_check;
         let key_value_persistence = configuration.key_value_persistence;
         #[cfg(feature = "password-hashing")]
         let argon = argon::Hasher::new(configuration.argon);
+        #[cfg(feature = "password-hashing")]
+        let password_policy = configuration.password_policy;
         #[cfg(feature = "encryption")]
         let default_encryption_key = configuration.default_encryption_key;
         #[cfg(all(feature = "compression", feature = "encryption"))]
@@ -320,6 +390,7 @@ impl Storage {
         let tree_vault = TreeVault::new_if_needed(configuration.default_compression);
 
         let authenticated_permissions = configuration.authenticated_permissions;
+        let (document_reaper_shutdown, document_reaper_shutdown_receiver) = flume::bounded(1);
 
         let storage = Self {
             instance: StorageInstance {
@@ -332,6 +403,8 @@ impl Storage {
                     sessions: RwLock::default(),
                     #[cfg(feature = "password-hashing")]
                     argon,
+                    #[cfg(feature = "password-hashing")]
+                    password_policy,
                     #[cfg(feature = "encryption")]
                     vault,
                     #[cfg(feature = "encryption")]
@@ -340,14 +413,23 @@ impl Storage {
                     tree_vault,
                     path: owned_path,
                     file_manager,
+                    memory_only: configuration.memory_only,
+                    read_only: configuration.read_only,
                     chunk_cache: ChunkCache::new(2000, 160_384),
                     threadpool: ThreadPool::new(parallelization),
                     schemas: RwLock::new(configuration.initial_schemas),
                     available_databases: RwLock::default(),
+                    database_permissions: RwLock::default(),
                     open_roots: Mutex::default(),
                     key_value_persistence,
+                    key_value_max_value_size: configuration.key_value_max_value_size,
                     check_view_integrity_on_database_open,
-                    relay: Relay::default(),
+                    revision_history: configuration.revision_history,
+                    pubsub_retention: configuration.pubsub_retention,
+                    pubsub_max_payload_size: configuration.pubsub_max_payload_size,
+                    retained_messages: Mutex::default(),
+                    replica_databases: RwLock::default(),
+                    _document_reaper_shutdown: document_reaper_shutdown,
                 }),
             },
             authentication: None,
@@ -358,6 +440,18 @@ impl Storage {
 
         storage.create_admin_database_if_needed()?;
 
+        if !storage.instance.is_read_only() {
+            storage.instance.spawn_document_reaper_worker(
+                document_reaper_shutdown_receiver,
+                configuration.document_expiration_interval,
+            );
+        }
+
+        let report = storage.check_integrity(integrity_check, !storage.instance.is_read_only())?;
+        for view in report.inconsistencies() {
+            log::warn!("[storage] Integrity check failed: {view}");
+        }
+
         Ok(storage)
     }
 
@@ -369,6 +463,19 @@ impl Storage {
             .database_without_schema(&name, Some(self), None)
     }
 
+    /// Marks the database named `name` as a replica. Replicas reject all
+    /// modifications except those applied through replication.
+    #[cfg(feature = "internal-apis")]
+    #[doc(hidden)]
+    pub fn set_replica(&self, name: &str, replica: bool) {
+        let mut replicas = self.instance.data.replica_databases.write();
+        if replica {
+            replicas.insert(name.to_string());
+        } else {
+            replicas.remove(name);
+        }
+    }
+
     fn lookup_or_create_id(
         configuration: &StorageConfiguration,
         path: &Path,
@@ -382,7 +489,28 @@ impl Storage {
             }
         };
 
-        let (id, file) = if let Some(id) = configuration.unique_id {
+        let (id, file) = if configuration.read_only {
+            // Read-only storage can't create or update the id file. A shared
+            // lock allows other read-only instances to open the storage, but
+            // not instances that can write to it.
+            let mut file = File::open(id_path)?;
+            file.try_lock_shared().map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::WouldBlock,
+                    "the storage is locked by another process",
+                )
+            })?;
+            let id = if let Some(id) = configuration.unique_id {
+                id
+            } else {
+                let mut bytes = Vec::new();
+                file.read_to_end(&mut bytes)?;
+                let existing_id =
+                    String::from_utf8(bytes).expect("server-id contains invalid data");
+                existing_id.parse().expect("server-id isn't numeric")
+            };
+            (id, file)
+        } else if let Some(id) = configuration.unique_id {
             // The configuraiton id override is not persisted to disk. This is
             // mostly to prevent someone from accidentally adding this
             // configuration, realizing it breaks things, and then wanting to
@@ -423,19 +551,30 @@ impl Storage {
                 (id, file)
             }
         };
-        Ok(StorageLock::new(StorageId(id), file))
+        Ok(StorageLock::new(StorageId(id), Some(file)))
     }
 
     fn cache_available_databases(&self) -> Result<(), Error> {
-        let available_databases = self
-            .admin()
-            .view::<ByName>()
-            .query()?
-            .into_iter()
-            .map(|map| (map.key, map.value))
-            .collect();
-        let mut storage_databases = self.instance.data.available_databases.write();
-        *storage_databases = available_databases;
+        let mut available_databases = HashMap::new();
+        let mut database_permissions = HashMap::new();
+        for record in DatabaseRecord::all(&self.admin()).query()? {
+            if !record.contents.permissions.is_empty() {
+                database_permissions.insert(
+                    record.contents.name.clone(),
+                    Permissions::from(record.contents.permissions),
+                );
+            }
+            available_databases.insert(
+                record.contents.name.clone(),
+                connection::Database {
+                    name: record.contents.name,
+                    schema: record.contents.schema,
+                    created_at: record.contents.created_at,
+                },
+            );
+        }
+        *self.instance.data.available_databases.write() = available_databases;
+        *self.instance.data.database_permissions.write() = database_permissions;
         Ok(())
     }
 
@@ -464,6 +603,85 @@ impl Storage {
         self.instance.data.lock.id()
     }
 
+    /// Compacts every database in this storage, returning the number of bytes
+    /// reclaimed.
+    ///
+    /// Compaction is performed using low-priority jobs, and databases remain
+    /// readable while they are being compacted. Databases are compacted one at
+    /// a time, and the bytes reclaimed are measured by comparing each
+    /// database's size on disk before and after it was compacted. Memory-only
+    /// storage always reports 0 bytes reclaimed.
+    ///
+    /// This requires permission to perform
+    /// [`DatabaseAction::Compact`](bonsaidb_core::permissions::bonsai::DatabaseAction::Compact)
+    /// on each database.
+    pub fn compact(&self) -> Result<u64, Error> {
+        self.compact_databases(|database| {
+            database.compact()?;
+            Ok(())
+        })
+    }
+
+    /// Compacts the collection `C` in every database whose schema contains it,
+    /// returning the number of bytes reclaimed. See [`Self::compact()`] for
+    /// more information.
+    pub fn compact_collection<C: Collection + 'static>(&self) -> Result<u64, Error> {
+        self.compact_databases(|database| {
+            if database.schematic().contains_collection::<C>() {
+                database.compact_collection::<C>()?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Removes all stored data for `collection` from the database named
+    /// `database`, including its documents, tombstones, and view indexes.
+    ///
+    /// This is intended for cleaning up after a collection has been removed
+    /// from the database's schema. See [`Database::delete_collection()`] for
+    /// more information.
+    pub fn delete_collection(
+        &self,
+        database: &str,
+        collection: &CollectionName,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.instance
+            .database_without_schema(database, Some(self), None)?
+            .delete_collection(collection)
+    }
+
+    fn compact_databases(
+        &self,
+        mut compact: impl FnMut(&Database) -> Result<(), Error>,
+    ) -> Result<u64, Error> {
+        let names = self
+            .instance
+            .data
+            .available_databases
+            .read()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut reclaimed = 0;
+        for name in names {
+            let database = self
+                .instance
+                .database_without_schema(&name, Some(self), None)?;
+            let size_before = self.database_size(&name)?;
+            compact(&database)?;
+            reclaimed += size_before.saturating_sub(self.database_size(&name)?);
+        }
+        Ok(reclaimed)
+    }
+
+    fn database_size(&self, name: &str) -> Result<u64, Error> {
+        if self.instance.data.memory_only {
+            Ok(0)
+        } else {
+            Ok(directory_size(&self.instance.data.path.join(name))?)
+        }
+    }
+
     #[must_use]
     pub(crate) fn parallelization(&self) -> usize {
         self.instance.data.parallelization
@@ -545,6 +763,38 @@ impl Storage {
         }
     }
 
+    /// Authenticates as `identity`, which has already been verified using
+    /// `method` outside of this storage, such as by a server's registered
+    /// authenticators or session tokens.
+    ///
+    /// The current session must be allowed to perform
+    /// [`ServerAction::Authenticate`] with `method` against the identity's
+    /// resource name. The new session's permissions include the permission
+    /// groups and roles of the identity.
+    pub fn authenticate_identity(
+        &self,
+        identity: IdentityReference<'_>,
+        method: bonsaidb_core::connection::AuthenticationMethod,
+    ) -> Result<Self, bonsaidb_core::Error> {
+        let admin = self.admin();
+        let action = BonsaiAction::Server(ServerAction::Authenticate(method));
+        match identity {
+            IdentityReference::User(user) => {
+                let user =
+                    User::load(user, &admin)?.ok_or(bonsaidb_core::Error::InvalidCredentials)?;
+                self.check_permission(user_resource_name(user.header.id), &action)?;
+                self.instance.assume_user(user, &admin)
+            }
+            IdentityReference::Role(role) => {
+                let role =
+                    Role::load(role, &admin)?.ok_or(bonsaidb_core::Error::InvalidCredentials)?;
+                self.check_permission(role_resource_name(role.header.id), &action)?;
+                self.instance.assume_role(role, &admin)
+            }
+            _ => Err(bonsaidb_core::Error::InvalidCredentials),
+        }
+    }
+
     /// Converts this instance into its blocking version, which is able to be
     /// used without async. The returned instance uses the current Tokio runtime
     /// handle to spawn blocking tasks.
@@ -597,19 +847,26 @@ impl Debug for Data {
             .field("parallelization", &self.parallelization)
             .field("threadpool", &self.threadpool)
             .field("file_manager", &self.file_manager)
+            .field("memory_only", &self.memory_only)
+            .field("read_only", &self.read_only)
             .field("tasks", &self.tasks)
             .field("available_databases", &self.available_databases)
+            .field("database_permissions", &self.database_permissions)
             .field("open_roots", &self.open_roots)
             .field("authenticated_permissions", &self.authenticated_permissions)
             .field("sessions", &self.sessions)
             .field("subscribers", &self.subscribers)
             .field("key_value_persistence", &self.key_value_persistence)
+            .field("key_value_max_value_size", &self.key_value_max_value_size)
             .field("chunk_cache", &self.chunk_cache)
             .field(
                 "check_view_integrity_on_database_open",
                 &self.check_view_integrity_on_database_open,
             )
-            .field("relay", &self.relay);
+            .field("revision_history", &self.revision_history)
+            .field("pubsub_retention", &self.pubsub_retention)
+            .field("pubsub_max_payload_size", &self.pubsub_max_payload_size)
+            .field("retained_messages", &self.retained_messages);
 
         if let Some(schemas) = self.schemas.try_read() {
             let mut schemas = schemas.keys().collect::<Vec<_>>();
@@ -620,7 +877,8 @@ impl Debug for Data {
         }
 
         #[cfg(feature = "password-hashing")]
-        f.field("argon", &self.argon);
+        f.field("argon", &self.argon)
+            .field("password_policy", &self.password_policy);
         #[cfg(feature = "encryption")]
         {
             f.field("vault", &self.vault)
@@ -633,6 +891,23 @@ impl Debug for Data {
     }
 }
 
+/// Periodically deletes expired documents until the storage is dropped.
+fn document_reaper_worker(
+    storage: &Weak<Data>,
+    shutdown: &flume::Receiver<()>,
+    interval: Duration,
+) {
+    // recv_timeout panics if Instant::checked_add(interval) fails, so the
+    // interval is capped at 1 day.
+    let interval = interval.min(Duration::from_secs(60 * 60 * 24));
+    while let Err(flume::RecvTimeoutError::Timeout) = shutdown.recv_timeout(interval) {
+        let Some(data) = storage.upgrade() else {
+            break;
+        };
+        StorageInstance { data }.spawn_document_reapers();
+    }
+}
+
 impl StorageInstance {
     #[cfg_attr(
         not(any(feature = "encryption", feature = "compression")),
@@ -659,6 +934,7 @@ impl StorageInstance {
             let context = Context::new(
                 roots,
                 self.data.key_value_persistence.clone(),
+                self.data.key_value_max_value_size,
                 Some(self.data.lock.clone()),
             );
 
@@ -676,11 +952,55 @@ impl StorageInstance {
         self.data.check_view_integrity_on_database_open
     }
 
-    pub(crate) fn relay(&self) -> &'_ Relay {
-        &self.data.relay
+    pub(crate) fn revision_history(&self) -> usize {
+        self.data.revision_history
+    }
+
+    pub(crate) fn is_replica(&self, name: &str) -> bool {
+        self.data.replica_databases.read().contains(name)
+    }
+
+    /// Returns true if this storage was opened in read-only mode.
+    pub(crate) fn is_read_only(&self) -> bool {
+        self.data.read_only
+    }
+
+    /// Returns [`bonsaidb_core::Error::ReadOnly`] if this storage was opened
+    /// in read-only mode.
+    pub(crate) fn check_writable(&self) -> Result<(), bonsaidb_core::Error> {
+        if self.is_read_only() {
+            Err(bonsaidb_core::Error::ReadOnly)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn spawn_document_reaper_worker(&self, shutdown: flume::Receiver<()>, interval: Duration) {
+        let storage = Arc::downgrade(&self.data);
+        std::thread::Builder::new()
+            .name(String::from("document-reaper"))
+            .spawn(move || document_reaper_worker(&storage, &shutdown, interval))
+            .unwrap();
+    }
+
+    /// Enqueues a job to delete the expired documents of each database that
+    /// has been opened and has collections that expire documents.
+    fn spawn_document_reapers(&self) {
+        for name in self.tasks().expiring_databases() {
+            // The database may have been deleted since it was opened.
+            if let Ok(database) = self.database_without_schema(&name, None, None) {
+                drop(self.tasks().spawn_document_reaper(&database));
+            }
+        }
     }
 
     /// Opens a database through a generic-free trait.
+    /// Returns the permissions granted to all sessions accessing the database
+    /// named `name`, if any.
+    pub(crate) fn database_permissions(&self, name: &str) -> Option<Permissions> {
+        self.data.database_permissions.read().get(name).cloned()
+    }
+
     pub(crate) fn database_without_schema(
         &self,
         name: &str,
@@ -695,6 +1015,7 @@ impl StorageInstance {
                 .ok_or_else(|| {
                     Error::Core(bonsaidb_core::Error::DatabaseNotFound(name.to_string()))
                 })?
+                .schema
                 .clone()
         };
 
@@ -752,7 +1073,6 @@ impl StorageInstance {
         }
     }
 
-    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
     #[cfg_attr(
         any(
             not(feature = "token-authentication"),
@@ -760,6 +1080,10 @@ impl StorageInstance {
         ),
         allow(unused_variables, clippy::needless_pass_by_value)
     )]
+    #[cfg_attr(
+        not(any(feature = "token-authentication", feature = "password-hashing")),
+        allow(clippy::unused_self)
+    )]
     fn authenticate_inner(
         &self,
         authentication: bonsaidb_core::connection::Authentication,
@@ -801,6 +1125,11 @@ impl StorageInstance {
                     .verify(user.header.id, password, saved_hash)?;
                 self.assume_user(user, admin)
             }
+            // Custom authenticators and session tokens are only available
+            // through a server.
+            Authentication::Custom { .. } | Authentication::SessionToken(_) => {
+                Err(bonsaidb_core::Error::InvalidCredentials)
+            }
         }
     }
 
@@ -974,12 +1303,13 @@ impl StorageConnection for StorageInstance {
             schema.name = schema.name.as_ref(),
         )
     ))]
-    fn create_database_with_schema(
+    fn create_database_with_schema_and_permissions(
         &self,
         name: &str,
         schema: SchemaName,
         only_if_needed: bool,
-    ) -> Result<(), bonsaidb_core::Error> {
+        permissions: Vec<Statement>,
+    ) -> Result<DatabaseCreation, bonsaidb_core::Error> {
         Storage::validate_name(name)?;
 
         {
@@ -991,21 +1321,41 @@ impl StorageConnection for StorageInstance {
 
         let mut available_databases = self.data.available_databases.write();
         let admin = self.admin();
-        if !available_databases.contains_key(name) {
+        if available_databases.contains_key(name) {
+            if only_if_needed {
+                Ok(DatabaseCreation::AlreadyExisted)
+            } else {
+                Err(bonsaidb_core::Error::DatabaseNameAlreadyTaken(
+                    name.to_string(),
+                ))
+            }
+        } else {
+            let created_at = Some(TimestampAsNanoseconds::now());
             admin
                 .collection::<DatabaseRecord>()
                 .push(&admin::Database {
                     name: name.to_string(),
                     schema: schema.clone(),
+                    created_at,
+                    permissions: permissions.clone(),
                 })?;
-            available_databases.insert(name.to_string(), schema);
-        } else if !only_if_needed {
-            return Err(bonsaidb_core::Error::DatabaseNameAlreadyTaken(
+            if !permissions.is_empty() {
+                self.data
+                    .database_permissions
+                    .write()
+                    .insert(name.to_string(), Permissions::from(permissions));
+            }
+            available_databases.insert(
                 name.to_string(),
-            ));
-        }
+                connection::Database {
+                    name: name.to_string(),
+                    schema,
+                    created_at,
+                },
+            );
 
-        Ok(())
+            Ok(DatabaseCreation::Created)
+        }
     }
 
     fn database<DB: Schema>(&self, name: &str) -> Result<Self::Database, bonsaidb_core::Error> {
@@ -1015,15 +1365,17 @@ impl StorageConnection for StorageInstance {
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
     fn delete_database(&self, name: &str) -> Result<(), bonsaidb_core::Error> {
+        self.check_writable()?;
         let admin = self.admin();
         let mut available_databases = self.data.available_databases.write();
         available_databases.remove(name);
+        self.data.database_permissions.write().remove(name);
 
         let mut open_roots = self.data.open_roots.lock();
         open_roots.remove(name);
 
         let database_folder = self.data.path.join(name);
-        if database_folder.exists() {
+        if self.data.memory_only || database_folder.exists() {
             let file_manager = self.data.file_manager.clone();
             file_manager
                 .delete_directory(&database_folder)
@@ -1047,13 +1399,7 @@ impl StorageConnection for StorageInstance {
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     fn list_databases(&self) -> Result<Vec<connection::Database>, bonsaidb_core::Error> {
         let available_databases = self.data.available_databases.read();
-        Ok(available_databases
-            .iter()
-            .map(|(name, schema)| connection::Database {
-                name: name.to_string(),
-                schema: schema.clone(),
-            })
-            .collect())
+        Ok(available_databases.values().cloned().collect())
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
@@ -1063,6 +1409,7 @@ impl StorageConnection for StorageInstance {
 
         Ok(available_databases
             .values()
+            .map(|database| &database.schema)
             .unique()
             .filter_map(|name| {
                 schemas
@@ -1072,6 +1419,29 @@ impl StorageConnection for StorageInstance {
             .collect())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip(self)))]
+    fn database_stats(
+        &self,
+        name: &str,
+    ) -> Result<connection::DatabaseStats, bonsaidb_core::Error> {
+        let database = self.database_without_schema(name, None, None)?;
+        let mut collections = HashMap::new();
+        for collection in database.schematic().collections() {
+            collections.insert(collection.clone(), database.collection_stats(collection)?);
+        }
+
+        let total_bytes = if self.data.memory_only {
+            0
+        } else {
+            directory_size(&self.data.path.join(name)).map_err(Error::from)?
+        };
+
+        Ok(connection::DatabaseStats {
+            total_bytes,
+            collections,
+        })
+    }
+
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         let result = self
@@ -1093,6 +1463,21 @@ impl StorageConnection for StorageInstance {
         Ok(())
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn list_users(&self) -> Result<Vec<connection::UserInfo>, bonsaidb_core::Error> {
+        let admin = self.admin();
+        Ok(User::all(&admin)
+            .query()?
+            .into_iter()
+            .map(|user| connection::UserInfo {
+                id: user.header.id,
+                username: user.contents.username,
+                groups: user.contents.groups,
+                roles: user.contents.roles,
+            })
+            .collect())
+    }
+
     #[cfg(feature = "password-hashing")]
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     fn set_user_password<'user, U: Nameable<'user, u64> + Send + Sync>(
@@ -1100,6 +1485,7 @@ impl StorageConnection for StorageInstance {
         user: U,
         password: bonsaidb_core::connection::SensitiveString,
     ) -> Result<(), bonsaidb_core::Error> {
+        self.data.password_policy.check(&password)?;
         let admin = self.admin();
         let mut user = User::load(user, &admin)?.ok_or(bonsaidb_core::Error::UserNotFound)?;
         user.contents.argon_hash = Some(self.data.argon.hash(user.header.id, password)?);
@@ -1107,7 +1493,6 @@ impl StorageConnection for StorageInstance {
     }
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
-    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
     fn authenticate(
         &self,
         authentication: bonsaidb_core::connection::Authentication,
@@ -1184,6 +1569,25 @@ impl StorageConnection for StorageInstance {
         )
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn list_user_groups<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<connection::PermissionGroupInfo>, bonsaidb_core::Error> {
+        let admin = self.admin();
+        let user = User::load(user, &admin)?.ok_or(bonsaidb_core::Error::UserNotFound)?;
+        Ok(
+            PermissionGroup::get_multiple(user.contents.groups.iter(), &admin)?
+                .into_iter()
+                .map(|group| connection::PermissionGroupInfo {
+                    id: group.header.id,
+                    name: group.contents.name,
+                    parent_group: group.contents.parent_group,
+                })
+                .collect(),
+        )
+    }
+
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     fn add_role_to_user<
         'user,
@@ -1231,18 +1635,23 @@ impl StorageConnection for Storage {
         self.instance.admin()
     }
 
-    fn create_database_with_schema(
+    fn create_database_with_schema_and_permissions(
         &self,
         name: &str,
         schema: SchemaName,
         only_if_needed: bool,
-    ) -> Result<(), bonsaidb_core::Error> {
+        permissions: Vec<Statement>,
+    ) -> Result<DatabaseCreation, bonsaidb_core::Error> {
         self.check_permission(
             database_resource_name(name),
             &BonsaiAction::Server(ServerAction::CreateDatabase),
         )?;
-        self.instance
-            .create_database_with_schema(name, schema, only_if_needed)
+        self.instance.create_database_with_schema_and_permissions(
+            name,
+            schema,
+            only_if_needed,
+            permissions,
+        )
     }
 
     fn database<DB: Schema>(&self, name: &str) -> Result<Self::Database, bonsaidb_core::Error> {
@@ -1273,6 +1682,17 @@ impl StorageConnection for Storage {
         self.instance.list_available_schemas()
     }
 
+    fn database_stats(
+        &self,
+        name: &str,
+    ) -> Result<connection::DatabaseStats, bonsaidb_core::Error> {
+        self.check_permission(
+            database_resource_name(name),
+            &BonsaiAction::Server(ServerAction::GetDatabaseStats),
+        )?;
+        self.instance.database_stats(name)
+    }
+
     fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         self.check_permission(
             bonsaidb_resource_name(),
@@ -1297,6 +1717,14 @@ impl StorageConnection for Storage {
         self.instance.delete_user(user)
     }
 
+    fn list_users(&self) -> Result<Vec<connection::UserInfo>, bonsaidb_core::Error> {
+        self.check_permission(
+            bonsaidb_resource_name(),
+            &BonsaiAction::Server(ServerAction::ListUsers),
+        )?;
+        self.instance.list_users()
+    }
+
     #[cfg(feature = "password-hashing")]
     fn set_user_password<'user, U: Nameable<'user, u64> + Send + Sync>(
         &self,
@@ -1315,9 +1743,12 @@ impl StorageConnection for Storage {
         self.instance.set_user_password(user, password)
     }
 
-    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
     #[cfg_attr(not(feature = "token-authentication"), allow(unused_assignments))]
     #[cfg_attr(not(feature = "password-hashing"), allow(unused_mut))]
+    #[cfg_attr(
+        not(any(feature = "token-authentication", feature = "password-hashing")),
+        allow(unreachable_code)
+    )]
     fn authenticate(
         &self,
         authentication: bonsaidb_core::connection::Authentication,
@@ -1348,6 +1779,10 @@ impl StorageConnection for Storage {
             }
             #[cfg(feature = "token-authentication")]
             bonsaidb_core::connection::Authentication::TokenChallengeResponse(_) => {}
+            bonsaidb_core::connection::Authentication::Custom { .. }
+            | bonsaidb_core::connection::Authentication::SessionToken(_) => {
+                return Err(bonsaidb_core::Error::InvalidCredentials);
+            }
         }
         self.instance.authenticate_inner(
             authentication,
@@ -1443,6 +1878,22 @@ impl StorageConnection for Storage {
             )
     }
 
+    fn list_user_groups<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<connection::PermissionGroupInfo>, bonsaidb_core::Error> {
+        let admin = self.admin();
+        let user = user.name()?;
+        let user_id = user
+            .id::<User, _>(&admin)?
+            .ok_or(bonsaidb_core::Error::UserNotFound)?;
+        self.check_permission(
+            user_resource_name(user_id),
+            &BonsaiAction::Server(ServerAction::ListUserGroups),
+        )?;
+        self.instance.list_user_groups(user_id)
+    }
+
     fn add_role_to_user<
         'user,
         'group,
@@ -1484,6 +1935,27 @@ impl StorageConnection for Storage {
     }
 }
 
+/// Returns the total size of the files contained within `path`, including
+/// files in subdirectories. Returns 0 if `path` doesn't exist.
+fn directory_size(path: &Path) -> std::io::Result<u64> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    let mut total = 0;
+    for entry in entries {
+        let entry = entry?;
+        let metadata = entry.metadata()?;
+        if metadata.is_dir() {
+            total += directory_size(&entry.path())?;
+        } else {
+            total += metadata.len();
+        }
+    }
+    Ok(total)
+}
+
 #[test]
 fn name_validation_tests() {
     assert!(matches!(Storage::validate_name("azAZ09.-"), Ok(())));
@@ -1633,6 +2105,23 @@ pub trait StorageNonBlocking: Sized {
     /// authentication session. This call will only succeed if there is no
     /// current session.
     fn assume_session(&self, session: Session) -> Result<Self, bonsaidb_core::Error>;
+
+    /// Scales the number of workers that execute background tasks, such as
+    /// view indexing and compaction, to `worker_count`. The initial number of
+    /// workers is configured using
+    /// [`Tasks::worker_count`](crate::config::Tasks::worker_count).
+    ///
+    /// Reducing the number of workers limits the CPU used by background tasks,
+    /// at the expense of queries that need to wait for views to be updated.
+    /// When scaling down, workers exit after finishing the task they are
+    /// executing. A `worker_count` of 0 is treated as 1, as queries can't
+    /// complete without a worker to update their views.
+    fn set_tasks_worker_count(&self, worker_count: usize);
+
+    /// Returns the current status of the workers that execute background
+    /// tasks.
+    #[must_use]
+    fn tasks_status(&self) -> TasksStatus;
 }
 
 impl StorageNonBlocking for Storage {
@@ -1640,6 +2129,17 @@ impl StorageNonBlocking for Storage {
         &self.instance.data.path
     }
 
+    fn set_tasks_worker_count(&self, worker_count: usize) {
+        self.instance
+            .tasks()
+            .jobs
+            .set_worker_count(worker_count.max(1));
+    }
+
+    fn tasks_status(&self) -> TasksStatus {
+        self.instance.tasks().jobs.status()
+    }
+
     fn assume_session(&self, session: Session) -> Result<Storage, bonsaidb_core::Error> {
         if self.authentication.is_some() {
             // TODO better error
@@ -1781,16 +2281,18 @@ impl StorageLock {
 }
 
 #[derive(Debug)]
-struct LockData(File);
+struct LockData(Option<File>);
 
 impl StorageLock {
-    fn new(id: StorageId, file: File) -> Self {
+    fn new(id: StorageId, file: Option<File>) -> Self {
         Self(id, Arc::new(LockData(file)))
     }
 }
 
 impl Drop for LockData {
     fn drop(&mut self) {
-        drop(self.0.unlock());
+        if let Some(file) = &self.0 {
+            drop(file.unlock());
+        }
     }
 }
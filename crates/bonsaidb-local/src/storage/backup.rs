@@ -1,12 +1,16 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs::DirEntry;
-use std::io::ErrorKind;
+use std::io::{ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
 
+use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::connection::{LowLevelConnection, Range, Sort, StorageConnection};
 use bonsaidb_core::document::DocumentId;
-use bonsaidb_core::schema::{Collection, Qualified, SchemaName};
+use bonsaidb_core::schema::{Collection, CollectionName, Qualified, SchemaName};
 use bonsaidb_core::transaction::{Operation, Transaction};
 use bonsaidb_core::{admin, AnyError};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 
 use crate::database::keyvalue::Entry;
 use crate::database::DatabaseNonBlocking;
@@ -54,28 +58,57 @@ pub trait BackupLocation: Send + Sync {
 impl Storage {
     /// Stores a copy of all data in this instance to `location`.
     pub fn backup<L: AnyBackupLocation>(&self, location: &L) -> Result<(), Error> {
-        let databases = {
-            self.instance
-                .data
-                .available_databases
-                .read()
-                .keys()
-                .cloned()
-                .collect::<Vec<_>>()
+        for name in self.available_database_names() {
+            let database = self
+                .instance
+                .database_without_schema(&name, Some(self), None)?;
+            Self::backup_database(&database, location)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a copy of all data in this instance to `writer` as a single
+    /// archive, which can be restored using [`Storage::restore_from()`].
+    ///
+    /// The archive contains every database's documents and key-value entries,
+    /// along with the name of each database's schema and the collections it
+    /// contained. Views are not stored, as they are rebuilt from the restored
+    /// documents.
+    ///
+    /// Backups can be taken while the storage is in use. Writes are not
+    /// blocked, and each collection is captured as it existed at a single
+    /// point in time. Writes that span multiple collections may be only
+    /// partially reflected if they occur while the backup is in progress.
+    pub fn backup_to<W: Write + Send>(&self, writer: W) -> Result<(), Error> {
+        let archive = ArchiveWriter {
+            writer: Mutex::new(writer),
         };
+        archive.write_record(&ArchiveRecord::Header {
+            version: ARCHIVE_VERSION,
+        })?;
 
-        for name in databases {
+        for name in self.available_database_names() {
             let database = self
                 .instance
                 .database_without_schema(&name, Some(self), None)?;
-            Self::backup_database(&database, location)?;
+            archive.write_record(&ArchiveRecord::Database {
+                schema: database.schematic().name.clone(),
+                name,
+                collections: database.schematic().collections().cloned().collect(),
+            })?;
+            Self::backup_database(&database, &archive)?;
         }
 
+        archive.write_record(&ArchiveRecord::End)?;
+        archive.writer.into_inner().flush()?;
+
         Ok(())
     }
 
     /// Restores all data from a previously stored backup `location`.
     pub fn restore<L: AnyBackupLocation>(&self, location: &L) -> Result<(), Error> {
+        self.instance.check_writable()?;
         for schema in location
             .list_schemas()
             .map_err(|err| Error::Backup(Box::new(err)))?
@@ -97,6 +130,30 @@ impl Storage {
         Ok(())
     }
 
+    /// Restores all data from an archive previously written by
+    /// [`Storage::backup_to()`].
+    ///
+    /// Before any data is restored, the archive's schema metadata is checked
+    /// against this instance. An error is returned if a schema in the archive
+    /// isn't registered, or if a collection in the archive isn't part of its
+    /// database's schema. Restoring is intended to be done into a freshly
+    /// created storage; restoring documents that already exist will fail.
+    pub fn restore_from<R: Read>(&self, reader: R) -> Result<(), Error> {
+        let archive = ArchiveContents::read(reader)?;
+        archive.validate(self)?;
+        self.restore(&archive)
+    }
+
+    fn available_database_names(&self) -> Vec<String> {
+        self.instance
+            .data
+            .available_databases
+            .read()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
     pub(crate) fn backup_database(
         database: &Database,
         location: &dyn AnyBackupLocation,
@@ -263,6 +320,256 @@ where
     }
 }
 
+/// The version of the archive format written by [`Storage::backup_to()`].
+const ARCHIVE_VERSION: u32 = 0;
+
+/// A record within a backup archive. Each record is written as a
+/// little-endian `u64` length followed by the `pot`-encoded record.
+///
+/// An archive begins with a `Header`, and ends with an `End` record. Each
+/// `Object` belongs to the most recently written `Database`.
+#[derive(Serialize, Deserialize, Debug)]
+enum ArchiveRecord {
+    Header {
+        version: u32,
+    },
+    Database {
+        schema: SchemaName,
+        name: String,
+        collections: Vec<CollectionName>,
+    },
+    Object {
+        container: String,
+        name: String,
+        contents: Bytes,
+    },
+    End,
+}
+
+fn archive_error(message: impl std::fmt::Display) -> Error {
+    Error::other("backup archive", message)
+}
+
+struct ArchiveWriter<W> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write> ArchiveWriter<W> {
+    fn write_record(&self, record: &ArchiveRecord) -> Result<(), Error> {
+        let bytes = pot::to_vec(record)?;
+        let mut writer = self.writer.lock();
+        writer.write_all(&(bytes.len() as u64).to_le_bytes())?;
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+impl<W: Write + Send> AnyBackupLocation for ArchiveWriter<W> {
+    fn store(
+        &self,
+        _schema: &SchemaName,
+        _database_name: &str,
+        container: &str,
+        name: &str,
+        object: &[u8],
+    ) -> Result<(), Error> {
+        self.write_record(&ArchiveRecord::Object {
+            container: container.to_string(),
+            name: name.to_string(),
+            contents: Bytes::from(object.to_vec()),
+        })
+    }
+
+    fn list_schemas(&self) -> Result<Vec<SchemaName>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn list_databases(&self, _schema: &SchemaName) -> Result<Vec<String>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn list_stored(
+        &self,
+        _schema: &SchemaName,
+        _database_name: &str,
+        _container: &str,
+    ) -> Result<Vec<String>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn load(
+        &self,
+        _schema: &SchemaName,
+        _database_name: &str,
+        _container: &str,
+        _name: &str,
+    ) -> Result<Vec<u8>, Error> {
+        Err(archive_error("archives being written cannot be read from"))
+    }
+}
+
+#[derive(Default)]
+struct ArchiveContents {
+    databases: Vec<ArchivedDatabase>,
+}
+
+struct ArchivedDatabase {
+    schema: SchemaName,
+    name: String,
+    collections: Vec<CollectionName>,
+    containers: HashMap<String, BTreeMap<String, Vec<u8>>>,
+}
+
+impl ArchiveContents {
+    fn read<R: Read>(mut reader: R) -> Result<Self, Error> {
+        match read_record(&mut reader)? {
+            ArchiveRecord::Header {
+                version: ARCHIVE_VERSION,
+            } => {}
+            ArchiveRecord::Header { version } => {
+                return Err(archive_error(format!(
+                    "unsupported archive version {version}"
+                )))
+            }
+            _ => return Err(archive_error("missing archive header")),
+        }
+
+        let mut contents = Self::default();
+        loop {
+            match read_record(&mut reader)? {
+                ArchiveRecord::Database {
+                    schema,
+                    name,
+                    collections,
+                } => contents.databases.push(ArchivedDatabase {
+                    schema,
+                    name,
+                    collections,
+                    containers: HashMap::new(),
+                }),
+                ArchiveRecord::Object {
+                    container,
+                    name,
+                    contents: object,
+                } => {
+                    let database = contents
+                        .databases
+                        .last_mut()
+                        .ok_or_else(|| archive_error("object found before any database"))?;
+                    database
+                        .containers
+                        .entry(container)
+                        .or_default()
+                        .insert(name, object.into_vec());
+                }
+                ArchiveRecord::End => return Ok(contents),
+                ArchiveRecord::Header { .. } => {
+                    return Err(archive_error("unexpected archive header"))
+                }
+            }
+        }
+    }
+
+    fn validate(&self, storage: &Storage) -> Result<(), Error> {
+        let schemas = storage.instance.data.schemas.read();
+        for database in &self.databases {
+            let schematic = schemas
+                .get(&database.schema)
+                .ok_or_else(|| bonsaidb_core::Error::SchemaNotRegistered(database.schema.clone()))?
+                .schematic();
+            if let Some(collection) = database
+                .collections
+                .iter()
+                .find(|collection| !schematic.collections().any(|c| c == *collection))
+            {
+                return Err(archive_error(format!(
+                    "collection {collection} in database {} is not part of schema {}",
+                    database.name, database.schema
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn database(&self, schema: &SchemaName, database_name: &str) -> Option<&ArchivedDatabase> {
+        self.databases
+            .iter()
+            .find(|database| &database.schema == schema && database.name == database_name)
+    }
+}
+
+fn read_record<R: Read>(reader: &mut R) -> Result<ArchiveRecord, Error> {
+    let mut length = [0; 8];
+    reader.read_exact(&mut length)?;
+    let length = usize::try_from(u64::from_le_bytes(length))
+        .map_err(|_| archive_error("archive record is too large"))?;
+    let mut bytes = vec![0; length];
+    reader.read_exact(&mut bytes)?;
+    Ok(pot::from_slice(&bytes)?)
+}
+
+impl AnyBackupLocation for ArchiveContents {
+    fn store(
+        &self,
+        _schema: &SchemaName,
+        _database_name: &str,
+        _container: &str,
+        _name: &str,
+        _object: &[u8],
+    ) -> Result<(), Error> {
+        Err(archive_error(
+            "archives being restored cannot be written to",
+        ))
+    }
+
+    fn list_schemas(&self) -> Result<Vec<SchemaName>, Error> {
+        let mut schemas = Vec::new();
+        for database in &self.databases {
+            if !schemas.contains(&database.schema) {
+                schemas.push(database.schema.clone());
+            }
+        }
+        Ok(schemas)
+    }
+
+    fn list_databases(&self, schema: &SchemaName) -> Result<Vec<String>, Error> {
+        Ok(self
+            .databases
+            .iter()
+            .filter(|database| &database.schema == schema)
+            .map(|database| database.name.clone())
+            .collect())
+    }
+
+    fn list_stored(
+        &self,
+        schema: &SchemaName,
+        database_name: &str,
+        container: &str,
+    ) -> Result<Vec<String>, Error> {
+        Ok(self
+            .database(schema, database_name)
+            .and_then(|database| database.containers.get(container))
+            .map(|objects| objects.keys().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    fn load(
+        &self,
+        schema: &SchemaName,
+        database_name: &str,
+        container: &str,
+        name: &str,
+    ) -> Result<Vec<u8>, Error> {
+        self.database(schema, database_name)
+            .and_then(|database| database.containers.get(container))
+            .and_then(|objects| objects.get(name))
+            .cloned()
+            .ok_or_else(|| archive_error(format!("{container}/{name} not found in archive")))
+    }
+}
+
 impl BackupLocation for Path {
     type Error = std::io::Error;
 
@@ -447,7 +754,7 @@ mod tests {
     use bonsaidb_core::test_util::{Basic, TestDirectory};
 
     use crate::config::{Builder, KeyValuePersistence, PersistenceThreshold, StorageConfiguration};
-    use crate::Storage;
+    use crate::{Error, Storage};
 
     #[test]
     fn backup_restore() -> anyhow::Result<()> {
@@ -496,4 +803,49 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn archive_backup_restore() -> anyhow::Result<()> {
+        let mut archive = Vec::new();
+        let test_doc = {
+            let database_directory = TestDirectory::new("archive-backup-restore.bonsaidb");
+            let storage = Storage::open(
+                StorageConfiguration::new(&database_directory).with_schema::<Basic>()?,
+            )?;
+
+            let db = storage.create_database::<Basic>("basic", false)?;
+            let test_doc = db.collection::<Basic>().push(&Basic::new("somevalue"))?;
+            db.set_numeric_key("key1", 1_u64).execute()?;
+
+            storage.backup_to(&mut archive)?;
+
+            test_doc
+        };
+
+        // Restoring into a storage that doesn't have the schema registered
+        // should fail without creating any databases.
+        let database_directory = TestDirectory::new("archive-backup-restore-invalid.bonsaidb");
+        let incompatible_storage = Storage::open(StorageConfiguration::new(&database_directory))?;
+        assert!(matches!(
+            incompatible_storage.restore_from(archive.as_slice()),
+            Err(Error::Core(bonsaidb_core::Error::SchemaNotRegistered(_)))
+        ));
+        assert!(incompatible_storage.database::<Basic>("basic").is_err());
+
+        // A truncated archive should be rejected.
+        let database_directory = TestDirectory::new("archive-backup-restore.bonsaidb");
+        let restored_storage =
+            Storage::open(StorageConfiguration::new(&database_directory).with_schema::<Basic>()?)?;
+        assert!(restored_storage
+            .restore_from(&archive[..archive.len() - 1])
+            .is_err());
+
+        restored_storage.restore_from(archive.as_slice())?;
+        let db = restored_storage.database::<Basic>("basic")?;
+        let doc = Basic::get(&test_doc.id, &db)?.expect("Backed up document.not found");
+        assert_eq!(doc.contents.value, "somevalue");
+        assert_eq!(db.get_key("key1").into_u64()?, Some(1));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,165 @@
+use std::fmt::Display;
+
+use bonsaidb_core::schema::ViewName;
+
+use crate::config::IntegrityCheck;
+use crate::{Error, Storage};
+
+/// The results of verifying the views of a [`Storage`]. Returned from
+/// [`Storage::verify()`].
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct IntegrityReport {
+    /// The results of verifying each view, ordered by database name.
+    pub views: Vec<ViewIntegrity>,
+}
+
+impl IntegrityReport {
+    /// Returns true if every view verified is consistent.
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.views.iter().all(ViewIntegrity::is_consistent)
+    }
+
+    /// Returns an iterator over the views that are inconsistent.
+    pub fn inconsistencies(&self) -> impl Iterator<Item = &ViewIntegrity> {
+        self.views.iter().filter(|view| !view.is_consistent())
+    }
+}
+
+/// The results of verifying a single view.
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ViewIntegrity {
+    /// The name of the database containing the view.
+    pub database: String,
+    /// The name of the view.
+    pub view: ViewName,
+    /// True if the view's data was stored by a different version of the view,
+    /// or if the view has never been updated. Outdated views are rebuilt
+    /// before they are next queried, so they aren't considered inconsistent.
+    /// When true, no other checks are performed.
+    pub outdated: bool,
+    /// The number of documents that haven't been mapped by the view and aren't
+    /// waiting to be mapped.
+    pub unmapped_documents: usize,
+    /// The number of deleted documents that still have entries in the view
+    /// and aren't waiting to be removed from it.
+    pub stale_documents: usize,
+    /// The number of view entry mappings that don't match the keys recorded
+    /// for the documents they were produced from. This is only checked by
+    /// [`IntegrityCheck::Full`], and is `None` otherwise.
+    pub inconsistent_entries: Option<usize>,
+    /// True if the view was rebuilt because it was inconsistent.
+    pub repaired: bool,
+}
+
+impl ViewIntegrity {
+    pub(crate) fn new(database: String, view: ViewName, full: bool) -> Self {
+        Self {
+            database,
+            view,
+            outdated: false,
+            unmapped_documents: 0,
+            stale_documents: 0,
+            inconsistent_entries: full.then_some(0),
+            repaired: false,
+        }
+    }
+
+    /// Returns true if no inconsistencies were found.
+    #[must_use]
+    pub fn is_consistent(&self) -> bool {
+        self.unmapped_documents == 0
+            && self.stale_documents == 0
+            && self.inconsistent_entries.unwrap_or_default() == 0
+    }
+}
+
+impl Display for ViewIntegrity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "view {} in database {}", self.view, self.database)?;
+        if self.outdated {
+            f.write_str(" is outdated")?;
+        } else {
+            write!(
+                f,
+                " has {} unmapped documents, {} stale documents",
+                self.unmapped_documents, self.stale_documents
+            )?;
+            if let Some(inconsistent_entries) = self.inconsistent_entries {
+                write!(f, ", {inconsistent_entries} inconsistent entries")?;
+            }
+        }
+        if self.repaired {
+            f.write_str(" (repaired)")?;
+        }
+        Ok(())
+    }
+}
+
+impl Storage {
+    /// Verifies that every view in this storage is consistent with the
+    /// documents it indexes, performing the checks described by
+    /// [`IntegrityCheck::Full`]. Inconsistencies are reported, but not
+    /// repaired. Inconsistent views can be repaired using
+    /// [`Database::rebuild_view()`](crate::Database::rebuild_view).
+    ///
+    /// Each view is verified using a background job. Documents waiting to be
+    /// mapped are not considered inconsistent, but views that are updated
+    /// while being verified may be reported as inconsistent. Databases whose
+    /// schema isn't registered with this storage are skipped.
+    pub fn verify(&self) -> Result<IntegrityReport, Error> {
+        self.check_integrity(IntegrityCheck::Full, false)
+    }
+
+    pub(crate) fn check_integrity(
+        &self,
+        check: IntegrityCheck,
+        repair: bool,
+    ) -> Result<IntegrityReport, Error> {
+        let full = match check {
+            IntegrityCheck::None => return Ok(IntegrityReport::default()),
+            IntegrityCheck::Quick => false,
+            IntegrityCheck::Full => true,
+        };
+
+        let mut names = self
+            .instance
+            .data
+            .available_databases
+            .read()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+        names.sort();
+
+        let tasks = self.instance.tasks();
+        let mut report = IntegrityReport::default();
+        for name in names {
+            let database = match self
+                .instance
+                .database_without_schema(&name, Some(self), None)
+            {
+                Ok(database) => database,
+                Err(Error::Core(bonsaidb_core::Error::SchemaNotRegistered(_))) => continue,
+                Err(err) => return Err(err),
+            };
+            let verifications = database
+                .data
+                .schema
+                .views()
+                .map(|view| (view, tasks.verify_view(view, &database, full)))
+                .collect::<Vec<_>>();
+            for (view, verification) in verifications {
+                let mut integrity = verification.receive()??;
+                if repair && !integrity.is_consistent() {
+                    tasks.repair_view(view, &database, &integrity).receive()??;
+                    integrity.repaired = true;
+                }
+                report.views.push(integrity);
+            }
+        }
+
+        Ok(report)
+    }
+}
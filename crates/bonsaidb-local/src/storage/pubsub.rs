@@ -1,20 +1,187 @@
 use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Instant;
 
+use bonsaidb_core::arc_bytes::OwnedBytes;
+use bonsaidb_core::circulate::{flume, Message};
 use bonsaidb_core::connection::SessionId;
-use bonsaidb_core::pubsub::Receiver;
+use bonsaidb_core::pubsub::{self, database_topic, BufferLimit, MessageSender, PublishReceipt};
 
-use crate::storage::SessionSubscriber;
+use crate::config::PubSubRetention;
+use crate::storage::{SessionSubscriber, SessionSubscribers};
 use crate::{Database, Subscriber};
 
+/// The messages retained for a single topic, oldest first.
+#[derive(Debug, Default)]
+pub struct RetainedMessages(VecDeque<(Instant, Message)>);
+
+impl RetainedMessages {
+    fn push(&mut self, message: Message, retention: PubSubRetention) {
+        self.0.push_back((Instant::now(), message));
+        self.prune(retention);
+    }
+
+    fn prune(&mut self, retention: PubSubRetention) {
+        match retention {
+            PubSubRetention::Count(count) => {
+                while self.0.len() > count {
+                    self.0.pop_front();
+                }
+            }
+            PubSubRetention::Duration(duration) => {
+                while self
+                    .0
+                    .front()
+                    .map_or(false, |(published_at, _)| published_at.elapsed() > duration)
+                {
+                    self.0.pop_front();
+                }
+            }
+        }
+    }
+}
+
+/// A trie of namespaced topic prefixes, used to find every subscriber with a
+/// prefix subscription matching a published topic without checking each
+/// subscription.
+#[derive(Debug, Default)]
+pub struct TopicTrie {
+    subscribers: HashSet<u64>,
+    children: HashMap<u8, TopicTrie>,
+}
+
+impl TopicTrie {
+    pub fn is_empty(&self) -> bool {
+        self.subscribers.is_empty() && self.children.is_empty()
+    }
+
+    pub fn insert(&mut self, prefix: &[u8], subscriber_id: u64) {
+        let mut node = self;
+        for byte in prefix {
+            node = node.children.entry(*byte).or_default();
+        }
+        node.subscribers.insert(subscriber_id);
+    }
+
+    pub fn remove(&mut self, prefix: &[u8], subscriber_id: u64) {
+        if let Some((first, remaining)) = prefix.split_first() {
+            if let Some(child) = self.children.get_mut(first) {
+                child.remove(remaining, subscriber_id);
+                if child.is_empty() {
+                    self.children.remove(first);
+                }
+            }
+        } else {
+            self.subscribers.remove(&subscriber_id);
+        }
+    }
+
+    /// Adds the id of every subscriber subscribed to a prefix of `key` to
+    /// `matches`. A subscriber is only added once, even if it is subscribed to
+    /// multiple matching prefixes.
+    pub fn collect_matches(&self, key: &[u8], matches: &mut HashSet<u64>) {
+        let mut node = self;
+        matches.extend(&node.subscribers);
+        for byte in key {
+            let Some(child) = node.children.get(byte) else {
+                return;
+            };
+            node = child;
+            matches.extend(&node.subscribers);
+        }
+    }
+}
+
+/// Returns the namespaced key that prefix subscriptions are matched against.
+/// String topics are matched using the string's bytes, while all other topics
+/// are matched using their serialized bytes.
+fn prefix_match_key(database: &str, topic: &[u8]) -> Vec<u8> {
+    match pot::from_slice::<String>(topic) {
+        Ok(topic) => database_topic(database, topic.as_bytes()),
+        Err(_) => database_topic(database, topic),
+    }
+}
+
+/// Returns the ids of every subscriber subscribed to `topic` directly or to a
+/// matching prefix. A subscriber is only returned once, even if it is
+/// subscribed to the topic directly and to matching prefixes.
+fn matching_subscribers(
+    subscribers: &SessionSubscribers,
+    database: &str,
+    topic: &[u8],
+    namespaced_topic: &[u8],
+) -> HashSet<u64> {
+    let mut matches = subscribers
+        .topics
+        .get(namespaced_topic)
+        .cloned()
+        .unwrap_or_default();
+    if !subscribers.prefixes.is_empty() {
+        subscribers
+            .prefixes
+            .collect_matches(&prefix_match_key(database, topic), &mut matches);
+    }
+    matches
+}
+
+/// Messages that couldn't be delivered without waiting for a subscriber
+/// whose buffer is full and applies backpressure, along with the number of
+/// messages that were delivered without waiting.
+#[derive(Debug, Default)]
+#[must_use]
+pub struct BackpressuredMessages {
+    delivered: usize,
+    messages: Vec<(MessageSender, Message)>,
+    batches: Vec<(MessageSender, Vec<Message>)>,
+}
+
+impl BackpressuredMessages {
+    /// Delivers each message, blocking the current thread until each
+    /// subscriber has room.
+    pub fn wait(self) -> PublishReceipt {
+        let mut delivered_to = self.delivered;
+        for (sender, message) in self.messages {
+            // Subscribers that have been dropped no longer need the message.
+            if sender.send(message).is_ok() {
+                delivered_to += 1;
+            }
+        }
+        for (sender, messages) in self.batches {
+            let count = messages.len();
+            if sender.send_batch(messages).is_ok() {
+                delivered_to += count;
+            }
+        }
+        PublishReceipt { delivered_to }
+    }
+
+    /// Delivers each message, waiting until each subscriber has room.
+    pub async fn wait_async(self) -> PublishReceipt {
+        let mut delivered_to = self.delivered;
+        for (sender, message) in self.messages {
+            if sender.send_async(message).await.is_ok() {
+                delivered_to += 1;
+            }
+        }
+        for (sender, messages) in self.batches {
+            let count = messages.len();
+            if sender.send_batch_async(messages).await.is_ok() {
+                delivered_to += count;
+            }
+        }
+        PublishReceipt { delivered_to }
+    }
+}
+
 impl crate::storage::StorageInstance {
     pub(crate) fn register_subscriber(
         &self,
         session_id: Option<SessionId>,
         database: Database,
+        limit: Option<BufferLimit>,
     ) -> Subscriber {
-        let subscriber = self.relay().create_subscriber();
+        let (sender, receiver) = pubsub::channel(limit, true);
         let mut data = self.data.subscribers.write();
-        let receiver = Receiver::new_stripping_prefixes(subscriber.receiver().clone());
         let id = loop {
             data.last_id = data.last_id.wrapping_add(1);
             let id = data.last_id;
@@ -22,7 +189,10 @@ impl crate::storage::StorageInstance {
             if matches!(entry, Entry::Vacant(_)) {
                 entry.or_insert(SessionSubscriber {
                     session_id,
-                    subscriber: subscriber.clone(),
+                    database: database.clone(),
+                    sender: sender.clone(),
+                    topics: HashSet::new(),
+                    prefixes: HashSet::new(),
                 });
                 break id;
             }
@@ -31,7 +201,6 @@ impl crate::storage::StorageInstance {
         Subscriber {
             id,
             database,
-            subscriber,
             receiver,
         }
     }
@@ -40,4 +209,273 @@ impl crate::storage::StorageInstance {
         let mut data = self.data.subscribers.write();
         data.unregister(subscriber.id);
     }
+
+    /// Returns an error if `payload` is larger than the maximum payload size.
+    pub(crate) fn check_payload_size(&self, payload: &[u8]) -> Result<(), bonsaidb_core::Error> {
+        match self.data.pubsub_max_payload_size {
+            Some(maximum) if payload.len() > maximum => {
+                Err(bonsaidb_core::Error::MessageTooLarge {
+                    size: payload.len() as u64,
+                    maximum: maximum as u64,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Publishes a message generated by BonsaiDb to `topic` within
+    /// `database`, delivering it only to subscribers whose database `allowed`
+    /// returns true for.
+    ///
+    /// Unlike messages published by clients, these messages are never
+    /// retained and publishing never waits: if a subscriber's buffer is full
+    /// and applies backpressure, the message is dropped and counted as lagged.
+    /// `payload` is only invoked if at least one subscriber will receive the
+    /// message.
+    pub(crate) fn publish_internal(
+        &self,
+        database: &str,
+        topic: &[u8],
+        mut allowed: impl FnMut(&Database) -> bool,
+        payload: impl FnOnce() -> Result<Vec<u8>, bonsaidb_core::Error>,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let namespaced_topic = database_topic(database, topic);
+        let subscribers = self.data.subscribers.read();
+        let recipients = matching_subscribers(&subscribers, database, topic, &namespaced_topic)
+            .into_iter()
+            .filter_map(|subscriber_id| subscribers.subscribers.get(&subscriber_id))
+            .filter(|subscriber| allowed(&subscriber.database))
+            .collect::<Vec<_>>();
+        if recipients.is_empty() {
+            return Ok(());
+        }
+
+        let message = Message {
+            topic: OwnedBytes::from(namespaced_topic),
+            payload: OwnedBytes::from(payload()?),
+        };
+        for subscriber in recipients {
+            if let Err(flume::TrySendError::Full(_)) = subscriber.sender.try_send(message.clone()) {
+                subscriber.sender.add_lagged(1);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Publishes `payload` to all `topics` within `database`, retaining the
+    /// message for each topic that has a retention policy.
+    ///
+    /// Messages are delivered to every subscriber that has room without
+    /// waiting. The messages for subscribers whose buffers are full and apply
+    /// backpressure are returned, and must be waited on by the caller after
+    /// this function returns.
+    pub(crate) fn publish_to_all(
+        &self,
+        database: &str,
+        topics: Vec<Vec<u8>>,
+        payload: Vec<u8>,
+    ) -> BackpressuredMessages {
+        let payload = OwnedBytes::from(payload);
+        // The retained messages lock is held while publishing to ensure
+        // subscribers replaying a topic never receive a message twice or out
+        // of order.
+        let mut retained = if topics
+            .iter()
+            .any(|topic| self.data.pubsub_retention.contains_key(topic))
+        {
+            Some(self.data.retained_messages.lock())
+        } else {
+            None
+        };
+        let subscribers = self.data.subscribers.read();
+        let mut backpressured = BackpressuredMessages::default();
+        // Once a message to a subscriber is backpressured, all following
+        // messages to that subscriber must be too, to preserve their order.
+        let mut backpressured_subscribers = HashSet::new();
+        for topic in topics {
+            let namespaced_topic = database_topic(database, &topic);
+            let message = Message {
+                topic: OwnedBytes::from(namespaced_topic.clone()),
+                payload: payload.clone(),
+            };
+            if let Some(retained) = &mut retained {
+                if let Some(retention) = self.data.pubsub_retention.get(&topic) {
+                    retained
+                        .entry(namespaced_topic.clone())
+                        .or_default()
+                        .push(message.clone(), *retention);
+                }
+            }
+
+            for subscriber_id in
+                matching_subscribers(&subscribers, database, &topic, &namespaced_topic)
+            {
+                let Some(subscriber) = subscribers.subscribers.get(&subscriber_id) else {
+                    continue;
+                };
+                if backpressured_subscribers.contains(&subscriber_id) {
+                    backpressured
+                        .messages
+                        .push((subscriber.sender.clone(), message.clone()));
+                } else {
+                    match subscriber.sender.try_send(message.clone()) {
+                        Ok(()) => backpressured.delivered += 1,
+                        Err(flume::TrySendError::Full(message)) => {
+                            backpressured_subscribers.insert(subscriber_id);
+                            backpressured
+                                .messages
+                                .push((subscriber.sender.clone(), message));
+                        }
+                        Err(flume::TrySendError::Disconnected(_)) => {}
+                    }
+                }
+            }
+        }
+
+        backpressured
+    }
+
+    /// Publishes each `(topic, payload)` pair in `messages` within `database`
+    /// as a single batch, retaining each message whose topic has a retention
+    /// policy.
+    ///
+    /// The messages matching each subscriber are sent to it as one batch, so
+    /// that they are received contiguously. See [`Self::publish_to_all()`]
+    /// for how backpressure is handled.
+    pub(crate) fn publish_batch(
+        &self,
+        database: &str,
+        messages: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> BackpressuredMessages {
+        let mut retained = if messages
+            .iter()
+            .any(|(topic, _)| self.data.pubsub_retention.contains_key(topic))
+        {
+            Some(self.data.retained_messages.lock())
+        } else {
+            None
+        };
+        let subscribers = self.data.subscribers.read();
+        let mut batches = HashMap::<u64, Vec<Message>>::new();
+        // The order subscribers are first matched in, to deliver the batches
+        // deterministically.
+        let mut matched_order = Vec::new();
+        for (topic, payload) in messages {
+            let namespaced_topic = database_topic(database, &topic);
+            let message = Message {
+                topic: OwnedBytes::from(namespaced_topic.clone()),
+                payload: OwnedBytes::from(payload),
+            };
+            if let Some(retained) = &mut retained {
+                if let Some(retention) = self.data.pubsub_retention.get(&topic) {
+                    retained
+                        .entry(namespaced_topic.clone())
+                        .or_default()
+                        .push(message.clone(), *retention);
+                }
+            }
+
+            for subscriber_id in
+                matching_subscribers(&subscribers, database, &topic, &namespaced_topic)
+            {
+                let batch = batches.entry(subscriber_id).or_insert_with(|| {
+                    matched_order.push(subscriber_id);
+                    Vec::new()
+                });
+                batch.push(message.clone());
+            }
+        }
+
+        let mut backpressured = BackpressuredMessages::default();
+        for subscriber_id in matched_order {
+            let (Some(subscriber), Some(batch)) = (
+                subscribers.subscribers.get(&subscriber_id),
+                batches.remove(&subscriber_id),
+            ) else {
+                continue;
+            };
+            let count = batch.len();
+            match subscriber.sender.try_send_batch(batch) {
+                Ok(()) => backpressured.delivered += count,
+                Err(flume::TrySendError::Full(batch)) => {
+                    backpressured
+                        .batches
+                        .push((subscriber.sender.clone(), batch));
+                }
+                Err(flume::TrySendError::Disconnected(_)) => {}
+            }
+        }
+
+        backpressured
+    }
+
+    /// Subscribes `subscriber` to `topic`.
+    pub(crate) fn subscribe(&self, subscriber: &Subscriber, topic: &[u8]) {
+        let namespaced_topic = database_topic(subscriber.database.name(), topic);
+        let mut data = self.data.subscribers.write();
+        if let Some(session_subscriber) = data.subscribers.get_mut(&subscriber.id) {
+            if session_subscriber.topics.insert(namespaced_topic.clone()) {
+                data.topics
+                    .entry(namespaced_topic)
+                    .or_default()
+                    .insert(subscriber.id);
+            }
+        }
+    }
+
+    /// Subscribes `subscriber` to `topic`, queueing all retained messages for
+    /// `topic` to be received before any newly published messages.
+    pub(crate) fn subscribe_with_replay(&self, subscriber: &Subscriber, topic: &[u8]) {
+        let Some(retention) = self.data.pubsub_retention.get(topic) else {
+            self.subscribe(subscriber, topic);
+            return;
+        };
+
+        let mut retained = self.data.retained_messages.lock();
+        self.subscribe(subscriber, topic);
+        if let Some(messages) = retained.get_mut(&database_topic(subscriber.database.name(), topic))
+        {
+            messages.prune(*retention);
+            subscriber
+                .receiver
+                .deliver(messages.0.iter().map(|(_, message)| message.clone()));
+        }
+    }
+
+    /// Unsubscribes `subscriber` from `topic`.
+    pub(crate) fn unsubscribe(&self, subscriber: &Subscriber, topic: &[u8]) {
+        let namespaced_topic = database_topic(subscriber.database.name(), topic);
+        let mut data = self.data.subscribers.write();
+        if let Some(session_subscriber) = data.subscribers.get_mut(&subscriber.id) {
+            if session_subscriber.topics.remove(&namespaced_topic) {
+                data.remove_topic_subscriber(&namespaced_topic, subscriber.id);
+            }
+        }
+    }
+
+    /// Subscribes `subscriber` to all topics starting with `prefix`.
+    pub(crate) fn subscribe_to_prefix(&self, subscriber: &Subscriber, prefix: &[u8]) {
+        let namespaced_prefix = database_topic(subscriber.database.name(), prefix);
+        let mut data = self.data.subscribers.write();
+        if let Some(session_subscriber) = data.subscribers.get_mut(&subscriber.id) {
+            if session_subscriber
+                .prefixes
+                .insert(namespaced_prefix.clone())
+            {
+                data.prefixes.insert(&namespaced_prefix, subscriber.id);
+            }
+        }
+    }
+
+    /// Unsubscribes `subscriber` from topics starting with `prefix`.
+    pub(crate) fn unsubscribe_from_prefix(&self, subscriber: &Subscriber, prefix: &[u8]) {
+        let namespaced_prefix = database_topic(subscriber.database.name(), prefix);
+        let mut data = self.data.subscribers.write();
+        if let Some(session_subscriber) = data.subscribers.get_mut(&subscriber.id) {
+            if session_subscriber.prefixes.remove(&namespaced_prefix) {
+                data.prefixes.remove(&namespaced_prefix, subscriber.id);
+            }
+        }
+    }
 }
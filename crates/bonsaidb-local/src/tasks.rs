@@ -1,27 +1,35 @@
 use std::borrow::Cow;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::Duration;
 
 use bonsaidb_core::connection::Connection;
 use bonsaidb_core::keyvalue::Timestamp;
 use bonsaidb_core::schema::{view, CollectionName, ViewName};
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 
+use crate::database::expiration::DocumentReaper;
 use crate::database::keyvalue::ExpirationLoader;
+use crate::database::triggers::{TriggerKey, TriggerRunner};
 use crate::database::Database;
+use crate::storage::ViewIntegrity;
 use crate::tasks::compactor::Compactor;
 use crate::tasks::handle::Handle;
-use crate::tasks::manager::Manager;
+use crate::tasks::manager::{Manager, Priority};
 use crate::views::integrity_scanner::{IntegrityScan, IntegrityScanner, OptionalViewMapHandle};
 use crate::views::mapper::{Map, Mapper};
+use crate::views::rebuilder::ViewRebuilder;
+use crate::views::verifier::{Verification, ViewVerifier};
 use crate::Error;
 
+mod context;
 /// Types related to defining [`Job`]s.
 pub mod handle;
 /// Types related to the job [`Manager`](manager::Manager).
 pub mod manager;
 mod traits;
 
+pub use self::context::{JobContext, JobProgress};
 pub use self::traits::{Job, Keyed};
 
 mod compactor;
@@ -42,6 +50,9 @@ pub struct Statuses {
     completed_integrity_checks: HashSet<ViewKey>,
     key_value_expiration_loads: HashSet<Arc<Cow<'static, str>>>,
     view_update_last_status: HashMap<ViewKey, u64>,
+    trigger_locks: HashMap<TriggerKey, Arc<Mutex<()>>>,
+    trigger_databases_opened: HashSet<Arc<Cow<'static, str>>>,
+    expiring_databases: HashSet<Arc<Cow<'static, str>>>,
 }
 
 impl TaskManager {
@@ -58,6 +69,12 @@ impl TaskManager {
         database: &Database,
         block_until_updated: bool,
     ) -> Result<(), crate::Error> {
+        // Read-only storage can't update views, so queries use the index as it
+        // exists on disk.
+        if database.storage.instance.is_read_only() {
+            return Ok(());
+        }
+
         let view_name = view.view_name();
         if let Some(job) = self.spawn_integrity_check(view, database) {
             job.receive()??;
@@ -84,14 +101,17 @@ impl TaskManager {
             if needs_reindex {
                 let wait_for_transaction = current_transaction_id;
                 loop {
-                    let job = self.jobs.lookup_or_enqueue(Mapper {
-                        database: database.clone(),
-                        map: Map {
-                            database: database.data.name.clone(),
-                            collection: view.collection(),
-                            view_name: view_name.clone(),
+                    let job = self.jobs.lookup_or_enqueue_with_priority(
+                        Mapper {
+                            database: database.clone(),
+                            map: Map {
+                                database: database.data.name.clone(),
+                                collection: view.collection(),
+                                view_name: view_name.clone(),
+                            },
                         },
-                    });
+                        Priority::High,
+                    );
 
                     if !block_until_updated {
                         break;
@@ -108,6 +128,65 @@ impl TaskManager {
         Ok(())
     }
 
+    pub fn rebuild_view(
+        &self,
+        view: &dyn view::Serialized,
+        database: &Database,
+    ) -> Handle<(), Error> {
+        self.jobs.lookup_or_enqueue(ViewRebuilder {
+            database: database.clone(),
+            map: Map {
+                database: database.data.name.clone(),
+                collection: view.collection(),
+                view_name: view.view_name(),
+            },
+            reset_to_version: None,
+        })
+    }
+
+    pub fn verify_view(
+        &self,
+        view: &dyn view::Serialized,
+        database: &Database,
+        full: bool,
+    ) -> Handle<ViewIntegrity, Error> {
+        self.jobs.lookup_or_enqueue(ViewVerifier {
+            database: database.clone(),
+            verification: Verification {
+                map: Map {
+                    database: database.data.name.clone(),
+                    collection: view.collection(),
+                    view_name: view.view_name(),
+                },
+                view_version: view.version(),
+                full,
+            },
+        })
+    }
+
+    /// Rebuilds a view that failed verification. Views whose stored entries
+    /// can't be trusted are cleared before being rebuilt.
+    pub fn repair_view(
+        &self,
+        view: &dyn view::Serialized,
+        database: &Database,
+        integrity: &ViewIntegrity,
+    ) -> Handle<(), Error> {
+        let reset = integrity.inconsistent_entries.unwrap_or_default() > 0;
+        self.jobs.lookup_or_enqueue_with_priority(
+            ViewRebuilder {
+                database: database.clone(),
+                map: Map {
+                    database: database.data.name.clone(),
+                    collection: view.collection(),
+                    view_name: view.view_name(),
+                },
+                reset_to_version: reset.then(|| view.version()),
+            },
+            Priority::High,
+        )
+    }
+
     pub fn key_value_expiration_loaded(&self, database: &Arc<Cow<'static, str>>) -> bool {
         let statuses = self.statuses.read();
         statuses.key_value_expiration_loads.contains(database)
@@ -131,22 +210,27 @@ impl TaskManager {
         database: &Database,
     ) -> Option<Handle<OptionalViewMapHandle, Error>> {
         let view_name = view.view_name();
-        if self.view_integrity_checked(
-            database.data.name.clone(),
-            view.collection(),
-            view_name.clone(),
-        ) {
+        if database.storage.instance.is_read_only()
+            || self.view_integrity_checked(
+                database.data.name.clone(),
+                view.collection(),
+                view_name.clone(),
+            )
+        {
             None
         } else {
-            let job = self.jobs.lookup_or_enqueue(IntegrityScanner {
-                database: database.clone(),
-                scan: IntegrityScan {
-                    database: database.data.name.clone(),
-                    view_version: view.version(),
-                    collection: view.collection(),
-                    view_name,
+            let job = self.jobs.lookup_or_enqueue_with_priority(
+                IntegrityScanner {
+                    database: database.clone(),
+                    scan: IntegrityScan {
+                        database: database.data.name.clone(),
+                        view_version: view.version(),
+                        collection: view.collection(),
+                        view_name,
+                    },
                 },
-            });
+                Priority::High,
+            );
             Some(job)
         }
     }
@@ -185,7 +269,9 @@ impl TaskManager {
         &self,
         database: &Database,
     ) -> Option<Handle<(), Error>> {
-        if self.key_value_expiration_loaded(&database.data.name) {
+        if database.storage.instance.is_read_only()
+            || self.key_value_expiration_loaded(&database.data.name)
+        {
             None
         } else {
             Some(self.jobs.lookup_or_enqueue(ExpirationLoader {
@@ -195,13 +281,98 @@ impl TaskManager {
         }
     }
 
+    /// Enqueues the triggers registered for each of `collections` to deliver
+    /// any changes that they haven't processed.
+    pub fn spawn_triggers<'a>(
+        &self,
+        database: &Database,
+        collections: impl IntoIterator<Item = &'a CollectionName>,
+    ) {
+        // Bursts of transactions only need to spawn each trigger once.
+        const TRIGGER_DEBOUNCE: Duration = Duration::from_millis(100);
+
+        if database.storage.instance.is_read_only() {
+            return;
+        }
+
+        for collection in collections {
+            for trigger in database.data.schema.triggers_in_collection(collection) {
+                let key = TriggerKey {
+                    database: database.data.name.clone(),
+                    collection: collection.clone(),
+                    name: trigger.name(),
+                };
+                let lock = self
+                    .statuses
+                    .write()
+                    .trigger_locks
+                    .entry(key.clone())
+                    .or_default()
+                    .clone();
+                drop(
+                    self.jobs.enqueue_debounced(
+                        TriggerRunner::new(database, key, lock),
+                        TRIGGER_DEBOUNCE,
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Enqueues every trigger in `database` the first time it's opened, which
+    /// delivers any changes that weren't delivered before the storage was
+    /// last closed.
+    pub fn spawn_triggers_on_open(&self, database: &Database) {
+        let first_open = self
+            .statuses
+            .write()
+            .trigger_databases_opened
+            .insert(database.data.name.clone());
+        if first_open {
+            self.spawn_triggers(database, database.data.schema.collections());
+        }
+    }
+
+    /// Records that `database` has been opened and contains collections that
+    /// expire documents. The storage periodically spawns a
+    /// [`DocumentReaper`] for each registered database.
+    pub fn register_expiring_database(&self, database: &Database) {
+        if database.storage.instance.is_read_only()
+            || database.data.schema.expiring_collections().next().is_none()
+        {
+            return;
+        }
+
+        self.statuses
+            .write()
+            .expiring_databases
+            .insert(database.data.name.clone());
+    }
+
+    /// Returns the names of the databases registered using
+    /// [`Self::register_expiring_database()`].
+    pub fn expiring_databases(&self) -> Vec<Arc<Cow<'static, str>>> {
+        let statuses = self.statuses.read();
+        statuses.expiring_databases.iter().cloned().collect()
+    }
+
+    /// Enqueues a job that deletes the expired documents in `database`.
+    pub fn spawn_document_reaper(&self, database: &Database) -> Handle<(), Error> {
+        self.jobs.lookup_or_enqueue_with_priority(
+            DocumentReaper {
+                database: database.clone(),
+            },
+            Priority::Low,
+        )
+    }
+
     pub fn spawn_compact_target(
         &self,
         database: Database,
         target: compactor::Target,
     ) -> Handle<(), Error> {
         self.jobs
-            .lookup_or_enqueue(Compactor::target(database, target))
+            .lookup_or_enqueue_with_priority(Compactor::target(database, target), Priority::Low)
     }
 
     pub fn compact_collection(
@@ -211,21 +382,24 @@ impl TaskManager {
     ) -> Result<(), Error> {
         Ok(self
             .jobs
-            .lookup_or_enqueue(Compactor::collection(database, collection_name))
+            .lookup_or_enqueue_with_priority(
+                Compactor::collection(database, collection_name),
+                Priority::Low,
+            )
             .receive()??)
     }
 
     pub fn compact_key_value_store(&self, database: Database) -> Result<(), Error> {
         Ok(self
             .jobs
-            .lookup_or_enqueue(Compactor::keyvalue(database))
+            .lookup_or_enqueue_with_priority(Compactor::keyvalue(database), Priority::Low)
             .receive()??)
     }
 
     pub fn compact_database(&self, database: Database) -> Result<(), Error> {
         Ok(self
             .jobs
-            .lookup_or_enqueue(Compactor::database(database))
+            .lookup_or_enqueue_with_priority(Compactor::database(database), Priority::Low)
             .receive()??)
     }
 }
@@ -5,8 +5,11 @@ use bonsaidb_core::schema::CollectionName;
 use nebari::tree::{Root, Unversioned, Versioned};
 
 use crate::database::keyvalue::KEY_TREE;
-use crate::database::{document_tree_name, DatabaseNonBlocking};
-use crate::tasks::{Job, Keyed, Task};
+use crate::database::{
+    deleted_document_tree_name, document_changes_tree_name, document_expirations_tree_name,
+    document_tree_name, DatabaseNonBlocking,
+};
+use crate::tasks::{Job, JobContext, Keyed, Task};
 use crate::views::{
     view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
     view_versions_tree_name,
@@ -86,7 +89,7 @@ impl Job for Compactor {
     type Output = ();
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
-    fn execute(&mut self) -> Result<Self::Output, Error> {
+    fn execute(&mut self, _context: &JobContext) -> Result<Self::Output, Error> {
         self.compaction.target.clone().compact(&self.database)
     }
 }
@@ -103,6 +106,32 @@ fn gather_collection_trees(
     trees: &mut Vec<Target>,
 ) {
     trees.push(Target::VersionedTree(document_tree_name(collection)));
+    if database.data.schema.collection_uses_soft_delete(collection) {
+        trees.push(Target::UnversionedTree(deleted_document_tree_name(
+            collection,
+        )));
+    }
+    if database
+        .data
+        .schema
+        .collection_expiration(collection)
+        .is_some()
+    {
+        trees.push(Target::UnversionedTree(document_expirations_tree_name(
+            collection,
+        )));
+    }
+    if database
+        .data
+        .schema
+        .triggers_in_collection(collection)
+        .next()
+        .is_some()
+    {
+        trees.push(Target::UnversionedTree(document_changes_tree_name(
+            collection,
+        )));
+    }
     trees.push(Target::UnversionedTree(view_versions_tree_name(collection)));
 
     for view in database.data.schema.views_in_collection(collection) {
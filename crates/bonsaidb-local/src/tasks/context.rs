@@ -0,0 +1,70 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use watchable::{Watchable, Watcher};
+
+/// State shared between an executing [`Job`](crate::tasks::Job) and the
+/// [`Manager`](crate::tasks::manager::Manager) that queued it.
+#[derive(Debug, Clone)]
+pub struct JobContext {
+    cancelled: Arc<AtomicBool>,
+    started: Arc<AtomicBool>,
+    progress: Arc<Watchable<JobProgress>>,
+}
+
+impl Default for JobContext {
+    fn default() -> Self {
+        Self {
+            cancelled: Arc::default(),
+            started: Arc::default(),
+            progress: Arc::new(Watchable::new(JobProgress::default())),
+        }
+    }
+}
+
+impl JobContext {
+    /// Returns true if the job has been cancelled. Long-running jobs should
+    /// check this periodically and return early once it is set.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Reports the progress of the executing job to all
+    /// [`Handle`](crate::tasks::handle::Handle)s watching it. `fraction` is
+    /// clamped to the range `0.0..=1.0`.
+    ///
+    /// Reporting progress never blocks, and succeeds even if no handles are
+    /// watching the job.
+    pub fn report_progress(&self, fraction: f32, message: Option<String>) {
+        self.progress.replace(JobProgress {
+            fraction: fraction.clamp(0., 1.),
+            message,
+        });
+    }
+
+    pub(crate) fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn start(&self) {
+        self.started.store(true, Ordering::Release);
+    }
+
+    pub(crate) fn has_started(&self) -> bool {
+        self.started.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn watch_progress(&self) -> Watcher<JobProgress> {
+        self.progress.watch()
+    }
+}
+
+/// The most recent progress reported by an executing job.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JobProgress {
+    /// The fraction of the job that has been completed, between 0.0 and 1.0.
+    pub fraction: f32,
+    /// An optional description of what the job is currently doing.
+    pub message: Option<String>,
+}
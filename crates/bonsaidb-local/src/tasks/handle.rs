@@ -1,6 +1,10 @@
 use std::fmt::Debug;
 use std::sync::Arc;
 
+use watchable::Watcher;
+
+use crate::tasks::JobProgress;
+
 /// he `Id` of an executing task.
 #[derive(Debug, Hash, Eq, PartialEq, Clone, Copy)]
 pub struct Id(pub(crate) u64);
@@ -11,7 +15,9 @@ pub struct Handle<T, E> {
     /// The task's id.
     pub id: Id,
 
-    pub(crate) receiver: flume::Receiver<Result<T, Arc<E>>>,
+    pub(crate) receiver: flume::Receiver<Result<Result<T, Arc<E>>, JobCancelled>>,
+    pub(crate) progress: Watcher<JobProgress>,
+    pub(crate) canceller: Box<dyn CancelJob>,
 }
 
 impl<T, E> Handle<T, E>
@@ -23,8 +29,56 @@ where
     ///
     /// # Errors
     ///
-    /// Returns an error if the job is cancelled.
-    pub fn receive(self) -> Result<Result<T, Arc<E>>, flume::RecvError> {
-        self.receiver.recv()
+    /// Returns [`JobCancelled`] if the job is cancelled.
+    pub fn receive(self) -> Result<Result<T, Arc<E>>, JobCancelled> {
+        self.receiver.recv().unwrap_or(Err(JobCancelled))
+    }
+
+    /// Waits for the job to complete and returns the result, without
+    /// blocking the current thread.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`JobCancelled`] if the job is cancelled.
+    pub async fn receive_async(self) -> Result<Result<T, Arc<E>>, JobCancelled> {
+        self.receiver
+            .recv_async()
+            .await
+            .unwrap_or(Err(JobCancelled))
+    }
+
+    /// Returns a watcher of the progress reported by the job.
+    ///
+    /// Once the job finishes, the watcher is disconnected.
+    #[must_use]
+    pub fn progress(&self) -> Watcher<JobProgress> {
+        self.progress.clone()
+    }
+
+    /// Cancels this handle's interest in the job.
+    ///
+    /// If no other live handles to the same job remain, the job is cancelled:
+    /// if it hasn't started executing, it is removed from the queue, and if it
+    /// is currently executing, it will be asked to stop at the next
+    /// opportunity. Any keyed entry for the job is removed, so a later request
+    /// for the same work will queue a fresh job.
+    pub fn cancel(self) {
+        let Self {
+            id,
+            receiver,
+            canceller,
+            ..
+        } = self;
+        drop(receiver);
+        canceller.cancel_job(id);
     }
 }
+
+/// A job was cancelled before its result was delivered.
+#[derive(thiserror::Error, Debug, Clone, Copy, Eq, PartialEq)]
+#[error("job cancelled")]
+pub struct JobCancelled;
+
+pub(crate) trait CancelJob: Debug + Send + Sync {
+    fn cancel_job(&self, id: Id);
+}
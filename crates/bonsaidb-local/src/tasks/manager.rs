@@ -1,17 +1,22 @@
 use std::fmt::Debug;
 use std::sync::Arc;
+use std::time::Duration;
 
 use derive_where::derive_where;
 use parking_lot::RwLock;
 
-use crate::tasks::handle::{Handle, Id};
-use crate::tasks::traits::Executable;
+use crate::metrics::MetricsRecorder;
+use crate::tasks::handle::{CancelJob, Handle, Id};
 use crate::tasks::{Job, Keyed};
 
 pub(crate) mod jobs;
 mod managed_job;
+mod queue;
 pub(crate) use managed_job::ManagedJob;
 
+pub use self::queue::Priority;
+use self::queue::Queue;
+
 #[cfg(test)]
 mod tests;
 
@@ -27,24 +32,92 @@ impl<Key> Manager<Key>
 where
     Key: Clone + std::hash::Hash + Eq + Send + Sync + Debug + 'static,
 {
-    /// Pushes a `job` into the queue. Pushing the same job definition twice
-    /// will yield two tasks in the queue.
+    /// Returns a new manager with `worker_count` workers.
+    pub fn new(worker_count: usize) -> Self {
+        let manager = Self::default();
+        manager.set_worker_count(worker_count);
+        manager
+    }
+
+    /// Pushes a `job` into the queue with [`Priority::Normal`]. Pushing the
+    /// same job definition twice will yield two tasks in the queue.
     #[cfg(test)]
     pub fn enqueue<J: Job + 'static>(&self, job: J) -> Handle<J::Output, J::Error> {
+        self.enqueue_with_priority(job, Priority::Normal)
+    }
+
+    /// Pushes a `job` into the queue with `priority`. Pushing the same job
+    /// definition twice will yield two tasks in the queue.
+    #[cfg(test)]
+    pub fn enqueue_with_priority<J: Job + 'static>(
+        &self,
+        job: J,
+        priority: Priority,
+    ) -> Handle<J::Output, J::Error> {
         let mut jobs = self.jobs.write();
-        jobs.enqueue(job, None, self.clone())
+        jobs.enqueue(job, None, priority, self.clone())
     }
 
     /// Uses [`Keyed::key`] to ensure no other job with the same `key` is
     /// currently running. If another job is already running that matches, a
     /// clone of that [`Handle`] will be returned. When the job finishes, all
     /// [`Handle`] clones will be notified with a copy of the result.
+    ///
+    /// New jobs are queued with [`Priority::Normal`].
     pub fn lookup_or_enqueue<J: Keyed<Key>>(
         &self,
         job: J,
+    ) -> Handle<<J as Job>::Output, <J as Job>::Error> {
+        self.lookup_or_enqueue_with_priority(job, Priority::Normal)
+    }
+
+    /// Uses [`Keyed::key`] to ensure no other job with the same `key` is
+    /// currently running. If another job is already running that matches, a
+    /// clone of that [`Handle`] will be returned and `priority` is ignored.
+    /// Otherwise, the job is queued with `priority`.
+    pub fn lookup_or_enqueue_with_priority<J: Keyed<Key>>(
+        &self,
+        job: J,
+        priority: Priority,
     ) -> Handle<<J as Job>::Output, <J as Job>::Error> {
         let mut jobs = self.jobs.write();
-        jobs.lookup_or_enqueue(job, self.clone())
+        jobs.lookup_or_enqueue(job, priority, self.clone())
+    }
+
+    /// Pushes a `job` into the queue with [`Priority::Normal`], unless an
+    /// equivalent job was enqueued using this function less than `debounce`
+    /// ago and hasn't started executing. In that case, a clone of the existing
+    /// job's [`Handle`] is returned and `job` is dropped.
+    ///
+    /// Jobs are equivalent if they are the same type and are equal according
+    /// to their [`Hash`](std::hash::Hash) and [`Eq`] implementations. The
+    /// window begins when a job is first enqueued, and is not extended by
+    /// later calls that return the same job.
+    ///
+    /// This is useful for jobs that are requested repeatedly in quick
+    /// succession, but that only need to run once for the entire burst.
+    /// Debounced jobs are tracked separately from the jobs enqueued using
+    /// [`Self::lookup_or_enqueue()`]: they are never returned by key lookups,
+    /// and keyed jobs are never returned by this function.
+    pub fn enqueue_debounced<J: Job + Clone + std::hash::Hash + Eq>(
+        &self,
+        job: J,
+        debounce: Duration,
+    ) -> Handle<J::Output, J::Error> {
+        self.enqueue_debounced_with_priority(job, debounce, Priority::Normal)
+    }
+
+    /// Pushes a `job` into the queue with `priority`, unless an equivalent
+    /// job is pending. See [`Self::enqueue_debounced()`] for more
+    /// information. If an existing job is returned, `priority` is ignored.
+    pub fn enqueue_debounced_with_priority<J: Job + Clone + std::hash::Hash + Eq>(
+        &self,
+        job: J,
+        debounce: Duration,
+        priority: Priority,
+    ) -> Handle<J::Output, J::Error> {
+        let mut jobs = self.jobs.write();
+        jobs.enqueue_debounced(job, debounce, priority, self.clone())
     }
 
     fn job_completed<T: Clone + Send + Sync + 'static, E: Send + Sync + 'static>(
@@ -57,22 +130,77 @@ where
         jobs.job_completed(id, key, result);
     }
 
-    /// Spawns a worker. In general, you shouldn't need to call this function
-    /// directly.
-    pub fn spawn_worker(&self) {
-        let receiver = {
+    /// Sets the recorder that is notified as jobs are enqueued and completed,
+    /// and as workers start and finish executing jobs.
+    pub fn set_metrics_recorder(&self, metrics: Option<Arc<dyn MetricsRecorder>>) {
+        let mut jobs = self.jobs.write();
+        jobs.queue().set_metrics_recorder(metrics.clone());
+        jobs.set_metrics_recorder(metrics);
+    }
+
+    /// Scales the number of workers executing jobs to `worker_count`.
+    ///
+    /// New workers are spawned immediately. When scaling down, idle workers
+    /// exit immediately, and busy workers exit after finishing the job they
+    /// are executing.
+    pub fn set_worker_count(&self, worker_count: usize) {
+        let queue = {
+            let jobs = self.jobs.read();
+            jobs.queue()
+        };
+        for _ in 0..queue.set_worker_count(worker_count) {
+            let queue = queue.clone();
+            std::thread::Builder::new()
+                .name(String::from("bonsaidb-tasks"))
+                .spawn(move || worker_thread(&queue))
+                .unwrap();
+        }
+    }
+
+    /// Returns the current status of this manager's workers and queue.
+    pub fn status(&self) -> TasksStatus {
+        let queue = {
             let jobs = self.jobs.read();
             jobs.queue()
         };
-        std::thread::Builder::new()
-            .name(String::from("bonsaidb-tasks"))
-            .spawn(move || worker_thread(&receiver))
-            .unwrap();
+        let (worker_count, busy_workers) = queue.workers();
+        TasksStatus {
+            worker_count,
+            busy_workers,
+            queue_depth: queue.len(),
+        }
+    }
+}
+
+/// The status of the workers that execute background tasks, such as view
+/// indexing and compaction.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct TasksStatus {
+    /// The number of running workers. While the number of workers is being
+    /// reduced, this includes workers that will exit once they finish their
+    /// current job.
+    pub worker_count: usize,
+    /// The number of workers executing a job.
+    pub busy_workers: usize,
+    /// The number of jobs waiting to be executed.
+    pub queue_depth: usize,
+}
+
+impl<Key> CancelJob for Manager<Key>
+where
+    Key: Clone + std::hash::Hash + Eq + Send + Sync + Debug + 'static,
+{
+    fn cancel_job(&self, id: Id) {
+        let mut jobs = self.jobs.write();
+        jobs.cancel(id);
     }
 }
 
-fn worker_thread(receiver: &flume::Receiver<Box<dyn Executable>>) {
-    while let Ok(mut job) = receiver.recv() {
+fn worker_thread(queue: &Queue) {
+    let mut finished_job = false;
+    while let Some(mut job) = queue.pop(finished_job) {
         job.execute();
+        finished_job = true;
     }
 }
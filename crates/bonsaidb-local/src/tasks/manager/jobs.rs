@@ -1,21 +1,28 @@
-use std::any::Any;
+use std::any::{Any, TypeId};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use flume::{Receiver, Sender};
-
-use crate::tasks::handle::{Handle, Id};
+use crate::metrics::MetricsRecorder;
+use crate::tasks::handle::{Handle, Id, JobCancelled};
+use crate::tasks::manager::queue::{Priority, Queue};
 use crate::tasks::manager::{ManagedJob, Manager};
-use crate::tasks::traits::Executable;
-use crate::tasks::{Job, Keyed};
+use crate::tasks::{Job, JobContext, Keyed};
 
 pub struct Jobs<Key> {
     last_task_id: u64,
     result_senders: HashMap<Id, Vec<Box<dyn AnySender>>>,
+    // Each context also provides the job's progress to its handles.
+    contexts: HashMap<Id, JobContext>,
     keyed_jobs: HashMap<Key, Id>,
-    queuer: Sender<Box<dyn Executable>>,
-    queue: Receiver<Box<dyn Executable>>,
+    // Debounced jobs that haven't started yet, grouped by the hash of the job.
+    debounced_jobs: HashMap<u64, Vec<DebouncedJob>>,
+    debounced_hashes: HashMap<Id, u64>,
+    queue: Arc<Queue>,
+    metrics: Option<Arc<dyn MetricsRecorder>>,
 }
 
 impl<Key> Debug for Jobs<Key>
@@ -26,89 +33,211 @@ where
         f.debug_struct("Jobs")
             .field("last_task_id", &self.last_task_id)
             .field("result_senders", &self.result_senders.len())
+            .field("contexts", &self.contexts.len())
             .field("keyed_jobs", &self.keyed_jobs)
-            .field("queuer", &self.queuer)
+            .field("debounced_jobs", &self.debounced_hashes.len())
             .field("queue", &self.queue)
+            .field("metrics", &self.metrics)
             .finish()
     }
 }
 
 impl<Key> Default for Jobs<Key> {
     fn default() -> Self {
-        let (queuer, queue) = flume::unbounded();
-
         Self {
             last_task_id: 0,
             result_senders: HashMap::new(),
+            contexts: HashMap::new(),
             keyed_jobs: HashMap::new(),
-            queuer,
-            queue,
+            debounced_jobs: HashMap::new(),
+            debounced_hashes: HashMap::new(),
+            queue: Arc::default(),
+            metrics: None,
         }
     }
 }
 
+impl<Key> Drop for Jobs<Key> {
+    fn drop(&mut self) {
+        // Allow the workers to exit once the remaining jobs are drained.
+        self.queue.close();
+    }
+}
+
 impl<Key> Jobs<Key>
 where
     Key: Clone + std::hash::Hash + Eq + Send + Sync + Debug + 'static,
 {
-    pub fn queue(&self) -> Receiver<Box<dyn Executable>> {
+    pub fn queue(&self) -> Arc<Queue> {
         self.queue.clone()
     }
 
+    pub fn set_metrics_recorder(&mut self, metrics: Option<Arc<dyn MetricsRecorder>>) {
+        self.metrics = metrics;
+    }
+
+    #[cfg(test)]
+    pub fn set_last_task_id(&mut self, id: u64) {
+        self.last_task_id = id;
+    }
+
+    /// Returns an id that isn't used by any job that is pending or whose
+    /// result hasn't been delivered. Ids wrap around, so ids that are still
+    /// in use are skipped to prevent results being delivered to the wrong
+    /// handles.
+    fn next_id(&mut self) -> Id {
+        loop {
+            self.last_task_id = self.last_task_id.wrapping_add(1);
+            let id = Id(self.last_task_id);
+            if !self.contexts.contains_key(&id) && !self.result_senders.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+
     pub fn enqueue<J: Job + 'static>(
         &mut self,
         job: J,
         key: Option<Key>,
+        priority: Priority,
         manager: Manager<Key>,
     ) -> Handle<J::Output, J::Error> {
-        self.last_task_id = self.last_task_id.wrapping_add(1);
-        let id = Id(self.last_task_id);
-        self.queuer
-            .send(Box::new(ManagedJob {
+        let id = self.next_id();
+        let context = JobContext::default();
+        self.contexts.insert(id, context.clone());
+        self.queue.push(
+            Box::new(ManagedJob {
                 id,
                 job,
-                manager,
+                manager: manager.clone(),
                 key,
-            }))
-            .unwrap();
+                context,
+            }),
+            priority,
+        );
+        if let Some(metrics) = &self.metrics {
+            metrics.job_enqueued(self.queue.len());
+        }
 
-        self.create_new_task_handle(id)
+        self.create_new_task_handle(id, manager)
     }
 
     pub fn create_new_task_handle<T: Send + Sync + 'static, E: Send + Sync + 'static>(
         &mut self,
         id: Id,
+        manager: Manager<Key>,
     ) -> Handle<T, E> {
         let (sender, receiver) = flume::bounded(1);
         let senders = self.result_senders.entry(id).or_insert_with(Vec::default);
         senders.push(Box::new(sender));
+        let progress = self.contexts.get(&id).map_or_else(
+            || JobContext::default().watch_progress(),
+            JobContext::watch_progress,
+        );
 
-        Handle { id, receiver }
+        Handle {
+            id,
+            receiver,
+            progress,
+            canceller: Box::new(manager),
+        }
     }
 
     pub fn lookup_or_enqueue<J: Keyed<Key>>(
         &mut self,
         job: J,
+        priority: Priority,
         manager: Manager<Key>,
     ) -> Handle<<J as Job>::Output, <J as Job>::Error> {
         let key = job.key();
         if let Some(&id) = self.keyed_jobs.get(&key) {
-            self.create_new_task_handle(id)
+            self.create_new_task_handle(id, manager)
         } else {
-            let handle = self.enqueue(job, Some(key.clone()), manager);
+            let handle = self.enqueue(job, Some(key.clone()), priority, manager);
             self.keyed_jobs.insert(key, handle.id);
             handle
         }
     }
 
+    pub fn enqueue_debounced<J: Job + Clone + Hash + Eq>(
+        &mut self,
+        job: J,
+        debounce: Duration,
+        priority: Priority,
+        manager: Manager<Key>,
+    ) -> Handle<J::Output, J::Error> {
+        let hash = {
+            let mut hasher = DefaultHasher::new();
+            TypeId::of::<J>().hash(&mut hasher);
+            job.hash(&mut hasher);
+            hasher.finish()
+        };
+        let now = Instant::now();
+        let contexts = &self.contexts;
+        let mut existing = None;
+        if let Some(debounced) = self.debounced_jobs.get_mut(&hash) {
+            // Forget jobs that can no longer be returned, either because their
+            // window has elapsed or because they have started executing.
+            debounced.retain(|debounced| {
+                now.duration_since(debounced.enqueued_at) < debounced.debounce
+                    && contexts
+                        .get(&debounced.id)
+                        .map_or(false, |context| !context.has_started())
+            });
+            existing = debounced
+                .iter()
+                .find(|debounced| debounced.job.downcast_ref::<J>() == Some(&job))
+                .map(|debounced| debounced.id);
+        }
+        if let Some(id) = existing {
+            return self.create_new_task_handle(id, manager);
+        }
+
+        let copy = Box::new(job.clone());
+        let handle = self.enqueue(job, None, priority, manager);
+        self.debounced_hashes.insert(handle.id, hash);
+        self.debounced_jobs
+            .entry(hash)
+            .or_default()
+            .push(DebouncedJob {
+                id: handle.id,
+                job: copy,
+                enqueued_at: now,
+                debounce,
+            });
+        handle
+    }
+
+    fn forget_debounced(&mut self, id: Id) {
+        let Some(hash) = self.debounced_hashes.remove(&id) else {
+            return;
+        };
+        if let Some(debounced) = self.debounced_jobs.get_mut(&hash) {
+            debounced.retain(|debounced| debounced.id != id);
+            if debounced.is_empty() {
+                self.debounced_jobs.remove(&hash);
+            }
+        }
+    }
+
     pub fn job_completed<T: Clone + Send + Sync + 'static, E: Send + Sync + 'static>(
         &mut self,
         id: Id,
         key: Option<&Key>,
         result: Result<T, E>,
     ) {
+        // If this job was cancelled while executing, a new job may have been
+        // enqueued with the same key. Only remove the entry if it still refers
+        // to this job.
         if let Some(key) = key {
-            self.keyed_jobs.remove(key);
+            if self.keyed_jobs.get(key) == Some(&id) {
+                self.keyed_jobs.remove(key);
+            }
+        }
+        self.forget_debounced(id);
+        self.contexts.remove(&id);
+        if let Some(metrics) = &self.metrics {
+            metrics.job_completed(self.queue.len());
         }
 
         if let Some(senders) = self.result_senders.remove(&id) {
@@ -116,23 +245,67 @@ where
             for sender_handle in senders {
                 let sender = sender_handle
                     .as_any()
-                    .downcast_ref::<flume::Sender<Result<T, Arc<E>>>>()
+                    .downcast_ref::<flume::Sender<Result<Result<T, Arc<E>>, JobCancelled>>>()
                     .unwrap();
-                drop(sender.send(result.clone()));
+                drop(sender.send(Ok(result.clone())));
+            }
+        }
+    }
+
+    /// Cancels the job with `id` if all of its [`Handle`]s have been dropped
+    /// or cancelled. A job that hasn't started is removed from the queue, and
+    /// a running job is asked to stop. Any receivers that are still waiting on
+    /// the job receive [`JobCancelled`].
+    pub fn cancel(&mut self, id: Id) {
+        let Some(senders) = self.result_senders.get_mut(&id) else {
+            return;
+        };
+        if senders.iter().any(|sender| !sender.is_disconnected()) {
+            senders.retain(|sender| !sender.is_disconnected());
+            return;
+        }
+
+        if let Some(senders) = self.result_senders.remove(&id) {
+            for sender in senders {
+                sender.cancel();
             }
         }
+        if let Some(context) = self.contexts.remove(&id) {
+            context.cancel();
+        }
+        self.queue.remove(id);
+        self.keyed_jobs.retain(|_, job_id| *job_id != id);
+        self.forget_debounced(id);
     }
 }
 
+struct DebouncedJob {
+    id: Id,
+    // A copy of the job, used to compare against newly enqueued jobs.
+    job: Box<dyn Any + Send + Sync>,
+    enqueued_at: Instant,
+    debounce: Duration,
+}
+
 pub trait AnySender: Any + Send + Sync {
     fn as_any(&self) -> &'_ dyn Any;
+    fn is_disconnected(&self) -> bool;
+    fn cancel(&self);
 }
 
-impl<T> AnySender for flume::Sender<T>
+impl<T> AnySender for flume::Sender<Result<T, JobCancelled>>
 where
     T: Send + Sync + 'static,
 {
     fn as_any(&self) -> &'_ dyn Any {
         self
     }
+
+    fn is_disconnected(&self) -> bool {
+        flume::Sender::is_disconnected(self)
+    }
+
+    fn cancel(&self) {
+        drop(self.try_send(Err(JobCancelled)));
+    }
 }
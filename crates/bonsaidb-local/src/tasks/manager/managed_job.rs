@@ -3,7 +3,7 @@ use std::fmt::Debug;
 use crate::tasks::handle::Id;
 use crate::tasks::manager::Manager;
 use crate::tasks::traits::Executable;
-use crate::tasks::Job;
+use crate::tasks::{Job, JobContext};
 
 #[derive(Debug)]
 pub struct ManagedJob<J, Key> {
@@ -11,6 +11,7 @@ pub struct ManagedJob<J, Key> {
     pub job: J,
     pub manager: Manager<Key>,
     pub key: Option<Key>,
+    pub context: JobContext,
 }
 
 impl<J, Key> Executable for ManagedJob<J, Key>
@@ -18,8 +19,20 @@ where
     J: Job,
     Key: Clone + std::hash::Hash + Eq + Send + Sync + Debug + 'static,
 {
+    fn id(&self) -> Id {
+        self.id
+    }
+
     fn execute(&mut self) {
-        let result = self.job.execute();
+        // A job can be cancelled after a worker has taken it from the queue
+        // but before it starts. All bookkeeping was already cleaned up when
+        // the job was cancelled.
+        if self.context.is_cancelled() {
+            return;
+        }
+        self.context.start();
+
+        let result = self.job.execute(&self.context);
 
         self.manager
             .job_completed(self.id, self.key.as_ref(), result);
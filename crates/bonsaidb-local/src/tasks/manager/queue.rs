@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::metrics::MetricsRecorder;
+use crate::tasks::handle::Id;
+use crate::tasks::traits::Executable;
+
+/// The priority of a queued job. Workers always execute the oldest job of the
+/// highest priority available.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Priority {
+    /// Jobs that other operations are actively waiting on, such as view
+    /// indexing.
+    High,
+    /// The default priority.
+    #[default]
+    Normal,
+    /// Maintenance jobs that can be deferred while other work is pending.
+    Low,
+}
+
+impl Priority {
+    const COUNT: usize = 3;
+
+    const fn index(self) -> usize {
+        match self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        }
+    }
+}
+
+/// A multi-priority job queue shared between the jobs manager and its workers.
+///
+/// The queue also tracks the workers executing its jobs. When the number of
+/// workers is reduced, workers exit after finishing the job they are
+/// executing, rather than while executing it.
+#[derive(Default)]
+pub struct Queue {
+    state: Mutex<QueueState>,
+    job_available: Condvar,
+}
+
+#[derive(Default)]
+struct QueueState {
+    queues: [VecDeque<Box<dyn Executable>>; Priority::COUNT],
+    closed: bool,
+    /// The number of worker threads that haven't exited.
+    workers: usize,
+    /// The number of workers that should be running.
+    target_workers: usize,
+    /// The number of workers executing a job.
+    busy_workers: usize,
+    metrics: Option<Arc<dyn MetricsRecorder>>,
+}
+
+impl QueueState {
+    fn workers_changed(&self) -> Option<(Arc<dyn MetricsRecorder>, usize, usize)> {
+        self.metrics
+            .clone()
+            .map(|metrics| (metrics, self.workers, self.busy_workers))
+    }
+}
+
+impl Queue {
+    pub fn push(&self, job: Box<dyn Executable>, priority: Priority) {
+        let mut state = self.state.lock();
+        state.queues[priority.index()].push_back(job);
+        drop(state);
+        self.job_available.notify_one();
+    }
+
+    /// Removes the job with `id` if it is waiting to be executed. Returns true
+    /// if the job was removed.
+    pub fn remove(&self, id: Id) -> bool {
+        let mut state = self.state.lock();
+        for queue in &mut state.queues {
+            if let Some(index) = queue.iter().position(|job| job.id() == id) {
+                queue.remove(index);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Returns the number of jobs waiting to be executed.
+    pub fn len(&self) -> usize {
+        let state = self.state.lock();
+        state.queues.iter().map(VecDeque::len).sum()
+    }
+
+    /// Returns the number of workers, and the number of those workers that
+    /// are executing a job.
+    pub fn workers(&self) -> (usize, usize) {
+        let state = self.state.lock();
+        (state.workers, state.busy_workers)
+    }
+
+    /// Sets the recorder notified as workers start and finish jobs, and
+    /// reports the current workers to it.
+    pub fn set_metrics_recorder(&self, metrics: Option<Arc<dyn MetricsRecorder>>) {
+        let mut state = self.state.lock();
+        state.metrics = metrics;
+        let metrics = state.workers_changed();
+        drop(state);
+        report_workers(metrics);
+    }
+
+    /// Sets the number of workers that should be running to `count`. Returns
+    /// the number of workers that need to be spawned, which are considered
+    /// running once this function returns. Excess workers exit the next time
+    /// they wait for a job.
+    pub fn set_worker_count(&self, count: usize) -> usize {
+        let mut state = self.state.lock();
+        state.target_workers = count;
+        let spawn = count.saturating_sub(state.workers);
+        state.workers += spawn;
+        let metrics = state.workers_changed();
+        drop(state);
+        if spawn == 0 {
+            self.job_available.notify_all();
+        }
+        report_workers(metrics);
+        spawn
+    }
+
+    /// Waits for the next job to execute. Returns `None` once the queue has
+    /// been closed and all remaining jobs have been drained, or if the
+    /// calling worker should exit because there are more workers than
+    /// requested.
+    ///
+    /// `finished_job` should be true if the calling worker executed a job
+    /// since it last called this function.
+    pub fn pop(&self, finished_job: bool) -> Option<Box<dyn Executable>> {
+        let mut state = self.state.lock();
+        if finished_job {
+            state.busy_workers -= 1;
+        }
+        let job = loop {
+            if state.workers > state.target_workers {
+                break None;
+            }
+            if let Some(job) = state.queues.iter_mut().find_map(VecDeque::pop_front) {
+                break Some(job);
+            }
+            if state.closed {
+                break None;
+            }
+
+            self.job_available.wait(&mut state);
+        };
+        if job.is_some() {
+            state.busy_workers += 1;
+        } else {
+            state.workers -= 1;
+        }
+        let metrics = state.workers_changed();
+        drop(state);
+        report_workers(metrics);
+        job
+    }
+
+    pub fn close(&self) {
+        let mut state = self.state.lock();
+        state.closed = true;
+        drop(state);
+        self.job_available.notify_all();
+    }
+}
+
+fn report_workers(metrics: Option<(Arc<dyn MetricsRecorder>, usize, usize)>) {
+    if let Some((metrics, workers, busy_workers)) = metrics {
+        metrics.job_workers_changed(workers, busy_workers);
+    }
+}
+
+impl Debug for Queue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock();
+        f.debug_struct("Queue")
+            .field("high", &state.queues[Priority::High.index()].len())
+            .field("normal", &state.queues[Priority::Normal.index()].len())
+            .field("low", &state.queues[Priority::Low.index()].len())
+            .field("closed", &state.closed)
+            .field("workers", &state.workers)
+            .field("target_workers", &state.target_workers)
+            .field("busy_workers", &state.busy_workers)
+            .finish()
+    }
+}
@@ -1,11 +1,13 @@
 use std::convert::Infallible;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::time::Duration;
 
-use super::Manager;
-use crate::tasks::{Job, Keyed};
+use super::{Manager, Priority};
+use crate::tasks::handle::{Id, JobCancelled};
+use crate::tasks::{Job, JobContext, JobProgress, Keyed};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
 struct Echo<T>(T);
 
 impl<T> Job for Echo<T>
@@ -15,7 +17,7 @@ where
     type Error = Infallible;
     type Output = T;
 
-    fn execute(&mut self) -> Result<Self::Output, Self::Error> {
+    fn execute(&mut self, _context: &JobContext) -> Result<Self::Output, Self::Error> {
         Ok(self.0.clone())
     }
 }
@@ -30,9 +32,8 @@ where
 }
 
 #[test]
-fn simple() -> Result<(), flume::RecvError> {
-    let manager = Manager::<usize>::default();
-    manager.spawn_worker();
+fn simple() -> Result<(), JobCancelled> {
+    let manager = Manager::<usize>::new(1);
     let handle = manager.enqueue(Echo(1));
     if let Ok(value) = handle.receive()? {
         assert_eq!(value, 1);
@@ -53,7 +54,7 @@ fn keyed_simple() {
     let handle3 = manager.lookup_or_enqueue(Echo(1));
     assert_eq!(handle3.id, handle.id);
 
-    manager.spawn_worker();
+    manager.set_worker_count(1);
 
     let result1 = handle.receive().unwrap();
     let result2 = handle2.receive().unwrap();
@@ -65,3 +66,364 @@ fn keyed_simple() {
         assert_eq!(result.unwrap(), 1);
     }
 }
+
+#[test]
+fn cancel_queued() {
+    let manager = Manager::<usize>::default();
+    let handle = manager.lookup_or_enqueue(Echo(1));
+    let cancelled_id = handle.id;
+    handle.cancel();
+    assert_eq!(manager.status().queue_depth, 0);
+
+    // The keyed entry should have been removed, causing a new job to be queued.
+    let handle = manager.lookup_or_enqueue(Echo(1));
+    assert_ne!(handle.id, cancelled_id);
+
+    manager.set_worker_count(1);
+    assert_eq!(handle.receive().unwrap().unwrap(), 1);
+}
+
+#[test]
+fn cancel_with_siblings() {
+    let manager = Manager::<usize>::default();
+    let handle = manager.lookup_or_enqueue(Echo(1));
+    let handle2 = manager.lookup_or_enqueue(Echo(1));
+    assert_eq!(handle.id, handle2.id);
+
+    // Another live handle exists, so the job should still execute.
+    handle.cancel();
+    let handle3 = manager.lookup_or_enqueue(Echo(1));
+    assert_eq!(handle3.id, handle2.id);
+
+    manager.set_worker_count(1);
+    assert_eq!(handle2.receive().unwrap().unwrap(), 1);
+    assert_eq!(handle3.receive().unwrap().unwrap(), 1);
+}
+
+#[derive(Debug)]
+struct WaitForCancel {
+    started: flume::Sender<()>,
+}
+
+impl Job for WaitForCancel {
+    type Error = Infallible;
+    type Output = ();
+
+    fn execute(&mut self, context: &JobContext) -> Result<Self::Output, Self::Error> {
+        self.started.send(()).unwrap();
+        while !context.is_cancelled() {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn cancel_running() {
+    let manager = Manager::<usize>::new(1);
+    let (started, started_receiver) = flume::bounded(1);
+    let handle = manager.enqueue(WaitForCancel { started });
+    started_receiver.recv().unwrap();
+    handle.cancel();
+
+    // The worker should be freed up once the running job notices the
+    // cancellation.
+    let handle = manager.enqueue(Echo(2));
+    assert_eq!(handle.receive().unwrap().unwrap(), 2);
+}
+
+#[derive(Debug)]
+struct Record {
+    value: usize,
+    completed: flume::Sender<usize>,
+}
+
+impl Job for Record {
+    type Error = Infallible;
+    type Output = ();
+
+    fn execute(&mut self, _context: &JobContext) -> Result<Self::Output, Self::Error> {
+        self.completed.send(self.value).unwrap();
+        Ok(())
+    }
+}
+
+#[test]
+fn priorities() {
+    let manager = Manager::<usize>::default();
+    let (completed, completed_receiver) = flume::unbounded();
+    let low = manager.enqueue_with_priority(
+        Record {
+            value: 1,
+            completed: completed.clone(),
+        },
+        Priority::Low,
+    );
+    let normal = manager.enqueue(Record {
+        value: 2,
+        completed: completed.clone(),
+    });
+    let high = manager.enqueue_with_priority(
+        Record {
+            value: 3,
+            completed,
+        },
+        Priority::High,
+    );
+
+    // A single worker guarantees the jobs execute sequentially.
+    manager.set_worker_count(1);
+    for handle in [low, normal, high] {
+        handle.receive().unwrap().unwrap();
+    }
+
+    let order = completed_receiver.drain().collect::<Vec<_>>();
+    assert_eq!(order, vec![3, 2, 1]);
+}
+
+#[derive(Debug)]
+struct ReportProgress {
+    proceed: flume::Receiver<()>,
+}
+
+impl Job for ReportProgress {
+    type Error = Infallible;
+    type Output = ();
+
+    fn execute(&mut self, context: &JobContext) -> Result<Self::Output, Self::Error> {
+        for step in 1..=2_u8 {
+            // Wait for the test to observe the previous step.
+            drop(self.proceed.recv());
+            context.report_progress(f32::from(step) / 2., Some(format!("step {step}")));
+        }
+        drop(self.proceed.recv());
+        Ok(())
+    }
+}
+
+#[test]
+fn progress() {
+    let manager = Manager::<usize>::new(1);
+    let (proceed, proceed_receiver) = flume::unbounded();
+    let handle = manager.enqueue(ReportProgress {
+        proceed: proceed_receiver,
+    });
+    let mut progress = handle.progress();
+    assert_eq!(*progress.read(), JobProgress::default());
+
+    proceed.send(()).unwrap();
+    assert_eq!(
+        progress.next_value().unwrap(),
+        JobProgress {
+            fraction: 0.5,
+            message: Some(String::from("step 1")),
+        }
+    );
+
+    // Dropping every handle must not interfere with the job reporting
+    // progress.
+    drop(handle);
+    proceed.send(()).unwrap();
+    assert_eq!(
+        progress.next_value().unwrap(),
+        JobProgress {
+            fraction: 1.,
+            message: Some(String::from("step 2")),
+        }
+    );
+
+    // Once the job completes, the watcher is disconnected.
+    proceed.send(()).unwrap();
+    while progress.watch().is_ok() {}
+}
+
+#[test]
+fn debounced() {
+    let manager = Manager::<usize>::default();
+    let handle = manager.enqueue_debounced(Echo(1), Duration::from_secs(60));
+    let handle2 = manager.enqueue_debounced(Echo(1), Duration::from_secs(60));
+    assert_eq!(handle.id, handle2.id);
+    let other = manager.enqueue_debounced(Echo(2), Duration::from_secs(60));
+    assert_ne!(other.id, handle.id);
+    // Debounced jobs are independent of keyed jobs.
+    let keyed = manager.lookup_or_enqueue(Echo(1));
+    assert_ne!(keyed.id, handle.id);
+
+    manager.set_worker_count(1);
+    assert_eq!(handle.receive().unwrap().unwrap(), 1);
+    assert_eq!(handle2.receive().unwrap().unwrap(), 1);
+    assert_eq!(other.receive().unwrap().unwrap(), 2);
+    assert_eq!(keyed.receive().unwrap().unwrap(), 1);
+
+    // Once the job has completed, a new job is queued.
+    let handle3 = manager.enqueue_debounced(Echo(1), Duration::from_secs(60));
+    assert_ne!(handle3.id, handle.id);
+    assert_eq!(handle3.receive().unwrap().unwrap(), 1);
+}
+
+#[test]
+fn debounce_window_elapsed() {
+    let manager = Manager::<usize>::default();
+    let handle = manager.enqueue_debounced(Echo(1), Duration::from_millis(10));
+    std::thread::sleep(Duration::from_millis(20));
+    let handle2 = manager.enqueue_debounced(Echo(1), Duration::from_millis(10));
+    assert_ne!(handle.id, handle2.id);
+
+    manager.set_worker_count(1);
+    assert_eq!(handle.receive().unwrap().unwrap(), 1);
+    assert_eq!(handle2.receive().unwrap().unwrap(), 1);
+}
+
+/// A job that waits to be allowed to finish. Only `id` is used for equality.
+#[derive(Debug, Clone)]
+struct Gated {
+    id: usize,
+    started: flume::Sender<()>,
+    proceed: flume::Receiver<()>,
+}
+
+impl PartialEq for Gated {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Gated {}
+
+impl Hash for Gated {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl Job for Gated {
+    type Error = Infallible;
+    type Output = usize;
+
+    fn execute(&mut self, _context: &JobContext) -> Result<Self::Output, Self::Error> {
+        self.started.send(()).unwrap();
+        drop(self.proceed.recv());
+        Ok(self.id)
+    }
+}
+
+#[test]
+fn debounced_started() {
+    let manager = Manager::<usize>::new(1);
+    let (started, started_receiver) = flume::unbounded();
+    let (proceed, proceed_receiver) = flume::unbounded();
+    let job = Gated {
+        id: 1,
+        started,
+        proceed: proceed_receiver,
+    };
+    let running = manager.enqueue_debounced(job.clone(), Duration::from_secs(60));
+    started_receiver.recv().unwrap();
+
+    // The first job has started, so it may not observe whatever prompted this
+    // request. A new job must be queued.
+    let queued = manager.enqueue_debounced(job.clone(), Duration::from_secs(60));
+    assert_ne!(queued.id, running.id);
+    let queued2 = manager.enqueue_debounced(job, Duration::from_secs(60));
+    assert_eq!(queued2.id, queued.id);
+
+    proceed.send(()).unwrap();
+    proceed.send(()).unwrap();
+    assert_eq!(running.receive().unwrap().unwrap(), 1);
+    assert_eq!(queued.receive().unwrap().unwrap(), 1);
+    assert_eq!(queued2.receive().unwrap().unwrap(), 1);
+}
+
+#[test]
+fn scale_workers() {
+    let manager = Manager::<usize>::new(2);
+    let (started, started_receiver) = flume::unbounded();
+    let (proceed, proceed_receiver) = flume::unbounded();
+    let first = manager.enqueue(Gated {
+        id: 1,
+        started: started.clone(),
+        proceed: proceed_receiver.clone(),
+    });
+    let second = manager.enqueue(Gated {
+        id: 2,
+        started: started.clone(),
+        proceed: proceed_receiver.clone(),
+    });
+    started_receiver.recv().unwrap();
+    started_receiver.recv().unwrap();
+    let status = manager.status();
+    assert_eq!(status.worker_count, 2);
+    assert_eq!(status.busy_workers, 2);
+    assert_eq!(status.queue_depth, 0);
+
+    // Busy workers keep running until their jobs complete.
+    manager.set_worker_count(1);
+    let third = manager.enqueue(Gated {
+        id: 3,
+        started,
+        proceed: proceed_receiver,
+    });
+    let status = manager.status();
+    assert_eq!(status.worker_count, 2);
+    assert_eq!(status.queue_depth, 1);
+
+    for _ in 0..3 {
+        proceed.send(()).unwrap();
+    }
+    assert_eq!(first.receive().unwrap().unwrap(), 1);
+    assert_eq!(second.receive().unwrap().unwrap(), 2);
+    assert_eq!(third.receive().unwrap().unwrap(), 3);
+
+    // The extra worker exits after returning its result.
+    let mut status = manager.status();
+    while status.worker_count > 1 || status.busy_workers > 0 {
+        std::thread::sleep(Duration::from_millis(1));
+        status = manager.status();
+    }
+    assert_eq!(status.worker_count, 1);
+
+    manager.set_worker_count(3);
+    assert_eq!(manager.status().worker_count, 3);
+    assert_eq!(manager.enqueue(Echo(4)).receive().unwrap().unwrap(), 4);
+}
+
+#[test]
+fn debounced_cancel() {
+    let manager = Manager::<usize>::default();
+    let handle = manager.enqueue_debounced(Echo(1), Duration::from_secs(60));
+    let cancelled_id = handle.id;
+    handle.cancel();
+
+    let handle = manager.enqueue_debounced(Echo(1), Duration::from_secs(60));
+    assert_ne!(handle.id, cancelled_id);
+
+    manager.set_worker_count(1);
+    assert_eq!(handle.receive().unwrap().unwrap(), 1);
+}
+
+#[test]
+fn task_ids_skip_pending_jobs_when_wrapping() {
+    let manager = Manager::<usize>::default();
+    manager.jobs.write().set_last_task_id(u64::MAX - 1);
+    let last = manager.enqueue(Echo(1));
+    let wrapped = manager.enqueue(Echo(2));
+    assert_eq!(last.id, Id(u64::MAX));
+    assert_eq!(wrapped.id, Id(0));
+
+    // Rewind the allocator so that the next ids would collide with the
+    // pending jobs.
+    manager.jobs.write().set_last_task_id(u64::MAX - 1);
+    let skipped = manager.enqueue(Echo(3));
+    assert_eq!(skipped.id, Id(1));
+
+    manager.set_worker_count(1);
+    assert_eq!(last.receive().unwrap().unwrap(), 1);
+    assert_eq!(wrapped.receive().unwrap().unwrap(), 2);
+    assert_eq!(skipped.receive().unwrap().unwrap(), 3);
+
+    // Once the results are delivered, the ids can be reused.
+    manager.jobs.write().set_last_task_id(u64::MAX - 1);
+    let reused = manager.enqueue(Echo(4));
+    assert_eq!(reused.id, Id(u64::MAX));
+    assert_eq!(reused.receive().unwrap().unwrap(), 4);
+}
@@ -4,11 +4,16 @@ use std::sync::Arc;
 use crate::tasks::compactor::Compaction;
 use crate::views::integrity_scanner::IntegrityScan;
 use crate::views::mapper::Map;
+use crate::views::verifier::Verification;
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum Task {
     IntegrityScan(IntegrityScan),
     ViewMap(Map),
+    ViewRebuild(Map),
+    ViewReset(Map),
+    ViewVerify(Verification),
     Compaction(Compaction),
     ExpirationLoader(Arc<Cow<'static, str>>),
+    DocumentReaper(Arc<Cow<'static, str>>),
 }
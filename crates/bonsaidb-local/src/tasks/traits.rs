@@ -1,5 +1,8 @@
 use std::fmt::Debug;
 
+use crate::tasks::handle::Id;
+use crate::tasks::JobContext;
+
 /// Defines a background job that can be queued and executed.
 pub trait Job: Debug + Send + Sync + 'static {
     /// The output type of the job.
@@ -7,8 +10,9 @@ pub trait Job: Debug + Send + Sync + 'static {
     /// The error type of the job.
     type Error: Send + Sync + 'static;
 
-    /// Executes the job and returns the result.
-    fn execute(&mut self) -> Result<Self::Output, Self::Error>;
+    /// Executes the job and returns the result. `context` can be used to
+    /// check whether the job has been cancelled while executing.
+    fn execute(&mut self, context: &JobContext) -> Result<Self::Output, Self::Error>;
 }
 
 /// Defines a background job that has a unique `key`.
@@ -21,5 +25,6 @@ where
 }
 
 pub trait Executable: Send + Sync + Debug {
+    fn id(&self) -> Id;
     fn execute(&mut self);
 }
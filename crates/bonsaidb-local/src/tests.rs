@@ -7,11 +7,12 @@ use bonsaidb_core::permissions::{Permissions, Statement};
 #[cfg(feature = "encryption")]
 use bonsaidb_core::test_util::EncryptedBasic;
 use bonsaidb_core::test_util::{
-    Basic, BasicByBrokenParentId, BasicByParentId, BasicCollectionWithNoViews,
+    Basic, BasicByBrokenParentId, BasicByParentId, BasicByTag, BasicCollectionWithNoViews,
     BasicCollectionWithOnlyBrokenParentId, BasicSchema, HarnessTest, TestDirectory,
 };
 
 use crate::config::{Builder, StorageConfiguration};
+use crate::storage::StorageNonBlocking;
 use crate::{Database, Storage};
 
 macro_rules! define_local_suite {
@@ -34,11 +35,12 @@ macro_rules! define_local_suite {
                     async fn new(test: HarnessTest) -> anyhow::Result<Self> {
                         let directory =
                             TestDirectory::new(format!("async-{}-{}", stringify!($name), test));
-                        let mut config =
-                            StorageConfiguration::new(&directory).with_schema::<BasicSchema>()?;
-                        if stringify!($name) == "memory" {
-                            config = config.memory_only()
-                        }
+                        let config = if stringify!($name) == "memory" {
+                            StorageConfiguration::in_memory()
+                        } else {
+                            StorageConfiguration::new(&directory)
+                        };
+                        let mut config = config.with_schema::<BasicSchema>()?;
 
                         #[cfg(feature = "compression")]
                         {
@@ -106,11 +108,12 @@ macro_rules! define_local_suite {
                     fn new(test: HarnessTest) -> anyhow::Result<Self> {
                         let directory =
                             TestDirectory::new(format!("blocking-{}-{}", stringify!($name), test));
-                        let mut config =
-                            StorageConfiguration::new(&directory).with_schema::<BasicSchema>()?;
-                        if stringify!($name) == "memory" {
-                            config = config.memory_only()
-                        }
+                        let config = if stringify!($name) == "memory" {
+                            StorageConfiguration::in_memory()
+                        } else {
+                            StorageConfiguration::new(&directory)
+                        };
+                        let mut config = config.with_schema::<BasicSchema>()?;
 
                         #[cfg(feature = "compression")]
                         {
@@ -169,6 +172,17 @@ macro_rules! define_local_suite {
 define_local_suite!(persisted);
 define_local_suite!(memory);
 
+#[cfg(feature = "async")]
+mod in_process {
+    use bonsaidb_core::test_util::InProcessTestHarness;
+
+    use crate::AsyncStorage;
+
+    type InProcessHarness = InProcessTestHarness<AsyncStorage>;
+
+    bonsaidb_core::define_async_connection_test_suite!(InProcessHarness);
+}
+
 #[test]
 #[cfg_attr(not(feature = "compression"), allow(unused_mut))]
 fn integrity_checks() -> anyhow::Result<()> {
@@ -318,3 +332,2275 @@ fn expiration_after_close() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[test]
+fn views_including_soft_deleted() -> anyhow::Result<()> {
+    use bonsaidb_core::document::{CollectionDocument, Emit};
+    use bonsaidb_core::schema::view::ViewUpdatePolicy;
+    use bonsaidb_core::schema::{
+        Collection, CollectionMapReduce, SerializedCollection, View, ViewMapResult, ViewSchema,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "articles", views = [Published, Archive, EagerArchive], soft_delete, core = bonsaidb_core)]
+    struct Article {
+        title: String,
+    }
+
+    #[derive(View, ViewSchema, Debug, Clone)]
+    #[view(collection = Article, key = String, value = (), name = "published", core = bonsaidb_core)]
+    #[view_schema(core = bonsaidb_core)]
+    struct Published;
+
+    impl CollectionMapReduce for Published {
+        fn map<'doc>(
+            &self,
+            document: CollectionDocument<<Self::View as View>::Collection>,
+        ) -> ViewMapResult<'doc, Self::View> {
+            document.header.emit_key(document.contents.title)
+        }
+    }
+
+    #[derive(View, Debug, Clone)]
+    #[view(collection = Article, key = String, value = (), name = "archive", core = bonsaidb_core)]
+    struct Archive;
+
+    impl ViewSchema for Archive {
+        type MappedKey<'doc> = String;
+        type View = Self;
+
+        fn include_soft_deleted(&self) -> bool {
+            true
+        }
+    }
+
+    impl CollectionMapReduce for Archive {
+        fn map<'doc>(
+            &self,
+            document: CollectionDocument<<Self::View as View>::Collection>,
+        ) -> ViewMapResult<'doc, Self::View> {
+            document.header.emit_key(document.contents.title)
+        }
+    }
+
+    #[derive(View, Debug, Clone)]
+    #[view(collection = Article, key = String, value = (), name = "eager-archive", core = bonsaidb_core)]
+    struct EagerArchive;
+
+    impl ViewSchema for EagerArchive {
+        type MappedKey<'doc> = String;
+        type View = Self;
+
+        fn update_policy(&self) -> ViewUpdatePolicy {
+            ViewUpdatePolicy::Eager
+        }
+
+        fn include_soft_deleted(&self) -> bool {
+            true
+        }
+    }
+
+    impl CollectionMapReduce for EagerArchive {
+        fn map<'doc>(
+            &self,
+            document: CollectionDocument<<Self::View as View>::Collection>,
+        ) -> ViewMapResult<'doc, Self::View> {
+            document.header.emit_key(document.contents.title)
+        }
+    }
+
+    fn titles<V: bonsaidb_core::schema::SerializedView<Key = String>>(
+        db: &Database,
+    ) -> anyhow::Result<Vec<String>> {
+        Ok(db
+            .view::<V>()
+            .query()?
+            .into_iter()
+            .map(|mapping| mapping.key)
+            .collect())
+    }
+
+    let path = TestDirectory::new("views-including-soft-deleted");
+    let db = Database::open::<Article>(StorageConfiguration::new(&path))?;
+    let a = Article {
+        title: String::from("a"),
+    }
+    .push_into(&db)?;
+    let b = Article {
+        title: String::from("b"),
+    }
+    .push_into(&db)?;
+    assert_eq!(titles::<Published>(&db)?, ["a", "b"]);
+    assert_eq!(titles::<Archive>(&db)?, ["a", "b"]);
+
+    // Only the views that opt in continue to map soft-deleted documents.
+    b.delete(&db)?;
+    assert_eq!(titles::<Published>(&db)?, ["a"]);
+    assert_eq!(titles::<Archive>(&db)?, ["a", "b"]);
+    assert_eq!(titles::<EagerArchive>(&db)?, ["a", "b"]);
+
+    // Rebuilding a view maps the documents that were deleted.
+    db.rebuild_view::<Archive>()?.receive()??;
+    assert_eq!(titles::<Archive>(&db)?, ["a", "b"]);
+
+    Article::restore(&b.header.id, &db)?;
+    assert_eq!(titles::<Published>(&db)?, ["a", "b"]);
+    assert_eq!(titles::<Archive>(&db)?, ["a", "b"]);
+
+    // Permanently discarding a tombstone removes it from every view.
+    a.delete(&db)?;
+    Article::overwrite(
+        &a.header.id,
+        Article {
+            title: String::from("c"),
+        },
+        &db,
+    )?;
+    assert_eq!(titles::<Published>(&db)?, ["b", "c"]);
+    assert_eq!(titles::<Archive>(&db)?, ["b", "c"]);
+    assert_eq!(titles::<EagerArchive>(&db)?, ["b", "c"]);
+
+    Ok(())
+}
+
+#[test]
+fn in_memory_storage() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::StorageConnection;
+    use bonsaidb_core::keyvalue::KeyValue;
+    use bonsaidb_core::schema::SerializedCollection;
+
+    let path = TestDirectory::new("in-memory-storage");
+    let storage = Storage::open(
+        StorageConfiguration::in_memory()
+            .path(&path)
+            .with_schema::<BasicSchema>()?,
+    )?;
+    let db = storage.create_database::<BasicSchema>("tests", false)?;
+    let doc = Basic::new("in memory").push_into(&db)?;
+    db.set_key("key", &1_u32).execute()?;
+    assert_eq!(
+        Basic::get(&doc.header.id, &db)?.unwrap().contents.value,
+        "in memory"
+    );
+
+    // Deleting and recreating a database discards its previous contents.
+    drop(db);
+    storage.delete_database("tests")?;
+    let db = storage.create_database::<BasicSchema>("tests", false)?;
+    assert_eq!(Basic::all(&db).count()?, 0);
+    assert!(db.get_key("key").query()?.is_none());
+    drop(db);
+    drop(storage);
+
+    // Nothing was ever written to disk.
+    assert!(!path.as_ref().exists());
+
+    Ok(())
+}
+
+#[test]
+fn job_metrics() -> anyhow::Result<()> {
+    use std::sync::Arc;
+
+    use crate::metrics::{MetricsRecorder, PrometheusRecorder};
+
+    let path = TestDirectory::new("job-metrics");
+    let recorder = Arc::new(PrometheusRecorder::default());
+    let mut config = StorageConfiguration::new(&path);
+    config.metrics_recorder = Some(recorder.clone());
+    let db = Database::open::<Basic>(config)?;
+    db.collection::<Basic>()
+        .push(&Basic::default().with_parent_id(1))?;
+    // Querying the view requires the view indexing job to complete.
+    assert_eq!(db.view::<BasicByParentId>().query()?.len(), 1);
+
+    let snapshot = recorder.snapshot();
+    assert!(snapshot.contains("# TYPE bonsaidb_jobs_enqueued_total counter"));
+    assert!(!snapshot.contains("bonsaidb_jobs_enqueued_total 0\n"));
+    assert!(!snapshot.contains("bonsaidb_jobs_completed_total 0\n"));
+    assert!(snapshot.contains("bonsaidb_connections_total 0\n"));
+    assert!(snapshot.contains("# TYPE bonsaidb_job_workers gauge"));
+    assert!(!snapshot.contains("bonsaidb_job_workers 0\n"));
+
+    Ok(())
+}
+
+#[test]
+fn scale_task_workers() -> anyhow::Result<()> {
+    let path = TestDirectory::new("scale-task-workers");
+    let mut config = StorageConfiguration::new(&path).with_schema::<Basic>()?;
+    config.workers.worker_count = 4;
+    let storage = Storage::open(config)?;
+    assert_eq!(storage.tasks_status().worker_count, 4);
+
+    storage.set_tasks_worker_count(1);
+    let db = storage.create_database::<Basic>("tests", false)?;
+    db.collection::<Basic>()
+        .push(&Basic::default().with_parent_id(1))?;
+    // The remaining worker still updates views.
+    assert_eq!(db.view::<BasicByParentId>().query()?.len(), 1);
+    // Busy workers exit after their current job completes.
+    while storage.tasks_status().worker_count > 1 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn pubsub_retention() -> anyhow::Result<()> {
+    use bonsaidb_core::pubsub::{PubSub, Subscriber, TryReceiveError};
+
+    use crate::config::PubSubRetention;
+
+    let path = TestDirectory::new("pubsub-retention");
+    let mut config = StorageConfiguration::new(&path);
+    config
+        .pubsub_retention
+        .insert(pot::to_vec(&"retained")?, PubSubRetention::Count(2));
+    let db = Database::open::<Basic>(config)?;
+    for value in 0_u32..3 {
+        db.publish(&"retained", &value)?;
+        db.publish(&"unretained", &value)?;
+    }
+
+    let subscriber = db.create_subscriber()?;
+    subscriber.subscribe_to_with_replay(&"retained")?;
+    subscriber.subscribe_to_with_replay(&"unretained")?;
+    db.publish(&"retained", &3_u32)?;
+
+    // Only the two most recent messages are replayed, and they are received
+    // before the message published after subscribing.
+    for expected in 1_u32..=3 {
+        let message = subscriber.receiver().receive()?;
+        assert_eq!(message.topic::<String>()?, "retained");
+        assert_eq!(message.payload::<u32>()?, expected);
+    }
+    assert!(matches!(
+        subscriber.receiver().try_receive(),
+        Err(TryReceiveError::Empty)
+    ));
+
+    // Subscribing without replay only receives new messages.
+    let subscriber = db.create_subscriber()?;
+    subscriber.subscribe_to(&"retained")?;
+    assert!(matches!(
+        subscriber.receiver().try_receive(),
+        Err(TryReceiveError::Empty)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn pubsub_buffer_limits() -> anyhow::Result<()> {
+    use bonsaidb_core::changes::{change_feed_topic, Change, ChangeOperation};
+    use bonsaidb_core::pubsub::{BufferLimit, PubSub, Subscriber, TryReceiveError};
+    use bonsaidb_core::schema::{Collection, SerializedCollection};
+    use futures::StreamExt;
+
+    let path = TestDirectory::new("pubsub-buffer-limits");
+    let db = Database::open::<Basic>(StorageConfiguration::new(&path))?;
+
+    // When the buffer is full, the oldest messages are dropped and counted.
+    let subscriber = db.create_subscriber_with_limit(BufferLimit::drop_oldest(2))?;
+    subscriber.subscribe_to(&"lossy")?;
+    for value in 0_u32..5 {
+        db.publish(&"lossy", &value)?;
+    }
+    let mut receiver = subscriber.receiver().clone();
+    let received = futures::executor::block_on(async {
+        let mut received = Vec::new();
+        for _ in 0..2 {
+            let message = StreamExt::next(&mut receiver).await.unwrap();
+            received.push(message.payload::<u32>().unwrap());
+        }
+        received
+    });
+    assert_eq!(received, vec![3, 4]);
+    assert_eq!(receiver.take_lagged(), 3);
+    assert_eq!(receiver.take_lagged(), 0);
+
+    // When the buffer is full, publishers wait until there is room.
+    let subscriber = db.create_subscriber_with_limit(BufferLimit::backpressure(1))?;
+    subscriber.subscribe_to(&"lossless")?;
+    let publisher = std::thread::spawn({
+        let db = db.clone();
+        move || {
+            for value in 0_u32..5 {
+                db.publish(&"lossless", &value).unwrap();
+            }
+        }
+    });
+    for expected in 0_u32..5 {
+        let message = subscriber.receiver().receive()?;
+        assert_eq!(message.payload::<u32>()?, expected);
+    }
+    publisher.join().unwrap();
+    assert!(matches!(
+        subscriber.receiver().try_receive(),
+        Err(TryReceiveError::Empty)
+    ));
+    assert_eq!(subscriber.receiver().take_lagged(), 0);
+
+    // Change events never wait for the subscriber to make room, so the
+    // transaction completes and the missed event is counted.
+    let subscriber = db.create_subscriber_with_limit(BufferLimit::backpressure(1))?;
+    subscriber.subscribe_to(&change_feed_topic(&Basic::collection_name()))?;
+    Basic::new("first").push_into(&db)?;
+    Basic::new("second").push_into(&db)?;
+    let change = subscriber.receiver().receive()?.payload::<Change>()?;
+    assert_eq!(change.operation, ChangeOperation::Insert);
+    assert!(matches!(
+        subscriber.receiver().try_receive(),
+        Err(TryReceiveError::Empty)
+    ));
+    assert_eq!(subscriber.receiver().take_lagged(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn unique_index() -> anyhow::Result<()> {
+    use bonsaidb_core::document::DocumentId;
+    use bonsaidb_core::schema::{
+        Collection, CollectionName, DefaultSerialization, Schematic, SerializedCollection, ViewName,
+    };
+    use bonsaidb_core::Error;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+    struct User {
+        username: String,
+    }
+
+    impl Collection for User {
+        type PrimaryKey = u64;
+
+        fn collection_name() -> CollectionName {
+            CollectionName::new("khonsulabs", "users")
+        }
+
+        fn define_views(schema: &mut Schematic) -> Result<(), Error> {
+            schema.define_unique_index::<Self, _>("unique-username", |user| user.username.clone())
+        }
+    }
+
+    impl DefaultSerialization for User {}
+
+    let user = |username: &str| User {
+        username: username.to_string(),
+    };
+    let path = TestDirectory::new("unique-index");
+    let db = Database::open::<User>(StorageConfiguration::new(&path))?;
+    let mut ecton = user("ecton").push_into(&db)?;
+
+    // Inserting a second user with the same username fails, reporting the
+    // index that was violated.
+    let err = user("ecton").push_into(&db).unwrap_err();
+    let Error::UniqueKeyViolation {
+        view,
+        existing_document,
+        ..
+    } = err.error
+    else {
+        unreachable!("unexpected error: {:?}", err.error)
+    };
+    assert_eq!(
+        view,
+        ViewName::new(User::collection_name(), "unique-username")?
+    );
+    assert_eq!(existing_document.id, DocumentId::from_u64(ecton.header.id));
+    assert_eq!(User::all(&db).count()?, 1);
+
+    // Updates are also checked, and the rejected update isn't saved.
+    let mut other = user("daxpedda").push_into(&db)?;
+    other.contents.username = String::from("ecton");
+    assert!(matches!(
+        other.update(&db),
+        Err(Error::UniqueKeyViolation { .. })
+    ));
+    let other = User::get(&other.header.id, &db)?.unwrap();
+    assert_eq!(other.contents, user("daxpedda"));
+
+    // Changing a username frees the previous username.
+    ecton.contents.username = String::from("ecton2");
+    ecton.update(&db)?;
+    user("ecton").push_into(&db)?;
+
+    Ok(())
+}
+
+#[test]
+fn collection_migration() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::{Collection, SerializedCollection};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "people", core = bonsaidb_core)]
+    struct PersonV0 {
+        name: String,
+    }
+
+    #[derive(Collection, Serialize, Deserialize, Debug, Eq, PartialEq)]
+    #[collection(name = "people", version = 1, migrate = Self::migrate_v0, core = bonsaidb_core)]
+    struct Person {
+        first_name: String,
+        last_name: String,
+    }
+
+    impl Person {
+        fn new(first_name: &str, last_name: &str) -> Self {
+            Self {
+                first_name: first_name.to_string(),
+                last_name: last_name.to_string(),
+            }
+        }
+
+        fn migrate_v0(from: u32, contents: &[u8]) -> Result<Vec<u8>, bonsaidb_core::Error> {
+            assert_eq!(from, 0);
+            let person = PersonV0::deserialize(contents)?;
+            let (first_name, last_name) = person
+                .name
+                .split_once(' ')
+                .unwrap_or((person.name.as_str(), ""));
+            Self::serialize(&Self::new(first_name, last_name))
+        }
+    }
+
+    let path = TestDirectory::new("collection-migration");
+    let config = StorageConfiguration::new(&path);
+    let (ada, grace) = {
+        let db = Database::open::<PersonV0>(config.clone())?;
+        let ada = PersonV0 {
+            name: String::from("Ada Lovelace"),
+        }
+        .push_into(&db)?;
+        let grace = PersonV0 {
+            name: String::from("Grace Hopper"),
+        }
+        .push_into(&db)?;
+        (ada.header.id, grace.header.id)
+    };
+
+    let db = Database::open::<Person>(config)?;
+    // Reading a document stored with an older version migrates it lazily.
+    let ada = Person::get(&ada, &db)?.expect("document not found");
+    assert_eq!(ada.contents, Person::new("Ada", "Lovelace"));
+
+    // Eagerly migrating rewrites both documents, after which there is nothing
+    // left to migrate.
+    assert_eq!(db.migrate_collection::<Person>()?, 2);
+    assert_eq!(db.migrate_collection::<Person>()?, 0);
+    let grace = Person::get(&grace, &db)?.expect("document not found");
+    assert_eq!(grace.contents, Person::new("Grace", "Hopper"));
+
+    // Documents written with the current version are read unmodified.
+    let alan = Person::new("Alan", "Turing").push_into(&db)?;
+    assert_eq!(db.migrate_collection::<Person>()?, 0);
+    let alan = Person::get(&alan.header.id, &db)?.expect("document not found");
+    assert_eq!(alan.contents, Person::new("Alan", "Turing"));
+
+    Ok(())
+}
+
+#[test]
+fn collection_validation() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::{Collection, SerializedCollection, ValidationError};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "people", validate = Self::check, core = bonsaidb_core)]
+    struct Person {
+        name: String,
+    }
+
+    impl Person {
+        fn new(name: &str) -> Self {
+            Self {
+                name: name.to_string(),
+            }
+        }
+
+        fn check(&self) -> Result<(), ValidationError> {
+            if self.name.is_empty() {
+                Err(ValidationError::new("name must not be empty"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    fn is_validation_error(error: &bonsaidb_core::Error) -> bool {
+        matches!(
+            error,
+            bonsaidb_core::Error::ValidationFailed { collection, message }
+                if collection == &Person::collection_name() && message == "name must not be empty"
+        )
+    }
+
+    let path = TestDirectory::new("collection-validation");
+    let db = Database::open::<Person>(StorageConfiguration::new(&path))?;
+    let mut ada = Person::new("Ada").push_into(&db)?;
+
+    let err = Person::new("").push_into(&db).unwrap_err();
+    assert!(is_validation_error(&err.error));
+
+    // A transaction containing an invalid document is rejected entirely.
+    let err = Person::insert_many([Person::new("Grace"), Person::new("")], &db).unwrap_err();
+    assert!(is_validation_error(&err.error));
+    assert_eq!(Person::all(&db).count()?, 1);
+
+    // Updates are also validated.
+    ada.contents.name.clear();
+    assert!(is_validation_error(&ada.update(&db).unwrap_err()));
+    let ada = Person::get(&ada.header.id, &db)?.expect("document not found");
+    assert_eq!(ada.contents.name, "Ada");
+
+    Ok(())
+}
+
+#[test]
+fn collection_triggers() -> anyhow::Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    use bonsaidb_core::changes::{ChangeEvent, ChangeOperation};
+    use bonsaidb_core::schema::{
+        Collection, CollectionName, DefaultSerialization, Name, Schematic, SerializedCollection,
+        Trigger,
+    };
+    use nebari::tree::Unversioned;
+    use serde::{Deserialize, Serialize};
+
+    use crate::database::document_changes_tree_name;
+
+    type RecordedChange = (ChangeOperation, u64, Option<String>);
+
+    static CHANGES: Mutex<Vec<RecordedChange>> = Mutex::new(Vec::new());
+    static FAILED_ONCE: AtomicBool = AtomicBool::new(false);
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Note {
+        text: String,
+    }
+
+    impl Collection for Note {
+        type PrimaryKey = u64;
+
+        fn collection_name() -> CollectionName {
+            CollectionName::new("khonsulabs", "trigger-notes")
+        }
+
+        fn define_views(schema: &mut Schematic) -> Result<(), bonsaidb_core::Error> {
+            schema.define_trigger(RecordChanges)
+        }
+    }
+
+    impl DefaultSerialization for Note {}
+
+    #[derive(Debug)]
+    struct RecordChanges;
+
+    impl Trigger for RecordChanges {
+        type Collection = Note;
+
+        fn name(&self) -> Name {
+            Name::new("record-changes")
+        }
+
+        fn on_change(&self, change: ChangeEvent<Note>) -> Result<(), bonsaidb_core::Error> {
+            // Fail the first delivery to ensure it is retried.
+            if !FAILED_ONCE.swap(true, Ordering::SeqCst) {
+                return Err(bonsaidb_core::Error::other("test", "transient failure"));
+            }
+            CHANGES.lock().unwrap().push((
+                change.operation,
+                change.id,
+                change.contents.map(|note| note.text),
+            ));
+            Ok(())
+        }
+    }
+
+    fn wait_for_changes(count: usize) -> Vec<RecordedChange> {
+        for _ in 0..500 {
+            let changes = CHANGES.lock().unwrap().clone();
+            if changes.len() >= count {
+                return changes;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        unreachable!("trigger did not receive {count} changes")
+    }
+
+    fn wait_for_idle(db: &Database) {
+        for _ in 0..500 {
+            let status = db.storage().tasks_status();
+            if status.queue_depth == 0 && status.busy_workers == 0 {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        unreachable!("tasks did not finish")
+    }
+
+    let path = TestDirectory::new("collection-triggers");
+    let db = Database::open::<Note>(StorageConfiguration::new(&path))?;
+    let mut note = Note {
+        text: String::from("first"),
+    }
+    .push_into(&db)?;
+    let id = note.header.id;
+    // Each change contains the contents written by its transaction, even
+    // though the document is deleted before the trigger executes.
+    note.contents.text = String::from("second");
+    note.update(&db)?;
+    note.delete(&db)?;
+    assert_eq!(
+        wait_for_changes(3),
+        vec![
+            (ChangeOperation::Insert, id, Some(String::from("first"))),
+            (ChangeOperation::Update, id, Some(String::from("second"))),
+            (ChangeOperation::Delete, id, None),
+        ]
+    );
+    wait_for_idle(&db);
+    // The recorded changes are removed once the trigger has processed them.
+    let recorded = db
+        .roots()
+        .tree(db.collection_tree::<Unversioned, _>(
+            &Note::collection_name(),
+            document_changes_tree_name(&Note::collection_name()),
+        )?)?
+        .get_range(&(..))?;
+    assert!(recorded.is_empty());
+    drop(db);
+
+    // Reopening the database doesn't deliver the changes again.
+    let db = Database::open::<Note>(StorageConfiguration::new(&path))?;
+    let third = Note {
+        text: String::from("third"),
+    }
+    .push_into(&db)?;
+    let changes = wait_for_changes(4);
+    assert_eq!(
+        changes[3..],
+        [(
+            ChangeOperation::Insert,
+            third.header.id,
+            Some(String::from("third"))
+        )]
+    );
+    wait_for_idle(&db);
+    assert_eq!(CHANGES.lock().unwrap().len(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn virtual_collections() -> anyhow::Result<()> {
+    use std::collections::BTreeMap;
+
+    use bonsaidb_core::schema::virtual_collection::Sources;
+    use bonsaidb_core::schema::{
+        Collection, CollectionName, DefaultSerialization, Schema, Schematic, SerializedCollection,
+        VirtualCollection,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "people", core = bonsaidb_core)]
+    struct Person {
+        name: String,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+    struct Greeting(String);
+
+    impl Collection for Greeting {
+        type PrimaryKey = u64;
+
+        fn collection_name() -> CollectionName {
+            CollectionName::new("khonsulabs", "greetings")
+        }
+
+        fn define_views(schema: &mut Schematic) -> Result<(), bonsaidb_core::Error> {
+            schema.define_virtual_collection::<Self>()
+        }
+    }
+
+    impl DefaultSerialization for Greeting {}
+
+    impl VirtualCollection for Greeting {
+        fn sources() -> Vec<CollectionName> {
+            vec![Person::collection_name()]
+        }
+
+        fn compute(sources: &Sources) -> Result<BTreeMap<u64, Self>, bonsaidb_core::Error> {
+            Ok(sources
+                .documents::<Person>()?
+                .into_iter()
+                .map(|person| {
+                    (
+                        person.header.id,
+                        Greeting(format!("Hello, {}!", person.contents.name)),
+                    )
+                })
+                .collect())
+        }
+    }
+
+    #[derive(Schema, Debug)]
+    #[schema(name = "greetings", collections = [Person, Greeting], core = bonsaidb_core)]
+    struct Greetings;
+
+    fn greetings(db: &Database) -> anyhow::Result<Vec<(u64, String)>> {
+        Ok(Greeting::all(db)
+            .query()?
+            .into_iter()
+            .map(|greeting| (greeting.header.id, greeting.contents.0))
+            .collect())
+    }
+
+    let path = TestDirectory::new("virtual-collections");
+    let db = Database::open::<Greetings>(StorageConfiguration::new(&path))?;
+    let mut ada = Person {
+        name: String::from("Ada"),
+    }
+    .push_into(&db)?;
+    let grace = Person {
+        name: String::from("Grace"),
+    }
+    .push_into(&db)?;
+
+    assert_eq!(
+        greetings(&db)?,
+        vec![
+            (ada.header.id, String::from("Hello, Ada!")),
+            (grace.header.id, String::from("Hello, Grace!")),
+        ]
+    );
+
+    // Modifying a source is reflected by the next read.
+    ada.contents.name = String::from("Ada Lovelace");
+    ada.update(&db)?;
+    grace.delete(&db)?;
+    assert_eq!(
+        Greeting::get(&ada.header.id, &db)?.unwrap().contents,
+        Greeting(String::from("Hello, Ada Lovelace!"))
+    );
+    assert_eq!(Greeting::all(&db).count()?, 1);
+
+    // Unchanged documents aren't rewritten when refreshing.
+    let revision = Greeting::get(&ada.header.id, &db)?.unwrap().header.revision;
+    Person {
+        name: String::from("Hopper"),
+    }
+    .push_into(&db)?;
+    assert_eq!(greetings(&db)?.len(), 2);
+    assert_eq!(
+        Greeting::get(&ada.header.id, &db)?.unwrap().header.revision,
+        revision
+    );
+
+    // Virtual collections can't be modified.
+    assert!(matches!(
+        Greeting(String::from("Hi")).push_into(&db),
+        Err(bonsaidb_core::schema::InsertError {
+            error: bonsaidb_core::Error::ReadOnly,
+            ..
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn document_expiration() -> anyhow::Result<()> {
+    use std::time::Instant;
+
+    use bonsaidb_core::schema::{Collection, SerializedCollection};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "sessions", expire_after = Duration::from_secs(1), core = bonsaidb_core)]
+    struct Session {
+        user: String,
+    }
+
+    // Expired documents aren't returned, even before they've been deleted.
+    let path = TestDirectory::new("document-expiration");
+    let db = Database::open::<Session>(
+        StorageConfiguration::new(&path).document_expiration_interval(Duration::from_secs(60 * 60)),
+    )?;
+    let expired = Session {
+        user: String::from("ecton"),
+    }
+    .push_into(&db)?;
+    std::thread::sleep(Duration::from_millis(1100));
+    let mut current = Session {
+        user: String::from("daxpedda"),
+    }
+    .push_into(&db)?;
+    assert!(Session::get(&expired.header.id, &db)?.is_none());
+    let sessions = Session::get_multiple(&[expired.header.id, current.header.id], &db)?;
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].header.id, current.header.id);
+    let sessions = Session::all(&db).query()?;
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].header.id, current.header.id);
+    assert_eq!(Session::all(&db).count()?, 2);
+
+    // Updating a document restarts its expiration.
+    std::thread::sleep(Duration::from_millis(600));
+    current.contents.user = String::from("khonsulabs");
+    current.update(&db)?;
+    std::thread::sleep(Duration::from_millis(600));
+    assert!(Session::get(&current.header.id, &db)?.is_some());
+
+    // Expired documents are deleted in the background.
+    let path = TestDirectory::new("document-expiration-reaper");
+    let db = Database::open::<Session>(
+        StorageConfiguration::new(&path).document_expiration_interval(Duration::from_millis(50)),
+    )?;
+    let inserted_at = Instant::now();
+    Session {
+        user: String::from("ecton"),
+    }
+    .push_into(&db)?;
+    while Session::all(&db).count()? > 0 {
+        assert!(
+            inserted_at.elapsed() < Duration::from_secs(10),
+            "expired document was never deleted"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    assert!(inserted_at.elapsed() >= Duration::from_secs(1));
+
+    Ok(())
+}
+
+#[test]
+fn document_patch() -> anyhow::Result<()> {
+    use bonsaidb_core::document::DocumentId;
+    use bonsaidb_core::schema::{Collection, DocumentPatch, Schema, SerializedCollection};
+    use bonsaidb_core::transaction::{Operation, Transaction};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Schema, Debug)]
+    #[schema(name = "patching", collections = [Page, Basic], core = bonsaidb_core)]
+    struct PatchingSchema;
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "pages", patch = AddVisits, core = bonsaidb_core)]
+    struct Page {
+        visits: u64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct AddVisits(u64);
+
+    impl DocumentPatch<Page> for AddVisits {
+        fn apply(self, contents: &mut Page) {
+            contents.visits += self.0;
+        }
+    }
+
+    let path = TestDirectory::new("document-patch");
+    let db = Database::open::<PatchingSchema>(StorageConfiguration::new(&path))?;
+    let mut page = Page { visits: 0 }.push_into(&db)?;
+
+    // Patching by id doesn't require knowing the current revision.
+    let header = Page::patch(&page.header.id, &AddVisits(2), &db)?;
+    assert_ne!(header.revision, page.header.revision);
+    let stored = Page::get(&page.header.id, &db)?.expect("document not found");
+    assert_eq!(stored.contents.visits, 2);
+
+    // `page` is now stale, so patching it is rejected.
+    let err = page.patch(AddVisits(1), &db).unwrap_err();
+    assert!(matches!(err, bonsaidb_core::Error::DocumentConflict(..)));
+    assert_eq!(page.contents.visits, 0);
+
+    let mut page = stored;
+    page.patch(AddVisits(3), &db)?;
+    assert_eq!(page.contents.visits, 5);
+    let stored = Page::get(&page.header.id, &db)?.expect("document not found");
+    assert_eq!(stored.header, page.header);
+    assert_eq!(stored.contents.visits, 5);
+
+    let err = Page::patch(&u64::MAX, &AddVisits(1), &db).unwrap_err();
+    assert!(matches!(err, bonsaidb_core::Error::DocumentNotFound(..)));
+
+    // Collections without a registered patch type reject patches.
+    let basic = Basic::new("basic").push_into(&db)?;
+    let err = Transaction::from(Operation::patch(
+        Basic::collection_name(),
+        DocumentId::new(&basic.header.id)?,
+        None,
+        Vec::new(),
+    ))
+    .apply(&db)
+    .unwrap_err();
+    assert!(matches!(err, bonsaidb_core::Error::PatchUnsupported(_)));
+
+    Ok(())
+}
+
+#[test]
+fn clearing_and_deleting_collections() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::{Collection, Schema, SerializedCollection};
+    use bonsaidb_core::test_util::Unique;
+
+    // Shares the name of `BasicSchema`, but no longer contains `Basic`.
+    #[derive(Schema, Debug)]
+    #[schema(name = "basic", collections = [Unique], core = bonsaidb_core)]
+    struct SchemaWithoutBasic;
+
+    let path = TestDirectory::new("clearing-and-deleting-collections");
+    let config = StorageConfiguration::new(&path);
+    {
+        let db = Database::open::<BasicSchema>(config.clone())?;
+        let parent = Basic::new("parent").push_into(&db)?;
+        for child in ["a", "b"] {
+            Basic::new(child)
+                .with_parent_id(parent.header.id)
+                .push_into(&db)?;
+        }
+        assert_eq!(db.view::<BasicByParentId>().query()?.len(), 3);
+
+        assert_eq!(db.clear_collection::<Basic>()?, 3);
+        assert_eq!(Basic::all(&db).count()?, 0);
+        assert!(db.view::<BasicByParentId>().query()?.is_empty());
+        assert_eq!(db.clear_collection::<Basic>()?, 0);
+
+        Basic::new("remaining").push_into(&db)?;
+        assert!(matches!(
+            db.delete_collection(&Basic::collection_name()),
+            Err(bonsaidb_core::Error::Other { .. })
+        ));
+        assert_eq!(Basic::all(&db).count()?, 1);
+    }
+
+    {
+        let db = Database::open::<SchemaWithoutBasic>(config.clone())?;
+        db.delete_collection(&Basic::collection_name())?;
+    }
+
+    let db = Database::open::<BasicSchema>(config)?;
+    assert_eq!(Basic::all(&db).count()?, 0);
+    assert!(db.view::<BasicByParentId>().query()?.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn clearing_collection_during_writes() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::SerializedCollection;
+
+    let path = TestDirectory::new("clearing-collection-during-writes");
+    let db = Database::open::<BasicSchema>(StorageConfiguration::new(&path))?;
+    let writer = std::thread::spawn({
+        let db = db.clone();
+        move || -> anyhow::Result<u64> {
+            let mut inserted = 0;
+            for i in 0..200 {
+                let doc = Basic::new(i.to_string()).push_into(&db)?;
+                inserted += 1;
+                // The document may be cleared before it is updated.
+                if let Some(mut doc) = Basic::get(&doc.header.id, &db)? {
+                    doc.contents.value.push_str("-updated");
+                    match doc.update(&db) {
+                        Ok(())
+                        | Err(
+                            bonsaidb_core::Error::DocumentNotFound(..)
+                            | bonsaidb_core::Error::DocumentConflict(..),
+                        ) => {}
+                        Err(err) => return Err(err.into()),
+                    }
+                }
+            }
+            Ok(inserted)
+        }
+    });
+
+    // Clearing never conflicts with the concurrent writes, and each document
+    // is deleted by exactly one clear.
+    let mut cleared = 0;
+    while !writer.is_finished() {
+        cleared += db.clear_collection::<Basic>()?;
+    }
+    let inserted = writer.join().unwrap()?;
+    cleared += db.clear_collection::<Basic>()?;
+    assert_eq!(cleared, inserted);
+    assert_eq!(Basic::all(&db).count()?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn included_schema_views() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::{Collection, Schema, SerializedCollection};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Schema, Debug)]
+    #[schema(name = "composed", collections = [Note], include = [BasicSchema], core = bonsaidb_core)]
+    struct ComposedSchema;
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "notes", core = bonsaidb_core)]
+    struct Note {
+        text: String,
+    }
+
+    let path = TestDirectory::new("included-schema-views");
+    let db = Database::open::<ComposedSchema>(StorageConfiguration::new(&path))?;
+    Note {
+        text: String::from("hello"),
+    }
+    .push_into(&db)?;
+    let parent = Basic::new("parent").push_into(&db)?;
+    Basic::new("child")
+        .with_parent_id(parent.header.id)
+        .push_into(&db)?;
+
+    // Views defined by the included schema are indexed like any other view.
+    let children = db
+        .view::<BasicByParentId>()
+        .with_key(&Some(parent.header.id))
+        .query_with_collection_docs()?;
+    assert_eq!(children.len(), 1);
+    assert_eq!(
+        children.documents.values().next().unwrap().contents.value,
+        "child"
+    );
+    assert_eq!(Note::all(&db).count()?, 1);
+
+    Ok(())
+}
+
+#[test]
+fn view_sorting_by_value() -> anyhow::Result<()> {
+    use bonsaidb_core::document::{CollectionDocument, Emit};
+    use bonsaidb_core::schema::{
+        Collection, CollectionMapReduce, SerializedCollection, View, ViewMapResult, ViewSchema,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "players", views = [ScoresByName], core = bonsaidb_core)]
+    struct Player {
+        name: String,
+        score: f32,
+    }
+
+    #[derive(View, ViewSchema, Debug, Clone)]
+    #[view(collection = Player, key = String, value = f32, name = "scores-by-name", core = bonsaidb_core)]
+    #[view_schema(core = bonsaidb_core)]
+    struct ScoresByName;
+
+    impl CollectionMapReduce for ScoresByName {
+        fn map<'doc>(
+            &self,
+            document: CollectionDocument<<Self::View as View>::Collection>,
+        ) -> ViewMapResult<'doc, Self::View> {
+            document
+                .header
+                .emit_key_and_value(document.contents.name, document.contents.score)
+        }
+    }
+
+    let path = TestDirectory::new("view-sorting-by-value");
+    let db = Database::open::<Player>(StorageConfiguration::new(&path))?;
+    for (name, score) in [("a", 3.), ("b", 1.), ("c", 4.), ("d", 1.), ("e", 5.)] {
+        Player {
+            name: String::from(name),
+            score,
+        }
+        .push_into(&db)?;
+    }
+
+    let top = db
+        .view::<ScoresByName>()
+        .sort_by_value()
+        .descending()
+        .limit(3)
+        .query()?;
+    let names = top.iter().map(|m| m.key.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["e", "c", "a"]);
+
+    // Equal values are returned in ascending key order.
+    let bottom = db.view::<ScoresByName>().sort_by_value().query()?;
+    let names = bottom.iter().map(|m| m.key.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["b", "d", "a", "c", "e"]);
+
+    // The key filter is applied before sorting, and only the documents of
+    // the returned mappings are loaded.
+    let top = db
+        .view::<ScoresByName>()
+        .with_key_range(String::from("b")..)
+        .sort_by_value()
+        .descending()
+        .limit(2)
+        .query_with_collection_docs()?;
+    assert_eq!(top.len(), 2);
+    assert_eq!(top.documents.len(), 2);
+    let names = (&top)
+        .into_iter()
+        .map(|m| m.document.contents.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["e", "c"]);
+
+    assert!(db
+        .view::<ScoresByName>()
+        .sort_by_value()
+        .limit(2)
+        .query_page()
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn collection_format_conversion() -> anyhow::Result<()> {
+    use bonsaidb_core::key::Key;
+    use bonsaidb_core::schema::{Collection, SerializedCollection};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "counters", core = bonsaidb_core)]
+    struct PotCounter {
+        label: String,
+        count: u64,
+    }
+
+    #[derive(Collection, Key, Clone, Debug, Eq, PartialEq)]
+    #[collection(name = "counters", serialization = Key, core = bonsaidb_core)]
+    #[collection(format_name = "key", convert_format = Self::convert)]
+    #[key(core = bonsaidb_core)]
+    struct Counter {
+        label: String,
+        count: u64,
+    }
+
+    impl Counter {
+        fn new(label: &str, count: u64) -> Self {
+            Self {
+                label: label.to_string(),
+                count,
+            }
+        }
+
+        fn convert(from: Option<&str>, contents: &[u8]) -> Result<Vec<u8>, bonsaidb_core::Error> {
+            assert_eq!(from, None);
+            let counter = PotCounter::deserialize(contents)?;
+            Self::serialize(&Self::new(&counter.label, counter.count))
+        }
+    }
+
+    let path = TestDirectory::new("collection-format-conversion");
+    let config = StorageConfiguration::new(&path);
+    let (visits, errors) = {
+        let db = Database::open::<PotCounter>(config.clone())?;
+        let visits = PotCounter {
+            label: String::from("visits"),
+            count: 42,
+        }
+        .push_into(&db)?;
+        let errors = PotCounter {
+            label: String::from("errors"),
+            count: 1,
+        }
+        .push_into(&db)?;
+        (visits.header.id, errors.header.id)
+    };
+
+    let new_id = {
+        let db = Database::open::<Counter>(config.clone())?;
+        // Reading a document stored with another format converts it lazily.
+        let visits = Counter::get(&visits, &db)?.expect("document not found");
+        assert_eq!(visits.contents, Counter::new("visits", 42));
+
+        assert_eq!(db.migrate_collection::<Counter>()?, 2);
+        assert_eq!(db.migrate_collection::<Counter>()?, 0);
+        let errors = Counter::get(&errors, &db)?.expect("document not found");
+        assert_eq!(errors.contents, Counter::new("errors", 1));
+
+        Counter::new("signups", 7).push_into(&db)?.header.id
+    };
+
+    // The stored format name prevents documents from being decoded using the
+    // wrong format.
+    let db = Database::open::<PotCounter>(config)?;
+    assert!(matches!(
+        PotCounter::get(&new_id, &db),
+        Err(bonsaidb_core::Error::DocumentFormatUnsupported { format: Some(format), .. })
+            if format == "key"
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn database_permissions() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::StorageConnection;
+    use bonsaidb_core::permissions::bonsai::{
+        database_resource_name, BonsaiAction, DatabaseAction, DocumentAction,
+    };
+    use bonsaidb_core::schema::{InsertError, SerializedCollection};
+
+    let path = TestDirectory::new("database-permissions");
+    let config = StorageConfiguration::new(&path).with_schema::<BasicSchema>()?;
+    {
+        let storage = Storage::open(config.clone())?;
+        storage.create_database_with_permissions::<BasicSchema>(
+            "granted",
+            false,
+            vec![
+                Statement::for_resource(database_resource_name("granted")).allowing(
+                    &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Insert)),
+                ),
+            ],
+        )?;
+        storage.create_database::<BasicSchema>("restricted", false)?;
+    }
+
+    // The permissions are persisted with the database's record.
+    let storage = Storage::open(config)?;
+    let granted = storage
+        .database::<BasicSchema>("granted")?
+        .with_effective_permissions(Permissions::default())
+        .unwrap();
+    Basic::new("allowed").push_into(&granted)?;
+
+    let restricted = storage
+        .database::<BasicSchema>("restricted")?
+        .with_effective_permissions(Permissions::default())
+        .unwrap();
+    assert!(matches!(
+        Basic::new("denied").push_into(&restricted),
+        Err(InsertError {
+            error: bonsaidb_core::Error::PermissionDenied(_),
+            ..
+        })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn change_feed_permissions() -> anyhow::Result<()> {
+    use bonsaidb_core::changes::{change_feed_topic, Change};
+    use bonsaidb_core::connection::StorageConnection;
+    use bonsaidb_core::document::DocumentId;
+    use bonsaidb_core::permissions::bonsai::{
+        document_resource_name, BonsaiAction, DatabaseAction, DocumentAction, PubSubAction,
+    };
+    use bonsaidb_core::pubsub::{PubSub, Subscriber, TryReceiveError};
+    use bonsaidb_core::schema::{Collection, SerializedCollection};
+
+    let path = TestDirectory::new("change-feed-permissions");
+    let storage = Storage::open(StorageConfiguration::new(&path).with_schema::<BasicSchema>()?)?;
+    let db = storage.create_database::<BasicSchema>("changes", false)?;
+    let mut visible = Basic::new("visible").push_into(&db)?;
+    let mut hidden = Basic::new("hidden").push_into(&db)?;
+
+    let visible_id = DocumentId::from_u64(visible.header.id);
+    let topic = change_feed_topic(&Basic::collection_name());
+    // Change feed topics are reserved, even for connections that are allowed
+    // to publish to any topic.
+    assert!(matches!(
+        db.publish(&topic, &()),
+        Err(bonsaidb_core::Error::ReservedTopic)
+    ));
+
+    let watcher = db
+        .with_effective_permissions(Permissions::from(vec![
+            Statement::for_any()
+                .allowing(&BonsaiAction::Database(DatabaseAction::PubSub(
+                    PubSubAction::CreateSuscriber,
+                )))
+                .allowing(&BonsaiAction::Database(DatabaseAction::PubSub(
+                    PubSubAction::SubscribeTo,
+                ))),
+            Statement::for_resource(document_resource_name(
+                "changes",
+                &Basic::collection_name(),
+                &visible_id,
+            ))
+            .allowing(&BonsaiAction::Database(DatabaseAction::Document(
+                DocumentAction::Get,
+            ))),
+        ]))
+        .unwrap();
+    let subscriber = watcher.create_subscriber()?;
+    subscriber.subscribe_to(&topic)?;
+
+    // Only changes to documents the subscriber is allowed to get are
+    // delivered.
+    hidden.contents.value = String::from("hidden2");
+    hidden.update(&db)?;
+    visible.contents.value = String::from("visible2");
+    visible.update(&db)?;
+    let change = subscriber.receiver().receive()?.payload::<Change>()?;
+    assert_eq!(change.id, visible_id);
+    assert!(matches!(
+        subscriber.receiver().try_receive(),
+        Err(TryReceiveError::Empty)
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn permission_group_inheritance() -> anyhow::Result<()> {
+    use bonsaidb_core::admin::{PermissionGroup, User};
+    use bonsaidb_core::connection::StorageConnection;
+    use bonsaidb_core::permissions::bonsai::{
+        database_resource_name, BonsaiAction, DatabaseAction, DocumentAction,
+    };
+    use bonsaidb_core::schema::SerializedCollection;
+
+    let path = TestDirectory::new("permission-group-inheritance");
+    let storage = Storage::open(StorageConfiguration::new(&path))?;
+    let admin = storage.admin();
+
+    let insert = BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Insert));
+    let delete = BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Delete));
+    let mut root = PermissionGroup::named("root")
+        .with_group_ids([
+            Statement::for_resource(database_resource_name("tenant")).allowing(&insert)
+        ])
+        .push_into(&admin)?;
+    let middle = PermissionGroup::named("middle")
+        .with_parent_group(root.header.id)
+        .push_into(&admin)?;
+    let leaf = PermissionGroup::named("leaf")
+        .with_group_ids([
+            Statement::for_resource(database_resource_name("tenant")).allowing(&delete)
+        ])
+        .with_parent_group(middle.header.id)
+        .push_into(&admin)?;
+
+    // Members of the leaf group inherit the statements of every ancestor.
+    let user_id = storage.create_user("tenant-user")?;
+    storage.add_permission_group_to_user(user_id, leaf.header.id)?;
+    let user = User::get(&user_id, &admin)?.unwrap();
+    let permissions = user
+        .contents
+        .effective_permissions(&admin, &Permissions::default())?;
+    assert!(permissions.allowed_to(database_resource_name("tenant"), &insert));
+    assert!(permissions.allowed_to(database_resource_name("tenant"), &delete));
+    assert!(!permissions.allowed_to(database_resource_name("other"), &insert));
+
+    // Making the root group inherit from one of its descendants is rejected.
+    assert!(matches!(
+        PermissionGroup::set_parent_group(&mut root, Some(leaf.header.id), &admin),
+        Err(bonsaidb_core::Error::PermissionGroupCycle(id)) if id == root.header.id
+    ));
+    assert!(matches!(
+        PermissionGroup::set_parent_group(&mut root, Some(root.header.id), &admin),
+        Err(bonsaidb_core::Error::PermissionGroupCycle(_))
+    ));
+    assert_eq!(
+        PermissionGroup::get(&root.header.id, &admin)?
+            .unwrap()
+            .contents
+            .parent_group,
+        None
+    );
+
+    Ok(())
+}
+
+#[test]
+fn seeded_rng_is_reproducible() {
+    use bonsaidb_core::test_util::{seeded_rng, HarnessTest};
+    use rand::Rng;
+
+    let values = seeded_rng("values").gen::<[u64; 4]>();
+    assert_eq!(values, seeded_rng("values").gen::<[u64; 4]>());
+    assert_ne!(values, seeded_rng("other-values").gen::<[u64; 4]>());
+    assert_eq!(
+        HarnessTest::KvBasic.rng().gen::<u64>(),
+        seeded_rng("KvBasic").gen::<u64>()
+    );
+    // Once chosen, the seed can't be changed.
+    assert!(!HarnessTest::set_seed(HarnessTest::seed().wrapping_add(1)));
+}
+
+#[test]
+fn view_multiple_emits() -> anyhow::Result<()> {
+    use bonsaidb_core::document::{CollectionDocument, Emit};
+    use bonsaidb_core::schema::{
+        Collection, CollectionMapReduce, ReduceResult, SerializedCollection, View, ViewMapResult,
+        ViewMappedValue, ViewSchema,
+    };
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "articles", views = [ArticlesByTag], core = bonsaidb_core)]
+    struct Article {
+        tags: Vec<String>,
+    }
+
+    #[derive(View, ViewSchema, Debug, Clone)]
+    #[view(collection = Article, key = String, value = u32, name = "by-tag", core = bonsaidb_core)]
+    #[view_schema(core = bonsaidb_core)]
+    struct ArticlesByTag;
+
+    impl CollectionMapReduce for ArticlesByTag {
+        fn map<'doc>(
+            &self,
+            document: CollectionDocument<<Self::View as View>::Collection>,
+        ) -> ViewMapResult<'doc, Self::View> {
+            document
+                .header
+                .emit_all(document.contents.tags.into_iter().map(|tag| (tag, 1)))
+        }
+
+        fn reduce(
+            &self,
+            mappings: &[ViewMappedValue<'_, Self::View>],
+            _rereduce: bool,
+        ) -> ReduceResult<Self::View> {
+            Ok(mappings.iter().map(|map| map.value).sum())
+        }
+    }
+
+    let path = TestDirectory::new("view-multiple-emits");
+    let db = Database::open::<Article>(StorageConfiguration::new(&path))?;
+    let mut article = Article {
+        tags: vec![String::from("a"), String::from("b"), String::from("c")],
+    }
+    .push_into(&db)?;
+    Article {
+        tags: vec![String::from("b")],
+    }
+    .push_into(&db)?;
+
+    let tags = |db: &Database| -> anyhow::Result<Vec<(String, u32)>> {
+        Ok(db
+            .view::<ArticlesByTag>()
+            .reduce_grouped()?
+            .into_iter()
+            .map(|entry| (entry.key, entry.value))
+            .collect())
+    };
+    assert_eq!(
+        tags(&db)?,
+        [
+            (String::from("a"), 1),
+            (String::from("b"), 2),
+            (String::from("c"), 1)
+        ]
+    );
+    assert_eq!(db.view::<ArticlesByTag>().with_key("a").query()?.len(), 1);
+
+    // Emitting fewer keys removes the document from the keys that are no
+    // longer emitted.
+    article.contents.tags = vec![String::from("b"), String::from("d")];
+    article.update(&db)?;
+    assert_eq!(
+        tags(&db)?,
+        [
+            (String::from("b"), 2),
+            (String::from("c"), 1),
+            (String::from("d"), 1)
+        ]
+    );
+    assert!(db.view::<ArticlesByTag>().with_key("a").query()?.is_empty());
+
+    article.contents.tags = vec![String::from("d")];
+    article.update(&db)?;
+    assert_eq!(tags(&db)?, [(String::from("b"), 1), (String::from("d"), 1)]);
+    let mappings = db.view::<ArticlesByTag>().query()?;
+    assert_eq!(mappings.len(), 2);
+    assert!(mappings
+        .iter()
+        .any(|mapping| mapping.key == "d" && mapping.source.id == article.header.id));
+
+    Ok(())
+}
+
+#[test]
+fn storage_compaction() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::StorageConnection;
+    use bonsaidb_core::schema::SerializedCollection;
+
+    let path = TestDirectory::new("storage-compaction");
+    let storage = Storage::open(StorageConfiguration::new(&path).with_schema::<BasicSchema>()?)?;
+    let db = storage.create_database::<BasicSchema>("compaction", false)?;
+
+    // Overwriting a document repeatedly leaves old revisions on disk until
+    // the collection is compacted.
+    let mut doc = Basic::new("initial").push_into(&db)?;
+    for i in 0..100 {
+        doc.contents.value = format!("revision {i}");
+        doc.update(&db)?;
+    }
+
+    assert!(storage.compact_collection::<Basic>()? > 0);
+    let doc = Basic::get(&doc.header.id, &db)?.expect("document missing");
+    assert_eq!(doc.contents.value, "revision 99");
+
+    storage.compact()?;
+
+    Ok(())
+}
+
+#[test]
+fn uuid_primary_keys() -> anyhow::Result<()> {
+    use bonsaidb_core::document::DocumentId;
+    use bonsaidb_core::schema::{Collection, SerializedCollection};
+    use bonsaidb_core::uuid::Uuid;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "sessions", primary_key = Uuid, core = bonsaidb_core)]
+    struct UserSession {
+        user: String,
+    }
+
+    let path = TestDirectory::new("uuid-primary-keys");
+    let db = Database::open::<UserSession>(StorageConfiguration::new(&path))?;
+    let ids = [
+        Uuid::from_u128(3 << 64),
+        Uuid::from_u128(1),
+        Uuid::from_u128(2 << 64 | 5),
+    ];
+    for (index, id) in ids.iter().enumerate() {
+        UserSession {
+            user: format!("user {index}"),
+        }
+        .insert_into(id, &db)?;
+    }
+
+    let session = UserSession::get(&ids[0], &db)?.expect("session missing");
+    assert_eq!(session.header.id, ids[0]);
+    assert_eq!(session.contents.user, "user 0");
+
+    // Primary keys are ordered by their values.
+    let listed = UserSession::all(&db)
+        .query()?
+        .into_iter()
+        .map(|session| session.header.id)
+        .collect::<Vec<_>>();
+    let mut sorted = ids.to_vec();
+    sorted.sort();
+    assert_eq!(listed, sorted);
+    let listed = UserSession::list(ids[2].., &db)
+        .query()?
+        .into_iter()
+        .map(|session| session.header.id)
+        .collect::<Vec<_>>();
+    assert_eq!(listed, [ids[2], ids[0]]);
+
+    // Document ids convert to and from their primary key types.
+    let document_id = DocumentId::from(ids[1]);
+    assert_eq!(Uuid::try_from(&document_id)?, ids[1]);
+    assert_eq!(u64::try_from(&DocumentId::from(42_u64))?, 42);
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "password-hashing")]
+fn password_policy() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::{SensitiveString, StorageConnection};
+
+    use crate::config::PasswordPolicy;
+
+    let storage = Storage::open(StorageConfiguration::in_memory().password_policy(
+        PasswordPolicy {
+            min_length: 8,
+            require_mixed_case: true,
+            require_digit: true,
+            require_symbol: false,
+        },
+    ))?;
+    let user_id = storage.create_user("ecton")?;
+
+    for weak in ["Sh0rt", "alllowercase1", "NoDigitsHere"] {
+        assert!(matches!(
+            storage.set_user_password(user_id, SensitiveString::from(weak)),
+            Err(bonsaidb_core::Error::WeakPassword(_))
+        ));
+    }
+    storage.set_user_password(user_id, SensitiveString::from("Hunter2Hunter2"))?;
+
+    Ok(())
+}
+
+#[test]
+fn revision_history() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::StorageConnection;
+    use bonsaidb_core::schema::SerializedCollection;
+
+    let storage = Storage::open(
+        StorageConfiguration::in_memory()
+            .revision_history(2)
+            .with_schema::<BasicSchema>()?,
+    )?;
+    let db = storage.create_database::<BasicSchema>("tests", false)?;
+    let mut doc = Basic::new("first").push_into(&db)?;
+    let history = doc.revision_history(&db)?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].revision, doc.header.revision);
+
+    let mut revisions = vec![doc.header.revision];
+    for value in ["second", "third", "fourth"] {
+        doc.contents.value = String::from(value);
+        doc.update(&db)?;
+        revisions.push(doc.header.revision);
+    }
+
+    // Only the two most recent revisions are retained.
+    let history = doc.revision_history(&db)?;
+    assert_eq!(
+        history
+            .iter()
+            .map(|historical| historical.revision)
+            .collect::<Vec<_>>(),
+        &revisions[2..]
+    );
+    assert!(history[0].transaction_id < history[1].transaction_id);
+    assert!(history
+        .iter()
+        .all(|historical| historical.timestamp.is_some()));
+
+    // History remains available after the document is deleted.
+    doc.delete(&db)?;
+    assert_eq!(doc.revision_history(&db)?, history);
+
+    // Without revision history, only the current revision is returned.
+    let storage = Storage::open(StorageConfiguration::in_memory().with_schema::<BasicSchema>()?)?;
+    let db = storage.create_database::<BasicSchema>("tests", false)?;
+    let mut doc = Basic::new("first").push_into(&db)?;
+    doc.contents.value = String::from("second");
+    doc.update(&db)?;
+    let history = doc.revision_history(&db)?;
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].revision, doc.header.revision);
+    assert_eq!(history[0].transaction_id, None);
+    assert_eq!(history[0].timestamp, None);
+
+    doc.delete(&db)?;
+    assert!(matches!(
+        doc.revision_history(&db),
+        Err(bonsaidb_core::Error::DocumentNotFound(..))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn key_value_max_value_size() -> anyhow::Result<()> {
+    use bonsaidb_core::keyvalue::{KeyStatus, KeyValue};
+
+    let path = TestDirectory::new("key-value-max-value-size");
+    let mut config = StorageConfiguration::new(&path);
+    config.key_value_max_value_size = Some(4);
+    let db = Database::open::<Basic>(config)?;
+
+    assert_eq!(
+        db.set_binary_key("small", b"1234").execute()?,
+        KeyStatus::Inserted
+    );
+    assert!(matches!(
+        db.set_binary_key("large", b"12345").execute(),
+        Err(bonsaidb_core::Error::ValueTooLarge {
+            size: 5,
+            maximum: 4
+        })
+    ));
+    assert!(db.get_key("large").query()?.is_none());
+
+    assert_eq!(db.set_key_streaming("small", &b"5"[..])?, 1);
+    assert!(matches!(
+        db.set_key_streaming("large", &b"12345"[..]),
+        Err(bonsaidb_core::Error::ValueTooLarge { .. })
+    ));
+
+    // Numeric values aren't affected by the limit.
+    db.set_numeric_key("numeric", u64::MAX).execute()?;
+
+    Ok(())
+}
+
+#[test]
+fn pubsub_max_payload_size() -> anyhow::Result<()> {
+    use bonsaidb_core::pubsub::{PubSub, Subscriber};
+
+    let path = TestDirectory::new("pubsub-max-payload-size");
+    let mut config = StorageConfiguration::new(&path);
+    config.pubsub_max_payload_size = Some(4);
+    let db = Database::open::<Basic>(config)?;
+    let subscriber = db.create_subscriber()?;
+    subscriber.subscribe_to(&"topic")?;
+
+    let receipt = db.publish_bytes(pot::to_vec(&"topic")?, b"1234".to_vec())?;
+    assert_eq!(receipt.delivered_to, 1);
+    assert!(matches!(
+        db.publish_bytes(pot::to_vec(&"topic")?, b"12345".to_vec()),
+        Err(bonsaidb_core::Error::MessageTooLarge {
+            size: 5,
+            maximum: 4
+        })
+    ));
+    assert!(matches!(
+        db.publish_bytes_to_all([pot::to_vec(&"topic")?], b"12345".to_vec()),
+        Err(bonsaidb_core::Error::MessageTooLarge { .. })
+    ));
+
+    // Only the message within the limit was delivered.
+    subscriber
+        .receiver()
+        .receive()
+        .expect("message not received");
+    assert!(subscriber.receiver().try_receive().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn database_stats() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::StorageConnection;
+    use bonsaidb_core::schema::{Collection, SerializedCollection};
+
+    let path = TestDirectory::new("database-stats");
+    let storage = Storage::open(StorageConfiguration::new(&path).with_schema::<BasicSchema>()?)?;
+    let db = storage.create_database::<BasicSchema>("stats", false)?;
+
+    let empty = storage.database_stats("stats")?;
+    let basic = &empty.collections[&Basic::collection_name()];
+    assert_eq!(basic.document_count, 0);
+    assert_eq!(basic.document_bytes, 0);
+
+    let parent = Basic::new("parent").push_into(&db)?;
+    Basic::new("child")
+        .with_parent_id(parent.header.id)
+        .push_into(&db)?;
+    // Querying the view ensures its index has been built.
+    db.view::<BasicByParentId>().query()?;
+
+    let stats = storage.database_stats("stats")?;
+    let basic = &stats.collections[&Basic::collection_name()];
+    assert_eq!(basic.document_count, 2);
+    assert!(basic.document_bytes > 0);
+    assert!(basic.index_bytes > 0);
+    assert!(stats.total_bytes > 0);
+    // Every collection in the schema is reported, even if it's empty.
+    assert_eq!(
+        stats.collections[&BasicCollectionWithNoViews::collection_name()],
+        Default::default()
+    );
+
+    assert!(matches!(
+        storage.database_stats("missing"),
+        Err(bonsaidb_core::Error::DatabaseNotFound(_))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn rebuild_view() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::{Schema, SerializedCollection, View};
+
+    let path = TestDirectory::new("rebuild-view");
+    let db = Database::open::<BasicSchema>(StorageConfiguration::new(&path))?;
+    let parent = Basic::new("parent").push_into(&db)?;
+    Basic::new("child")
+        .with_parent_id(parent.header.id)
+        .push_into(&db)?;
+    // Build the index once so that the rebuild replaces existing entries.
+    assert_eq!(db.view::<BasicByParentId>().query()?.len(), 2);
+
+    db.rebuild_view::<BasicByParentId>()?.receive()??;
+    let mappings = db
+        .view::<BasicByParentId>()
+        .with_access_policy(AccessPolicy::NoUpdate)
+        .query()?;
+    assert_eq!(mappings.len(), 2);
+    assert_eq!(
+        db.view::<BasicByParentId>()
+            .with_key(&Some(parent.header.id))
+            .with_access_policy(AccessPolicy::NoUpdate)
+            .query()?
+            .len(),
+        1
+    );
+
+    // Rebuilding a view that has no entries succeeds.
+    db.rebuild_view::<BasicByTag>()?.receive()??;
+
+    let views = Basic::views()?;
+    assert_eq!(views.len(), 6);
+    let by_parent_id = views
+        .iter()
+        .find(|view| view.name == BasicByParentId.view_name())
+        .unwrap();
+    assert_eq!(by_parent_id.value_type, "usize");
+    assert!(BasicSchema::views()?.len() > views.len());
+
+    Ok(())
+}
+
+#[test]
+fn integrity_check() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::{SerializedCollection, View};
+
+    use crate::config::IntegrityCheck;
+    use crate::views::view_document_map_tree_name;
+
+    let path = TestDirectory::new("integrity-check");
+    let storage = Storage::open(StorageConfiguration::new(&path).with_schema::<BasicSchema>()?)?;
+    let db = storage.create_database::<BasicSchema>("tests", false)?;
+    let parent = Basic::new("parent").push_into(&db)?;
+    Basic::new("child")
+        .with_parent_id(parent.header.id)
+        .push_into(&db)?;
+    assert_eq!(db.view::<BasicByParentId>().query()?.len(), 2);
+    // Documents that haven't been mapped yet aren't inconsistent.
+    Basic::new("unmapped").push_into(&db)?;
+    assert!(storage.verify()?.is_consistent());
+
+    // Without the document map, the view has no record of which documents
+    // its entries were produced from.
+    db.roots()
+        .delete_tree(view_document_map_tree_name(&BasicByParentId.view_name()))?;
+    let report = storage.verify()?;
+    let inconsistencies = report.inconsistencies().collect::<Vec<_>>();
+    assert_eq!(inconsistencies.len(), 1);
+    assert_eq!(inconsistencies[0].database, "tests");
+    assert_eq!(inconsistencies[0].view, BasicByParentId.view_name());
+    assert_eq!(inconsistencies[0].unmapped_documents, 2);
+    assert_eq!(inconsistencies[0].inconsistent_entries, Some(2));
+    assert!(!inconsistencies[0].repaired);
+    drop(db);
+    drop(storage);
+
+    // Opening the storage with an integrity check repairs the view.
+    let storage = Storage::open(
+        StorageConfiguration::new(&path)
+            .with_schema::<BasicSchema>()?
+            .integrity_check(IntegrityCheck::Full),
+    )?;
+    assert!(storage.verify()?.is_consistent());
+    let db = storage.database::<BasicSchema>("tests")?;
+    assert_eq!(
+        db.view::<BasicByParentId>()
+            .with_access_policy(AccessPolicy::NoUpdate)
+            .query()?
+            .len(),
+        3
+    );
+    assert_eq!(
+        db.view::<BasicByParentId>()
+            .with_key(&Some(parent.header.id))
+            .with_access_policy(AccessPolicy::NoUpdate)
+            .query()?
+            .len(),
+        1
+    );
+
+    Ok(())
+}
+
+#[test]
+fn last_transaction_id_persists() -> anyhow::Result<()> {
+    use bonsaidb_core::schema::SerializedCollection;
+
+    let path = TestDirectory::new("last-transaction-id");
+    let storage = Storage::open(StorageConfiguration::new(&path).with_schema::<BasicSchema>()?)?;
+    let db = storage.create_database::<BasicSchema>("tests", false)?;
+    assert_eq!(db.last_transaction_id()?, None);
+    let doc = Basic::new("first").push_into(&db)?;
+    let first = db.last_transaction_id()?.expect("no transaction id");
+    Basic::new("second").push_into(&db)?;
+    let second = db.last_transaction_id()?.expect("no transaction id");
+    assert!(second > first);
+    drop(db);
+    drop(storage);
+
+    let storage = Storage::open(StorageConfiguration::new(&path).with_schema::<BasicSchema>()?)?;
+    let db = storage.database::<BasicSchema>("tests")?;
+    assert_eq!(db.last_transaction_id()?, Some(second));
+    doc.delete(&db)?;
+    assert!(db.last_transaction_id()?.expect("no transaction id") > second);
+
+    Ok(())
+}
+
+#[test]
+fn read_only() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::StorageConnection;
+    use bonsaidb_core::keyvalue::KeyValue;
+    use bonsaidb_core::schema::SerializedCollection;
+
+    let path = TestDirectory::new("read-only");
+    assert!(Storage::open(StorageConfiguration::new(&path).read_only(true)).is_err());
+
+    let storage = Storage::open(StorageConfiguration::new(&path).with_schema::<BasicSchema>()?)?;
+    let db = storage.create_database::<BasicSchema>("tests", false)?;
+    let first = Basic::new("first").push_into(&db)?;
+    assert_eq!(db.view::<BasicByParentId>().query()?.len(), 1);
+    // This document isn't indexed by the view before the storage is closed.
+    Basic::new("second").push_into(&db)?;
+    db.set_key("key", &1_u32).execute()?;
+
+    // The storage can't be opened read-only while it can be written to.
+    let read_only_config = StorageConfiguration::new(&path)
+        .with_schema::<BasicSchema>()?
+        .read_only(true);
+    assert!(Storage::open(read_only_config.clone()).is_err());
+    drop(db);
+    drop(storage);
+
+    // Multiple read-only instances can be open at the same time.
+    let storage = Storage::open(read_only_config.clone())?;
+    let other = Storage::open(read_only_config)?;
+    let db = storage.database::<BasicSchema>("tests")?;
+    assert!(other.database::<BasicSchema>("tests").is_ok());
+
+    assert_eq!(
+        Basic::get(&first.header.id, &db)?.unwrap().contents.value,
+        "first"
+    );
+    assert_eq!(db.get_key("key").into::<u32>()?, Some(1));
+    // Views are queried as they existed on disk.
+    assert_eq!(db.view::<BasicByParentId>().query()?.len(), 1);
+
+    assert!(matches!(
+        Basic::new("third").push_into(&db),
+        Err(bonsaidb_core::schema::InsertError {
+            error: bonsaidb_core::Error::ReadOnly,
+            ..
+        })
+    ));
+    assert!(matches!(
+        db.set_key("key", &2_u32).execute(),
+        Err(bonsaidb_core::Error::ReadOnly)
+    ));
+    assert!(matches!(db.compact(), Err(bonsaidb_core::Error::ReadOnly)));
+    assert!(matches!(
+        db.rebuild_view::<BasicByParentId>(),
+        Err(bonsaidb_core::Error::ReadOnly)
+    ));
+    assert!(matches!(
+        storage.create_database::<BasicSchema>("other", false),
+        Err(bonsaidb_core::Error::ReadOnly)
+    ));
+    assert!(matches!(
+        storage.delete_database("tests"),
+        Err(bonsaidb_core::Error::ReadOnly)
+    ));
+
+    Ok(())
+}
+
+mod relationships {
+    use bonsaidb_core::document::{DocumentId, Header};
+    use bonsaidb_core::schema::relationship::OnDelete;
+    use bonsaidb_core::schema::{
+        Collection, CollectionName, DefaultSerialization, Schema, Schematic, SerializedCollection,
+    };
+    use bonsaidb_core::transaction::{Operation, Transaction};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, Schema)]
+    #[schema(name = "relationships", collections = [Post, Comment, Reply, Like, Pin], core = bonsaidb_core)]
+    struct Blog;
+
+    #[derive(Collection, Serialize, Deserialize, Debug)]
+    #[collection(name = "posts", authority = "khonsulabs", core = bonsaidb_core)]
+    struct Post {
+        title: String,
+    }
+
+    impl Post {
+        fn new(title: &str) -> Self {
+            Self {
+                title: title.to_string(),
+            }
+        }
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Comment {
+        post_id: u64,
+    }
+
+    impl Collection for Comment {
+        type PrimaryKey = u64;
+
+        fn collection_name() -> CollectionName {
+            CollectionName::new("khonsulabs", "comments")
+        }
+
+        fn define_views(schema: &mut Schematic) -> Result<(), bonsaidb_core::Error> {
+            schema.define_belongs_to::<Self, Post>(
+                "post",
+                |comment| Some(comment.post_id),
+                OnDelete::Cascade,
+            )
+        }
+    }
+
+    impl DefaultSerialization for Comment {}
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Reply {
+        comment_id: u64,
+    }
+
+    impl Collection for Reply {
+        type PrimaryKey = u64;
+
+        fn collection_name() -> CollectionName {
+            CollectionName::new("khonsulabs", "replies")
+        }
+
+        fn define_views(schema: &mut Schematic) -> Result<(), bonsaidb_core::Error> {
+            schema.define_belongs_to::<Self, Comment>(
+                "comment",
+                |reply| Some(reply.comment_id),
+                OnDelete::Cascade,
+            )
+        }
+    }
+
+    impl DefaultSerialization for Reply {}
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Like {
+        post_id: Option<u64>,
+    }
+
+    impl Collection for Like {
+        type PrimaryKey = u64;
+
+        fn collection_name() -> CollectionName {
+            CollectionName::new("khonsulabs", "likes")
+        }
+
+        fn define_views(schema: &mut Schematic) -> Result<(), bonsaidb_core::Error> {
+            schema.define_belongs_to::<Self, Post>(
+                "post",
+                |like| like.post_id,
+                OnDelete::SetNull(|like| like.post_id = None),
+            )
+        }
+    }
+
+    impl DefaultSerialization for Like {}
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Pin {
+        post_id: u64,
+    }
+
+    impl Collection for Pin {
+        type PrimaryKey = u64;
+
+        fn collection_name() -> CollectionName {
+            CollectionName::new("khonsulabs", "pins")
+        }
+
+        fn define_views(schema: &mut Schematic) -> Result<(), bonsaidb_core::Error> {
+            schema.define_belongs_to::<Self, Post>(
+                "post",
+                |pin| Some(pin.post_id),
+                OnDelete::Restrict,
+            )
+        }
+    }
+
+    impl DefaultSerialization for Pin {}
+
+    #[test]
+    fn cascade() -> anyhow::Result<()> {
+        let path = TestDirectory::new("relationships-cascade");
+        let db = Database::open::<Blog>(StorageConfiguration::new(&path))?;
+        let post = Post::new("first").push_into(&db)?;
+        let other_post = Post::new("second").push_into(&db)?;
+        let comment = Comment {
+            post_id: post.header.id,
+        }
+        .push_into(&db)?;
+        let reply = Reply {
+            comment_id: comment.header.id,
+        }
+        .push_into(&db)?;
+        let other_comment = Comment {
+            post_id: other_post.header.id,
+        }
+        .push_into(&db)?;
+
+        let changes_before = db.list_executed_transactions(None, None)?.len();
+        post.delete(&db)?;
+        assert!(Comment::get(&comment.header.id, &db)?.is_none());
+        // Deletes cascade through every relationship they affect.
+        assert!(Reply::get(&reply.header.id, &db)?.is_none());
+        assert!(Comment::get(&other_comment.header.id, &db)?.is_some());
+        // Everything was deleted in a single transaction.
+        let transactions = db.list_executed_transactions(None, None)?;
+        assert_eq!(transactions.len(), changes_before + 1);
+        let changes = transactions
+            .last()
+            .unwrap()
+            .changes
+            .documents()
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>();
+        assert_eq!(changes.len(), 3);
+        assert!(changes.iter().all(|(_, change)| change.deleted));
+
+        Ok(())
+    }
+
+    #[test]
+    fn cascade_with_deleted_children() -> anyhow::Result<()> {
+        let path = TestDirectory::new("relationships-cascade-deleted-children");
+        let db = Database::open::<Blog>(StorageConfiguration::new(&path))?;
+        let post = Post::new("first").push_into(&db)?;
+        let comment = Comment {
+            post_id: post.header.id,
+        }
+        .push_into(&db)?;
+        let reply = Reply {
+            comment_id: comment.header.id,
+        }
+        .push_into(&db)?;
+
+        // Deleting the post cascades to the comment and reply, which the
+        // transaction deletes itself afterwards.
+        let results = db.apply_transaction(
+            Transaction::new()
+                .with(Operation::delete(
+                    Post::collection_name(),
+                    Header::try_from(post.header.clone())?,
+                ))
+                .with(Operation::delete(
+                    Comment::collection_name(),
+                    Header::try_from(comment.header.clone())?,
+                ))
+                .with(Operation::delete(
+                    Reply::collection_name(),
+                    Header::try_from(reply.header.clone())?,
+                )),
+        )?;
+        assert_eq!(results.len(), 3);
+        assert!(Post::get(&post.header.id, &db)?.is_none());
+        assert!(Comment::get(&comment.header.id, &db)?.is_none());
+        assert!(Reply::get(&reply.header.id, &db)?.is_none());
+
+        // Each document is only deleted once.
+        let transactions = db.list_executed_transactions(None, None)?;
+        let changes = transactions
+            .last()
+            .unwrap()
+            .changes
+            .documents()
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>();
+        assert_eq!(changes.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn restrict() -> anyhow::Result<()> {
+        let path = TestDirectory::new("relationships-restrict");
+        let db = Database::open::<Blog>(StorageConfiguration::new(&path))?;
+        let post = Post::new("pinned").push_into(&db)?;
+        let pin = Pin {
+            post_id: post.header.id,
+        }
+        .push_into(&db)?;
+        let comment = Comment {
+            post_id: post.header.id,
+        }
+        .push_into(&db)?;
+
+        match post.delete(&db) {
+            Err(bonsaidb_core::Error::DeleteRestricted {
+                collection,
+                id,
+                relationship,
+                referencing_documents,
+            }) => {
+                assert_eq!(collection, Post::collection_name());
+                assert_eq!(*id, DocumentId::from_u64(post.header.id));
+                assert_eq!(relationship.collection, Pin::collection_name());
+                assert_eq!(relationship.name.as_ref(), "post");
+                assert_eq!(referencing_documents, 1);
+            }
+            other => unreachable!("delete should have been restricted: {other:?}"),
+        }
+        // Nothing the transaction changed was applied, including cascading
+        // deletes.
+        assert!(Post::get(&post.header.id, &db)?.is_some());
+        assert!(Comment::get(&comment.header.id, &db)?.is_some());
+
+        // Removing the restricting documents earlier in the same transaction
+        // allows the delete.
+        db.apply_transaction(
+            Transaction::new()
+                .with(Operation::delete(
+                    Pin::collection_name(),
+                    Header::try_from(pin.header.clone())?,
+                ))
+                .with(Operation::delete(
+                    Post::collection_name(),
+                    Header::try_from(post.header.clone())?,
+                )),
+        )?;
+        assert!(Post::get(&post.header.id, &db)?.is_none());
+        assert!(Comment::get(&comment.header.id, &db)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn set_null() -> anyhow::Result<()> {
+        let path = TestDirectory::new("relationships-set-null");
+        let db = Database::open::<Blog>(StorageConfiguration::new(&path))?;
+        let post = Post::new("liked").push_into(&db)?;
+        let other_post = Post::new("also liked").push_into(&db)?;
+        let like = Like {
+            post_id: Some(post.header.id),
+        }
+        .push_into(&db)?;
+        let other_like = Like {
+            post_id: Some(other_post.header.id),
+        }
+        .push_into(&db)?;
+
+        let results = db.apply_transaction(Transaction::new().with(Operation::delete(
+            Post::collection_name(),
+            Header::try_from(post.header.clone())?,
+        )))?;
+        // Only the results of the transaction's own operations are returned.
+        assert_eq!(results.len(), 1);
+
+        let like = Like::get(&like.header.id, &db)?.expect("like was deleted");
+        assert_eq!(like.contents.post_id, None);
+        let other_like = Like::get(&other_like.header.id, &db)?.expect("like was deleted");
+        assert_eq!(other_like.contents.post_id, Some(other_post.header.id));
+
+        // The documents are no longer related, so deleting a document they
+        // referenced doesn't affect them.
+        other_post.delete(&db)?;
+        let like_after = Like::get(&like.header.id, &db)?.expect("like was deleted");
+        assert_eq!(like_after.header.revision, like.header.revision);
+
+        Ok(())
+    }
+}
@@ -68,6 +68,7 @@ use hpke::kdf::HkdfSha256;
 use hpke::kem::DhP256HkdfSha256;
 use hpke::{self, Deserializable, Kem, OpModeS, Serializable};
 use lockedbox::LockedBox;
+use parking_lot::Mutex;
 use rand::{thread_rng, Rng};
 use serde::{Deserialize, Serialize};
 use zeroize::{Zeroize, Zeroizing};
@@ -179,21 +180,29 @@ impl From<bincode::Error> for Error {
 }
 
 impl Vault {
+    /// Loads or initializes the vault stored in `server_directory`. If
+    /// `server_directory` is `None`, a new vault is initialized without
+    /// persisting its master keys.
     pub fn initialize(
         server_id: StorageId,
-        server_directory: &Path,
+        server_directory: Option<&Path>,
         master_key_storage: Arc<dyn AnyVaultKeyStorage>,
     ) -> Result<Self, Error> {
-        let master_keys_path = server_directory.join("master-keys");
-        if master_keys_path.exists() {
-            Self::unseal(&master_keys_path, server_id, master_key_storage)
-        } else {
-            Self::initialize_vault_key_storage(&master_keys_path, server_id, master_key_storage)
+        let master_keys_path = server_directory.map(|directory| directory.join("master-keys"));
+        match master_keys_path {
+            Some(master_keys_path) if master_keys_path.exists() => {
+                Self::unseal(&master_keys_path, server_id, master_key_storage)
+            }
+            master_keys_path => Self::initialize_vault_key_storage(
+                master_keys_path.as_deref(),
+                server_id,
+                master_key_storage,
+            ),
         }
     }
 
     fn initialize_vault_key_storage(
-        master_keys_path: &Path,
+        master_keys_path: Option<&Path>,
         server_id: StorageId,
         master_key_storage: Arc<dyn AnyVaultKeyStorage>,
     ) -> Result<Self, Error> {
@@ -246,9 +255,13 @@ impl Vault {
                 tag,
             })?;
 
-            File::create(master_keys_path)
-                .and_then(move |mut file| file.write_all(&encrypted_master_keys_payload))
-                .map_err(|err| Error::Initializing(format!("error saving vault key: {err:?}")))?;
+            if let Some(master_keys_path) = master_keys_path {
+                File::create(master_keys_path)
+                    .and_then(move |mut file| file.write_all(&encrypted_master_keys_payload))
+                    .map_err(|err| {
+                        Error::Initializing(format!("error saving vault key: {err:?}"))
+                    })?;
+            }
 
             Ok(Self {
                 _vault_public_key: PublicKey::P256(public),
@@ -591,6 +604,38 @@ impl VaultKeyStorage for LocalVaultKeyStorage {
     }
 }
 
+/// Stores vault keys in memory. This is used by default for
+/// [memory-only](crate::config::StorageConfiguration#structfield.memory_only)
+/// storage, whose vault is discarded when the storage is closed.
+#[derive(Default)]
+pub struct MemoryVaultKeyStorage {
+    keys: Mutex<HashMap<StorageId, Zeroizing<Vec<u8>>>>,
+}
+
+impl Debug for MemoryVaultKeyStorage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryVaultKeyStorage")
+            .finish_non_exhaustive()
+    }
+}
+
+impl VaultKeyStorage for MemoryVaultKeyStorage {
+    type Error = Error;
+
+    fn vault_key_for(&self, storage_id: StorageId) -> Result<Option<KeyPair>, Self::Error> {
+        self.keys
+            .lock()
+            .get(&storage_id)
+            .map(|bytes| KeyPair::from_bytes(bytes.as_slice()))
+            .transpose()
+    }
+
+    fn set_vault_key_for(&self, storage_id: StorageId, key: KeyPair) -> Result<(), Self::Error> {
+        self.keys.lock().insert(storage_id, key.to_bytes()?);
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct VaultPayload<'a> {
     // TODO make key_id be the additional data
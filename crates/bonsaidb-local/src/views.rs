@@ -22,6 +22,8 @@ pub struct EntryMapping {
 
 pub mod integrity_scanner;
 pub mod mapper;
+pub mod rebuilder;
+pub mod verifier;
 
 pub fn view_entries_tree_name(view_name: &impl Display) -> String {
     format!("view.{view_name:#}")
@@ -12,11 +12,12 @@ use nebari::{ArcBytes, Roots, Tree};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 
-use super::mapper::{Map, Mapper};
+use super::mapper::{maps_soft_deleted, Map, Mapper};
 use super::{view_invalidated_docs_tree_name, view_versions_tree_name};
-use crate::database::{document_tree_name, Database};
+use crate::database::{deleted_document_tree_name, document_tree_name, Database};
 use crate::tasks::handle::Handle;
-use crate::tasks::{Job, Keyed, Task};
+use crate::tasks::manager::Priority;
+use crate::tasks::{Job, JobContext, Keyed, Task};
 use crate::views::{view_document_map_tree_name, view_entries_tree_name};
 use crate::Error;
 
@@ -42,15 +43,7 @@ impl Job for IntegrityScanner {
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     #[allow(clippy::too_many_lines)]
-    fn execute(&mut self) -> Result<Self::Output, Self::Error> {
-        let documents =
-            self.database
-                .roots()
-                .tree(self.database.collection_tree::<Versioned, _>(
-                    &self.scan.collection,
-                    document_tree_name(&self.scan.collection),
-                )?)?;
-
+    fn execute(&mut self, _context: &JobContext) -> Result<Self::Output, Self::Error> {
         let view_versions_tree = self.database.collection_tree::<Unversioned, _>(
             &self.scan.collection,
             view_versions_tree_name(&self.scan.collection),
@@ -72,7 +65,8 @@ impl Job for IntegrityScanner {
             None
         } else {
             // The view isn't the current version, queue up all documents.
-            let missing_entries = tree_keys::<Versioned>(&documents)?;
+            let missing_entries =
+                mappable_document_ids(&self.database, &self.scan.collection, &self.scan.view_name)?;
             // When a version is updated, we can make no guarantees about
             // existing keys. The best we can do is delete the existing files so
             // that the view starts fresh.
@@ -109,14 +103,17 @@ impl Job for IntegrityScanner {
                     .instance
                     .tasks()
                     .jobs
-                    .lookup_or_enqueue(Mapper {
-                        database: self.database.clone(),
-                        map: Map {
-                            database: self.database.data.name.clone(),
-                            collection: self.scan.collection.clone(),
-                            view_name: self.scan.view_name.clone(),
+                    .lookup_or_enqueue_with_priority(
+                        Mapper {
+                            database: self.database.clone(),
+                            map: Map {
+                                database: self.database.data.name.clone(),
+                                collection: self.scan.collection.clone(),
+                                view_name: self.scan.view_name.clone(),
+                            },
                         },
-                    }),
+                        Priority::High,
+                    ),
             ))))
         };
 
@@ -183,7 +180,7 @@ impl ViewVersion {
     }
 }
 
-fn tree_keys<R: nebari::tree::Root>(
+pub fn tree_keys<R: nebari::tree::Root>(
     tree: &Tree<R, AnyFile>,
 ) -> Result<HashSet<DocumentId>, crate::Error> {
     let mut ids = Vec::new();
@@ -204,6 +201,30 @@ fn tree_keys<R: nebari::tree::Root>(
         .collect::<Result<HashSet<_>, bonsaidb_core::Error>>()?)
 }
 
+/// Returns the ids of every document `view_name` should map: the documents in
+/// `collection`, along with its tombstones if the view maps soft-deleted
+/// documents.
+pub fn mappable_document_ids(
+    database: &Database,
+    collection: &CollectionName,
+    view_name: &ViewName,
+) -> Result<HashSet<DocumentId>, crate::Error> {
+    let roots = database.roots();
+    let mut ids = tree_keys::<Versioned>(&roots.tree(
+        database.collection_tree::<Versioned, _>(collection, document_tree_name(collection))?,
+    )?)?;
+    let view = database.data.schema.view_by_name(view_name)?;
+    if maps_soft_deleted(&database.data.schema, view) {
+        ids.extend(tree_keys::<Unversioned>(&roots.tree(
+            database.collection_tree::<Unversioned, _>(
+                collection,
+                deleted_document_tree_name(collection),
+            )?,
+        )?)?);
+    }
+    Ok(ids)
+}
+
 impl Keyed<Task> for IntegrityScanner {
     fn key(&self) -> Task {
         Task::IntegrityScan(self.scan.clone())
@@ -7,14 +7,17 @@ use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::arc_bytes::{ArcBytes, OwnedBytes};
 use bonsaidb_core::connection::Connection;
 use bonsaidb_core::schema::view::{self, map, Serialized, ViewUpdatePolicy};
-use bonsaidb_core::schema::{CollectionName, ViewName};
+use bonsaidb_core::schema::{CollectionName, Schematic, ViewName};
 use easy_parallel::Parallel;
 use nebari::io::any::AnyFile;
 use nebari::tree::{AnyTreeRoot, CompareSwap, KeyOperation, Operation, Unversioned, Versioned};
 use nebari::{LockedTransactionTree, Tree, UnlockedTransactionTree};
 
-use crate::database::{deserialize_document, document_tree_name, Database};
-use crate::tasks::{Job, Keyed, Task};
+use crate::database::{
+    deleted_document_tree_name, deserialize_current_document, deserialize_deleted_document,
+    document_tree_name, Database,
+};
+use crate::tasks::{Job, JobContext, Keyed, Task};
 use crate::views::{
     view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
     EntryMapping, ViewEntry,
@@ -40,7 +43,7 @@ impl Job for Mapper {
 
     #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
     #[allow(clippy::too_many_lines)]
-    fn execute(&mut self) -> Result<Self::Output, Error> {
+    fn execute(&mut self, context: &JobContext) -> Result<Self::Output, Error> {
         let documents =
             self.database
                 .roots()
@@ -73,6 +76,23 @@ impl Job for Mapper {
                     view_invalidated_docs_tree_name(&self.map.view_name),
                 )?)?;
 
+        let view = self
+            .database
+            .data
+            .schema
+            .view_by_name(&self.map.view_name)?;
+        let tombstones =
+            if maps_soft_deleted(&self.database.data.schema, view) {
+                Some(self.database.roots().tree(
+                    self.database.collection_tree::<Unversioned, _>(
+                        &self.map.collection,
+                        deleted_document_tree_name(&self.map.collection),
+                    )?,
+                )?)
+            } else {
+                None
+            };
+
         let transaction_id = self
             .database
             .last_transaction_id()?
@@ -85,11 +105,19 @@ impl Job for Mapper {
             &invalidated_entries,
             &document_map,
             &documents,
+            tombstones.as_ref(),
             &view_entries,
             &storage,
             &map_request,
+            context,
         )?;
 
+        // A cancelled job may have stopped before mapping every invalidated
+        // document, so the view can't be marked as up-to-date.
+        if context.is_cancelled() {
+            return Ok(transaction_id);
+        }
+
         self.database.storage.instance.tasks().mark_view_updated(
             self.map.database.clone(),
             self.map.collection.clone(),
@@ -105,9 +133,11 @@ fn map_view(
     invalidated_entries: &Tree<Unversioned, AnyFile>,
     document_map: &Tree<Unversioned, AnyFile>,
     documents: &Tree<Versioned, AnyFile>,
+    tombstones: Option<&Tree<Unversioned, AnyFile>>,
     view_entries: &Tree<Unversioned, AnyFile>,
     database: &Database,
     map_request: &Map,
+    context: &JobContext,
 ) -> Result<(), Error> {
     const CHUNK_SIZE: usize = 100_000;
     // Only do any work if there are invalidated documents to process
@@ -116,15 +146,26 @@ fn map_view(
         .into_iter()
         .map(|(key, _)| key)
         .collect::<Vec<_>>();
+    let total = invalidated_ids.len();
     while !invalidated_ids.is_empty() {
+        // Each chunk is committed independently, so stopping between chunks
+        // leaves the remaining documents invalidated for a future mapping.
+        if context.is_cancelled() {
+            break;
+        }
+
+        let mut trees = vec![
+            Box::new(invalidated_entries.clone()) as Box<dyn AnyTreeRoot<AnyFile>>,
+            Box::new(document_map.clone()),
+            Box::new(documents.clone()),
+            Box::new(view_entries.clone()),
+        ];
+        if let Some(tombstones) = tombstones {
+            trees.push(Box::new(tombstones.clone()));
+        }
         let transaction = database
             .roots()
-            .transaction::<_, dyn AnyTreeRoot<AnyFile>>(&[
-                Box::new(invalidated_entries.clone()) as Box<dyn AnyTreeRoot<AnyFile>>,
-                Box::new(document_map.clone()),
-                Box::new(documents.clone()),
-                Box::new(view_entries.clone()),
-            ])?;
+            .transaction::<_, dyn AnyTreeRoot<AnyFile>>(&trees)?;
         {
             let view = database
                 .data
@@ -138,12 +179,14 @@ fn map_view(
             let document_map = transaction.unlocked_tree(1).unwrap();
             let documents = transaction.unlocked_tree(2).unwrap();
             let view_entries = transaction.unlocked_tree(3).unwrap();
+            let tombstones = tombstones.map(|_| transaction.unlocked_tree(4).unwrap());
             DocumentRequest {
                 document_ids: document_ids.clone(),
                 map_request,
                 database,
                 document_map,
                 documents,
+                tombstones,
                 view_entries,
                 view,
             }
@@ -153,6 +196,13 @@ fn map_view(
             invalidated_entries.modify(document_ids, nebari::tree::Operation::Remove)?;
         }
         transaction.commit()?;
+
+        let mapped = total - invalidated_ids.len();
+        #[allow(clippy::cast_precision_loss)]
+        context.report_progress(
+            mapped as f32 / total as f32,
+            Some(format!("mapped {mapped} of {total} documents")),
+        );
     }
 
     Ok(())
@@ -165,11 +215,24 @@ pub struct DocumentRequest<'a> {
 
     pub document_map: &'a UnlockedTransactionTree<AnyFile>,
     pub documents: &'a UnlockedTransactionTree<AnyFile>,
+    /// The collection's tombstones, if soft-deleted documents should be
+    /// mapped by this view.
+    pub tombstones: Option<&'a UnlockedTransactionTree<AnyFile>>,
     pub view_entries: &'a UnlockedTransactionTree<AnyFile>,
     pub view: &'a dyn Serialized,
 }
 
-type DocumentIdPayload = (ArcBytes<'static>, Option<ArcBytes<'static>>);
+/// Returns true if `view` maps the soft-deleted documents of its collection.
+pub fn maps_soft_deleted(schema: &Schematic, view: &dyn Serialized) -> bool {
+    view.include_soft_deleted() && schema.collection_uses_soft_delete(&view.collection())
+}
+
+enum StoredBytes {
+    Document(ArcBytes<'static>),
+    Tombstone(ArcBytes<'static>),
+}
+
+type DocumentIdPayload = (ArcBytes<'static>, Option<StoredBytes>);
 type BatchPayload = (Vec<ArcBytes<'static>>, flume::Receiver<DocumentIdPayload>);
 
 impl<'a> DocumentRequest<'a> {
@@ -177,16 +240,39 @@ impl<'a> DocumentRequest<'a> {
         batch_sender: flume::Sender<BatchPayload>,
         document_ids: &[ArcBytes<'static>],
         documents: &UnlockedTransactionTree<AnyFile>,
+        tombstones: Option<&UnlockedTransactionTree<AnyFile>>,
     ) -> Result<(), Error> {
         // Generate batches
         let mut documents = documents.lock::<Versioned>();
+        let mut tombstones = tombstones.map(UnlockedTransactionTree::lock::<Unversioned>);
         for chunk in document_ids.chunks(1024) {
             let (document_id_sender, document_id_receiver) = flume::bounded(chunk.len());
             batch_sender
                 .send((chunk.to_vec(), document_id_receiver))
                 .unwrap();
-            let mut documents = documents.get_multiple(chunk.iter().map(ArcBytes::as_slice))?;
+            let mut documents = documents
+                .get_multiple(chunk.iter().map(ArcBytes::as_slice))?
+                .into_iter()
+                .map(|(key, document)| (key, StoredBytes::Document(document)))
+                .collect::<Vec<_>>();
             documents.sort_by(|a, b| a.0.cmp(&b.0));
+            if let Some(tombstones) = &mut tombstones {
+                // Documents that no longer exist may have been soft-deleted.
+                let missing = chunk
+                    .iter()
+                    .filter(|id| documents.binary_search_by(|(key, _)| key.cmp(*id)).is_err())
+                    .map(ArcBytes::as_slice)
+                    .collect::<Vec<_>>();
+                if !missing.is_empty() {
+                    documents.extend(
+                        tombstones
+                            .get_multiple(missing)?
+                            .into_iter()
+                            .map(|(key, tombstone)| (key, StoredBytes::Tombstone(tombstone))),
+                    );
+                    documents.sort_by(|a, b| a.0.cmp(&b.0));
+                }
+            }
 
             for document_id in chunk.iter().rev() {
                 let document = documents
@@ -209,6 +295,8 @@ impl<'a> DocumentRequest<'a> {
         batch_receiver: &flume::Receiver<BatchPayload>,
         mapped_sender: flume::Sender<Batch>,
         view: &dyn Serialized,
+        collection: &CollectionName,
+        schema: &Schematic,
         parallelization: usize,
     ) -> Result<(), Error> {
         // Process batches
@@ -221,9 +309,16 @@ impl<'a> DocumentRequest<'a> {
                 .each(1..=parallelization, |_| -> Result<_, Error> {
                     let mut results = Vec::new();
                     while let Ok((document_id, document)) = document_id_receiver.recv() {
+                        let document = match &document {
+                            Some(StoredBytes::Document(bytes)) => {
+                                Some(deserialize_current_document(bytes, collection, schema)?)
+                            }
+                            Some(StoredBytes::Tombstone(bytes)) => {
+                                Some(deserialize_deleted_document(bytes, collection, schema)?)
+                            }
+                            None => None,
+                        };
                         let map_result = if let Some(document) = document {
-                            let document = deserialize_document(&document)?;
-
                             // Call the schema map function
                             view.map(&document).map_err(bonsaidb_core::Error::from)?
                         } else {
@@ -365,12 +460,21 @@ impl<'a> DocumentRequest<'a> {
         let (mapped_sender, mapped_receiver) = flume::bounded(1);
 
         for result in Parallel::new()
-            .add(|| Self::generate_batches(batch_sender, &self.document_ids, self.documents))
+            .add(|| {
+                Self::generate_batches(
+                    batch_sender,
+                    &self.document_ids,
+                    self.documents,
+                    self.tombstones,
+                )
+            })
             .add(|| {
                 Self::map_batches(
                     &batch_receiver,
                     mapped_sender,
                     self.view,
+                    &self.map_request.collection,
+                    &self.database.data.schema,
                     self.database.storage().parallelization(),
                 )
             })
@@ -0,0 +1,109 @@
+use nebari::tree::{Operation, Unversioned};
+use nebari::ArcBytes;
+
+use super::integrity_scanner::{mappable_document_ids, tree_keys, ViewVersion};
+use super::mapper::{Map, Mapper};
+use super::{
+    view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
+    view_versions_tree_name,
+};
+use crate::database::Database;
+use crate::tasks::{Job, JobContext, Keyed, Task};
+use crate::Error;
+
+/// Re-maps every document in a view's collection.
+///
+/// Rather than clearing the view, every document is marked as invalidated and
+/// mapped again. Each document's existing entries are replaced as it is
+/// mapped, allowing the view to be queried while it is being rebuilt.
+///
+/// If `reset_to_version` is set, the view's stored data can't be trusted. It
+/// is removed before the documents are mapped, and the view is recorded as
+/// being the version provided.
+#[derive(Debug)]
+pub struct ViewRebuilder {
+    pub database: Database,
+    pub map: Map,
+    pub reset_to_version: Option<u64>,
+}
+
+impl Job for ViewRebuilder {
+    type Error = Error;
+    type Output = ();
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn execute(&mut self, context: &JobContext) -> Result<Self::Output, Self::Error> {
+        // Without any transactions, there are no documents to map.
+        if self.database.last_transaction_id()?.is_none() {
+            return Ok(());
+        }
+
+        if self.reset_to_version.is_some() {
+            let roots = self.database.roots();
+            roots.delete_tree(view_invalidated_docs_tree_name(&self.map.view_name))?;
+            roots.delete_tree(view_entries_tree_name(&self.map.view_name))?;
+            roots.delete_tree(view_document_map_tree_name(&self.map.view_name))?;
+        }
+
+        let document_map =
+            self.database
+                .roots()
+                .tree(self.database.collection_tree::<Unversioned, _>(
+                    &self.map.collection,
+                    view_document_map_tree_name(&self.map.view_name),
+                )?)?;
+
+        // Documents that are in the view but no longer exist are included so
+        // that their entries are removed.
+        let mut document_ids =
+            mappable_document_ids(&self.database, &self.map.collection, &self.map.view_name)?;
+        document_ids.extend(tree_keys::<Unversioned>(&document_map)?);
+        let mut document_ids = document_ids
+            .into_iter()
+            .map(|id| ArcBytes::from(id.to_vec()))
+            .collect::<Vec<_>>();
+        document_ids.sort();
+
+        let invalidated_entries_tree = self.database.collection_tree::<Unversioned, _>(
+            &self.map.collection,
+            view_invalidated_docs_tree_name(&self.map.view_name),
+        )?;
+        let mut trees = vec![invalidated_entries_tree];
+        if self.reset_to_version.is_some() {
+            trees.push(self.database.collection_tree::<Unversioned, _>(
+                &self.map.collection,
+                view_versions_tree_name(&self.map.collection),
+            )?);
+        }
+        let transaction = self.database.roots().transaction(&trees)?;
+        transaction
+            .tree::<Unversioned>(0)
+            .unwrap()
+            .modify(document_ids, Operation::Set(ArcBytes::default()))?;
+        if let Some(view_version) = self.reset_to_version {
+            transaction.tree::<Unversioned>(1).unwrap().set(
+                self.map.view_name.to_string().as_bytes().to_vec(),
+                ViewVersion::current_for(view_version).to_vec()?,
+            )?;
+        }
+        transaction.commit()?;
+
+        Mapper {
+            database: self.database.clone(),
+            map: self.map.clone(),
+        }
+        .execute(context)?;
+
+        Ok(())
+    }
+}
+
+impl Keyed<Task> for ViewRebuilder {
+    fn key(&self) -> Task {
+        if self.reset_to_version.is_some() {
+            Task::ViewReset(self.map.clone())
+        } else {
+            Task::ViewRebuild(self.map.clone())
+        }
+    }
+}
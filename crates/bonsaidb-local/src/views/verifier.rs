@@ -0,0 +1,176 @@
+use std::collections::{HashMap, HashSet};
+
+use bonsaidb_core::arc_bytes::OwnedBytes;
+use bonsaidb_core::document::DocumentId;
+use nebari::tree::Unversioned;
+
+use super::integrity_scanner::{mappable_document_ids, tree_keys, ViewVersion};
+use super::mapper::Map;
+use super::{
+    view_document_map_tree_name, view_entries_tree_name, view_invalidated_docs_tree_name,
+    view_versions_tree_name, ViewEntry,
+};
+use crate::database::Database;
+use crate::storage::ViewIntegrity;
+use crate::tasks::{Job, JobContext, Keyed, Task};
+use crate::Error;
+
+/// Verifies that a view's stored data is consistent with the documents of
+/// its collection.
+///
+/// Documents that are waiting to be mapped are not considered inconsistent.
+/// A view that is being updated while it is verified may be reported as
+/// inconsistent.
+#[derive(Debug)]
+pub struct ViewVerifier {
+    pub database: Database,
+    pub verification: Verification,
+}
+
+#[derive(Debug, Hash, Eq, PartialEq, Clone)]
+pub struct Verification {
+    pub map: Map,
+    pub view_version: u64,
+    pub full: bool,
+}
+
+impl Job for ViewVerifier {
+    type Error = Error;
+    type Output = ViewIntegrity;
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+    fn execute(&mut self, _context: &JobContext) -> Result<Self::Output, Self::Error> {
+        let collection = &self.verification.map.collection;
+        let view_name = &self.verification.map.view_name;
+        let mut integrity = ViewIntegrity::new(
+            self.database.data.name.to_string(),
+            view_name.clone(),
+            self.verification.full,
+        );
+
+        let roots = self.database.roots();
+        let view_versions = roots.tree(self.database.collection_tree::<Unversioned, _>(
+            collection,
+            view_versions_tree_name(collection),
+        )?)?;
+        integrity.outdated = match view_versions.get(view_name.to_string().as_bytes())? {
+            Some(version) => {
+                !ViewVersion::from_bytes(&version)?.is_current(self.verification.view_version)
+            }
+            None => true,
+        };
+        if integrity.outdated {
+            // The integrity scanner replaces everything stored for an
+            // outdated view before it is queried, so there's nothing to
+            // compare.
+            return Ok(integrity);
+        }
+
+        let documents = mappable_document_ids(&self.database, collection, view_name)?;
+
+        // The invalidated documents must be read before the document map,
+        // because the mapper removes documents from the invalidated list in
+        // the same transaction that updates the document map.
+        let invalidated = tree_keys::<Unversioned>(&roots.tree(
+            self.database.collection_tree::<Unversioned, _>(
+                collection,
+                view_invalidated_docs_tree_name(view_name),
+            )?,
+        )?)?;
+        let document_map = roots
+            .tree(self.database.collection_tree::<Unversioned, _>(
+                collection,
+                view_document_map_tree_name(view_name),
+            )?)?
+            .get_range(&(..))?
+            .into_iter()
+            .filter_map(|(id, keys)| {
+                let id = match DocumentId::try_from(id.as_slice()) {
+                    Ok(id) => id,
+                    Err(err) => return Some(Err(Error::from(err))),
+                };
+                if invalidated.contains(&id) {
+                    None
+                } else {
+                    Some(
+                        bincode::deserialize::<HashSet<OwnedBytes>>(&keys)
+                            .map(|keys| (id, keys))
+                            .map_err(Error::from),
+                    )
+                }
+            })
+            .collect::<Result<HashMap<_, _>, Error>>()?;
+
+        integrity.unmapped_documents = documents
+            .iter()
+            .filter(|id| !invalidated.contains(id) && !document_map.contains_key(id))
+            .count();
+        // Deleted documents remain in the document map with no keys after
+        // they are mapped.
+        integrity.stale_documents = document_map
+            .iter()
+            .filter(|(id, keys)| !keys.is_empty() && !documents.contains(id))
+            .count();
+
+        if self.verification.full {
+            integrity.inconsistent_entries =
+                Some(self.verify_entries(&invalidated, &document_map)?);
+        }
+
+        Ok(integrity)
+    }
+}
+
+impl ViewVerifier {
+    /// Compares every entry stored in the view against the keys recorded in
+    /// the document map, returning the number of mappings that don't match.
+    fn verify_entries(
+        &self,
+        invalidated: &HashSet<DocumentId>,
+        document_map: &HashMap<DocumentId, HashSet<OwnedBytes>>,
+    ) -> Result<usize, Error> {
+        let entries = self
+            .database
+            .roots()
+            .tree(self.database.collection_tree::<Unversioned, _>(
+                &self.verification.map.collection,
+                view_entries_tree_name(&self.verification.map.view_name),
+            )?)?
+            .get_range(&(..))?;
+
+        let mut inconsistent = 0;
+        let mut matched = 0;
+        for (key, entry) in entries {
+            let Ok(entry) = bincode::deserialize::<ViewEntry>(&entry) else {
+                inconsistent += 1;
+                continue;
+            };
+            let key = OwnedBytes::from(key.as_slice());
+            for mapping in entry.mappings {
+                if invalidated.contains(&mapping.source.id) {
+                    continue;
+                }
+
+                if document_map
+                    .get(&mapping.source.id)
+                    .map_or(false, |keys| keys.contains(&key))
+                {
+                    matched += 1;
+                } else {
+                    inconsistent += 1;
+                }
+            }
+        }
+
+        // Every key recorded in the document map that no entry was found for
+        // is also inconsistent.
+        let recorded = document_map.values().map(HashSet::len).sum::<usize>();
+        Ok(inconsistent + recorded.saturating_sub(matched))
+    }
+}
+
+impl Keyed<Task> for ViewVerifier {
+    fn key(&self) -> Task {
+        Task::ViewVerify(self.verification.clone())
+    }
+}
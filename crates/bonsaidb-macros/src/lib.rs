@@ -74,10 +74,27 @@ struct CollectionAttribute {
     encryption_key: Option<Expr>,
     encryption_required: bool,
     encryption_optional: bool,
+    soft_delete: bool,
+    #[attribute(example = "Duration::from_secs(60)")]
+    expire_after: Option<Expr>,
+    #[attribute(example = "2")]
+    version: Option<Expr>,
+    #[attribute(example = "Self::migrate_contents")]
+    migrate: Option<Expr>,
+    #[attribute(example = "\"json\"")]
+    format_name: Option<String>,
+    #[attribute(example = "Self::convert_contents")]
+    convert_format: Option<Expr>,
+    #[attribute(example = "Self::validate_contents")]
+    validate: Option<Expr>,
+    #[attribute(example = "SomePatch")]
+    patch: Option<Type>,
     #[attribute(example = "u64")]
     primary_key: Option<Type>,
     #[attribute(example = "self.0 or something(self)")]
     natural_id: Option<Expr>,
+    #[attribute(example = "UpdateAfter")]
+    access_policy: Option<Ident>,
     #[attribute(example = "bosaidb::core")]
     core: Option<Path>,
 }
@@ -106,6 +123,15 @@ pub fn collection_derive(input: proc_macro::TokenStream) -> Result {
         encryption_key,
         encryption_required,
         encryption_optional,
+        soft_delete,
+        expire_after,
+        version,
+        migrate,
+        format_name,
+        convert_format,
+        validate,
+        patch,
+        access_policy,
     } = CollectionAttribute::from_attributes(&attrs)?;
 
     if let Data::Struct(DataStruct { fields, .. }) = data {
@@ -229,6 +255,76 @@ pub fn collection_derive(input: proc_macro::TokenStream) -> Result {
         }
     });
 
+    let soft_delete = soft_delete.then(|| {
+        quote! {
+            fn soft_delete() -> bool {
+                true
+            }
+        }
+    });
+
+    let expire_after = expire_after.map(|expire_after| {
+        quote! {
+            fn expire_after() -> Option<std::time::Duration> {
+                Some(#expire_after)
+            }
+        }
+    });
+
+    let version = version.map(|version| {
+        quote! {
+            const CURRENT_VERSION: u32 = #version;
+        }
+    });
+
+    let migrate = migrate.map(|migrate| {
+        quote! {
+            fn migrate(from: u32, contents: &[u8]) -> Result<Vec<u8>, #core::Error> {
+                (#migrate)(from, contents)
+            }
+        }
+    });
+
+    let format_name = format_name.map(|format_name| {
+        quote! {
+            const FORMAT_NAME: Option<&'static str> = Some(#format_name);
+        }
+    });
+
+    let convert_format = convert_format.map(|convert_format| {
+        quote! {
+            fn convert_format(from: Option<&str>, contents: &[u8]) -> Result<Vec<u8>, #core::Error> {
+                (#convert_format)(from, contents)
+            }
+        }
+    });
+
+    let validate = validate.map(|validate| {
+        quote! {
+            fn validate(contents: &[u8]) -> Result<(), #core::schema::ValidationError> {
+                let contents = <Self as #core::schema::SerializedCollection>::deserialize(contents)
+                    .map_err(#core::schema::ValidationError::new)?;
+                (#validate)(&contents)
+            }
+        }
+    });
+
+    let patch = patch.map(|patch| {
+        quote! {
+            fn apply_patch(contents: &[u8], patch: &[u8]) -> Result<Vec<u8>, #core::Error> {
+                <#patch as #core::schema::DocumentPatch<Self>>::apply_serialized(contents, patch)
+            }
+        }
+    });
+
+    let access_policy = access_policy.map(|access_policy| {
+        quote! {
+            fn default_access_policy() -> Option<#core::connection::AccessPolicy> {
+                Some(#core::connection::AccessPolicy::#access_policy)
+            }
+        }
+    });
+
     Ok(quote! {
         impl #impl_generics #core::schema::Collection for #ident #ty_generics #where_clause {
             type PrimaryKey = #primary_key;
@@ -241,14 +337,23 @@ pub fn collection_derive(input: proc_macro::TokenStream) -> Result {
                 Ok(())
             }
             #encryption
+            #soft_delete
+            #expire_after
+            #version
+            #migrate
+            #format_name
+            #convert_format
+            #validate
+            #patch
+            #access_policy
         }
         #serialization
     })
 }
 /// Derives the `bonsaidb::core::schema::View` trait.
 ///
-/// `#[view(collection=CollectionType, key=KeyType, value=ValueType, name = "by-name")]`
-/// `name` and `value` are optional
+/// `#[view(collection=CollectionType, key=KeyType, value=ValueType, name = "by-name", access_policy = UpdateAfter)]`
+/// `name`, `value`, and `access_policy` are optional
 #[manyhow]
 #[proc_macro_derive(View, attributes(view))]
 pub fn view_derive(input: proc_macro::TokenStream) -> Result {
@@ -275,14 +380,18 @@ struct SchemaAttribute {
     collections: Vec<Type>,
     #[attribute(optional, example = "[SomeSchema, AnotherSchema]")]
     include: Vec<Type>,
+    #[attribute(example = "validate_name")]
+    name_policy: Option<Expr>,
+    #[attribute(example = "UpdateAfter")]
+    access_policy: Option<Ident>,
     #[attribute(example = "bosaidb::core")]
     core: Option<Path>,
 }
 
 /// Derives the `bonsaidb::core::schema::Schema` trait.
 ///
-/// `#[schema(name = "Name", authority = "Authority", collections = [A, B, C]), core = bonsaidb::core]`
-/// `authority`, `collections` and `core` are optional
+/// `#[schema(name = "Name", authority = "Authority", collections = [A, B, C], include = [OtherSchema], name_policy = validate_name, access_policy = UpdateAfter, core = bonsaidb::core)]`
+/// `authority`, `collections`, `include`, `name_policy`, `access_policy` and `core` are optional
 #[manyhow]
 #[proc_macro_derive(Schema, attributes(schema))]
 pub fn schema_derive(input: proc_macro::TokenStream) -> Result {
@@ -298,6 +407,8 @@ pub fn schema_derive(input: proc_macro::TokenStream) -> Result {
         authority,
         collections,
         include,
+        name_policy,
+        access_policy,
         core,
     } = SchemaAttribute::from_attributes(&attrs)?;
 
@@ -309,18 +420,39 @@ pub fn schema_derive(input: proc_macro::TokenStream) -> Result {
         |authority| quote!(#core::schema::Qualified::new(#authority, #name)),
     );
 
+    let name_policy = name_policy.map(|name_policy| {
+        quote! {
+            fn validate_collection_name(
+                name: &#core::schema::CollectionName
+            ) -> Result<(), #core::schema::InvalidNameError> {
+                (#name_policy)(name)
+            }
+        }
+    });
+
+    let access_policy = access_policy.map(|access_policy| {
+        quote! {
+            fn default_view_access_policy() -> #core::connection::AccessPolicy {
+                #core::connection::AccessPolicy::#access_policy
+            }
+        }
+    });
+
     Ok(quote! {
         impl #impl_generics #core::schema::Schema for #ident #ty_generics #where_clause {
             fn schema_name() -> #core::schema::SchemaName {
                 #name
             }
 
+            #name_policy
+            #access_policy
+
             fn define_collections(
                 schema: &mut #core::schema::Schematic
             ) -> Result<(), #core::Error> {
                 #( schema.define_collection::<#collections>()?; )*
 
-                #( <#include as #core::schema::Schema>::define_collections(schema)?; )*
+                #( schema.include::<#include>()?; )*
 
                 Ok(())
             }
@@ -436,6 +568,9 @@ pub fn key_derive(input: proc_macro::TokenStream) -> Result {
         NullHandling::Allow => (quote!(allowing_null_bytes), quote!(allowing_null_bytes)),
         NullHandling::Deny => (quote!(denying_null_bytes), quote!(denying_null_bytes)),
     };
+    // Allowing null bytes in variable length fields can cause the encoded
+    // bytes to sort differently than the values.
+    let order_preserving = !matches!(null_handling, NullHandling::Allow);
 
     let core = core.unwrap_or_else(core_path);
     let (_, ty_generics, _) = generics.split_for_impl();
@@ -488,6 +623,7 @@ pub fn key_derive(input: proc_macro::TokenStream) -> Result {
                 type Error = <#ty as KeyEncoding>::Error;
 
                 const LENGTH: Option<usize> = <#ty>::LENGTH;
+                const ORDER_PRESERVING: bool = <#ty>::ORDER_PRESERVING;
 
                 fn describe<Visitor>(visitor: &mut Visitor)
                 where
@@ -828,6 +964,7 @@ pub fn key_derive(input: proc_macro::TokenStream) -> Result {
 
             // TODO fixed width if possible
             const LENGTH: Option<usize> = None;
+            const ORDER_PRESERVING: bool = #order_preserving;
 
             fn describe<Visitor>(visitor: &mut Visitor)
             where
@@ -23,6 +23,8 @@ struct ViewAttribute {
     core: Option<Path>,
     #[attribute(example = "Format or None")]
     serialization: Option<Path>,
+    #[attribute(example = "UpdateAfter")]
+    access_policy: Option<Ident>,
 }
 
 pub fn derive(
@@ -40,6 +42,7 @@ pub fn derive(
         value,
         core,
         serialization,
+        access_policy,
     } = ViewAttribute::from_attributes(&attrs)?;
 
     let core = core.unwrap_or_else(core_path);
@@ -55,6 +58,12 @@ pub fn derive(
         .as_ref()
         .map_or_else(|| ident.to_string(), LitStr::value);
 
+    let access_policy = access_policy.map(|access_policy| {
+        quote!(fn default_access_policy() -> Option<#core::connection::AccessPolicy> {
+            Some(#core::connection::AccessPolicy::#access_policy)
+        })
+    });
+
     let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
     let serialization = match serialization {
@@ -82,6 +91,8 @@ pub fn derive(
             fn name(&self) -> #core::schema::Name {
                 #core::schema::Name::new(#name)
             }
+
+            #access_policy
         }
         #serialization
     })
@@ -141,6 +141,21 @@ fn encryption_key() {
     struct Test;
 }
 
+#[test]
+fn access_policy() {
+    use bonsaidb::core::connection::AccessPolicy;
+
+    #[derive(Collection, Debug, Deserialize, Serialize)]
+    #[collection(name = "Name")]
+    #[collection(access_policy = UpdateAfter)]
+    struct Test;
+
+    assert!(matches!(
+        Test::default_access_policy(),
+        Some(AccessPolicy::UpdateAfter)
+    ));
+}
+
 #[test]
 fn primary_key() {
     #[derive(Collection, Debug, Deserialize, Serialize)]
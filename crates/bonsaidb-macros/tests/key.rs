@@ -1,6 +1,8 @@
 use std::borrow::Cow;
 
+use bonsaidb::core::connection::{MaybeOwned, QueryKey};
 use bonsaidb::core::key::{Key, KeyEncoding};
+use bonsaidb::core::Error;
 
 #[test]
 fn tuple_struct() {
@@ -141,3 +143,28 @@ fn lifetime() {
             .as_ref()
     )
 }
+
+#[test]
+fn null_handling_order_preserving() {
+    #[derive(Clone, Debug, Key, PartialEq)]
+    struct Escaped(String, String);
+    #[derive(Clone, Debug, Key, PartialEq)]
+    #[key(null_handling = allow)]
+    struct Allowed(String, String);
+
+    let escaped = QueryKey::<Escaped>::Range(
+        (Escaped("a".into(), "b".into())..Escaped("c".into(), "d".into())).into(),
+    );
+    assert!(escaped.serialized().is_ok());
+
+    let allowed = QueryKey::<Allowed>::Range(
+        (Allowed("a".into(), "b".into())..Allowed("c".into(), "d".into())).into(),
+    );
+    assert!(matches!(
+        allowed.serialized(),
+        Err(Error::KeyNotOrderPreserving(_))
+    ));
+    // Exact matches don't depend on ordering.
+    let allowed = QueryKey::<Allowed>::Matches(MaybeOwned::Owned(Allowed("a".into(), "b".into())));
+    assert!(allowed.serialized().is_ok());
+}
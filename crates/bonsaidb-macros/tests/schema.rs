@@ -1,6 +1,8 @@
 use core::fmt::Debug;
 
-use bonsaidb::core::schema::{Collection, CollectionName, Qualified, Schema, Schematic};
+use bonsaidb::core::schema::{
+    Collection, CollectionName, InvalidNameError, Qualified, Schema, Schematic,
+};
 
 #[test]
 fn core() {
@@ -70,3 +72,73 @@ fn plugins() {
     #[collection(name = "name")]
     struct TestCollection;
 }
+
+#[test]
+fn name_policy() {
+    #[derive(Schema, Debug)]
+    #[schema(name = "name", collections = [TestCollection], name_policy = reject_private)]
+    struct TestSchema;
+
+    fn reject_private(name: &CollectionName) -> Result<(), InvalidNameError> {
+        if name.authority.as_ref() == "private" {
+            Err(InvalidNameError(name.to_string()))
+        } else {
+            Ok(())
+        }
+    }
+
+    assert!(Schematic::from_schema::<TestSchema>().is_err());
+
+    #[derive(Collection, Debug)]
+    #[collection(name = "name")]
+    struct TestCollection;
+}
+
+#[test]
+fn access_policy() {
+    use bonsaidb::core::connection::AccessPolicy;
+    use bonsaidb::core::schema::View;
+
+    #[derive(Schema, Debug)]
+    #[schema(name = "name", collections = [Inherited, Overridden], access_policy = NoUpdate)]
+    struct TestSchema;
+
+    #[derive(Collection, Debug)]
+    #[collection(name = "inherited")]
+    struct Inherited;
+
+    #[derive(Collection, Debug)]
+    #[collection(name = "overridden", access_policy = UpdateAfter)]
+    struct Overridden;
+
+    #[derive(View, Debug)]
+    #[view(collection = Inherited, key = ())]
+    struct InheritedView;
+
+    #[derive(View, Debug)]
+    #[view(collection = Overridden, key = ())]
+    struct CollectionView;
+
+    #[derive(View, Debug)]
+    #[view(collection = Overridden, key = (), access_policy = UpdateBefore)]
+    struct OverriddenView;
+
+    assert!(matches!(
+        TestSchema::default_view_access_policy(),
+        AccessPolicy::NoUpdate
+    ));
+
+    let schematic = Schematic::from_schema::<TestSchema>().unwrap();
+    assert!(matches!(
+        schematic.default_access_policy::<InheritedView>(),
+        AccessPolicy::NoUpdate
+    ));
+    assert!(matches!(
+        schematic.default_access_policy::<CollectionView>(),
+        AccessPolicy::UpdateAfter
+    ));
+    assert!(matches!(
+        schematic.default_access_policy::<OverriddenView>(),
+        AccessPolicy::UpdateBefore
+    ));
+}
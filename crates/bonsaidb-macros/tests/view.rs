@@ -31,3 +31,19 @@ fn serialization_none() {
     #[view(serialization = None)]
     struct TestView;
 }
+
+#[test]
+fn access_policy() {
+    use bonsaidb::core::connection::AccessPolicy;
+
+    #[derive(View, Debug)]
+    #[view(collection = TestCollection)]
+    #[view(key = ())]
+    #[view(access_policy = NoUpdate)]
+    struct TestView;
+
+    assert!(matches!(
+        TestView::default_access_policy(),
+        Some(AccessPolicy::NoUpdate)
+    ));
+}
@@ -0,0 +1,71 @@
+use std::fmt::Debug;
+
+use bonsaidb_core::async_trait::async_trait;
+use bonsaidb_core::connection::IdentityReference;
+use bonsaidb_core::schema::Nameable;
+
+/// A custom authentication method that can be registered with a server using
+/// [`ServerConfiguration::with_authenticator`](crate::ServerConfiguration::with_authenticator).
+///
+/// Clients authenticate using a custom authenticator by calling
+/// `authenticate()` with
+/// [`Authentication::custom()`](bonsaidb_core::connection::Authentication::custom).
+/// The payload is passed to [`authenticate()`](Self::authenticate)
+/// unmodified. If an identity is returned, the connection is authenticated as
+/// that user or role, and the session is granted the permissions of the
+/// identity's permission groups and roles.
+///
+/// The connection must also be allowed to perform
+/// [`ServerAction::Authenticate`](bonsaidb_core::permissions::bonsai::ServerAction::Authenticate)
+/// with
+/// [`AuthenticationMethod::Custom`](bonsaidb_core::connection::AuthenticationMethod::Custom)
+/// against the resolved identity.
+#[async_trait]
+pub trait CustomAuthenticator: Debug + Send + Sync + 'static {
+    /// Verifies `payload` and returns the identity it authenticates.
+    ///
+    /// If the payload is not valid,
+    /// [`Error::InvalidCredentials`](bonsaidb_core::Error::InvalidCredentials)
+    /// should be returned.
+    async fn authenticate(
+        &self,
+        payload: &[u8],
+    ) -> Result<AuthenticatedIdentity, bonsaidb_core::Error>;
+}
+
+/// An identity that has been verified by a [`CustomAuthenticator`].
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct AuthenticatedIdentity(IdentityReference<'static>);
+
+impl AuthenticatedIdentity {
+    /// Returns an authenticated identity for a
+    /// [`User`](bonsaidb_core::admin::User). This function accepts either the
+    /// user's unique id or their username.
+    pub fn user<'name, User: Nameable<'name, u64>>(
+        user: User,
+    ) -> Result<Self, bonsaidb_core::Error> {
+        IdentityReference::user(user).map(Self::from)
+    }
+
+    /// Returns an authenticated identity for a
+    /// [`Role`](bonsaidb_core::admin::Role). This function accepts either the
+    /// role's unique id or the role's name.
+    pub fn role<'name, Role: Nameable<'name, u64>>(
+        role: Role,
+    ) -> Result<Self, bonsaidb_core::Error> {
+        IdentityReference::role(role).map(Self::from)
+    }
+
+    /// Returns the reference to the authenticated identity.
+    #[must_use]
+    pub fn into_identity(self) -> IdentityReference<'static> {
+        self.0
+    }
+}
+
+impl<'name> From<IdentityReference<'name>> for AuthenticatedIdentity {
+    fn from(identity: IdentityReference<'name>) -> Self {
+        Self(identity.into_owned())
+    }
+}
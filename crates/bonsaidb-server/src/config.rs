@@ -3,21 +3,33 @@ use std::marker::PhantomData;
 use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
 
 use bonsaidb_core::api;
 use bonsaidb_core::api::ApiName;
 #[cfg(feature = "encryption")]
 use bonsaidb_core::document::KeyId;
+#[cfg(feature = "compression")]
+use bonsaidb_core::networking::TransportCompression;
 use bonsaidb_core::permissions::{Permissions, Statement};
 use bonsaidb_core::schema::Schema;
 #[cfg(feature = "compression")]
 use bonsaidb_local::config::Compression;
-use bonsaidb_local::config::{Builder, KeyValuePersistence, StorageConfiguration};
+use bonsaidb_local::config::{
+    Builder, IntegrityCheck, KeyValuePersistence, PubSubRetention, StorageConfiguration,
+};
+use bonsaidb_local::metrics::MetricsRecorder;
 #[cfg(feature = "encryption")]
 use bonsaidb_local::vault::AnyVaultKeyStorage;
+use fabruic::{CertificateChain, PrivateKey};
+use futures::future::BoxFuture;
+use serde::Serialize;
 
 use crate::api::{AnyHandler, AnyWrapper, Handler};
-use crate::{Backend, Error, NoBackend};
+use crate::{
+    Backend, BackendError, CustomAuthenticator, CustomServer, Error, NoBackend, RateLimit,
+    RateLimiter, TokenBucketRateLimiter,
+};
 
 /// Configuration options for [`Server`](crate::Server)
 #[derive(Debug, Clone)]
@@ -39,11 +51,48 @@ pub struct ServerConfiguration<B: Backend = NoBackend> {
     pub storage: StorageConfiguration,
     /// The permissions granted to all connections to this server.
     pub default_permissions: DefaultPermissions,
+    /// The compression used for `WebSocket` connections whose client requests
+    /// it. If a client requests compression and this is `None`, the connection
+    /// falls back to being uncompressed. The default compresses messages of at
+    /// least 1 kilobyte using zstd at level 3.
+    #[cfg(feature = "compression")]
+    pub transport_compression: Option<TransportCompression>,
     /// The ACME settings for automatic TLS certificate management.
     #[cfg(feature = "acme")]
     pub acme: AcmeConfiguration,
+    /// How long session tokens issued after authenticating with a password
+    /// remain valid. If `None`, which is the default, session tokens are not
+    /// issued.
+    #[cfg(feature = "token-authentication")]
+    pub session_ttl: Option<Duration>,
+    /// The maximum number of clients that can be connected at the same time.
+    /// If `None`, which is the default, the number of connections is not
+    /// limited.
+    pub connection_limit: Option<usize>,
+    /// The maximum number of clients that can be connected from a single IP
+    /// address at the same time. If `None`, which is the default, the number
+    /// of connections per address is not limited.
+    pub connection_limit_per_address: Option<usize>,
+    /// How often all databases are compacted while the server is running. If
+    /// `None`, which is the default, databases are only compacted when
+    /// requested.
+    pub auto_compaction: Option<Duration>,
+    /// The maximum size, in bytes, of documents that clients can insert or
+    /// update. If `None`, which is the default, the size of documents is only
+    /// limited by the available memory. See
+    /// [`Self::with_max_document_size()`] for how the limit is enforced.
+    pub max_document_size: Option<usize>,
+    /// How long a client can go without sending a request before it is
+    /// disconnected. If `None`, which is the default, idle clients remain
+    /// connected.
+    pub idle_timeout: Option<Duration>,
 
     pub(crate) custom_apis: HashMap<ApiName, Arc<dyn AnyHandler<B>>>,
+    pub(crate) custom_authenticators: HashMap<String, Arc<dyn CustomAuthenticator>>,
+    pub(crate) rate_limiter: Option<Arc<dyn RateLimiter>>,
+    pub(crate) certificate: Option<(CertificateChain, PrivateKey)>,
+    pub(crate) startup_hooks: Vec<LifecycleHook<B>>,
+    pub(crate) shutdown_hooks: Vec<LifecycleHook<B>>,
 }
 
 impl<B: Backend> ServerConfiguration<B> {
@@ -58,9 +107,25 @@ impl<B: Backend> ServerConfiguration<B> {
             request_workers: 16,
             storage: bonsaidb_local::config::StorageConfiguration::default(),
             default_permissions: DefaultPermissions::Permissions(Permissions::default()),
+            #[cfg(feature = "compression")]
+            transport_compression: Some(TransportCompression::new(
+                bonsaidb_core::networking::Compression::Zstd { level: 3 },
+            )),
             custom_apis: HashMap::default(),
+            custom_authenticators: HashMap::default(),
+            rate_limiter: None,
+            certificate: None,
+            startup_hooks: Vec::new(),
+            shutdown_hooks: Vec::new(),
             #[cfg(feature = "acme")]
             acme: AcmeConfiguration::default(),
+            #[cfg(feature = "token-authentication")]
+            session_ttl: None,
+            connection_limit: None,
+            connection_limit_per_address: None,
+            auto_compaction: None,
+            max_document_size: None,
+            idle_timeout: None,
         }
     }
 
@@ -96,6 +161,192 @@ impl<B: Backend> ServerConfiguration<B> {
         self
     }
 
+    /// Sets [`Self::transport_compression`](Self#structfield.transport_compression) to `compression` and returns self.
+    #[cfg(feature = "compression")]
+    pub fn transport_compression(mut self, compression: Option<TransportCompression>) -> Self {
+        self.transport_compression = compression;
+        self
+    }
+
+    /// Limits each authenticated identity to `limit` using a
+    /// [`TokenBucketRateLimiter`], and returns self. Unauthenticated
+    /// connections are limited per peer address to [`RateLimit::stricter()`].
+    ///
+    /// Requests exceeding the limit are rejected with
+    /// [`Error::RateLimited`](bonsaidb_core::Error::RateLimited).
+    pub fn with_rate_limit(self, limit: RateLimit) -> Self {
+        self.with_rate_limits(limit, limit.stricter())
+    }
+
+    /// Limits each authenticated identity to `authenticated` and each
+    /// unauthenticated peer address to `unauthenticated` using a
+    /// [`TokenBucketRateLimiter`], and returns self.
+    pub fn with_rate_limits(self, authenticated: RateLimit, unauthenticated: RateLimit) -> Self {
+        self.with_rate_limiter(TokenBucketRateLimiter::new(authenticated, unauthenticated))
+    }
+
+    /// Sets the [`RateLimiter`] that every request is checked against, and
+    /// returns self.
+    pub fn with_rate_limiter<Limiter: RateLimiter>(mut self, limiter: Limiter) -> Self {
+        self.rate_limiter = Some(Arc::new(limiter));
+        self
+    }
+
+    /// Installs `certificate_chain` and `private_key` for TLS connections when
+    /// the server is opened, and returns self. This allows using certificates
+    /// issued by a certificate authority, such as Let's Encrypt, rather than a
+    /// self-signed certificate. Both RSA and ECDSA keys are supported.
+    ///
+    /// The certificate is validated using the same rules as
+    /// [`CustomServer::reload_certificate()`](crate::CustomServer::reload_certificate),
+    /// and opening the server fails if it is invalid.
+    pub fn with_certificate_chain(
+        mut self,
+        certificate_chain: CertificateChain,
+        private_key: PrivateKey,
+    ) -> Self {
+        self.certificate = Some((certificate_chain, private_key));
+        self
+    }
+
+    /// Issues session tokens that remain valid for `ttl`, and returns self.
+    ///
+    /// When a client authenticates as a user or role using any method other
+    /// than a session token, the server issues a session token, which the
+    /// client can use to authenticate future connections with
+    /// [`Authentication::session_token()`](bonsaidb_core::connection::Authentication::session_token).
+    /// Sessions authenticated using a token are granted the current permission
+    /// groups and roles of the user the token was issued to. Tokens can be
+    /// revoked using
+    /// [`CustomServer::revoke_token()`](crate::CustomServer::revoke_token).
+    #[cfg(feature = "token-authentication")]
+    pub fn with_session_ttl(mut self, ttl: Duration) -> Self {
+        self.session_ttl = Some(ttl);
+        self
+    }
+
+    /// Limits the number of clients that can be connected at the same time to
+    /// `limit`, and returns self.
+    ///
+    /// Connections beyond the limit are rejected while connecting. The first
+    /// request sent by a rejected client fails with
+    /// [`networking::Error::ConnectionLimitReached`](bonsaidb_core::networking::Error::ConnectionLimitReached),
+    /// and each rejection is logged and reported to the server's
+    /// [`MetricsRecorder`].
+    pub fn with_connection_limit(mut self, limit: usize) -> Self {
+        self.connection_limit = Some(limit);
+        self
+    }
+
+    /// Limits the number of clients that can be connected from a single IP
+    /// address at the same time to `limit`, and returns self. See
+    /// [`Self::with_connection_limit()`] for how rejected connections are
+    /// handled.
+    pub fn with_connection_limit_per_address(mut self, limit: usize) -> Self {
+        self.connection_limit_per_address = Some(limit);
+        self
+    }
+
+    /// Compacts all databases every `interval` while the server is running, and
+    /// returns self.
+    ///
+    /// Compaction is performed using
+    /// [`AsyncStorage::compact()`](bonsaidb_local::AsyncStorage::compact),
+    /// which uses low-priority jobs and does not prevent databases from being
+    /// read. The number of bytes reclaimed by each compaction is logged.
+    pub fn with_auto_compaction(mut self, interval: Duration) -> Self {
+        self.auto_compaction = Some(interval);
+        self
+    }
+
+    /// Disconnects clients that haven't sent a request for `timeout`, and
+    /// returns self.
+    ///
+    /// Clients with requests that are still executing are not disconnected.
+    /// Idle connections are checked for every quarter of `timeout`, so a
+    /// client may remain connected for up to 25% longer than `timeout`.
+    ///
+    /// The timeout is reported to clients in
+    /// [`ServerStatus::idle_timeout`](bonsaidb_core::networking::ServerStatus::idle_timeout).
+    /// Clients that need to stay connected while idle should enable
+    /// keepalives using an interval shorter than `timeout`, such as with
+    /// `bonsaidb_client::Builder::with_keepalive()`.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Installs `recorder` to receive metrics about requests, connections, and
+    /// background jobs, and returns self. The recorded metrics can be exported
+    /// using [`CustomServer::metrics_snapshot()`](crate::CustomServer::metrics_snapshot).
+    ///
+    /// This sets
+    /// [`StorageConfiguration::metrics_recorder`](StorageConfiguration#structfield.metrics_recorder).
+    pub fn with_metrics_recorder(mut self, recorder: Arc<dyn MetricsRecorder>) -> Self {
+        self.storage.metrics_recorder = Some(recorder);
+        self
+    }
+
+    /// Retains messages published to `topic` in every database according to
+    /// `retention`, and returns self. Subscribers that subscribe using
+    /// [`subscribe_to_with_replay()`](bonsaidb_core::pubsub::AsyncSubscriber::subscribe_to_with_replay)
+    /// receive the retained messages before any newly published messages.
+    ///
+    /// This sets
+    /// [`StorageConfiguration::pubsub_retention`](StorageConfiguration#structfield.pubsub_retention).
+    pub fn with_pubsub_retention<Topic: Serialize>(
+        mut self,
+        topic: &Topic,
+        retention: PubSubRetention,
+    ) -> Result<Self, Error> {
+        self.storage
+            .pubsub_retention
+            .insert(pot::to_vec(topic)?, retention);
+        Ok(self)
+    }
+
+    /// Limits values stored in the key-value store to `max_bytes` and returns
+    /// self. Operations that would store a larger value are rejected with
+    /// [`Error::ValueTooLarge`](bonsaidb_core::Error::ValueTooLarge).
+    ///
+    /// This sets
+    /// [`StorageConfiguration::key_value_max_value_size`](StorageConfiguration#structfield.key_value_max_value_size).
+    pub fn with_max_value_size(mut self, max_bytes: usize) -> Self {
+        self.storage.key_value_max_value_size = Some(max_bytes);
+        self
+    }
+
+    /// Limits the payloads of `PubSub` messages to `max_bytes` and returns
+    /// self. Publishing a larger payload is rejected with
+    /// [`Error::MessageTooLarge`](bonsaidb_core::Error::MessageTooLarge).
+    ///
+    /// This sets
+    /// [`StorageConfiguration::pubsub_max_payload_size`](StorageConfiguration#structfield.pubsub_max_payload_size).
+    pub fn with_max_pubsub_payload_size(mut self, max_bytes: usize) -> Self {
+        self.storage.pubsub_max_payload_size = Some(max_bytes);
+        self
+    }
+
+    /// Limits the documents that clients can insert or update to `max_bytes`
+    /// and returns self. Transactions containing a larger document are
+    /// rejected with
+    /// [`Error::DocumentTooLarge`](bonsaidb_core::Error::DocumentTooLarge)
+    /// before any of their operations are applied.
+    ///
+    /// Requests received over `WebSocket` and HTTP connections are also
+    /// limited to 64 kilobytes more than `max_bytes`, which is checked while
+    /// the request is being received so that oversized requests are never
+    /// fully buffered. A client sending a larger request is disconnected.
+    /// Requests received over QUIC are only checked once they have been
+    /// received.
+    ///
+    /// This only limits documents sent by clients. Documents stored using the
+    /// server's own database connections are not limited.
+    pub fn with_max_document_size(mut self, max_bytes: usize) -> Self {
+        self.max_document_size = Some(max_bytes);
+        self
+    }
+
     /// Sets [`AcmeConfiguration::contact_email`] to `contact_email` and returns self.
     #[cfg(feature = "acme")]
     pub fn acme_contact_email(mut self, contact_email: impl Into<String>) -> Self {
@@ -130,6 +381,113 @@ impl<B: Backend> ServerConfiguration<B> {
         self.register_custom_api::<Dispatcher, Api>()?;
         Ok(self)
     }
+
+    /// Registers `authenticator` to handle
+    /// [`Authentication::Custom`](bonsaidb_core::connection::Authentication::Custom)
+    /// requests whose name is `name`. Registering an authenticator with an
+    /// existing name replaces the previously registered authenticator.
+    pub fn register_authenticator<Authenticator: CustomAuthenticator>(
+        &mut self,
+        name: impl Into<String>,
+        authenticator: Authenticator,
+    ) {
+        self.custom_authenticators
+            .insert(name.into(), Arc::new(authenticator));
+    }
+
+    /// Registers `authenticator` as `name` and returns self. See
+    /// [`Self::register_authenticator`] for more information.
+    pub fn with_authenticator<Authenticator: CustomAuthenticator>(
+        mut self,
+        name: impl Into<String>,
+        authenticator: Authenticator,
+    ) -> Self {
+        self.register_authenticator(name, authenticator);
+        self
+    }
+
+    /// Registers `hook` to be invoked once the server has been opened and
+    /// returns self. Startup hooks are invoked at the end of
+    /// [`CustomServer::open()`], after
+    /// [`Backend::initialize()`](crate::Backend::initialize). Because the
+    /// server can't accept connections until `open()` returns, hooks can
+    /// prepare the server, such as by running migrations or warming caches,
+    /// before any client can connect.
+    ///
+    /// Hooks are invoked in the order they were registered. If a hook returns
+    /// an error, the server is shut down and the error is returned from
+    /// `open()`.
+    ///
+    /// ```rust
+    /// # use bonsaidb_server::{NoBackend, ServerConfiguration};
+    /// use futures::FutureExt;
+    ///
+    /// let configuration = ServerConfiguration::<NoBackend>::default().with_startup_hook(|server| {
+    ///     async move {
+    ///         log::info!("{} is ready", server.primary_domain());
+    ///         Ok(())
+    ///     }
+    ///     .boxed()
+    /// });
+    /// ```
+    pub fn with_startup_hook<Hook>(mut self, hook: Hook) -> Self
+    where
+        Hook: for<'a> Fn(&'a CustomServer<B>) -> BoxFuture<'a, Result<(), BackendError<B::Error>>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.startup_hooks.push(LifecycleHook(Arc::new(hook)));
+        self
+    }
+
+    /// Registers `hook` to be invoked when the server is shut down and
+    /// returns self. Shutdown hooks are invoked by
+    /// [`CustomServer::shutdown()`] before connections are closed, which
+    /// allows hooks to perform tasks like deregistering from service
+    /// discovery while the server is still fully operational.
+    ///
+    /// Hooks are invoked in the order they were registered, and only the first
+    /// time the server is shut down. Errors returned from a hook are logged
+    /// and do not prevent the server from shutting down.
+    pub fn with_shutdown_hook<Hook>(mut self, hook: Hook) -> Self
+    where
+        Hook: for<'a> Fn(&'a CustomServer<B>) -> BoxFuture<'a, Result<(), BackendError<B::Error>>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        self.shutdown_hooks.push(LifecycleHook(Arc::new(hook)));
+        self
+    }
+}
+
+/// A function registered using [`ServerConfiguration::with_startup_hook()`]
+/// or [`ServerConfiguration::with_shutdown_hook()`].
+pub(crate) struct LifecycleHook<B: Backend>(Arc<LifecycleHookFn<B>>);
+
+type LifecycleHookFn<B> = dyn for<'a> Fn(
+        &'a CustomServer<B>,
+    ) -> BoxFuture<'a, Result<(), BackendError<<B as Backend>::Error>>>
+    + Send
+    + Sync;
+
+impl<B: Backend> LifecycleHook<B> {
+    pub async fn invoke(&self, server: &CustomServer<B>) -> Result<(), BackendError<B::Error>> {
+        (self.0)(server).await
+    }
+}
+
+impl<B: Backend> Clone for LifecycleHook<B> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<B: Backend> std::fmt::Debug for LifecycleHook<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LifecycleHook").finish_non_exhaustive()
+    }
 }
 
 impl<B> Default for ServerConfiguration<B>
@@ -215,6 +573,11 @@ impl<B: Backend> Builder for ServerConfiguration<B> {
         self
     }
 
+    fn read_only(mut self, read_only: bool) -> Self {
+        self.storage.read_only = read_only;
+        self
+    }
+
     fn path<P: AsRef<Path>>(mut self, path: P) -> Self {
         self.storage.path = Some(path.as_ref().to_owned());
         self
@@ -255,6 +618,11 @@ impl<B: Backend> Builder for ServerConfiguration<B> {
         self
     }
 
+    fn integrity_check(mut self, check: IntegrityCheck) -> Self {
+        self.storage.integrity_check = check;
+        self
+    }
+
     #[cfg(feature = "compression")]
     fn default_compression(mut self, compression: Compression) -> Self {
         self.storage.default_compression = Some(compression);
@@ -279,6 +647,22 @@ impl<B: Backend> Builder for ServerConfiguration<B> {
         self.storage.argon = argon;
         self
     }
+
+    #[cfg(feature = "password-hashing")]
+    fn password_policy(mut self, policy: bonsaidb_local::config::PasswordPolicy) -> Self {
+        self.storage.password_policy = policy;
+        self
+    }
+
+    fn revision_history(mut self, max_per_document: usize) -> Self {
+        self.storage.revision_history = max_per_document;
+        self
+    }
+
+    fn document_expiration_interval(mut self, interval: Duration) -> Self {
+        self.storage.document_expiration_interval = interval;
+        self
+    }
 }
 
 /// Configuration for the BonsaiDb network protocol.
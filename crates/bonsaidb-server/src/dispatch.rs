@@ -5,17 +5,21 @@ use bonsaidb_core::connection::{
     AsyncConnection, AsyncLowLevelConnection, AsyncStorageConnection, HasSession,
 };
 use bonsaidb_core::keyvalue::AsyncKeyValue;
+#[cfg(feature = "password-hashing")]
+use bonsaidb_core::networking::SetUserPassword;
 use bonsaidb_core::networking::{
     AlterUserPermissionGroupMembership, AlterUserRoleMembership, ApplyTransaction, AssumeIdentity,
-    Compact, CompactCollection, CompactKeyValueStore, Count, CreateDatabase, CreateSubscriber,
-    CreateUser, DeleteDatabase, DeleteDocs, DeleteUser, ExecuteKeyOperation, Get, GetMultiple,
-    LastTransactionId, List, ListAvailableSchemas, ListDatabases, ListExecutedTransactions,
-    ListHeaders, LogOutSession, Publish, PublishToAll, Query, QueryWithDocs, Reduce, ReduceGrouped,
-    SubscribeTo, UnregisterSubscriber, UnsubscribeFrom,
+    Authenticate, Authenticated, Compact, CompactCollection, CompactKeyValueStore, Count,
+    CreateDatabase, CreateSubscriber, CreateUser, DeleteDatabase, DeleteDocs, DeleteUser,
+    ExecuteBatch, ExecuteKeyOperation, Explain, FilterDocuments, Get, GetDatabaseStats,
+    GetMultiple, GetServerStatus, LastTransactionId, List, ListAvailableSchemas, ListDatabases,
+    ListExecutedTransactions, ListHeaders, ListIncludingDeleted, ListUserGroups, ListUsers,
+    LogOutSession, Ping, Publish, PublishBatch, PublishToAll, Query, QueryWithDocs, Reduce,
+    ReduceGrouped, Restore, RevisionHistory, ServerEvent, SubscribeTo, SubscribeToPrefix,
+    UnregisterSubscriber, UnsubscribeFrom, UnsubscribeFromPrefix,
 };
-#[cfg(feature = "password-hashing")]
-use bonsaidb_core::networking::{Authenticate, SetUserPassword};
-use bonsaidb_core::pubsub::AsyncPubSub;
+use bonsaidb_core::permissions::bonsai::{bonsaidb_resource_name, BonsaiAction, ServerAction};
+use bonsaidb_core::pubsub::{AsyncPubSub, BufferLimit, Overflow};
 
 use crate::api::{Handler, HandlerError, HandlerResult, HandlerSession};
 use crate::{Backend, Error, ServerConfiguration};
@@ -29,6 +33,7 @@ pub fn register_api_handlers<B: Backend>(
         .with_api::<ServerDispatcher, AlterUserRoleMembership>()?
         .with_api::<ServerDispatcher, ApplyTransaction>()?
         .with_api::<ServerDispatcher, AssumeIdentity>()?
+        .with_api::<ServerDispatcher, Authenticate>()?
         .with_api::<ServerDispatcher, Compact>()?
         .with_api::<ServerDispatcher, CompactCollection>()?
         .with_api::<ServerDispatcher, CompactKeyValueStore>()?
@@ -39,31 +44,43 @@ pub fn register_api_handlers<B: Backend>(
         .with_api::<ServerDispatcher, DeleteDatabase>()?
         .with_api::<ServerDispatcher, DeleteDocs>()?
         .with_api::<ServerDispatcher, DeleteUser>()?
+        .with_api::<ServerDispatcher, ExecuteBatch>()?
         .with_api::<ServerDispatcher, ExecuteKeyOperation>()?
+        .with_api::<ServerDispatcher, Explain>()?
+        .with_api::<ServerDispatcher, FilterDocuments>()?
         .with_api::<ServerDispatcher, Get>()?
+        .with_api::<ServerDispatcher, GetDatabaseStats>()?
         .with_api::<ServerDispatcher, GetMultiple>()?
+        .with_api::<ServerDispatcher, GetServerStatus>()?
         .with_api::<ServerDispatcher, LastTransactionId>()?
         .with_api::<ServerDispatcher, List>()?
         .with_api::<ServerDispatcher, ListHeaders>()?
+        .with_api::<ServerDispatcher, ListIncludingDeleted>()?
         .with_api::<ServerDispatcher, ListAvailableSchemas>()?
         .with_api::<ServerDispatcher, ListDatabases>()?
         .with_api::<ServerDispatcher, ListExecutedTransactions>()?
+        .with_api::<ServerDispatcher, ListUserGroups>()?
+        .with_api::<ServerDispatcher, ListUsers>()?
         .with_api::<ServerDispatcher, LogOutSession>()?
+        .with_api::<ServerDispatcher, Ping>()?
         .with_api::<ServerDispatcher, Publish>()?
         .with_api::<ServerDispatcher, PublishToAll>()?
+        .with_api::<ServerDispatcher, PublishBatch>()?
         .with_api::<ServerDispatcher, Query>()?
         .with_api::<ServerDispatcher, QueryWithDocs>()?
         .with_api::<ServerDispatcher, Reduce>()?
         .with_api::<ServerDispatcher, ReduceGrouped>()?
+        .with_api::<ServerDispatcher, Restore>()?
+        .with_api::<ServerDispatcher, RevisionHistory>()?
         .with_api::<ServerDispatcher, SubscribeTo>()?
+        .with_api::<ServerDispatcher, SubscribeToPrefix>()?
         .with_api::<ServerDispatcher, UnregisterSubscriber>()?
-        .with_api::<ServerDispatcher, UnsubscribeFrom>()?;
+        .with_api::<ServerDispatcher, UnsubscribeFrom>()?
+        .with_api::<ServerDispatcher, UnsubscribeFromPrefix>()?;
 
     #[cfg(feature = "password-hashing")]
     {
-        config = config
-            .with_api::<ServerDispatcher, Authenticate>()?
-            .with_api::<ServerDispatcher, SetUserPassword>()?;
+        config = config.with_api::<ServerDispatcher, SetUserPassword>()?;
     }
 
     Ok(config)
@@ -93,13 +110,14 @@ impl<B: Backend> Handler<CreateDatabase, B> for ServerDispatcher {
     ) -> HandlerResult<CreateDatabase> {
         session
             .as_client
-            .create_database_with_schema(
+            .create_database_with_schema_and_permissions(
                 &request.database.name,
                 request.database.schema,
                 request.only_if_needed,
+                request.permissions,
             )
-            .await?;
-        Ok(())
+            .await
+            .map_err(HandlerError::from)
     }
 }
 
@@ -128,6 +146,20 @@ impl<B: Backend> Handler<ListDatabases, B> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<GetDatabaseStats, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: GetDatabaseStats,
+    ) -> HandlerResult<GetDatabaseStats> {
+        session
+            .as_client
+            .database_stats(&command.name)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<ListAvailableSchemas, B> for ServerDispatcher {
     async fn handle(
@@ -142,6 +174,56 @@ impl<B: Backend> Handler<ListAvailableSchemas, B> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<Ping, B> for ServerDispatcher {
+    async fn handle(_session: HandlerSession<'_, B>, _command: Ping) -> HandlerResult<Ping> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<GetServerStatus, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        _command: GetServerStatus,
+    ) -> HandlerResult<GetServerStatus> {
+        let mut status = session.server.server_status().await?;
+        if !session.as_client.allowed_to(
+            bonsaidb_resource_name(),
+            &BonsaiAction::Server(ServerAction::GetServerStatus),
+        ) {
+            status.database_count = None;
+            status.connection_count = None;
+        }
+        Ok(status)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<ExecuteBatch, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: ExecuteBatch,
+    ) -> HandlerResult<ExecuteBatch> {
+        let mut results = Vec::with_capacity(command.requests.len());
+        for request in command.requests {
+            let result = Self::dispatch_api_request(
+                HandlerSession {
+                    server: session.server,
+                    as_client: session.as_client.clone(),
+                    client: session.client,
+                },
+                &request.name,
+                request.value,
+            )
+            .await
+            .map_err(bonsaidb_core::Error::from);
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<CreateUser, B> for ServerDispatcher {
     async fn handle(
@@ -170,6 +252,34 @@ impl<B: Backend> Handler<DeleteUser, B> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<ListUsers, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        _command: ListUsers,
+    ) -> HandlerResult<ListUsers> {
+        session
+            .as_client
+            .list_users()
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<ListUserGroups, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: ListUserGroups,
+    ) -> HandlerResult<ListUserGroups> {
+        session
+            .as_client
+            .list_user_groups(command.user)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
 #[cfg(feature = "password-hashing")]
 #[async_trait]
 impl<B: Backend> Handler<SetUserPassword, B> for ServerDispatcher {
@@ -185,22 +295,57 @@ impl<B: Backend> Handler<SetUserPassword, B> for ServerDispatcher {
     }
 }
 
-#[cfg(feature = "password-hashing")]
 #[async_trait]
 impl<B: Backend> Handler<Authenticate, B> for ServerDispatcher {
     async fn handle(
         session: HandlerSession<'_, B>,
         command: Authenticate,
     ) -> HandlerResult<Authenticate> {
-        let authenticated = session
-            .as_client
-            .authenticate(command.authentication)
-            .await?;
+        #[cfg(feature = "token-authentication")]
+        let existing_token = match &command.authentication {
+            bonsaidb_core::connection::Authentication::SessionToken(token) => Some(token.clone()),
+            _ => None,
+        };
+        #[cfg(feature = "password-hashing")]
+        let user = match &command.authentication {
+            bonsaidb_core::connection::Authentication::Password { user, .. } => Some(user.clone()),
+            _ => None,
+        };
+        #[cfg(not(feature = "password-hashing"))]
+        let user = None;
+        let authenticated = match session.as_client.authenticate(command.authentication).await {
+            Ok(authenticated) => authenticated,
+            Err(err) => {
+                session
+                    .server
+                    .publish_server_event(&ServerEvent::AuthenticationFailed {
+                        client_id: session.client.id(),
+                        address: *session.client.address(),
+                        user,
+                        error: err.clone(),
+                    });
+                return Err(HandlerError::from(err));
+            }
+        };
         let new_session = authenticated.session().cloned().unwrap();
 
         session.client.logged_in_as(new_session.clone());
 
-        Ok(new_session)
+        // Sessions authenticated with a session token continue using it, while
+        // sessions authenticated any other way are issued a new token.
+        #[cfg(feature = "token-authentication")]
+        let session_token = match (existing_token, new_session.identity()) {
+            (Some(token), _) => Some(token),
+            (None, Some(identity)) => session.server.issue_session_token(identity).await?,
+            (None, None) => None,
+        };
+        #[cfg(not(feature = "token-authentication"))]
+        let session_token = None;
+
+        Ok(Authenticated {
+            session: new_session,
+            session_token,
+        })
     }
 }
 
@@ -356,6 +501,63 @@ impl<B: Backend> Handler<ListHeaders, B> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<ListIncludingDeleted, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: ListIncludingDeleted,
+    ) -> HandlerResult<ListIncludingDeleted> {
+        let database = session
+            .as_client
+            .database_without_schema(&command.0.database)
+            .await?;
+        database
+            .list_including_deleted_from_collection(
+                command.0.ids,
+                command.0.order,
+                command.0.limit,
+                &command.0.collection,
+            )
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<Restore, B> for ServerDispatcher {
+    async fn handle(session: HandlerSession<'_, B>, command: Restore) -> HandlerResult<Restore> {
+        session
+            .server
+            .check_restored_document_size(&command.database, &command.collection, &command.id)
+            .await?;
+        let database = session
+            .as_client
+            .database_without_schema(&command.database)
+            .await?;
+        database
+            .restore_from_collection(command.id, &command.collection)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<RevisionHistory, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: RevisionHistory,
+    ) -> HandlerResult<RevisionHistory> {
+        let database = session
+            .as_client
+            .database_without_schema(&command.database)
+            .await?;
+        database
+            .revision_history_from_collection(command.id, &command.collection)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<Count, B> for ServerDispatcher {
     async fn handle(session: HandlerSession<'_, B>, command: Count) -> HandlerResult<Count> {
@@ -451,6 +653,7 @@ impl<B: Backend> Handler<ApplyTransaction, B> for ServerDispatcher {
         session: HandlerSession<'_, B>,
         command: ApplyTransaction,
     ) -> HandlerResult<ApplyTransaction> {
+        session.server.check_document_sizes(&command.transaction)?;
         let database = session
             .as_client
             .database_without_schema(&command.database)
@@ -479,6 +682,37 @@ impl<B: Backend> Handler<DeleteDocs, B> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<Explain, B> for ServerDispatcher {
+    async fn handle(session: HandlerSession<'_, B>, command: Explain) -> HandlerResult<Explain> {
+        let database = session
+            .as_client
+            .database_without_schema(&command.database)
+            .await?;
+        database
+            .explain_by_name(&command.view, command.key, command.order, command.limit)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<FilterDocuments, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: FilterDocuments,
+    ) -> HandlerResult<FilterDocuments> {
+        let database = session
+            .as_client
+            .database_without_schema(&command.database)
+            .await?;
+        database
+            .filter_from_collection(&command.filter, command.limit, &command.collection)
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<ListExecutedTransactions, B> for ServerDispatcher {
     async fn handle(
@@ -523,11 +757,21 @@ impl<B: Backend> Handler<CreateSubscriber, B> for ServerDispatcher {
             .as_client
             .database_without_schema(&command.database)
             .await?;
-        let subscriber = database.create_subscriber().await?;
+        // Remote subscribers can't apply backpressure, otherwise a slow or
+        // unresponsive client would delay every publisher of its topics.
+        let limit = command.limit.map(|limit| BufferLimit {
+            overflow: Overflow::DropOldest,
+            ..limit
+        });
+        let subscriber = match limit {
+            Some(limit) => database.create_subscriber_with_limit(limit).await?,
+            None => database.create_subscriber().await?,
+        };
         let subscriber_id = subscriber.id();
 
         session.client.register_subscriber(
             subscriber,
+            limit,
             session.as_client.session().and_then(|session| session.id),
         );
 
@@ -569,6 +813,29 @@ impl<B: Backend> Handler<PublishToAll, B> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<PublishBatch, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: PublishBatch,
+    ) -> HandlerResult<PublishBatch> {
+        let database = session
+            .as_client
+            .database_without_schema(&command.database)
+            .await?;
+        database
+            .publish_batch_bytes(
+                command
+                    .messages
+                    .into_iter()
+                    .map(|(topic, payload)| (topic.into_vec(), payload.into_vec()))
+                    .collect(),
+            )
+            .await
+            .map_err(HandlerError::from)
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<SubscribeTo, B> for ServerDispatcher {
     async fn handle(
@@ -580,6 +847,7 @@ impl<B: Backend> Handler<SubscribeTo, B> for ServerDispatcher {
             .subscribe_by_id(
                 command.subscriber_id,
                 command.topic,
+                command.replay,
                 session.as_client.session().and_then(|session| session.id),
             )
             .map_err(HandlerError::from)
@@ -603,6 +871,40 @@ impl<B: Backend> Handler<UnsubscribeFrom, B> for ServerDispatcher {
     }
 }
 
+#[async_trait]
+impl<B: Backend> Handler<SubscribeToPrefix, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: SubscribeToPrefix,
+    ) -> HandlerResult<SubscribeToPrefix> {
+        session
+            .client
+            .subscribe_to_prefix_by_id(
+                command.subscriber_id,
+                command.prefix,
+                session.as_client.session().and_then(|session| session.id),
+            )
+            .map_err(HandlerError::from)
+    }
+}
+
+#[async_trait]
+impl<B: Backend> Handler<UnsubscribeFromPrefix, B> for ServerDispatcher {
+    async fn handle(
+        session: HandlerSession<'_, B>,
+        command: UnsubscribeFromPrefix,
+    ) -> HandlerResult<UnsubscribeFromPrefix> {
+        session
+            .client
+            .unsubscribe_from_prefix_by_id(
+                command.subscriber_id,
+                &command.prefix,
+                session.as_client.session().and_then(|session| session.id),
+            )
+            .map_err(HandlerError::from)
+    }
+}
+
 #[async_trait]
 impl<B: Backend> Handler<UnregisterSubscriber, B> for ServerDispatcher {
     async fn handle(
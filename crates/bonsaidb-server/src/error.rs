@@ -38,6 +38,10 @@ pub enum Error {
     #[error("a certificate error: {0}")]
     Certificate(#[from] fabruic::error::Certificate),
 
+    /// A certificate failed validation.
+    #[error("an invalid certificate: {0}")]
+    InvalidCertificate(String),
+
     /// An error occurred parsing a PEM file.
     #[error("an invalid PEM file: {0}")]
     #[cfg(feature = "pem")]
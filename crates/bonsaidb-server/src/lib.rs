@@ -18,6 +18,7 @@
 
 /// Types for defining API handlers.
 pub mod api;
+mod authenticator;
 mod backend;
 /// Command-line interface for the server.
 #[cfg(feature = "cli")]
@@ -26,6 +27,7 @@ mod config;
 mod dispatch;
 mod error;
 pub(crate) mod hosted;
+mod rate_limit;
 mod server;
 
 #[cfg(feature = "acme")]
@@ -33,12 +35,15 @@ pub use config::{
     AcmeConfiguration, LETS_ENCRYPT_PRODUCTION_DIRECTORY, LETS_ENCRYPT_STAGING_DIRECTORY,
 };
 
+pub use self::authenticator::{AuthenticatedIdentity, CustomAuthenticator};
 pub use self::backend::{Backend, BackendError, ConnectionHandling, NoBackend};
 pub use self::config::{BonsaiListenConfig, DefaultPermissions, ServerConfiguration};
 pub use self::error::Error;
+pub use self::rate_limit::{RateLimit, RateLimitKey, RateLimiter, TokenBucketRateLimiter};
 pub use self::server::{
-    ApplicationProtocols, ConnectedClient, CustomServer, HttpService, LockedClientDataGuard, Peer,
-    Server, ServerDatabase, StandardTcpProtocols, TcpService, Transport,
+    ApplicationProtocols, ClientEvent, ConnectedClient, CustomServer, HttpService,
+    LockedClientDataGuard, Peer, Replication, ReplicationMode, Server, ServerDatabase,
+    StandardTcpProtocols, TcpService, Transport, CLIENT_EVENTS_TOPIC,
 };
 
 #[cfg(test)]
@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use bonsaidb_core::connection::IdentityId;
+use parking_lot::Mutex;
+
+/// The number of buckets a [`TokenBucketRateLimiter`] tracks before it
+/// discards buckets that have fully refilled.
+const BUCKET_PRUNE_THRESHOLD: usize = 1024;
+
+/// Limits how quickly requests are accepted for a single key.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    /// The sustained number of requests allowed per second.
+    pub per_user_requests_per_second: u32,
+    /// The number of requests that can be made in rapid succession before the
+    /// sustained rate is enforced.
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Returns the limit used for unauthenticated connections when only an
+    /// authenticated limit is configured: a quarter of this limit's rate and
+    /// burst, with a minimum of 1 each.
+    #[must_use]
+    pub fn stricter(&self) -> Self {
+        Self {
+            per_user_requests_per_second: (self.per_user_requests_per_second / 4).max(1),
+            burst: (self.burst / 4).max(1),
+        }
+    }
+}
+
+/// The key a request's rate limit is tracked by.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum RateLimitKey {
+    /// The request was made by a session authenticated as this identity.
+    Identity(IdentityId),
+    /// The request was made by an unauthenticated connection from this
+    /// address.
+    Peer(IpAddr),
+}
+
+/// Decides whether a request should be accepted.
+///
+/// A rate limiter can be installed using
+/// [`ServerConfiguration::with_rate_limiter`](crate::ServerConfiguration::with_rate_limiter).
+/// Requests that are rejected are answered with
+/// [`Error::RateLimited`](bonsaidb_core::Error::RateLimited) without being
+/// queued.
+pub trait RateLimiter: Debug + Send + Sync + 'static {
+    /// Records a request for `key`. Returns `Ok(())` if the request should be
+    /// processed, or the amount of time to wait before the request would be
+    /// accepted.
+    fn check(&self, key: RateLimitKey) -> Result<(), Duration>;
+}
+
+/// A [`RateLimiter`] that uses a token bucket for each key.
+///
+/// Each bucket holds up to [`RateLimit::burst`] tokens and refills at
+/// [`RateLimit::per_user_requests_per_second`] tokens per second. Each request
+/// consumes one token.
+#[derive(Debug)]
+pub struct TokenBucketRateLimiter {
+    authenticated: RateLimit,
+    unauthenticated: RateLimit,
+    buckets: Mutex<HashMap<RateLimitKey, TokenBucket>>,
+}
+
+impl TokenBucketRateLimiter {
+    /// Returns a new rate limiter that applies `authenticated` to each
+    /// authenticated identity and `unauthenticated` to each peer address
+    /// without an authenticated session.
+    #[must_use]
+    pub fn new(authenticated: RateLimit, unauthenticated: RateLimit) -> Self {
+        Self {
+            authenticated,
+            unauthenticated,
+            buckets: Mutex::default(),
+        }
+    }
+
+    fn limit_for(&self, key: RateLimitKey) -> RateLimit {
+        match key {
+            RateLimitKey::Identity(_) => self.authenticated,
+            RateLimitKey::Peer(_) => self.unauthenticated,
+        }
+    }
+}
+
+impl RateLimiter for TokenBucketRateLimiter {
+    fn check(&self, key: RateLimitKey) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock();
+        if buckets.len() >= BUCKET_PRUNE_THRESHOLD {
+            buckets.retain(|key, bucket| !bucket.is_full(self.limit_for(*key), now));
+        }
+        let limit = self.limit_for(key);
+        buckets
+            .entry(key)
+            .or_insert_with(|| TokenBucket::full(limit, now))
+            .take(limit, now)
+    }
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    fn full(limit: RateLimit, now: Instant) -> Self {
+        Self {
+            tokens: f64::from(limit.burst),
+            refilled_at: now,
+        }
+    }
+
+    fn available(&self, limit: RateLimit, now: Instant) -> f64 {
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        let refilled = elapsed.as_secs_f64() * f64::from(limit.per_user_requests_per_second);
+        (self.tokens + refilled).min(f64::from(limit.burst))
+    }
+
+    fn is_full(&self, limit: RateLimit, now: Instant) -> bool {
+        self.available(limit, now) >= f64::from(limit.burst)
+    }
+
+    fn take(&mut self, limit: RateLimit, now: Instant) -> Result<(), Duration> {
+        self.tokens = self.available(limit, now);
+        self.refilled_at = now;
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            Ok(())
+        } else if limit.per_user_requests_per_second == 0 {
+            Err(Duration::MAX)
+        } else {
+            Err(Duration::from_secs_f64(
+                (1. - self.tokens) / f64::from(limit.per_user_requests_per_second),
+            ))
+        }
+    }
+}
@@ -5,63 +5,97 @@ use std::ops::Deref;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 use async_trait::async_trait;
 use bonsaidb_core::admin::{Admin, ADMIN_DATABASE_NAME};
 use bonsaidb_core::api;
-use bonsaidb_core::api::ApiName;
+use bonsaidb_core::api::{Api as _, ApiName};
 use bonsaidb_core::arc_bytes::serde::Bytes;
 use bonsaidb_core::connection::{
-    self, AsyncConnection, AsyncStorageConnection, HasSession, IdentityReference, Session,
-    SessionId,
+    self, AsyncConnection, AsyncLowLevelConnection, AsyncStorageConnection, DatabaseCreation,
+    HasSession, Identity, IdentityId, IdentityReference, Session, SessionId,
+};
+use bonsaidb_core::document::DocumentId;
+use bonsaidb_core::networking::{
+    self, AcknowledgeMessages, CancelRequest, Payload, ServerEvent, ServerStatus,
+    CURRENT_PROTOCOL_VERSION, SERVER_EVENTS_TOPIC, SUPPORTED_PROTOCOL_VERSIONS,
 };
-use bonsaidb_core::networking::{self, Payload, CURRENT_PROTOCOL_VERSION};
 use bonsaidb_core::permissions::bonsai::{bonsaidb_resource_name, BonsaiAction, ServerAction};
-use bonsaidb_core::permissions::Permissions;
+use bonsaidb_core::permissions::{Permissions, Statement};
 use bonsaidb_core::schema::{self, Nameable, NamedCollection, Schema, SchemaSummary};
+use bonsaidb_core::transaction::{Command, Transaction};
 use bonsaidb_local::config::Builder;
-use bonsaidb_local::{AsyncStorage, Storage, StorageNonBlocking};
+use bonsaidb_local::metrics::MetricsRecorder;
+use bonsaidb_local::{AsyncDatabase, AsyncStorage, Storage, StorageNonBlocking};
 use bonsaidb_utils::fast_async_lock;
 use derive_where::derive_where;
 use fabruic::{self, CertificateChain, Endpoint, KeyPair, PrivateKey};
 use flume::Sender;
+use futures::future::{AbortRegistration, Abortable};
 use futures::{Future, StreamExt};
 use parking_lot::{Mutex, RwLock};
+use ring::signature::{
+    EcdsaKeyPair, Ed25519KeyPair, KeyPair as _, RsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING,
+    ECDSA_P384_SHA384_ASN1_SIGNING,
+};
 use rustls::sign::CertifiedKey;
 use schema::SchemaName;
 #[cfg(not(windows))]
 use signal_hook::consts::SIGQUIT;
 use signal_hook::consts::{SIGINT, SIGTERM};
 use tokio::sync::{oneshot, Notify};
+use x509_parser::certificate::X509Certificate;
 
 use crate::api::{AnyHandler, HandlerSession};
 use crate::backend::ConnectionHandling;
 #[cfg(feature = "acme")]
 use crate::config::AcmeConfiguration;
+use crate::config::LifecycleHook;
 use crate::dispatch::{register_api_handlers, ServerDispatcher};
 use crate::error::Error;
 use crate::hosted::{Hosted, SerializablePrivateKey, TlsCertificate, TlsCertificatesByDomain};
 use crate::server::shutdown::{Shutdown, ShutdownState, ShutdownStateWatcher};
-use crate::{Backend, BackendError, BonsaiListenConfig, NoBackend, ServerConfiguration};
+use crate::{
+    Backend, BackendError, BonsaiListenConfig, CustomAuthenticator, NoBackend, RateLimitKey,
+    RateLimiter, ServerConfiguration,
+};
 
 #[cfg(feature = "acme")]
 pub mod acme;
 mod connected_client;
 mod database;
+#[cfg(feature = "http")]
+mod long_polling;
+mod replication;
+#[cfg(feature = "token-authentication")]
+mod session_tokens;
 
 mod shutdown;
 mod tcp;
+#[cfg(all(unix, feature = "websockets"))]
+mod unix_socket;
 #[cfg(feature = "websockets")]
 mod websockets;
 
 use self::connected_client::OwnedClient;
+pub use bonsaidb_core::networking::{ClientEvent, CLIENT_EVENTS_TOPIC};
+
 pub use self::connected_client::{ConnectedClient, LockedClientDataGuard, Transport};
 pub use self::database::ServerDatabase;
+#[cfg(feature = "http")]
+use self::long_polling::HttpConnections;
+pub use self::replication::{Replication, ReplicationMode};
+#[cfg(feature = "token-authentication")]
+use self::session_tokens::SessionTokens;
 pub use self::tcp::{ApplicationProtocols, HttpService, Peer, StandardTcpProtocols, TcpService};
 
 static CONNECTED_CLIENT_ID_COUNTER: AtomicU32 = AtomicU32::new(0);
 
+/// The number of bytes a request can exceed the maximum document size by,
+/// which leaves room for the rest of the request.
+const REQUEST_OVERHEAD: usize = 64 * 1024;
+
 /// A BonsaiDb server.
 #[derive(Debug)]
 #[derive_where(Clone)]
@@ -95,11 +129,30 @@ struct Data<B: Backend = NoBackend> {
     primary_tls_key: CachedCertifiedKey,
     primary_domain: String,
     custom_apis: RwLock<HashMap<ApiName, Arc<dyn AnyHandler<B>>>>,
+    custom_authenticators: HashMap<String, Arc<dyn CustomAuthenticator>>,
+    rate_limiter: Option<Arc<dyn RateLimiter>>,
+    connection_limit: Option<usize>,
+    connection_limit_per_address: Option<usize>,
+    max_document_size: Option<usize>,
+    idle_timeout: Option<Duration>,
+    /// The admin database, opened without a session so that server events can
+    /// be published regardless of the permissions of the connection that
+    /// caused them.
+    admin: AsyncDatabase,
+    #[cfg(feature = "token-authentication")]
+    session_tokens: SessionTokens,
+    metrics: Option<Arc<dyn MetricsRecorder>>,
+    #[cfg(feature = "compression")]
+    transport_compression: Option<networking::TransportCompression>,
     #[cfg(feature = "acme")]
     acme: AcmeConfiguration,
     #[cfg(feature = "acme")]
     alpn_keys: AlpnKeys,
+    #[cfg(feature = "http")]
+    http_connections: HttpConnections,
     shutdown: Shutdown,
+    shutdown_hooks: Mutex<Vec<LifecycleHook<B>>>,
+    started_at: Instant,
 }
 
 #[derive(Default)]
@@ -119,6 +172,141 @@ impl Deref for CachedCertifiedKey {
     }
 }
 
+fn certified_key(
+    certificate_chain: &CertificateChain,
+    private_key: &PrivateKey,
+) -> Result<Arc<CertifiedKey>, Error> {
+    let private_key =
+        rustls::PrivateKey(fabruic::dangerous::PrivateKey::as_ref(private_key).to_vec());
+    let private_key = rustls::sign::any_supported_type(&private_key)?;
+
+    let certificates = certificate_chain
+        .iter()
+        .map(|cert| rustls::Certificate(cert.as_ref().to_vec()))
+        .collect::<Vec<_>>();
+
+    Ok(Arc::new(CertifiedKey::new(certificates, private_key)))
+}
+
+fn parse_end_entity_certificate(
+    certificate_chain: &CertificateChain,
+) -> Result<X509Certificate<'_>, Error> {
+    let end_entity = certificate_chain
+        .iter()
+        .next()
+        .ok_or_else(|| Error::InvalidCertificate(String::from("the certificate chain is empty")))?;
+    let (_, certificate) = x509_parser::parse_x509_certificate(end_entity.as_ref())
+        .map_err(|err| Error::InvalidCertificate(err.to_string()))?;
+    Ok(certificate)
+}
+
+fn validate_certificate(
+    certificate_chain: &CertificateChain,
+    private_key: &PrivateKey,
+) -> Result<(), Error> {
+    KeyPair::from_parts(certificate_chain.clone(), private_key.clone())?;
+    certified_key(certificate_chain, private_key)?;
+
+    let certificate = parse_end_entity_certificate(certificate_chain)?;
+    let public_key = public_key_of(fabruic::dangerous::PrivateKey::as_ref(private_key))
+        .ok_or_else(|| {
+            Error::InvalidCertificate(String::from("the private key is not a supported key type"))
+        })?;
+    if public_key != certificate.public_key().subject_public_key.data.as_ref() {
+        return Err(Error::InvalidCertificate(String::from(
+            "the private key does not match the certificate",
+        )));
+    }
+
+    if certificate.validity().is_valid() {
+        Ok(())
+    } else {
+        Err(Error::InvalidCertificate(String::from(
+            "the certificate is expired or not yet valid",
+        )))
+    }
+}
+
+/// Returns the public key corresponding to the DER-encoded `private_key`, in
+/// the same encoding used by X.509 certificates. The supported key types match
+/// those supported by [`rustls::sign::any_supported_type()`].
+fn public_key_of(private_key: &[u8]) -> Option<Vec<u8>> {
+    [
+        &ECDSA_P256_SHA256_ASN1_SIGNING,
+        &ECDSA_P384_SHA384_ASN1_SIGNING,
+    ]
+    .into_iter()
+    .find_map(|algorithm| EcdsaKeyPair::from_pkcs8(algorithm, private_key).ok())
+    .map(|key| key.public_key().as_ref().to_vec())
+    .or_else(|| {
+        RsaKeyPair::from_pkcs8(private_key)
+            .or_else(|_| RsaKeyPair::from_der(private_key))
+            .ok()
+            .map(|key| key.public_key().as_ref().to_vec())
+    })
+    .or_else(|| {
+        Ed25519KeyPair::from_pkcs8_maybe_unchecked(private_key)
+            .ok()
+            .map(|key| key.public_key().as_ref().to_vec())
+    })
+}
+
+/// Compacts `storage` every `interval` until the server is shut down.
+async fn compact_periodically(
+    storage: AsyncStorage,
+    interval: Duration,
+    mut shutdown: ShutdownStateWatcher,
+) {
+    loop {
+        tokio::select! {
+            () = tokio::time::sleep(interval) => {
+                match storage.compact().await {
+                    Ok(reclaimed) => {
+                        log::info!("[server] Compaction reclaimed {reclaimed} bytes");
+                    }
+                    Err(err) => log::error!("[server] Error compacting storage: {err:?}"),
+                }
+            }
+            state = shutdown.wait_for_shutdown() => {
+                if !matches!(state, ShutdownState::Running) {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Disconnects clients of `server` that have been idle for at least `timeout`
+/// until the server is shut down.
+async fn disconnect_idle_clients<B: Backend>(
+    server: CustomServer<B>,
+    timeout: Duration,
+    mut shutdown: ShutdownStateWatcher,
+) {
+    let interval = (timeout / 4).max(Duration::from_millis(1));
+    loop {
+        tokio::select! {
+            () = tokio::time::sleep(interval) => {
+                for client in server.connected_clients() {
+                    if client.idle_duration() >= timeout && !client.has_in_flight_requests() {
+                        log::info!(
+                            "[server] Disconnecting client {} ({}): idle timeout",
+                            client.id(),
+                            client.address()
+                        );
+                        client.disconnect();
+                    }
+                }
+            }
+            state = shutdown.wait_for_shutdown() => {
+                if !matches!(state, ShutdownState::Running) {
+                    break;
+                }
+            }
+        }
+    }
+}
+
 impl<B: Backend> CustomServer<B> {
     /// Opens a server using `directory` for storage.
     pub async fn open(
@@ -130,38 +318,64 @@ impl<B: Backend> CustomServer<B> {
             let request_receiver = request_receiver.clone();
             tokio::task::spawn(async move {
                 while let Ok(mut client_request) = request_receiver.recv_async().await {
-                    let request = client_request.request.take().unwrap();
+                    let Payload { name, value, .. } = client_request.request.take().unwrap();
+                    let abort = client_request.abort.take();
                     let session = client_request.session.clone();
+                    let metrics = client_request.server.data.metrics.clone();
+                    let started_at = metrics.as_ref().map(|_| Instant::now());
+                    let value = value.unwrap();
                     // TODO we should be able to upgrade a session-less Storage to one with a Session.
                     // The Session needs to be looked up from the client based on the request's session id.
-                    let result = match client_request.server.storage.assume_session(session) {
-                        Ok(storage) => {
-                            let client = HandlerSession {
-                                server: &client_request.server,
-                                client: &client_request.client,
-                                as_client: Self {
-                                    data: client_request.server.data.clone(),
-                                    storage,
-                                },
-                            };
-                            ServerDispatcher::dispatch_api_request(
-                                client,
-                                &request.name,
-                                request.value.unwrap(),
-                            )
+                    let dispatch = async {
+                        let storage = client_request.server.storage.assume_session(session)?;
+                        let client = HandlerSession {
+                            server: &client_request.server,
+                            client: &client_request.client,
+                            as_client: Self {
+                                data: client_request.server.data.clone(),
+                                storage,
+                            },
+                        };
+                        ServerDispatcher::dispatch_api_request(client, &name, value)
                             .await
                             .map_err(bonsaidb_core::Error::from)
-                        }
-                        Err(err) => Err(err),
                     };
-                    drop(client_request.result_sender.send((request.name, result)));
+                    // Requests that the client cancels stop at their next
+                    // await point. Work that has already been handed to a
+                    // blocking thread still runs to completion.
+                    let result = match abort {
+                        Some(abort) => Abortable::new(dispatch, abort).await.unwrap_or(Err(
+                            bonsaidb_core::Error::Networking(networking::Error::RequestCancelled),
+                        )),
+                        None => dispatch.await,
+                    };
+                    if let (Some(metrics), Some(started_at)) = (metrics, started_at) {
+                        metrics.record_request(&name, started_at.elapsed());
+                    }
+                    // Errors that aren't part of a request's normal operation
+                    // are converted to `Error::Other`.
+                    if let Err(err @ bonsaidb_core::Error::Other { .. }) = &result {
+                        client_request
+                            .server
+                            .publish_server_event(&ServerEvent::RequestFailed {
+                                client_id: client_request.client.id(),
+                                request: name.clone(),
+                                error: err.clone(),
+                            });
+                    }
+                    drop(client_request.result_sender.send((name, result)));
                 }
             });
         }
 
+        let metrics = configuration.storage.metrics_recorder.clone();
         let storage = AsyncStorage::open(configuration.storage.with_schema::<Hosted>()?).await?;
 
         storage.create_database::<Hosted>("_hosted", true).await?;
+        #[cfg(feature = "token-authentication")]
+        let session_tokens = SessionTokens::new(&storage, configuration.session_ttl).await?;
+
+        let admin = storage.admin().await;
 
         let default_permissions = Permissions::from(configuration.default_permissions);
 
@@ -179,15 +393,60 @@ impl<B: Backend> CustomServer<B> {
                 primary_tls_key: CachedCertifiedKey::default(),
                 primary_domain: configuration.server_name,
                 custom_apis: parking_lot::RwLock::new(configuration.custom_apis),
+                custom_authenticators: configuration.custom_authenticators,
+                rate_limiter: configuration.rate_limiter,
+                connection_limit: configuration.connection_limit,
+                connection_limit_per_address: configuration.connection_limit_per_address,
+                max_document_size: configuration.max_document_size,
+                idle_timeout: configuration.idle_timeout,
+                admin,
+                #[cfg(feature = "token-authentication")]
+                session_tokens,
+                metrics,
+                #[cfg(feature = "compression")]
+                transport_compression: configuration.transport_compression,
                 #[cfg(feature = "acme")]
                 acme: configuration.acme,
                 #[cfg(feature = "acme")]
                 alpn_keys: AlpnKeys::default(),
+                #[cfg(feature = "http")]
+                http_connections: HttpConnections::default(),
                 shutdown: Shutdown::new(),
+                shutdown_hooks: Mutex::new(configuration.shutdown_hooks),
+                started_at: Instant::now(),
             }),
         };
 
+        if let Some((certificate_chain, private_key)) = configuration.certificate {
+            server
+                .reload_certificate(&certificate_chain, &private_key)
+                .await
+                .map_err(BackendError::Server)?;
+        }
+
+        if let Some(interval) = configuration.auto_compaction {
+            if let Some(shutdown) = server.data.shutdown.watcher().await {
+                tokio::spawn(compact_periodically(
+                    server.storage.clone(),
+                    interval,
+                    shutdown,
+                ));
+            }
+        }
+
+        if let Some(timeout) = configuration.idle_timeout {
+            if let Some(shutdown) = server.data.shutdown.watcher().await {
+                tokio::spawn(disconnect_idle_clients(server.clone(), timeout, shutdown));
+            }
+        }
+
         server.data.backend.initialize(&server).await?;
+        for hook in &configuration.startup_hooks {
+            if let Err(err) = hook.invoke(&server).await {
+                server.data.shutdown.shutdown().await;
+                return Err(err);
+            }
+        }
         Ok(server)
     }
 
@@ -205,6 +464,20 @@ impl<B: Backend> CustomServer<B> {
         &self.data.primary_domain
     }
 
+    /// Returns the metrics gathered by the
+    /// [`MetricsRecorder`] installed with
+    /// [`ServerConfiguration::with_metrics_recorder`], in the Prometheus text
+    /// exposition format. If no recorder is installed, an empty string is
+    /// returned.
+    #[must_use]
+    pub fn metrics_snapshot(&self) -> String {
+        self.data
+            .metrics
+            .as_ref()
+            .map(|metrics| metrics.snapshot())
+            .unwrap_or_default()
+    }
+
     /// Returns the [`Backend`] implementor for this server.
     #[must_use]
     pub fn backend(&self) -> &B {
@@ -306,22 +579,44 @@ impl<B: Backend> CustomServer<B> {
         Ok(())
     }
 
+    /// Validates and installs a certificate chain and private key used for
+    /// TLS connections without restarting the server. New TLS connections use
+    /// the new certificate, while existing connections continue using the
+    /// certificate they were established with until they close.
+    ///
+    /// Before anything is changed, `private_key` is checked to match
+    /// `certificate_chain`, and the end-entity certificate must currently be
+    /// valid. If validation fails, an error is returned and the current
+    /// certificate remains in use.
+    ///
+    /// QUIC endpoints keep using the certificate that was installed when
+    /// [`listen_on()`](Self::listen_on) was called.
+    pub async fn reload_certificate(
+        &self,
+        certificate_chain: &CertificateChain,
+        private_key: &PrivateKey,
+    ) -> Result<(), Error> {
+        validate_certificate(certificate_chain, private_key)?;
+
+        self.install_certificate(certificate_chain, private_key)
+            .await
+    }
+
+    /// Returns the time the current certificate expires.
+    pub async fn certificate_expiry(&self) -> Result<SystemTime, Error> {
+        let certificate_chain = self.certificate_chain().await?;
+        let certificate = parse_end_entity_certificate(&certificate_chain)?;
+        let seconds_since_epoch =
+            u64::try_from(certificate.validity().not_after.timestamp()).unwrap_or_default();
+        Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds_since_epoch))
+    }
+
     async fn refresh_certified_key(&self) -> Result<(), Error> {
         let certificate = self.tls_certificate().await?;
+        let certified_key =
+            certified_key(&certificate.certificate_chain, &certificate.private_key.0)?;
 
         let mut cached_key = self.data.primary_tls_key.lock();
-        let private_key = rustls::PrivateKey(
-            fabruic::dangerous::PrivateKey::as_ref(&certificate.private_key.0).to_vec(),
-        );
-        let private_key = rustls::sign::any_ecdsa_type(&Arc::new(private_key))?;
-
-        let certificates = certificate
-            .certificate_chain
-            .iter()
-            .map(|cert| rustls::Certificate(cert.as_ref().to_vec()))
-            .collect::<Vec<_>>();
-
-        let certified_key = Arc::new(CertifiedKey::new(certificates, private_key));
         *cached_key = Some(certified_key);
         Ok(())
     }
@@ -383,7 +678,11 @@ impl<B: Backend> CustomServer<B> {
         let keypair =
             KeyPair::from_parts(certificate.certificate_chain, certificate.private_key.0)?;
         let mut builder = Endpoint::builder();
-        builder.set_protocols([CURRENT_PROTOCOL_VERSION.as_bytes().to_vec()]);
+        builder.set_protocols(
+            SUPPORTED_PROTOCOL_VERSIONS
+                .iter()
+                .map(|version| version.as_bytes().to_vec()),
+        );
         builder.set_address(config.address);
         builder.set_max_idle_timeout(None)?;
         builder.set_server_key_pair(Some(keypair));
@@ -427,12 +726,75 @@ impl<B: Backend> CustomServer<B> {
     }
 
     /// Returns all of the currently connected clients.
+    ///
+    /// Each time a client connects or disconnects, a [`ClientEvent`] is
+    /// published to [`CLIENT_EVENTS_TOPIC`] on the [admin
+    /// database](Self::admin).
     #[must_use]
     pub fn connected_clients(&self) -> Vec<ConnectedClient<B>> {
         let clients = self.data.clients.read();
         clients.values().cloned().collect()
     }
 
+    /// Returns the status of this server, including the number of databases
+    /// and connected clients.
+    ///
+    /// Clients can request this status using
+    /// [`GetServerStatus`](networking::GetServerStatus). The counts are only
+    /// returned to clients permitted
+    /// [`ServerAction::GetServerStatus`].
+    pub async fn server_status(&self) -> Result<ServerStatus, Error> {
+        let database_count = self.storage.list_databases().await?.len();
+        let connection_count = self.data.clients.read().len();
+        Ok(ServerStatus {
+            version: String::from(env!("CARGO_PKG_VERSION")),
+            protocol_version: String::from(CURRENT_PROTOCOL_VERSION),
+            uptime: self.data.started_at.elapsed(),
+            database_count: Some(u64::try_from(database_count).unwrap_or(u64::MAX)),
+            connection_count: Some(u64::try_from(connection_count).unwrap_or(u64::MAX)),
+            idle_timeout: self.data.idle_timeout,
+        })
+    }
+
+    /// Closes the connection of the client with `id`. Returns false if no
+    /// client with `id` is connected.
+    ///
+    /// See [`ConnectedClient::disconnect()`] for more information.
+    pub fn disconnect_client(&self, id: u32) -> bool {
+        let clients = self.data.clients.read();
+        if let Some(client) = clients.get(&id) {
+            client.disconnect();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn publish_client_event(&self, event: &ClientEvent) {
+        if let Err(err) = self
+            .data
+            .admin
+            .as_blocking()
+            .publish_internal(&CLIENT_EVENTS_TOPIC, event)
+        {
+            log::error!("[server] Error publishing client event: {err:?}");
+        }
+    }
+
+    /// Publishes `event` to [`SERVER_EVENTS_TOPIC`] on the admin database.
+    /// Subscribers that aren't keeping up miss events rather than delaying
+    /// the server.
+    pub(crate) fn publish_server_event(&self, event: &ServerEvent) {
+        if let Err(err) = self
+            .data
+            .admin
+            .as_blocking()
+            .publish_internal(&SERVER_EVENTS_TOPIC, event)
+        {
+            log::error!("[server] Error publishing server event: {err:?}");
+        }
+    }
+
     /// Sends a custom API response to all connected clients.
     pub fn broadcast<Api: api::Api>(&self, response: &Api::Response) {
         let clients = self.data.clients.read();
@@ -458,6 +820,14 @@ impl<B: Backend> CustomServer<B> {
         let client = loop {
             let next_id = CONNECTED_CLIENT_ID_COUNTER.fetch_add(1, Ordering::SeqCst);
             let mut clients = self.data.clients.write();
+            // Connections are typically checked against the limits before
+            // this point, but clients connecting simultaneously may have all
+            // passed those checks.
+            if self.connection_limit_reached(&clients, address) {
+                drop(clients);
+                self.connection_rejected(address);
+                return None;
+            }
             if let hash_map::Entry::Vacant(e) = clients.entry(next_id) {
                 let client = OwnedClient::new(
                     next_id,
@@ -471,9 +841,18 @@ impl<B: Backend> CustomServer<B> {
                 break client;
             }
         };
+        if let Some(metrics) = &self.data.metrics {
+            metrics.connection_opened();
+        }
 
         match self.data.backend.client_connected(&client, self).await {
-            Ok(ConnectionHandling::Accept) => Some(client),
+            Ok(ConnectionHandling::Accept) => {
+                self.publish_client_event(&ClientEvent::Connected {
+                    id: client.id(),
+                    address: *client.address(),
+                });
+                Some(client)
+            }
             Ok(ConnectionHandling::Reject) => None,
             Err(err) => {
                 log::error!(
@@ -484,13 +863,114 @@ impl<B: Backend> CustomServer<B> {
         }
     }
 
-    async fn disconnect_client(&self, id: u32) {
+    /// Returns true if accepting a connection from `address` would exceed the
+    /// configured connection limits.
+    fn connection_limit_reached(
+        &self,
+        clients: &HashMap<u32, ConnectedClient<B>>,
+        address: SocketAddr,
+    ) -> bool {
+        self.data
+            .connection_limit
+            .map_or(false, |limit| clients.len() >= limit)
+            || self
+                .data
+                .connection_limit_per_address
+                .map_or(false, |limit| {
+                    clients
+                        .values()
+                        .filter(|client| client.address().ip() == address.ip())
+                        .count()
+                        >= limit
+                })
+    }
+
+    /// Returns true if a connection from `address` can be accepted without
+    /// exceeding the configured connection limits. If it can't, the rejection
+    /// is logged and recorded.
+    pub(crate) fn accepts_connection_from(&self, address: SocketAddr) -> bool {
+        let limit_reached = {
+            let clients = self.data.clients.read();
+            self.connection_limit_reached(&clients, address)
+        };
+        if limit_reached {
+            self.connection_rejected(address);
+        }
+        !limit_reached
+    }
+
+    /// Returns the largest request, in bytes, that clients can send over
+    /// `WebSocket` and HTTP connections, or `None` if requests aren't limited.
+    pub(crate) fn max_request_size(&self) -> Option<usize> {
+        self.data
+            .max_document_size
+            .map(|limit| limit.saturating_add(REQUEST_OVERHEAD))
+    }
+
+    /// Returns an error if any document inserted or updated by `transaction`
+    /// is larger than the configured maximum document size.
+    pub(crate) fn check_document_sizes(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let Some(limit) = self.data.max_document_size else {
+            return Ok(());
+        };
+        for operation in &transaction.operations {
+            if let Command::Insert { contents, .. }
+            | Command::Update { contents, .. }
+            | Command::Overwrite { contents, .. } = &operation.command
+            {
+                check_document_size(contents.len(), limit)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an error if restoring the soft-deleted document with `id`
+    /// would insert a document larger than the configured maximum document
+    /// size.
+    pub(crate) async fn check_restored_document_size(
+        &self,
+        database: &str,
+        collection: &schema::CollectionName,
+        id: &DocumentId,
+    ) -> Result<(), bonsaidb_core::Error> {
+        let Some(limit) = self.data.max_document_size else {
+            return Ok(());
+        };
+        let database = self.database_without_schema(database).await?;
+        let deleted = database
+            .list_including_deleted_from_collection(
+                connection::Range::from(id.clone()..=id.clone()),
+                connection::Sort::Ascending,
+                Some(1),
+                collection,
+            )
+            .await?;
+        for deleted in deleted.iter().filter(|deleted| deleted.is_deleted()) {
+            check_document_size(deleted.document.contents.len(), limit)?;
+        }
+        Ok(())
+    }
+
+    fn connection_rejected(&self, address: SocketAddr) {
+        log::warn!("[server] Rejecting connection from {address}: connection limit reached");
+        if let Some(metrics) = &self.data.metrics {
+            metrics.connection_rejected();
+        }
+    }
+
+    async fn remove_client(&self, id: u32) {
         let removed_client = {
             let mut clients = self.data.clients.write();
             clients.remove(&id)
         };
 
         if let Some(client) = removed_client {
+            if let Some(metrics) = &self.data.metrics {
+                metrics.connection_closed();
+            }
             client.set_disconnected();
             for session in client.all_sessions::<Vec<_>>() {
                 if let Err(err) = self
@@ -506,6 +986,8 @@ impl<B: Backend> CustomServer<B> {
             if let Err(err) = self.data.backend.client_disconnected(client, self).await {
                 log::error!("[server] Error in `client_disconnected`: {err:?}");
             }
+
+            self.publish_client_event(&ClientEvent::Disconnected { id });
         }
     }
 
@@ -526,7 +1008,24 @@ impl<B: Backend> CustomServer<B> {
                 .accept::<networking::Payload, networking::Payload>()
                 .await
             {
-                Ok((sender, receiver)) => {
+                Ok((sender, mut receiver)) => {
+                    if !self.accepts_connection_from(connection.remote_address()) {
+                        // Reply to the client's first request with the reason
+                        // the connection is being closed.
+                        if let Some(Ok(payload)) = receiver.next().await {
+                            drop(sender.send(&Payload {
+                                session_id: payload.session_id,
+                                id: payload.id,
+                                name: payload.name,
+                                value: Err(bonsaidb_core::Error::Networking(
+                                    networking::Error::ConnectionLimitReached,
+                                )),
+                            }));
+                        }
+                        drop(sender.finish());
+                        return Ok(());
+                    }
+
                     let (api_response_sender, api_response_receiver) = flume::unbounded();
                     if let Some(disconnector) = self
                         .initialize_client(
@@ -620,8 +1119,69 @@ impl<B: Backend> CustomServer<B> {
                         }
                     }
                 };
+                client.record_activity();
                 let session_id = payload.session_id;
                 let id = payload.id;
+
+                let immediate =
+                    if payload.name == CancelRequest::name() {
+                        // Cancellations were applied when they were received by
+                        // `ConnectedClient::receive_payload()`, and only need to
+                        // be acknowledged.
+                        true
+                    } else if payload.name == AcknowledgeMessages::name() {
+                        // Acknowledgements are handled immediately, because the
+                        // requests queued ahead of them may be publishes waiting
+                        // for room in the acknowledged subscriber's buffer.
+                        if let Some(acknowledge) = payload.value.as_ref().ok().and_then(|bytes| {
+                            pot::from_slice::<AcknowledgeMessages>(&bytes[..]).ok()
+                        }) {
+                            drop(client.acknowledge_messages_by_id(
+                                acknowledge.subscriber_id,
+                                acknowledge.count,
+                                session_id,
+                            ));
+                        }
+                        true
+                    } else {
+                        false
+                    };
+                if immediate {
+                    drop(
+                        response_sender.send(Payload {
+                            session_id,
+                            id,
+                            name: payload.name,
+                            value: pot::to_vec(&Result::<(), bonsaidb_core::Error>::Ok(()))
+                                .map(Bytes::from)
+                                .map_err(bonsaidb_core::Error::from),
+                        }),
+                    );
+                    requests_in_queue.fetch_sub(1, Ordering::SeqCst);
+                    continue;
+                }
+
+                let abort = match id {
+                    Some(request_id) => {
+                        let Some(registration) = client.begin_request(request_id) else {
+                            // The client cancelled the request while it was
+                            // queued, so it is rejected without being executed.
+                            drop(response_sender.send(Payload {
+                                session_id,
+                                id,
+                                name: payload.name,
+                                value: Err(bonsaidb_core::Error::Networking(
+                                    networking::Error::RequestCancelled,
+                                )),
+                            }));
+                            requests_in_queue.fetch_sub(1, Ordering::SeqCst);
+                            continue;
+                        };
+                        Some(registration)
+                    }
+                    None => None,
+                };
+
                 let task_sender = response_sender.clone();
 
                 let notify = notify.clone();
@@ -643,6 +1203,7 @@ impl<B: Backend> CustomServer<B> {
                         Ok(())
                     },
                     client.clone(),
+                    abort,
                 )
                 .unwrap();
             }
@@ -657,23 +1218,64 @@ impl<B: Backend> CustomServer<B> {
         request: Payload,
         callback: F,
         client: ConnectedClient<B>,
+        abort: Option<AbortRegistration>,
     ) -> Result<(), Error> {
+        let request_id = request.id;
+        let Some(session) = client.session(request.session_id).or_else(|| {
+            request
+                .session_id
+                .is_none()
+                .then(|| self.data.default_session.clone())
+        }) else {
+            // The session was logged out or was authenticated on a previous
+            // connection.
+            if let Some(id) = request_id {
+                client.finish_request(id);
+            }
+            tokio::spawn(async move {
+                callback(request.name, Err(bonsaidb_core::Error::SessionExpired)).await
+            });
+            return Ok(());
+        };
+        if let Some(limiter) = &self.data.rate_limiter {
+            let key = match session.identity() {
+                Some(Identity::User { id, .. }) => RateLimitKey::Identity(IdentityId::User(*id)),
+                Some(Identity::Role { id, .. }) => RateLimitKey::Identity(IdentityId::Role(*id)),
+                _ => RateLimitKey::Peer(client.address().ip()),
+            };
+            if let Err(retry_after) = limiter.check(key) {
+                // Reject the request without queueing it.
+                if let Some(id) = request_id {
+                    client.finish_request(id);
+                }
+                tokio::spawn(async move {
+                    callback(
+                        request.name,
+                        Err(bonsaidb_core::Error::RateLimited { retry_after }),
+                    )
+                    .await
+                });
+                return Ok(());
+            }
+        }
+
         let (result_sender, result_receiver) = oneshot::channel();
-        let session = client
-            .session(request.session_id)
-            .unwrap_or_else(|| self.data.default_session.clone());
         self.data
             .request_processor
             .send(ClientRequest::<B>::new(
                 request,
+                abort,
                 self.clone(),
-                client,
+                client.clone(),
                 session,
                 result_sender,
             ))
             .map_err(|_| Error::InternalCommunication)?;
         tokio::spawn(async move {
             let (name, result) = result_receiver.await?;
+            if let Some(id) = request_id {
+                client.finish_request(id);
+            }
             // Map the error into a Response::Error. The jobs system supports
             // multiple receivers receiving output, and wraps Err to avoid
             // requiring the error to be cloneable. As such, we have to unwrap
@@ -694,8 +1296,10 @@ impl<B: Backend> CustomServer<B> {
         mut shutdown: ShutdownStateWatcher,
     ) -> Result<(), Error> {
         let (payload_sender, payload_receiver) = flume::unbounded();
+        let connected_client = client.clone();
         tokio::spawn({
             let mut shutdown = shutdown.clone();
+            let connected_client = connected_client.clone();
             async move {
                 'stream: loop {
                     let payload = loop {
@@ -711,6 +1315,7 @@ impl<B: Backend> CustomServer<B> {
                                     break 'stream
                                 }
                             }
+                            () = connected_client.disconnect_requested() => break 'stream,
                         }
                     };
                     if sender.send(&payload).is_err() {
@@ -754,9 +1359,12 @@ impl<B: Backend> CustomServer<B> {
                             return Ok(());
                         }
                     }
+                    () = connected_client.disconnect_requested() => return Ok(()),
                 }
             };
-            drop(request_sender.send_async(payload?).await);
+            let payload = payload?;
+            connected_client.receive_payload(&payload);
+            drop(request_sender.send_async(payload).await);
         }
     }
 
@@ -764,7 +1372,18 @@ impl<B: Backend> CustomServer<B> {
     /// accepting new connections and attempt to respond to any outstanding
     /// requests already being processed. After the `timeout` has elapsed or if
     /// no `timeout` was provided, the server is forcefully shut down.
+    ///
+    /// Before the server begins shutting down, any hooks registered using
+    /// [`ServerConfiguration::with_shutdown_hook()`] are invoked.
     pub async fn shutdown(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        // Hooks are only invoked by the first call to shutdown.
+        let hooks = std::mem::take(&mut *self.data.shutdown_hooks.lock());
+        for hook in hooks {
+            if let Err(err) = hook.invoke(self).await {
+                log::error!("[server] Error in shutdown hook: {err:?}");
+            }
+        }
+
         if let Some(timeout) = timeout {
             self.data.shutdown.graceful_shutdown(timeout).await;
         } else {
@@ -860,6 +1479,7 @@ impl<B: Backend> Deref for CustomServer<B> {
 #[derive(Debug)]
 struct ClientRequest<B: Backend> {
     request: Option<Payload>,
+    abort: Option<AbortRegistration>,
     client: ConnectedClient<B>,
     session: Session,
     server: CustomServer<B>,
@@ -869,6 +1489,7 @@ struct ClientRequest<B: Backend> {
 impl<B: Backend> ClientRequest<B> {
     pub fn new(
         request: Payload,
+        abort: Option<AbortRegistration>,
         server: CustomServer<B>,
         client: ConnectedClient<B>,
         session: Session,
@@ -876,6 +1497,7 @@ impl<B: Backend> ClientRequest<B> {
     ) -> Self {
         Self {
             request: Some(request),
+            abort,
             server,
             client,
             session,
@@ -890,6 +1512,17 @@ impl<B: Backend> HasSession for CustomServer<B> {
     }
 }
 
+fn check_document_size(size: usize, limit: usize) -> Result<(), bonsaidb_core::Error> {
+    if size > limit {
+        Err(bonsaidb_core::Error::DocumentTooLarge {
+            size: size as u64,
+            limit: limit as u64,
+        })
+    } else {
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
     type Authenticated = Self;
@@ -899,15 +1532,29 @@ impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
         self.database::<Admin>(ADMIN_DATABASE_NAME).await.unwrap()
     }
 
-    async fn create_database_with_schema(
+    async fn create_database_with_schema_and_permissions(
         &self,
         name: &str,
         schema: SchemaName,
         only_if_needed: bool,
-    ) -> Result<(), bonsaidb_core::Error> {
-        self.storage
-            .create_database_with_schema(name, schema, only_if_needed)
-            .await
+        permissions: Vec<Statement>,
+    ) -> Result<DatabaseCreation, bonsaidb_core::Error> {
+        let creation = self
+            .storage
+            .create_database_with_schema_and_permissions(
+                name,
+                schema.clone(),
+                only_if_needed,
+                permissions,
+            )
+            .await?;
+        if creation == DatabaseCreation::Created {
+            self.publish_server_event(&ServerEvent::DatabaseCreated {
+                name: name.to_string(),
+                schema,
+            });
+        }
+        Ok(creation)
     }
 
     async fn database<DB: Schema>(
@@ -922,7 +1569,11 @@ impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
     }
 
     async fn delete_database(&self, name: &str) -> Result<(), bonsaidb_core::Error> {
-        self.storage.delete_database(name).await
+        self.storage.delete_database(name).await?;
+        self.publish_server_event(&ServerEvent::DatabaseDeleted {
+            name: name.to_string(),
+        });
+        Ok(())
     }
 
     async fn list_databases(&self) -> Result<Vec<connection::Database>, bonsaidb_core::Error> {
@@ -933,6 +1584,13 @@ impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
         self.storage.list_available_schemas().await
     }
 
+    async fn database_stats(
+        &self,
+        name: &str,
+    ) -> Result<connection::DatabaseStats, bonsaidb_core::Error> {
+        self.storage.database_stats(name).await
+    }
+
     async fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         self.storage.create_user(username).await
     }
@@ -944,6 +1602,10 @@ impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
         self.storage.delete_user(user).await
     }
 
+    async fn list_users(&self) -> Result<Vec<connection::UserInfo>, bonsaidb_core::Error> {
+        self.storage.list_users().await
+    }
+
     #[cfg(feature = "password-hashing")]
     async fn set_user_password<'user, U: Nameable<'user, u64> + Send + Sync>(
         &self,
@@ -953,12 +1615,34 @@ impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
         self.storage.set_user_password(user, password).await
     }
 
-    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
     async fn authenticate(
         &self,
         authentication: bonsaidb_core::connection::Authentication,
     ) -> Result<Self::Authenticated, bonsaidb_core::Error> {
-        let storage = self.storage.authenticate(authentication).await?;
+        let storage = match authentication {
+            bonsaidb_core::connection::Authentication::Custom { name, payload } => {
+                let authenticator = self
+                    .data
+                    .custom_authenticators
+                    .get(&name)
+                    .ok_or(bonsaidb_core::Error::InvalidCredentials)?;
+                let identity = authenticator.authenticate(&payload).await?;
+                self.storage
+                    .authenticate_identity(
+                        identity.into_identity(),
+                        connection::AuthenticationMethod::Custom,
+                    )
+                    .await?
+            }
+            #[cfg(feature = "token-authentication")]
+            bonsaidb_core::connection::Authentication::SessionToken(token) => {
+                let identity = self.data.session_tokens.identity(&token).await?;
+                self.storage
+                    .authenticate_identity(identity, connection::AuthenticationMethod::SessionToken)
+                    .await?
+            }
+            authentication => self.storage.authenticate(authentication).await?,
+        };
         Ok(Self {
             data: self.data.clone(),
             storage,
@@ -1006,6 +1690,13 @@ impl<B: Backend> AsyncStorageConnection for CustomServer<B> {
             .await
     }
 
+    async fn list_user_groups<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<connection::PermissionGroupInfo>, bonsaidb_core::Error> {
+        self.storage.list_user_groups(user).await
+    }
+
     async fn add_role_to_user<
         'user,
         'group,
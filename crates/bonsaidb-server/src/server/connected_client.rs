@@ -3,19 +3,21 @@ use std::net::SocketAddr;
 use std::ops::{Deref, DerefMut};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use async_lock::{Mutex, MutexGuard};
-use bonsaidb_core::api;
-use bonsaidb_core::api::ApiName;
+use bonsaidb_core::api::{self, Api, ApiName};
 use bonsaidb_core::arc_bytes::serde::Bytes;
-use bonsaidb_core::connection::{Session, SessionId};
-use bonsaidb_core::networking::MessageReceived;
-use bonsaidb_core::pubsub::{Receiver, Subscriber as _};
+use bonsaidb_core::connection::{Identity, Session, SessionId};
+use bonsaidb_core::networking::{AcknowledgeMessages, CancelRequest, MessageReceived, Payload};
+use bonsaidb_core::pubsub::{BufferLimit, Receiver, Subscriber as _};
 use bonsaidb_local::Subscriber;
 use bonsaidb_utils::fast_async_lock;
 use derive_where::derive_where;
 use flume::Sender;
+use futures::future::{AbortHandle, AbortRegistration};
 use parking_lot::RwLock;
+use tokio::sync::{watch, Semaphore};
 
 use crate::{Backend, CustomServer, Error, NoBackend};
 
@@ -27,6 +29,12 @@ pub enum Transport {
     /// A connection over WebSockets.
     #[cfg(feature = "websockets")]
     WebSocket,
+    /// A connection over WebSockets on a Unix domain socket.
+    #[cfg(all(unix, feature = "websockets"))]
+    UnixSocket,
+    /// A connection using HTTP long-polling.
+    #[cfg(feature = "http")]
+    Http,
 }
 
 /// A connected database client.
@@ -45,15 +53,73 @@ struct Data<B: Backend = NoBackend> {
     response_sender: Sender<(Option<SessionId>, ApiName, Bytes)>,
     client_data: Mutex<Option<B::ClientData>>,
     connected: AtomicBool,
+    connected_at: SystemTime,
+    last_activity: parking_lot::Mutex<Instant>,
+    disconnect_sender: watch::Sender<bool>,
+    disconnect_receiver: watch::Receiver<bool>,
+    in_flight_requests: parking_lot::Mutex<HashMap<u32, InFlightRequest>>,
+}
+
+#[derive(Debug)]
+struct InFlightRequest {
+    abort: AbortHandle,
+    /// Taken once the request begins executing.
+    registration: Option<AbortRegistration>,
 }
 
 #[derive(Debug)]
 struct ClientSession {
     session: Session,
     subscribers: HashMap<u64, Subscriber>,
+    /// The number of messages that can be sent to each subscriber created
+    /// with a [`BufferLimit`] before the client acknowledges receiving them.
+    message_credits: HashMap<u64, MessageCredits>,
+}
+
+/// Limits the messages sent to a subscriber to the number the client has room
+/// for. Messages that can't be sent remain buffered in the server, where the
+/// subscriber's [`BufferLimit`] is enforced.
+#[derive(Debug)]
+struct MessageCredits {
+    available: Arc<Semaphore>,
+    capacity: usize,
+}
+
+impl MessageCredits {
+    fn new(limit: BufferLimit) -> Self {
+        let capacity = limit.capacity.max(1);
+        Self {
+            available: Arc::new(Semaphore::new(capacity)),
+            capacity,
+        }
+    }
+
+    fn acknowledge(&self, count: u64) {
+        let count = usize::try_from(count).unwrap_or(usize::MAX);
+        // Never allow more messages than the client has room for, even if the
+        // client acknowledges more messages than were sent.
+        let room = self
+            .capacity
+            .saturating_sub(self.available.available_permits());
+        self.available.add_permits(count.min(room));
+    }
+}
+
+impl Drop for MessageCredits {
+    fn drop(&mut self) {
+        // Stops the task forwarding messages to the subscriber.
+        self.available.close();
+    }
 }
 
 impl<B: Backend> ConnectedClient<B> {
+    /// Returns the server-assigned id of this client. Ids are unique among
+    /// all clients currently connected to the server.
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.data.id
+    }
+
     /// Returns the address of the connected client.
     #[must_use]
     pub fn address(&self) -> &SocketAddr {
@@ -72,6 +138,116 @@ impl<B: Backend> ConnectedClient<B> {
         self.data.connected.load(Ordering::Relaxed)
     }
 
+    /// Returns the time the client connected to the server.
+    #[must_use]
+    pub fn connected_at(&self) -> SystemTime {
+        self.data.connected_at
+    }
+
+    /// Returns how long it has been since the server last received a request
+    /// from this client or finished executing one of its requests.
+    ///
+    /// If the server is configured with an
+    /// [idle timeout](crate::ServerConfiguration::with_idle_timeout), clients
+    /// are disconnected once this reaches the timeout.
+    #[must_use]
+    pub fn idle_duration(&self) -> Duration {
+        self.data.last_activity.lock().elapsed()
+    }
+
+    pub(crate) fn record_activity(&self) {
+        *self.data.last_activity.lock() = Instant::now();
+    }
+
+    /// Returns the identities of all sessions this client has authenticated.
+    #[must_use]
+    pub fn authenticated_as<C: FromIterator<Identity>>(&self) -> C {
+        let sessions = self.data.sessions.read();
+        sessions
+            .values()
+            .filter_map(|s| s.session.identity().cloned())
+            .collect()
+    }
+
+    /// Closes this client's connection. Requests that are already executing
+    /// are allowed to finish, but their responses will not be delivered, and
+    /// the client will receive an error on its next request.
+    ///
+    /// The client is removed from
+    /// [`CustomServer::connected_clients()`](crate::CustomServer::connected_clients)
+    /// once the transport has been shut down.
+    pub fn disconnect(&self) {
+        // The receiver is owned by `self.data`, so sending can't fail.
+        drop(self.data.disconnect_sender.send(true));
+    }
+
+    /// Waits until [`Self::disconnect()`] has been called.
+    pub(crate) async fn disconnect_requested(&self) {
+        let mut receiver = self.data.disconnect_receiver.clone();
+        while !*receiver.borrow_and_update() {
+            if receiver.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+
+    /// Handles `payload` as soon as it has been received from the client,
+    /// before it is queued. Requests are tracked from this point on, and
+    /// cancellations are applied immediately, allowing requests that are still
+    /// waiting in the queue to be cancelled before they begin executing.
+    pub(crate) fn receive_payload(&self, payload: &Payload) {
+        if payload.name == CancelRequest::name() {
+            if let Some(cancel) = payload
+                .value
+                .as_ref()
+                .ok()
+                .and_then(|bytes| pot::from_slice::<CancelRequest>(&bytes[..]).ok())
+            {
+                self.cancel_request(cancel.request_id);
+            }
+        } else if payload.name != AcknowledgeMessages::name() {
+            if let Some(id) = payload.id {
+                let (abort, registration) = AbortHandle::new_pair();
+                self.data.in_flight_requests.lock().insert(
+                    id,
+                    InFlightRequest {
+                        abort,
+                        registration: Some(registration),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Begins executing the request with `id`, returning a registration that
+    /// is aborted if the client cancels it. Returns `None` if the client
+    /// cancelled the request before it began executing.
+    ///
+    /// The request is tracked until [`Self::finish_request()`] is called.
+    pub(crate) fn begin_request(&self, id: u32) -> Option<AbortRegistration> {
+        self.data
+            .in_flight_requests
+            .lock()
+            .get_mut(&id)
+            .and_then(|request| request.registration.take())
+    }
+
+    pub(crate) fn finish_request(&self, id: u32) {
+        self.data.in_flight_requests.lock().remove(&id);
+        self.record_activity();
+    }
+
+    pub(crate) fn has_in_flight_requests(&self) -> bool {
+        !self.data.in_flight_requests.lock().is_empty()
+    }
+
+    /// Aborts the request with `id` if it is still queued or executing.
+    pub(crate) fn cancel_request(&self, id: u32) {
+        if let Some(request) = self.data.in_flight_requests.lock().remove(&id) {
+            request.abort.abort();
+        }
+    }
+
     pub(crate) fn set_disconnected(&self) {
         self.data.connected.store(false, Ordering::Relaxed);
     }
@@ -83,6 +259,7 @@ impl<B: Backend> ConnectedClient<B> {
             ClientSession {
                 session,
                 subscribers: HashMap::default(),
+                message_credits: HashMap::default(),
             },
         );
     }
@@ -131,16 +308,24 @@ impl<B: Backend> ConnectedClient<B> {
     pub(crate) fn register_subscriber(
         &self,
         subscriber: Subscriber,
+        limit: Option<BufferLimit>,
         session_id: Option<SessionId>,
     ) {
         let subscriber_id = subscriber.id();
         let receiver = subscriber.receiver().clone();
+        let credits = limit.map(MessageCredits::new);
+        let available_credits = credits.as_ref().map(|credits| credits.available.clone());
         {
             let mut sessions = self.data.sessions.write();
             if let Some(client_session) = sessions.get_mut(&session_id) {
                 client_session
                     .subscribers
                     .insert(subscriber.id(), subscriber);
+                if let Some(credits) = credits {
+                    client_session
+                        .message_credits
+                        .insert(subscriber_id, credits);
+                }
             } else {
                 // TODO return error for session not found.
                 return;
@@ -149,7 +334,7 @@ impl<B: Backend> ConnectedClient<B> {
         let task_self = self.clone();
         tokio::task::spawn(async move {
             task_self
-                .forward_notifications_for(session_id, subscriber_id, receiver)
+                .forward_notifications_for(session_id, subscriber_id, receiver, available_credits)
                 .await;
         });
     }
@@ -165,9 +350,22 @@ impl<B: Backend> ConnectedClient<B> {
         session_id: Option<SessionId>,
         subscriber_id: u64,
         receiver: Receiver,
+        credits: Option<Arc<Semaphore>>,
     ) {
         let session = self.session(session_id);
-        while let Ok(message) = receiver.receive_async().await {
+        loop {
+            if let Some(credits) = &credits {
+                // Wait for the client to have room before taking the next
+                // message, leaving messages buffered where the subscriber's
+                // limit is enforced.
+                match credits.acquire().await {
+                    Ok(permit) => permit.forget(),
+                    Err(_) => break,
+                }
+            }
+            let Ok(message) = receiver.receive_async().await else {
+                break;
+            };
             if self
                 .send::<MessageReceived>(
                     session.as_ref(),
@@ -175,6 +373,7 @@ impl<B: Backend> ConnectedClient<B> {
                         subscriber_id,
                         topic: Bytes::from(message.topic.0.into_vec()),
                         payload: Bytes::from(&message.payload[..]),
+                        lagged: receiver.take_lagged(),
                     },
                 )
                 .is_err()
@@ -188,12 +387,17 @@ impl<B: Backend> ConnectedClient<B> {
         &self,
         subscriber_id: u64,
         topic: Bytes,
+        replay: bool,
         check_session_id: Option<SessionId>,
     ) -> Result<(), crate::Error> {
         let mut sessions = self.data.sessions.write();
         if let Some(client_session) = sessions.get_mut(&check_session_id) {
             if let Some(subscriber) = client_session.subscribers.get(&subscriber_id) {
-                subscriber.subscribe_to_bytes(topic.0)?;
+                if replay {
+                    subscriber.subscribe_to_bytes_with_replay(topic.0)?;
+                } else {
+                    subscriber.subscribe_to_bytes(topic.0)?;
+                }
                 Ok(())
             } else {
                 Err(Error::other(
@@ -228,6 +432,75 @@ impl<B: Backend> ConnectedClient<B> {
         }
     }
 
+    pub(crate) fn subscribe_to_prefix_by_id(
+        &self,
+        subscriber_id: u64,
+        prefix: Bytes,
+        check_session_id: Option<SessionId>,
+    ) -> Result<(), crate::Error> {
+        let mut sessions = self.data.sessions.write();
+        if let Some(client_session) = sessions.get_mut(&check_session_id) {
+            if let Some(subscriber) = client_session.subscribers.get(&subscriber_id) {
+                subscriber.subscribe_to_prefix_bytes(prefix.0)?;
+                Ok(())
+            } else {
+                Err(Error::other(
+                    "bonsaidb-server pubsub",
+                    "invalid subscriber id",
+                ))
+            }
+        } else {
+            Err(Error::other("bonsaidb-server auth", "invalid session id"))
+        }
+    }
+
+    pub(crate) fn unsubscribe_from_prefix_by_id(
+        &self,
+        subscriber_id: u64,
+        prefix: &[u8],
+        check_session_id: Option<SessionId>,
+    ) -> Result<(), crate::Error> {
+        let mut sessions = self.data.sessions.write();
+        if let Some(client_session) = sessions.get_mut(&check_session_id) {
+            if let Some(subscriber) = client_session.subscribers.get(&subscriber_id) {
+                subscriber.unsubscribe_from_prefix_bytes(prefix)?;
+                Ok(())
+            } else {
+                Err(Error::other(
+                    "bonsaidb-server pubsub",
+                    "invalid subscriber id",
+                ))
+            }
+        } else {
+            Err(Error::other("bonsaidb-server auth", "invalid session id"))
+        }
+    }
+
+    pub(crate) fn acknowledge_messages_by_id(
+        &self,
+        subscriber_id: u64,
+        count: u64,
+        check_session_id: Option<SessionId>,
+    ) -> Result<(), crate::Error> {
+        let sessions = self.data.sessions.read();
+        if let Some(client_session) = sessions.get(&check_session_id) {
+            if let Some(credits) = client_session.message_credits.get(&subscriber_id) {
+                credits.acknowledge(count);
+                Ok(())
+            } else if client_session.subscribers.contains_key(&subscriber_id) {
+                // Subscribers without a limit don't track credits.
+                Ok(())
+            } else {
+                Err(Error::other(
+                    "bonsaidb-server pubsub",
+                    "invalid subscriber id",
+                ))
+            }
+        } else {
+            Err(Error::other("bonsaidb-server auth", "invalid session id"))
+        }
+    }
+
     pub(crate) fn unregister_subscriber_by_id(
         &self,
         subscriber_id: u64,
@@ -235,6 +508,7 @@ impl<B: Backend> ConnectedClient<B> {
     ) -> Result<(), crate::Error> {
         let mut sessions = self.data.sessions.write();
         if let Some(client_session) = sessions.get_mut(&check_session_id) {
+            client_session.message_credits.remove(&subscriber_id);
             if client_session.subscribers.remove(&subscriber_id).is_some() {
                 Ok(())
             } else {
@@ -288,8 +562,10 @@ impl<B: Backend> OwnedClient<B> {
             ClientSession {
                 session: default_session,
                 subscribers: HashMap::default(),
+                message_credits: HashMap::default(),
             },
         );
+        let (disconnect_sender, disconnect_receiver) = watch::channel(false);
         Self {
             client: ConnectedClient {
                 data: Arc::new(Data {
@@ -300,6 +576,11 @@ impl<B: Backend> OwnedClient<B> {
                     sessions: RwLock::new(session),
                     client_data: Mutex::default(),
                     connected: AtomicBool::new(true),
+                    connected_at: SystemTime::now(),
+                    last_activity: parking_lot::Mutex::new(Instant::now()),
+                    disconnect_sender,
+                    disconnect_receiver,
+                    in_flight_requests: parking_lot::Mutex::default(),
                 }),
             },
             runtime: Arc::new(tokio::runtime::Handle::current()),
@@ -317,7 +598,7 @@ impl<B: Backend> Drop for OwnedClient<B> {
         let id = self.client.data.id;
         let server = self.server.take().unwrap();
         self.runtime
-            .spawn(async move { server.disconnect_client(id).await });
+            .spawn(async move { server.remove_client(id).await });
     }
 }
 
@@ -2,14 +2,17 @@ use std::ops::Deref;
 
 use async_trait::async_trait;
 use bonsaidb_core::connection::{
-    AccessPolicy, AsyncLowLevelConnection, HasSchema, HasSession, Range, SerializedQueryKey, Sort,
+    AccessPolicy, AsyncLowLevelConnection, HasSchema, HasSession, QueryPlan, Range,
+    SerializedQueryKey, Sort,
+};
+use bonsaidb_core::document::{
+    DocumentId, Header, HistoricalRevision, MaybeDeleted, OwnedDocument,
 };
-use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
 use bonsaidb_core::keyvalue::AsyncKeyValue;
-use bonsaidb_core::permissions::Permissions;
-use bonsaidb_core::pubsub::AsyncPubSub;
+use bonsaidb_core::permissions::{Action, Identifier, Permissions};
+use bonsaidb_core::pubsub::{AsyncPubSub, BufferLimit, PublishReceipt};
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
-use bonsaidb_core::schema::{self, CollectionName, Schematic, ViewName};
+use bonsaidb_core::schema::{self, CollectionName, Filter, Schematic, ViewName};
 use bonsaidb_core::transaction::{OperationResult, Transaction};
 use bonsaidb_local::{AsyncDatabase, Database};
 use derive_where::derive_where;
@@ -67,11 +70,18 @@ impl<B: Backend> AsyncPubSub for ServerDatabase<B> {
         Ok(subscriber)
     }
 
+    async fn create_subscriber_with_limit(
+        &self,
+        limit: BufferLimit,
+    ) -> Result<Self::Subscriber, bonsaidb_core::Error> {
+        self.db.create_subscriber_with_limit(limit).await
+    }
+
     async fn publish_bytes(
         &self,
         topic: Vec<u8>,
         payload: Vec<u8>,
-    ) -> Result<(), bonsaidb_core::Error> {
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
         self.db.publish_bytes(topic, payload).await
     }
 
@@ -79,15 +89,38 @@ impl<B: Backend> AsyncPubSub for ServerDatabase<B> {
         &self,
         topics: impl IntoIterator<Item = Vec<u8>> + Send + 'async_trait,
         payload: Vec<u8>,
-    ) -> Result<(), bonsaidb_core::Error> {
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
         self.db.publish_bytes_to_all(topics, payload).await
     }
+
+    async fn publish_batch_bytes(
+        &self,
+        messages: Vec<(Vec<u8>, Vec<u8>)>,
+    ) -> Result<PublishReceipt, bonsaidb_core::Error> {
+        self.db.publish_batch_bytes(messages).await
+    }
 }
 
 impl<B: Backend> HasSession for ServerDatabase<B> {
     fn session(&self) -> Option<&bonsaidb_core::connection::Session> {
         self.server.session()
     }
+
+    fn allowed_to<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> bool {
+        self.db.allowed_to(resource_name, action)
+    }
+
+    fn check_permission<'a, R: AsRef<[Identifier<'a>]>, P: Action>(
+        &self,
+        resource_name: R,
+        action: &P,
+    ) -> Result<(), bonsaidb_core::Error> {
+        self.db.check_permission(resource_name, action)
+    }
 }
 
 /// Pass-through implementation
@@ -159,6 +192,17 @@ impl<B: Backend> AsyncLowLevelConnection for ServerDatabase<B> {
             .await
     }
 
+    async fn filter_from_collection(
+        &self,
+        filter: &Filter,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<OwnedDocument>, bonsaidb_core::Error> {
+        self.db
+            .filter_from_collection(filter, limit, collection)
+            .await
+    }
+
     async fn list_headers_from_collection(
         &self,
         ids: Range<DocumentId>,
@@ -171,6 +215,36 @@ impl<B: Backend> AsyncLowLevelConnection for ServerDatabase<B> {
             .await
     }
 
+    async fn list_including_deleted_from_collection(
+        &self,
+        ids: Range<DocumentId>,
+        order: Sort,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<MaybeDeleted>, bonsaidb_core::Error> {
+        self.db
+            .list_including_deleted_from_collection(ids, order, limit, collection)
+            .await
+    }
+
+    async fn restore_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Header, bonsaidb_core::Error> {
+        self.db.restore_from_collection(id, collection).await
+    }
+
+    async fn revision_history_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Vec<HistoricalRevision>, bonsaidb_core::Error> {
+        self.db
+            .revision_history_from_collection(id, collection)
+            .await
+    }
+
     async fn count_from_collection(
         &self,
         ids: Range<DocumentId>,
@@ -249,6 +323,16 @@ impl<B: Backend> AsyncLowLevelConnection for ServerDatabase<B> {
         self.db.delete_docs_by_name(view, key, access_policy).await
     }
 
+    async fn explain_by_name(
+        &self,
+        view: &ViewName,
+        key: Option<SerializedQueryKey>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, bonsaidb_core::Error> {
+        self.db.explain_by_name(view, key, order, limit).await
+    }
+
     async fn apply_transaction(
         &self,
         transaction: Transaction,
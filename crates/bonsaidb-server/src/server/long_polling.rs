@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use bonsaidb_core::networking::{
+    write_http_frame, HTTP_CONNECTION_HEADER, HTTP_PROTOCOL_HEADER, PROTOCOL_VERSIONS_HEADER,
+    SUPPORTED_PROTOCOL_VERSIONS,
+};
+use futures::stream::BoxStream;
+use futures::{Sink, SinkExt, Stream, StreamExt};
+use hyper::body::HttpBody;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use parking_lot::Mutex;
+use ring::rand::{SecureRandom, SystemRandom};
+use tokio_tungstenite::tungstenite::Message;
+
+use crate::server::websockets::negotiate_protocol;
+use crate::{Backend, CustomServer, Transport};
+
+/// The longest a poll waits for a message before responding without one.
+const POLL_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// How long a connection can go without being polled before it is
+/// disconnected.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The HTTP long-polling connections of a server, keyed by the id assigned
+/// when connecting.
+#[derive(Debug, Default)]
+pub struct HttpConnections(Mutex<HashMap<String, Arc<HttpConnection>>>);
+
+#[derive(Debug)]
+struct HttpConnection {
+    /// Messages received from the client.
+    incoming: flume::Sender<Message>,
+    /// Messages waiting for the client to poll for them.
+    outgoing: flume::Receiver<Message>,
+    last_polled: Mutex<Instant>,
+}
+
+impl<B: Backend> CustomServer<B> {
+    /// Handles `request` as part of an HTTP long-polling connection, which
+    /// allows clients to connect using `http://` and `https://` URLs when a
+    /// `WebSocket` connection can't be established, such as through a proxy
+    /// that doesn't support them. Requires feature `http` to be enabled.
+    ///
+    /// Every request sent to the URL clients connect to should be passed to
+    /// this function, regardless of its method. Messages are encoded the same
+    /// way as they are over a `WebSocket`, including any negotiated
+    /// compression.
+    ///
+    /// ## Latency
+    ///
+    /// Long-polling is slower than the other transports, and should only be
+    /// used when they aren't available:
+    ///
+    /// - Each request the client sends is a separate HTTP request. Requests
+    ///   made while another is being sent are batched together.
+    /// - The server can only send responses and `PubSub` messages while the
+    ///   client has a poll outstanding. Messages that arrive while no poll is
+    ///   outstanding wait for the client's next poll, which adds a round trip
+    ///   of latency.
+    /// - Polls that don't receive any messages are answered after 20 seconds,
+    ///   so an idle connection still sends a request every 20 seconds.
+    ///
+    /// Connections that aren't polled for 60 seconds are disconnected.
+    pub async fn handle_http_request(
+        &self,
+        peer_address: SocketAddr,
+        request: Request<Body>,
+    ) -> Response<Body> {
+        let connection_id = request
+            .headers()
+            .get(HTTP_CONNECTION_HEADER)
+            .and_then(|id| id.to_str().ok())
+            .map(String::from);
+        let method = request.method().clone();
+        match (&method, connection_id) {
+            (&Method::POST, None) => self.connect_http(peer_address, &request),
+            (&Method::POST, Some(id)) => self.receive_http_messages(&id, request.into_body()).await,
+            (&Method::GET, Some(id)) => self.poll_http_connection(&id).await,
+            _ => status_response(StatusCode::BAD_REQUEST),
+        }
+    }
+
+    fn connect_http(&self, peer_address: SocketAddr, request: &Request<Body>) -> Response<Body> {
+        if !self.accepts_connection_from(peer_address) {
+            return status_response(StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        let Some((protocol, compression)) = request
+            .headers()
+            .get(HTTP_PROTOCOL_HEADER)
+            .and_then(|protocols| protocols.to_str().ok())
+            .and_then(|protocols| {
+                negotiate_protocol(protocols, self.supported_transport_compression())
+            })
+        else {
+            // Let the client know which versions are supported so that it can
+            // report the incompatibility.
+            let mut response = status_response(StatusCode::NOT_ACCEPTABLE);
+            response.headers_mut().insert(
+                PROTOCOL_VERSIONS_HEADER,
+                SUPPORTED_PROTOCOL_VERSIONS.join(", ").try_into().unwrap(),
+            );
+            return response;
+        };
+
+        let Some(id) = new_connection_id() else {
+            return status_response(StatusCode::INTERNAL_SERVER_ERROR);
+        };
+        let (incoming_sender, incoming_receiver) = flume::unbounded();
+        let (outgoing_sender, outgoing_receiver) = flume::unbounded();
+        let connection = Arc::new(HttpConnection {
+            incoming: incoming_sender,
+            outgoing: outgoing_receiver,
+            last_polled: Mutex::new(Instant::now()),
+        });
+        self.data
+            .http_connections
+            .0
+            .lock()
+            .insert(id.clone(), connection.clone());
+
+        let stream = HttpStream {
+            incoming: incoming_receiver
+                .into_stream()
+                .map(Ok::<_, Infallible>)
+                .boxed(),
+            outgoing: Box::pin(outgoing_sender.into_sink()),
+        };
+        let task_self = self.clone();
+        tokio::spawn(async move {
+            task_self
+                .handle_websocket_with_compression(
+                    stream,
+                    peer_address,
+                    Transport::Http,
+                    compression,
+                )
+                .await;
+        });
+        tokio::spawn(
+            self.clone()
+                .disconnect_idle_http_connection(id.clone(), Arc::downgrade(&connection)),
+        );
+
+        let mut response = status_response(StatusCode::OK);
+        response
+            .headers_mut()
+            .insert(HTTP_CONNECTION_HEADER, id.try_into().unwrap());
+        response
+            .headers_mut()
+            .insert(HTTP_PROTOCOL_HEADER, protocol.try_into().unwrap());
+        response
+    }
+
+    async fn receive_http_messages(&self, id: &str, mut body: Body) -> Response<Body> {
+        let Some(connection) = self.http_connection(id) else {
+            return status_response(StatusCode::NOT_FOUND);
+        };
+
+        // Frames are delivered as they are received so that an oversized
+        // frame can be rejected as soon as its length has been read.
+        let max_frame_size = self.max_request_size();
+        let mut buffer = Vec::new();
+        while let Some(chunk) = body.data().await {
+            let Ok(chunk) = chunk else {
+                return status_response(StatusCode::BAD_REQUEST);
+            };
+            buffer.extend_from_slice(&chunk);
+            if let Err(status) = deliver_frames(&connection, &mut buffer, max_frame_size) {
+                self.remove_http_connection(id);
+                return status_response(status);
+            }
+        }
+
+        if buffer.is_empty() {
+            status_response(StatusCode::NO_CONTENT)
+        } else {
+            status_response(StatusCode::BAD_REQUEST)
+        }
+    }
+
+    /// Responds with the messages waiting to be sent to the client, waiting
+    /// up to [`POLL_TIMEOUT`] for one to be available.
+    async fn poll_http_connection(&self, id: &str) -> Response<Body> {
+        let Some(connection) = self.http_connection(id) else {
+            return status_response(StatusCode::NOT_FOUND);
+        };
+        *connection.last_polled.lock() = Instant::now();
+
+        let mut body = Vec::new();
+        let mut closed =
+            match tokio::time::timeout(POLL_TIMEOUT, connection.outgoing.recv_async()).await {
+                Ok(Ok(message)) => write_message(&mut body, message),
+                Ok(Err(_)) => true,
+                Err(_) => false,
+            };
+        while !closed {
+            match connection.outgoing.try_recv() {
+                Ok(message) => closed = write_message(&mut body, message),
+                Err(flume::TryRecvError::Empty) => break,
+                Err(flume::TryRecvError::Disconnected) => closed = true,
+            }
+        }
+        *connection.last_polled.lock() = Instant::now();
+
+        if closed {
+            self.remove_http_connection(id);
+            if body.is_empty() {
+                return status_response(StatusCode::GONE);
+            }
+        }
+
+        Response::new(Body::from(body))
+    }
+
+    /// Disconnects the connection with `id` once it hasn't been polled for
+    /// [`IDLE_TIMEOUT`].
+    async fn disconnect_idle_http_connection(self, id: String, connection: Weak<HttpConnection>) {
+        loop {
+            tokio::time::sleep(IDLE_TIMEOUT / 4).await;
+            let Some(connection) = connection.upgrade() else {
+                break;
+            };
+            let idle = connection.last_polled.lock().elapsed();
+            if idle > IDLE_TIMEOUT {
+                log::warn!("[server] Disconnecting idle HTTP connection");
+                self.remove_http_connection(&id);
+                break;
+            }
+        }
+    }
+
+    fn http_connection(&self, id: &str) -> Option<Arc<HttpConnection>> {
+        self.data.http_connections.0.lock().get(id).cloned()
+    }
+
+    /// Removes the connection with `id`. Once any outstanding requests
+    /// complete, the connection's channels are dropped, which disconnects the
+    /// client.
+    fn remove_http_connection(&self, id: &str) {
+        self.data.http_connections.0.lock().remove(id);
+    }
+}
+
+/// Returns a new, unguessable connection id.
+fn new_connection_id() -> Option<String> {
+    let mut bytes = [0; 16];
+    SystemRandom::new().fill(&mut bytes).ok()?;
+    Some(bytes.iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+/// Sends each complete frame at the start of `buffer` to `connection`,
+/// removing the frames from `buffer`. Frames are written using
+/// [`write_http_frame()`].
+///
+/// Returns `PAYLOAD_TOO_LARGE` if a frame is longer than `max_frame_size`, or
+/// `GONE` if the connection has been closed.
+fn deliver_frames(
+    connection: &HttpConnection,
+    buffer: &mut Vec<u8>,
+    max_frame_size: Option<usize>,
+) -> Result<(), StatusCode> {
+    let mut offset = 0;
+    while let Some(length) = buffer.get(offset..offset + 4) {
+        let length = u32::from_be_bytes(length.try_into().expect("4 bytes"));
+        let length = usize::try_from(length).map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+        if max_frame_size.map_or(false, |max| length > max) {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        let Some(frame) = buffer[offset + 4..].get(..length) else {
+            break;
+        };
+        connection
+            .incoming
+            .send(Message::Binary(frame.to_vec()))
+            .map_err(|_| StatusCode::GONE)?;
+        offset += 4 + length;
+    }
+    buffer.drain(..offset);
+    Ok(())
+}
+
+/// Appends `message` to `body`, returning true if the message closes the
+/// connection.
+fn write_message(body: &mut Vec<u8>, message: Message) -> bool {
+    match message {
+        Message::Binary(frame) => {
+            write_http_frame(body, &frame);
+            false
+        }
+        Message::Close(_) => true,
+        // Clients don't send pings, so no other messages need to be
+        // delivered.
+        _ => false,
+    }
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// The messages of an [`HttpConnection`], adapted to the stream of messages
+/// that connections are handled with.
+struct HttpStream {
+    incoming: BoxStream<'static, Result<Message, Infallible>>,
+    outgoing: Pin<Box<dyn Sink<Message, Error = flume::SendError<Message>> + Send>>,
+}
+
+impl Stream for HttpStream {
+    type Item = Result<Message, Infallible>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.incoming.poll_next_unpin(cx)
+    }
+}
+
+impl Sink<Message> for HttpStream {
+    type Error = flume::SendError<Message>;
+
+    fn poll_ready(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.outgoing.poll_ready_unpin(cx)
+    }
+
+    fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+        self.outgoing.start_send_unpin(item)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.outgoing.poll_flush_unpin(cx)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.outgoing.poll_close_unpin(cx)
+    }
+}
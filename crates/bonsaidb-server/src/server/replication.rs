@@ -0,0 +1,366 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bonsaidb_core::connection::{AsyncLowLevelConnection, AsyncStorageConnection};
+use bonsaidb_core::document::DocumentId;
+use bonsaidb_core::keyvalue::{
+    AsyncKeyValue, Command, KeyOperation, Numeric, Output, SetCommand, Value,
+};
+use bonsaidb_core::schema::CollectionName;
+use bonsaidb_core::transaction::{ChangedKey, Changes, DocumentChanges, Operation, Transaction};
+use bonsaidb_local::{AsyncDatabase, Storage};
+use parking_lot::Mutex;
+use tokio::task::JoinHandle;
+
+use crate::{Backend, CustomServer, Error};
+
+/// The key-value namespace used to track replication progress within a
+/// replica. Keys in this namespace are never replicated.
+const REPLICATION_NAMESPACE: &str = "_bonsaidb.replication";
+const LAST_APPLIED_KEY: &str = "last-applied-transaction";
+
+/// The maximum number of transactions requested from the leader at once.
+const BATCH_SIZE: u32 = 1_000;
+/// How long to wait between checking the leader for new transactions once the
+/// replica has caught up.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// How long to wait before retrying after an error while replicating
+/// continuously.
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Controls how long replication continues after a replica has caught up with
+/// its leader.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReplicationMode {
+    /// Stop replicating once all transactions that have been executed on the
+    /// leader have been applied.
+    Once,
+    /// Continue applying transactions as they are executed on the leader until
+    /// the [`Replication`] is stopped or dropped. Errors, including
+    /// disconnections from the leader, are logged and retried from the last
+    /// applied transaction.
+    Continuous,
+}
+
+impl<B: Backend> CustomServer<B> {
+    /// Replicates `databases` from `leader` into this server, making this
+    /// server a standby for those databases.
+    ///
+    /// `leader` is typically a `Client` connected to the leader server, and
+    /// must be permitted to read the transaction log, documents, and key-value
+    /// entries of each database. Any database that doesn't exist on this
+    /// server is created using the same schema as the leader's database, which
+    /// must be registered with this server.
+    ///
+    /// While the returned [`Replication`] is alive, the replicated databases
+    /// can be read normally, but any attempt to modify them returns
+    /// [`bonsaidb_core::Error::ReadOnlyReplica`]. Stopping or dropping the
+    /// [`Replication`] makes the databases writable again, which can be used
+    /// to promote this server if the leader is lost.
+    ///
+    /// Replication applies the leader's executed transactions in order,
+    /// copying the current state of each changed document and key. The
+    /// replica converges on the leader's state, but may skip intermediate
+    /// states of a document that changed several times between polls. The
+    /// last applied transaction is stored in each replica, allowing
+    /// replication to resume where it left off after a network failure or a
+    /// restart. Because of this, a replica should only ever be replicated from
+    /// a single leader.
+    pub async fn replicate_from<L>(
+        &self,
+        leader: L,
+        databases: &[&str],
+        mode: ReplicationMode,
+    ) -> Result<Replication, Error>
+    where
+        L: AsyncStorageConnection + 'static,
+        L::Database: AsyncKeyValue + 'static,
+    {
+        let leader_databases = leader.list_databases().await?;
+        let mut replicas = Vec::with_capacity(databases.len());
+        for &name in databases {
+            let schema = leader_databases
+                .iter()
+                .find(|database| database.name == name)
+                .ok_or_else(|| bonsaidb_core::Error::DatabaseNotFound(name.to_string()))?
+                .schema
+                .clone();
+            self.storage
+                .create_database_with_schema(name, schema, true)
+                .await?;
+            replicas.push((
+                name.to_string(),
+                self.storage.database_without_schema(name).await?,
+            ));
+        }
+
+        let storage = self.storage.to_blocking();
+        for (name, _) in &replicas {
+            storage.set_replica(name, true);
+        }
+
+        let last_applied = Arc::new(Mutex::new(HashMap::new()));
+        let task = tokio::spawn(replicate(leader, replicas, mode, last_applied.clone()));
+
+        Ok(Replication {
+            task: Some(task),
+            storage,
+            databases: databases.iter().map(|name| (*name).to_string()).collect(),
+            last_applied,
+        })
+    }
+}
+
+/// An active replication started by [`CustomServer::replicate_from()`].
+///
+/// Dropping this value stops replication and allows the replicated databases
+/// to be modified again.
+#[derive(Debug)]
+#[must_use = "replication stops when `Replication` is dropped"]
+pub struct Replication {
+    task: Option<JoinHandle<Result<(), Error>>>,
+    storage: Storage,
+    databases: Vec<String>,
+    last_applied: Arc<Mutex<HashMap<String, u64>>>,
+}
+
+impl Replication {
+    /// Returns the id of the last transaction from the leader that has been
+    /// applied to the replica of `database`, if any.
+    #[must_use]
+    pub fn last_applied_transaction(&self, database: &str) -> Option<u64> {
+        self.last_applied.lock().get(database).copied()
+    }
+
+    /// Waits for replication to finish. With [`ReplicationMode::Once`], this
+    /// returns after all transactions have been applied. With
+    /// [`ReplicationMode::Continuous`], this only returns if replication could
+    /// not be started.
+    pub async fn wait(mut self) -> Result<(), Error> {
+        let task = self.task.take().expect("only taken in wait or drop");
+        task.await.map_err(|err| Error::other("replication", err))?
+    }
+
+    /// Stops replicating and allows the replicated databases to be modified.
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+impl Drop for Replication {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+        for name in &self.databases {
+            self.storage.set_replica(name, false);
+        }
+    }
+}
+
+async fn replicate<L>(
+    leader: L,
+    replicas: Vec<(String, AsyncDatabase)>,
+    mode: ReplicationMode,
+    last_applied: Arc<Mutex<HashMap<String, u64>>>,
+) -> Result<(), Error>
+where
+    L: AsyncStorageConnection,
+    L::Database: AsyncKeyValue,
+{
+    let mut databases = Vec::with_capacity(replicas.len());
+    for (name, replica) in replicas {
+        let applied = read_last_applied(&replica).await?;
+        if let Some(applied) = applied {
+            last_applied.lock().insert(name.clone(), applied);
+        }
+        databases.push(ReplicatedDatabase {
+            leader: leader.database::<()>(&name).await?,
+            name,
+            replica,
+            last_applied: applied,
+        });
+    }
+
+    loop {
+        let mut caught_up = true;
+        for database in &mut databases {
+            match database.apply_next_batch(&last_applied).await {
+                Ok(database_caught_up) => caught_up &= database_caught_up,
+                Err(err) if mode == ReplicationMode::Continuous => {
+                    log::error!(
+                        "[server] error replicating database {}: {err}",
+                        database.name
+                    );
+                    tokio::time::sleep(RETRY_DELAY).await;
+                    caught_up = false;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        if caught_up {
+            match mode {
+                ReplicationMode::Once => return Ok(()),
+                ReplicationMode::Continuous => tokio::time::sleep(POLL_INTERVAL).await,
+            }
+        }
+    }
+}
+
+struct ReplicatedDatabase<Leader> {
+    name: String,
+    leader: Leader,
+    replica: AsyncDatabase,
+    last_applied: Option<u64>,
+}
+
+impl<Leader> ReplicatedDatabase<Leader>
+where
+    Leader: AsyncLowLevelConnection + AsyncKeyValue,
+{
+    /// Applies the next batch of transactions from the leader, returning true
+    /// if the replica has caught up.
+    async fn apply_next_batch(
+        &mut self,
+        last_applied: &Mutex<HashMap<String, u64>>,
+    ) -> Result<bool, Error> {
+        let transactions = self
+            .leader
+            .list_executed_transactions(self.last_applied.map(|id| id + 1), Some(BATCH_SIZE))
+            .await?;
+
+        for executed in &transactions {
+            match &executed.changes {
+                Changes::Documents(changes) => self.apply_document_changes(changes).await?,
+                Changes::Keys(keys) => self.apply_key_changes(keys).await?,
+            }
+
+            self.replica
+                .execute_replicated_key_operation(KeyOperation {
+                    namespace: Some(REPLICATION_NAMESPACE.to_string()),
+                    key: LAST_APPLIED_KEY.to_string(),
+                    command: Command::Set(SetCommand {
+                        value: Value::Numeric(Numeric::UnsignedInteger(executed.id)),
+                        expiration: None,
+                        keep_existing_expiration: false,
+                        check: None,
+                        return_previous_value: false,
+                    }),
+                })
+                .await?;
+            self.last_applied = Some(executed.id);
+            last_applied.lock().insert(self.name.clone(), executed.id);
+        }
+
+        Ok(transactions.len() < BATCH_SIZE as usize)
+    }
+
+    async fn apply_document_changes(&self, changes: &DocumentChanges) -> Result<(), Error> {
+        let mut changed_ids = HashMap::<&CollectionName, Vec<DocumentId>>::new();
+        for (collection, changed) in changes.iter() {
+            let ids = changed_ids.entry(collection).or_default();
+            if !ids.contains(&changed.id) {
+                ids.push(changed.id.clone());
+            }
+        }
+
+        let mut transaction = Transaction::new();
+        for (collection, ids) in changed_ids {
+            let mut documents = self
+                .leader
+                .get_multiple_from_collection(&ids, collection)
+                .await?
+                .into_iter()
+                .map(|document| (document.header.id.clone(), document.contents))
+                .collect::<HashMap<_, _>>();
+            for id in ids {
+                if let Some(contents) = documents.remove(&id) {
+                    transaction.push(Operation::overwrite(collection.clone(), id, contents));
+                } else if let Some(existing) =
+                    self.replica.get_from_collection(id, collection).await?
+                {
+                    transaction.push(Operation::delete(collection.clone(), existing.header));
+                }
+            }
+        }
+
+        if !transaction.operations.is_empty() {
+            self.replica
+                .apply_replicated_transaction(transaction)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn apply_key_changes(&self, keys: &[ChangedKey]) -> Result<(), Error> {
+        for changed in keys
+            .iter()
+            .filter(|changed| changed.namespace.as_deref() != Some(REPLICATION_NAMESPACE))
+        {
+            let value = if changed.deleted {
+                None
+            } else {
+                match self
+                    .leader_key(changed, Command::Get { delete: false })
+                    .await?
+                {
+                    Output::Value(value) => value,
+                    _ => None,
+                }
+            };
+            let command = if let Some(value) = value {
+                let expiration = match self.leader_key(changed, Command::GetExpiration).await? {
+                    Output::Expiration(expiration) => expiration,
+                    _ => None,
+                };
+                Command::Set(SetCommand {
+                    value,
+                    expiration,
+                    keep_existing_expiration: false,
+                    check: None,
+                    return_previous_value: false,
+                })
+            } else {
+                Command::Delete
+            };
+
+            self.replica
+                .execute_replicated_key_operation(KeyOperation {
+                    namespace: changed.namespace.clone(),
+                    key: changed.key.clone(),
+                    command,
+                })
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn leader_key(&self, changed: &ChangedKey, command: Command) -> Result<Output, Error> {
+        Ok(self
+            .leader
+            .execute_key_operation(KeyOperation {
+                namespace: changed.namespace.clone(),
+                key: changed.key.clone(),
+                command,
+            })
+            .await?)
+    }
+}
+
+async fn read_last_applied(replica: &AsyncDatabase) -> Result<Option<u64>, Error> {
+    let output = replica
+        .execute_replicated_key_operation(KeyOperation {
+            namespace: Some(REPLICATION_NAMESPACE.to_string()),
+            key: LAST_APPLIED_KEY.to_string(),
+            command: Command::Get { delete: false },
+        })
+        .await?;
+    Ok(match output {
+        Output::Value(Some(value)) => value.as_u64(),
+        _ => None,
+    })
+}
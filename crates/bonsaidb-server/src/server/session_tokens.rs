@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use bonsaidb_core::connection::{
+    AsyncStorageConnection, Identity, IdentityId, IdentityReference, SensitiveString,
+};
+use bonsaidb_core::keyvalue::{AsyncKeyValue, KeyStatus};
+use bonsaidb_local::{AsyncDatabase, AsyncStorage};
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+
+use crate::hosted::Hosted;
+use crate::{Backend, CustomServer, Error};
+
+/// The key-value namespace within the server's hosted database that session
+/// tokens are stored in.
+const SESSION_TOKEN_NAMESPACE: &str = "_bonsaidb.session-tokens";
+
+impl<B: Backend> CustomServer<B> {
+    /// Revokes `token`, preventing it from being used to authenticate.
+    /// Returns true if `token` was a valid session token.
+    ///
+    /// Revoking a token does not affect connections that have already
+    /// authenticated using it.
+    pub async fn revoke_token(&self, token: &SensitiveString) -> Result<bool, Error> {
+        Ok(self.data.session_tokens.revoke(token).await?)
+    }
+
+    /// Issues a new session token for `identity`, returning `None` if session
+    /// tokens are not enabled for this server.
+    pub(crate) async fn issue_session_token(
+        &self,
+        identity: &Identity,
+    ) -> Result<Option<SensitiveString>, bonsaidb_core::Error> {
+        self.data.session_tokens.issue(identity).await
+    }
+}
+
+/// Issues and verifies session tokens.
+///
+/// Only a hash of each token is stored, and the key-value store removes it
+/// once the token expires. Tokens are verified using the server's own storage
+/// rather than the storage of the session being authenticated, which typically
+/// isn't permitted to access the hosted database.
+#[derive(Debug)]
+pub(crate) struct SessionTokens {
+    ttl: Option<Duration>,
+    hosted: AsyncDatabase,
+}
+
+impl SessionTokens {
+    pub async fn new(storage: &AsyncStorage, ttl: Option<Duration>) -> Result<Self, Error> {
+        Ok(Self {
+            ttl,
+            hosted: storage.database::<Hosted>("_hosted").await?,
+        })
+    }
+
+    /// Issues a new session token for `identity`, returning `None` if session
+    /// tokens are not enabled or the storage is read-only.
+    pub async fn issue(
+        &self,
+        identity: &Identity,
+    ) -> Result<Option<SensitiveString>, bonsaidb_core::Error> {
+        let Some(ttl) = self.ttl else {
+            return Ok(None);
+        };
+        let identity = match identity {
+            Identity::User { id, .. } => IdentityId::User(*id),
+            Identity::Role { id, .. } => IdentityId::Role(*id),
+            _ => return Ok(None),
+        };
+
+        let tokens = self.hosted.with_key_namespace(SESSION_TOKEN_NAMESPACE);
+        loop {
+            let token = random_token();
+            let status = match tokens
+                .set_key(token_key(&token), &identity)
+                .expire_in(ttl)
+                .only_if_vacant()
+                .await
+            {
+                Ok(status) => status,
+                // Tokens can't be stored when the storage is read-only.
+                Err(bonsaidb_core::Error::ReadOnly) => return Ok(None),
+                Err(err) => return Err(err),
+            };
+            if status == KeyStatus::Inserted {
+                return Ok(Some(token));
+            }
+        }
+    }
+
+    /// Returns the identity that `token` was issued to, if it is valid.
+    pub async fn identity(
+        &self,
+        token: &SensitiveString,
+    ) -> Result<IdentityReference<'static>, bonsaidb_core::Error> {
+        let identity = self
+            .hosted
+            .with_key_namespace(SESSION_TOKEN_NAMESPACE)
+            .get_key(token_key(token))
+            .into::<IdentityId>()
+            .await?
+            .ok_or(bonsaidb_core::Error::InvalidCredentials)?;
+        match identity {
+            IdentityId::User(id) => IdentityReference::user(id),
+            IdentityId::Role(id) => IdentityReference::role(id),
+            _ => Err(bonsaidb_core::Error::InvalidCredentials),
+        }
+    }
+
+    async fn revoke(&self, token: &SensitiveString) -> Result<bool, bonsaidb_core::Error> {
+        let status = self
+            .hosted
+            .with_key_namespace(SESSION_TOKEN_NAMESPACE)
+            .delete_key(token_key(token))
+            .await?;
+        Ok(status == KeyStatus::Deleted)
+    }
+}
+
+fn random_token() -> SensitiveString {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_-";
+    let mut rng = thread_rng();
+    SensitiveString(
+        std::iter::repeat_with(|| ALPHABET.choose(&mut rng))
+            .take(43)
+            .map(|c| *c.unwrap() as char)
+            .collect(),
+    )
+}
+
+fn token_key(token: &SensitiveString) -> String {
+    blake3::hash(token.as_bytes()).to_hex().to_string()
+}
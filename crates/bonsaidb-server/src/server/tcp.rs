@@ -156,7 +156,11 @@ impl<B: Backend> CustomServer<B> {
         if let Err(connection) = service.handle_connection(connection, &peer).await {
             #[cfg(feature = "websockets")]
             if let Err(err) = self
-                .handle_raw_websocket_connection(connection, peer.address)
+                .handle_raw_websocket_connection(
+                    connection,
+                    peer.address,
+                    crate::Transport::WebSocket,
+                )
                 .await
             {
                 log::error!(
@@ -0,0 +1,68 @@
+use std::net::{Ipv6Addr, SocketAddr};
+use std::os::unix::fs::FileTypeExt;
+use std::path::Path;
+
+use tokio::net::UnixListener;
+
+use crate::{Backend, CustomServer, Error, Transport};
+
+impl<B: Backend> CustomServer<B> {
+    /// Listens for connections on the Unix domain socket at `path`. Clients
+    /// connect using a `unix` URL, such as `unix:///var/run/bonsaidb.sock`.
+    ///
+    /// The same protocol used for `WebSocket` connections is used over the
+    /// socket, which allows local clients to connect without the overhead of
+    /// TCP and TLS. Access to the socket is controlled by the filesystem
+    /// permissions of `path`.
+    ///
+    /// If a socket already exists at `path`, it is removed before listening.
+    /// Because Unix domain sockets have no peer address, clients connected
+    /// via the socket report a loopback address from
+    /// [`ConnectedClient::address()`](crate::ConnectedClient::address).
+    pub async fn listen_on_unix_socket(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        // A socket left behind by a previous run prevents binding.
+        if let Ok(metadata) = tokio::fs::symlink_metadata(path).await {
+            if metadata.file_type().is_socket() {
+                tokio::fs::remove_file(path).await?;
+            }
+        }
+
+        let listener = UnixListener::bind(path)?;
+        let mut shutdown_watcher = self
+            .data
+            .shutdown
+            .watcher()
+            .await
+            .expect("server already shutdown");
+
+        loop {
+            tokio::select! {
+                _ = shutdown_watcher.wait_for_shutdown() => {
+                    break;
+                }
+                incoming = listener.accept() => {
+                    let Ok((connection, _)) = incoming else {
+                        continue;
+                    };
+
+                    let task_self = self.clone();
+                    tokio::spawn(async move {
+                        if let Err(err) = task_self
+                            .handle_raw_websocket_connection(
+                                connection,
+                                SocketAddr::from((Ipv6Addr::LOCALHOST, 0)),
+                                Transport::UnixSocket,
+                            )
+                            .await
+                        {
+                            log::error!("[server] closing unix socket connection: {:?}", err);
+                        }
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,6 +1,9 @@
-use bonsaidb_core::networking::{Payload, CURRENT_PROTOCOL_VERSION};
+use bonsaidb_core::networking::{
+    Payload, TransportCompression, PROTOCOL_VERSIONS_HEADER, SUPPORTED_PROTOCOL_VERSIONS,
+};
 use futures::{SinkExt, StreamExt};
 use tokio::io::{AsyncRead, AsyncWrite};
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
 use tokio_tungstenite::tungstenite::Message;
 
 use crate::server::connected_client::OwnedClient;
@@ -9,6 +12,21 @@ use crate::{Backend, CustomServer, Error, Transport};
 
 impl<B: Backend> CustomServer<B> {
     /// Listens for websocket connections on `addr`.
+    ///
+    /// When `with_tls` is true, connections are secured using the server's
+    /// installed certificate, and clients connect using `wss://` URLs.
+    /// Otherwise, clients connect using `ws://` URLs.
+    ///
+    /// Web browsers refuse insecure websocket connections from pages served
+    /// over HTTPS. For browsers to accept a secure connection, the server's
+    /// certificate must be issued for the domain in the URL and be signed by
+    /// a certificate authority the browser trusts. Certificates installed
+    /// using
+    /// [`install_self_signed_certificate()`](Self::install_self_signed_certificate)
+    /// are not trusted by browsers. Instead, install a certificate issued by a
+    /// public authority using
+    /// [`install_certificate()`](Self::install_certificate), or enable feature
+    /// `acme` to have one automatically managed.
     pub async fn listen_for_websockets_on<T: tokio::net::ToSocketAddrs + Send + Sync>(
         &self,
         addr: T,
@@ -27,12 +45,45 @@ impl<B: Backend> CustomServer<B> {
         &self,
         connection: S,
         peer_address: std::net::SocketAddr,
+        transport: Transport,
     ) -> Result<(), Error> {
-        let stream = tokio_tungstenite::accept_hdr_async(connection, VersionChecker).await?;
-        self.handle_websocket(stream, peer_address).await;
+        let mut compression = None;
+        let stream = tokio_tungstenite::accept_hdr_async_with_config(
+            connection,
+            VersionChecker {
+                supported_compression: self.supported_transport_compression(),
+                negotiated_compression: &mut compression,
+                connection_limit_reached: !self.accepts_connection_from(peer_address),
+            },
+            self.websocket_config(),
+        )
+        .await?;
+        self.handle_websocket_with_compression(stream, peer_address, transport, compression)
+            .await;
         Ok(())
     }
 
+    /// Returns the configuration for `WebSocket` connections, which limits the
+    /// size of messages received to [`Self::max_request_size()`].
+    fn websocket_config(&self) -> Option<WebSocketConfig> {
+        self.max_request_size().map(|max_size| WebSocketConfig {
+            max_message_size: Some(max_size),
+            max_frame_size: Some(max_size),
+            ..WebSocketConfig::default()
+        })
+    }
+
+    #[cfg(feature = "compression")]
+    pub(crate) fn supported_transport_compression(&self) -> Option<TransportCompression> {
+        self.data.transport_compression
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[allow(clippy::unused_self)]
+    pub(crate) const fn supported_transport_compression(&self) -> Option<TransportCompression> {
+        None
+    }
+
     /// Handles upgrading an HTTP connection to the `WebSocket` protocol based
     /// on the upgrade `request`. Requires feature `hyper` to be enabled.
     #[cfg(feature = "hyper")]
@@ -61,11 +112,21 @@ impl<B: Backend> CustomServer<B> {
             return response;
         };
 
+        if !self.accepts_connection_from(peer_address) {
+            *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+            return response;
+        }
+
         let task_self = self.clone();
         tokio::spawn(async move {
             match hyper::upgrade::on(&mut request).await {
                 Ok(upgraded) => {
-                    let ws = WebSocketStream::from_raw_socket(upgraded, Role::Server, None).await;
+                    let ws = WebSocketStream::from_raw_socket(
+                        upgraded,
+                        Role::Server,
+                        task_self.websocket_config(),
+                    )
+                    .await;
                     task_self.handle_websocket(ws, peer_address).await;
                 }
                 Err(err) => {
@@ -100,6 +161,28 @@ impl<B: Backend> CustomServer<B> {
         &self,
         connection: S,
         peer_address: std::net::SocketAddr,
+    ) {
+        self.handle_websocket_with_compression(
+            connection,
+            peer_address,
+            Transport::WebSocket,
+            None,
+        )
+        .await;
+    }
+
+    pub(crate) async fn handle_websocket_with_compression<
+        S: futures::Stream<Item = Result<tokio_tungstenite::tungstenite::Message, E>>
+            + futures::Sink<tokio_tungstenite::tungstenite::Message>
+            + Send
+            + 'static,
+        E: std::fmt::Debug + Send,
+    >(
+        &self,
+        connection: S,
+        peer_address: std::net::SocketAddr,
+        transport: Transport,
+        compression: Option<TransportCompression>,
     ) {
         let mut shutdown = self
             .data
@@ -114,7 +197,7 @@ impl<B: Backend> CustomServer<B> {
 
         let (api_response_sender, api_response_receiver) = flume::unbounded();
         let Some(client) = self
-            .initialize_client(Transport::WebSocket, peer_address, api_response_sender)
+            .initialize_client(transport, peer_address, api_response_sender)
             .await
         else {
             return;
@@ -150,7 +233,10 @@ impl<B: Backend> CustomServer<B> {
         tokio::spawn(async move {
             while let Ok(response) = response_receiver.recv_async().await {
                 if task_sender
-                    .send(Message::Binary(bincode::serialize(&response)?))
+                    .send(Message::Binary(encode_payload(
+                        &response,
+                        compression.as_ref(),
+                    )?))
                     .is_err()
                 {
                     break;
@@ -163,6 +249,8 @@ impl<B: Backend> CustomServer<B> {
         let (request_sender, request_receiver) =
             flume::bounded::<Payload>(self.data.client_simultaneous_request_limit);
 
+        let max_request_size = self.max_request_size();
+        let connected_client = client.clone();
         self.spawn_client_request_handler(client, request_receiver, response_sender, &shutdown);
 
         loop {
@@ -170,8 +258,11 @@ impl<B: Backend> CustomServer<B> {
                 payload = receiver.next() => {
                     if let Some(payload) = payload {
                         match payload {
-                            Ok(Message::Binary(binary)) => match bincode::deserialize::<Payload>(&binary) {
-                                Ok(payload) => drop(request_sender.send_async(payload).await),
+                            Ok(Message::Binary(binary)) => match decode_payload(&binary, compression.is_some(), max_request_size) {
+                                Ok(payload) => {
+                                    connected_client.receive_payload(&payload);
+                                    drop(request_sender.send_async(payload).await);
+                                }
                                 Err(err) => {
                                     log::error!("[server] error decoding message: {:?}", err);
                                     break;
@@ -195,6 +286,10 @@ impl<B: Backend> CustomServer<B> {
                         return;
                     }
                 }
+                () = connected_client.disconnect_requested() => {
+                    drop(message_sender.send(Message::Close(None)));
+                    return;
+                }
             }
         }
     }
@@ -223,6 +318,69 @@ impl<B: Backend> CustomServer<B> {
     }
 }
 
+/// Chooses the most preferred protocol version supported by this server from
+/// the comma-separated list of `offered` protocols. Returns the chosen
+/// protocol and the compression it enables, or `None` if none of the offered
+/// protocols are supported.
+pub(crate) fn negotiate_protocol(
+    offered: &str,
+    supported_compression: Option<TransportCompression>,
+) -> Option<(String, Option<TransportCompression>)> {
+    let offered = offered.split(',').map(str::trim).collect::<Vec<_>>();
+    for version in SUPPORTED_PROTOCOL_VERSIONS {
+        // Prefer a compressed connection if the client requested one that
+        // this server supports. Otherwise, fall back to an uncompressed
+        // connection.
+        if let Some(compression) = supported_compression {
+            let compressed_protocol = compression.compression.protocol_name(version);
+            if offered.contains(&compressed_protocol.as_str()) {
+                return Some((compressed_protocol, Some(compression)));
+            }
+        }
+
+        if offered.contains(version) {
+            return Some(((*version).to_string(), None));
+        }
+    }
+
+    None
+}
+
+fn encode_payload(
+    payload: &Payload,
+    compression: Option<&TransportCompression>,
+) -> Result<Vec<u8>, Error> {
+    let encoded = bincode::serialize(payload)?;
+    match compression {
+        #[cfg(feature = "compression")]
+        Some(compression) => Ok(compression.encode(&encoded)?),
+        _ => Ok(encoded),
+    }
+}
+
+/// Decodes a message received from a client. Uncompressed messages are
+/// already limited to `max_size` by the transport, but compressed messages
+/// must also be limited once decompressed.
+#[cfg_attr(not(feature = "compression"), allow(unused_variables))]
+fn decode_payload(
+    message: &[u8],
+    compressed: bool,
+    max_size: Option<usize>,
+) -> Result<Payload, Error> {
+    #[cfg(feature = "compression")]
+    if compressed {
+        let decoded = match max_size {
+            Some(max_size) => TransportCompression::decode_with_limit(message, max_size)?,
+            None => TransportCompression::decode(message)?,
+        };
+        return Ok(bincode::deserialize(&decoded)?);
+    }
+    #[cfg(not(feature = "compression"))]
+    debug_assert!(!compressed);
+
+    Ok(bincode::deserialize(message)?)
+}
+
 #[cfg(feature = "hyper")]
 fn compute_websocket_accept_header(key: &[u8]) -> hyper::header::HeaderValue {
     use base64::engine::general_purpose::STANDARD as BASE64;
@@ -236,9 +394,13 @@ fn compute_websocket_accept_header(key: &[u8]) -> hyper::header::HeaderValue {
     hyper::header::HeaderValue::from_str(&encoded).expect("base64 is a valid value")
 }
 
-struct VersionChecker;
+struct VersionChecker<'a> {
+    supported_compression: Option<TransportCompression>,
+    negotiated_compression: &'a mut Option<TransportCompression>,
+    connection_limit_reached: bool,
+}
 
-impl tokio_tungstenite::tungstenite::handshake::server::Callback for VersionChecker {
+impl tokio_tungstenite::tungstenite::handshake::server::Callback for VersionChecker<'_> {
     fn on_request(
         self,
         request: &tokio_tungstenite::tungstenite::handshake::server::Request,
@@ -247,22 +409,37 @@ impl tokio_tungstenite::tungstenite::handshake::server::Callback for VersionChec
         tokio_tungstenite::tungstenite::handshake::server::Response,
         tokio_tungstenite::tungstenite::handshake::server::ErrorResponse,
     > {
-        if let Some(protocols) = request.headers().get("Sec-WebSocket-Protocol") {
-            if let Ok(protocols) = protocols.to_str() {
-                for protocol in protocols.split(',').map(str::trim) {
-                    if protocol == CURRENT_PROTOCOL_VERSION {
-                        response.headers_mut().insert(
-                            "Sec-WebSocket-Protocol",
-                            CURRENT_PROTOCOL_VERSION.try_into().unwrap(),
-                        );
-                        return Ok(response);
-                    }
-                }
+        if self.connection_limit_reached {
+            let mut err =
+                tokio_tungstenite::tungstenite::handshake::server::ErrorResponse::new(None);
+            *err.status_mut() = 503_u16.try_into().unwrap();
+            return Err(err);
+        }
+
+        if let Some(protocols) = request
+            .headers()
+            .get("Sec-WebSocket-Protocol")
+            .and_then(|protocols| protocols.to_str().ok())
+        {
+            if let Some((protocol, compression)) =
+                negotiate_protocol(protocols, self.supported_compression)
+            {
+                response
+                    .headers_mut()
+                    .insert("Sec-WebSocket-Protocol", protocol.try_into().unwrap());
+                *self.negotiated_compression = compression;
+                return Ok(response);
             }
         }
 
+        // Let the client know which versions are supported so that it can
+        // report the incompatibility.
         let mut err = tokio_tungstenite::tungstenite::handshake::server::ErrorResponse::new(None);
         *err.status_mut() = 406_u16.try_into().unwrap();
+        err.headers_mut().insert(
+            PROTOCOL_VERSIONS_HEADER,
+            SUPPORTED_PROTOCOL_VERSIONS.join(", ").try_into().unwrap(),
+        );
         Err(err)
     }
 }
@@ -35,6 +35,171 @@ async fn install_self_signed_certificate_tests() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn reload_certificate_tests() -> anyhow::Result<()> {
+    use std::time::SystemTime;
+
+    use fabruic::KeyPair;
+
+    let test_dir = TestDirectory::new("cert-reload-test");
+    let server = initialize_basic_server(test_dir.as_ref()).await?;
+    let old_certificate = server.certificate_chain().await?;
+    assert!(server.certificate_expiry().await? > SystemTime::now());
+
+    // A private key that doesn't belong to the certificate is rejected, and the
+    // existing certificate remains installed.
+    let keypair = KeyPair::new_self_signed("reloaded");
+    let mismatched = KeyPair::new_self_signed("mismatched");
+    assert!(server
+        .reload_certificate(keypair.certificate_chain(), mismatched.private_key())
+        .await
+        .is_err());
+    assert_eq!(server.certificate_chain().await?, old_certificate);
+
+    server
+        .reload_certificate(keypair.certificate_chain(), keypair.private_key())
+        .await?;
+    assert_eq!(
+        &server.certificate_chain().await?,
+        keypair.certificate_chain()
+    );
+    assert!(server.certificate_expiry().await? > SystemTime::now());
+    Ok(())
+}
+
+#[tokio::test]
+async fn configured_certificate_chain_tests() -> anyhow::Result<()> {
+    use bonsaidb_local::config::Builder;
+    use fabruic::KeyPair;
+
+    use crate::ServerConfiguration;
+
+    let test_dir = TestDirectory::new("configured-certificate-chain");
+    let keypair = KeyPair::new_self_signed("configured");
+    let server = Server::open(ServerConfiguration::new(&test_dir).with_certificate_chain(
+        keypair.certificate_chain().clone(),
+        keypair.private_key().clone(),
+    ))
+    .await?;
+    assert_eq!(
+        &server.certificate_chain().await?,
+        keypair.certificate_chain()
+    );
+    drop(server);
+
+    // An invalid certificate prevents the server from opening.
+    let test_dir = TestDirectory::new("configured-certificate-chain-invalid");
+    let mismatched = KeyPair::new_self_signed("mismatched");
+    assert!(
+        Server::open(ServerConfiguration::new(&test_dir).with_certificate_chain(
+            keypair.certificate_chain().clone(),
+            mismatched.private_key().clone(),
+        ),)
+        .await
+        .is_err()
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn lifecycle_hook_tests() -> anyhow::Result<()> {
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    use bonsaidb_local::config::Builder;
+    use futures::FutureExt;
+
+    use crate::ServerConfiguration;
+
+    let test_dir = TestDirectory::new("lifecycle-hooks");
+    let started = Arc::new(AtomicU32::new(0));
+    let stopped = Arc::new(AtomicU32::new(0));
+    let hook_started = started.clone();
+    let hook_stopped = stopped.clone();
+    let server = Server::open(
+        ServerConfiguration::new(&test_dir)
+            .with_startup_hook(move |server| {
+                let started = hook_started.clone();
+                async move {
+                    // The server is usable from within the hook.
+                    server.create_database::<BasicSchema>("tests", true).await?;
+                    started.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+                .boxed()
+            })
+            .with_shutdown_hook(move |_server| {
+                let stopped = hook_stopped.clone();
+                async move {
+                    stopped.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+                .boxed()
+            })
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    assert_eq!(started.load(Ordering::SeqCst), 1);
+    assert_eq!(stopped.load(Ordering::SeqCst), 0);
+
+    // Shutdown hooks are only invoked once.
+    server.shutdown(None).await?;
+    server.shutdown(None).await?;
+    assert_eq!(stopped.load(Ordering::SeqCst), 1);
+    drop(server);
+
+    // A failing startup hook prevents the server from opening.
+    let test_dir = TestDirectory::new("lifecycle-hooks-failing");
+    assert!(Server::open(
+        ServerConfiguration::new(&test_dir).with_startup_hook(|_server| {
+            async move {
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "startup failed").into())
+        }
+        .boxed()
+        })
+    )
+    .await
+    .is_err());
+    Ok(())
+}
+
+#[test]
+fn token_bucket_rate_limiter_tests() {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+
+    use bonsaidb_core::connection::IdentityId;
+
+    use crate::{RateLimit, RateLimitKey, RateLimiter, TokenBucketRateLimiter};
+
+    let limiter = TokenBucketRateLimiter::new(
+        RateLimit {
+            per_user_requests_per_second: 1,
+            burst: 4,
+        },
+        RateLimit {
+            per_user_requests_per_second: 1,
+            burst: 1,
+        },
+    );
+    let user = RateLimitKey::Identity(IdentityId::User(1));
+    for _ in 0..4 {
+        limiter.check(user).unwrap();
+    }
+    let retry_after = limiter.check(user).unwrap_err();
+    assert!(retry_after > Duration::ZERO && retry_after <= Duration::from_secs(1));
+
+    // Other identities have their own buckets.
+    limiter
+        .check(RateLimitKey::Identity(IdentityId::User(2)))
+        .unwrap();
+
+    // Unauthenticated peers use the stricter limit.
+    let peer = RateLimitKey::Peer(IpAddr::V4(Ipv4Addr::LOCALHOST));
+    limiter.check(peer).unwrap();
+    assert!(limiter.check(peer).is_err());
+}
+
 struct TestHarness {
     _directory: TestDirectory,
     server: Server,
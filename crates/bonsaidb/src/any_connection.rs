@@ -2,13 +2,17 @@ use bonsaidb_client::{AsyncClient, AsyncRemoteDatabase};
 use bonsaidb_core::async_trait::async_trait;
 use bonsaidb_core::connection::{
     self, AccessPolicy, AsyncConnection, AsyncLowLevelConnection, AsyncStorageConnection,
-    HasSchema, HasSession, IdentityReference, Range, SerializedQueryKey, Session, Sort,
+    DatabaseCreation, HasSchema, HasSession, IdentityReference, QueryPlan, Range,
+    SerializedQueryKey, Session, Sort,
 };
-use bonsaidb_core::document::{DocumentId, Header, OwnedDocument};
+use bonsaidb_core::document::{
+    DocumentId, Header, HistoricalRevision, MaybeDeleted, OwnedDocument,
+};
+use bonsaidb_core::permissions::Statement;
 use bonsaidb_core::schema::view::map::MappedSerializedValue;
 use bonsaidb_core::schema::{
-    self, Collection, CollectionName, Nameable, Schema, SchemaName, SchemaSummary, Schematic,
-    ViewName,
+    self, Collection, CollectionName, Filter, Nameable, Schema, SchemaName, SchemaSummary,
+    Schematic, ViewName,
 };
 use bonsaidb_core::transaction::{Executed, OperationResult, Transaction};
 use bonsaidb_server::{Backend, CustomServer, NoBackend, ServerDatabase};
@@ -57,21 +61,32 @@ impl<B: Backend> AsyncStorageConnection for AnyServerConnection<B> {
         }
     }
 
-    async fn create_database_with_schema(
+    async fn create_database_with_schema_and_permissions(
         &self,
         name: &str,
         schema: SchemaName,
         only_if_needed: bool,
-    ) -> Result<(), bonsaidb_core::Error> {
+        permissions: Vec<Statement>,
+    ) -> Result<DatabaseCreation, bonsaidb_core::Error> {
         match self {
             Self::Local(server) => {
                 server
-                    .create_database_with_schema(name, schema, only_if_needed)
+                    .create_database_with_schema_and_permissions(
+                        name,
+                        schema,
+                        only_if_needed,
+                        permissions,
+                    )
                     .await
             }
             Self::Networked(client) => {
                 client
-                    .create_database_with_schema(name, schema, only_if_needed)
+                    .create_database_with_schema_and_permissions(
+                        name,
+                        schema,
+                        only_if_needed,
+                        permissions,
+                    )
                     .await
             }
         }
@@ -98,6 +113,16 @@ impl<B: Backend> AsyncStorageConnection for AnyServerConnection<B> {
         }
     }
 
+    async fn database_stats(
+        &self,
+        name: &str,
+    ) -> Result<connection::DatabaseStats, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.database_stats(name).await,
+            Self::Networked(client) => client.database_stats(name).await,
+        }
+    }
+
     async fn create_user(&self, username: &str) -> Result<u64, bonsaidb_core::Error> {
         match self {
             Self::Local(server) => server.create_user(username).await,
@@ -115,6 +140,13 @@ impl<B: Backend> AsyncStorageConnection for AnyServerConnection<B> {
         }
     }
 
+    async fn list_users(&self) -> Result<Vec<connection::UserInfo>, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.list_users().await,
+            Self::Networked(client) => client.list_users().await,
+        }
+    }
+
     #[cfg(feature = "password-hashing")]
     async fn set_user_password<'user, U: Nameable<'user, u64> + Send + Sync>(
         &self,
@@ -127,7 +159,6 @@ impl<B: Backend> AsyncStorageConnection for AnyServerConnection<B> {
         }
     }
 
-    #[cfg(any(feature = "token-authentication", feature = "password-hashing"))]
     async fn authenticate(
         &self,
         authentication: bonsaidb_core::connection::Authentication,
@@ -199,6 +230,16 @@ impl<B: Backend> AsyncStorageConnection for AnyServerConnection<B> {
         }
     }
 
+    async fn list_user_groups<'user, U: Nameable<'user, u64> + Send + Sync>(
+        &self,
+        user: U,
+    ) -> Result<Vec<connection::PermissionGroupInfo>, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.list_user_groups(user).await,
+            Self::Networked(client) => client.list_user_groups(user).await,
+        }
+    }
+
     async fn add_role_to_user<
         'user,
         'role,
@@ -354,6 +395,26 @@ impl<B: Backend> AsyncLowLevelConnection for AnyDatabase<B> {
         }
     }
 
+    async fn filter_from_collection(
+        &self,
+        filter: &Filter,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<OwnedDocument>, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => {
+                server
+                    .filter_from_collection(filter, limit, collection)
+                    .await
+            }
+            Self::Networked(client) => {
+                client
+                    .filter_from_collection(filter, limit, collection)
+                    .await
+            }
+        }
+    }
+
     async fn list_headers_from_collection(
         &self,
         ids: Range<DocumentId>,
@@ -375,6 +436,57 @@ impl<B: Backend> AsyncLowLevelConnection for AnyDatabase<B> {
         }
     }
 
+    async fn list_including_deleted_from_collection(
+        &self,
+        ids: Range<DocumentId>,
+        order: Sort,
+        limit: Option<u32>,
+        collection: &CollectionName,
+    ) -> Result<Vec<MaybeDeleted>, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => {
+                server
+                    .list_including_deleted_from_collection(ids, order, limit, collection)
+                    .await
+            }
+            Self::Networked(client) => {
+                client
+                    .list_including_deleted_from_collection(ids, order, limit, collection)
+                    .await
+            }
+        }
+    }
+
+    async fn restore_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Header, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.restore_from_collection(id, collection).await,
+            Self::Networked(client) => client.restore_from_collection(id, collection).await,
+        }
+    }
+
+    async fn revision_history_from_collection(
+        &self,
+        id: DocumentId,
+        collection: &CollectionName,
+    ) -> Result<Vec<HistoricalRevision>, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => {
+                server
+                    .revision_history_from_collection(id, collection)
+                    .await
+            }
+            Self::Networked(client) => {
+                client
+                    .revision_history_from_collection(id, collection)
+                    .await
+            }
+        }
+    }
+
     async fn count_from_collection(
         &self,
         ids: Range<DocumentId>,
@@ -494,6 +606,19 @@ impl<B: Backend> AsyncLowLevelConnection for AnyDatabase<B> {
             Self::Networked(client) => client.delete_docs_by_name(view, key, access_policy).await,
         }
     }
+
+    async fn explain_by_name(
+        &self,
+        view: &ViewName,
+        key: Option<SerializedQueryKey>,
+        order: Sort,
+        limit: Option<u32>,
+    ) -> Result<QueryPlan, bonsaidb_core::Error> {
+        match self {
+            Self::Local(server) => server.explain_by_name(view, key, order, limit).await,
+            Self::Networked(client) => client.explain_by_name(view, key, order, limit).await,
+        }
+    }
 }
 
 impl<B: Backend> HasSchema for AnyDatabase<B> {
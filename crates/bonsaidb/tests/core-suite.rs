@@ -6,14 +6,17 @@ use bonsaidb::core::actionable::Permissions;
 use bonsaidb::core::admin::{Admin, PermissionGroup, ADMIN_DATABASE_NAME};
 use bonsaidb::core::circulate::flume;
 use bonsaidb::core::keyvalue::AsyncKeyValue;
+use bonsaidb::core::networking::{CURRENT_PROTOCOL_VERSION, SUPPORTED_PROTOCOL_VERSIONS};
 use bonsaidb::core::permissions::bonsai::{BonsaiAction, ServerAction};
 use bonsaidb::core::permissions::Statement;
 use bonsaidb::core::schema::{InsertError, SerializedCollection};
-use bonsaidb::core::test_util::{BasicSchema, HarnessTest, TestDirectory};
+use bonsaidb::core::test_util::{seeded_rng, BasicSchema, HarnessTest, TestDirectory};
 use bonsaidb::local::config::Builder;
 use bonsaidb::server::fabruic::Certificate;
 use bonsaidb::server::test_util::{initialize_basic_server, BASIC_SERVER_NAME};
-use bonsaidb::server::{DefaultPermissions, Server, ServerConfiguration};
+use bonsaidb::server::{
+    AuthenticatedIdentity, CustomAuthenticator, DefaultPermissions, Server, ServerConfiguration,
+};
 use bonsaidb_core::connection::{Authentication, AuthenticationMethod, SensitiveString};
 use once_cell::sync::Lazy;
 use rand::distributions::Alphanumeric;
@@ -64,12 +67,45 @@ fn run_shared_server(certificate_sender: flume::Sender<Certificate>) -> anyhow::
             });
         }
 
+        #[cfg(feature = "http")]
+        {
+            let task_server = server.clone();
+            tokio::spawn(async move {
+                serve_http(task_server, "127.0.0.1:6021").await.unwrap();
+            });
+        }
+
         server.listen_on(6000).await.unwrap();
     });
 
     Ok(())
 }
 
+/// Serves HTTP long-polling connections to `server` on `address`.
+#[cfg(feature = "http")]
+async fn serve_http(server: Server, address: &str) -> anyhow::Result<()> {
+    use std::convert::Infallible;
+
+    use hyper::server::conn::AddrStream;
+    use hyper::service::{make_service_fn, service_fn};
+
+    hyper::Server::try_bind(&address.parse()?)?
+        .serve(make_service_fn(move |connection: &AddrStream| {
+            let server = server.clone();
+            let peer_address = connection.remote_addr();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    let server = server.clone();
+                    async move {
+                        Ok::<_, Infallible>(server.handle_http_request(peer_address, request).await)
+                    }
+                }))
+            }
+        }))
+        .await?;
+    Ok(())
+}
+
 #[cfg(feature = "websockets")]
 mod websockets {
     use bonsaidb_client::{BlockingClient, BlockingRemoteDatabase};
@@ -187,10 +223,122 @@ mod websockets {
         let url = Url::parse("ws://localhost:6001")?;
         let client = AsyncClient::build(url.clone())
             .with_certificate(certificate.clone())
-            .with_protocol_version(INCOMPATIBLE_PROTOCOL_VERSION)
+            .with_protocol_versions(&[INCOMPATIBLE_PROTOCOL_VERSION])
+            .build()?;
+
+        check_incompatible_client(client, SUPPORTED_PROTOCOL_VERSIONS).await
+    }
+
+    #[tokio::test]
+    async fn negotiates_supported_protocol_version() -> anyhow::Result<()> {
+        initialize_shared_server().await;
+
+        // The server chooses the version it supports even though the client
+        // prefers a version the server doesn't know about.
+        let client = AsyncClient::build(Url::parse("ws://localhost:6001")?)
+            .with_protocol_versions(&[INCOMPATIBLE_PROTOCOL_VERSION, CURRENT_PROTOCOL_VERSION])
+            .build()?;
+        client.ping().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "compression")]
+    async fn compressed_large_document_round_trip() -> anyhow::Result<()> {
+        use bonsaidb::core::networking::Compression;
+        use bonsaidb::core::test_util::Basic;
+        use bonsaidb_core::connection::AsyncStorageConnection;
+
+        initialize_shared_server().await;
+        let client = AsyncClient::build(Url::parse("ws://localhost:6001")?)
+            .with_compression(Compression::Zstd { level: 3 })
+            .build()?;
+        client
+            .create_database::<BasicSchema>("websockets-compression", true)
+            .await?;
+        let db = client
+            .database::<BasicSchema>("websockets-compression")
+            .await?;
+
+        // Repetitive contents compress well, and are large enough that both
+        // the request and the response exceed the compression threshold.
+        let value = "compressible ".repeat(100_000);
+        let doc = Basic::new(value.clone()).push_into_async(&db).await?;
+        let retrieved = Basic::get_async(&doc.header.id, &db)
+            .await?
+            .expect("document not found");
+        assert_eq!(retrieved.contents.value, value);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn default_database() -> anyhow::Result<()> {
+        use bonsaidb::core::test_util::Basic;
+        use bonsaidb_core::connection::AsyncStorageConnection;
+
+        initialize_shared_server().await;
+        let client = AsyncClient::build(Url::parse("ws://localhost:6001")?)
+            .with_default_database("websockets-default-database")
             .build()?;
+        assert_eq!(
+            client.default_database_name(),
+            Some("websockets-default-database")
+        );
+        client
+            .create_database::<BasicSchema>("websockets-default-database", true)
+            .await?;
+        let db = client.default_database::<BasicSchema>()?;
+        let doc = Basic::new("default").push_into_async(&db).await?;
+        let named = client
+            .database::<BasicSchema>("websockets-default-database")
+            .await?;
+        assert!(Basic::get_async(&doc.header.id, &named).await?.is_some());
+
+        let client = AsyncClient::build(Url::parse("ws://localhost:6001")?).build()?;
+        let err = client.default_database::<BasicSchema>().unwrap_err();
+        assert!(err.to_string().contains("no default database"));
+
+        Ok(())
+    }
 
-        check_incompatible_client(client).await
+    #[test]
+    fn blocking_client_pool() -> anyhow::Result<()> {
+        use bonsaidb_client::{ClientPool, Error};
+
+        Runtime::new()?.block_on(initialize_shared_server());
+        let url = Url::parse("ws://localhost:6001")?;
+        let pool = ClientPool::build(move || BlockingClient::build(url.clone()).build())
+            .with_min_size(1)
+            .with_max_size(2)
+            .with_checkout_timeout(Duration::from_millis(100))
+            .build()?;
+        assert_eq!(pool.open_connections(), 1);
+        assert_eq!(pool.idle_connections(), 1);
+
+        let first = pool.get()?;
+        first.ping()?;
+        let second = pool.get()?;
+        assert_eq!(pool.open_connections(), 2);
+        assert!(matches!(pool.get(), Err(Error::PoolTimeout)));
+
+        // Returned clients are reused rather than reconnecting.
+        drop(first);
+        let third = pool.get()?;
+        assert_eq!(pool.open_connections(), 2);
+
+        // Discarded clients are closed, making room for a new client.
+        third.discard();
+        assert_eq!(pool.open_connections(), 1);
+        let fourth = pool.get()?;
+        fourth.ping()?;
+        assert_eq!(pool.open_connections(), 2);
+
+        drop((second, fourth));
+        assert_eq!(pool.idle_connections(), 2);
+
+        Ok(())
     }
 
     bonsaidb_core::define_blocking_connection_test_suite!(BlockingWebsocketTestHarness);
@@ -199,6 +347,77 @@ mod websockets {
     bonsaidb_core::define_blocking_kv_test_suite!(BlockingWebsocketTestHarness);
 }
 
+#[cfg(feature = "http")]
+mod http {
+    use super::*;
+
+    struct HttpTestHarness {
+        client: AsyncClient,
+        url: Url,
+        db: AsyncRemoteDatabase,
+    }
+
+    impl HttpTestHarness {
+        pub async fn new(test: HarnessTest) -> anyhow::Result<Self> {
+            use bonsaidb_core::connection::AsyncStorageConnection;
+
+            initialize_shared_server().await;
+            let url = Url::parse("http://127.0.0.1:6021")?;
+            let client = AsyncClient::new(url.clone())?;
+
+            let dbname = format!("http-{test}");
+            client
+                .create_database::<BasicSchema>(&dbname, false)
+                .await?;
+            let db = client.database::<BasicSchema>(&dbname).await?;
+
+            Ok(Self { client, url, db })
+        }
+
+        pub const fn server_name() -> &'static str {
+            "http"
+        }
+
+        pub fn server(&self) -> &AsyncClient {
+            &self.client
+        }
+
+        pub async fn connect(&self) -> anyhow::Result<AsyncRemoteDatabase> {
+            Ok(self.db.clone())
+        }
+
+        #[allow(dead_code)] // We will want this in the future but it's currently unused
+        pub async fn connect_with_permissions(
+            &self,
+            permissions: Vec<Statement>,
+            label: &str,
+        ) -> anyhow::Result<AsyncRemoteDatabase> {
+            let client = AsyncClient::new(self.url.clone())?;
+            assume_permissions(client, label, self.db.name(), permissions).await
+        }
+
+        pub async fn shutdown(&self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    bonsaidb_core::define_async_connection_test_suite!(HttpTestHarness);
+
+    bonsaidb_core::define_async_pubsub_test_suite!(HttpTestHarness);
+    bonsaidb_core::define_async_kv_test_suite!(HttpTestHarness);
+
+    #[tokio::test]
+    async fn incompatible_client_version() -> anyhow::Result<()> {
+        initialize_shared_server().await;
+
+        let client = AsyncClient::build(Url::parse("http://127.0.0.1:6021")?)
+            .with_protocol_versions(&[INCOMPATIBLE_PROTOCOL_VERSION])
+            .build()?;
+
+        check_incompatible_client(client, SUPPORTED_PROTOCOL_VERSIONS).await
+    }
+}
+
 mod bonsai {
     use super::*;
     struct BonsaiTestHarness {
@@ -272,10 +491,125 @@ mod bonsai {
         ))?;
         let client = AsyncClient::build(url.clone())
             .with_certificate(certificate.clone())
-            .with_protocol_version(INCOMPATIBLE_PROTOCOL_VERSION)
+            .with_protocol_versions(&[INCOMPATIBLE_PROTOCOL_VERSION])
+            .build()?;
+
+        check_incompatible_client(client, &[]).await
+    }
+
+    #[tokio::test]
+    async fn certificate_fingerprint() -> anyhow::Result<()> {
+        use bonsaidb_core::networking;
+        use sha2::{Digest, Sha256};
+
+        let certificate = initialize_shared_server().await;
+        let url = Url::parse(&format!(
+            "bonsaidb://localhost:6000?server={BASIC_SERVER_NAME}"
+        ))?;
+
+        let fingerprint = <[u8; 32]>::from(Sha256::digest(certificate.as_ref()));
+        let client = AsyncClient::build(url.clone())
+            .with_certificate(certificate.clone())
+            .with_certificate_fingerprint(fingerprint)
+            .build()?;
+        client.ping().await?;
+
+        let client = AsyncClient::build(url)
+            .with_certificate(certificate)
+            .with_certificate_fingerprint([0; 32])
+            .build()?;
+        match client.ping().await {
+            Err(bonsaidb_core::Error::Networking(
+                networking::Error::CertificateFingerprintMismatch { expected, received },
+            )) => {
+                assert_eq!(expected, [0; 32]);
+                assert_eq!(received, Some(fingerprint));
+            }
+            other => unreachable!("expected fingerprint mismatch, got {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn batch_requests() -> anyhow::Result<()> {
+        use bonsaidb_core::connection::AsyncStorageConnection;
+        use bonsaidb_core::document::DocumentId;
+        use bonsaidb_core::keyvalue::{Command, KeyOperation, Numeric, Output, Value};
+        use bonsaidb_core::networking::{ExecuteKeyOperation, Get};
+        use bonsaidb_core::schema::Collection;
+        use bonsaidb_core::test_util::Basic;
+
+        let certificate = initialize_shared_server().await;
+        let url = Url::parse(&format!(
+            "bonsaidb://localhost:6000?server={BASIC_SERVER_NAME}"
+        ))?;
+        let client = AsyncClient::build(url)
+            .with_certificate(certificate)
+            .build()?;
+        client
+            .create_database::<BasicSchema>("bonsai-batch", false)
+            .await?;
+        let db = client.database::<BasicSchema>("bonsai-batch").await?;
+        let doc = Basic::new("batched").push_into_async(&db).await?;
+        db.set_numeric_key("counter", 42_u64).await?;
+
+        let get = |database: &str| Get {
+            database: database.to_string(),
+            collection: Basic::collection_name(),
+            id: DocumentId::from_u64(doc.header.id),
+        };
+        let mut batch = client.batch();
+        let existing = batch.push(&get("bonsai-batch"))?;
+        let missing_database = batch.push(&get("bonsai-batch-missing"))?;
+        let counter = batch.push(&ExecuteKeyOperation {
+            database: String::from("bonsai-batch"),
+            op: KeyOperation {
+                namespace: None,
+                key: String::from("counter"),
+                command: Command::Get { delete: false },
+            },
+        })?;
+        assert_eq!(batch.len(), 3);
+        let results = batch.send().await?;
+        assert_eq!(results.len(), 3);
+
+        // Each request succeeds or fails independently.
+        let existing = results.get(existing)?.expect("document not found");
+        assert_eq!(existing.header.id, DocumentId::from_u64(doc.header.id));
+        assert!(matches!(
+            results
+                .get(missing_database)
+                .map_err(bonsaidb_core::Error::from),
+            Err(bonsaidb_core::Error::DatabaseNotFound(_))
+        ));
+        assert!(matches!(
+            results.get(counter)?,
+            Output::Value(Some(Value::Numeric(Numeric::UnsignedInteger(42))))
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn remote_database_identity() -> anyhow::Result<()> {
+        use bonsaidb_core::connection::AsyncStorageConnection;
+        use bonsaidb_core::schema::Schema;
+
+        let certificate = initialize_shared_server().await;
+        let url = Url::parse(&format!(
+            "bonsaidb://localhost:6000?server={BASIC_SERVER_NAME}"
+        ))?;
+        let client = AsyncClient::build(url)
+            .with_certificate(certificate)
             .build()?;
+        let db = client
+            .create_database::<BasicSchema>("bonsai-identity", true)
+            .await?;
+        assert_eq!(db.name(), "bonsai-identity");
+        assert_eq!(db.schema_name(), &BasicSchema::schema_name());
 
-        check_incompatible_client(client).await
+        Ok(())
     }
 
     bonsaidb_core::define_async_connection_test_suite!(BonsaiTestHarness);
@@ -283,7 +617,10 @@ mod bonsai {
     bonsaidb_core::define_async_kv_test_suite!(BonsaiTestHarness);
 }
 
-async fn check_incompatible_client(client: AsyncClient) -> anyhow::Result<()> {
+async fn check_incompatible_client(
+    client: AsyncClient,
+    expected_server_versions: &[&str],
+) -> anyhow::Result<()> {
     use bonsaidb_core::connection::AsyncStorageConnection;
     match client
         .database::<()>("a database")
@@ -291,11 +628,11 @@ async fn check_incompatible_client(client: AsyncClient) -> anyhow::Result<()> {
         .set_numeric_key("a", 1_u64)
         .await
     {
-        Err(bonsaidb_core::Error::Other { error, .. }) => {
-            assert!(
-                error.contains("protocol version"),
-                "unexpected error: {error:?}",
-            );
+        Err(bonsaidb_core::Error::Networking(
+            bonsaidb_core::networking::Error::IncompatibleProtocolVersion { client, server },
+        )) => {
+            assert_eq!(client, [INCOMPATIBLE_PROTOCOL_VERSION]);
+            assert_eq!(server, expected_server_versions);
         }
         other => unreachable!(
             "Unexpected result with invalid protocol version: {:?}",
@@ -316,7 +653,7 @@ async fn assume_permissions(
     use bonsaidb_core::connection::AsyncStorageConnection;
     let username = format!("{database_name}-{label}");
     let password = SensitiveString(
-        rand::thread_rng()
+        seeded_rng(&username)
             .sample_iter(&Alphanumeric)
             .take(8)
             .map(char::from)
@@ -334,6 +671,7 @@ async fn assume_permissions(
             let administrator_group_id = match (PermissionGroup {
                 name: String::from(label),
                 statements,
+                parent_group: None,
             }
             .push_into_async(&admin)
             .await)
@@ -427,11 +765,214 @@ async fn authenticated_permissions_test() -> anyhow::Result<()> {
 }
 
 #[tokio::test]
-async fn client_disconnection() -> anyhow::Result<()> {
+async fn user_management_test() -> anyhow::Result<()> {
     use bonsaidb_core::connection::AsyncStorageConnection;
-    let database_path = TestDirectory::new("client_disconnection");
+    let database_path = TestDirectory::new("user-management");
     let server = Server::open(
-        ServerConfiguration::new(&database_path).default_permissions(Permissions::allow_all()),
+        ServerConfiguration::new(&database_path)
+            .default_permissions(Permissions::from(
+                Statement::for_any()
+                    .allowing(&BonsaiAction::Server(ServerAction::Connect))
+                    .allowing(&BonsaiAction::Server(ServerAction::Authenticate(
+                        AuthenticationMethod::PasswordHash,
+                    ))),
+            ))
+            .authenticated_permissions(DefaultPermissions::AllowAll),
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+
+    let ecton_id = server.create_user("ecton").await?;
+    server
+        .set_user_password("ecton", SensitiveString::from("hunter2"))
+        .await?;
+    let admin = server.database::<Admin>(ADMIN_DATABASE_NAME).await?;
+    let group = PermissionGroup::named("editors")
+        .push_into_async(&admin)
+        .await?;
+    tokio::spawn(async move {
+        server.listen_on(6017).await?;
+        Result::<(), anyhow::Error>::Ok(())
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let url = Url::parse("bonsaidb://localhost:6017")?;
+    let client = AsyncClient::build(url)
+        .with_certificate(certificate)
+        .build()?;
+    match client.list_users().await {
+        Err(bonsaidb_core::Error::PermissionDenied(_)) => {}
+        other => unreachable!("should not have permission to list users: {other:?}"),
+    }
+    match client.list_user_groups("ecton").await {
+        Err(bonsaidb_core::Error::PermissionDenied(_)) => {}
+        other => unreachable!("should not have permission to list groups: {other:?}"),
+    }
+
+    let authenticated_client = client
+        .authenticate(Authentication::password(
+            "ecton",
+            SensitiveString(String::from("hunter2")),
+        )?)
+        .await?;
+    let other_id = authenticated_client.create_user("otheruser").await?;
+    authenticated_client
+        .add_permission_group_to_user("otheruser", "editors")
+        .await?;
+
+    let users = authenticated_client.list_users().await?;
+    assert_eq!(users.len(), 2);
+    let ecton = users.iter().find(|user| user.id == ecton_id).unwrap();
+    assert_eq!(ecton.username, "ecton");
+    assert!(ecton.groups.is_empty());
+    let other = users.iter().find(|user| user.id == other_id).unwrap();
+    assert_eq!(other.username, "otheruser");
+    assert_eq!(other.groups, [group.header.id]);
+
+    let groups = authenticated_client.list_user_groups(other_id).await?;
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].id, group.header.id);
+    assert_eq!(groups[0].name, "editors");
+
+    authenticated_client
+        .remove_permission_group_from_user("otheruser", "editors")
+        .await?;
+    assert!(authenticated_client
+        .list_user_groups("otheruser")
+        .await?
+        .is_empty());
+
+    authenticated_client.delete_user("otheruser").await?;
+    let users = authenticated_client.list_users().await?;
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].id, ecton_id);
+    assert!(matches!(
+        authenticated_client.list_user_groups("otheruser").await,
+        Err(bonsaidb_core::Error::UserNotFound)
+    ));
+
+    Ok(())
+}
+
+#[derive(Debug)]
+struct SharedSecretAuthenticator;
+
+#[bonsaidb::core::async_trait::async_trait]
+impl CustomAuthenticator for SharedSecretAuthenticator {
+    async fn authenticate(
+        &self,
+        payload: &[u8],
+    ) -> Result<AuthenticatedIdentity, bonsaidb_core::Error> {
+        match payload {
+            b"ecton-secret" => AuthenticatedIdentity::user("ecton"),
+            _ => Err(bonsaidb_core::Error::InvalidCredentials),
+        }
+    }
+}
+
+#[tokio::test]
+async fn custom_authenticator_test() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::AsyncStorageConnection;
+    let database_path = TestDirectory::new("custom-authenticator");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path)
+            .default_permissions(Permissions::from(
+                Statement::for_any()
+                    .allowing(&BonsaiAction::Server(ServerAction::Connect))
+                    .allowing(&BonsaiAction::Server(ServerAction::Authenticate(
+                        AuthenticationMethod::Custom,
+                    ))),
+            ))
+            .with_authenticator("shared-secret", SharedSecretAuthenticator),
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+
+    // Grant the user's permissions through a permission group, which the
+    // authenticated session should inherit.
+    let user_id = server.create_user("ecton").await?;
+    let admin = server.database::<Admin>(ADMIN_DATABASE_NAME).await?;
+    let group = PermissionGroup {
+        name: String::from("custom-authenticated"),
+        statements: vec![Statement::allow_all_for_any_resource()],
+        parent_group: None,
+    }
+    .push_into_async(&admin)
+    .await?;
+    server
+        .add_permission_group_to_user(user_id, group.header.id)
+        .await?;
+    tokio::spawn(async move {
+        server.listen_on(6004).await?;
+        Result::<(), anyhow::Error>::Ok(())
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let url = Url::parse("bonsaidb://localhost:6004")?;
+    let client = AsyncClient::build(url)
+        .with_certificate(certificate)
+        .build()?;
+
+    assert!(matches!(
+        client
+            .authenticate(Authentication::custom("shared-secret", b"wrong".to_vec()))
+            .await,
+        Err(bonsaidb_core::Error::InvalidCredentials)
+    ));
+    assert!(matches!(
+        client
+            .authenticate(Authentication::custom("unknown", b"ecton-secret".to_vec()))
+            .await,
+        Err(bonsaidb_core::Error::InvalidCredentials)
+    ));
+
+    let authenticated_client = client
+        .authenticate(Authentication::custom(
+            "shared-secret",
+            b"ecton-secret".to_vec(),
+        ))
+        .await
+        .unwrap();
+    authenticated_client
+        .create_user("otheruser")
+        .await
+        .expect("should be able to create user after logging in");
+
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "token-authentication")]
+async fn session_token_test() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::AsyncStorageConnection;
+    let database_path = TestDirectory::new("session-tokens");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path)
+            .default_permissions(Permissions::from(
+                Statement::for_any()
+                    .allowing(&BonsaiAction::Server(ServerAction::Connect))
+                    .allowing(&BonsaiAction::Server(ServerAction::Authenticate(
+                        AuthenticationMethod::PasswordHash,
+                    )))
+                    .allowing(&BonsaiAction::Server(ServerAction::Authenticate(
+                        AuthenticationMethod::Custom,
+                    )))
+                    .allowing(&BonsaiAction::Server(ServerAction::Authenticate(
+                        AuthenticationMethod::SessionToken,
+                    ))),
+            ))
+            .with_authenticator("shared-secret", SharedSecretAuthenticator)
+            .with_session_ttl(Duration::from_secs(60)),
     )
     .await?;
     server.install_self_signed_certificate(false).await?;
@@ -440,17 +981,318 @@ async fn client_disconnection() -> anyhow::Result<()> {
         .await?
         .into_end_entity_certificate();
 
+    // Sessions authenticated using a token should have the same permission
+    // groups as the user the token was issued to.
+    let user_id = server.create_user("ecton").await?;
+    server
+        .set_user_password(user_id, SensitiveString::from("hunter2"))
+        .await?;
+    let admin = server.database::<Admin>(ADMIN_DATABASE_NAME).await?;
+    let group = PermissionGroup {
+        name: String::from("session-token"),
+        statements: vec![Statement::allow_all_for_any_resource()],
+        parent_group: None,
+    }
+    .push_into_async(&admin)
+    .await?;
+    server
+        .add_permission_group_to_user(user_id, group.header.id)
+        .await?;
     tokio::spawn({
         let server = server.clone();
         async move {
-            server.listen_on(6003).await?;
+            server.listen_on(6011).await?;
             Result::<(), anyhow::Error>::Ok(())
         }
     });
     // Give the server time to listen
     tokio::time::sleep(Duration::from_millis(10)).await;
 
-    let url = Url::parse("bonsaidb://localhost:6003")?;
+    let url = Url::parse("bonsaidb://localhost:6011")?;
+    let client = AsyncClient::build(url.clone())
+        .with_certificate(certificate.clone())
+        .build()?;
+    assert!(client.session_token().is_none());
+    let password_client = client
+        .authenticate(Authentication::password(
+            "ecton",
+            SensitiveString::from("hunter2"),
+        )?)
+        .await?;
+    let token = password_client
+        .session_token()
+        .expect("token not issued")
+        .clone();
+
+    // Tokens are issued regardless of how the session was authenticated.
+    let custom_client = client
+        .authenticate(Authentication::custom(
+            "shared-secret",
+            b"ecton-secret".to_vec(),
+        ))
+        .await?;
+    let custom_token = custom_client
+        .session_token()
+        .expect("token not issued")
+        .clone();
+    assert_ne!(custom_token, token);
+
+    // Authenticate a new connection using the token.
+    let client = AsyncClient::build(url)
+        .with_certificate(certificate)
+        .build()?;
+    assert!(matches!(
+        client
+            .authenticate(Authentication::session_token(SensitiveString::from(
+                "not-a-token"
+            )))
+            .await,
+        Err(bonsaidb_core::Error::InvalidCredentials)
+    ));
+    let token_client = client
+        .authenticate(Authentication::session_token(token.clone()))
+        .await?;
+    assert_eq!(token_client.session_token(), Some(&token));
+    token_client
+        .create_user("otheruser")
+        .await
+        .expect("should be able to create user after logging in");
+
+    // Once revoked, the token can no longer be used.
+    assert!(server.revoke_token(&token).await?);
+    assert!(!server.revoke_token(&token).await?);
+    assert!(matches!(
+        client
+            .authenticate(Authentication::session_token(token))
+            .await,
+        Err(bonsaidb_core::Error::InvalidCredentials)
+    ));
+
+    Ok(())
+}
+
+/// Disconnects the only client connected to `server`, and waits for `client`
+/// to reconnect.
+async fn reconnect(server: &Server, client: &AsyncClient) {
+    let connected_clients = server.connected_clients();
+    assert!(server.disconnect_client(connected_clients[0].id()));
+    let start = Instant::now();
+    while connected_clients[0].connected() && start.elapsed() < Duration::from_secs(2) {
+        tokio::task::yield_now().await;
+    }
+    while client.ping().await.is_err() && start.elapsed() < Duration::from_secs(5) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+}
+
+#[tokio::test]
+async fn credential_refresh_test() -> anyhow::Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use bonsaidb_core::connection::AsyncStorageConnection;
+    let database_path = TestDirectory::new("credential-refresh");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path).default_permissions(Permissions::from(
+            Statement::for_any()
+                .allowing(&BonsaiAction::Server(ServerAction::Connect))
+                .allowing(&BonsaiAction::Server(ServerAction::Authenticate(
+                    AuthenticationMethod::PasswordHash,
+                ))),
+        )),
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+
+    let user_id = server.create_user("ecton").await?;
+    server
+        .set_user_password(user_id, SensitiveString::from("hunter2"))
+        .await?;
+    let admin = server.database::<Admin>(ADMIN_DATABASE_NAME).await?;
+    let group = PermissionGroup::named("credential-refresh")
+        .with_group_ids([Statement::allow_all_for_any_resource()])
+        .push_into_async(&admin)
+        .await?;
+    server
+        .add_permission_group_to_user(user_id, group.header.id)
+        .await?;
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6013).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let refreshes = Arc::new(AtomicUsize::new(0));
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:6013")?)
+        .with_certificate(certificate)
+        .with_credential_provider({
+            let refreshes = refreshes.clone();
+            move || {
+                refreshes.fetch_add(1, Ordering::SeqCst);
+                async { Authentication::password("ecton", SensitiveString::from("hunter2")) }
+            }
+        })
+        .build()?;
+    let authenticated = client.authenticate_with_retry().await?;
+    authenticated.list_databases().await?;
+    assert_eq!(refreshes.load(Ordering::SeqCst), 1);
+
+    // Sessions don't survive reconnecting, so forcing the client to reconnect
+    // expires the session.
+    reconnect(&server, &client).await;
+
+    // All of the failing requests are retried after authenticating only once.
+    let (first, second, third) = tokio::join!(
+        authenticated.list_databases(),
+        authenticated.list_databases(),
+        authenticated.list_databases()
+    );
+    first?;
+    second?;
+    third?;
+    assert_eq!(refreshes.load(Ordering::SeqCst), 2);
+
+    // Clients that authenticated without a credential provider report the
+    // expired session.
+    let plain = client
+        .authenticate(Authentication::password(
+            "ecton",
+            SensitiveString::from("hunter2"),
+        )?)
+        .await?;
+    reconnect(&server, &client).await;
+    assert!(matches!(
+        plain.list_databases().await,
+        Err(bonsaidb_core::Error::SessionExpired)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn connection_limit_test() -> anyhow::Result<()> {
+    use std::sync::Arc;
+
+    use bonsaidb::local::metrics::PrometheusRecorder;
+    use bonsaidb_core::connection::AsyncStorageConnection;
+    use bonsaidb_core::networking;
+
+    let database_path = TestDirectory::new("connection-limit");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path)
+            .default_permissions(Permissions::from(
+                Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::Connect)),
+            ))
+            .with_connection_limit(2)
+            .with_connection_limit_per_address(1)
+            .with_metrics_recorder(Arc::new(PrometheusRecorder::default())),
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+
+    #[cfg(feature = "websockets")]
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server
+                .listen_for_websockets_on("localhost:6015", false)
+                .await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6014).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let first = AsyncClient::build(Url::parse("bonsaidb://localhost:6014")?)
+        .with_certificate(certificate.clone())
+        .build()?;
+    first.ping().await?;
+
+    // All clients connect from the same address, so the per-address limit
+    // rejects the second connection.
+    let second = AsyncClient::build(Url::parse("bonsaidb://localhost:6014")?)
+        .with_certificate(certificate)
+        .build()?;
+    assert!(matches!(
+        second.ping().await,
+        Err(bonsaidb_core::Error::Networking(
+            networking::Error::ConnectionLimitReached
+        ))
+    ));
+
+    #[cfg(feature = "websockets")]
+    {
+        let client = AsyncClient::new(Url::parse("ws://localhost:6015")?)?;
+        assert!(matches!(
+            client.ping().await,
+            Err(bonsaidb_core::Error::Networking(
+                networking::Error::ConnectionLimitReached
+            ))
+        ));
+    }
+
+    assert!(server.metrics_snapshot().lines().any(|line| line
+        .starts_with("bonsaidb_connections_rejected_total")
+        && !line.ends_with(" 0")));
+
+    // Once the first client disconnects, the second client is able to connect.
+    drop(first);
+    let start = Instant::now();
+    while !server.connected_clients().is_empty() && start.elapsed() < Duration::from_secs(2) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    while second.ping().await.is_err() && start.elapsed() < Duration::from_secs(5) {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    second.ping().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_disconnection() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::AsyncStorageConnection;
+    let database_path = TestDirectory::new("client_disconnection");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path).default_permissions(Permissions::allow_all()),
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6003).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let url = Url::parse("bonsaidb://localhost:6003")?;
     let client = AsyncClient::build(url)
         .with_certificate(certificate)
         .build()?;
@@ -470,3 +1312,977 @@ async fn client_disconnection() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn forced_client_disconnection() -> anyhow::Result<()> {
+    use bonsaidb::server::{ClientEvent, CLIENT_EVENTS_TOPIC};
+    use bonsaidb_core::connection::AsyncStorageConnection;
+    use bonsaidb_core::pubsub::{AsyncPubSub, AsyncSubscriber};
+    let database_path = TestDirectory::new("forced_client_disconnection");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path).default_permissions(Permissions::allow_all()),
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    let subscriber = server.admin().await.create_subscriber().await?;
+    subscriber.subscribe_to(&CLIENT_EVENTS_TOPIC).await?;
+
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6007).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:6007")?)
+        .with_certificate(certificate)
+        .build()?;
+    client.ping().await?;
+
+    let connected_clients = server.connected_clients();
+    assert_eq!(connected_clients.len(), 1);
+    let connected_client = &connected_clients[0];
+    assert!(connected_client.connected_at().elapsed()? < Duration::from_secs(60));
+    assert!(connected_client.authenticated_as::<Vec<_>>().is_empty());
+    let message = subscriber.receiver().receive_async().await?;
+    assert_eq!(
+        message.payload::<ClientEvent>()?,
+        ClientEvent::Connected {
+            id: connected_client.id(),
+            address: *connected_client.address(),
+        }
+    );
+
+    assert!(server.disconnect_client(connected_client.id()));
+    let message = subscriber.receiver().receive_async().await?;
+    assert_eq!(
+        message.payload::<ClientEvent>()?,
+        ClientEvent::Disconnected {
+            id: connected_client.id()
+        }
+    );
+    assert!(!connected_client.connected());
+    assert!(server.connected_clients().is_empty());
+    assert!(!server.disconnect_client(connected_client.id()));
+
+    // The client observes the closed transport on its next request.
+    assert!(client.ping().await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn pubsub_replay() -> anyhow::Result<()> {
+    use bonsaidb::local::config::PubSubRetention;
+    use bonsaidb_core::connection::AsyncStorageConnection;
+    use bonsaidb_core::pubsub::{AsyncPubSub, AsyncSubscriber};
+    let database_path = TestDirectory::new("pubsub_replay");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path)
+            .default_permissions(Permissions::allow_all())
+            .with_pubsub_retention(&"retained", PubSubRetention::Count(2))?
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    let db = server
+        .create_database::<BasicSchema>("replay", false)
+        .await?;
+    for value in 0_u32..3 {
+        db.publish(&"retained", &value).await?;
+    }
+
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6008).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:6008")?)
+        .with_certificate(certificate)
+        .build()?;
+    let remote_db = client.database::<BasicSchema>("replay").await?;
+    let subscriber = remote_db.create_subscriber().await?;
+    subscriber.subscribe_to_with_replay(&"retained").await?;
+    db.publish(&"retained", &3_u32).await?;
+
+    for expected in 1_u32..=3 {
+        let message = subscriber.receiver().receive_async().await?;
+        assert_eq!(message.topic::<String>()?, "retained");
+        assert_eq!(message.payload::<u32>()?, expected);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn replication() -> anyhow::Result<()> {
+    use bonsaidb::server::ReplicationMode;
+    use bonsaidb_core::connection::{AsyncLowLevelConnection, AsyncStorageConnection};
+    use bonsaidb_core::test_util::Basic;
+
+    async fn wait_for_replication(
+        replication: &bonsaidb::server::Replication,
+        leader: &impl AsyncLowLevelConnection,
+    ) -> anyhow::Result<()> {
+        let target = leader.last_transaction_id().await?;
+        let start = Instant::now();
+        while replication.last_applied_transaction("replicated") < target {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "replication timed out"
+            );
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        Ok(())
+    }
+
+    let leader_path = TestDirectory::new("replication-leader");
+    let leader = Server::open(
+        ServerConfiguration::new(&leader_path)
+            .default_permissions(Permissions::allow_all())
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    leader.install_self_signed_certificate(false).await?;
+    let certificate = leader
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    let leader_db = leader
+        .create_database::<BasicSchema>("replicated", false)
+        .await?;
+    let mut first = Basic::new("first").push_into_async(&leader_db).await?;
+    let second = Basic::new("second").push_into_async(&leader_db).await?;
+    leader_db.set_key("key", &1_u32).await?;
+
+    tokio::spawn({
+        let leader = leader.clone();
+        async move {
+            leader.listen_on(6009).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let follower_path = TestDirectory::new("replication-follower");
+    let follower = Server::open(
+        ServerConfiguration::new(&follower_path)
+            .default_permissions(Permissions::allow_all())
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:6009")?)
+        .with_certificate(certificate)
+        .build()?;
+    let replication = follower
+        .replicate_from(client, &["replicated"], ReplicationMode::Continuous)
+        .await?;
+    wait_for_replication(&replication, &leader_db).await?;
+
+    let follower_db = follower.database::<BasicSchema>("replicated").await?;
+    let replicated = Basic::get_async(&first.header.id, &follower_db)
+        .await?
+        .expect("document not replicated");
+    assert_eq!(replicated.contents.value, "first");
+    assert_eq!(follower_db.get_key("key").into_u64().await?, Some(1));
+
+    // Replicas reject modifications.
+    assert!(matches!(
+        Basic::new("rejected").push_into_async(&follower_db).await,
+        Err(InsertError {
+            error: bonsaidb_core::Error::ReadOnlyReplica(_),
+            ..
+        })
+    ));
+    assert!(matches!(
+        follower_db.set_key("key", &2_u32).await,
+        Err(bonsaidb_core::Error::ReadOnlyReplica(_))
+    ));
+
+    // Updates and deletes on the leader are replicated.
+    first.contents.value = String::from("updated");
+    first.update_async(&leader_db).await?;
+    second.delete_async(&leader_db).await?;
+    leader_db.delete_key("key").await?;
+    wait_for_replication(&replication, &leader_db).await?;
+
+    let replicated = Basic::get_async(&first.header.id, &follower_db)
+        .await?
+        .expect("document not replicated");
+    assert_eq!(replicated.contents.value, "updated");
+    assert!(Basic::get_async(&second.header.id, &follower_db)
+        .await?
+        .is_none());
+    assert_eq!(follower_db.get_key("key").into_u64().await?, None);
+
+    // Once replication stops, the database can be modified again.
+    replication.stop();
+    Basic::new("promoted").push_into_async(&follower_db).await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "websockets")]
+async fn replica_routing() -> anyhow::Result<()> {
+    use bonsaidb::client::ReplicaRouting;
+    use bonsaidb::server::ReplicationMode;
+    use bonsaidb_core::connection::{AsyncLowLevelConnection, AsyncStorageConnection};
+    use bonsaidb_core::test_util::Basic;
+
+    let primary_path = TestDirectory::new("replica-routing-primary");
+    let primary = Server::open(
+        ServerConfiguration::new(&primary_path)
+            .default_permissions(Permissions::allow_all())
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    let primary_db = primary
+        .create_database::<BasicSchema>("replicated", false)
+        .await?;
+    Basic::new("initial").push_into_async(&primary_db).await?;
+    tokio::spawn({
+        let primary = primary.clone();
+        async move {
+            primary
+                .listen_for_websockets_on("localhost:6018", false)
+                .await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+
+    let replica_path = TestDirectory::new("replica-routing-replica");
+    let replica = Server::open(
+        ServerConfiguration::new(&replica_path)
+            .default_permissions(Permissions::allow_all())
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    tokio::spawn({
+        let replica = replica.clone();
+        async move {
+            replica
+                .listen_for_websockets_on("localhost:6019", false)
+                .await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the servers time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let replication = replica
+        .replicate_from(
+            AsyncClient::new(Url::parse("ws://localhost:6018")?)?,
+            &["replicated"],
+            ReplicationMode::Continuous,
+        )
+        .await?;
+    // A database that only exists on the replica reveals which server a read
+    // was sent to.
+    let replica_only = replica
+        .create_database::<BasicSchema>("replica-only", false)
+        .await?;
+    let only_on_replica = Basic::new("replica").push_into_async(&replica_only).await?;
+
+    for routing in [ReplicaRouting::RoundRobin, ReplicaRouting::LeastLoaded] {
+        // Nothing listens on the first replica, so reads routed to it fall
+        // back to the second replica.
+        let client = AsyncClient::build(Url::parse("ws://localhost:6018")?)
+            .with_replicas([
+                Url::parse("ws://localhost:6020")?,
+                Url::parse("ws://localhost:6019")?,
+            ])
+            .with_replica_routing(routing)
+            .build()?;
+        let client_replica_only = client.database::<BasicSchema>("replica-only").await?;
+        for _ in 0..4 {
+            let doc = Basic::get_async(&only_on_replica.header.id, &client_replica_only)
+                .await?
+                .expect("read not sent to replica");
+            assert_eq!(doc.contents.value, "replica");
+        }
+
+        // Writes are sent to the primary, and become readable through the
+        // replica once replicated.
+        let client_db = client.database::<BasicSchema>("replicated").await?;
+        let written = Basic::new("written").push_into_async(&client_db).await?;
+        let target = primary_db.last_transaction_id().await?;
+        let start = Instant::now();
+        while replication.last_applied_transaction("replicated") < target {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "replication timed out"
+            );
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(Basic::get_async(&written.header.id, &client_db)
+            .await?
+            .is_some());
+    }
+
+    // Writing to a replica directly is rejected.
+    let replica_client = AsyncClient::new(Url::parse("ws://localhost:6019")?)?;
+    let replica_db = replica_client.database::<BasicSchema>("replicated").await?;
+    assert!(matches!(
+        Basic::new("rejected").push_into_async(&replica_db).await,
+        Err(InsertError {
+            error: bonsaidb_core::Error::ReadOnlyReplica(_),
+            ..
+        })
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn database_permissions_test() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::{AsyncConnection, AsyncStorageConnection};
+    use bonsaidb_core::permissions::bonsai::{
+        database_resource_name, DatabaseAction, DocumentAction,
+    };
+    use bonsaidb_core::test_util::Basic;
+    let database_path = TestDirectory::new("database-permissions");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path)
+            .default_permissions(Permissions::from(
+                Statement::for_any()
+                    .allowing(&BonsaiAction::Server(ServerAction::Connect))
+                    .allowing(&BonsaiAction::Server(ServerAction::CreateDatabase)),
+            ))
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6010).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:6010")?)
+        .with_certificate(certificate)
+        .build()?;
+
+    // The default permissions don't allow inserting documents.
+    let restricted = client
+        .create_database::<BasicSchema>("restricted", false)
+        .await?;
+    match Basic::new("denied").push_into_async(&restricted).await {
+        Err(InsertError {
+            error: bonsaidb_core::Error::PermissionDenied(_),
+            ..
+        }) => {}
+        other => unreachable!("inserting should not be allowed: {other:?}"),
+    }
+
+    // The database's permissions apply as soon as it is created.
+    let granted = client
+        .create_database_with_permissions::<BasicSchema>(
+            "granted",
+            false,
+            vec![
+                Statement::for_resource(database_resource_name("granted")).allowing(
+                    &BonsaiAction::Database(DatabaseAction::Document(DocumentAction::Insert)),
+                ),
+            ],
+        )
+        .await?;
+    Basic::new("allowed").push_into_async(&granted).await?;
+
+    // The permissions are only granted for the database they were created
+    // with, and apply to local sessions as well.
+    match Basic::new("denied").push_into_async(&restricted).await {
+        Err(InsertError {
+            error: bonsaidb_core::Error::PermissionDenied(_),
+            ..
+        }) => {}
+        other => unreachable!("inserting should not be allowed: {other:?}"),
+    }
+    let local_granted = server
+        .database::<BasicSchema>("granted")
+        .await?
+        .with_effective_permissions(Permissions::default())
+        .unwrap();
+    Basic::new("local").push_into_async(&local_granted).await?;
+    assert_eq!(
+        server
+            .database::<BasicSchema>("granted")
+            .await?
+            .collection::<Basic>()
+            .count()
+            .await?,
+        2
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(all(unix, feature = "websockets"))]
+async fn unix_socket_test() -> anyhow::Result<()> {
+    use bonsaidb::server::Transport;
+    use bonsaidb_core::connection::AsyncStorageConnection;
+
+    let database_path = TestDirectory::new("unix-socket");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path).default_permissions(Permissions::from(
+            Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::Connect)),
+        )),
+    )
+    .await?;
+    let socket_path = database_path.0.join("bonsaidb.sock");
+    // Leave a stale socket behind to ensure it is replaced.
+    drop(std::os::unix::net::UnixListener::bind(&socket_path)?);
+    tokio::spawn({
+        let server = server.clone();
+        let socket_path = socket_path.clone();
+        async move {
+            server.listen_on_unix_socket(socket_path).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let url = Url::parse(&format!("unix://{}", socket_path.display()))?;
+    let client = AsyncClient::new(url)?;
+    client.ping().await?;
+    match client.create_user("otheruser").await {
+        Err(bonsaidb_core::Error::PermissionDenied(_)) => {}
+        other => unreachable!("should not have permission to create a user: {other:?}"),
+    }
+
+    let connected = server.connected_clients();
+    assert_eq!(connected.len(), 1);
+    assert_eq!(connected[0].transport(), &Transport::UnixSocket);
+
+    server.shutdown(None).await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "http")]
+async fn http_fallback_test() -> anyhow::Result<()> {
+    use bonsaidb::server::Transport;
+
+    let database_path = TestDirectory::new("http-fallback");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path).default_permissions(Permissions::from(
+            Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::Connect)),
+        )),
+    )
+    .await?;
+    tokio::spawn({
+        let server = server.clone();
+        async move { serve_http(server, "127.0.0.1:6022").await }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    // Nothing is listening for websockets, so the client falls back to
+    // connecting over HTTP.
+    let client = AsyncClient::build(Url::parse("ws://127.0.0.1:6023")?)
+        .with_fallback(Url::parse("http://127.0.0.1:6022")?)
+        .build()?;
+    client.ping().await?;
+    client.ping().await?;
+
+    let connected = server.connected_clients();
+    assert_eq!(connected.len(), 1);
+    assert_eq!(connected[0].transport(), &Transport::Http);
+
+    // Without a fallback, the connection error is returned.
+    let client = AsyncClient::new(Url::parse("ws://127.0.0.1:6023")?)?;
+    assert!(client.ping().await.is_err());
+
+    server.shutdown(None).await?;
+    Ok(())
+}
+
+#[tokio::test]
+#[cfg(feature = "websockets")]
+async fn secure_websockets_test() -> anyhow::Result<()> {
+    use bonsaidb::server::Transport;
+    use bonsaidb_core::connection::AsyncStorageConnection;
+
+    let database_path = TestDirectory::new("secure-websockets");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path)
+            .server_name("localhost")
+            .default_permissions(Permissions::from(
+                Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::Connect)),
+            )),
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server
+                .listen_for_websockets_on("localhost:6016", true)
+                .await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    // The self-signed certificate isn't trusted by the system.
+    let client = AsyncClient::new(Url::parse("wss://localhost:6016")?)?;
+    assert!(client.ping().await.is_err());
+
+    let client = AsyncClient::build(Url::parse("wss://localhost:6016")?)
+        .with_certificate_authorities([certificate])
+        .build()?;
+    client.ping().await?;
+    match client.create_user("otheruser").await {
+        Err(bonsaidb_core::Error::PermissionDenied(_)) => {}
+        other => unreachable!("should not have permission to create a user: {other:?}"),
+    }
+
+    let connected = server.connected_clients();
+    assert_eq!(connected.len(), 1);
+    assert_eq!(connected[0].transport(), &Transport::WebSocket);
+
+    server.shutdown(None).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn ping_test() -> anyhow::Result<()> {
+    use bonsaidb_core::connection::AsyncStorageConnection;
+    let database_path = TestDirectory::new("ping");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path).default_permissions(Permissions::from(
+            Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::Connect)),
+        )),
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+
+    #[cfg(feature = "websockets")]
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server
+                .listen_for_websockets_on("localhost:6006", false)
+                .await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6005).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:6005")?)
+        .with_certificate(certificate)
+        .build()?;
+    // Pinging only requires permission to connect.
+    client.ping().await?;
+    // So does requesting the server's status, but the counts are withheld.
+    let status = client.server_status().await?;
+    assert_eq!(
+        status.protocol_version,
+        bonsaidb_core::networking::CURRENT_PROTOCOL_VERSION
+    );
+    assert!(!status.version.is_empty());
+    assert_eq!(status.database_count, None);
+    assert_eq!(status.connection_count, None);
+    let full_status = server.server_status().await?;
+    assert_eq!(full_status.version, status.version);
+    assert!(full_status.uptime >= status.uptime);
+    assert!(full_status.database_count.is_some());
+    assert_eq!(full_status.connection_count, Some(1));
+    match client.create_user("otheruser").await {
+        Err(bonsaidb_core::Error::PermissionDenied(_)) => {}
+        other => unreachable!("should not have permission to create a user: {other:?}"),
+    }
+
+    #[cfg(feature = "websockets")]
+    {
+        let client = AsyncClient::new(Url::parse("ws://localhost:6006")?)?;
+        client.ping().await?;
+    }
+
+    // Once the server is gone, pinging reports a transport failure rather
+    // than a permission error.
+    server.shutdown(None).await?;
+    match client.ping().await {
+        Err(bonsaidb_core::Error::PermissionDenied(_)) | Ok(_) => {
+            unreachable!("ping should fail after the server shuts down")
+        }
+        Err(_) => {}
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn request_interceptor_test() -> anyhow::Result<()> {
+    use std::fmt::Display;
+    use std::sync::Arc;
+
+    use bonsaidb::client::{RequestInfo, RequestInterceptor};
+    use bonsaidb_core::connection::AsyncStorageConnection;
+
+    #[derive(Debug, Default)]
+    struct Recorder {
+        events: std::sync::Mutex<Vec<(u32, String, Option<String>, Option<String>)>>,
+    }
+
+    impl RequestInterceptor for Recorder {
+        fn before_request(&self, request: &RequestInfo<'_>) {
+            self.events.lock().unwrap().push((
+                request.id,
+                request.api.to_string(),
+                request.database.map(String::from),
+                None,
+            ));
+        }
+
+        fn after_response(&self, request: &RequestInfo<'_>, error: Option<&dyn Display>) {
+            self.events.lock().unwrap().push((
+                request.id,
+                request.api.to_string(),
+                request.database.map(String::from),
+                Some(error.map_or_else(String::new, ToString::to_string)),
+            ));
+        }
+    }
+
+    let database_path = TestDirectory::new("request-interceptor");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path)
+            .default_permissions(Permissions::allow_all())
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    server
+        .create_database::<BasicSchema>("tests", false)
+        .await?;
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6012).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let recorder = Arc::new(Recorder::default());
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:6012")?)
+        .with_certificate(certificate)
+        .with_interceptor(recorder.clone())
+        .build()?;
+    let db = client.database::<BasicSchema>("tests").await?;
+    db.set_numeric_key("a", 1_u64).await?;
+    let missing = client.database::<BasicSchema>("missing").await?;
+    assert!(missing.set_numeric_key("a", 1_u64).await.is_err());
+
+    let events = recorder.events.lock().unwrap().clone();
+    let key_operations = events
+        .iter()
+        .filter(|(_, api, ..)| api == "bonsaidb.ExecuteKeyOperation")
+        .collect::<Vec<_>>();
+    assert_eq!(key_operations.len(), 4);
+    // Each request is reported before it is sent and after it completes.
+    assert_eq!(key_operations[0].0, key_operations[1].0);
+    assert_eq!(key_operations[0].2.as_deref(), Some("tests"));
+    assert_eq!(key_operations[0].3, None);
+    assert_eq!(key_operations[1].3.as_deref(), Some(""));
+    assert_eq!(key_operations[2].0, key_operations[3].0);
+    assert_eq!(key_operations[3].2.as_deref(), Some("missing"));
+    assert!(!key_operations[3].3.as_ref().unwrap().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn max_document_size_test() -> anyhow::Result<()> {
+    use bonsaidb::core::test_util::{Basic, SoftDeleted};
+    use bonsaidb_core::connection::AsyncStorageConnection;
+
+    let database_path = TestDirectory::new("max-document-size");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path)
+            .default_permissions(Permissions::allow_all())
+            .with_max_document_size(1024)
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    server
+        .create_database::<BasicSchema>("limited", false)
+        .await?;
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6024).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    #[cfg(feature = "websockets")]
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server
+                .listen_for_websockets_on("localhost:6025", false)
+                .await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:6024")?)
+        .with_certificate(certificate)
+        .build()?;
+    let db = client.database::<BasicSchema>("limited").await?;
+    Basic::new("small").push_into_async(&db).await?;
+    assert!(matches!(
+        Basic::new("a".repeat(2048)).push_into_async(&db).await,
+        Err(InsertError {
+            error: bonsaidb_core::Error::DocumentTooLarge { limit: 1024, .. },
+            ..
+        })
+    ));
+    // The rejected document wasn't stored.
+    assert_eq!(Basic::all_async(&db).count().await?, 1);
+
+    // Restoring a soft-deleted document is limited the same way. The server's
+    // own connection isn't limited, allowing a large document to be stored.
+    let local = server.database::<BasicSchema>("limited").await?;
+    let large = SoftDeleted::new("a".repeat(2048))
+        .push_into_async(&local)
+        .await?;
+    large.delete_async(&local).await?;
+    assert!(matches!(
+        SoftDeleted::restore_async(&large.header.id, &db).await,
+        Err(bonsaidb_core::Error::DocumentTooLarge { limit: 1024, .. })
+    ));
+    assert!(SoftDeleted::get_async(&large.header.id, &db)
+        .await?
+        .is_none());
+
+    #[cfg(feature = "websockets")]
+    {
+        let client = AsyncClient::new(Url::parse("ws://localhost:6025")?)?;
+        let db = client.database::<BasicSchema>("limited").await?;
+        assert!(matches!(
+            Basic::new("a".repeat(2048)).push_into_async(&db).await,
+            Err(InsertError {
+                error: bonsaidb_core::Error::DocumentTooLarge { limit: 1024, .. },
+                ..
+            })
+        ));
+
+        // Requests that are much larger than the limit are rejected while
+        // they are being received, which disconnects the client.
+        assert!(Basic::new("a".repeat(1024 * 1024))
+            .push_into_async(&db)
+            .await
+            .is_err());
+
+        // The client reconnects for its next request.
+        Basic::new("small").push_into_async(&db).await?;
+        assert_eq!(Basic::all_async(&db).count().await?, 2);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn idle_timeout_test() -> anyhow::Result<()> {
+    let database_path = TestDirectory::new("idle-timeout");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path)
+            .default_permissions(Permissions::allow_all())
+            .with_idle_timeout(Duration::from_millis(400)),
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6026).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let idle_client = AsyncClient::build(Url::parse("bonsaidb://localhost:6026")?)
+        .with_certificate(certificate.clone())
+        .build()?;
+    assert_eq!(idle_client.keepalive(), None);
+    let status = idle_client.server_status().await?;
+    assert_eq!(status.idle_timeout, Some(Duration::from_millis(400)));
+    let idle_client_id = server.connected_clients()[0].id();
+
+    let keepalive_client = AsyncClient::build(Url::parse("bonsaidb://localhost:6026")?)
+        .with_certificate(certificate)
+        .with_keepalive(Duration::from_millis(100))
+        .build()?;
+    assert_eq!(
+        keepalive_client.keepalive(),
+        Some(Duration::from_millis(100))
+    );
+    keepalive_client.ping().await?;
+    assert_eq!(server.connected_clients().len(), 2);
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    // Only the client sending keepalives is still connected.
+    let connected_clients = server.connected_clients();
+    assert_eq!(connected_clients.len(), 1);
+    assert_ne!(connected_clients[0].id(), idle_client_id);
+    assert!(connected_clients[0].idle_duration() < Duration::from_millis(400));
+    keepalive_client.ping().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn server_events() -> anyhow::Result<()> {
+    use bonsaidb::core::networking::{ClientEvent, ServerEvent};
+    use bonsaidb::core::schema::{NamedReference, Schema};
+    use bonsaidb_core::connection::AsyncStorageConnection;
+    use futures::StreamExt;
+    let database_path = TestDirectory::new("server-events");
+    let server = Server::open(
+        ServerConfiguration::new(&database_path)
+            .default_permissions(Permissions::allow_all())
+            .with_schema::<BasicSchema>()?,
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    tokio::spawn({
+        let server = server.clone();
+        async move {
+            server.listen_on(6027).await?;
+            Result::<(), anyhow::Error>::Ok(())
+        }
+    });
+    // Give the server time to listen
+    tokio::time::sleep(Duration::from_millis(10)).await;
+
+    let monitor = AsyncClient::build(Url::parse("bonsaidb://localhost:6027")?)
+        .with_certificate(certificate.clone())
+        .build()?;
+    let mut events = monitor.subscribe_to_server_events().await?;
+
+    let client = AsyncClient::build(Url::parse("bonsaidb://localhost:6027")?)
+        .with_certificate(certificate)
+        .build()?;
+    client.ping().await?;
+    let Some(ServerEvent::Client(ClientEvent::Connected { id: client_id, .. })) =
+        events.next().await
+    else {
+        unreachable!("expected a client to connect")
+    };
+
+    assert!(client
+        .authenticate(Authentication::password(
+            "missing-user",
+            SensitiveString(String::from("hunter2")),
+        )?)
+        .await
+        .is_err());
+    let Some(ServerEvent::AuthenticationFailed {
+        client_id: failed_client_id,
+        user,
+        ..
+    }) = events.next().await
+    else {
+        unreachable!("expected an authentication failure")
+    };
+    assert_eq!(failed_client_id, client_id);
+    assert_eq!(user, Some(NamedReference::from("missing-user")));
+
+    client
+        .create_database::<BasicSchema>("server-events", false)
+        .await?;
+    let Some(ServerEvent::DatabaseCreated { name, schema }) = events.next().await else {
+        unreachable!("expected a database to be created")
+    };
+    assert_eq!(name, "server-events");
+    assert_eq!(schema, BasicSchema::schema_name());
+
+    // Creating a database that already exists doesn't publish an event.
+    client
+        .create_database::<BasicSchema>("server-events", true)
+        .await?;
+    client.delete_database("server-events").await?;
+    let Some(ServerEvent::DatabaseDeleted { name }) = events.next().await else {
+        unreachable!("expected a database to be deleted")
+    };
+    assert_eq!(name, "server-events");
+
+    assert!(server.disconnect_client(client_id));
+    let Some(ServerEvent::Client(ClientEvent::Disconnected {
+        id: disconnected_client_id,
+    })) = events.next().await
+    else {
+        unreachable!("expected a client to disconnect")
+    };
+    assert_eq!(disconnected_client_id, client_id);
+
+    Ok(())
+}
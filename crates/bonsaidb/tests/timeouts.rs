@@ -1,6 +1,8 @@
 //! Tests request and connection timeouts
 
+use std::collections::HashSet;
 use std::net::UdpSocket;
+use std::sync::Mutex;
 use std::time::{Duration, Instant};
 
 use bonsaidb::client::url::Url;
@@ -108,6 +110,27 @@ impl Handler<LongCall> for LongCall {
     }
 }
 
+/// Records the keys of [`CountedCall`]s that ran to completion.
+static COMPLETED_CALLS: Lazy<Mutex<HashSet<u32>>> = Lazy::new(Mutex::default);
+
+#[derive(Api, Debug, Serialize, Deserialize, Clone)]
+#[api(name = "counted-call")]
+struct CountedCall {
+    key: u32,
+}
+
+#[async_trait]
+impl Handler<CountedCall> for CountedCall {
+    async fn handle(
+        _session: HandlerSession<'_>,
+        request: CountedCall,
+    ) -> HandlerResult<CountedCall> {
+        tokio::time::sleep(Duration::from_secs(2)).await;
+        COMPLETED_CALLS.lock().unwrap().insert(request.key);
+        Ok(())
+    }
+}
+
 fn shared_server() -> &'static Certificate {
     static SHARED_SERVER: Lazy<Certificate> = Lazy::new(|| {
         drop(env_logger::try_init());
@@ -123,6 +146,8 @@ fn shared_server() -> &'static Certificate {
                         .with_schema::<Basic>()
                         .unwrap()
                         .with_api::<LongCall, LongCall>()
+                        .unwrap()
+                        .with_api::<CountedCall, CountedCall>()
                         .unwrap(),
                 )
                 .await
@@ -241,3 +266,124 @@ fn blocking_quic_request_timeout() {
         other => unreachable!("expected request timeout, got {other:?}"),
     }
 }
+
+async fn assert_timed_out_request_is_cancelled(client: AsyncClient, key: u32) {
+    let start = Instant::now();
+    match client
+        .with_request_timeout(Duration::from_millis(500))
+        .send_api_request(&CountedCall { key })
+        .await
+    {
+        Err(ApiError::Client(bonsaidb_client::Error::Core(bonsaidb_core::Error::Networking(
+            networking::Error::RequestTimeout,
+        )))) => {
+            assert!(start.elapsed() < Duration::from_secs(2));
+        }
+        other => unreachable!("expected request timeout, got {other:?}"),
+    }
+
+    // Had the server not cancelled the request, it would have completed
+    // during this sleep.
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert!(!COMPLETED_CALLS.lock().unwrap().contains(&key));
+
+    // The client's own timeout is unaffected, and the connection is still
+    // usable.
+    client
+        .send_api_request(&CountedCall { key: key + 1 })
+        .await
+        .unwrap();
+    assert!(COMPLETED_CALLS.lock().unwrap().contains(&(key + 1)));
+}
+
+#[tokio::test]
+#[cfg(feature = "websockets")]
+async fn ws_request_cancelled_on_timeout() {
+    shared_server();
+    // Give the server a moment to actually start up.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = AsyncClient::build(Url::parse("ws://127.0.0.1:7023").unwrap())
+        .with_request_timeout(Duration::from_secs(10))
+        .build()
+        .unwrap();
+    assert_timed_out_request_is_cancelled(client, 1).await;
+}
+
+#[tokio::test]
+async fn quic_request_cancelled_on_timeout() {
+    let cert_chain = shared_server();
+    // Give the server a moment to actually start up.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = AsyncClient::build(Url::parse("bonsaidb://127.0.0.1:7024").unwrap())
+        .with_request_timeout(Duration::from_secs(10))
+        .with_certificate(cert_chain.clone())
+        .build()
+        .unwrap();
+    assert_timed_out_request_is_cancelled(client, 3).await;
+}
+
+#[tokio::test]
+async fn queued_request_cancelled_before_starting() -> anyhow::Result<()> {
+    let dir = TestDirectory::new("queued-request-cancelled.bonsaidb");
+    // Only one request is executed at a time, causing later requests to wait
+    // in the queue.
+    let server = Server::open(
+        ServerConfiguration::new(&dir)
+            .default_permissions(DefaultPermissions::AllowAll)
+            .client_simultaneous_request_limit(1)
+            .with_api::<CountedCall, CountedCall>()?,
+    )
+    .await?;
+    server.install_self_signed_certificate(false).await?;
+    let certificate = server
+        .certificate_chain()
+        .await?
+        .into_end_entity_certificate();
+    tokio::task::spawn({
+        let server = server.clone();
+        async move { server.listen_on(7025).await }
+    });
+    // Give the server a moment to actually start up.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    let client = AsyncClient::build(Url::parse("bonsaidb://127.0.0.1:7025")?)
+        .with_request_timeout(Duration::from_secs(10))
+        .with_certificate(certificate)
+        .build()?;
+    let running = tokio::task::spawn({
+        let client = client.clone();
+        async move { client.send_api_request(&CountedCall { key: 5 }).await }
+    });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // This request times out while the first request is still executing.
+    match client
+        .with_request_timeout(Duration::from_millis(500))
+        .send_api_request(&CountedCall { key: 6 })
+        .await
+    {
+        Err(ApiError::Client(bonsaidb_client::Error::Core(bonsaidb_core::Error::Networking(
+            networking::Error::RequestTimeout,
+        )))) => {}
+        other => unreachable!("expected request timeout, got {other:?}"),
+    }
+
+    running.await?.unwrap();
+    assert!(COMPLETED_CALLS.lock().unwrap().contains(&5));
+
+    // The cancelled request never began executing, even though the server
+    // became able to execute it.
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert!(!COMPLETED_CALLS.lock().unwrap().contains(&6));
+
+    // The connection is still usable.
+    client
+        .send_api_request(&CountedCall { key: 7 })
+        .await
+        .unwrap();
+    assert!(COMPLETED_CALLS.lock().unwrap().contains(&7));
+
+    Ok(())
+}
@@ -67,6 +67,7 @@ async fn main() -> anyhow::Result<()> {
     let users_group_id = match (PermissionGroup {
         name: String::from("basic-users"),
         statements: vec![Statement::for_any()
+        parent_group: None,
             .allowing(&BonsaiAction::Database(DatabaseAction::Document(
                 DocumentAction::Insert,
             )))
@@ -97,6 +98,7 @@ async fn main() -> anyhow::Result<()> {
     let superusers_group_id = match (PermissionGroup {
         name: String::from("superusers"),
         statements: vec![Statement::allow_all_for_any_resource()],
+        parent_group: None,
     }
     .push_into_async(&admin)
     .await)
@@ -133,6 +135,7 @@ async fn main() -> anyhow::Result<()> {
     let administrators_group_id = match (PermissionGroup {
         name: String::from("administrators"),
         statements: vec![
+        parent_group: None,
             Statement::for_any().allowing(&BonsaiAction::Server(ServerAction::AssumeIdentity))
         ],
     }